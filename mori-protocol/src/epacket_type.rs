@@ -0,0 +1,139 @@
+use serde::de::{self, Deserializer, Visitor};
+use serde::{Deserialize, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+
+/// Message type, the little endian `u32` every ENet packet starts with.
+#[repr(u32)]
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum EPacketType {
+    NetMessageUnknown,
+    NetMessageServerHello,
+    NetMessageGenericText,
+    NetMessageGameMessage,
+    NetMessageGamePacket,
+    NetMessageError,
+    NetMessageTrack,
+    NetMessageClientLogRequest,
+    NetMessageClientLogResponse,
+    NetMessageMax,
+}
+
+impl From<u32> for EPacketType {
+    fn from(value: u32) -> Self {
+        match value {
+            0 => EPacketType::NetMessageUnknown,
+            1 => EPacketType::NetMessageServerHello,
+            2 => EPacketType::NetMessageGenericText,
+            3 => EPacketType::NetMessageGameMessage,
+            4 => EPacketType::NetMessageGamePacket,
+            5 => EPacketType::NetMessageError,
+            6 => EPacketType::NetMessageTrack,
+            7 => EPacketType::NetMessageClientLogRequest,
+            8 => EPacketType::NetMessageClientLogResponse,
+            9 => EPacketType::NetMessageMax,
+            _ => EPacketType::NetMessageUnknown,
+        }
+    }
+}
+
+impl EPacketType {
+    /// Short name used in logs and by human readable serialization.
+    pub fn name(&self) -> &'static str {
+        match self {
+            EPacketType::NetMessageUnknown => "Unknown",
+            EPacketType::NetMessageServerHello => "ServerHello",
+            EPacketType::NetMessageGenericText => "GenericText",
+            EPacketType::NetMessageGameMessage => "GameMessage",
+            EPacketType::NetMessageGamePacket => "GamePacket",
+            EPacketType::NetMessageError => "Error",
+            EPacketType::NetMessageTrack => "Track",
+            EPacketType::NetMessageClientLogRequest => "ClientLogRequest",
+            EPacketType::NetMessageClientLogResponse => "ClientLogResponse",
+            EPacketType::NetMessageMax => "Max",
+        }
+    }
+}
+
+impl fmt::Display for EPacketType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for EPacketType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        (0..=9)
+            .map(EPacketType::from)
+            .find(|packet_type| packet_type.name() == s)
+            .ok_or_else(|| format!("unknown packet type: {}", s))
+    }
+}
+
+impl Serialize for EPacketType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(self.name())
+        } else {
+            serializer.serialize_u32(*self as u32)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for EPacketType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct PacketTypeVisitor;
+
+        impl<'de> Visitor<'de> for PacketTypeVisitor {
+            type Value = EPacketType;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a packet type name or number")
+            }
+
+            fn visit_u64<E: de::Error>(self, value: u64) -> Result<Self::Value, E> {
+                u32::try_from(value)
+                    .map(EPacketType::from)
+                    .map_err(|_| E::custom(format!("packet type out of range: {}", value)))
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+                value.parse().map_err(E::custom)
+            }
+        }
+
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_any(PacketTypeVisitor)
+        } else {
+            deserializer.deserialize_u32(PacketTypeVisitor)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_round_trip() {
+        for value in 0..=9 {
+            let packet_type = EPacketType::from(value);
+            assert_eq!(packet_type as u32, value);
+            assert_eq!(packet_type.to_string().parse::<EPacketType>(), Ok(packet_type));
+        }
+        assert_eq!(EPacketType::from(10), EPacketType::NetMessageUnknown);
+        assert!("Bogus".parse::<EPacketType>().is_err());
+    }
+
+    #[test]
+    fn serializes_by_name_and_by_number() {
+        let json = serde_json::to_string(&EPacketType::NetMessageGamePacket).unwrap();
+        assert_eq!(json, "\"GamePacket\"");
+        assert_eq!(serde_json::from_str::<EPacketType>(&json).unwrap(), EPacketType::NetMessageGamePacket);
+        assert_eq!(serde_json::from_str::<EPacketType>("2").unwrap(), EPacketType::NetMessageGenericText);
+        let bytes = bincode::serialize(&EPacketType::NetMessageGamePacket).unwrap();
+        assert_eq!(bytes, 4u32.to_le_bytes());
+    }
+}
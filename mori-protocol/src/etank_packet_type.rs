@@ -0,0 +1,335 @@
+use serde::de::{self, Deserializer, Visitor};
+use serde::{Deserialize, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+
+/// Type byte of a [`TankPacket`](crate::tank_packet::TankPacket).
+///
+/// Values without a known name are kept as `Unknown(value)` so they survive a round trip.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum ETankPacketType {
+    NetGamePacketState,
+    NetGamePacketCallFunction,
+    NetGamePacketUpdateStatus,
+    NetGamePacketTileChangeRequest,
+    NetGamePacketSendMapData,
+    NetGamePacketSendTileUpdateData,
+    NetGamePacketSendTileUpdateDataMultiple,
+    NetGamePacketTileActivateRequest,
+    NetGamePacketTileApplyDamage,
+    NetGamePacketSendInventoryState,
+    NetGamePacketItemActivateRequest,
+    NetGamePacketItemActivateObjectRequest,
+    NetGamePacketSendTileTreeState,
+    NetGamePacketModifyItemInventory,
+    NetGamePacketItemChangeObject,
+    NetGamePacketSendLock,
+    NetGamePacketSendItemDatabaseData,
+    NetGamePacketSendParticleEffect,
+    NetGamePacketSetIconState,
+    NetGamePacketItemEffect,
+    NetGamePacketSetCharacterState,
+    NetGamePacketPingReply,
+    NetGamePacketPingRequest,
+    NetGamePacketGotPunched,
+    NetGamePacketAppCheckResponse,
+    NetGamePacketAppIntegrityFail,
+    NetGamePacketDisconnect,
+    NetGamePacketBattleJoin,
+    NetGamePacketBattleEvent,
+    NetGamePacketUseDoor,
+    NetGamePacketSendParental,
+    NetGamePacketGoneFishin,
+    NetGamePacketSteam,
+    NetGamePacketPetBattle,
+    NetGamePacketNpc,
+    NetGamePacketSpecial,
+    NetGamePacketSendParticleEffectV2,
+    NetGameActivateArrowToItem,
+    NetGameSelectTileIndex,
+    NetGamePacketSendPlayerTributeData,
+    NetGamePacketFTUESetItemToQuickInventory,
+    NetGamePacketPVENpc,
+    NetGamePacketPVPCardBattle,
+    NetGamePacketPVEApplyPlayerDamage,
+    NetGamePacketPVENPCPositionUpdate,
+    NetGamePacketSetExtraMods,
+    NetGamePacketOnStepTileMod,
+    // Anything newer than this list, kept so it can be logged and re-sent unchanged
+    Unknown(u8),
+}
+
+impl Default for ETankPacketType {
+    fn default() -> Self {
+        ETankPacketType::NetGamePacketState
+    }
+}
+
+impl From<u8> for ETankPacketType {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => ETankPacketType::NetGamePacketState,
+            1 => ETankPacketType::NetGamePacketCallFunction,
+            2 => ETankPacketType::NetGamePacketUpdateStatus,
+            3 => ETankPacketType::NetGamePacketTileChangeRequest,
+            4 => ETankPacketType::NetGamePacketSendMapData,
+            5 => ETankPacketType::NetGamePacketSendTileUpdateData,
+            6 => ETankPacketType::NetGamePacketSendTileUpdateDataMultiple,
+            7 => ETankPacketType::NetGamePacketTileActivateRequest,
+            8 => ETankPacketType::NetGamePacketTileApplyDamage,
+            9 => ETankPacketType::NetGamePacketSendInventoryState,
+            10 => ETankPacketType::NetGamePacketItemActivateRequest,
+            11 => ETankPacketType::NetGamePacketItemActivateObjectRequest,
+            12 => ETankPacketType::NetGamePacketSendTileTreeState,
+            13 => ETankPacketType::NetGamePacketModifyItemInventory,
+            14 => ETankPacketType::NetGamePacketItemChangeObject,
+            15 => ETankPacketType::NetGamePacketSendLock,
+            16 => ETankPacketType::NetGamePacketSendItemDatabaseData,
+            17 => ETankPacketType::NetGamePacketSendParticleEffect,
+            18 => ETankPacketType::NetGamePacketSetIconState,
+            19 => ETankPacketType::NetGamePacketItemEffect,
+            20 => ETankPacketType::NetGamePacketSetCharacterState,
+            21 => ETankPacketType::NetGamePacketPingReply,
+            22 => ETankPacketType::NetGamePacketPingRequest,
+            23 => ETankPacketType::NetGamePacketGotPunched,
+            24 => ETankPacketType::NetGamePacketAppCheckResponse,
+            25 => ETankPacketType::NetGamePacketAppIntegrityFail,
+            26 => ETankPacketType::NetGamePacketDisconnect,
+            27 => ETankPacketType::NetGamePacketBattleJoin,
+            28 => ETankPacketType::NetGamePacketBattleEvent,
+            29 => ETankPacketType::NetGamePacketUseDoor,
+            30 => ETankPacketType::NetGamePacketSendParental,
+            31 => ETankPacketType::NetGamePacketGoneFishin,
+            32 => ETankPacketType::NetGamePacketSteam,
+            33 => ETankPacketType::NetGamePacketPetBattle,
+            34 => ETankPacketType::NetGamePacketNpc,
+            35 => ETankPacketType::NetGamePacketSpecial,
+            36 => ETankPacketType::NetGamePacketSendParticleEffectV2,
+            37 => ETankPacketType::NetGameActivateArrowToItem,
+            38 => ETankPacketType::NetGameSelectTileIndex,
+            39 => ETankPacketType::NetGamePacketSendPlayerTributeData,
+            40 => ETankPacketType::NetGamePacketFTUESetItemToQuickInventory,
+            41 => ETankPacketType::NetGamePacketPVENpc,
+            42 => ETankPacketType::NetGamePacketPVPCardBattle,
+            43 => ETankPacketType::NetGamePacketPVEApplyPlayerDamage,
+            44 => ETankPacketType::NetGamePacketPVENPCPositionUpdate,
+            45 => ETankPacketType::NetGamePacketSetExtraMods,
+            46 => ETankPacketType::NetGamePacketOnStepTileMod,
+            _ => ETankPacketType::Unknown(value),
+        }
+    }
+}
+
+impl From<ETankPacketType> for u8 {
+    fn from(value: ETankPacketType) -> Self {
+        match value {
+            ETankPacketType::NetGamePacketState => 0,
+            ETankPacketType::NetGamePacketCallFunction => 1,
+            ETankPacketType::NetGamePacketUpdateStatus => 2,
+            ETankPacketType::NetGamePacketTileChangeRequest => 3,
+            ETankPacketType::NetGamePacketSendMapData => 4,
+            ETankPacketType::NetGamePacketSendTileUpdateData => 5,
+            ETankPacketType::NetGamePacketSendTileUpdateDataMultiple => 6,
+            ETankPacketType::NetGamePacketTileActivateRequest => 7,
+            ETankPacketType::NetGamePacketTileApplyDamage => 8,
+            ETankPacketType::NetGamePacketSendInventoryState => 9,
+            ETankPacketType::NetGamePacketItemActivateRequest => 10,
+            ETankPacketType::NetGamePacketItemActivateObjectRequest => 11,
+            ETankPacketType::NetGamePacketSendTileTreeState => 12,
+            ETankPacketType::NetGamePacketModifyItemInventory => 13,
+            ETankPacketType::NetGamePacketItemChangeObject => 14,
+            ETankPacketType::NetGamePacketSendLock => 15,
+            ETankPacketType::NetGamePacketSendItemDatabaseData => 16,
+            ETankPacketType::NetGamePacketSendParticleEffect => 17,
+            ETankPacketType::NetGamePacketSetIconState => 18,
+            ETankPacketType::NetGamePacketItemEffect => 19,
+            ETankPacketType::NetGamePacketSetCharacterState => 20,
+            ETankPacketType::NetGamePacketPingReply => 21,
+            ETankPacketType::NetGamePacketPingRequest => 22,
+            ETankPacketType::NetGamePacketGotPunched => 23,
+            ETankPacketType::NetGamePacketAppCheckResponse => 24,
+            ETankPacketType::NetGamePacketAppIntegrityFail => 25,
+            ETankPacketType::NetGamePacketDisconnect => 26,
+            ETankPacketType::NetGamePacketBattleJoin => 27,
+            ETankPacketType::NetGamePacketBattleEvent => 28,
+            ETankPacketType::NetGamePacketUseDoor => 29,
+            ETankPacketType::NetGamePacketSendParental => 30,
+            ETankPacketType::NetGamePacketGoneFishin => 31,
+            ETankPacketType::NetGamePacketSteam => 32,
+            ETankPacketType::NetGamePacketPetBattle => 33,
+            ETankPacketType::NetGamePacketNpc => 34,
+            ETankPacketType::NetGamePacketSpecial => 35,
+            ETankPacketType::NetGamePacketSendParticleEffectV2 => 36,
+            ETankPacketType::NetGameActivateArrowToItem => 37,
+            ETankPacketType::NetGameSelectTileIndex => 38,
+            ETankPacketType::NetGamePacketSendPlayerTributeData => 39,
+            ETankPacketType::NetGamePacketFTUESetItemToQuickInventory => 40,
+            ETankPacketType::NetGamePacketPVENpc => 41,
+            ETankPacketType::NetGamePacketPVPCardBattle => 42,
+            ETankPacketType::NetGamePacketPVEApplyPlayerDamage => 43,
+            ETankPacketType::NetGamePacketPVENPCPositionUpdate => 44,
+            ETankPacketType::NetGamePacketSetExtraMods => 45,
+            ETankPacketType::NetGamePacketOnStepTileMod => 46,
+            ETankPacketType::Unknown(value) => value,
+        }
+    }
+}
+
+impl ETankPacketType {
+    /// Short name used in logs and by human readable serialization.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ETankPacketType::NetGamePacketState => "State",
+            ETankPacketType::NetGamePacketCallFunction => "CallFunction",
+            ETankPacketType::NetGamePacketUpdateStatus => "UpdateStatus",
+            ETankPacketType::NetGamePacketTileChangeRequest => "TileChangeRequest",
+            ETankPacketType::NetGamePacketSendMapData => "SendMapData",
+            ETankPacketType::NetGamePacketSendTileUpdateData => "SendTileUpdateData",
+            ETankPacketType::NetGamePacketSendTileUpdateDataMultiple => "SendTileUpdateDataMultiple",
+            ETankPacketType::NetGamePacketTileActivateRequest => "TileActivateRequest",
+            ETankPacketType::NetGamePacketTileApplyDamage => "TileApplyDamage",
+            ETankPacketType::NetGamePacketSendInventoryState => "SendInventoryState",
+            ETankPacketType::NetGamePacketItemActivateRequest => "ItemActivateRequest",
+            ETankPacketType::NetGamePacketItemActivateObjectRequest => "ItemActivateObjectRequest",
+            ETankPacketType::NetGamePacketSendTileTreeState => "SendTileTreeState",
+            ETankPacketType::NetGamePacketModifyItemInventory => "ModifyItemInventory",
+            ETankPacketType::NetGamePacketItemChangeObject => "ItemChangeObject",
+            ETankPacketType::NetGamePacketSendLock => "SendLock",
+            ETankPacketType::NetGamePacketSendItemDatabaseData => "SendItemDatabaseData",
+            ETankPacketType::NetGamePacketSendParticleEffect => "SendParticleEffect",
+            ETankPacketType::NetGamePacketSetIconState => "SetIconState",
+            ETankPacketType::NetGamePacketItemEffect => "ItemEffect",
+            ETankPacketType::NetGamePacketSetCharacterState => "SetCharacterState",
+            ETankPacketType::NetGamePacketPingReply => "PingReply",
+            ETankPacketType::NetGamePacketPingRequest => "PingRequest",
+            ETankPacketType::NetGamePacketGotPunched => "GotPunched",
+            ETankPacketType::NetGamePacketAppCheckResponse => "AppCheckResponse",
+            ETankPacketType::NetGamePacketAppIntegrityFail => "AppIntegrityFail",
+            ETankPacketType::NetGamePacketDisconnect => "Disconnect",
+            ETankPacketType::NetGamePacketBattleJoin => "BattleJoin",
+            ETankPacketType::NetGamePacketBattleEvent => "BattleEvent",
+            ETankPacketType::NetGamePacketUseDoor => "UseDoor",
+            ETankPacketType::NetGamePacketSendParental => "SendParental",
+            ETankPacketType::NetGamePacketGoneFishin => "GoneFishin",
+            ETankPacketType::NetGamePacketSteam => "Steam",
+            ETankPacketType::NetGamePacketPetBattle => "PetBattle",
+            ETankPacketType::NetGamePacketNpc => "Npc",
+            ETankPacketType::NetGamePacketSpecial => "Special",
+            ETankPacketType::NetGamePacketSendParticleEffectV2 => "SendParticleEffectV2",
+            ETankPacketType::NetGameActivateArrowToItem => "ActivateArrowToItem",
+            ETankPacketType::NetGameSelectTileIndex => "SelectTileIndex",
+            ETankPacketType::NetGamePacketSendPlayerTributeData => "SendPlayerTributeData",
+            ETankPacketType::NetGamePacketFTUESetItemToQuickInventory => "FTUESetItemToQuickInventory",
+            ETankPacketType::NetGamePacketPVENpc => "PVENpc",
+            ETankPacketType::NetGamePacketPVPCardBattle => "PVPCardBattle",
+            ETankPacketType::NetGamePacketPVEApplyPlayerDamage => "PVEApplyPlayerDamage",
+            ETankPacketType::NetGamePacketPVENPCPositionUpdate => "PVENPCPositionUpdate",
+            ETankPacketType::NetGamePacketSetExtraMods => "SetExtraMods",
+            ETankPacketType::NetGamePacketOnStepTileMod => "OnStepTileMod",
+            ETankPacketType::Unknown(_) => "Unknown",
+        }
+    }
+}
+
+impl fmt::Display for ETankPacketType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ETankPacketType::Unknown(value) => write!(f, "Unknown({})", value),
+            _ => f.write_str(self.name()),
+        }
+    }
+}
+
+impl FromStr for ETankPacketType {
+    type Err = String;
+
+    // Accepts everything `Display` produces
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(value) = s.strip_prefix("Unknown(").and_then(|s| s.strip_suffix(')')) {
+            return value
+                .parse::<u8>()
+                .map(ETankPacketType::from)
+                .map_err(|err| err.to_string());
+        }
+        (0..=u8::MAX)
+            .map(ETankPacketType::from)
+            .find(|packet_type| packet_type.to_string() == s)
+            .ok_or_else(|| format!("unknown tank packet type: {}", s))
+    }
+}
+
+// The wire format (bincode) needs the raw byte, captures and JSON get the name
+impl Serialize for ETankPacketType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.collect_str(self)
+        } else {
+            serializer.serialize_u8(u8::from(*self))
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for ETankPacketType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct TankPacketTypeVisitor;
+
+        impl<'de> Visitor<'de> for TankPacketTypeVisitor {
+            type Value = ETankPacketType;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a tank packet type name or byte")
+            }
+
+            fn visit_u64<E: de::Error>(self, value: u64) -> Result<Self::Value, E> {
+                u8::try_from(value)
+                    .map(ETankPacketType::from)
+                    .map_err(|_| E::custom(format!("tank packet type out of range: {}", value)))
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+                value.parse().map_err(E::custom)
+            }
+        }
+
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_any(TankPacketTypeVisitor)
+        } else {
+            deserializer.deserialize_u8(TankPacketTypeVisitor)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_byte_round_trips() {
+        for value in (0..=39).chain([46, 47, 100, 255]) {
+            let packet_type = ETankPacketType::from(value);
+            assert_eq!(u8::from(packet_type), value);
+            assert_eq!(packet_type.to_string().parse::<ETankPacketType>(), Ok(packet_type));
+        }
+    }
+
+    #[test]
+    fn unknown_bytes_keep_their_value() {
+        assert_eq!(ETankPacketType::from(47), ETankPacketType::Unknown(47));
+        assert_eq!(ETankPacketType::from(255).to_string(), "Unknown(255)");
+        assert_eq!(ETankPacketType::from(0), ETankPacketType::NetGamePacketState);
+    }
+
+    #[test]
+    fn serializes_by_name_and_by_byte() {
+        for value in [0, 3, 39, 47] {
+            let packet_type = ETankPacketType::from(value);
+            let json = serde_json::to_string(&packet_type).unwrap();
+            assert_eq!(json, format!("\"{}\"", packet_type));
+            assert_eq!(serde_json::from_str::<ETankPacketType>(&json).unwrap(), packet_type);
+            let bytes = bincode::serialize(&packet_type).unwrap();
+            assert_eq!(bytes, vec![value]);
+            assert_eq!(bincode::deserialize::<ETankPacketType>(&bytes).unwrap(), packet_type);
+        }
+        assert_eq!(serde_json::from_str::<ETankPacketType>("3").unwrap(), ETankPacketType::from(3));
+    }
+}
@@ -0,0 +1,23 @@
+//! Growtopia wire types shared by Mori and standalone tools.
+//!
+//! Everything here is plain (de)serialization, there is no networking, so the
+//! crate can be used by packet analyzers or replay tools without pulling in
+//! ENet, the GUI or Lua.
+//!
+//! An ENet packet starts with a little endian `u32` [`EPacketType`]. Text
+//! messages carry `key|value` lines (see [`text`]), game packets carry a
+//! [`TankPacket`] header followed by extended data, which for call functions
+//! is a [`VariantList`].
+
+pub mod epacket_type;
+pub mod etank_packet_type;
+pub mod login_info;
+pub mod tank_packet;
+pub mod text;
+pub mod variant;
+
+pub use epacket_type::EPacketType;
+pub use etank_packet_type::ETankPacketType;
+pub use login_info::LoginInfo;
+pub use tank_packet::TankPacket;
+pub use variant::{Variant, VariantList};
@@ -0,0 +1,58 @@
+use crate::epacket_type::EPacketType;
+use crate::etank_packet_type::ETankPacketType;
+use serde::{Deserialize, Serialize};
+
+/// Fixed 56 byte header of a `NetMessageGamePacket`, any extended data follows it.
+///
+/// Field order is the wire order, the `unk*` fields are reused by different
+/// packet types for different purposes.
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct TankPacket {
+    pub _type: ETankPacketType,
+    pub unk1: u8,
+    pub unk2: u8,
+    pub unk3: u8,
+    pub net_id: u32,
+    pub sec_id: u32,
+    pub flags: u32,
+    pub unk6: f32,
+    pub value: u32,
+    pub vector_x: f32,
+    pub vector_y: f32,
+    pub vector_x2: f32,
+    pub vector_y2: f32,
+    pub unk12: f32,
+    pub int_x: i32,
+    pub int_y: i32,
+    pub extended_data_length: u32,
+}
+
+impl TankPacket {
+    /// Decodes the header from the start of `data`, trailing extended data is ignored.
+    pub fn from_bytes(data: &[u8]) -> Result<Self, bincode::Error> {
+        bincode::deserialize(data)
+    }
+
+    /// Encodes the 56 byte header, without the packet type prefix or extended data.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, bincode::Error> {
+        bincode::serialize(self)
+    }
+
+    /// Encodes the whole ENet payload: the `NetMessageGamePacket` prefix, the header and
+    /// `extended`, which has to be exactly `extended_data_length` bytes.
+    pub fn to_message(&self, extended: &[u8]) -> Result<Vec<u8>, bincode::Error> {
+        if extended.len() != self.extended_data_length as usize {
+            return Err(Box::new(bincode::ErrorKind::Custom(format!(
+                "extended_data_length is {} but {} bytes were given",
+                self.extended_data_length,
+                extended.len()
+            ))));
+        }
+        let header = self.to_bytes()?;
+        let mut data = Vec::with_capacity(4 + header.len() + extended.len());
+        data.extend_from_slice(&(EPacketType::NetMessageGamePacket as u32).to_le_bytes());
+        data.extend_from_slice(&header);
+        data.extend_from_slice(extended);
+        Ok(data)
+    }
+}
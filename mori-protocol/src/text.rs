@@ -0,0 +1,112 @@
+use std::collections::HashMap;
+use base64::Engine;
+use base64::engine::general_purpose;
+
+/// Splits a `a|b|c` value into its parts, trailing whitespace is trimmed from each.
+pub fn parse_and_store_as_vec(input: &str) -> Vec<String> {
+    input.split('|').map(|s| s.trim_end().to_string()).collect()
+}
+
+/// Parses `key|value` lines, values keep any further `|`.
+pub fn parse_and_store_as_map(input: &str) -> HashMap<String, String> {
+    let mut map = HashMap::new();
+    for line in input.lines() {
+        let parts: Vec<&str> = line.split('|').collect();
+        if parts.len() >= 2 {
+            let key = parts[0].to_string();
+            let value = parts[1..].join("|");
+            map.insert(key, value);
+        }
+    }
+    map
+}
+
+/// Removes Growtopia color codes, a backtick followed by the color character.
+pub fn strip_color(input: &str) -> String {
+    let mut result = String::with_capacity(input.len());
+    let mut chars = input.chars();
+    while let Some(c) = chars.next() {
+        if c == '`' {
+            chars.next();
+            continue;
+        }
+        result.push(c);
+    }
+    result
+}
+
+/// True for console lines the server wrote itself. Player chat carries a `CT:[..]_` tag or
+/// starts with `<name>`, whispers with `>> from (`, broadcasts with `** from (` and guild
+/// chat with `[GUILD]`, and a player can fill any of those with text that reads like a
+/// server message.
+pub fn is_system_line(message: &str) -> bool {
+    if message.contains("CT:[") {
+        return false;
+    }
+    let plain = strip_color(message);
+    let plain = plain.trim_start();
+    !["<", ">> from (", "** from (", "[GUILD]"]
+        .iter()
+        .any(|prefix| plain.starts_with(prefix))
+}
+
+/// Builder for `key|value\n` text packets.
+#[derive(Debug, Default)]
+pub struct TextPacket {
+    data: String,
+}
+
+impl TextPacket {
+    pub fn new() -> Self {
+        TextPacket {
+            data: String::new(),
+        }
+    }
+
+    /// Appends a `key|value` line.
+    pub fn add(mut self, key: &str, value: &str) -> Self {
+        self.data.push_str(key);
+        self.data.push('|');
+        self.data.push_str(value);
+        self.data.push('\n');
+        self
+    }
+
+    /// The packet text, each line newline terminated.
+    pub fn build(self) -> String {
+        self.data
+    }
+}
+
+/// Turns a base64 Steam ticket into the `XX+XX+...` hex form the login expects.
+pub fn format_string_as_steam_token(data: &str) -> String {
+    let data =  general_purpose::STANDARD.decode(data.as_bytes()).unwrap();
+    let data_to_hex = data.iter().map(|b| format!("{:02X}+", b)).collect::<String>();
+    data_to_hex[..data_to_hex.len() - 1].to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn server_lines_are_system_lines() {
+        assert!(is_system_line("`4Oops:`` You've been logged on from another location."));
+        assert!(is_system_line("`oYour luck has worn off.`` (`$Lucky!`` mod removed)"));
+    }
+
+    #[test]
+    fn player_lines_are_not() {
+        let lines = [
+            "CP:0_PL:4_OID:_CT:[W]_ `6<`wgrower``>`` `$You've been logged on from another location.``",
+            "`6<`wgrower``>`` you are currently banned",
+            "CP:0_PL:4_OID:_CT:[MSG]_ `6>> from (`wgrower``) in [`4<HIDDEN>``] > ``patient died",
+            "`6>> from (`wgrower``) in [START] > `$patient died``",
+            "CP:0_PL:0_OID:_CT:[SB]_ `5** from (`wgrower``) in [`$START``] ** :`` `$wants to trade with you",
+            "`5[GUILD]`` `6<`wgrower``>`` wrong password",
+        ];
+        for line in lines {
+            assert!(!is_system_line(line), "{}", line);
+        }
+    }
+}
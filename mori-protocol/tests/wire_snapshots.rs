@@ -0,0 +1,131 @@
+// Byte-for-byte snapshots of what the client puts on the wire, any change here changes
+// what the server sees
+use mori_protocol::text::{self, TextPacket};
+use mori_protocol::{ETankPacketType, LoginInfo, TankPacket, VariantList};
+
+const TILE_CHANGE: [u8; 56] = [
+    0x03, 0x00, 0x00, 0x00, 0x07, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x12, 0x00, 0x00, 0x00, 0x00, 0x00, 0xa0, 0x43, 0x00, 0x00, 0xc0, 0x42,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x0a, 0x00, 0x00, 0x00,
+    0xff, 0xff, 0xff, 0xff, 0x00, 0x00, 0x00, 0x00,
+];
+
+// OnSetPos with a Vec2 of (320, 96)
+const ON_SET_POS: [u8; 25] = [
+    0x02, 0x00, 0x02, 0x08, 0x00, 0x00, 0x00, 0x4f, 0x6e, 0x53, 0x65, 0x74, 0x50, 0x6f, 0x73, 0x01,
+    0x03, 0x00, 0x00, 0xa0, 0x43, 0x00, 0x00, 0xc0, 0x42,
+];
+
+// NetMessageGamePacket prefix, CallFunction header for net id -1 with the extended flag
+// and 25 bytes of extended data, followed by the OnSetPos variant list above
+const CALL_FUNCTION_MESSAGE: [u8; 85] = [
+    0x04, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0xff, 0xff, 0xff, 0xff, 0x00, 0x00, 0x00, 0x00,
+    0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x19, 0x00, 0x00, 0x00, 0x02, 0x00, 0x02, 0x08,
+    0x00, 0x00, 0x00, 0x4f, 0x6e, 0x53, 0x65, 0x74, 0x50, 0x6f, 0x73, 0x01, 0x03, 0x00, 0x00, 0xa0,
+    0x43, 0x00, 0x00, 0xc0, 0x42,
+];
+
+fn punch() -> TankPacket {
+    TankPacket {
+        _type: ETankPacketType::NetGamePacketTileChangeRequest,
+        net_id: 7,
+        value: 18,
+        vector_x: 320.0,
+        vector_y: 96.0,
+        int_x: 10,
+        int_y: -1,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn tank_packet_header_bytes() {
+    assert_eq!(punch().to_bytes().unwrap(), TILE_CHANGE);
+}
+
+#[test]
+fn tank_packet_header_decodes() {
+    let mut data = TILE_CHANGE.to_vec();
+    // Extended data after the header is not part of it
+    data.extend_from_slice(&[0xde, 0xad]);
+    let packet = TankPacket::from_bytes(&data).unwrap();
+    assert_eq!(packet._type, ETankPacketType::NetGamePacketTileChangeRequest);
+    assert_eq!((packet.net_id, packet.value), (7, 18));
+    assert_eq!((packet.vector_x, packet.vector_y), (320.0, 96.0));
+    assert_eq!((packet.int_x, packet.int_y), (10, -1));
+    assert_eq!(packet.to_bytes().unwrap(), TILE_CHANGE);
+}
+
+#[test]
+fn variant_list_decodes() {
+    let variants = VariantList::deserialize(&ON_SET_POS).unwrap();
+    assert_eq!(variants.get(0).unwrap().as_string(), "OnSetPos");
+    assert_eq!(variants.get(1).unwrap().as_vec2(), (320.0, 96.0));
+    assert!(variants.get(2).is_none());
+}
+
+#[test]
+fn truncated_variant_list_is_an_error() {
+    assert!(VariantList::deserialize(&ON_SET_POS[..20]).is_err());
+}
+
+#[test]
+fn login_packet_text() {
+    let mut info = LoginInfo::new();
+    info.mac = "02:00:00:00:00:00".to_string();
+    info.game_version = "4.71".to_string();
+    info.rid = "RID".to_string();
+    info.token = "TOKEN".to_string();
+    info.wk = "WK".to_string();
+    assert_eq!(
+        info.to_string(),
+        "fhash|-716928004\nmac|02:00:00:00:00:00\nrequestedName|BraveDuck\nhash2|\nfz|47142936\nf|1\n\
+         player_age|20\ngame_version|4.71\nlmode|1\ncbits|1040\nrid|RID\ngdpr|3\nhash|0\ncategory|_-5100\n\
+         token|TOKEN\ntotalPlaytime|0\ndoorID|\nklv|\nmeta|\nplatformID|0,1,1\ndeviceVersion|0\n\
+         zf|-821693372\ncountry|jp\nuser|\nwk|WK"
+    );
+}
+
+#[test]
+fn text_packet_lines() {
+    let packet = TextPacket::new().add("action", "join_request").add("name", "START").build();
+    assert_eq!(packet, "action|join_request\nname|START\n");
+    let map = text::parse_and_store_as_map(&packet);
+    assert_eq!(map.get("name").map(String::as_str), Some("START"));
+}
+
+#[test]
+fn steam_token_is_plus_separated_hex() {
+    assert_eq!(text::format_string_as_steam_token("AQL/"), "01+02+FF");
+}
+
+#[test]
+fn message_with_extended_data_bytes() {
+    let packet = TankPacket {
+        _type: ETankPacketType::NetGamePacketCallFunction,
+        net_id: u32::MAX,
+        flags: 8,
+        extended_data_length: ON_SET_POS.len() as u32,
+        ..Default::default()
+    };
+    assert_eq!(packet.to_message(&ON_SET_POS).unwrap(), CALL_FUNCTION_MESSAGE);
+    let decoded = TankPacket::from_bytes(&CALL_FUNCTION_MESSAGE[4..]).unwrap();
+    assert_eq!(decoded.extended_data_length, 25);
+    assert_eq!(&CALL_FUNCTION_MESSAGE[60..], &ON_SET_POS);
+}
+
+#[test]
+fn message_without_extended_data_is_prefix_and_header() {
+    let message = punch().to_message(&[]).unwrap();
+    assert_eq!(message[..4], [0x04, 0x00, 0x00, 0x00]);
+    assert_eq!(message[4..], TILE_CHANGE);
+}
+
+#[test]
+fn extended_data_length_mismatch_is_an_error() {
+    assert!(punch().to_message(&[0x01]).is_err());
+    let packet = TankPacket { extended_data_length: 4, ..punch() };
+    assert!(packet.to_message(&[0x01, 0x02]).is_err());
+}
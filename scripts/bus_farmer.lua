@@ -0,0 +1,36 @@
+-- Farmer half of the message bus example, pair it with bus_mule.lua.
+-- Put the mule bots in a group called "mules" and run this on each farmer
+local MULES = "mules"
+
+local waiting = false
+
+function on_inventory_nearly_full(free_slots)
+    if waiting then
+        return
+    end
+    local sent = bus.send(MULES, "inventory_full", {
+        world = bot.get_world().name,
+        free_slots = free_slots,
+    })
+    waiting = sent > 0
+    print("asked " .. sent .. " mules for a meet-up")
+end
+
+bus.on("meet", function(message, sender)
+    print(sender .. " is waiting in " .. message.world)
+    bot.warp(message.world)
+    bot.sleep(5000)
+    for _, id in ipairs(message.items) do
+        local item = bot.get_inventory().items[id]
+        if item ~= nil then
+            bot.drop(id, item.amount)
+            bot.sleep(500)
+        end
+    end
+    bus.send(sender, "done", {})
+    waiting = false
+end)
+
+while true do
+    bot.sleep(1000)
+end
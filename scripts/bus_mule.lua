@@ -0,0 +1,21 @@
+-- Mule half of the message bus example, pair it with bus_farmer.lua.
+-- Answers every farmer that fills up with the world to meet in
+local MEET_WORLD = "MULEWORLD"
+local ITEMS = { 242, 1796 }
+
+bus.on("inventory_full", function(message, sender)
+    print(sender .. " is full in " .. message.world .. " (" .. message.free_slots .. " free)")
+    if bot.get_world().name ~= MEET_WORLD then
+        bot.warp(MEET_WORLD)
+    end
+    bus.send(sender, "meet", { world = MEET_WORLD, items = ITEMS })
+end)
+
+bus.on("done", function(_, sender)
+    print(sender .. " finished dropping")
+end)
+
+-- Testing on a single bot: bus.loopback(true) and send "inventory_full" to yourself
+while true do
+    bot.sleep(1000)
+end
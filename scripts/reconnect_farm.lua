@@ -0,0 +1,53 @@
+-- Breaks and places a block next to the bot, pausing while it is offline.
+-- on_login only fires once the bot has spawned in, so the farm never resumes into
+-- a half loaded session
+local BLOCK = 2 -- Dirt
+local OFFSET_X, OFFSET_Y = 1, 0
+
+local online = bot.is_in_world()
+local redirecting = false
+
+function on_login_status(status)
+    print("status: " .. status)
+end
+
+function on_redirect(server)
+    -- The login carries over to the new server, its disconnect isn't a pause
+    redirecting = true
+    print("redirected to " .. server)
+end
+
+function on_connect(server_ip, port)
+    redirecting = false
+    print("connected to " .. server_ip .. ":" .. port)
+end
+
+function on_disconnect(reason, detail, reconnect)
+    if redirecting then
+        return
+    end
+    online = false
+    if reconnect then
+        print("disconnected (" .. reason .. "), pausing until the next login")
+    else
+        print("disconnected (" .. reason .. ": " .. detail .. "), not coming back")
+    end
+end
+
+function on_login(net_id, user_id)
+    online = true
+    print("logged in as net id " .. net_id .. ", resuming")
+end
+
+while true do
+    if online and bot.is_in_world() then
+        bot.place(OFFSET_X, OFFSET_Y, BLOCK)
+        bot.sleep(250)
+        for _ = 1, 4 do
+            bot.punch(OFFSET_X, OFFSET_Y)
+            bot.sleep(250)
+        end
+    else
+        bot.sleep(1000)
+    end
+end
@@ -0,0 +1,218 @@
+use super::astar::PathError;
+use super::Bot;
+use crate::types::{etank_packet_type::ETankPacketType, tank_packet::TankPacket};
+use crate::utils::config;
+use gtworld_r::TileType;
+use std::thread;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+// The server ignores activations from further than one tile away
+const ACTIVATE_RANGE: i32 = 1;
+const MAIN_DOOR_ID: u16 = 6;
+// items.dat action types
+const ACTION_USER_DOOR: u8 = 2;
+const ACTION_DOOR: u8 = 13;
+const ACTION_PORTAL: u8 = 26;
+const ACTION_CHECKPOINT: u8 = 27;
+const ACTION_SWITCHEROO: u8 = 31;
+const DOOR_TIMEOUT: Duration = Duration::from_secs(5);
+const SWITCH_TIMEOUT: Duration = Duration::from_secs(3);
+
+#[derive(Error, Debug)]
+pub enum ActivateError {
+    #[error("Not in a world")]
+    NotInWorld,
+    #[error("Tile {0}|{1} is outside the world")]
+    OutOfBounds(u32, u32),
+    #[error("Tile {0}|{1} is out of range")]
+    OutOfRange(u32, u32),
+    #[error("Can't walk to the tile: {0}")]
+    NoRoute(PathError),
+    #[error("Tile {0}|{1} is not a door")]
+    NotADoor(u32, u32),
+    #[error("Tile {0}|{1} is not a switch")]
+    NotASwitch(u32, u32),
+    #[error("Tile {0}|{1} is not a checkpoint")]
+    NotACheckpoint(u32, u32),
+    #[error("Door didn't take the bot anywhere")]
+    NoTeleport,
+    #[error("Main door not found")]
+    NoMainDoor,
+    #[error("Server didn't confirm the toggle")]
+    NotConfirmed,
+}
+
+// Sends a tile activation, walking into range first when walk_to_activate is set
+pub fn tile_activate(bot: &Bot, x: u32, y: u32) -> Result<(), ActivateError> {
+    tile(bot, x, y)?;
+    walk_into_range(bot, x, y)?;
+    send_activate(bot, x, y);
+    Ok(())
+}
+
+fn walk_into_range(bot: &Bot, x: u32, y: u32) -> Result<(), ActivateError> {
+    if !in_range(bot, x, y) {
+        if !config::get_walk_to_activate() {
+            return Err(ActivateError::OutOfRange(x, y));
+        }
+        bot.find_path(x, y).map_err(ActivateError::NoRoute)?;
+        if !in_range(bot, x, y) {
+            return Err(ActivateError::OutOfRange(x, y));
+        }
+    }
+    Ok(())
+}
+
+fn send_activate(bot: &Bot, x: u32, y: u32) {
+    let mut pkt = TankPacket::default();
+    pkt._type = ETankPacketType::NetGamePacketTileActivateRequest;
+    {
+        let position = bot.position.lock().expect("Failed to lock position");
+        pkt.vector_x = position.x;
+        pkt.vector_y = position.y;
+    }
+    pkt.int_x = x as i32;
+    pkt.int_y = y as i32;
+    bot.send_packet_raw(&pkt, None);
+}
+
+// Activates a door and waits for the server to move the bot, either the world packet of
+// another world or a new position in this one
+pub fn enter_door(bot: &Bot, x: u32, y: u32) -> Result<(), ActivateError> {
+    let (fg, action_type, is_door) = tile(bot, x, y)?;
+    if !is_door
+        && fg != MAIN_DOOR_ID
+        && ![ACTION_USER_DOOR, ACTION_DOOR, ACTION_PORTAL].contains(&action_type)
+    {
+        return Err(ActivateError::NotADoor(x, y));
+    }
+    let world_before = bot.world.read().unwrap().name.clone();
+    walk_into_range(bot, x, y)?;
+    let sent = Instant::now();
+    bot.temporary_data.write().unwrap().door_entered = Some(sent);
+    send_activate(bot, x, y);
+
+    while sent.elapsed() < DOOR_TIMEOUT {
+        let set_pos = bot.temporary_data.read().unwrap().last_set_pos;
+        if set_pos.map_or(false, |at| at >= sent) || bot.world.read().unwrap().name != world_before {
+            return Ok(());
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+    Err(ActivateError::NoTeleport)
+}
+
+// Activates a switcheroo and waits for the server to send the tile's new state
+pub fn toggle_switch(bot: &Bot, x: u32, y: u32) -> Result<(), ActivateError> {
+    let (_, action_type, _) = tile(bot, x, y)?;
+    if action_type != ACTION_SWITCHEROO {
+        return Err(ActivateError::NotASwitch(x, y));
+    }
+    let sent = Instant::now();
+    tile_activate(bot, x, y)?;
+
+    while sent.elapsed() < SWITCH_TIMEOUT {
+        let updated = bot
+            .temporary_data
+            .read()
+            .unwrap()
+            .last_tile_update
+            .map_or(false, |(update_x, update_y, at)| {
+                update_x == x && update_y == y && at >= sent
+            });
+        if updated {
+            return Ok(());
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+    Err(ActivateError::NotConfirmed)
+}
+
+// Checkpoints only move the respawn point, there's nothing to wait for
+pub fn use_checkpoint(bot: &Bot, x: u32, y: u32) -> Result<(), ActivateError> {
+    let (_, action_type, _) = tile(bot, x, y)?;
+    if action_type != ACTION_CHECKPOINT {
+        return Err(ActivateError::NotACheckpoint(x, y));
+    }
+    tile_activate(bot, x, y)
+}
+
+// Whether the server moving the bot to x,y is on purpose rather than a desync: right after
+// enter_door, or onto a door, portal or checkpoint, where doors lead and deaths respawn
+pub fn is_teleport(bot: &Bot, x: f32, y: f32) -> bool {
+    let door_entered = bot.temporary_data.write().unwrap().door_entered.take();
+    if door_entered.map_or(false, |at| at.elapsed() < DOOR_TIMEOUT) {
+        return true;
+    }
+    if x < 0.0 || y < 0.0 {
+        return false;
+    }
+    tile(bot, (x / 32.0).floor() as u32, (y / 32.0).floor() as u32)
+        .map_or(false, |(fg, action_type, is_door)| is_arrival(fg, action_type, is_door))
+}
+
+fn is_arrival(fg: u16, action_type: u8, is_door: bool) -> bool {
+    is_door
+        || fg == MAIN_DOOR_ID
+        || [ACTION_USER_DOOR, ACTION_DOOR, ACTION_PORTAL, ACTION_CHECKPOINT].contains(&action_type)
+}
+
+// Foreground id, its action type and whether the tile carries door data
+fn tile(bot: &Bot, x: u32, y: u32) -> Result<(u16, u8, bool), ActivateError> {
+    if !bot.is_inworld() {
+        return Err(ActivateError::NotInWorld);
+    }
+    let (fg, is_door) = {
+        let world = bot.world.read().unwrap();
+        if x >= world.width || y >= world.height {
+            return Err(ActivateError::OutOfBounds(x, y));
+        }
+        let tile = world
+            .get_tile(x, y)
+            .ok_or(ActivateError::OutOfBounds(x, y))?;
+        (
+            tile.foreground_item_id,
+            matches!(tile.tile_type, TileType::Door { .. }),
+        )
+    };
+    let action_type = bot
+        .item_database
+        .read()
+        .unwrap()
+        .get_item(&(fg as u32))
+        .map_or(0, |item| item.action_type);
+    Ok((fg, action_type, is_door))
+}
+
+fn bot_tile(bot: &Bot) -> (i32, i32) {
+    let position = bot.position.lock().expect("Failed to lock position");
+    ((position.x / 32.0).floor() as i32, (position.y / 32.0).floor() as i32)
+}
+
+fn in_range(bot: &Bot, x: u32, y: u32) -> bool {
+    let (tile_x, tile_y) = bot_tile(bot);
+    (tile_x - x as i32).abs() <= ACTIVATE_RANGE && (tile_y - y as i32).abs() <= ACTIVATE_RANGE
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn doors_and_respawn_points_are_arrivals() {
+        assert!(is_arrival(MAIN_DOOR_ID, 0, true));
+        assert!(is_arrival(MAIN_DOOR_ID, 0, false));
+        assert!(is_arrival(12, ACTION_DOOR, true));
+        assert!(is_arrival(3398, ACTION_PORTAL, false));
+        assert!(is_arrival(762, ACTION_USER_DOOR, false));
+        assert!(is_arrival(1684, ACTION_CHECKPOINT, false));
+    }
+
+    #[test]
+    fn other_tiles_are_not() {
+        assert!(!is_arrival(0, 0, false));
+        assert!(!is_arrival(2, 0, false));
+        assert!(!is_arrival(3400, ACTION_SWITCHEROO, false));
+    }
+}
@@ -1,18 +1,53 @@
 use gtitem_r::structs::ItemDatabase;
+use std::sync::atomic::{AtomicBool, Ordering as AtomicOrdering};
 use std::sync::RwLock;
 use std::{
-    cmp::Ordering,
+    cmp::{Ordering, Reverse},
     collections::{BinaryHeap, HashMap, HashSet},
     sync::Arc,
 };
 
-use super::Bot;
+use super::{hazard, Bot};
+use crate::types::ehazard_mode::EHazardMode;
+use thiserror::Error;
+
+const HAZARD_PENALTY: u32 = 100;
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum PathError {
+    #[error("Not in a world")]
+    NotInWorld,
+    #[error("Target is outside the world")]
+    TargetOutOfBounds,
+    #[error("Target tile is blocked")]
+    TargetBlocked,
+    #[error("No route to the target")]
+    NoRoute,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PathHandle {
+    Reached,
+    // The target itself was out of reach, walked to (x, y) `distance` tiles away instead
+    ReachedNearby { x: u32, y: u32, distance: u32 },
+}
 
 pub struct AStar {
     pub width: u32,
     pub height: u32,
     pub grid: Vec<Node>,
     pub item_database: Arc<RwLock<ItemDatabase>>,
+    pub hazard_mode: EHazardMode,
+    // Collision doesn't apply, every tile inside the world is walkable
+    pub noclip: bool,
+}
+
+// Stretch of a path walked in one go, ends at (x, y) `tiles` tiles from where it starts
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Segment {
+    pub x: u32,
+    pub y: u32,
+    pub tiles: u32,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -23,10 +58,11 @@ pub struct Node {
     pub x: u32,
     pub y: u32,
     pub collision_type: u8,
+    pub harmful: bool,
 }
 
 impl Node {
-    pub fn new(x: u32, y: u32, collision_type: u8) -> Node {
+    pub fn new(x: u32, y: u32, collision_type: u8, harmful: bool) -> Node {
         Node {
             g: 0,
             h: 0,
@@ -34,6 +70,7 @@ impl Node {
             x,
             y,
             collision_type,
+            harmful,
         }
     }
 }
@@ -57,6 +94,8 @@ impl AStar {
             height: 0,
             grid: Vec::new(),
             item_database,
+            hazard_mode: EHazardMode::default(),
+            noclip: false,
         }
     }
 
@@ -67,8 +106,11 @@ impl AStar {
     }
 
     pub fn update(&mut self, bot: &Bot) {
+        self.load_world(&bot.world.read().unwrap());
+    }
+
+    pub fn load_world(&mut self, world: &gtworld_r::World) {
         self.reset();
-        let world = bot.world.read().unwrap();
         self.width = world.width;
         self.height = world.height;
         for i in 0..world.tiles.len() {
@@ -79,11 +121,24 @@ impl AStar {
                 .get_item(&(world.tiles[i].foreground_item_id as u32))
                 .unwrap();
             let collision_type = item.collision_type;
-            self.grid.push(Node::new(x, y, collision_type));
+            let harmful = hazard::is_harmful(&item);
+            self.grid.push(Node::new(x, y, collision_type, harmful));
         }
     }
 
     pub fn find_path(&self, from_x: u32, from_y: u32, to_x: u32, to_y: u32) -> Option<Vec<Node>> {
+        self.find_path_cancellable(from_x, from_y, to_x, to_y, &AtomicBool::new(false))
+    }
+
+    // Gives up and returns None as soon as `cancel` is set
+    pub fn find_path_cancellable(
+        &self,
+        from_x: u32,
+        from_y: u32,
+        to_x: u32,
+        to_y: u32,
+        cancel: &AtomicBool,
+    ) -> Option<Vec<Node>> {
         let mut open_list = BinaryHeap::new();
         let mut came_from: HashMap<(u32, u32), (u32, u32)> = HashMap::new();
         let mut closed_set: HashSet<(u32, u32)> = HashSet::new();
@@ -97,6 +152,9 @@ impl AStar {
         open_list.push(start_node);
 
         while let Some(current_node) = open_list.pop() {
+            if cancel.load(AtomicOrdering::Relaxed) {
+                return None;
+            }
             if current_node.x == to_x && current_node.y == to_y {
                 return Some(self.reconstruct_path(&came_from, (to_x, to_y), (from_x, from_y)));
             }
@@ -131,6 +189,116 @@ impl AStar {
         None
     }
 
+    // Single Dijkstra flood that stops at the first target it reaches, so finding the
+    // nearest of n targets costs one search instead of n. Equal costs are broken by
+    // lowest y then lowest x, which keeps the choice stable between calls
+    pub fn find_path_to_any(
+        &self,
+        from_x: u32,
+        from_y: u32,
+        targets: &[(u32, u32)],
+    ) -> Option<Vec<Node>> {
+        let targets: HashSet<(u32, u32)> = targets
+            .iter()
+            .copied()
+            .filter(|&(x, y)| x < self.width && y < self.height)
+            .collect();
+        if targets.is_empty() || from_x >= self.width || from_y >= self.height {
+            return None;
+        }
+
+        let mut open_list = BinaryHeap::new();
+        let mut best_g: HashMap<(u32, u32), u32> = HashMap::new();
+        let mut came_from: HashMap<(u32, u32), (u32, u32)> = HashMap::new();
+        let mut closed_set: HashSet<(u32, u32)> = HashSet::new();
+
+        best_g.insert((from_x, from_y), 0);
+        open_list.push(Reverse((0, from_y, from_x)));
+
+        while let Some(Reverse((g, y, x))) = open_list.pop() {
+            if targets.contains(&(x, y)) {
+                return Some(self.reconstruct_path(&came_from, (x, y), (from_x, from_y)));
+            }
+            if !closed_set.insert((x, y)) {
+                continue;
+            }
+
+            let current_node = &self.grid[(y * self.width + x) as usize];
+            for neighbor in self.get_neighbors(current_node) {
+                let position = (neighbor.x, neighbor.y);
+                if closed_set.contains(&position) {
+                    continue;
+                }
+                let tentative_g = g + self.movement_cost(current_node, &neighbor);
+                if best_g.get(&position).map_or(true, |&known| tentative_g < known) {
+                    best_g.insert(position, tentative_g);
+                    came_from.insert(position, (x, y));
+                    open_list.push(Reverse((tentative_g, neighbor.y, neighbor.x)));
+                }
+            }
+        }
+
+        None
+    }
+
+    // Route to the target, or when it is blocked or cut off to the nearest reachable tile
+    // at most `tolerance` tiles from it. The error is why the exact target failed
+    pub fn plan(
+        &self,
+        from_x: u32,
+        from_y: u32,
+        to_x: u32,
+        to_y: u32,
+        tolerance: u32,
+    ) -> Result<(Vec<Node>, PathHandle), PathError> {
+        if self.width == 0 {
+            return Err(PathError::NotInWorld);
+        }
+        if to_x >= self.width || to_y >= self.height {
+            return Err(PathError::TargetOutOfBounds);
+        }
+        let exact = if self.is_passable(to_x, to_y) {
+            self.find_path(from_x, from_y, to_x, to_y).ok_or(PathError::NoRoute)
+        } else {
+            Err(PathError::TargetBlocked)
+        };
+        match exact {
+            Ok(path) => Ok((path, PathHandle::Reached)),
+            Err(error) => {
+                let (path, distance) = self
+                    .find_path_near(from_x, from_y, to_x, to_y, tolerance)
+                    .ok_or(error)?;
+                let (x, y) = path.last().map_or((from_x, from_y), |node| (node.x, node.y));
+                Ok((path, PathHandle::ReachedNearby { x, y, distance }))
+            }
+        }
+    }
+
+    // Route to the reachable tile closest to (to_x, to_y), at most `tolerance` tiles away.
+    // Searches ring by ring so a nearer tile always wins over a cheaper route to a farther one
+    pub fn find_path_near(
+        &self,
+        from_x: u32,
+        from_y: u32,
+        to_x: u32,
+        to_y: u32,
+        tolerance: u32,
+    ) -> Option<(Vec<Node>, u32)> {
+        for distance in 1..=tolerance as i32 {
+            let ring: Vec<(u32, u32)> = (-distance..=distance)
+                .flat_map(|dy| (-distance..=distance).map(move |dx| (dx, dy)))
+                .filter(|&(dx, dy)| dx.abs().max(dy.abs()) == distance)
+                .map(|(dx, dy)| (to_x as i32 + dx, to_y as i32 + dy))
+                .filter(|&(x, y)| x >= 0 && y >= 0 && self.is_passable(x as u32, y as u32))
+                .map(|(x, y)| (x as u32, y as u32))
+                .collect();
+            if let Some(path) = self.find_path_to_any(from_x, from_y, &ring) {
+                return Some((path, distance as u32));
+            }
+        }
+        None
+    }
+
     fn movement_cost(&self, from: &Node, to: &Node) -> u32 {
         let dx = if to.x > from.x {
             to.x - from.x
@@ -142,13 +310,21 @@ impl AStar {
         } else {
             from.y - to.y
         };
-        if dx == 1 && dy == 1 {
-            14
+        let cost = if dx == 1 && dy == 1 { 14 } else { 10 };
+        if to.harmful && self.hazard_mode == EHazardMode::Penalty {
+            cost + HAZARD_PENALTY
         } else {
-            10
+            cost
         }
     }
 
+    // Total movement cost of a path returned by one of the searches
+    pub fn path_cost(&self, path: &[Node]) -> u32 {
+        path.windows(2)
+            .map(|step| self.movement_cost(&step[0], &step[1]))
+            .sum()
+    }
+
     fn calculate_h(&self, from_x: u32, from_y: u32, to_x: u32, to_y: u32) -> u32 {
         let dx = if to_x > from_x {
             to_x - from_x
@@ -163,6 +339,59 @@ impl AStar {
         14 * dx.min(dy) + 10 * (dx.max(dy) - dx.min(dy))
     }
 
+    // Merges straight horizontal runs along the ground into segments of at most `max_tiles`.
+    // Climbs, drops, diagonals and tiles with nothing underneath stay one node each
+    pub fn smooth_path(&self, start: (u32, u32), path: &[Node], max_tiles: u32) -> Vec<Segment> {
+        let mut segments: Vec<Segment> = Vec::new();
+        let mut previous = start;
+        // Direction of the run the last segment is part of, 0 when it can't be extended
+        let mut run_direction = 0i64;
+        for node in path {
+            let dx = node.x as i64 - previous.0 as i64;
+            let horizontal =
+                node.y == previous.1 && dx.abs() == 1 && self.is_supported(node.x, node.y);
+            let extends = horizontal
+                && dx == run_direction
+                && segments.last().map_or(false, |last| last.tiles < max_tiles);
+            match segments.last_mut() {
+                Some(last) if extends => {
+                    last.x = node.x;
+                    last.tiles += 1;
+                }
+                _ => segments.push(Segment { x: node.x, y: node.y, tiles: 1 }),
+            }
+            run_direction = if horizontal && self.is_supported(previous.0, previous.1) {
+                dx
+            } else {
+                0
+            };
+            previous = (node.x, node.y);
+        }
+        segments
+    }
+
+    // Something to stand on below the tile, or no gravity to fall with
+    fn is_supported(&self, x: u32, y: u32) -> bool {
+        if self.noclip || y + 1 >= self.height {
+            return true;
+        }
+        self.grid[((y + 1) * self.width + x) as usize].collision_type != 0
+    }
+
+    // Whether the bot can stand in the tile, false outside the world
+    pub fn is_passable(&self, x: u32, y: u32) -> bool {
+        if x >= self.width || y >= self.height {
+            return false;
+        }
+        let node = &self.grid[(y * self.width + x) as usize];
+        if self.noclip {
+            return !(node.harmful && self.hazard_mode == EHazardMode::Block);
+        }
+        !(node.collision_type == 1
+            || node.collision_type == 6
+            || node.harmful && self.hazard_mode == EHazardMode::Block)
+    }
+
     fn get_neighbors(&self, node: &Node) -> Vec<Node> {
         let mut neighbors = Vec::new();
         let directions = [
@@ -184,11 +413,11 @@ impl AStar {
                 let index = (new_y as u32 * self.width + new_x as u32) as usize;
                 let neighbor = &self.grid[index];
 
-                if neighbor.collision_type == 1 || neighbor.collision_type == 6 {
+                if !self.is_passable(neighbor.x, neighbor.y) {
                     continue;
                 }
 
-                if dx != 0 && dy != 0 {
+                if dx != 0 && dy != 0 && !self.noclip {
                     let adj1_x = node.x as i32 + dx;
                     let adj1_y = node.y as i32;
                     let adj2_x = node.x as i32;
@@ -254,3 +483,283 @@ impl AStar {
         path
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    // '#' is solid, anything else walkable
+    fn grid(rows: &[&str]) -> AStar {
+        let mut astar = AStar::new(Arc::new(RwLock::new(ItemDatabase::new())));
+        astar.height = rows.len() as u32;
+        astar.width = rows[0].len() as u32;
+        for (y, row) in rows.iter().enumerate() {
+            for (x, tile) in row.chars().enumerate() {
+                let collision_type = if tile == '#' { 1 } else { 0 };
+                astar.grid.push(Node::new(x as u32, y as u32, collision_type, false));
+            }
+        }
+        astar
+    }
+
+    fn end(path: &[Node]) -> (u32, u32) {
+        let last = path.last().unwrap();
+        (last.x, last.y)
+    }
+
+    #[test]
+    fn reaches_the_nearest_target() {
+        let astar = grid(&[
+            "..........",
+            "..........",
+            "..........",
+        ]);
+        let path = astar.find_path_to_any(0, 1, &[(9, 1), (3, 1), (6, 0)]).unwrap();
+        assert_eq!(end(&path), (3, 1));
+        assert_eq!(astar.path_cost(&path), 30);
+    }
+
+    #[test]
+    fn nearest_is_by_route_not_by_distance() {
+        let astar = grid(&[
+            "..#.......",
+            "..#.......",
+            "..#.......",
+            "..........",
+        ]);
+        // (3,0) is closer in a straight line but behind the wall
+        let path = astar.find_path_to_any(0, 0, &[(3, 0), (0, 3)]).unwrap();
+        assert_eq!(end(&path), (0, 3));
+    }
+
+    #[test]
+    fn ties_break_by_lowest_y_then_x() {
+        let astar = grid(&[
+            ".....",
+            ".....",
+            ".....",
+            ".....",
+            ".....",
+        ]);
+        for targets in [[(4, 2), (0, 2), (2, 0)], [(2, 0), (4, 2), (0, 2)]] {
+            let path = astar.find_path_to_any(2, 2, &targets).unwrap();
+            assert_eq!(end(&path), (2, 0));
+        }
+        for targets in [[(4, 2), (0, 2)], [(0, 2), (4, 2)]] {
+            let path = astar.find_path_to_any(2, 2, &targets).unwrap();
+            assert_eq!(end(&path), (0, 2));
+        }
+    }
+
+    #[test]
+    fn unreachable_and_out_of_bounds_targets() {
+        let astar = grid(&[
+            "..#..",
+            "..#..",
+            "..#..",
+        ]);
+        assert_eq!(astar.find_path_to_any(0, 0, &[(4, 1), (9, 9)]), None);
+        assert_eq!(astar.find_path_to_any(0, 0, &[]), None);
+        let path = astar.find_path_to_any(0, 0, &[(0, 0)]).unwrap();
+        assert_eq!(path.len(), 1);
+    }
+
+    #[test]
+    fn matches_the_cheapest_single_target_search() {
+        let astar = grid(&[
+            "....#.....",
+            "..#.#..#..",
+            "..#...##..",
+            "..####....",
+            "..........",
+        ]);
+        let targets = [(9, 0), (5, 1), (0, 4), (8, 4), (6, 0)];
+        let flood = astar.find_path_to_any(0, 0, &targets).unwrap();
+        let cheapest = targets
+            .iter()
+            .filter_map(|&(x, y)| astar.find_path(0, 0, x, y))
+            .map(|path| astar.path_cost(&path))
+            .min()
+            .unwrap();
+        assert_eq!(astar.path_cost(&flood), cheapest);
+    }
+
+    #[test]
+    fn plan_needs_a_world() {
+        let astar = AStar::new(Arc::new(RwLock::new(ItemDatabase::new())));
+        assert_eq!(astar.plan(0, 0, 1, 1, 2).unwrap_err(), PathError::NotInWorld);
+    }
+
+    #[test]
+    fn plan_rejects_targets_outside_the_world() {
+        let astar = grid(&["....", "...."]);
+        assert_eq!(astar.plan(0, 0, 4, 0, 2).unwrap_err(), PathError::TargetOutOfBounds);
+        assert_eq!(astar.plan(0, 0, 0, 2, 2).unwrap_err(), PathError::TargetOutOfBounds);
+    }
+
+    #[test]
+    fn plan_reports_blocked_targets() {
+        let astar = grid(&["...#", "...."]);
+        assert_eq!(astar.plan(0, 0, 3, 0, 0).unwrap_err(), PathError::TargetBlocked);
+    }
+
+    #[test]
+    fn plan_reports_missing_routes() {
+        let astar = grid(&["..#..", "..#..", "..#.."]);
+        assert_eq!(astar.plan(0, 0, 4, 1, 0).unwrap_err(), PathError::NoRoute);
+        // Nothing reachable within the tolerance either, the exact failure is kept
+        assert_eq!(astar.plan(0, 0, 4, 1, 1).unwrap_err(), PathError::NoRoute);
+    }
+
+    #[test]
+    fn plan_walks_next_to_blocked_targets() {
+        let astar = grid(&["...#", "...."]);
+        let (path, handle) = astar.plan(0, 0, 3, 0, 1).unwrap();
+        assert_eq!(handle, PathHandle::ReachedNearby { x: 2, y: 0, distance: 1 });
+        assert_eq!(end(&path), (2, 0));
+    }
+
+    #[test]
+    fn plan_reaches_open_targets() {
+        let astar = grid(&["....", "...."]);
+        let (path, handle) = astar.plan(0, 0, 3, 1, 2).unwrap();
+        assert_eq!(handle, PathHandle::Reached);
+        assert_eq!(end(&path), (3, 1));
+    }
+
+    // cargo test --release -- --ignored --nocapture bench_nearest
+    #[test]
+    #[ignore]
+    fn bench_nearest_of_many() {
+        let row = ".".repeat(100);
+        let mut rows: Vec<String> = (0..60).map(|_| row.clone()).collect();
+        for y in (5..55).step_by(6) {
+            rows[y] = format!("{}{}", "#".repeat(90), ".".repeat(10));
+        }
+        let rows: Vec<&str> = rows.iter().map(String::as_str).collect();
+        let astar = grid(&rows);
+        let targets: Vec<(u32, u32)> = (0..64).map(|i| ((i * 37) % 100, 56 + i % 4)).collect();
+
+        let started = Instant::now();
+        let flood = astar.find_path_to_any(0, 0, &targets).unwrap();
+        let flood_time = started.elapsed();
+
+        let started = Instant::now();
+        let naive = targets
+            .iter()
+            .filter_map(|&(x, y)| astar.find_path(0, 0, x, y))
+            .min_by_key(|path| astar.path_cost(path))
+            .unwrap();
+        let naive_time = started.elapsed();
+
+        assert_eq!(astar.path_cost(&flood), astar.path_cost(&naive));
+        println!(
+            "{} targets: one flood {:?}, one search per target {:?}",
+            targets.len(),
+            flood_time,
+            naive_time
+        );
+    }
+
+    // cargo test --release -- --ignored --nocapture bench_corridor
+    // Walking a long corridor with a config read per node: parsing the config the way every
+    // getter did before the cache (the file read left out, so this is a lower bound) against
+    // the cached getter
+    #[test]
+    #[ignore]
+    fn bench_corridor_config_reads() {
+        // Rows joined by a gap at alternating ends, so the path runs the full width every time
+        let mut rows: Vec<String> = Vec::new();
+        for y in 0..59 {
+            rows.push(match y % 4 {
+                1 => format!("{}.", "#".repeat(99)),
+                3 => format!(".{}", "#".repeat(99)),
+                _ => ".".repeat(100),
+            });
+        }
+        let rows: Vec<&str> = rows.iter().map(String::as_str).collect();
+        let astar = grid(&rows);
+        let json = serde_json::to_string(&crate::types::config::Config::default()).unwrap();
+
+        let started = Instant::now();
+        let path = astar.find_path(0, 0, 0, 58).unwrap();
+        let mut delay = 0;
+        for _ in &path {
+            let config: crate::types::config::Config = serde_json::from_str(&json).unwrap();
+            delay += config.findpath_delay;
+        }
+        let parsed_time = started.elapsed();
+
+        let started = Instant::now();
+        let path = astar.find_path(0, 0, 0, 58).unwrap();
+        for _ in &path {
+            delay += crate::utils::config::get_findpath_delay();
+        }
+        let cached_time = started.elapsed();
+
+        assert!(delay > 0);
+        println!(
+            "{} node corridor: config parsed per node {:?}, cached {:?}",
+            path.len(),
+            parsed_time,
+            cached_time
+        );
+    }
+
+    fn nodes(tiles: &[(u32, u32)]) -> Vec<Node> {
+        tiles.iter().map(|&(x, y)| Node::new(x, y, 0, false)).collect()
+    }
+
+    fn segment(x: u32, y: u32, tiles: u32) -> Segment {
+        Segment { x, y, tiles }
+    }
+
+    #[test]
+    fn merges_a_straight_run_up_to_the_max() {
+        let astar = grid(&[
+            "..........",
+            "##########",
+        ]);
+        let path = nodes(&(1..=8).map(|x| (x, 0)).collect::<Vec<_>>());
+        assert_eq!(astar.smooth_path((0, 0), &path, 6), vec![segment(6, 0, 6), segment(8, 0, 2)]);
+        assert_eq!(astar.smooth_path((0, 0), &path, 1).len(), 8);
+    }
+
+    #[test]
+    fn keeps_corners_and_drops_as_nodes() {
+        let astar = grid(&[
+            "......",
+            "###...",
+            "......",
+            "######",
+        ]);
+        // Along the ledge, off its end, down two tiles and along the floor
+        let path = nodes(&[(1, 0), (2, 0), (3, 0), (3, 1), (3, 2), (4, 2), (5, 2)]);
+        assert_eq!(
+            astar.smooth_path((0, 0), &path, 6),
+            vec![
+                segment(2, 0, 2),
+                segment(3, 0, 1),
+                segment(3, 1, 1),
+                segment(3, 2, 1),
+                segment(5, 2, 2),
+            ]
+        );
+    }
+
+    #[test]
+    fn walks_a_staircase_tile_by_tile() {
+        let astar = grid(&[
+            ".....",
+            "...##",
+            "..###",
+            ".####",
+            "#####",
+        ]);
+        let path = nodes(&[(0, 2), (1, 2), (1, 1), (2, 1), (2, 0), (3, 0)]);
+        let segments = astar.smooth_path((0, 3), &path, 6);
+        assert_eq!(segments.len(), path.len());
+        assert!(segments.iter().all(|segment| segment.tiles == 1));
+    }
+}
@@ -0,0 +1,209 @@
+use super::timer_wheel;
+use crate::manager::notifications;
+use crate::types::eseverity::ESeverity;
+use crate::utils::{config, date};
+use paris::{info, warn};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::thread;
+use std::time::Duration;
+
+const MB: u64 = 1024 * 1024;
+const CHECK_INTERVAL: Duration = Duration::from_secs(10);
+// A rate from the first seconds of an hour says nothing
+const MIN_RATE_WINDOW: u64 = 60;
+
+// Bytes of every bot in the current clock hour, and whether that went over the cap
+static FARM_HOUR: HourCounter = HourCounter::new();
+static THROTTLED: AtomicBool = AtomicBool::new(false);
+
+// The hour and its bytes share one atomic, so a count racing the hour change can neither
+// be lost to the reset nor land in the old hour. 40 bits are a terabyte per hour
+const BYTE_BITS: u32 = 40;
+const BYTE_MASK: u64 = (1 << BYTE_BITS) - 1;
+const HOUR_MASK: u64 = (1 << (64 - BYTE_BITS)) - 1;
+
+// Bytes counted into the current clock hour, restarted on the first count of a new one
+struct HourCounter {
+    packed: AtomicU64,
+}
+
+impl HourCounter {
+    const fn new() -> Self {
+        HourCounter {
+            packed: AtomicU64::new(0),
+        }
+    }
+
+    fn add(&self, bytes: u64) {
+        self.add_at(bytes, date::now_secs());
+    }
+
+    fn get(&self) -> u64 {
+        self.get_at(date::now_secs())
+    }
+
+    // A count that read the clock just before another thread moved on to the next hour
+    // goes into that next hour rather than resetting it
+    fn add_at(&self, bytes: u64, now_secs: u64) {
+        let _ = self.packed.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |packed| {
+            let hour = (now_secs / 3600 & HOUR_MASK).max(packed >> BYTE_BITS);
+            let counted = if packed >> BYTE_BITS == hour { packed & BYTE_MASK } else { 0 };
+            Some(hour << BYTE_BITS | (counted + bytes).min(BYTE_MASK))
+        });
+    }
+
+    fn get_at(&self, now_secs: u64) -> u64 {
+        let packed = self.packed.load(Ordering::Relaxed);
+        if packed >> BYTE_BITS != now_secs / 3600 & HOUR_MASK {
+            return 0;
+        }
+        packed & BYTE_MASK
+    }
+}
+
+impl Default for HourCounter {
+    fn default() -> Self {
+        HourCounter::new()
+    }
+}
+
+// Payload bytes of one bot both ways, bumped from the event loop without taking a lock.
+// ENet's own headers and acks never reach the bot and aren't counted
+#[derive(Default)]
+pub struct Bandwidth {
+    bytes_in: AtomicU64,
+    bytes_out: AtomicU64,
+    hour: HourCounter,
+    // Not yet added to the daily stats
+    unsaved_in: AtomicU64,
+    unsaved_out: AtomicU64,
+}
+
+impl Bandwidth {
+    pub fn record_in(&self, bytes: usize) {
+        let bytes = bytes as u64;
+        self.bytes_in.fetch_add(bytes, Ordering::Relaxed);
+        self.unsaved_in.fetch_add(bytes, Ordering::Relaxed);
+        self.hour.add(bytes);
+        FARM_HOUR.add(bytes);
+    }
+
+    pub fn record_out(&self, bytes: usize) {
+        let bytes = bytes as u64;
+        self.bytes_out.fetch_add(bytes, Ordering::Relaxed);
+        self.unsaved_out.fetch_add(bytes, Ordering::Relaxed);
+        self.hour.add(bytes);
+        FARM_HOUR.add(bytes);
+    }
+
+    // In and out since the bot was created
+    pub fn totals(&self) -> (u64, u64) {
+        (self.bytes_in.load(Ordering::Relaxed), self.bytes_out.load(Ordering::Relaxed))
+    }
+
+    pub fn mb_per_hour(&self) -> f64 {
+        rate(self.hour.get())
+    }
+
+    // In and out since the last call, for the daily stats
+    pub fn take_unsaved(&self) -> (u64, u64) {
+        (
+            self.unsaved_in.swap(0, Ordering::Relaxed),
+            self.unsaved_out.swap(0, Ordering::Relaxed),
+        )
+    }
+}
+
+pub fn farm_mb_per_hour() -> f64 {
+    rate(FARM_HOUR.get())
+}
+
+// Set while the farm is over its hourly budget, low priority traffic checks this and holds off
+pub fn throttled() -> bool {
+    THROTTLED.load(Ordering::Relaxed)
+}
+
+// Checks the farm against bandwidth_cap_mb every few seconds and lifts the throttle once a
+// new hour starts or the cap is raised
+pub fn spawn() {
+    thread::spawn(|| loop {
+        let cap = config::get_bandwidth_cap_mb() as u64;
+        let used = FARM_HOUR.get();
+        let over = cap > 0 && used > cap * MB;
+        if over && !THROTTLED.swap(true, Ordering::Relaxed) {
+            warn!("Bandwidth cap of {} MB reached this hour, holding off low priority traffic", cap);
+            notifications::publish(
+                "Mori",
+                ESeverity::Warning,
+                format!(
+                    "{} MB used this hour, over the {} MB cap. Telemetry, idle heartbeats, whisper auto-replies and world watching are paused until the next hour",
+                    used / MB,
+                    cap
+                ),
+                None,
+            );
+        } else if !over && THROTTLED.swap(false, Ordering::Relaxed) {
+            info!("Back under the bandwidth cap, resuming low priority traffic");
+        }
+        timer_wheel::sleep(CHECK_INTERVAL);
+    });
+}
+
+// Bytes so far this hour, scaled to a full hour
+fn rate(bytes: u64) -> f64 {
+    let elapsed = (date::now_secs() % 3600).max(MIN_RATE_WINDOW);
+    bytes as f64 / MB as f64 * 3600.0 / elapsed as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    const HOUR: u64 = 1_700_000_000 / 3600 * 3600;
+
+    #[test]
+    fn counts_within_an_hour() {
+        let counter = HourCounter::new();
+        counter.add_at(100, HOUR);
+        counter.add_at(50, HOUR + 3599);
+        assert_eq!(counter.get_at(HOUR + 10), 150);
+    }
+
+    #[test]
+    fn restarts_on_a_new_hour() {
+        let counter = HourCounter::new();
+        counter.add_at(100, HOUR + 3599);
+        assert_eq!(counter.get_at(HOUR + 3600), 0);
+        counter.add_at(7, HOUR + 3600);
+        assert_eq!(counter.get_at(HOUR + 3601), 7);
+    }
+
+    #[test]
+    fn a_late_count_for_the_old_hour_joins_the_new_one() {
+        let counter = HourCounter::new();
+        counter.add_at(7, HOUR + 3600);
+        counter.add_at(100, HOUR + 3599);
+        assert_eq!(counter.get_at(HOUR + 3600), 107);
+    }
+
+    #[test]
+    fn concurrent_counts_across_the_boundary_are_kept() {
+        let counter = Arc::new(HourCounter::new());
+        counter.add_at(1, HOUR + 3599);
+        let threads: Vec<_> = (0..8)
+            .map(|_| {
+                let counter = counter.clone();
+                thread::spawn(move || {
+                    for _ in 0..10_000 {
+                        counter.add_at(1, HOUR + 3600);
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        assert_eq!(counter.get_at(HOUR + 3600), 80_000);
+    }
+}
@@ -0,0 +1,198 @@
+use super::Bot;
+use crate::utils::config;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const WALK_TILES: u32 = 3;
+// How long a rejected step takes to come back as a resync
+const SETTLE: Duration = Duration::from_millis(1500);
+const SPAWN_TIMEOUT: Duration = Duration::from_secs(10);
+const WAIT_TIMEOUT: Duration = Duration::from_secs(20);
+const MAX_ATTEMPTS: u32 = 4;
+// Each rejected walk is retried this much slower
+const BACKOFF: f32 = 0.75;
+const MIN_MULTIPLIER: f32 = 0.3;
+// Acknowledged positions kept per walk, a few per tile
+const MAX_ACKS: usize = 32;
+
+// Measures how fast this bot can walk here without being pulled back, in the background.
+// Skipped when disabled in the config, while automation is paused or when one is running
+pub fn spawn(bot: &Arc<Bot>, reason: &str) {
+    if !config::get_movement_calibration() || bot.automation_paused() {
+        return;
+    }
+    {
+        let mut temp = bot.temporary_data.write().unwrap();
+        if temp.calibrating {
+            return;
+        }
+        temp.calibrating = true;
+    }
+    let bot = bot.clone();
+    let reason = reason.to_string();
+    thread::spawn(move || {
+        match calibrate(&bot) {
+            Ok(multiplier) => {
+                bot.temporary_data.write().unwrap().speed_multiplier = Some(multiplier);
+                bot.log_info(&format!(
+                    "Movement calibrated ({}): {:.2}x, {}ms per step",
+                    reason,
+                    multiplier,
+                    bot.step_delay()
+                ));
+            }
+            Err(err) => bot.log_info(&format!("Movement calibration skipped: {}", err)),
+        }
+        bot.temporary_data.write().unwrap().calibrating = false;
+    });
+}
+
+pub fn is_calibrating(bot: &Bot) -> bool {
+    bot.temporary_data.read().unwrap().calibrating
+}
+
+// Our own position as the server reflected it back, kept while a test walk is measured
+pub fn note_ack(bot: &Bot, x: f32, y: f32) {
+    let mut temp = bot.temporary_data.write().unwrap();
+    if temp.calibrating && temp.calibration_acks.len() < MAX_ACKS {
+        temp.calibration_acks.push((Instant::now(), (x / 32.0) as u32, (y / 32.0) as u32));
+    }
+}
+
+// Paths started mid-calibration would walk over the test walk, they wait for it instead
+pub fn wait(bot: &Bot) {
+    let started = Instant::now();
+    while is_calibrating(bot) && started.elapsed() < WAIT_TIMEOUT {
+        thread::sleep(Duration::from_millis(100));
+    }
+}
+
+// Walks WALK_TILES tiles out and back at the configured pace. The server pulls the bot back
+// when a step came sooner than it allows, so the pace is slowed until a walk goes through
+// untouched. How far apart the server acknowledged the first and last tile of the accepted
+// walk, against how far apart they were sent, gives the multiplier
+fn calibrate(bot: &Bot) -> Result<f32, String> {
+    let started = Instant::now();
+    while !bot.temporary_data.read().unwrap().entered_world {
+        if started.elapsed() > SPAWN_TIMEOUT || !bot.is_inworld() {
+            return Err("not spawned in a world".to_string());
+        }
+        thread::sleep(Duration::from_millis(200));
+    }
+
+    // Held for the whole calibration, paths wait on it rather than walk over the test walk
+    let _busy = bot.try_busy().ok_or("the bot is already moving")?;
+    let mut multiplier = 1.0;
+    for _ in 0..MAX_ATTEMPTS {
+        if bot.automation_paused() {
+            return Err("automation paused".to_string());
+        }
+        let (start, path) = test_walk(bot).ok_or("no straight stretch to walk on")?;
+        let delay = bot.step_delay_with(multiplier);
+        let resyncs = bot.resyncs.load(Ordering::SeqCst);
+
+        bot.temporary_data.write().unwrap().calibration_acks.clear();
+        for &(x, y) in &path {
+            bot.step_to(x, y);
+            thread::sleep(Duration::from_millis(delay));
+        }
+        thread::sleep(SETTLE);
+        let acks = std::mem::take(&mut bot.temporary_data.write().unwrap().calibration_acks);
+        let accepted = bot.resyncs.load(Ordering::SeqCst) == resyncs;
+        if accepted {
+            for &(x, y) in path.iter().rev().skip(1).chain([&start]) {
+                bot.step_to(x, y);
+                thread::sleep(Duration::from_millis(delay));
+            }
+            return measure(&path, &acks, delay, multiplier)
+                .ok_or_else(|| "the server didn't acknowledge the walk".to_string());
+        }
+        multiplier *= BACKOFF;
+        if multiplier < MIN_MULTIPLIER {
+            break;
+        }
+    }
+    Err(format!("still pulled back after {} walks", MAX_ATTEMPTS))
+}
+
+// The pace the server acknowledged the walk at against the pace it was sent at. Latency
+// cancels out since only the gap between the first and last tile's acknowledgement counts
+fn measure(path: &[(u32, u32)], acks: &[(Instant, u32, u32)], delay: u64, multiplier: f32) -> Option<f32> {
+    let (&first, &last) = (path.first()?, path.last()?);
+    let at = |(x, y): (u32, u32)| acks.iter().find(|&&(_, ack_x, ack_y)| ack_x == x && ack_y == y).map(|ack| ack.0);
+    let acknowledged = at(last)?.checked_duration_since(at(first)?)?;
+    let intended = Duration::from_millis(delay * (path.len() as u64 - 1));
+    if intended.is_zero() {
+        return None;
+    }
+    let ratio = intended.as_secs_f32() / acknowledged.as_secs_f32().max(0.001);
+    Some((multiplier * ratio.min(1.0)).max(MIN_MULTIPLIER))
+}
+
+// A straight walk of WALK_TILES tiles to the right or left of the bot, with where it stands
+fn test_walk(bot: &Bot) -> Option<((u32, u32), Vec<(u32, u32)>)> {
+    let (x, y) = {
+        let position = bot.position.lock().unwrap();
+        ((position.x / 32.0) as u32, (position.y / 32.0) as u32)
+    };
+    let astar = bot.astar.lock().unwrap();
+    let targets = [x.checked_add(WALK_TILES), x.checked_sub(WALK_TILES)];
+    targets
+        .into_iter()
+        .flatten()
+        .filter(|&to_x| to_x < astar.width && astar.is_passable(to_x, y))
+        .filter_map(|to_x| astar.find_path(x, y, to_x, y))
+        .map(|path| {
+            path.iter()
+                .filter(|node| node.x != x)
+                .map(|node| (node.x, node.y))
+                .collect::<Vec<(u32, u32)>>()
+        })
+        .find(|path| {
+            path.len() == WALK_TILES as usize && path.iter().all(|&(_, node_y)| node_y == y)
+        })
+        .map(|path| ((x, y), path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PATH: [(u32, u32); 3] = [(11, 5), (12, 5), (13, 5)];
+
+    // Acknowledgements `latency` after each step, `gap` apart
+    fn acks(latency: u64, gap: u64) -> Vec<(Instant, u32, u32)> {
+        let sent = Instant::now();
+        PATH.iter()
+            .enumerate()
+            .map(|(step, &(x, y))| (sent + Duration::from_millis(latency + gap * step as u64), x, y))
+            .collect()
+    }
+
+    #[test]
+    fn keeps_a_pace_the_server_keeps_up_with() {
+        assert_eq!(measure(&PATH, &acks(80, 250), 250, 1.0), Some(1.0));
+        // Acknowledged faster than sent is still only the pace it was sent at
+        assert_eq!(measure(&PATH, &acks(80, 200), 250, 0.75), Some(0.75));
+    }
+
+    #[test]
+    fn slows_to_the_acknowledged_pace() {
+        let multiplier = measure(&PATH, &acks(300, 500), 250, 1.0).unwrap();
+        assert!((multiplier - 0.5).abs() < 0.01, "{}", multiplier);
+        assert_eq!(measure(&PATH, &acks(0, 5000), 250, 1.0), Some(MIN_MULTIPLIER));
+    }
+
+    #[test]
+    fn needs_the_first_and_last_tile_acknowledged() {
+        let mut acks = acks(80, 250);
+        acks.pop();
+        assert_eq!(measure(&PATH, &acks, 250, 1.0), None);
+        assert_eq!(measure(&PATH, &[], 250, 1.0), None);
+        // Acknowledged on another row, e.g. pulled back down
+        let shifted: Vec<_> = self::acks(80, 250).into_iter().map(|(at, x, y)| (at, x, y + 1)).collect();
+        assert_eq!(measure(&PATH, &shifted, 250, 1.0), None);
+    }
+}
@@ -0,0 +1,100 @@
+use crate::types::{epacket_type::EPacketType, etank_packet_type::ETankPacketType};
+
+// enet channels used by the real client, keep every mapping in here so it can be
+// corrected in one place when we learn more about the client's behavior.
+pub const CHANNEL_DEFAULT: u8 = 0;
+pub const CHANNEL_STATE: u8 = 1;
+
+// Text messages all go on the default channel so far
+pub fn for_message(_packet_type: &EPacketType) -> u8 {
+    CHANNEL_DEFAULT
+}
+
+pub fn for_tank_packet(packet_type: &ETankPacketType) -> u8 {
+    match packet_type {
+        ETankPacketType::NetGamePacketState => CHANNEL_STATE,
+        _ => CHANNEL_DEFAULT,
+    }
+}
+
+// Counter name for packets received on `channel`, fixed strings so counting allocates nothing
+pub fn received_stat(channel: u8) -> &'static str {
+    match channel {
+        CHANNEL_DEFAULT => "packets_received_channel_0",
+        CHANNEL_STATE => "packets_received_channel_1",
+        _ => "packets_received_channel_other",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::tank_packet::TankPacket;
+    use rusty_enet as enet;
+    use std::net::UdpSocket;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    fn settings() -> enet::HostSettings {
+        enet::HostSettings {
+            peer_limit: 1,
+            channel_limit: 2,
+            ..Default::default()
+        }
+    }
+
+    // Sends each (channel, data) from a loopback client once connected and returns the
+    // (first byte, channel) of everything the server received, sorted
+    fn receive_over_loopback(packets: &[(u8, Vec<u8>)]) -> Vec<(u8, u8)> {
+        let server_socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let server_address = server_socket.local_addr().unwrap();
+        let mut server = enet::Host::new(server_socket, settings()).unwrap();
+        let mut client = enet::Host::new(UdpSocket::bind("127.0.0.1:0").unwrap(), settings()).unwrap();
+        let peer = client.connect(server_address, 2, 0).unwrap().id();
+
+        let started = Instant::now();
+        let mut connected = false;
+        let mut received = Vec::new();
+        while received.len() < packets.len() && started.elapsed() < Duration::from_secs(5) {
+            while let Some(event) = client.service().ok().flatten().map(|event| event.no_ref()) {
+                if let enet::EventNoRef::Connect { .. } = event {
+                    connected = true;
+                    for (channel, data) in packets {
+                        client.peer_mut(peer).send(*channel, &enet::Packet::reliable(data.as_slice())).unwrap();
+                    }
+                }
+            }
+            while let Some(event) = server.service().ok().flatten().map(|event| event.no_ref()) {
+                if let enet::EventNoRef::Receive { packet, channel_id, .. } = event {
+                    received.push((packet.data()[0], channel_id));
+                }
+            }
+            thread::sleep(Duration::from_millis(1));
+        }
+        assert!(connected, "loopback client never connected");
+        received.sort();
+        received
+    }
+
+    #[test]
+    fn packets_arrive_on_their_channel() {
+        let text = EPacketType::NetMessageGenericText;
+        let mut text_data = (text as u32).to_le_bytes().to_vec();
+        text_data.extend_from_slice(b"action|refresh_item_data\n");
+
+        let state = TankPacket {
+            _type: ETankPacketType::NetGamePacketState,
+            ..Default::default()
+        };
+        let state_data = state.to_message(&[]).unwrap();
+
+        let received = receive_over_loopback(&[
+            (for_message(&text), text_data.clone()),
+            (for_tank_packet(&state._type), state_data.clone()),
+        ]);
+        assert_eq!(
+            received,
+            vec![(text_data[0], CHANNEL_DEFAULT), (state_data[0], CHANNEL_STATE)]
+        );
+    }
+}
@@ -0,0 +1,45 @@
+use super::{calibration, Bot};
+use crate::types::{bot_event::BotEvent, character_state::CharacterState, tank_packet::TankPacket};
+use std::sync::Arc;
+
+pub fn handle(bot: &Arc<Bot>, packet: &TankPacket) {
+    let character = CharacterState::from_packet(packet);
+
+    {
+        let mut state = bot.state.lock().unwrap();
+        if packet.net_id == state.net_id {
+            if state.character.speed != character.speed
+                || state.character.gravity != character.gravity
+            {
+                bot.log_info(&format!(
+                    "Movement changed: speed {}, gravity {}",
+                    character.speed, character.gravity
+                ));
+            }
+            // Equipping or losing a speed item, the old measurement no longer applies
+            let recalibrate = state.character.speed != character.speed && state.logged_in;
+            state.character = character;
+            drop(state);
+            if recalibrate {
+                calibration::spawn(bot, "speed changed");
+            }
+            return;
+        }
+    }
+
+    let mut players = bot.players.lock().unwrap();
+    if let Some(player) = players.iter_mut().find(|player| player.net_id == packet.net_id) {
+        player.character = character;
+        player.invis = character.is_invisible();
+    }
+}
+
+// Moderators are recognised by the title in their spawn string, see Player::from_spawn
+pub fn intruder(bot: &Arc<Bot>, name: String, reason: &str) {
+    bot.log_warn(&format!("Possible mod in world: {} {}", name, reason));
+    bot.add_stat("intruders", 1);
+    bot.emit(BotEvent::Intruder {
+        name,
+        reason: reason.to_string(),
+    });
+}
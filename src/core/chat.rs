@@ -0,0 +1,214 @@
+use super::Bot;
+use crate::types::{echat_state::EChatState, epacket_type::EPacketType};
+use crate::utils::{config, textparse};
+use regex::Regex;
+use std::collections::VecDeque;
+use std::sync::LazyLock;
+use std::time::{Duration, Instant};
+
+// Anything longer is cut off by the server
+pub const MAX_MESSAGE_LENGTH: usize = 120;
+// Used when the server says we're spamming without telling how long to wait
+const DEFAULT_MUTE: Duration = Duration::from_secs(10);
+// Chunks waiting for the cooldown, a script that keeps talking while muted loses the rest
+const MAX_QUEUED: usize = 50;
+
+static MUTE_DURATION: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(\d+)\s*(seconds?|secs?|minutes?|mins?|hours?)").unwrap());
+
+#[derive(Default)]
+pub struct Chat {
+    pub queue: VecDeque<String>,
+    pub last_sent: Option<Instant>,
+    pub muted_until: Option<Instant>,
+}
+
+impl Chat {
+    pub fn state(&self) -> EChatState {
+        match self.muted_until {
+            Some(until) if until > Instant::now() => EChatState::Muted(until),
+            _ if !self.queue.is_empty() => EChatState::Queued(self.queue.len()),
+            _ => EChatState::Ready,
+        }
+    }
+
+    // Returns how many chunks didn't fit under MAX_QUEUED
+    fn enqueue(&mut self, chunks: Vec<String>) -> usize {
+        let room = MAX_QUEUED.saturating_sub(self.queue.len());
+        let dropped = chunks.len().saturating_sub(room);
+        self.queue.extend(chunks.into_iter().take(room));
+        dropped
+    }
+
+    fn can_send(&self, cooldown: Duration) -> bool {
+        if self.muted_until.map_or(false, |until| until > Instant::now()) {
+            return false;
+        }
+        self.last_sent.map_or(true, |last| last.elapsed() >= cooldown)
+    }
+}
+
+// Splits at word boundaries, words longer than the limit are hard split.
+// A leading `/msg name` is repeated on every chunk so each part still reaches the target
+pub fn split(message: &str, limit: usize) -> Vec<String> {
+    let (prefix, body) = match message.strip_prefix("/msg ") {
+        Some(rest) => match rest.split_once(' ') {
+            Some((name, body)) => (format!("/msg {} ", name), body),
+            None => (String::new(), message),
+        },
+        None => (String::new(), message),
+    };
+    let limit = limit.saturating_sub(prefix.chars().count()).max(1);
+
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+    for word in body.split_whitespace() {
+        let mut word = word.to_string();
+        while word.chars().count() > limit {
+            if !current.is_empty() {
+                chunks.push(std::mem::take(&mut current));
+            }
+            let head: String = word.chars().take(limit).collect();
+            word = word.chars().skip(limit).collect();
+            chunks.push(head);
+        }
+        let separator = usize::from(!current.is_empty());
+        let needed = separator + word.chars().count();
+        if current.chars().count() + needed > limit {
+            chunks.push(std::mem::take(&mut current));
+        }
+        if !current.is_empty() {
+            current.push(' ');
+        }
+        current.push_str(&word);
+    }
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+
+    chunks
+        .into_iter()
+        .map(|chunk| format!("{}{}", prefix, chunk))
+        .collect()
+}
+
+// e.g. "Spam detected! Please wait a bit before typing anything else" or
+// "You are muted, please wait 2 minutes before talking again". Player chat can carry the
+// same words, only server lines are looked at
+pub fn parse_mute(message: &str) -> Option<Duration> {
+    if !textparse::is_system_line(message) {
+        return None;
+    }
+    let message = textparse::strip_color(message).to_lowercase();
+    let muted = message.contains("spam detected")
+        || message.contains("before talking again")
+        || message.contains("duct-taped")
+        || message.contains("you are muted");
+    if !muted {
+        return None;
+    }
+
+    let duration = MUTE_DURATION.captures(&message).and_then(|captures| {
+        let amount: u64 = captures[1].parse().ok()?;
+        let unit = match &captures[2] {
+            unit if unit.starts_with("sec") => 1,
+            unit if unit.starts_with("min") => 60,
+            _ => 3600,
+        };
+        Some(Duration::from_secs(amount * unit))
+    });
+    Some(duration.unwrap_or(DEFAULT_MUTE))
+}
+
+// Returns true when every chunk went out right away
+pub fn say(bot: &Bot, message: &str) -> bool {
+    let chunks = split(message, MAX_MESSAGE_LENGTH);
+    let dropped = bot.chat.lock().unwrap().enqueue(chunks);
+    if dropped > 0 {
+        bot.log_warn(&format!("Chat queue is full, dropped {} message part(s)", dropped));
+        bot.add_stat("chat_dropped", dropped as u64);
+    }
+    flush(bot);
+    bot.chat.lock().unwrap().queue.is_empty()
+}
+
+// Sends at most one queued message, called from the poll thread and after every say
+pub fn flush(bot: &Bot) {
+    let cooldown = Duration::from_millis(config::get_chat_cooldown());
+    let message = {
+        let mut chat = bot.chat.lock().unwrap();
+        if chat.queue.is_empty() || !chat.can_send(cooldown) {
+            return;
+        }
+        chat.last_sent = Some(Instant::now());
+        chat.queue.pop_front()
+    };
+    if let Some(message) = message {
+        bot.send_packet(
+            EPacketType::NetMessageGenericText,
+            format!("action|input\n|text|{}\n", message),
+        );
+    }
+}
+
+pub fn handle_console_message(bot: &Bot, message: &str) {
+    if let Some(duration) = parse_mute(message) {
+        bot.log_warn(&format!(
+            "Chat muted, holding messages for {}s",
+            duration.as_secs()
+        ));
+        bot.chat.lock().unwrap().muted_until = Some(Instant::now() + duration);
+        bot.add_stat("chat_mutes", 1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_at_word_boundaries() {
+        let chunks = split("the quick brown fox jumps over", 10);
+        assert_eq!(chunks, vec!["the quick", "brown fox", "jumps over"]);
+        assert!(chunks.iter().all(|chunk| chunk.chars().count() <= 10));
+    }
+
+    #[test]
+    fn hard_splits_long_words() {
+        assert_eq!(split("abcdefghij klm", 4), vec!["abcd", "efgh", "ij", "klm"]);
+    }
+
+    #[test]
+    fn repeats_the_whisper_prefix() {
+        let chunks = split("/msg bob one two three", 14);
+        assert_eq!(chunks, vec!["/msg bob one", "/msg bob two", "/msg bob three"]);
+    }
+
+    #[test]
+    fn parses_server_mutes() {
+        assert_eq!(
+            parse_mute("`6>>`4Spam detected! ``Please wait a bit before typing anything else."),
+            Some(DEFAULT_MUTE)
+        );
+        assert_eq!(
+            parse_mute("`4You are muted``, please wait 2 minutes before talking again"),
+            Some(Duration::from_secs(120))
+        );
+        assert_eq!(parse_mute("`oWelcome back!``"), None);
+    }
+
+    #[test]
+    fn ignores_mutes_typed_by_players() {
+        assert_eq!(parse_mute("CP:_PL:0_OID:_CT:[W]_ `6<`wbob``>`` `$you are muted for 5 hours``"), None);
+        assert_eq!(parse_mute("`6<`wbob``>`` `$Spam detected!``"), None);
+        assert_eq!(parse_mute("`6>> from (`wbob``) in [`$HOME``] > ``You are muted for 1 hour"), None);
+    }
+
+    #[test]
+    fn caps_the_queue() {
+        let mut chat = Chat::default();
+        assert_eq!(chat.enqueue(vec!["hi".to_string(); MAX_QUEUED - 1]), 0);
+        assert_eq!(chat.enqueue(vec!["hi".to_string(); 3]), 2);
+        assert_eq!(chat.queue.len(), MAX_QUEUED);
+    }
+}
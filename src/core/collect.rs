@@ -0,0 +1,101 @@
+use super::{drop_age, value_table, Bot};
+use crate::utils::config;
+use gtworld_r::DroppedItem;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+
+// Tiles from the bot a drop can be picked up without moving
+pub const PICKUP_RANGE: f32 = 5.0;
+// High value drops this close are worth walking to
+const DETOUR_RANGE: f32 = 12.0;
+
+// Held while walking a path or in the middle of a farm step. Low value pickups wait
+// until every guard is dropped so they don't change the timing of what's running
+pub struct BusyGuard<'a>(&'a AtomicUsize);
+
+impl<'a> BusyGuard<'a> {
+    pub fn new(counter: &'a AtomicUsize) -> Self {
+        counter.fetch_add(1, Ordering::SeqCst);
+        BusyGuard(counter)
+    }
+
+    // Only when no other guard is held, checked and taken in one step
+    pub fn try_new(counter: &'a AtomicUsize) -> Option<Self> {
+        counter
+            .compare_exchange(0, 1, Ordering::SeqCst, Ordering::SeqCst)
+            .ok()
+            .map(|_| BusyGuard(counter))
+    }
+}
+
+impl Drop for BusyGuard<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+pub fn distance(bot_x: f32, bot_y: f32, x: f32, y: f32) -> f32 {
+    let dx = (bot_x - x).abs() / 32.0;
+    let dy = (bot_y - y).abs() / 32.0;
+    (dx.powi(2) + dy.powi(2)).sqrt()
+}
+
+// Walks to the most valuable out of reach drop above the threshold and back again, on
+// its own thread so the poll loop keeps going. Only while idle, without a task arbiter
+// there is no safe way to pause a running feature
+pub fn detour(bot: &Arc<Bot>) {
+    if bot.is_busy() || !bot.is_inworld() {
+        return;
+    }
+    if bot.temporary_data.read().unwrap().collect_filter.is_some() {
+        return;
+    }
+    let (bot_x, bot_y) = {
+        let position = bot.position.lock().unwrap();
+        (position.x, position.y)
+    };
+    let threshold = config::get_collect_threshold_wl();
+    let items = bot.dropped_items_with_age();
+    let target = items
+        .into_iter()
+        .filter(|(obj, _)| {
+            let distance = distance(bot_x, bot_y, obj.x, obj.y);
+            distance > PICKUP_RANGE && distance <= DETOUR_RANGE
+        })
+        .filter(|(obj, _)| !bot.temporary_data.read().unwrap().unreachable_drops.contains(&obj.uid))
+        .map(|(obj, age)| (value_table::value_of(obj.id as u32, obj.count as u32), age, obj))
+        .filter(|(value, _, _)| *value >= threshold)
+        .max_by(|a, b| drop_age::weighted_value(a.0, a.1).total_cmp(&drop_age::weighted_value(b.0, b.1)))
+        .map(|(value, _, obj)| (value, obj));
+    let (value, obj) = match target {
+        Some(target) => target,
+        None => return,
+    };
+    let bot = bot.clone();
+    thread::spawn(move || {
+        // Something may have started walking since the check above
+        if let Some(_busy) = bot.try_busy() {
+            walk_detour(&bot, value, obj, (bot_x, bot_y));
+        }
+    });
+}
+
+fn walk_detour(bot: &Bot, value: f32, obj: DroppedItem, (bot_x, bot_y): (f32, f32)) {
+    let (tile_x, tile_y) = ((obj.x / 32.0) as u32, (obj.y / 32.0) as u32);
+    let (home_x, home_y) = ((bot_x / 32.0) as u32, (bot_y / 32.0) as u32);
+    let path = match bot.plan_path(tile_x, tile_y, &AtomicBool::new(false)) {
+        Some(path) => path,
+        None => {
+            bot.log_debug(&format!("No path to item {} at {},{}, ignoring it", obj.id, tile_x, tile_y));
+            bot.temporary_data.write().unwrap().unreachable_drops.insert(obj.uid);
+            return;
+        }
+    };
+    bot.log_debug(&format!("Detouring to item {} ({:.2} WL) at {},{}", obj.id, value, tile_x, tile_y));
+    bot.walk_path(&path);
+    bot.collect();
+    if let Some(path) = bot.plan_path(home_x, home_y, &AtomicBool::new(false)) {
+        bot.walk_path(&path);
+    }
+}
@@ -0,0 +1,227 @@
+use super::{whisper, Bot};
+use crate::types::bot_event::BotEvent;
+use crate::types::console_line::ConsoleLine;
+use crate::types::echat_channel::EChatChannel;
+use crate::utils::{date, textparse};
+use std::collections::VecDeque;
+
+// Lines kept per channel, the oldest are dropped first
+const CAPACITY: usize = 200;
+
+// Console messages split up by channel, with what hasn't been looked at yet
+#[derive(Default)]
+pub struct Console {
+    lines: [VecDeque<ConsoleLine>; 5],
+    unread: [usize; 5],
+    // Moved up on every push, so the panel only copies a channel when it changed
+    versions: [u64; 5],
+}
+
+impl Console {
+    pub fn push(&mut self, line: ConsoleLine) {
+        let index = line.channel.index();
+        if self.lines[index].len() >= CAPACITY {
+            self.lines[index].pop_front();
+        }
+        self.lines[index].push_back(line);
+        self.unread[index] += 1;
+        self.versions[index] += 1;
+    }
+
+    // The channel's lines when they changed since `seen`, which is moved up to the current version
+    pub fn lines_since(&self, channel: EChatChannel, seen: &mut u64) -> Option<Vec<ConsoleLine>> {
+        let version = self.versions[channel.index()];
+        if version == *seen {
+            return None;
+        }
+        *seen = version;
+        Some(self.lines[channel.index()].iter().cloned().collect())
+    }
+
+    pub fn unread(&self, channel: EChatChannel) -> usize {
+        self.unread[channel.index()]
+    }
+
+    pub fn mark_read(&mut self, channel: EChatChannel) {
+        self.unread[channel.index()] = 0;
+    }
+}
+
+pub fn handle(bot: &Bot, message: &str) {
+    let line = classify(message);
+    match line.channel {
+        EChatChannel::SuperBroadcast => bot.emit(BotEvent::SuperBroadcast {
+            sender: line.sender.clone(),
+            world: line.world.clone().unwrap_or_default(),
+            text: textparse::strip_color(&line.text),
+        }),
+        EChatChannel::Guild => bot.emit(BotEvent::GuildChat {
+            sender: line.sender.clone(),
+            text: textparse::strip_color(&line.text),
+        }),
+        _ => {}
+    }
+    bot.console.lock().unwrap().push(line);
+}
+
+// Player chat carries a `CP:..._CT:[<tag>]_` prefix, W for world, G for guild, SB for
+// super-broadcasts and MSG for whispers. Broadcasts and guild lines also come without it
+fn classify(message: &str) -> ConsoleLine {
+    let mut line = ConsoleLine {
+        timestamp: date::now_secs(),
+        channel: EChatChannel::System,
+        sender: String::new(),
+        text: message.to_string(),
+        world: None,
+    };
+    if let Some((sender, text)) = whisper::parse(message) {
+        line.channel = EChatChannel::Whisper;
+        line.sender = sender;
+        line.text = text;
+        return line;
+    }
+    let (tag, body) = split_tag(message);
+    let plain = textparse::strip_color(body);
+    if tag == Some("SB") || plain.trim_start().starts_with("** from (") {
+        if let Some((sender, world, text)) = parse_broadcast(body) {
+            line.channel = EChatChannel::SuperBroadcast;
+            line.sender = sender;
+            line.world = world;
+            line.text = text;
+        }
+        return line;
+    }
+    let guild = tag == Some("G") || plain.trim_start().starts_with("[GUILD]");
+    if guild || tag == Some("W") {
+        let body = body.trim_start();
+        let body = if textparse::strip_color(body).starts_with("[GUILD]") {
+            body.split_once(']').map_or(body, |(_, rest)| rest)
+        } else {
+            body
+        };
+        if let Some((sender, text)) = parse_said(body) {
+            line.channel = if guild { EChatChannel::Guild } else { EChatChannel::World };
+            line.sender = sender;
+            line.text = text;
+        }
+    }
+    line
+}
+
+// `** from (name) in [WORLD] ** : text`. Private and jammed worlds show something that
+// isn't a world name, those have no world to warp to
+fn parse_broadcast(body: &str) -> Option<(String, Option<String>, String)> {
+    let plain = textparse::strip_color(body);
+    let rest = plain.trim_start().strip_prefix("** from (")?;
+    let (sender, rest) = rest.split_once(')')?;
+    let world = rest
+        .trim_start()
+        .strip_prefix("in [")
+        .and_then(|rest| rest.split_once(']'))
+        .map(|(world, _)| world.trim().to_uppercase())
+        .filter(|world| !world.is_empty() && world.chars().all(|c| c.is_ascii_alphanumeric()));
+    // The text keeps its colors, so it's cut from the original after the ` ** :` marker
+    let text = body
+        .split_once("** :")
+        .map_or(String::new(), |(_, text)| text.trim_start().trim_start_matches("``").trim().to_string());
+    Some((sender.trim().to_string(), world, text))
+}
+
+// `<name> text`, the name may be wrapped in color codes
+fn parse_said(body: &str) -> Option<(String, String)> {
+    let start = body.find('<')?;
+    let end = start + body[start..].find('>')?;
+    let sender = textparse::strip_color(&body[start + 1..end]).trim().to_string();
+    if sender.is_empty() {
+        return None;
+    }
+    let text = body[end + 1..].trim_start_matches("``").trim().to_string();
+    Some((sender, text))
+}
+
+fn split_tag(message: &str) -> (Option<&str>, &str) {
+    let rest = match message.find("CT:[") {
+        Some(index) => &message[index + "CT:[".len()..],
+        None => return (None, message),
+    };
+    match rest.split_once("]_") {
+        Some((tag, body)) => (Some(tag), body),
+        None => (None, message),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_each_channel() {
+        let cases = [
+            ("CP:0_PL:4_OID:_CT:[W]_ `6<`wbob``>`` `$hello there``", EChatChannel::World, "bob", "hello there"),
+            ("CP:0_PL:4_OID:_CT:[G]_ `5[GUILD]`` `6<`walice``>`` `5meet at base``", EChatChannel::Guild, "alice", "meet at base"),
+            ("`5[GUILD]`` `6<`walice``>`` `5meet at base``", EChatChannel::Guild, "alice", "meet at base"),
+            (
+                "CP:0_PL:4_OID:_CT:[SB]_ `5** from (`2carol``) in [`4CASINO``] ** :`` `$best odds``",
+                EChatChannel::SuperBroadcast,
+                "carol",
+                "best odds",
+            ),
+            ("`5** from (`2carol``) in [`4CASINO``] ** :`` `$best odds``", EChatChannel::SuperBroadcast, "carol", "best odds"),
+            ("`#>> from (`wdave``) in [`$START``] > `osell me dls``", EChatChannel::Whisper, "dave", "sell me dls"),
+            ("CP:0_PL:4_OID:_CT:[MSG]_ `#>> from (`wdave``) in [`$START``] > `osell me dls``", EChatChannel::Whisper, "dave", "sell me dls"),
+            ("`oWhere would you like to go? (`w12,345`` online)``", EChatChannel::System, "", "Where would you like to go? (12,345 online)"),
+        ];
+        for (message, channel, sender, text) in cases {
+            let line = classify(message);
+            assert_eq!(line.channel, channel, "{}", message);
+            assert_eq!(line.sender, sender, "{}", message);
+            assert_eq!(textparse::strip_color(&line.text), text, "{}", message);
+        }
+    }
+
+    #[test]
+    fn player_lines_that_dont_parse_stay_system() {
+        for message in ["CP:0_PL:4_OID:_CT:[W]_ no sender here", "CP:0_PL:4_OID:_CT:[SB]_ `5not a broadcast``"] {
+            assert_eq!(classify(message).channel, EChatChannel::System, "{}", message);
+        }
+    }
+
+    #[test]
+    fn extracts_the_advertised_world() {
+        let cases = [
+            ("`5** from (`2carol``) in [`4CASINO``] ** :`` `$come play``", Some("CASINO")),
+            ("`5** from (`2carol``) in [`4casino42``] ** :`` `$come play``", Some("CASINO42")),
+            // Private and jammed worlds have nothing to warp to
+            ("`5** from (`2carol``) in [`4<HIDDEN>``] ** :`` `$come play``", None),
+            ("`5** from (`2carol``) in [`4JAMMED!``] ** :`` `$come play``", None),
+            ("`5** from (`2carol``) in [] ** :`` `$come play``", None),
+            ("`5** from (`2carol``) ** :`` `$no world``", None),
+        ];
+        for (message, world) in cases {
+            let line = classify(message);
+            assert_eq!(line.channel, EChatChannel::SuperBroadcast, "{}", message);
+            assert_eq!(line.world.as_deref(), world, "{}", message);
+        }
+    }
+
+    #[test]
+    fn keeps_the_newest_lines_and_versions_each_channel() {
+        let mut console = Console::default();
+        let mut seen = u64::MAX;
+        for index in 0..CAPACITY + 5 {
+            console.push(classify(&format!("CP:0_PL:4_OID:_CT:[W]_ `6<`wbob``>`` line {}", index)));
+        }
+        let lines = console.lines_since(EChatChannel::World, &mut seen).unwrap();
+        assert_eq!(lines.len(), CAPACITY);
+        assert_eq!(lines[0].text, "line 5");
+        assert!(console.lines_since(EChatChannel::World, &mut seen).is_none());
+        assert_eq!(console.unread(EChatChannel::World), CAPACITY + 5);
+
+        // Other channels don't move with it
+        let mut system_seen = 0;
+        assert!(console.lines_since(EChatChannel::System, &mut system_seen).is_none());
+        console.push(classify("`oWorld locked by someone``"));
+        assert!(console.lines_since(EChatChannel::World, &mut seen).is_none());
+        assert_eq!(console.lines_since(EChatChannel::System, &mut system_seen).unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,218 @@
+use crate::core::Bot;
+use crate::manager::bot_manager::BotManager;
+use crate::utils::date;
+use std::backtrace::Backtrace;
+use std::cell::RefCell;
+use std::fmt::Write as _;
+use std::fs;
+use std::panic;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock, TryLockError, TryLockResult};
+use std::thread;
+use std::time::{Duration, Instant};
+
+pub const CRASH_DIR: &str = "crashes";
+// Name of the newest report, removed once the startup dialog was answered
+const PENDING_FILE: &str = "pending";
+const LOG_LINES: usize = 200;
+const PACKETS: usize = 50;
+// The panicking thread may hold any lock, so nothing in the hook blocks longer than this
+const LOCK_TIMEOUT: Duration = Duration::from_millis(500);
+const RETRY_DELAY: Duration = Duration::from_millis(10);
+
+thread_local! {
+    static CURRENT_BOT: RefCell<Option<String>> = RefCell::new(None);
+    // Inside crash::catch, a panic here is the supervisor's to handle
+    static CATCHING: RefCell<bool> = RefCell::new(false);
+    // Message and backtrace of the last caught panic, taken by catch
+    static CAUGHT: RefCell<Option<String>> = RefCell::new(None);
+}
+
+// Marks the calling thread as working for `username`, a panic on it is reported against that bot
+pub fn set_current_bot(username: &str) {
+    CURRENT_BOT.with(|current| *current.borrow_mut() = Some(username.to_string()));
+}
+
+fn current_bot() -> Option<String> {
+    CURRENT_BOT.try_with(|current| current.borrow().clone()).ok().flatten()
+}
+
+// Runs `work`, a panic in it comes back as Err with its message and backtrace instead of
+// writing a crash report. The backtrace is only available inside the hook
+pub fn catch<T>(work: impl FnOnce() -> T) -> Result<T, String> {
+    CATCHING.with(|catching| *catching.borrow_mut() = true);
+    let result = panic::catch_unwind(panic::AssertUnwindSafe(work));
+    CATCHING.with(|catching| *catching.borrow_mut() = false);
+    result.map_err(|_| {
+        CAUGHT
+            .with(|caught| caught.borrow_mut().take())
+            .unwrap_or_else(|| "unknown panic".to_string())
+    })
+}
+
+fn is_catching() -> bool {
+    CATCHING.try_with(|catching| *catching.borrow()).unwrap_or(false)
+}
+
+// Chains onto the default hook, which still prints the panic to the console. Only panics on
+// threads working for a bot are reported, anything else is left to the default hook
+pub fn install(manager: Arc<RwLock<BotManager>>) {
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        default_hook(info);
+        if is_catching() {
+            let message = format!("{}\n\nBacktrace:\n{}", info, Backtrace::force_capture());
+            let _ = CAUGHT.try_with(|caught| *caught.borrow_mut() = Some(message));
+            return;
+        }
+        let bot_name = match current_bot() {
+            Some(bot_name) => bot_name,
+            None => return,
+        };
+        let bots = snapshot(&manager).unwrap_or_default();
+        let report = build_report(&info.to_string(), &bot_name, &bots);
+        match write_report(&report) {
+            Ok(path) => eprintln!("Crash report written to {}", path.display()),
+            Err(err) => eprintln!("Failed to write crash report: {}", err),
+        }
+        for (name, bot) in &bots {
+            if *name != bot_name {
+                shutdown(bot);
+            }
+        }
+    }));
+}
+
+// Name of the report left by the last crash that wasn't dismissed yet
+pub fn pending_report() -> Option<String> {
+    fs::read_to_string(Path::new(CRASH_DIR).join(PENDING_FILE))
+        .ok()
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+}
+
+pub fn dismiss_pending() {
+    let _ = fs::remove_file(Path::new(CRASH_DIR).join(PENDING_FILE));
+}
+
+fn build_report(panic: &str, bot_name: &str, bots: &[(String, Arc<Bot>)]) -> String {
+    let now = date::now_secs();
+    let mut report = String::new();
+    let _ = writeln!(
+        report,
+        "Mori crash at {} {} UTC",
+        date::format_day(date::day_index(now, 0, 0)),
+        date::format_time(now, 0)
+    );
+    let _ = writeln!(report, "Thread: {}", thread::current().name().unwrap_or("unnamed"));
+    let _ = writeln!(report, "Bot: {}", bot_name);
+    let _ = writeln!(report, "\n{}", panic);
+    let _ = writeln!(report, "\nBacktrace:\n{}", Backtrace::force_capture());
+
+    match bots.iter().find(|(name, _)| name == bot_name) {
+        Some((_, bot)) => report.push_str(&bot_context(bot)),
+        None => report.push_str("\nBot context unavailable, the bot list was locked\n"),
+    }
+    report
+}
+
+fn bot_context(bot: &Bot) -> String {
+    let mut context = String::new();
+    let _ = writeln!(context, "\nLast {} log lines:", LOG_LINES);
+    match retry(|| recover(bot.logs.try_lock()).map(|logs| tail(logs.iter(), LOG_LINES))) {
+        Some(logs) => {
+            for entry in logs {
+                let _ = writeln!(
+                    context,
+                    "[{}] {}: {}",
+                    date::format_time(entry.timestamp, 0),
+                    entry.level,
+                    entry.message
+                );
+            }
+        }
+        None => context.push_str("(logs locked)\n"),
+    }
+
+    let _ = writeln!(context, "\nLast {} packets:", PACKETS);
+    let packets = retry(|| {
+        recover(bot.captured_packets.try_lock())
+            .map(|packets| tail(packets.iter(), PACKETS))
+    });
+    match packets {
+        Some(packets) => {
+            for packet in packets {
+                let _ = writeln!(
+                    context,
+                    "{} {:?} ch{} {}",
+                    packet.timestamp,
+                    packet.direction,
+                    packet.channel,
+                    hex::encode(&packet.data)
+                );
+            }
+        }
+        None => context.push_str("(packets locked)\n"),
+    }
+    if !bot.capturing.load(std::sync::atomic::Ordering::Relaxed) {
+        context.push_str("(capture was off)\n");
+    }
+    context
+}
+
+fn tail<'a, T: Clone + 'a>(items: impl ExactSizeIterator<Item = &'a T>, count: usize) -> Vec<T> {
+    let skip = items.len().saturating_sub(count);
+    items.skip(skip).cloned().collect()
+}
+
+fn write_report(report: &str) -> std::io::Result<PathBuf> {
+    let dir = Path::new(CRASH_DIR);
+    fs::create_dir_all(dir)?;
+    let name = format!("{}.txt", date::now_secs());
+    let path = dir.join(&name);
+    fs::write(&path, report)?;
+    fs::write(dir.join(PENDING_FILE), &name)?;
+    Ok(path)
+}
+
+fn snapshot(manager: &RwLock<BotManager>) -> Option<Vec<(String, Arc<Bot>)>> {
+    retry(|| recover(manager.try_read()).and_then(|manager| manager.try_snapshot()))
+}
+
+// Same as a normal stop, but gives up on any lock it can't get in time. The disconnect is
+// left to the bot's event loop, which holds `host` while it waits on the socket
+fn shutdown(bot: &Bot) {
+    if let Some(mut state) = retry(|| recover(bot.state.try_lock())) {
+        if !state.is_running {
+            return;
+        }
+        state.is_running = false;
+    }
+    let peer_id = retry(|| recover(bot.peer_id.try_lock()).map(|peer_id| peer_id.clone())).flatten();
+    if peer_id.is_some() {
+        bot.disconnect_requested.store(true, std::sync::atomic::Ordering::SeqCst);
+        bot.wake_event_loop();
+    }
+}
+
+// A poisoned lock is still read, the data is only reported
+fn recover<G>(result: TryLockResult<G>) -> Option<G> {
+    match result {
+        Ok(guard) => Some(guard),
+        Err(TryLockError::Poisoned(poisoned)) => Some(poisoned.into_inner()),
+        Err(TryLockError::WouldBlock) => None,
+    }
+}
+
+fn retry<T>(mut attempt: impl FnMut() -> Option<T>) -> Option<T> {
+    let deadline = Instant::now() + LOCK_TIMEOUT;
+    loop {
+        if let Some(value) = attempt() {
+            return Some(value);
+        }
+        if Instant::now() >= deadline {
+            return None;
+        }
+        thread::sleep(RETRY_DELAY);
+    }
+}
@@ -0,0 +1,100 @@
+use super::Bot;
+use crate::types::bot_event::BotEvent;
+use crate::utils::config;
+use std::collections::VecDeque;
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
+
+// Walking is client side, past a tile the server's idea of the position wins
+const THRESHOLD: f32 = 32.0;
+// config resync_warn_count resyncs within this means the movement delays are too tight
+const WARN_WINDOW: Duration = Duration::from_secs(60);
+
+// Compares a position the server sent for this bot against the local one. Past the threshold
+// the server's is adopted, any path being walked is abandoned and Resynced is emitted.
+// Returns whether it resynced
+pub fn check(bot: &Bot, server_x: f32, server_y: f32, source: &str) -> bool {
+    let (dx, dy) = {
+        let mut position = bot.position.lock().unwrap();
+        let (dx, dy) = match offset((position.x, position.y), (server_x, server_y)) {
+            Some(offset) => offset,
+            None => return false,
+        };
+        position.x = server_x;
+        position.y = server_y;
+        (dx, dy)
+    };
+    bot.resyncs.fetch_add(1, Ordering::SeqCst);
+    bot.log_warn(&format!(
+        "Position out of sync by {:.0},{:.0} px ({}), using the server's",
+        dx, dy, source
+    ));
+    bot.add_stat("resyncs", 1);
+    bot.add_stat("resync_distance_px", (dx * dx + dy * dy).sqrt() as u64);
+    bot.emit(BotEvent::Resynced { dx, dy });
+
+    let warn_count = config::get_resync_warn_count() as usize;
+    let recent = note_resync(&mut bot.temporary_data.write().unwrap().resyncs, Instant::now());
+    if warn_count > 0 && recent == warn_count {
+        bot.log_warn(&format!(
+            "{} position resyncs within a minute, the step or findpath delays are likely too aggressive",
+            recent
+        ));
+    }
+    true
+}
+
+// Doors, respawns and checkpoints move the bot on purpose. The position is taken over and
+// a path being walked is abandoned like on a resync, but it isn't counted as one
+pub fn teleported(bot: &Bot, server_x: f32, server_y: f32) {
+    {
+        let mut position = bot.position.lock().unwrap();
+        position.x = server_x;
+        position.y = server_y;
+    }
+    bot.resyncs.fetch_add(1, Ordering::SeqCst);
+    bot.log_debug(&format!("Teleported to {:.0},{:.0}", server_x, server_y));
+}
+
+// How far the server's position is from ours, None within a tile
+fn offset(local: (f32, f32), server: (f32, f32)) -> Option<(f32, f32)> {
+    let (dx, dy) = (server.0 - local.0, server.1 - local.1);
+    ((dx * dx + dy * dy).sqrt() > THRESHOLD).then_some((dx, dy))
+}
+
+// Resyncs within WARN_WINDOW including this one
+fn note_resync(resyncs: &mut VecDeque<Instant>, now: Instant) -> usize {
+    resyncs.retain(|at| now.saturating_duration_since(*at) < WARN_WINDOW);
+    resyncs.push_back(now);
+    resyncs.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ignores_drift_within_a_tile() {
+        assert_eq!(offset((100.0, 100.0), (100.0, 100.0)), None);
+        assert_eq!(offset((100.0, 100.0), (132.0, 100.0)), None);
+        assert_eq!(offset((100.0, 100.0), (120.0, 120.0)), None);
+    }
+
+    #[test]
+    fn reports_the_offset_past_a_tile() {
+        assert_eq!(offset((100.0, 100.0), (164.0, 100.0)), Some((64.0, 0.0)));
+        assert_eq!(offset((100.0, 100.0), (100.0, 40.0)), Some((0.0, -60.0)));
+    }
+
+    #[test]
+    fn counts_resyncs_within_the_window() {
+        let start = Instant::now();
+        let mut resyncs = VecDeque::new();
+        assert_eq!(note_resync(&mut resyncs, start), 1);
+        assert_eq!(note_resync(&mut resyncs, start + Duration::from_secs(20)), 2);
+        assert_eq!(note_resync(&mut resyncs, start + Duration::from_secs(59)), 3);
+        // The first one has aged out
+        assert_eq!(note_resync(&mut resyncs, start + Duration::from_secs(61)), 3);
+        assert_eq!(note_resync(&mut resyncs, start + Duration::from_secs(200)), 1);
+    }
+}
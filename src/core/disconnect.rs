@@ -0,0 +1,164 @@
+use super::Bot;
+use crate::types::{
+    bot_event::BotEvent, disconnect_info::DisconnectInfo, disconnect_phrases::DisconnectPhrases,
+    edisconnect_reason::EDisconnectReason,
+};
+use crate::utils::{date, textparse};
+use paris::warn;
+use std::fs;
+use std::sync::LazyLock;
+
+const PHRASES_PATH: &str = "disconnect_phrases.json";
+
+static PHRASES: LazyLock<DisconnectPhrases> = LazyLock::new(load_phrases);
+
+// Read once, a missing file is written out with the defaults so there is something to edit
+fn load_phrases() -> DisconnectPhrases {
+    if let Ok(contents) = fs::read_to_string(PHRASES_PATH) {
+        match serde_json::from_str(&contents) {
+            Ok(phrases) => return phrases,
+            Err(err) => warn!("Failed to parse {}, using the default phrases: {}", PHRASES_PATH, err),
+        }
+        return DisconnectPhrases::default();
+    }
+    let phrases = DisconnectPhrases::default();
+    if let Ok(contents) = serde_json::to_string_pretty(&phrases) {
+        if let Err(err) = fs::write(PHRASES_PATH, contents) {
+            warn!("Failed to write {}: {}", PHRASES_PATH, err);
+        }
+    }
+    phrases
+}
+
+pub fn classify(message: &str) -> Option<EDisconnectReason> {
+    PHRASES.classify(message)
+}
+
+// Console and game messages are checked so the reason is known once the disconnect arrives
+pub fn note_message(bot: &Bot, message: &str) {
+    if let Some(pending) = pending_reason(&PHRASES, message) {
+        bot.temporary_data.write().unwrap().pending_disconnect = Some(pending);
+    }
+}
+
+// Players can type any of the phrases, only lines the server wrote count
+fn pending_reason(phrases: &DisconnectPhrases, message: &str) -> Option<(EDisconnectReason, String)> {
+    if !textparse::is_system_line(message) {
+        return None;
+    }
+    classify_with_detail(phrases, message)
+}
+
+fn classify_with_detail(phrases: &DisconnectPhrases, message: &str) -> Option<(EDisconnectReason, String)> {
+    let reason = phrases.classify(message)?;
+    Some((reason, textparse::strip_color(message).trim().to_string()))
+}
+
+// NetMessageError text. Only an error matching a phrase becomes the pending reason, plenty
+// of them are answers to a single request and the connection carries on
+pub fn note_error(bot: &Bot, message: &str) -> Option<EDisconnectReason> {
+    let (reason, detail) = classify_with_detail(&PHRASES, message)?;
+    bot.temporary_data.write().unwrap().pending_disconnect = Some((reason, detail));
+    Some(reason)
+}
+
+pub fn note_packet(bot: &Bot) {
+    let mut temp = bot.temporary_data.write().unwrap();
+    if temp.pending_disconnect.is_none() {
+        temp.pending_disconnect = Some((
+            EDisconnectReason::Kicked,
+            "Server sent a disconnect packet".to_string(),
+        ));
+    }
+}
+
+pub fn clear_pending(bot: &Bot) {
+    bot.temporary_data.write().unwrap().pending_disconnect = None;
+}
+
+pub fn on_disconnect(bot: &Bot, code: u32) -> EDisconnectReason {
+    let pending = bot.temporary_data.write().unwrap().pending_disconnect.take();
+    let (reason, detail) =
+        pending.unwrap_or((EDisconnectReason::Unknown, format!("code {}", code)));
+    record(bot, reason, detail)
+}
+
+pub fn record(bot: &Bot, reason: EDisconnectReason, detail: String) -> EDisconnectReason {
+    bot.log_warn(&format!("Disconnected: {} ({})", reason.name(), detail));
+    bot.emit(BotEvent::Disconnect {
+        reason: reason.name().to_string(),
+        detail: detail.clone(),
+        reconnect: reason.reconnect_delay().is_some() && bot.state.lock().unwrap().is_running,
+    });
+    bot.state.lock().unwrap().last_disconnect = Some(DisconnectInfo {
+        reason,
+        detail,
+        timestamp: date::now_secs(),
+    });
+    reason
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reason(message: &str) -> Option<EDisconnectReason> {
+        pending_reason(&DisconnectPhrases::default(), message).map(|(reason, _)| reason)
+    }
+
+    #[test]
+    fn classifies_server_lines() {
+        let cases = [
+            ("`4OOPS:`` Someone else logged into this account! (You've been logged on from another location)", EDisconnectReason::LoggedInElsewhere),
+            ("`4Sorry, this account is currently banned.``", EDisconnectReason::Banned),
+            ("`4Growtopia is not quite ready for users.`` Please try again in a few minutes.", EDisconnectReason::ServerRestart),
+            ("`4Update required``: please update to `$V4.71``", EDisconnectReason::VersionMismatch),
+            ("`4You were kicked`` by a moderator", EDisconnectReason::Kicked),
+        ];
+        for (message, expected) in cases {
+            assert_eq!(reason(message), Some(expected), "{}", message);
+        }
+    }
+
+    #[test]
+    fn keeps_the_stripped_line_as_detail() {
+        let pending = pending_reason(&DisconnectPhrases::default(), "`4Sorry, this account is currently banned.`` ");
+        assert_eq!(pending.map(|(_, detail)| detail).as_deref(), Some("Sorry, this account is currently banned."));
+    }
+
+    #[test]
+    fn ignores_player_chat() {
+        assert_eq!(reason("CP:0_PL:4_OID:_CT:[W]_ `6<`wgrower``>`` `$you were kicked lol``"), None);
+        assert_eq!(reason("`6<`wgrower``>`` this account is currently banned"), None);
+        assert_eq!(reason("`6>> from (`wgrower``) in [START] > `$logged on from another location``"), None);
+    }
+
+    #[test]
+    fn ignores_kicks_of_other_players() {
+        assert_eq!(reason("`4grower`` has been kicked from the world by `2owner``"), None);
+    }
+
+    #[test]
+    fn classifies_error_packets() {
+        let phrases = DisconnectPhrases::default();
+        let error = |fixture: &str| classify_with_detail(&phrases, fixture).map(|(reason, _)| reason);
+        assert_eq!(
+            error(include_str!("../../tests/fixtures/packets/error_banned.txt")),
+            Some(EDisconnectReason::Banned)
+        );
+        assert_eq!(
+            error(include_str!("../../tests/fixtures/packets/error_logged_elsewhere.txt")),
+            Some(EDisconnectReason::LoggedInElsewhere)
+        );
+        // Not every error ends the session, an unknown one leaves no pending reason
+        assert_eq!(error(include_str!("../../tests/fixtures/packets/error_generic.txt")), None);
+    }
+
+    #[test]
+    fn phrases_load_from_json() {
+        let phrases: DisconnectPhrases =
+            serde_json::from_str(r#"{"phrases": [{"phrase": "maintenance", "reason": "ServerRestart"}]}"#).unwrap();
+        assert_eq!(phrases.classify("`4Down for MAINTENANCE``"), Some(EDisconnectReason::ServerRestart));
+        assert_eq!(phrases.classify("currently banned"), None);
+    }
+}
@@ -0,0 +1,365 @@
+use super::{packet_handler, supervisor, Bot};
+use crate::types::{epacket_type::EPacketType, etank_packet_type::ETankPacketType};
+use crate::utils::repaint;
+use std::collections::VecDeque;
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+// Received packets waiting for the handlers, past this the backpressure policy kicks in
+pub const QUEUE_CAPACITY: usize = 512;
+// Longest clear() waits for the packet being handled
+const HANDLER_WAIT: Duration = Duration::from_secs(5);
+
+// Game packets that only animate the world, a newer one makes an older one useless
+const DROPPABLE: [ETankPacketType; 3] = [
+    ETankPacketType::NetGamePacketState,
+    ETankPacketType::NetGamePacketSendParticleEffect,
+    ETankPacketType::NetGamePacketItemEffect,
+];
+
+pub struct Incoming {
+    pub packet_type: EPacketType,
+    // Without the 4 byte message type
+    pub data: Vec<u8>,
+    // Passed the bot's packet filter, the handler logs it
+    pub logged: bool,
+}
+
+impl Incoming {
+    fn droppable(&self) -> bool {
+        self.packet_type == EPacketType::NetMessageGamePacket
+            && self
+                .data
+                .first()
+                .map_or(false, |&tank_type| DROPPABLE.contains(&ETankPacketType::from(tank_type)))
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Pushed {
+    Queued,
+    // A droppable packet made room, the newcomer or an older one
+    Dropped,
+    // Full of packets that can't be dropped, the handlers are too far behind for this
+    // session to stay in sync. The newcomer isn't queued
+    Overflow,
+}
+
+#[derive(Default)]
+struct Queue {
+    packets: VecDeque<Incoming>,
+    closed: bool,
+    // A popped packet is still being handled
+    handling: bool,
+}
+
+// Hands received packets from the event loop to the dispatcher thread, so a slow handler
+// never holds back enet servicing. Connect and disconnect events stay on the event loop
+// and never go through here
+#[derive(Default)]
+pub struct Dispatch {
+    queue: Mutex<Queue>,
+    ready: Condvar,
+    idle: Condvar,
+    thread: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl Dispatch {
+    // Full queue: the oldest droppable packet makes room, and when there is none a
+    // droppable newcomer is the one dropped. Anything else overflows
+    pub fn push(&self, incoming: Incoming) -> Pushed {
+        let mut queue = self.queue.lock().unwrap();
+        let mut pushed = Pushed::Queued;
+        if queue.packets.len() >= QUEUE_CAPACITY {
+            match queue.packets.iter().position(Incoming::droppable) {
+                Some(index) => {
+                    queue.packets.remove(index);
+                    pushed = Pushed::Dropped;
+                }
+                None if incoming.droppable() => return Pushed::Dropped,
+                None => return Pushed::Overflow,
+            }
+        }
+        queue.packets.push_back(incoming);
+        self.ready.notify_one();
+        pushed
+    }
+
+    // Packets of a connection that is gone would only confuse the next session. Waits for
+    // the packet being handled, so its handler can't write over the session reset after this
+    pub fn clear(&self) {
+        let mut queue = self.queue.lock().unwrap();
+        queue.packets.clear();
+        drop(self.idle.wait_timeout_while(queue, HANDLER_WAIT, |queue| queue.handling).unwrap());
+    }
+
+    // Drops what is left, the dispatcher thread ends after the packet it is handling
+    pub fn close(&self) {
+        let mut queue = self.queue.lock().unwrap();
+        queue.closed = true;
+        queue.packets.clear();
+        self.ready.notify_all();
+    }
+
+    // Also marks the previous packet as handled
+    fn pop(&self) -> Option<Incoming> {
+        let mut queue = self.queue.lock().unwrap();
+        queue.handling = false;
+        self.idle.notify_all();
+        loop {
+            if queue.closed {
+                return None;
+            }
+            if let Some(incoming) = queue.packets.pop_front() {
+                queue.handling = true;
+                return Some(incoming);
+            }
+            queue = self.ready.wait(queue).unwrap();
+        }
+    }
+}
+
+// Runs the packet handlers in arrival order until the queue is closed. The dispatcher of
+// the previous session is joined first, so two never run at once
+pub fn spawn(bot: Arc<Bot>) {
+    let previous = bot.dispatch.thread.lock().unwrap().take();
+    if let Some(previous) = previous {
+        let _ = previous.join();
+    }
+    bot.dispatch.queue.lock().unwrap().closed = false;
+    let handle = supervisor::spawn(&bot, "dispatch", |bot| {
+        while let Some(incoming) = bot.dispatch.pop() {
+            packet_handler::handle(Arc::clone(bot), incoming.packet_type, &incoming.data, incoming.logged);
+            // Other bots only show what Watch and emit already redraw for
+            if bot.shown.load(Ordering::Relaxed) {
+                repaint::request();
+            }
+        }
+    });
+    *bot.dispatch.thread.lock().unwrap() = handle;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusty_enet::{self as enet, PacketReceived, Socket, SocketOptions, MTU_MAX};
+    use std::io;
+    use std::net::{SocketAddr, UdpSocket};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::thread;
+    use std::time::Instant;
+
+    fn state() -> Incoming {
+        Incoming {
+            packet_type: EPacketType::NetMessageGamePacket,
+            data: vec![ETankPacketType::NetGamePacketState as u8],
+            logged: false,
+        }
+    }
+
+    fn text(message: &str) -> Incoming {
+        Incoming {
+            packet_type: EPacketType::NetMessageGenericText,
+            data: message.as_bytes().to_vec(),
+            logged: false,
+        }
+    }
+
+    fn fill(dispatch: &Dispatch, droppable_at: Option<usize>) {
+        for index in 0..QUEUE_CAPACITY {
+            let incoming = if droppable_at == Some(index) { state() } else { text("filler") };
+            assert_eq!(dispatch.push(incoming), Pushed::Queued);
+        }
+    }
+
+    #[test]
+    fn full_queue_drops_the_oldest_droppable() {
+        let dispatch = Dispatch::default();
+        fill(&dispatch, Some(3));
+        assert_eq!(dispatch.push(text("critical")), Pushed::Dropped);
+        let queue = dispatch.queue.lock().unwrap();
+        assert_eq!(queue.packets.len(), QUEUE_CAPACITY);
+        assert!(!queue.packets.iter().any(Incoming::droppable));
+        assert_eq!(queue.packets.back().unwrap().data, b"critical");
+    }
+
+    #[test]
+    fn droppable_newcomer_is_dropped_when_nothing_else_can_be() {
+        let dispatch = Dispatch::default();
+        fill(&dispatch, None);
+        assert_eq!(dispatch.push(state()), Pushed::Dropped);
+        assert_eq!(dispatch.queue.lock().unwrap().packets.len(), QUEUE_CAPACITY);
+    }
+
+    #[test]
+    fn critical_packets_overflow_instead_of_growing_the_queue() {
+        let dispatch = Dispatch::default();
+        fill(&dispatch, None);
+        assert_eq!(dispatch.push(text("critical")), Pushed::Overflow);
+        assert_eq!(dispatch.queue.lock().unwrap().packets.len(), QUEUE_CAPACITY);
+    }
+
+    #[test]
+    fn clear_waits_for_the_running_handler() {
+        let dispatch = Arc::new(Dispatch::default());
+        dispatch.push(text("slow"));
+        dispatch.push(text("stale"));
+        let handler = {
+            let dispatch = dispatch.clone();
+            thread::spawn(move || {
+                let mut handled = 0;
+                while dispatch.pop().is_some() {
+                    thread::sleep(Duration::from_millis(200));
+                    handled += 1;
+                }
+                handled
+            })
+        };
+        while !dispatch.queue.lock().unwrap().handling {
+            thread::yield_now();
+        }
+        let started = Instant::now();
+        dispatch.clear();
+        assert!(started.elapsed() >= Duration::from_millis(100));
+        assert!(!dispatch.queue.lock().unwrap().handling);
+        dispatch.close();
+        assert_eq!(handler.join().unwrap(), 1);
+    }
+
+    // Loses every LOSS_EVERY-th datagram it sends and counts them all
+    struct LossyUdp {
+        inner: UdpSocket,
+        sent: Arc<AtomicUsize>,
+    }
+
+    const LOSS_EVERY: usize = 10;
+    const PACKETS: usize = 200;
+    const SEND_INTERVAL: Duration = Duration::from_millis(5);
+    const HANDLER_TIME: Duration = Duration::from_millis(20);
+    const SIMULATION_LIMIT: Duration = Duration::from_secs(60);
+
+    impl Socket for LossyUdp {
+        type Address = SocketAddr;
+        type Error = io::Error;
+
+        fn init(&mut self, socket_options: SocketOptions) -> Result<(), io::Error> {
+            Socket::init(&mut self.inner, socket_options)
+        }
+
+        fn send(&mut self, address: SocketAddr, buffer: &[u8]) -> Result<usize, io::Error> {
+            if (self.sent.fetch_add(1, Ordering::Relaxed) + 1) % LOSS_EVERY == 0 {
+                return Ok(buffer.len());
+            }
+            Socket::send(&mut self.inner, address, buffer)
+        }
+
+        fn receive(
+            &mut self,
+            buffer: &mut [u8; MTU_MAX],
+        ) -> Result<Option<(SocketAddr, PacketReceived)>, io::Error> {
+            Socket::receive(&mut self.inner, buffer)
+        }
+    }
+
+    fn settings() -> enet::HostSettings {
+        enet::HostSettings {
+            peer_limit: 1,
+            channel_limit: 2,
+            ..Default::default()
+        }
+    }
+
+    // Datagrams a server needs to get PACKETS reliable packets to a client whose handler
+    // takes HANDLER_TIME, the ones over one per packet being handshake and retransmits
+    fn datagrams_to_deliver(dispatched: bool) -> usize {
+        let sent = Arc::new(AtomicUsize::new(0));
+        let server_socket = LossyUdp {
+            inner: UdpSocket::bind("127.0.0.1:0").unwrap(),
+            sent: sent.clone(),
+        };
+        let server_address = server_socket.inner.local_addr().unwrap();
+        let mut server = enet::Host::new(server_socket, settings()).unwrap();
+        let server = thread::spawn(move || {
+            let started = Instant::now();
+            let mut client = None;
+            let mut queued = 0;
+            let mut next_send = Instant::now();
+            while started.elapsed() < SIMULATION_LIMIT {
+                if let Some(client) = client.filter(|_| queued < PACKETS && Instant::now() >= next_send) {
+                    let packet = enet::Packet::reliable(&[0u8; 64][..]);
+                    server.peer_mut(client).send(0, &packet).unwrap();
+                    queued += 1;
+                    next_send += SEND_INTERVAL;
+                }
+                match server.service().ok().flatten().map(|event| event.no_ref()) {
+                    Some(enet::EventNoRef::Connect { peer, .. }) => {
+                        client = Some(peer);
+                        next_send = Instant::now();
+                    }
+                    Some(enet::EventNoRef::Disconnect { .. }) => break,
+                    Some(_) => {}
+                    None => thread::sleep(Duration::from_millis(1)),
+                }
+            }
+            sent.load(Ordering::Relaxed)
+        });
+
+        let mut client = enet::Host::new(UdpSocket::bind("127.0.0.1:0").unwrap(), settings()).unwrap();
+        client.connect(server_address, 2, 0).unwrap();
+        let dispatch = Arc::new(Dispatch::default());
+        let handler = {
+            let dispatch = dispatch.clone();
+            thread::spawn(move || {
+                while dispatch.pop().is_some() {
+                    thread::sleep(HANDLER_TIME);
+                }
+            })
+        };
+        let started = Instant::now();
+        let mut received = 0;
+        while started.elapsed() < SIMULATION_LIMIT {
+            match client.service().ok().flatten().map(|event| event.no_ref()) {
+                Some(enet::EventNoRef::Receive { peer, packet, .. }) => {
+                    received += 1;
+                    if dispatched {
+                        let incoming = Incoming {
+                            packet_type: EPacketType::NetMessageGenericText,
+                            data: packet.data().to_vec(),
+                            logged: false,
+                        };
+                        assert_eq!(dispatch.push(incoming), Pushed::Queued);
+                    } else {
+                        thread::sleep(HANDLER_TIME);
+                    }
+                    if received == PACKETS {
+                        client.peer_mut(peer).disconnect(0);
+                    }
+                }
+                Some(enet::EventNoRef::Disconnect { .. }) => break,
+                Some(_) => {}
+                None => thread::sleep(Duration::from_millis(1)),
+            }
+        }
+        dispatch.close();
+        handler.join().unwrap();
+        assert_eq!(received, PACKETS);
+        server.join().unwrap()
+    }
+
+    // Lossy link simulation of the event loop before and after the dispatcher thread.
+    // Needs loopback networking and takes a while:
+    // cargo test lossy_link -- --ignored --nocapture
+    #[test]
+    #[ignore]
+    fn lossy_link_retransmissions() {
+        let inline = datagrams_to_deliver(false);
+        let dispatched = datagrams_to_deliver(true);
+        println!(
+            "Datagrams sent for {} packets with 1 in {} lost: {} handled inline, {} dispatched",
+            PACKETS, LOSS_EVERY, inline, dispatched
+        );
+        assert!(dispatched < inline);
+    }
+}
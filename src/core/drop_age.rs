@@ -0,0 +1,158 @@
+use super::Bot;
+use crate::utils::config;
+use gtworld_r::DroppedItem;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+// Called from the object spawn packet, so the age is the server's and not when we first looked
+pub fn record_spawn(bot: &Bot, uid: u32) {
+    bot.temporary_data
+        .write()
+        .unwrap()
+        .drop_seen
+        .insert(uid, Instant::now());
+}
+
+// Drops that came with the world data have no spawn packet, their age counts from the world load
+pub fn record_loaded(bot: &Bot) {
+    let uids: Vec<u32> = bot.world.read().unwrap().dropped.items.iter().map(|obj| obj.uid).collect();
+    let now = Instant::now();
+    let mut temp = bot.temporary_data.write().unwrap();
+    temp.drop_seen.clear();
+    temp.drop_seen.extend(uids.into_iter().map(|uid| (uid, now)));
+}
+
+pub fn forget(bot: &Bot, uid: u32) {
+    bot.temporary_data.write().unwrap().drop_seen.remove(&uid);
+}
+
+pub fn despawn_after() -> Duration {
+    Duration::from_secs(config::get_drop_despawn_secs())
+}
+
+// 0 for a fresh drop up to 1 for one about to despawn
+pub fn urgency(age: Duration) -> f32 {
+    urgency_within(age, despawn_after())
+}
+
+fn urgency_within(age: Duration, despawn: Duration) -> f32 {
+    if despawn.is_zero() {
+        return 0.0;
+    }
+    (age.as_secs_f32() / despawn.as_secs_f32()).min(1.0)
+}
+
+// Collection score, older drops get up to twice their value so they're picked up first
+pub fn weighted_value(value: f32, age: Duration) -> f32 {
+    value * (1.0 + urgency(age))
+}
+
+pub fn with_age(bot: &Bot) -> Vec<(DroppedItem, Duration)> {
+    let items = bot.world.read().unwrap().dropped.items.clone();
+    let temp = bot.temporary_data.read().unwrap();
+    items
+        .into_iter()
+        .map(|obj| {
+            let age = temp.drop_seen.get(&obj.uid).map_or(Duration::ZERO, |seen| seen.elapsed());
+            (obj, age)
+        })
+        .collect()
+}
+
+// Drops past the despawn window are gone on the server even when no removal packet
+// reached us, chasing them only wastes a walk
+pub fn prune(bot: &Bot) {
+    let despawn = despawn_after();
+    if despawn.is_zero() {
+        return;
+    }
+    let expired = expired(&bot.temporary_data.read().unwrap().drop_seen, Instant::now(), despawn);
+    if expired.is_empty() {
+        return;
+    }
+    {
+        let mut world = bot.world.write().unwrap();
+        let before = world.dropped.items.len();
+        world.dropped.items.retain(|obj| !expired.contains(&obj.uid));
+        let removed = before - world.dropped.items.len();
+        world.dropped.items_count -= removed as _;
+    }
+    let mut temp = bot.temporary_data.write().unwrap();
+    for uid in &expired {
+        temp.drop_seen.remove(uid);
+    }
+    bot.log_debug(&format!("Pruned {} drops past the despawn window", expired.len()));
+}
+
+fn expired(drop_seen: &HashMap<u32, Instant>, now: Instant, despawn: Duration) -> Vec<u32> {
+    drop_seen
+        .iter()
+        .filter(|(_, seen)| now.saturating_duration_since(**seen) > despawn)
+        .map(|(uid, _)| *uid)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DESPAWN: Duration = Duration::from_secs(600);
+
+    // Spawn and removal packets as they'd arrive, `secs` after the first one
+    enum Packet {
+        Spawn(u32),
+        Remove(u32),
+    }
+
+    fn replay(packets: &[(u64, Packet)], start: Instant) -> HashMap<u32, Instant> {
+        let mut drop_seen = HashMap::new();
+        for (secs, packet) in packets {
+            match packet {
+                Packet::Spawn(uid) => drop_seen.insert(*uid, start + Duration::from_secs(*secs)),
+                Packet::Remove(uid) => drop_seen.remove(uid),
+            };
+        }
+        drop_seen
+    }
+
+    fn sorted(mut uids: Vec<u32>) -> Vec<u32> {
+        uids.sort();
+        uids
+    }
+
+    #[test]
+    fn prunes_only_drops_past_the_window() {
+        let start = Instant::now();
+        let drop_seen = replay(&[(0, Packet::Spawn(1)), (100, Packet::Spawn(2)), (500, Packet::Spawn(3))], start);
+        assert!(expired(&drop_seen, start + Duration::from_secs(600), DESPAWN).is_empty());
+        assert_eq!(expired(&drop_seen, start + Duration::from_secs(601), DESPAWN), vec![1]);
+        assert_eq!(sorted(expired(&drop_seen, start + Duration::from_secs(701), DESPAWN)), vec![1, 2]);
+        assert_eq!(sorted(expired(&drop_seen, start + Duration::from_secs(1101), DESPAWN)), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn removed_drops_are_never_pruned() {
+        let start = Instant::now();
+        let drop_seen = replay(
+            &[(0, Packet::Spawn(1)), (0, Packet::Spawn(2)), (30, Packet::Remove(1)), (40, Packet::Spawn(4))],
+            start,
+        );
+        assert_eq!(expired(&drop_seen, start + Duration::from_secs(620), DESPAWN), vec![2]);
+    }
+
+    #[test]
+    fn a_respawned_uid_counts_from_its_new_spawn() {
+        let start = Instant::now();
+        let drop_seen = replay(&[(0, Packet::Spawn(1)), (300, Packet::Remove(1)), (400, Packet::Spawn(1))], start);
+        assert!(expired(&drop_seen, start + Duration::from_secs(700), DESPAWN).is_empty());
+        assert_eq!(expired(&drop_seen, start + Duration::from_secs(1001), DESPAWN), vec![1]);
+    }
+
+    #[test]
+    fn urgency_grows_with_age_and_is_off_without_a_window() {
+        assert_eq!(urgency_within(Duration::ZERO, DESPAWN), 0.0);
+        assert_eq!(urgency_within(Duration::from_secs(300), DESPAWN), 0.5);
+        assert_eq!(urgency_within(Duration::from_secs(900), DESPAWN), 1.0);
+        assert_eq!(urgency_within(Duration::from_secs(900), Duration::ZERO), 0.0);
+    }
+}
@@ -0,0 +1,84 @@
+use super::Bot;
+use crate::utils::textparse;
+use std::time::Duration;
+
+// A mod line this soon after consuming something is taken to be that item's effect
+const CONSUME_WINDOW: Duration = Duration::from_secs(5);
+
+// Consumables report their effect in the console, e.g.
+// "`oYour luck has improved!`` (`$Lucky!`` mod added, `$30 mins`` left)", and its end as
+// "`oYour luck has worn off.`` (`$Lucky!`` mod removed)"
+pub fn note_message(bot: &Bot, message: &str) {
+    if !textparse::is_system_line(message) {
+        return;
+    }
+    let plain = textparse::strip_color(message);
+    if let Some((name, duration)) = parse_added(&plain) {
+        let consumed = bot.temporary_data.write().unwrap().last_consumed.take();
+        match consumed.filter(|(_, at)| at.elapsed() <= CONSUME_WINDOW) {
+            Some((item_id, _)) => bot.add_effect(item_id, item_id, &name, duration),
+            // Nothing to re-consume for an effect that didn't come from an item
+            None => bot.log_debug(&format!("{} mod added without a consumed item", name)),
+        }
+    } else if let Some(name) = parse_removed(&plain) {
+        bot.remove_effect(&name);
+    }
+}
+
+fn parse_added(plain: &str) -> Option<(String, Duration)> {
+    let (before, after) = plain.split_once(" mod added, ")?;
+    let name = before.rsplit_once('(')?.1.trim();
+    let duration = parse_duration(after.split_once(" left)")?.0)?;
+    (!name.is_empty()).then(|| (name.to_string(), duration))
+}
+
+fn parse_removed(plain: &str) -> Option<String> {
+    let before = plain.split_once(" mod removed)")?.0;
+    let name = before.rsplit_once('(')?.1.trim();
+    (!name.is_empty()).then(|| name.to_string())
+}
+
+// "1 hours, 30 mins", "45 secs", "2 days, 3 hours"
+fn parse_duration(text: &str) -> Option<Duration> {
+    let mut secs = 0;
+    for part in text.split(',') {
+        let (amount, unit) = part.trim().split_once(' ')?;
+        let amount: u64 = amount.parse().ok()?;
+        let unit_secs = match unit.trim() {
+            unit if unit.starts_with("sec") => 1,
+            unit if unit.starts_with("min") => 60,
+            unit if unit.starts_with("hour") => 3600,
+            unit if unit.starts_with("day") => 86400,
+            _ => return None,
+        };
+        secs += amount * unit_secs;
+    }
+    Some(Duration::from_secs(secs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_added_mods() {
+        let line = textparse::strip_color("`oYour luck has improved!`` (`$Lucky!`` mod added, `$30 mins`` left)");
+        assert_eq!(parse_added(&line), Some(("Lucky!".to_string(), Duration::from_secs(1800))));
+        let line = textparse::strip_color("`oYou feel fireproof!`` (`$Fireproof`` mod added, `$1 hours, 5 mins`` left)");
+        assert_eq!(parse_added(&line), Some(("Fireproof".to_string(), Duration::from_secs(3900))));
+    }
+
+    #[test]
+    fn parses_removed_mods() {
+        let line = textparse::strip_color("`oYour luck has worn off.`` (`$Lucky!`` mod removed)");
+        assert_eq!(parse_removed(&line).as_deref(), Some("Lucky!"));
+        assert_eq!(parse_added(&line), None);
+    }
+
+    #[test]
+    fn rejects_unknown_durations() {
+        assert_eq!(parse_duration("a while"), None);
+        assert_eq!(parse_duration("3 fortnights"), None);
+        assert_eq!(parse_duration("45 secs"), Some(Duration::from_secs(45)));
+    }
+}
@@ -0,0 +1,190 @@
+use crate::core::features::transfer_items;
+use crate::core::{reactions, value_table, Bot};
+use crate::types::config::BotConfig;
+use crate::utils::{config, webhook};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+// A deposit that failed isn't retried straight away, the bot would otherwise keep
+// bouncing between worlds while still carrying everything
+const RETRY_AFTER: Duration = Duration::from_secs(600);
+const IDLE_POLL: Duration = Duration::from_millis(500);
+// How long a running feature gets to finish the action it's in the middle of
+const IDLE_TIMEOUT: Duration = Duration::from_secs(30);
+
+// Starts a deposit once the carried deposit items are worth more than the bot's
+// threshold. Waits for the running feature to finish its cycle and for the bot to be idle
+pub fn tick(bot: &Arc<Bot>) {
+    {
+        let temp = bot.temporary_data.read().unwrap();
+        if temp.depositing
+            || temp.reacting
+            || temp.active_feature.is_some()
+            || temp.last_deposit.map_or(false, |at| at.elapsed() < RETRY_AFTER)
+        {
+            return;
+        }
+    }
+    if !bot.is_inworld() || bot.is_busy() || bot.automation_paused() {
+        return;
+    }
+    let username = bot.info.lock().unwrap().payload[0].clone();
+    let bot_config = match config::get_bot_config(&username) {
+        Some(bot_config)
+            if bot_config.deposit_threshold > 0 && !bot_config.deposit_mule.is_empty() =>
+        {
+            bot_config
+        }
+        _ => return,
+    };
+    let value = carried_value(bot, &bot_config);
+    if value < bot_config.deposit_threshold as f32 {
+        return;
+    }
+
+    bot.log_info(&format!(
+        "Carrying {:.1} WL of deposit items, over the {} WL threshold",
+        value, bot_config.deposit_threshold
+    ));
+    bot.temporary_data.write().unwrap().depositing = true;
+    let bot = bot.clone();
+    thread::spawn(move || {
+        if let Err(err) = run(&bot, &bot_config) {
+            bot.log_error(&format!("Deposit failed: {}", err));
+        }
+        let mut temp = bot.temporary_data.write().unwrap();
+        temp.depositing = false;
+        temp.last_deposit = Some(Instant::now());
+    });
+}
+
+// World locks of the configured deposit items in the inventory, from the item value table
+pub fn carried_value(bot: &Bot, bot_config: &BotConfig) -> f32 {
+    bot_config
+        .deposit_items
+        .iter()
+        .map(|&item_id| value_table::value_of(item_id, amount_of(bot, item_id)))
+        .sum()
+}
+
+// Takes the deposit items to the storage world, walks the deposit route when one is set
+// and hands them to the mule there, then goes back to where the bot was. There is no
+// storage box handling yet, so a mule is the only way to hand items over, without one
+// nothing leaves the inventory. Returns the (item id, amount) pairs the mule received.
+// Automation is paused for the trip so a running feature doesn't carry on in the storage
+// world, unless it already was
+pub fn run(bot: &Arc<Bot>, bot_config: &BotConfig) -> Result<Vec<(u32, u32)>, String> {
+    if bot_config.storage_world.is_empty() {
+        return Err("no storage world set".to_string());
+    }
+    if bot_config.deposit_mule.is_empty() {
+        return Err("no mule set, nothing to hand the items to".to_string());
+    }
+    let mule = bot
+        .message_bus
+        .bot(&bot_config.deposit_mule)
+        .ok_or_else(|| format!("mule {} is not running", bot_config.deposit_mule))?;
+    let paused_here = !bot.automation_paused();
+    if paused_here {
+        bot.pause_automation();
+    }
+    let result = deposit(bot, &mule, bot_config);
+    if paused_here {
+        bot.resume_automation();
+    }
+    result
+}
+
+fn deposit(bot: &Arc<Bot>, mule: &Arc<Bot>, bot_config: &BotConfig) -> Result<Vec<(u32, u32)>, String> {
+    let started = Instant::now();
+    while bot.is_busy() {
+        if started.elapsed() >= IDLE_TIMEOUT {
+            return Err("the bot stayed busy, not leaving the world".to_string());
+        }
+        thread::sleep(IDLE_POLL);
+    }
+    let _busy = bot.busy();
+    let storage = bot_config.storage_world.to_uppercase();
+    let home = bot.world.read().unwrap().name.clone();
+    let home_position = {
+        let position = bot.position.lock().unwrap();
+        ((position.x / 32.0) as u32, (position.y / 32.0) as u32)
+    };
+
+    if !reactions::warp_to(bot, &storage) {
+        return Err(format!("failed to enter {}", storage));
+    }
+    let walked = if bot_config.deposit_route.is_empty() {
+        bot.walk_off_door();
+        Ok(())
+    } else {
+        bot.run_route(&bot_config.deposit_route)
+    };
+
+    let mut deposited = Vec::new();
+    let mut transferred = walked;
+    if transferred.is_ok() {
+        let items: Vec<(u32, u32)> = bot_config
+            .deposit_items
+            .iter()
+            .map(|&item_id| (item_id, amount_of(bot, item_id)))
+            .filter(|&(_, amount)| amount > 0)
+            .collect();
+        // The mule's inventory is the confirmation, see transfer_items
+        let report = transfer_items::run(bot, mule, items, storage.clone());
+        deposited = report
+            .items
+            .iter()
+            .filter(|&&(_, _, _, received)| received > 0)
+            .map(|&(item_id, _, _, received)| (item_id, received))
+            .collect();
+        receipt(bot, &storage, &deposited);
+        if let Some(error) = report.error {
+            transferred = Err(error);
+        }
+    }
+
+    if !home.is_empty() && home != "EXIT" && home != storage {
+        if !reactions::warp_to(bot, &home) {
+            return Err(format!("failed to return to {}", home));
+        }
+        if let Err(err) = bot.find_path(home_position.0, home_position.1) {
+            bot.log_warn(&format!("Couldn't walk back to {},{}: {}", home_position.0, home_position.1, err));
+        }
+    }
+    transferred.map(|_| deposited)
+}
+
+fn receipt(bot: &Bot, world: &str, deposited: &[(u32, u32)]) {
+    let url = config::get_webhook_url();
+    if url.is_empty() || deposited.is_empty() {
+        return;
+    }
+    let username = bot.info.lock().unwrap().payload[0].clone();
+    let lines: Vec<String> = {
+        let item_database = bot.item_database.read().unwrap();
+        deposited
+            .iter()
+            .map(|&(item_id, amount)| {
+                let name = item_database
+                    .get_item(&item_id)
+                    .map_or(format!("#{}", item_id), |item| item.name.clone());
+                format!("- {} x{}", name, amount)
+            })
+            .collect()
+    };
+    let content = format!("{} deposited in {}:\n{}", username, world, lines.join("\n"));
+    if let Err(err) = webhook::send(&url, &content) {
+        bot.log_error(&format!("Failed to send webhook: {}", err));
+    }
+}
+
+fn amount_of(bot: &Bot, item_id: u32) -> u32 {
+    bot.inventory
+        .lock()
+        .unwrap()
+        .items
+        .get(&(item_id as u16))
+        .map_or(0, |item| item.amount as u32)
+}
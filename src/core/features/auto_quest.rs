@@ -0,0 +1,62 @@
+use crate::core::Bot;
+use crate::types::epacket_type::EPacketType;
+use std::sync::Arc;
+
+// Hands in the daily quest when every required item is in the inventory
+pub fn run(bot: &Arc<Bot>) {
+    let quest = match bot.refresh_quests() {
+        Some(quest) => quest,
+        None => {
+            bot.log_warn("No daily quest found");
+            return;
+        }
+    };
+
+    let missing: Vec<String> = {
+        let inventory = bot.inventory.lock().unwrap();
+        quest
+            .requirements
+            .iter()
+            .filter_map(|requirement| {
+                let owned = inventory
+                    .items
+                    .get(&(requirement.item_id as u16))
+                    .map_or(0, |item| item.amount as u32);
+                if owned >= requirement.amount {
+                    None
+                } else {
+                    Some(format!(
+                        "{} {}",
+                        requirement.amount - owned,
+                        requirement.name
+                    ))
+                }
+            })
+            .collect()
+    };
+
+    if !missing.is_empty() {
+        bot.log_info(&format!("Daily quest still needs: {}", missing.join(", ")));
+        return;
+    }
+
+    let button = quest.buttons.iter().find(|(_, label)| {
+        let label = label.to_lowercase();
+        ["deliver", "turn in", "complete", "give"]
+            .iter()
+            .any(|keyword| label.contains(keyword))
+    });
+    match button {
+        Some((button, _)) => {
+            bot.log_info(&format!("Handing in daily quest for {}", quest.reward));
+            bot.send_packet(
+                EPacketType::NetMessageGenericText,
+                format!(
+                    "action|dialog_return\ndialog_name|{}\nbuttonClicked|{}\n",
+                    quest.dialog_name, button
+                ),
+            );
+        }
+        None => bot.log_warn("Daily quest dialog has no hand-in button"),
+    }
+}
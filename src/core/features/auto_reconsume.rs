@@ -0,0 +1,34 @@
+use crate::core::Bot;
+use crate::utils::config;
+use std::sync::Arc;
+use std::time::Duration;
+
+// Re-uses configured consumables shortly before their effect runs out
+pub fn tick(bot: &Arc<Bot>) {
+    let threshold = Duration::from_secs(config::get_reconsume_before_secs());
+    for consumable in config::get_consumables() {
+        let effect = match bot
+            .effects()
+            .into_iter()
+            .find(|effect| effect.source_item == consumable.item_id)
+        {
+            Some(effect) => effect,
+            None => continue,
+        };
+        if effect.remaining() > threshold {
+            continue;
+        }
+        let in_inventory = {
+            let inventory = bot.inventory.lock().unwrap();
+            inventory
+                .items
+                .get(&(consumable.item_id as u16))
+                .map_or(false, |item| item.amount > 0)
+        };
+        if !in_inventory {
+            continue;
+        }
+        bot.log_info(&format!("Re-consuming item {}", consumable.item_id));
+        bot.consume(consumable.item_id);
+    }
+}
@@ -0,0 +1,374 @@
+use crate::core::activation::ActivateError;
+use crate::core::{quest, Bot};
+use crate::types::bot_event::BotEvent;
+use crate::types::epacket_type::EPacketType;
+use crate::types::surgery_rules::SurgeryRules;
+use crate::types::surgery_stats::SurgeryStats;
+use crate::utils::textparse;
+use paris::warn;
+use std::fs;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+const RULES_PATH: &str = "surgery_rules.json";
+const DIALOG_TIMEOUT: Duration = Duration::from_secs(10);
+const STEP_DELAY: u64 = 600;
+// A surgery that hasn't ended after this many tools is stuck on a state the rules misread
+const MAX_STEPS: u32 = 60;
+
+#[derive(Error, Debug)]
+pub enum AutoSurgeryError {
+    #[error("Not in a world")]
+    NotInWorld,
+    #[error("No patient bed in this world")]
+    NoBed,
+    #[error("Can't start the surgery: {0}")]
+    Activate(#[from] ActivateError),
+    #[error("Surgery dialog never opened")]
+    NoDialog,
+    #[error("Out of {0}")]
+    OutOfTool(String),
+    #[error("Tool {0} has no item id in surgery_rules.json")]
+    UnknownTool(String),
+    #[error("No rule matches \"{0}\"")]
+    NoRule(String),
+    #[error("Surgery didn't end after {0} tools")]
+    TooManySteps(u32),
+}
+
+// What a surgery dialog asks for
+#[derive(Debug, PartialEq)]
+enum Step {
+    Tool { name: String, button: String, complication: bool },
+    Done(bool),
+}
+
+// Operates on the nearest patient bed until stopped or a needed tool runs out. Returns
+// the stats of the run, the reason it stopped is logged
+pub fn run(bot: &Arc<Bot>) -> SurgeryStats {
+    {
+        let mut temp = bot.temporary_data.write().unwrap();
+        if !temp.surgery.start() {
+            return temp.surgery.stats();
+        }
+    }
+    bot.emit(BotEvent::Feature { name: "auto_surgery".to_string(), running: true });
+    let rules = load_rules();
+
+    while is_running(bot) {
+        bot.wait_while_paused(|| is_running(bot));
+        if !is_running(bot) {
+            break;
+        }
+        match operate(bot, &rules) {
+            Ok(survived) => {
+                record(bot, |stats| {
+                    stats.completed += 1;
+                    stats.succeeded += survived as u32;
+                });
+                bot.add_stat("surgeries", 1);
+                if survived {
+                    bot.add_stat("surgeries_succeeded", 1);
+                }
+            }
+            Err(err) => {
+                bot.log_warn(&format!("Auto surgery stopped: {}", err));
+                break;
+            }
+        }
+    }
+
+    let stats = bot.temporary_data.write().unwrap().surgery.finish();
+    bot.log_info(&format!(
+        "Performed {} surgeries, {:.0}% successful, {} complications",
+        stats.completed,
+        stats.success_rate(),
+        stats.complications
+    ));
+    bot.emit(BotEvent::Feature { name: "auto_surgery".to_string(), running: false });
+    stats
+}
+
+pub fn stop(bot: &Bot) {
+    bot.temporary_data.write().unwrap().surgery.running = false;
+}
+
+pub fn is_running(bot: &Bot) -> bool {
+    bot.temporary_data.read().unwrap().surgery.running
+}
+
+// Console messages carry the outcome, they're only kept mid-surgery
+pub fn note_message(bot: &Bot, message: &str) {
+    let mut temp = bot.temporary_data.write().unwrap();
+    if temp.surgery.running {
+        if let Some(line) = surgery_line(message) {
+            temp.surgery_messages.push(line);
+        }
+    }
+}
+
+// Player chat can say the patient died as well, only the server's own lines count
+fn surgery_line(message: &str) -> Option<String> {
+    textparse::is_system_line(message).then(|| textparse::strip_color(message))
+}
+
+// The rules file is written with the defaults the first time so it can be edited
+pub fn load_rules() -> SurgeryRules {
+    if let Ok(contents) = fs::read_to_string(RULES_PATH) {
+        match serde_json::from_str(&contents) {
+            Ok(rules) => return rules,
+            Err(err) => warn!("Failed to parse {}, using the default rules: {}", RULES_PATH, err),
+        }
+        return SurgeryRules::default();
+    }
+    let rules = SurgeryRules::default();
+    if let Ok(contents) = serde_json::to_string_pretty(&rules) {
+        if let Err(err) = fs::write(RULES_PATH, contents) {
+            warn!("Failed to write {}: {}", RULES_PATH, err);
+        }
+    }
+    rules
+}
+
+// One patient from start to finish, Ok with whether they survived
+fn operate(bot: &Bot, rules: &SurgeryRules) -> Result<bool, AutoSurgeryError> {
+    if !bot.is_inworld() {
+        return Err(AutoSurgeryError::NotInWorld);
+    }
+    let (x, y) = nearest_bed(bot, rules).ok_or(AutoSurgeryError::NoBed)?;
+    {
+        let mut temp = bot.temporary_data.write().unwrap();
+        temp.dialog.clear();
+        temp.surgery_messages.clear();
+    }
+    bot.tile_activate(x, y)?;
+
+    for _ in 0..MAX_STEPS {
+        let dialog = match wait_for_change(bot, rules) {
+            Change::Outcome(survived) => return Ok(survived),
+            Change::Dialog(dialog) => dialog,
+            Change::Timeout => return Err(AutoSurgeryError::NoDialog),
+        };
+        let owned = |tool_id: u32| {
+            bot.inventory
+                .lock()
+                .unwrap()
+                .items
+                .get(&(tool_id as u16))
+                .map_or(0, |item| item.amount as u32)
+        };
+        let (name, button, complication) = match next_step(rules, &dialog, owned)? {
+            Step::Done(survived) => return Ok(survived),
+            Step::Tool { name, button, complication } => (name, button, complication),
+        };
+        if complication {
+            bot.log_info(&format!("Surgery complication, using {}", name));
+            record(bot, |stats| stats.complications += 1);
+        }
+
+        let delay = bot.humanizer.lock().unwrap().action_delay(STEP_DELAY);
+        thread::sleep(delay);
+        bot.temporary_data.write().unwrap().dialog.clear();
+        bot.send_packet(
+            EPacketType::NetMessageGenericText,
+            format!(
+                "action|dialog_return\ndialog_name|{}\nbuttonClicked|{}\n",
+                rules.dialog_name, button
+            ),
+        );
+    }
+    Err(AutoSurgeryError::TooManySteps(MAX_STEPS))
+}
+
+// Reads what the patient needs off a surgery dialog. `owned` is how many of an item the bot
+// carries, a needed tool it has none of ends the run
+fn next_step(rules: &SurgeryRules, dialog: &str, owned: impl Fn(u32) -> u32) -> Result<Step, AutoSurgeryError> {
+    let status = status_text(dialog);
+    if let Some(survived) = rules.outcome(&status) {
+        return Ok(Step::Done(survived));
+    }
+    let rule = rules
+        .next_rule(&status)
+        .ok_or_else(|| AutoSurgeryError::NoRule(status.lines().next().unwrap_or_default().to_string()))?;
+    let tool_id = rules
+        .tool_id(&rule.tool)
+        .ok_or_else(|| AutoSurgeryError::UnknownTool(rule.tool.clone()))?;
+    if owned(tool_id) == 0 {
+        return Err(AutoSurgeryError::OutOfTool(rule.tool.clone()));
+    }
+    Ok(Step::Tool {
+        name: rule.tool.clone(),
+        button: tool_button(dialog, tool_id),
+        complication: rule.complication,
+    })
+}
+
+enum Change {
+    Dialog(String),
+    Outcome(bool),
+    Timeout,
+}
+
+// Waits for the next surgery dialog, or a message saying how the surgery ended
+fn wait_for_change(bot: &Bot, rules: &SurgeryRules) -> Change {
+    let started = Instant::now();
+    while started.elapsed() < DIALOG_TIMEOUT {
+        {
+            let temp = bot.temporary_data.read().unwrap();
+            let outcome = temp.surgery_messages.iter().find_map(|message| rules.outcome(message));
+            if let Some(survived) = outcome {
+                return Change::Outcome(survived);
+            }
+            if quest::dialog_name(&temp.dialog).as_deref() == Some(rules.dialog_name.as_str()) {
+                return Change::Dialog(temp.dialog.clone());
+            }
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+    Change::Timeout
+}
+
+// Only what the dialog says about the patient, tool names on the buttons would match rules
+fn status_text(dialog: &str) -> String {
+    dialog
+        .lines()
+        .filter(|line| {
+            ["add_label", "add_textbox", "add_smalltext"]
+                .iter()
+                .any(|prefix| line.starts_with(prefix))
+        })
+        .filter_map(|line| line.split('|').find(|part| part.contains(' ') || part.contains(':')))
+        .map(textparse::strip_color)
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+// The dialog's own button for the tool when there is one, its usual key otherwise
+fn tool_button(dialog: &str, tool_id: u32) -> String {
+    let id = tool_id.to_string();
+    dialog
+        .lines()
+        .filter(|line| line.starts_with("add_button"))
+        .filter_map(|line| line.split('|').nth(1))
+        .find(|key| key.ends_with(id.as_str()))
+        .map_or_else(|| format!("tool{}", tool_id), |key| key.to_string())
+}
+
+fn nearest_bed(bot: &Bot, rules: &SurgeryRules) -> Option<(u32, u32)> {
+    let (bot_x, bot_y) = {
+        let position = bot.position.lock().unwrap();
+        ((position.x / 32.0) as i64, (position.y / 32.0) as i64)
+    };
+    let world = bot.world.read().unwrap();
+    let item_database = bot.item_database.read().unwrap();
+    world
+        .tiles
+        .iter()
+        .filter(|tile| tile.foreground_item_id != 0)
+        .filter(|tile| {
+            item_database
+                .get_item(&(tile.foreground_item_id as u32))
+                .map_or(false, |item| {
+                    rules.beds.iter().any(|bed| item.name.contains(bed.as_str()))
+                })
+        })
+        .min_by_key(|tile| (tile.x as i64 - bot_x).pow(2) + (tile.y as i64 - bot_y).pow(2))
+        .map(|tile| (tile.x, tile.y))
+}
+
+fn record(bot: &Bot, f: impl FnOnce(&mut SurgeryStats)) {
+    bot.temporary_data.write().unwrap().surgery.record(f);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A captured surgery, "# dialog" and "# console" lines start each packet
+    fn events(fixture: &str) -> Vec<(&str, String)> {
+        let mut events: Vec<(&str, String)> = Vec::new();
+        for line in fixture.lines() {
+            match line.strip_prefix("# ") {
+                Some(kind) => events.push((kind, String::new())),
+                None => {
+                    let (_, text) = events.last_mut().expect("fixture starts with a header");
+                    text.push_str(line);
+                    text.push('\n');
+                }
+            }
+        }
+        events
+    }
+
+    // Plays a fixture through the state machine the way operate does, returning the tools
+    // used and how it ended
+    fn replay(fixture: &str, owned: impl Fn(u32) -> u32) -> (Vec<String>, Result<bool, String>) {
+        let rules = SurgeryRules::default();
+        let mut tools = Vec::new();
+        let mut messages = Vec::new();
+        for (kind, text) in events(fixture) {
+            if kind == "console" {
+                messages.extend(surgery_line(text.trim_end()));
+                if let Some(survived) = messages.iter().find_map(|message| rules.outcome(message)) {
+                    return (tools, Ok(survived));
+                }
+                continue;
+            }
+            assert_eq!(quest::dialog_name(&text).as_deref(), Some(rules.dialog_name.as_str()));
+            match next_step(&rules, &text, &owned) {
+                Ok(Step::Done(survived)) => return (tools, Ok(survived)),
+                Ok(Step::Tool { name, button, complication }) => {
+                    assert_eq!(button, format!("tool{}", rules.tool_id(&name).unwrap()));
+                    tools.push(if complication { format!("{}!", name) } else { name });
+                }
+                Err(err) => return (tools, Err(err.to_string())),
+            }
+        }
+        (tools, Err("no outcome".to_string()))
+    }
+
+    fn stocked(_: u32) -> u32 {
+        20
+    }
+
+    #[test]
+    fn routine_surgery() {
+        let (tools, outcome) = replay(include_str!("../../../tests/fixtures/surgery/routine.txt"), stocked);
+        assert_eq!(tools, ["anesthetic", "scalpel", "sponge", "stitches"]);
+        assert_eq!(outcome, Ok(true));
+    }
+
+    #[test]
+    fn complications_are_marked() {
+        let (tools, outcome) = replay(include_str!("../../../tests/fixtures/surgery/complication.txt"), stocked);
+        assert_eq!(tools, ["defibrillator!", "antibiotics!"]);
+        assert_eq!(outcome, Ok(false));
+    }
+
+    #[test]
+    fn stops_when_out_of_a_tool() {
+        let defibrillator = SurgeryRules::default().tool_id("defibrillator").unwrap();
+        let (tools, outcome) = replay(
+            include_str!("../../../tests/fixtures/surgery/complication.txt"),
+            |tool_id| if tool_id == defibrillator { 0 } else { 20 },
+        );
+        assert!(tools.is_empty());
+        assert_eq!(outcome, Err("Out of defibrillator".to_string()));
+    }
+
+    #[test]
+    fn player_chat_does_not_end_the_surgery() {
+        let (tools, outcome) = replay(include_str!("../../../tests/fixtures/surgery/spoofed_chat.txt"), stocked);
+        assert_eq!(tools, ["anesthetic", "stitches"]);
+        assert_eq!(outcome, Ok(true));
+    }
+
+    #[test]
+    fn unknown_states_name_the_status() {
+        let dialog = "add_textbox|`2Status: Juggling``|left|\nend_dialog|surgery|Give up||\n";
+        let err = next_step(&SurgeryRules::default(), dialog, stocked).unwrap_err();
+        assert_eq!(err.to_string(), "No rule matches \"Status: Juggling\"");
+    }
+}
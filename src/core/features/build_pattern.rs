@@ -0,0 +1,398 @@
+use crate::core::features::claim_world;
+use crate::core::Bot;
+use crate::types::bot_event::BotEvent;
+use crate::types::build_pattern::{BuildCheckpoint, BuildPattern, MAX_SIZE};
+use crate::utils::{color, config};
+use gtitem_r::structs::ItemDatabase;
+use image::imageops::FilterType;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+const PLACE_DELAY: u64 = 250;
+// Bot::place ignores tiles further than this
+const PLACE_RANGE: i32 = 4;
+// Tiles around the target the bot may stand on to place it
+const STAND_RANGE: i32 = 2;
+// Placed tiles between checkpoint writes to the config
+const CHECKPOINT_EVERY: usize = 10;
+// How long a disconnected bot gets to come back before the build gives up
+const RECONNECT_TIMEOUT: Duration = Duration::from_secs(300);
+const WARP_TIMEOUT: Duration = Duration::from_secs(30);
+const PAUSED_POLL: Duration = Duration::from_millis(500);
+
+#[derive(Error, Debug)]
+pub enum BuildPatternError {
+    #[error("Not in a world")]
+    NotInWorld,
+    #[error("No build to resume")]
+    NothingToResume,
+    #[error("The pattern doesn't fit in the world at {0},{1}")]
+    OutOfBounds(u32, u32),
+    #[error("Missing materials: {0}")]
+    MissingMaterials(String),
+    #[error("No spot to place {0},{1} from")]
+    NoRoute(u32, u32),
+    #[error("Tile {0},{1} didn't take the block")]
+    NotPlaced(u32, u32),
+    #[error("Couldn't get back to {0}: {1}")]
+    Lost(String, String),
+    #[error("Stopped")]
+    Stopped,
+}
+
+// Places `pattern` with its top left corner on `anchor` in the current world. Tiles that
+// already hold the right block are skipped, progress is checkpointed so `resume` can carry on
+pub fn run(bot: &Arc<Bot>, pattern: BuildPattern, anchor: (u32, u32)) -> Result<(), BuildPatternError> {
+    if !bot.is_inworld() {
+        return Err(BuildPatternError::NotInWorld);
+    }
+    let checkpoint = BuildCheckpoint {
+        pattern,
+        world: bot.world_name.get(),
+        anchor,
+        done: 0,
+    };
+    build(bot, checkpoint)
+}
+
+// Picks up the bot's last unfinished build, warping back to its world first
+pub fn resume(bot: &Arc<Bot>) -> Result<(), BuildPatternError> {
+    let checkpoint = checkpoint(bot).ok_or(BuildPatternError::NothingToResume)?;
+    build(bot, checkpoint)
+}
+
+pub fn checkpoint(bot: &Bot) -> Option<BuildCheckpoint> {
+    let username = bot.info.lock().unwrap().payload[0].clone();
+    config::get_bot_config(&username).and_then(|bot_config| bot_config.build_checkpoint)
+}
+
+pub fn stop(bot: &Bot) {
+    bot.temporary_data.write().unwrap().building = false;
+}
+
+pub fn is_running(bot: &Bot) -> bool {
+    bot.temporary_data.read().unwrap().building
+}
+
+// Pattern items the inventory is short of for the tiles not yet right, with the amount short
+pub fn missing_materials(bot: &Bot, tiles: &[(u32, u32, u32)]) -> HashMap<u32, u32> {
+    let needed = {
+        let world = bot.world.read().unwrap();
+        needed(tiles, |x, y| world.get_tile(x, y).map(|tile| tile.foreground_item_id as u32))
+    };
+    let inventory = bot.inventory.lock().unwrap();
+    short_of(needed, |item_id| {
+        inventory.items.get(&(item_id as u16)).map_or(0, |item| item.amount as u32)
+    })
+}
+
+// Blocks still to place per item, tiles already holding theirs left out
+fn needed(tiles: &[(u32, u32, u32)], foreground: impl Fn(u32, u32) -> Option<u32>) -> HashMap<u32, u32> {
+    let mut needed: HashMap<u32, u32> = HashMap::new();
+    for &(x, y, item_id) in tiles {
+        if foreground(x, y) != Some(item_id) {
+            *needed.entry(item_id).or_insert(0) += 1;
+        }
+    }
+    needed
+}
+
+fn short_of(needed: HashMap<u32, u32>, owned: impl Fn(u32) -> u32) -> HashMap<u32, u32> {
+    needed
+        .into_iter()
+        .filter_map(|(item_id, amount)| {
+            let owned = owned(item_id);
+            (owned < amount).then(|| (item_id, amount - owned))
+        })
+        .collect()
+}
+
+// Scales the image down to MAX_SIZE and maps every pixel to the block with the closest base
+// color, out of `palette` when it has any and every block otherwise. Transparent pixels are
+// left empty
+pub fn import_image(
+    path: &str,
+    name: &str,
+    item_database: &ItemDatabase,
+    palette: &[u32],
+) -> Result<BuildPattern, String> {
+    let image = image::open(path).map_err(|err| format!("Can't open {}: {}", path, err))?;
+    let image = if image.width() > MAX_SIZE || image.height() > MAX_SIZE {
+        image.resize(MAX_SIZE, MAX_SIZE, FilterType::Nearest)
+    } else {
+        image
+    };
+    let image = image.to_rgba8();
+    let candidates = color_candidates(item_database, palette);
+    if candidates.is_empty() {
+        return Err("None of the blocks have a base color to match against".to_string());
+    }
+
+    let mut pattern = BuildPattern::new(name, image.width(), image.height());
+    for (x, y, pixel) in image.enumerate_pixels() {
+        let [r, g, b, a] = pixel.0;
+        if a < 128 {
+            continue;
+        }
+        // Past 255 colors the pixel goes to the closest one already used
+        let closest = if pattern.palette.len() < u8::MAX as usize {
+            nearest(candidates.iter(), (r, g, b))
+        } else {
+            let used = candidates.iter().filter(|(item_id, _)| pattern.palette.contains(item_id));
+            nearest(used, (r, g, b))
+        };
+        let item_id = match closest {
+            Some(item_id) => item_id,
+            None => continue,
+        };
+        let index = match pattern.palette.iter().position(|id| *id == item_id) {
+            Some(index) => index + 1,
+            None => {
+                pattern.palette.push(item_id);
+                pattern.palette.len()
+            }
+        };
+        pattern.set(x, y, index as u8);
+    }
+    Ok(pattern)
+}
+
+fn color_candidates(item_database: &ItemDatabase, palette: &[u32]) -> Vec<(u32, (u8, u8, u8))> {
+    let rgb = |base_color: u32| {
+        let (b, g, r, _) = color::extract_bgra(base_color);
+        (r, g, b)
+    };
+    if !palette.is_empty() {
+        return palette
+            .iter()
+            .filter_map(|item_id| item_database.get_item(item_id))
+            .filter(|item| item.base_color != 0)
+            .map(|item| (item.id, rgb(item.base_color)))
+            .collect();
+    }
+    // Seeds have odd ids
+    item_database
+        .items
+        .values()
+        .filter(|item| item.id % 2 == 0 && item.id != 0 && item.base_color != 0)
+        .map(|item| (item.id, rgb(item.base_color)))
+        .collect()
+}
+
+fn nearest<'a>(
+    candidates: impl Iterator<Item = &'a (u32, (u8, u8, u8))>,
+    (r, g, b): (u8, u8, u8),
+) -> Option<u32> {
+    candidates
+        .min_by_key(|(_, (cr, cg, cb))| {
+            let (dr, dg, db) = (*cr as i32 - r as i32, *cg as i32 - g as i32, *cb as i32 - b as i32);
+            dr * dr + dg * dg + db * db
+        })
+        .map(|(item_id, _)| *item_id)
+}
+
+fn build(bot: &Arc<Bot>, mut checkpoint: BuildCheckpoint) -> Result<(), BuildPatternError> {
+    {
+        let mut temp = bot.temporary_data.write().unwrap();
+        if temp.building {
+            return Ok(());
+        }
+        temp.building = true;
+    }
+    bot.emit(BotEvent::Feature { name: "build_pattern".to_string(), running: true });
+
+    let result = place_all(bot, &mut checkpoint);
+    match &result {
+        Ok(()) => {
+            save_checkpoint(bot, None);
+            let (x, y) = checkpoint.anchor;
+            bot.log_info(&format!("Built {} at {},{}", checkpoint.pattern.name, x, y));
+        }
+        Err(err) => {
+            save_checkpoint(bot, Some(checkpoint.clone()));
+            bot.log_warn(&format!("Pattern build stopped: {}", err));
+        }
+    }
+
+    {
+        let mut temp = bot.temporary_data.write().unwrap();
+        temp.building = false;
+        temp.build_progress = None;
+    }
+    bot.emit(BotEvent::Feature { name: "build_pattern".to_string(), running: false });
+    result
+}
+
+fn place_all(bot: &Bot, checkpoint: &mut BuildCheckpoint) -> Result<(), BuildPatternError> {
+    return_to(bot, &checkpoint.world)?;
+    let (anchor_x, anchor_y) = checkpoint.anchor;
+    let (width, height) = {
+        let world = bot.world.read().unwrap();
+        (world.width, world.height)
+    };
+    if anchor_x + checkpoint.pattern.width > width || anchor_y + checkpoint.pattern.height > height {
+        return Err(BuildPatternError::OutOfBounds(anchor_x, anchor_y));
+    }
+
+    let tiles = checkpoint.pattern.tiles(checkpoint.anchor);
+    let done = checkpoint.done.min(tiles.len());
+    let missing = missing_materials(bot, &tiles[done..]);
+    if !missing.is_empty() {
+        let item_database = bot.item_database.read().unwrap();
+        let list = missing
+            .iter()
+            .map(|(item_id, amount)| {
+                let name = item_database
+                    .get_item(item_id)
+                    .map_or_else(|| format!("item {}", item_id), |item| item.name.clone());
+                format!("{} {}", amount, name)
+            })
+            .collect::<Vec<String>>()
+            .join(", ");
+        return Err(BuildPatternError::MissingMaterials(list));
+    }
+
+    for (index, &(x, y, item_id)) in tiles.iter().enumerate().skip(done) {
+        bot.wait_while_paused(|| is_running(bot));
+        if !is_running(bot) {
+            return Err(BuildPatternError::Stopped);
+        }
+        bot.temporary_data.write().unwrap().build_progress = Some((index, tiles.len()));
+        if !bot.is_inworld() || bot.world_name.get() != checkpoint.world {
+            save_checkpoint(bot, Some(checkpoint.clone()));
+            return_to(bot, &checkpoint.world)?;
+        }
+
+        match foreground(bot, x, y) {
+            Some(foreground) if foreground == item_id => {}
+            // Something else is in the way, breaking it isn't this routine's call
+            Some(foreground) if foreground != 0 => {
+                bot.log_warn(&format!("{},{} is taken by item {}, skipping it", x, y, foreground));
+            }
+            _ => {
+                place(bot, x, y, item_id)?;
+                bot.add_stat("blocks_placed", 1);
+            }
+        }
+        checkpoint.done = index + 1;
+        if checkpoint.done % CHECKPOINT_EVERY == 0 {
+            save_checkpoint(bot, Some(checkpoint.clone()));
+        }
+    }
+    Ok(())
+}
+
+// Waits out a reconnect when the bot dropped, then warps back into the build's world
+fn return_to(bot: &Bot, world: &str) -> Result<(), BuildPatternError> {
+    if bot.is_inworld() && bot.world_name.get() == world {
+        return Ok(());
+    }
+    let started = Instant::now();
+    while !bot.state.lock().unwrap().is_ingame {
+        if started.elapsed() > RECONNECT_TIMEOUT {
+            return Err(BuildPatternError::Lost(world.to_string(), "the bot didn't reconnect".to_string()));
+        }
+        if !is_running(bot) {
+            return Err(BuildPatternError::Stopped);
+        }
+        thread::sleep(PAUSED_POLL);
+    }
+    bot.warp_and_wait(world, WARP_TIMEOUT)
+        .map_err(|err| BuildPatternError::Lost(world.to_string(), err))
+}
+
+// Walks within reach when needed and places the block, retrying until the world shows it
+fn place(bot: &Bot, x: u32, y: u32, item_id: u32) -> Result<(), BuildPatternError> {
+    if !within_reach(bot, x, y) {
+        walk_near(bot, x, y)?;
+    }
+    let delay = bot.humanizer.lock().unwrap().action_delay(PLACE_DELAY);
+    thread::sleep(delay);
+    let (bot_x, bot_y) = bot_tile(bot);
+    if claim_world::place_verified(bot, x as i32 - bot_x, y as i32 - bot_y, x, y, item_id) {
+        Ok(())
+    } else {
+        Err(BuildPatternError::NotPlaced(x, y))
+    }
+}
+
+fn within_reach(bot: &Bot, x: u32, y: u32) -> bool {
+    let (bot_x, bot_y) = bot_tile(bot);
+    let (dx, dy) = ((x as i32 - bot_x).abs(), (y as i32 - bot_y).abs());
+    // The bot can't place on the tile it stands on
+    dx <= PLACE_RANGE && dy <= PLACE_RANGE && (dx, dy) != (0, 0)
+}
+
+fn walk_near(bot: &Bot, x: u32, y: u32) -> Result<(), BuildPatternError> {
+    let (width, height) = {
+        let world = bot.world.read().unwrap();
+        (world.width as i32, world.height as i32)
+    };
+    let mut spots = Vec::new();
+    for dy in -STAND_RANGE..=STAND_RANGE {
+        for dx in -STAND_RANGE..=STAND_RANGE {
+            let (spot_x, spot_y) = (x as i32 + dx, y as i32 + dy);
+            if (dx, dy) != (0, 0) && spot_x >= 0 && spot_y >= 0 && spot_x < width && spot_y < height {
+                spots.push((spot_x as u32, spot_y as u32));
+            }
+        }
+    }
+    bot.walk_to_nearest(&spots).map(|_| ()).ok_or(BuildPatternError::NoRoute(x, y))
+}
+
+fn foreground(bot: &Bot, x: u32, y: u32) -> Option<u32> {
+    bot.world
+        .read()
+        .unwrap()
+        .get_tile(x, y)
+        .map(|tile| tile.foreground_item_id as u32)
+}
+
+fn bot_tile(bot: &Bot) -> (i32, i32) {
+    let position = bot.position.lock().unwrap();
+    ((position.x / 32.0).floor() as i32, (position.y / 32.0).floor() as i32)
+}
+
+fn save_checkpoint(bot: &Bot, checkpoint: Option<BuildCheckpoint>) {
+    let username = bot.info.lock().unwrap().payload[0].clone();
+    config::update_bot_config(&username, |bot_config| bot_config.build_checkpoint = checkpoint);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tiles_already_right_need_nothing() {
+        let tiles = [(0, 0, 2), (1, 0, 2), (2, 0, 4), (3, 0, 4)];
+        let placed = |x: u32, _: u32| match x {
+            0 => Some(2),
+            // Something else there still counts, the build skips it later
+            2 => Some(8),
+            _ => None,
+        };
+        assert_eq!(needed(&tiles, placed), HashMap::from([(2, 1), (4, 2)]));
+    }
+
+    #[test]
+    fn reports_only_what_the_inventory_is_short_of() {
+        let needed = HashMap::from([(2, 10), (4, 3), (6, 1)]);
+        let owned = |item_id: u32| match item_id {
+            2 => 4,
+            4 => 3,
+            _ => 0,
+        };
+        assert_eq!(short_of(needed, owned), HashMap::from([(2, 6), (6, 1)]));
+    }
+
+    #[test]
+    fn picks_the_closest_color() {
+        let candidates = [(2, (255, 0, 0)), (4, (0, 255, 0)), (6, (20, 20, 20))];
+        assert_eq!(nearest(candidates.iter(), (200, 30, 30)), Some(2));
+        assert_eq!(nearest(candidates.iter(), (0, 0, 0)), Some(6));
+        assert_eq!(nearest([].iter(), (0, 0, 0)), None);
+    }
+}
@@ -0,0 +1,164 @@
+use crate::core::astar::{PathError, PathHandle};
+use crate::core::Bot;
+use crate::types::bot_event::BotEvent;
+use crate::types::epacket_type::EPacketType;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+const WORLD_LOCK_ID: u32 = 242;
+const WORLD_LOCK_PRICE: i32 = 2000;
+const STEP_TIMEOUT: Duration = Duration::from_secs(10);
+pub(crate) const PLACE_ATTEMPTS: u32 = 3;
+// How far from the bot the server accepts a placement
+const PLACE_RANGE: i32 = 4;
+
+#[derive(Default)]
+pub struct ClaimOptions {
+    pub public: bool,
+    pub custom_music: bool,
+}
+
+#[derive(Error, Debug)]
+pub enum ClaimError {
+    #[error("Not enough gems for a World Lock ({0}/2000)")]
+    NotEnoughGems(i32),
+    #[error("World Lock purchase was not confirmed")]
+    Buy,
+    #[error("Failed to enter world {0}")]
+    Warp(String),
+    #[error("No free tile near the main door")]
+    Placement,
+    #[error("Can't walk to the main door: {0}")]
+    Walk(PathError),
+    #[error("Stopped at {0},{1}, too far from the lock tile")]
+    OutOfReach(u32, u32),
+    #[error("World Lock did not appear after {} attempts", PLACE_ATTEMPTS)]
+    Place,
+    #[error("Lock settings dialog never opened")]
+    Dialog,
+}
+
+// Buys a World Lock, places it next to the door of `world_name` and applies `options`
+pub fn run(bot: &Arc<Bot>, world_name: &str, options: &ClaimOptions) -> Result<(), ClaimError> {
+    bot.emit(BotEvent::Feature { name: "claim_world".to_string(), running: true });
+    let result = claim(bot, world_name, options);
+    bot.emit(BotEvent::Feature { name: "claim_world".to_string(), running: false });
+    result
+}
+
+fn claim(bot: &Arc<Bot>, world_name: &str, options: &ClaimOptions) -> Result<(), ClaimError> {
+    let gems = bot.gems.get();
+    if gems < WORLD_LOCK_PRICE {
+        return Err(ClaimError::NotEnoughGems(gems));
+    }
+
+    bot.log_info("Buying a World Lock");
+    let owned_before = lock_count(bot);
+    bot.send_packet(
+        EPacketType::NetMessageGenericText,
+        "action|buy\nitem|world_lock\n".to_string(),
+    );
+    if !wait_until(|| lock_count(bot) > owned_before) {
+        return Err(ClaimError::Buy);
+    }
+
+    let world_name = world_name.to_uppercase();
+    bot.warp(world_name.clone());
+    if !wait_until(|| bot.world.read().unwrap().name == world_name && bot.is_inworld()) {
+        return Err(ClaimError::Warp(world_name));
+    }
+
+    let (x, y) = placement_tile(bot).ok_or(ClaimError::Placement)?;
+    // Stand on the door, the lock goes right next to it
+    let (door_x, door_y) = bot.main_door().ok_or(ClaimError::Placement)?;
+    let (stand_x, stand_y) = match bot.find_path(door_x, door_y).map_err(ClaimError::Walk)? {
+        PathHandle::Reached => (door_x, door_y),
+        PathHandle::ReachedNearby { x, y, .. } => (x, y),
+    };
+    let (offset_x, offset_y) = (x as i32 - stand_x as i32, y as i32 - stand_y as i32);
+    if offset_x.abs() > PLACE_RANGE || offset_y.abs() > PLACE_RANGE {
+        return Err(ClaimError::OutOfReach(stand_x, stand_y));
+    }
+    if !place_verified(bot, offset_x, offset_y, x, y, WORLD_LOCK_ID) {
+        return Err(ClaimError::Place);
+    }
+
+    bot.temporary_data.write().unwrap().dialog.clear();
+    bot.wrench(offset_x, offset_y);
+    if !wait_until(|| bot.temporary_data.read().unwrap().dialog.contains("lock_edit")) {
+        return Err(ClaimError::Dialog);
+    }
+    bot.send_packet(
+        EPacketType::NetMessageGenericText,
+        format!(
+            "action|dialog_return\ndialog_name|lock_edit\ntilex|{}|\ntiley|{}|\ncheckbox_public|{}\ncheckbox_disable_music|{}\n",
+            x,
+            y,
+            options.public as u8,
+            !options.custom_music as u8
+        ),
+    );
+    bot.log_info(&format!("Claimed world {}", world_name));
+    Ok(())
+}
+
+// Places `item_id` at the offset and retries until the world shows it at `x`, `y`
+pub(crate) fn place_verified(bot: &Bot, offset_x: i32, offset_y: i32, x: u32, y: u32, item_id: u32) -> bool {
+    for _ in 0..PLACE_ATTEMPTS {
+        bot.place(offset_x, offset_y, item_id);
+        if wait_until(|| {
+            bot.world
+                .read()
+                .unwrap()
+                .get_tile(x, y)
+                .map_or(false, |tile| tile.foreground_item_id as u32 == item_id)
+        }) {
+            return true;
+        }
+    }
+    false
+}
+
+// First empty tile beside the door that has something solid underneath
+fn placement_tile(bot: &Bot) -> Option<(u32, u32)> {
+    let (door_x, door_y) = bot.main_door()?;
+    let world = bot.world.read().unwrap();
+    [1i32, -1, 2, -2]
+        .iter()
+        .filter_map(|offset| {
+            let x = door_x as i32 + offset;
+            (x >= 0 && (x as u32) < world.width).then_some(x as u32)
+        })
+        .find(|x| {
+            let empty = world
+                .get_tile(*x, door_y)
+                .map_or(false, |tile| tile.foreground_item_id == 0);
+            let supported = world
+                .get_tile(*x, door_y + 1)
+                .map_or(false, |tile| tile.foreground_item_id != 0);
+            empty && supported
+        })
+        .map(|x| (x, door_y))
+}
+
+fn lock_count(bot: &Bot) -> u8 {
+    bot.inventory
+        .lock()
+        .unwrap()
+        .items
+        .get(&(WORLD_LOCK_ID as u16))
+        .map_or(0, |item| item.amount)
+}
+
+fn wait_until<F: Fn() -> bool>(condition: F) -> bool {
+    let started = Instant::now();
+    while started.elapsed() < STEP_TIMEOUT {
+        if condition() {
+            return true;
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+    false
+}
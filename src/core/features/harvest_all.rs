@@ -0,0 +1,112 @@
+use crate::core::{profiler, world_summary, Bot};
+use crate::types::bot_event::BotEvent;
+use gtworld_r::TileType;
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+const MAX_HITS: u32 = 4;
+const HIT_DELAY: Duration = Duration::from_millis(300);
+
+// Harvests every ready tree in the world, optionally only the given seed ids,
+// nearest by walking distance first. A pause holds it until automation is resumed, the
+// trees are looked up again from wherever the bot is then. Returns how many trees were harvested
+pub fn run(bot: &Arc<Bot>, filter: Option<Vec<u32>>) -> u32 {
+    if !world_summary::build(bot).has_access {
+        bot.log_warn("No access in this world, nothing to harvest");
+        return 0;
+    }
+
+    bot.emit(BotEvent::Feature { name: "harvest_all".to_string(), running: true });
+    // Trees that didn't break, most likely behind a lock we can't build in
+    let mut skipped: HashSet<(u32, u32)> = HashSet::new();
+    let mut harvested = 0;
+    while bot.is_inworld() {
+        bot.wait_while_paused(|| bot.is_inworld());
+        if !bot.is_inworld() {
+            break;
+        }
+        let _span = profiler::span(bot, "harvest_round");
+        let ready = ready_trees(bot, &filter, &skipped);
+        if ready.is_empty() {
+            break;
+        }
+        if bot.walk_to_nearest(&ready).is_none() {
+            bot.log_warn(&format!("No route to the {} remaining ready trees", ready.len()));
+            break;
+        }
+        // Clear the whole cluster in reach before walking on. The list is rebuilt on the
+        // next round, which also picks up trees that became ready while walking
+        let (bot_x, bot_y) = tile_position(bot);
+        let range = bot.state.lock().unwrap().character.punch_range.max(1) as u32;
+        let cluster = ready_trees(bot, &filter, &skipped)
+            .into_iter()
+            .filter(|&(x, y)| bot_x.abs_diff(x) <= range && bot_y.abs_diff(y) <= range);
+        for (x, y) in cluster {
+            if bot.automation_paused() {
+                break;
+            }
+            if harvest(bot, x, y) {
+                harvested += 1;
+                bot.add_stat("trees_harvested", 1);
+            } else {
+                skipped.insert((x, y));
+            }
+        }
+    }
+
+    bot.log_info(&format!("Harvested {} trees", harvested));
+    bot.emit(BotEvent::Feature { name: "harvest_all".to_string(), running: false });
+    harvested
+}
+
+fn ready_trees(
+    bot: &Bot,
+    filter: &Option<Vec<u32>>,
+    skipped: &HashSet<(u32, u32)>,
+) -> Vec<(u32, u32)> {
+    let world = bot.world.read().unwrap();
+    world
+        .tiles
+        .iter()
+        .filter(|tile| matches!(tile.tile_type, TileType::Seed { .. }))
+        .filter(|tile| {
+            filter
+                .as_ref()
+                .map_or(true, |ids| ids.contains(&(tile.foreground_item_id as u32)))
+        })
+        .filter(|tile| !skipped.contains(&(tile.x, tile.y)))
+        .filter(|tile| world.is_tile_harvestable(tile))
+        .map(|tile| (tile.x, tile.y))
+        .collect()
+}
+
+fn is_ready(bot: &Bot, x: u32, y: u32) -> bool {
+    let world = bot.world.read().unwrap();
+    world.get_tile(x, y).map_or(false, |tile| {
+        matches!(tile.tile_type, TileType::Seed { .. }) && world.is_tile_harvestable(tile)
+    })
+}
+
+fn harvest(bot: &Bot, x: u32, y: u32) -> bool {
+    let _span = profiler::span(bot, "harvest_tree");
+    let _busy = bot.busy();
+    for _ in 0..MAX_HITS {
+        if !is_ready(bot, x, y) {
+            return true;
+        }
+        if !bot.is_inworld() {
+            return false;
+        }
+        let (bot_x, bot_y) = tile_position(bot);
+        bot.punch(x as i32 - bot_x as i32, y as i32 - bot_y as i32);
+        thread::sleep(HIT_DELAY);
+    }
+    !is_ready(bot, x, y)
+}
+
+fn tile_position(bot: &Bot) -> (u32, u32) {
+    let position = bot.position.lock().unwrap();
+    ((position.x / 32.0) as u32, (position.y / 32.0) as u32)
+}
@@ -1,3 +1,15 @@
 pub mod auto_tutorial;
 pub mod auto_clear_world;
-pub mod auto_dirt_farm;
\ No newline at end of file
+pub mod auto_deposit;
+pub mod auto_dirt_farm;
+pub mod auto_reconsume;
+pub mod auto_surgery;
+pub mod auto_quest;
+pub mod build_pattern;
+pub mod claim_world;
+pub mod harvest_all;
+pub mod place_lock;
+pub mod repeat_activate;
+pub mod static_farm;
+pub mod transfer_items;
+pub mod world_watcher;
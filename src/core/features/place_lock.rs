@@ -0,0 +1,114 @@
+use super::claim_world::{place_verified, PLACE_ATTEMPTS};
+use crate::core::lock_area::LockSize;
+use crate::core::Bot;
+use crate::types::bot_event::BotEvent;
+use crate::types::epacket_type::EPacketType;
+use crate::utils::config;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+const DIALOG_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Error, Debug)]
+pub enum PlaceLockError {
+    #[error("Not in a world")]
+    NotInWorld,
+    #[error("No {0} in the inventory")]
+    NotOwned(&'static str),
+    #[error("Tile {0}|{1} is not empty")]
+    Occupied(u32, u32),
+    #[error("Can't get next to tile {0}|{1}")]
+    NoRoute(u32, u32),
+    #[error("{0} did not appear after {} attempts", PLACE_ATTEMPTS)]
+    Place(&'static str),
+    #[error("Lock settings dialog never opened")]
+    Dialog,
+}
+
+// Walks next to `x`, `y`, places the lock there and applies the lock defaults from the config
+pub fn run(bot: &Arc<Bot>, size: LockSize, x: u32, y: u32) -> Result<(), PlaceLockError> {
+    bot.emit(BotEvent::Feature { name: "place_lock".to_string(), running: true });
+    let result = place(bot, size, x, y);
+    bot.emit(BotEvent::Feature { name: "place_lock".to_string(), running: false });
+    result
+}
+
+fn place(bot: &Arc<Bot>, size: LockSize, x: u32, y: u32) -> Result<(), PlaceLockError> {
+    if !bot.is_inworld() {
+        return Err(PlaceLockError::NotInWorld);
+    }
+    let owned = bot
+        .inventory
+        .lock()
+        .unwrap()
+        .items
+        .get(&(size.item_id() as u16))
+        .map_or(0, |item| item.amount);
+    if owned == 0 {
+        return Err(PlaceLockError::NotOwned(size.name()));
+    }
+    let empty = bot
+        .world
+        .read()
+        .unwrap()
+        .get_tile(x, y)
+        .map_or(false, |tile| tile.foreground_item_id == 0);
+    if !empty {
+        return Err(PlaceLockError::Occupied(x, y));
+    }
+
+    // Standing on the tile itself would block the placement
+    let beside = [(-1i32, 0i32), (1, 0), (0, -1), (0, 1)]
+        .into_iter()
+        .filter_map(|(dx, dy)| {
+            let (stand_x, stand_y) = (x as i32 + dx, y as i32 + dy);
+            (stand_x >= 0 && stand_y >= 0).then_some((stand_x as u32, stand_y as u32))
+        })
+        .find(|&(stand_x, stand_y)| bot.find_path(stand_x, stand_y).is_ok())
+        .ok_or(PlaceLockError::NoRoute(x, y))?;
+    let (offset_x, offset_y) = (x as i32 - beside.0 as i32, y as i32 - beside.1 as i32);
+    if !place_verified(bot, offset_x, offset_y, x, y, size.item_id()) {
+        return Err(PlaceLockError::Place(size.name()));
+    }
+    bot.log_info(&format!("Placed a {} at {}|{}", size.name(), x, y));
+
+    bot.temporary_data.write().unwrap().dialog.clear();
+    bot.wrench(offset_x, offset_y);
+    if !wait_until(|| bot.temporary_data.read().unwrap().dialog.contains("lock_edit")) {
+        return Err(PlaceLockError::Dialog);
+    }
+    // Each name goes through the dialog's player search, the public toggle is sent last
+    for name in config::get_lock_access() {
+        bot.send_packet(
+            EPacketType::NetMessageGenericText,
+            format!(
+                "action|dialog_return\ndialog_name|lock_edit\ntilex|{}|\ntiley|{}|\nbuttonClicked|searchPlayer\nsearchPlayer|{}\n",
+                x, y, name
+            ),
+        );
+        thread::sleep(Duration::from_millis(500));
+    }
+    bot.send_packet(
+        EPacketType::NetMessageGenericText,
+        format!(
+            "action|dialog_return\ndialog_name|lock_edit\ntilex|{}|\ntiley|{}|\ncheckbox_public|{}\n",
+            x,
+            y,
+            config::get_lock_public() as u8
+        ),
+    );
+    Ok(())
+}
+
+fn wait_until<F: Fn() -> bool>(condition: F) -> bool {
+    let started = Instant::now();
+    while started.elapsed() < DIALOG_TIMEOUT {
+        if condition() {
+            return true;
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+    false
+}
@@ -0,0 +1,324 @@
+use crate::core::activation::ActivateError;
+use crate::core::{quest, script_guard, Bot};
+use crate::types::bot_event::BotEvent;
+use crate::types::epacket_type::EPacketType;
+use crate::types::repeat_stats::RepeatStats;
+use crate::utils::textparse;
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, LazyLock};
+use std::thread;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+const DIALOG_TIMEOUT: Duration = Duration::from_secs(3);
+// Time for the prize to reach the inventory after the dialog is answered
+const SETTLE: Duration = Duration::from_secs(1);
+// Uses turned down with a cooldown in a row before giving up on the tile
+const MAX_REFUSED: u32 = 5;
+
+static COOLDOWN: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(\d+)\s*(hours?|hrs?|minutes?|mins?|seconds?|secs?)").unwrap());
+
+#[derive(Error, Debug)]
+pub enum RepeatActivateError {
+    #[error("Not in a world")]
+    NotInWorld,
+    #[error("Can't activate the tile: {0}")]
+    Activate(#[from] ActivateError),
+    #[error("Out of {0}")]
+    OutOfTickets(String),
+    #[error("The tile stayed on cooldown")]
+    Refused,
+}
+
+// Activates a tile again and again, e.g. a Wheel of Chance or a wishing fountain, until
+// stopped, `max_uses` is reached (0 for no limit) or the ticket runs out. Returns the stats
+// of the run, the reason it stopped is logged
+pub fn run(
+    bot: &Arc<Bot>,
+    tile: (u32, u32),
+    interval: Duration,
+    max_uses: u32,
+    consumable: Option<u32>,
+) -> RepeatStats {
+    {
+        let mut temp = bot.temporary_data.write().unwrap();
+        if !temp.repeat.start() {
+            return temp.repeat.stats();
+        }
+    }
+    bot.emit(BotEvent::Feature { name: "repeat_activate".to_string(), running: true });
+
+    match repeat(bot, tile, interval, max_uses, consumable) {
+        Ok(()) if is_running(bot) => {
+            bot.log_info(&format!("Repeat activate reached {} uses", max_uses));
+        }
+        Ok(()) => {}
+        Err(err) => bot.log_warn(&format!("Repeat activate stopped: {}", err)),
+    }
+
+    let stats = bot.temporary_data.write().unwrap().repeat.finish();
+    bot.log_info(&format!("Used the tile {} times, {}", stats.uses, summary(bot, &stats)));
+    bot.emit(BotEvent::Feature { name: "repeat_activate".to_string(), running: false });
+    stats
+}
+
+pub fn stop(bot: &Bot) {
+    bot.temporary_data.write().unwrap().repeat.running = false;
+}
+
+pub fn is_running(bot: &Bot) -> bool {
+    bot.temporary_data.read().unwrap().repeat.running
+}
+
+// Overlays and console lines telling the bot to come back later. Chat can say the same,
+// so only lines the server wrote itself count
+pub fn note_message(bot: &Bot, message: &str) {
+    if !textparse::is_system_line(message) {
+        return;
+    }
+    let cooldown = match parse_cooldown(message) {
+        Some(cooldown) => cooldown,
+        None => return,
+    };
+    let mut temp = bot.temporary_data.write().unwrap();
+    if temp.repeat.running {
+        temp.repeat_cooldown = Some(cooldown);
+    }
+}
+
+// e.g. "You can spin again in 4 minutes, 20 seconds" or "Try again in 30 secs"
+fn parse_cooldown(message: &str) -> Option<Duration> {
+    let message = textparse::strip_color(message).to_lowercase();
+    let rest = &message[message.find("again in")?..];
+    let secs: u64 = COOLDOWN
+        .captures_iter(rest)
+        .filter_map(|captures| {
+            let amount: u64 = captures[1].parse().ok()?;
+            let unit = match &captures[2] {
+                unit if unit.starts_with('s') => 1,
+                unit if unit.starts_with('m') => 60,
+                _ => 3600,
+            };
+            Some(amount * unit)
+        })
+        .sum();
+    (secs > 0).then(|| Duration::from_secs(secs))
+}
+
+// Items gained and lost over the run, by name
+pub fn summary(bot: &Bot, stats: &RepeatStats) -> String {
+    let changes = stats.changes();
+    if changes.is_empty() {
+        return "nothing gained".to_string();
+    }
+    let items = changes
+        .iter()
+        .map(|(item_id, amount)| format!("{:+} {}", amount, script_guard::item_name(bot, *item_id)))
+        .collect::<Vec<String>>()
+        .join(", ");
+    format!("got {}", items)
+}
+
+fn repeat(
+    bot: &Bot,
+    (x, y): (u32, u32),
+    interval: Duration,
+    max_uses: u32,
+    consumable: Option<u32>,
+) -> Result<(), RepeatActivateError> {
+    if !bot.is_inworld() {
+        return Err(RepeatActivateError::NotInWorld);
+    }
+    walk_into_range(bot, x, y);
+
+    let mut refused = 0;
+    // A use turned down with a cooldown keeps its ticket for the retry
+    let mut ticket_spent = false;
+    while is_running(bot) {
+        if bot.automation_paused() {
+            bot.wait_while_paused(|| is_running(bot));
+            if !is_running(bot) {
+                break;
+            }
+            if !bot.is_inworld() {
+                return Err(RepeatActivateError::NotInWorld);
+            }
+            // Whoever paused it may have walked the bot off
+            walk_into_range(bot, x, y);
+        }
+        if max_uses > 0 && record(bot, |stats| stats.uses) >= max_uses {
+            return Ok(());
+        }
+        let before = inventory_counts(bot);
+        if let Some(ticket) = consumable.filter(|_| !ticket_spent) {
+            if before.get(&ticket).copied().unwrap_or(0) == 0 {
+                return Err(RepeatActivateError::OutOfTickets(script_guard::item_name(bot, ticket)));
+            }
+            bot.consume(ticket);
+            ticket_spent = true;
+        }
+        {
+            let mut temp = bot.temporary_data.write().unwrap();
+            temp.dialog.clear();
+            temp.repeat_cooldown = None;
+        }
+        bot.tile_activate(x, y)?;
+        if let Some(dialog) = wait_for_dialog(bot) {
+            answer(bot, &dialog);
+        }
+        thread::sleep(SETTLE);
+
+        let cooldown = bot.temporary_data.write().unwrap().repeat_cooldown.take();
+        let wait = match cooldown {
+            Some(cooldown) => {
+                refused += 1;
+                if refused >= MAX_REFUSED {
+                    return Err(RepeatActivateError::Refused);
+                }
+                bot.log_info(&format!("Tile is on cooldown, waiting {}s", cooldown.as_secs()));
+                // A second extra so the retry doesn't land right on the edge
+                cooldown + Duration::from_secs(1)
+            }
+            None => {
+                refused = 0;
+                ticket_spent = false;
+                record(bot, |stats| stats.uses += 1);
+                bot.add_stat("tile_uses", 1);
+                interval.saturating_sub(SETTLE)
+            }
+        };
+        reconcile(bot, &before, consumable);
+        sleep_while_running(bot, wait);
+    }
+    Ok(())
+}
+
+// Up front and after a pause, tile_activate only walks when walk_to_activate is set
+fn walk_into_range(bot: &Bot, x: u32, y: u32) {
+    if let Err(err) = bot.find_path(x, y) {
+        bot.log_debug(&format!("No route to {},{}: {}", x, y, err));
+    }
+}
+
+fn wait_for_dialog(bot: &Bot) -> Option<String> {
+    let started = Instant::now();
+    while started.elapsed() < DIALOG_TIMEOUT {
+        {
+            let temp = bot.temporary_data.read().unwrap();
+            if !temp.dialog.is_empty() {
+                return Some(temp.dialog.clone());
+            }
+            if temp.repeat_cooldown.is_some() {
+                return None;
+            }
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+    None
+}
+
+fn answer(bot: &Bot, dialog: &str) {
+    if let Some(reply) = reply(dialog) {
+        bot.temporary_data.write().unwrap().dialog.clear();
+        bot.send_packet(EPacketType::NetMessageGenericText, reply);
+    }
+}
+
+// Presses the dialog's default button, its OK button when it has one and its first
+// button otherwise. None when there's nothing to press
+fn reply(dialog: &str) -> Option<String> {
+    let name = quest::dialog_name(dialog)?;
+    let mut reply = format!("action|dialog_return\ndialog_name|{}\n", name);
+    let mut accept = false;
+    let mut first_button = None;
+    for line in dialog.lines() {
+        let fields: Vec<&str> = line.split('|').collect();
+        match fields.as_slice() {
+            ["embed_data", key, value, ..] => reply.push_str(&format!("{}|{}\n", key, value)),
+            ["end_dialog", _, _, ok, ..] => accept = !ok.is_empty(),
+            ["add_button", key, ..] if first_button.is_none() => first_button = Some(key.to_string()),
+            _ => {}
+        }
+    }
+    if !accept {
+        reply.push_str(&format!("buttonClicked|{}\n", first_button?));
+    }
+    Some(reply)
+}
+
+fn inventory_counts(bot: &Bot) -> HashMap<u32, i32> {
+    bot.inventory
+        .lock()
+        .unwrap()
+        .items
+        .values()
+        .map(|item| (item.id as u32, item.amount as i32))
+        .collect()
+}
+
+// Whatever changed in the inventory since `before` came from the tile, apart from the ticket
+fn reconcile(bot: &Bot, before: &HashMap<u32, i32>, ticket: Option<u32>) {
+    let after = inventory_counts(bot);
+    let item_ids: HashSet<u32> = before.keys().chain(after.keys()).copied().collect();
+    record(bot, |stats| {
+        for item_id in item_ids {
+            let change = after.get(&item_id).unwrap_or(&0) - before.get(&item_id).unwrap_or(&0);
+            if Some(item_id) == ticket {
+                stats.tickets_used += (-change).max(0) as u32;
+            } else if change != 0 {
+                *stats.gained.entry(item_id).or_insert(0) += change;
+            }
+        }
+    });
+}
+
+fn sleep_while_running(bot: &Bot, duration: Duration) {
+    let started = Instant::now();
+    while started.elapsed() < duration && is_running(bot) {
+        thread::sleep(Duration::from_millis(250));
+    }
+}
+
+fn record<T>(bot: &Bot, f: impl FnOnce(&mut RepeatStats) -> T) -> T {
+    bot.temporary_data.write().unwrap().repeat.record(f)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_cooldowns() {
+        assert_eq!(parse_cooldown("You can spin again in 4 minutes, 20 seconds"), Some(Duration::from_secs(260)));
+        assert_eq!(parse_cooldown("Try again in 30 secs"), Some(Duration::from_secs(30)));
+        assert_eq!(parse_cooldown("`4Try again in `w1`` hour, `w5`` mins!``"), Some(Duration::from_secs(3900)));
+        assert_eq!(parse_cooldown("You can spin again now"), None);
+        assert_eq!(parse_cooldown("Wait 30 seconds"), None);
+    }
+
+    #[test]
+    fn accepts_a_dialog_with_an_ok_button() {
+        let dialog = "set_default_color|`o\nembed_data|tilex|12\nembed_data|tiley|30\nadd_button|spin|Spin|noflags|0|0|\nend_dialog|wheel|Cancel|OK|";
+        assert_eq!(
+            reply(dialog).unwrap(),
+            "action|dialog_return\ndialog_name|wheel\ntilex|12\ntiley|30\n"
+        );
+    }
+
+    #[test]
+    fn presses_the_first_button_without_an_ok() {
+        let dialog = "add_button|claim|Claim|noflags|0|0|\nadd_button|later|Later|noflags|0|0|\nend_dialog|prize|Close||";
+        assert_eq!(
+            reply(dialog).unwrap(),
+            "action|dialog_return\ndialog_name|prize\nbuttonClicked|claim\n"
+        );
+    }
+
+    #[test]
+    fn leaves_a_dialog_without_buttons() {
+        assert_eq!(reply("add_label|big|Nothing here|left|\nend_dialog|info|Close||"), None);
+        assert_eq!(reply("add_label|big|No end|left|"), None);
+    }
+}
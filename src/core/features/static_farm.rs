@@ -0,0 +1,196 @@
+use crate::core::features::claim_world;
+use crate::core::{profiler, Bot};
+use crate::types::bot_event::BotEvent;
+use crate::types::static_farm_stats::StaticFarmStats;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+const HIT_DELAY: u64 = 250;
+const PLACE_DELAY: u64 = 200;
+const MAX_HITS: u32 = 6;
+// A block still standing after MAX_HITS is usually lag, the world gets a moment to catch up
+const RESYNC_WAIT: Duration = Duration::from_secs(1);
+// Consecutive blocks that didn't place or break before giving up on the pattern
+const MAX_STUCK: u32 = 3;
+const PAUSED_POLL: Duration = Duration::from_millis(500);
+
+// Offsets from the bot's tile, worked through in order every cycle
+pub const PRESETS: &[(&str, &[(i32, i32)])] = &[
+    ("Beside", &[(-1, 0), (1, 0)]),
+    ("Below", &[(0, 1)]),
+    ("Beside and below", &[(-1, 0), (1, 0), (0, 1)]),
+];
+
+pub fn preset(name: &str) -> Option<Vec<(i32, i32)>> {
+    PRESETS
+        .iter()
+        .find(|(preset, _)| preset.eq_ignore_ascii_case(name))
+        .map(|(_, pattern)| pattern.to_vec())
+}
+
+// Stands still and places then breaks `item_id` on each offset of `pattern` until stopped,
+// never pathfinding. Out of blocks or a pattern that stops breaking pauses automation,
+// the loop carries on once it's resumed
+pub fn run(bot: &Arc<Bot>, pattern: Vec<(i32, i32)>, item_id: u32) {
+    if pattern.is_empty() {
+        bot.log_warn("Static farm pattern is empty");
+        return;
+    }
+    if !bot.is_inworld() {
+        bot.log_warn("Not in a world, nothing to farm");
+        return;
+    }
+    {
+        let mut temp = bot.temporary_data.write().unwrap();
+        if temp.static_farming {
+            return;
+        }
+        temp.static_farming = true;
+        temp.static_farm = Some(StaticFarmStats::new());
+    }
+
+    bot.emit(BotEvent::Feature { name: "static_farm".to_string(), running: true });
+    // Seeds share the block's id plus one
+    let seed_id = item_id + 1;
+    let mut blocks = amount_of(bot, item_id);
+    let mut seeds = amount_of(bot, seed_id);
+    let mut stuck = 0;
+    'farm: while is_running(bot) && bot.is_inworld() {
+        if bot.automation_paused() {
+            thread::sleep(PAUSED_POLL);
+            continue;
+        }
+        let _span = profiler::span(bot, "static_farm_cycle");
+        for &(offset_x, offset_y) in &pattern {
+            if !is_running(bot) || !bot.is_inworld() || bot.automation_paused() {
+                continue 'farm;
+            }
+            let (x, y) = match target(bot, offset_x, offset_y) {
+                Some(target) => target,
+                None => continue,
+            };
+            if foreground(bot, x, y) == Some(0) {
+                if amount_of(bot, item_id) == 0 {
+                    bot.log_warn(&format!("Out of item {}, pausing the static farm", item_id));
+                    bot.pause_automation();
+                    continue 'farm;
+                }
+                // Punching before the server confirmed the block would hit an empty tile
+                if !claim_world::place_verified(bot, offset_x, offset_y, x, y, item_id) {
+                    stuck += 1;
+                    bot.log_warn(&format!("Block at {},{} never appeared ({}/{})", x, y, stuck, MAX_STUCK));
+                    if stuck >= MAX_STUCK {
+                        bot.log_warn("Blocks keep failing to place, pausing the static farm");
+                        bot.pause_automation();
+                        stuck = 0;
+                    }
+                    continue;
+                }
+                delay(bot, PLACE_DELAY);
+            }
+
+            if break_tile(bot, offset_x, offset_y, x, y) {
+                stuck = 0;
+                record(bot, |stats| stats.broken += 1);
+            } else {
+                stuck += 1;
+                bot.log_warn(&format!("Block at {},{} didn't break ({}/{})", x, y, stuck, MAX_STUCK));
+                if stuck >= MAX_STUCK {
+                    bot.log_warn("Blocks keep surviving, pausing the static farm");
+                    bot.pause_automation();
+                    stuck = 0;
+                }
+            }
+
+            // Diffs rather than per-action checks, inventory updates trail the tile changes
+            let (now_blocks, now_seeds) = (amount_of(bot, item_id), amount_of(bot, seed_id));
+            let placed = blocks.saturating_sub(now_blocks);
+            let gained = now_seeds.saturating_sub(seeds);
+            record(bot, |stats| {
+                stats.placed += placed;
+                stats.seeds += gained;
+            });
+            blocks = now_blocks;
+            seeds = now_seeds;
+        }
+    }
+
+    let stats = bot.temporary_data.read().unwrap().static_farm.clone();
+    if let Some(stats) = stats {
+        bot.log_info(&format!(
+            "Static farm broke {} blocks ({:.0}/hour), {} seeds",
+            stats.broken,
+            stats.blocks_per_hour(),
+            stats.seeds
+        ));
+    }
+    bot.temporary_data.write().unwrap().static_farming = false;
+    bot.emit(BotEvent::Feature { name: "static_farm".to_string(), running: false });
+}
+
+pub fn stop(bot: &Bot) {
+    bot.temporary_data.write().unwrap().static_farming = false;
+}
+
+pub fn is_running(bot: &Bot) -> bool {
+    bot.temporary_data.read().unwrap().static_farming
+}
+
+// Punches until the tile is empty. When it isn't after MAX_HITS the world is read again
+// after RESYNC_WAIT, the break may only have been slow to arrive
+fn break_tile(bot: &Bot, offset_x: i32, offset_y: i32, x: u32, y: u32) -> bool {
+    let _busy = bot.busy();
+    for _ in 0..MAX_HITS {
+        if foreground(bot, x, y) != Some(0) {
+            bot.punch(offset_x, offset_y);
+            delay(bot, HIT_DELAY);
+        }
+        if foreground(bot, x, y) == Some(0) {
+            return true;
+        }
+    }
+    thread::sleep(RESYNC_WAIT);
+    foreground(bot, x, y) == Some(0)
+}
+
+fn record(bot: &Bot, f: impl FnOnce(&mut StaticFarmStats)) {
+    if let Some(stats) = bot.temporary_data.write().unwrap().static_farm.as_mut() {
+        f(stats);
+    }
+}
+
+fn delay(bot: &Bot, base_ms: u64) {
+    let delay = bot.humanizer.lock().unwrap().action_delay(base_ms);
+    thread::sleep(delay);
+}
+
+fn target(bot: &Bot, offset_x: i32, offset_y: i32) -> Option<(u32, u32)> {
+    let (bot_x, bot_y) = {
+        let position = bot.position.lock().unwrap();
+        ((position.x / 32.0) as i32, (position.y / 32.0) as i32)
+    };
+    let (x, y) = (bot_x + offset_x, bot_y + offset_y);
+    let world = bot.world.read().unwrap();
+    if x < 0 || y < 0 || x as u32 >= world.width || y as u32 >= world.height {
+        return None;
+    }
+    Some((x as u32, y as u32))
+}
+
+fn foreground(bot: &Bot, x: u32, y: u32) -> Option<u16> {
+    bot.world
+        .read()
+        .unwrap()
+        .get_tile(x, y)
+        .map(|tile| tile.foreground_item_id)
+}
+
+fn amount_of(bot: &Bot, item_id: u32) -> u32 {
+    bot.inventory
+        .lock()
+        .unwrap()
+        .items
+        .get(&(item_id as u16))
+        .map_or(0, |item| item.amount as u32)
+}
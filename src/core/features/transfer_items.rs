@@ -0,0 +1,266 @@
+use crate::core::Bot;
+use crate::types::bot_event::BotEvent;
+use crate::utils::{config, webhook};
+use std::collections::HashSet;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+// Drops are split so one lost stack never costs more than this
+const BATCH_SIZE: u32 = 50;
+const BATCH_RETRIES: u32 = 3;
+const STEP_TIMEOUT: Duration = Duration::from_secs(10);
+const RECEIVE_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Error, Debug)]
+pub enum TransferError {
+    #[error("{0} failed to enter {1}")]
+    Warp(String, String),
+    #[error("No free tile next to the receiver")]
+    NoSpot,
+    #[error("Sender only has {have}/{want} of item {item_id}")]
+    NotEnough { item_id: u32, have: u32, want: u32 },
+    #[error("Receiver has no room for item {0}")]
+    NoRoom(u32),
+    #[error("Aborted, {0} joined the world")]
+    Intruder(String),
+    #[error("Aborted, {0} left the world")]
+    Left(String),
+    #[error("{missing} of item {item_id} never arrived")]
+    Lost { item_id: u32, missing: u32 },
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct TransferReport {
+    // (item id, requested, sent, received)
+    pub items: Vec<(u32, u32, u32, u32)>,
+    pub error: Option<String>,
+}
+
+impl TransferReport {
+    pub fn is_complete(&self) -> bool {
+        self.error.is_none()
+            && self
+                .items
+                .iter()
+                .all(|&(_, requested, _, received)| received >= requested)
+    }
+
+    pub fn describe(&self) -> String {
+        let mut lines: Vec<String> = self
+            .items
+            .iter()
+            .map(|(item_id, requested, sent, received)| {
+                format!(
+                    "item {}: requested {}, sent {}, received {}",
+                    item_id, requested, sent, received
+                )
+            })
+            .collect();
+        if let Some(error) = &self.error {
+            lines.push(format!("stopped: {}", error));
+        }
+        lines.join("\n")
+    }
+}
+
+// Moves `items` (id, amount) from `from` to `to` inside `world`. The receiver stays at
+// the door and collects only the transferred ids while the sender drops them next to it
+pub fn run(from: &Arc<Bot>, to: &Arc<Bot>, items: Vec<(u32, u32)>, world: String) -> TransferReport {
+    let mut report = TransferReport {
+        items: items.iter().map(|&(item_id, amount)| (item_id, amount, 0, 0)).collect(),
+        error: None,
+    };
+    let item_ids: HashSet<u16> = items.iter().map(|&(item_id, _)| item_id as u16).collect();
+    to.temporary_data.write().unwrap().collect_filter = Some(item_ids);
+    from.emit(BotEvent::Feature { name: "transfer_items".to_string(), running: true });
+
+    if let Err(err) = transfer(from, to, &world, &mut report) {
+        from.log_error(&format!("Transfer to {} failed: {}", name(to), err));
+        report.error = Some(err.to_string());
+    }
+    to.temporary_data.write().unwrap().collect_filter = None;
+    from.emit(BotEvent::Feature { name: "transfer_items".to_string(), running: false });
+
+    let summary = format!(
+        "Transfer {} -> {} in {}\n{}",
+        name(from),
+        name(to),
+        world.to_uppercase(),
+        report.describe()
+    );
+    for line in summary.lines() {
+        from.log_info(line);
+    }
+    let url = config::get_webhook_url();
+    if config::get_transfer_webhook() && !url.is_empty() {
+        if let Err(err) = webhook::send(&url, &summary) {
+            from.log_error(&format!("Failed to send transfer report: {}", err));
+        }
+    }
+    report
+}
+
+fn transfer(
+    from: &Arc<Bot>,
+    to: &Arc<Bot>,
+    world: &str,
+    report: &mut TransferReport,
+) -> Result<(), TransferError> {
+    for &(item_id, requested, _, _) in &report.items {
+        let have = amount_of(from, item_id);
+        if have < requested {
+            return Err(TransferError::NotEnough { item_id, have, want: requested });
+        }
+    }
+
+    let world = world.to_uppercase();
+    for bot in [to, from] {
+        if bot.world.read().unwrap().name != world {
+            bot.warp(world.clone());
+        }
+        if !wait_until(STEP_TIMEOUT, || bot.is_inworld() && bot.world.read().unwrap().name == world) {
+            return Err(TransferError::Warp(name(bot), world));
+        }
+    }
+    check_players(from, to, &world)?;
+
+    let (spot_x, spot_y) = drop_spot(from, to).ok_or(TransferError::NoSpot)?;
+    if let Err(err) = from.find_path(spot_x, spot_y) {
+        from.log_warn(&format!("Dropping from here, can't reach the drop spot: {}", err));
+    }
+    from.face_towards(to.position.lock().unwrap().x as i32 / 32 - spot_x as i32);
+
+    for entry in report.items.iter_mut() {
+        let (item_id, requested, _, _) = *entry;
+        while entry.3 < requested {
+            check_players(from, to, &world)?;
+            let batch = (requested - entry.3).min(BATCH_SIZE);
+            if !has_room(to, item_id, batch) {
+                return Err(TransferError::NoRoom(item_id));
+            }
+            let (sent, received) = send_batch(from, to, item_id, batch, &world)?;
+            entry.2 += sent;
+            entry.3 += received;
+            // The retries inside send_batch are spent, dropping more would only lose more
+            if received < batch {
+                return Err(TransferError::Lost { item_id, missing: batch - received });
+            }
+            from.log_info(&format!(
+                "Item {}: {}/{} received by {}",
+                item_id,
+                entry.3,
+                requested,
+                name(to)
+            ));
+        }
+    }
+    Ok(())
+}
+
+// Drops `batch` and waits for the receiver's count to go up. Shortfalls are dropped
+// again, what is still missing after the retries is left for the caller to report.
+// Returns (sent, received), both as seen in the two inventories
+fn send_batch(
+    from: &Arc<Bot>,
+    to: &Arc<Bot>,
+    item_id: u32,
+    batch: u32,
+    world: &str,
+) -> Result<(u32, u32), TransferError> {
+    let before = amount_of(to, item_id);
+    let sender_start = amount_of(from, item_id);
+    let sent = || sender_start.saturating_sub(amount_of(from, item_id));
+    let mut missing = batch;
+    for _ in 0..BATCH_RETRIES {
+        let sender_before = amount_of(from, item_id);
+        from.drop_item(item_id, missing);
+        wait_until(STEP_TIMEOUT, || amount_of(from, item_id) < sender_before);
+
+        let expected = before + batch;
+        wait_until(RECEIVE_TIMEOUT, || {
+            to.collect();
+            amount_of(to, item_id) >= expected
+        });
+        check_players(from, to, world)?;
+        let received = amount_of(to, item_id).saturating_sub(before);
+        if received >= batch {
+            return Ok((sent(), batch));
+        }
+        missing = batch - received;
+        from.log_warn(&format!("{} of item {} did not arrive, retrying", missing, item_id));
+    }
+    Ok((sent(), amount_of(to, item_id).saturating_sub(before)))
+}
+
+// Anyone besides the two bots could pick the drops up
+fn check_players(from: &Bot, to: &Bot, world: &str) -> Result<(), TransferError> {
+    for (bot, other) in [(from, to), (to, from)] {
+        if !bot.is_inworld() || bot.world.read().unwrap().name != world {
+            return Err(TransferError::Left(name(bot)));
+        }
+        let other_net_id = other.state.lock().unwrap().net_id;
+        let stranger = bot
+            .players
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|player| player.net_id != other_net_id)
+            .map(|player| player.name.clone());
+        if let Some(stranger) = stranger {
+            return Err(TransferError::Intruder(stranger));
+        }
+    }
+    Ok(())
+}
+
+// A tile beside the receiver the sender can reach, preferring the left so drops land on it
+fn drop_spot(from: &Bot, to: &Bot) -> Option<(u32, u32)> {
+    let (to_x, to_y) = {
+        let position = to.position.lock().unwrap();
+        ((position.x / 32.0) as i32, (position.y / 32.0) as i32)
+    };
+    [-1, 1, -2, 2].iter().find_map(|offset| {
+        let x = to_x + offset;
+        if x < 0 || to_y < 0 {
+            return None;
+        }
+        from.plan_path(x as u32, to_y as u32, &AtomicBool::new(false))
+            .map(|_| (x as u32, to_y as u32))
+    })
+}
+
+fn has_room(bot: &Bot, item_id: u32, amount: u32) -> bool {
+    let inventory = bot.inventory.lock().unwrap();
+    match inventory.items.get(&(item_id as u16)) {
+        Some(item) => item.amount as u32 + amount <= 200,
+        None => inventory.size > inventory.item_count as u32,
+    }
+}
+
+fn amount_of(bot: &Bot, item_id: u32) -> u32 {
+    bot.inventory
+        .lock()
+        .unwrap()
+        .items
+        .get(&(item_id as u16))
+        .map_or(0, |item| item.amount as u32)
+}
+
+fn name(bot: &Bot) -> String {
+    bot.info.lock().unwrap().payload[0].clone()
+}
+
+fn wait_until<F: FnMut() -> bool>(timeout: Duration, mut condition: F) -> bool {
+    let started = Instant::now();
+    while started.elapsed() < timeout {
+        if condition() {
+            return true;
+        }
+        thread::sleep(Duration::from_millis(250));
+    }
+    false
+}
+
@@ -0,0 +1,159 @@
+use crate::core::{bandwidth, reactions, world_diff, world_snapshot, world_summary, Bot};
+use crate::types::bot_event::BotEvent;
+use crate::types::watch_report::WatchReport;
+use crate::utils::{config, date, webhook};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+// Joining worlds back to back gets the account warp limited
+const WARP_GAP: Duration = Duration::from_secs(10);
+const MAX_FAILURES: u32 = 2;
+// Players and drops arrive shortly after the map data
+const SETTLE: Duration = Duration::from_millis(1500);
+
+// Visits `worlds` in turn every `interval` until stopped, recording who is there, how
+// many gems lie around and how many tiles changed since the last saved snapshot
+pub fn run(bot: &Arc<Bot>, worlds: Vec<String>, interval: Duration) {
+    if worlds.is_empty() {
+        bot.log_warn("No worlds to watch");
+        return;
+    }
+    {
+        let mut temp = bot.temporary_data.write().unwrap();
+        if temp.watching {
+            return;
+        }
+        temp.watching = true;
+        temp.watch_reports.clear();
+    }
+
+    bot.emit(BotEvent::Feature { name: "world_watcher".to_string(), running: true });
+    let username = bot.info.lock().unwrap().payload[0].clone();
+    let mut failures: HashMap<String, u32> = HashMap::new();
+    let mut last_warp: Option<Instant> = None;
+    while is_watching(bot) {
+        let round_started = Instant::now();
+        // Every visit downloads the whole world, rounds wait out the bandwidth cap
+        if bandwidth::throttled() {
+            wait(bot, interval);
+            continue;
+        }
+        let mut reports = Vec::new();
+        for world in &worlds {
+            let world = world.trim().to_uppercase();
+            if !is_watching(bot) {
+                break;
+            }
+            if failures.get(&world).copied().unwrap_or(0) >= MAX_FAILURES {
+                continue;
+            }
+            let report = match visit(bot, &world, &mut last_warp) {
+                Some(report) => {
+                    failures.remove(&world);
+                    report
+                }
+                None => {
+                    let count = failures.entry(world.clone()).or_insert(0);
+                    *count += 1;
+                    if *count < MAX_FAILURES {
+                        bot.log_warn(&format!("Failed to enter {}, retrying next round", world));
+                        continue;
+                    }
+                    bot.log_warn(&format!("Failed to enter {} twice, skipping it", world));
+                    WatchReport {
+                        world: world.clone(),
+                        visited_at: date::now_secs(),
+                        failed: true,
+                        ..Default::default()
+                    }
+                }
+            };
+            bot.log_info(&report.describe());
+            record(bot, report.clone());
+            reports.push(report);
+        }
+        notify(bot, &username, &reports);
+
+        if worlds
+            .iter()
+            .all(|world| failures.get(&world.trim().to_uppercase()).copied().unwrap_or(0) >= MAX_FAILURES)
+        {
+            bot.log_error("Every watched world failed to load, stopping");
+            break;
+        }
+        wait(bot, interval.saturating_sub(round_started.elapsed()));
+    }
+
+    bot.temporary_data.write().unwrap().watching = false;
+    bot.emit(BotEvent::Feature { name: "world_watcher".to_string(), running: false });
+}
+
+// Lets the current visit finish, then ends the run
+pub fn stop(bot: &Bot) {
+    bot.temporary_data.write().unwrap().watching = false;
+}
+
+pub fn is_watching(bot: &Bot) -> bool {
+    bot.temporary_data.read().unwrap().watching
+}
+
+fn visit(bot: &Bot, world: &str, last_warp: &mut Option<Instant>) -> Option<WatchReport> {
+    if let Some(last_warp) = last_warp {
+        wait(bot, WARP_GAP.saturating_sub(last_warp.elapsed()));
+        if !is_watching(bot) {
+            return None;
+        }
+    }
+    // Entering the world overwrites its snapshot, so the old one has to be read first
+    let previous = world_snapshot::load(world, bot.item_database.clone()).ok();
+    *last_warp = Some(Instant::now());
+    if !reactions::warp_to(bot, world) {
+        return None;
+    }
+    thread::sleep(SETTLE);
+
+    let summary = world_summary::build(bot);
+    let changed_tiles = previous.and_then(|previous| {
+        let diff = world_diff::diff(&previous, &bot.world.read().unwrap());
+        diff.dimensions.is_none().then_some(diff.tiles.len())
+    });
+    Some(WatchReport {
+        world: world.to_string(),
+        visited_at: date::now_secs(),
+        players: summary.players,
+        dropped_gems: summary.dropped_gems,
+        changed_tiles,
+        failed: false,
+    })
+}
+
+// Keeps the latest report per world
+fn record(bot: &Bot, report: WatchReport) {
+    let mut temp = bot.temporary_data.write().unwrap();
+    match temp.watch_reports.iter_mut().find(|existing| existing.world == report.world) {
+        Some(existing) => *existing = report,
+        None => temp.watch_reports.push(report),
+    }
+}
+
+fn notify(bot: &Bot, username: &str, reports: &[WatchReport]) {
+    let url = config::get_webhook_url();
+    if url.is_empty() || reports.is_empty() {
+        return;
+    }
+    let lines: Vec<String> = reports.iter().map(|report| report.describe()).collect();
+    let content = format!("{} watched {} worlds:\n{}", username, reports.len(), lines.join("\n"));
+    if let Err(err) = webhook::send(&url, &content) {
+        bot.log_error(&format!("Failed to send webhook: {}", err));
+    }
+}
+
+// Sleeps in short steps so stopping doesn't wait out the whole interval
+fn wait(bot: &Bot, duration: Duration) {
+    let started = Instant::now();
+    while started.elapsed() < duration && is_watching(bot) {
+        thread::sleep(Duration::from_millis(250));
+    }
+}
@@ -0,0 +1,11 @@
+use gtitem_r::structs::Item;
+
+// items.dat action types that hurt or respawn the player on touch
+const ACTION_DEADLY: u8 = 6;
+const ACTION_LAVA: u8 = 16;
+const ACTION_POINTY: u8 = 25;
+
+// Shared by pathfinding and the world map overlay
+pub fn is_harmful(item: &Item) -> bool {
+    [ACTION_DEADLY, ACTION_LAVA, ACTION_POINTY].contains(&item.action_type)
+}
@@ -0,0 +1,44 @@
+use super::{bandwidth, Bot};
+use crate::utils::{config, random};
+use std::time::{Duration, Instant};
+
+// Idle clients still send a state packet every so often, a bot that stays silent
+// while standing still for minutes stands out. Anything else that moves the bot
+// resets the idle clock, so this only fills the gaps
+#[derive(Default)]
+pub struct Heartbeat {
+    next_after: Option<Duration>,
+}
+
+impl Heartbeat {
+    pub fn new() -> Self {
+        Heartbeat { next_after: None }
+    }
+
+    pub fn tick(&mut self, bot: &Bot, last_movement: Instant) {
+        let interval = config::get_idle_heartbeat();
+        if interval == 0
+            || bandwidth::throttled()
+            || !bot.is_inworld()
+            || !bot.state.lock().unwrap().is_running
+        {
+            self.next_after = None;
+            return;
+        }
+
+        let next_after = *self.next_after.get_or_insert_with(|| sample(interval));
+        if last_movement.elapsed() >= next_after {
+            bot.send_idle_state();
+            self.next_after = Some(sample(interval));
+        }
+    }
+}
+
+// Triangular around the configured interval (70% to 130%), so the gaps cluster
+// near the average instead of being evenly spread or perfectly regular
+fn sample(interval_secs: u64) -> Duration {
+    let base = interval_secs * 1000;
+    let low = base * 7 / 10;
+    let high = base * 13 / 10;
+    Duration::from_millis((random::range(low, high) + random::range(low, high)) / 2)
+}
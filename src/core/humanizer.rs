@@ -0,0 +1,140 @@
+use crate::types::{edelay_distribution::EDelayDistribution, ehumanizer_preset::EHumanizerPreset};
+use crate::utils::{config, random};
+use rand::rngs::StdRng;
+use std::time::Duration;
+
+// Paths shorter than this never overshoot, a one tile overshoot on a short hop looks odd
+const OVERSHOOT_MIN_PATH: usize = 8;
+
+pub struct Profile {
+    // Jitter applied around the configured delay, in percent of it
+    pub jitter_percent: u64,
+    pub delay_percent: u64,
+    pub micro_pause_chance: f64,
+    pub overshoot_chance: f64,
+}
+
+impl EHumanizerPreset {
+    pub fn profile(&self) -> Profile {
+        match self {
+            EHumanizerPreset::Off => Profile {
+                jitter_percent: 0,
+                delay_percent: 100,
+                micro_pause_chance: 0.0,
+                overshoot_chance: 0.0,
+            },
+            EHumanizerPreset::Fast => Profile {
+                jitter_percent: 10,
+                delay_percent: 100,
+                micro_pause_chance: 0.0,
+                overshoot_chance: 0.0,
+            },
+            EHumanizerPreset::Balanced => Profile {
+                jitter_percent: 25,
+                delay_percent: 120,
+                micro_pause_chance: 0.02,
+                overshoot_chance: 0.1,
+            },
+            EHumanizerPreset::Stealthy => Profile {
+                jitter_percent: 40,
+                delay_percent: 160,
+                micro_pause_chance: 0.05,
+                overshoot_chance: 0.25,
+            },
+        }
+    }
+}
+
+pub struct Humanizer {
+    pub preset: EHumanizerPreset,
+    // 0 when the RNG was seeded from entropy
+    pub seed: u64,
+    rng: StdRng,
+}
+
+impl Humanizer {
+    pub fn new(preset: EHumanizerPreset, seed: u64) -> Self {
+        Self {
+            preset,
+            seed,
+            rng: random::seeded(Some(seed).filter(|seed| *seed != 0)),
+        }
+    }
+
+    // Starts the delay sequence over from `seed`
+    pub fn reseed(&mut self, seed: u64) {
+        self.seed = seed;
+        self.rng = random::seeded(Some(seed).filter(|seed| *seed != 0));
+    }
+
+    pub fn action_delay(&mut self, base_ms: u64) -> Duration {
+        self.delay(base_ms, config::get_humanizer_distribution())
+    }
+
+    fn delay(&mut self, base_ms: u64, distribution: EDelayDistribution) -> Duration {
+        let profile = self.preset.profile();
+        let mean = base_ms * profile.delay_percent / 100;
+        let jitter = mean * profile.jitter_percent / 100;
+        let mut delay = match distribution {
+            EDelayDistribution::Uniform => random::jitter(&mut self.rng, mean, jitter),
+            EDelayDistribution::Normal => random::normal(&mut self.rng, mean, jitter),
+        };
+        if random::chance(&mut self.rng, profile.micro_pause_chance) {
+            delay += random::jitter(&mut self.rng, 1250, 750);
+        }
+        Duration::from_millis(delay)
+    }
+
+    pub fn should_overshoot(&mut self, path_len: usize) -> bool {
+        if path_len < OVERSHOOT_MIN_PATH {
+            return false;
+        }
+        let chance = self.preset.profile().overshoot_chance;
+        random::chance(&mut self.rng, chance)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn delays(seed: u64, distribution: EDelayDistribution) -> Vec<Duration> {
+        let mut humanizer = Humanizer::new(EHumanizerPreset::Stealthy, seed);
+        (0..50).map(|_| humanizer.delay(200, distribution)).collect()
+    }
+
+    #[test]
+    fn the_same_seed_gives_the_same_delays() {
+        for distribution in EDelayDistribution::ALL {
+            assert_eq!(delays(42, distribution), delays(42, distribution));
+            assert_ne!(delays(42, distribution), delays(43, distribution));
+        }
+    }
+
+    #[test]
+    fn reseeding_starts_the_sequence_over() {
+        let mut humanizer = Humanizer::new(EHumanizerPreset::Balanced, 7);
+        let first: Vec<bool> = (0..50).map(|_| humanizer.should_overshoot(20)).collect();
+        humanizer.reseed(7);
+        let second: Vec<bool> = (0..50).map(|_| humanizer.should_overshoot(20)).collect();
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn normal_delays_stay_within_the_jitter() {
+        // Stealthy stretches 200ms to 320ms with 40% jitter, micro-pauses add 500ms or more
+        for delay in delays(1, EDelayDistribution::Normal) {
+            let ms = delay.as_millis() as u64;
+            assert!((192..=448).contains(&ms) || ms >= 692, "{}ms", ms);
+        }
+    }
+
+    #[test]
+    fn off_keeps_the_configured_delay() {
+        let mut humanizer = Humanizer::new(EHumanizerPreset::Off, 0);
+        for distribution in EDelayDistribution::ALL {
+            assert_eq!(humanizer.delay(250, distribution), Duration::from_millis(250));
+        }
+        assert!(!humanizer.should_overshoot(100));
+    }
+}
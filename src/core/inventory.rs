@@ -40,6 +40,14 @@ impl Inventory {
         }
     }
 
+    // None until the first inventory packet, an empty inventory isn't a full one
+    pub fn free_slots(&self) -> Option<u32> {
+        if self.size == 0 {
+            return None;
+        }
+        Some(self.size.saturating_sub(self.items.len() as u32))
+    }
+
     pub fn reset(&mut self) {
         self.size = 0;
         self.item_count = 0;
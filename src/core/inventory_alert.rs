@@ -0,0 +1,48 @@
+use crate::core::{reactions, Bot};
+use crate::types::bot_event::BotEvent;
+use crate::utils::config;
+use std::sync::Arc;
+
+// Slots that have to free up past the threshold before an alert can fire again,
+// so picking up and dropping a single item doesn't retrigger it
+const HYSTERESIS: u32 = 2;
+
+// Raises InventoryNearlyFull/InventoryFull and runs the bot's configured reactions
+pub fn tick(bot: &Arc<Bot>) {
+    let free_slots = match bot.inventory.lock().unwrap().free_slots() {
+        Some(free_slots) => free_slots,
+        None => return,
+    };
+    let threshold = config::get_inventory_free_threshold();
+
+    let (nearly_full, full) = {
+        let mut temp = bot.temporary_data.write().unwrap();
+        if temp.inventory_nearly_full && free_slots >= threshold + HYSTERESIS {
+            temp.inventory_nearly_full = false;
+        }
+        if temp.inventory_full && free_slots >= HYSTERESIS {
+            temp.inventory_full = false;
+        }
+        let nearly_full = !temp.inventory_nearly_full && free_slots < threshold;
+        let full = !temp.inventory_full && free_slots == 0;
+        temp.inventory_nearly_full |= nearly_full;
+        temp.inventory_full |= full;
+        (nearly_full, full)
+    };
+
+    let reason = if full {
+        bot.emit(BotEvent::InventoryFull);
+        "inventory full".to_string()
+    } else if nearly_full {
+        bot.emit(BotEvent::InventoryNearlyFull { free_slots });
+        format!("inventory nearly full, {} slots free", free_slots)
+    } else {
+        return;
+    };
+    bot.log_warn(&format!("Inventory has {} free slots", free_slots));
+    let username = bot.info.lock().unwrap().payload[0].clone();
+    let reactions = config::get_bot_config(&username)
+        .map(|bot_config| bot_config.inventory_reactions)
+        .unwrap_or_default();
+    reactions::run(bot, reactions, reason);
+}
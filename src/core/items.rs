@@ -0,0 +1,145 @@
+use super::Bot;
+use crate::types::{epacket_type::EPacketType, tank_packet::TankPacket};
+use crate::utils::proton;
+use flate2::read::ZlibDecoder;
+use gtitem_r::structs::ItemDatabase;
+use std::fs;
+use std::io::Read;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::RwLock;
+use std::thread;
+use std::time::{Duration, Instant};
+
+pub const ITEMS_PATH: &str = "items.dat";
+const ITEMS_TEMP_PATH: &str = "items.dat.tmp";
+const DOWNLOAD_ATTEMPTS: u32 = 3;
+const DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(60);
+// Doubled after every failed attempt
+const RETRY_BACKOFF: Duration = Duration::from_secs(5);
+
+// Proton hash of the items.dat currently held by the shared database, 0 when nothing is loaded
+static LOADED_HASH: AtomicU32 = AtomicU32::new(0);
+
+pub fn is_current(server_hash: u32) -> bool {
+    LOADED_HASH.load(Ordering::SeqCst) == server_hash
+}
+
+// Loads items.dat from disk if it exists, called at startup and before falling back to a download
+pub fn load_from_disk(item_database: &RwLock<ItemDatabase>) -> Option<u32> {
+    let data = fs::read(ITEMS_PATH).ok()?;
+    let database = gtitem_r::load_from_memory(&data).ok()?;
+    let hash = proton::hash_bytes(&data);
+    *item_database.write().unwrap() = database;
+    LOADED_HASH.store(hash, Ordering::SeqCst);
+    Some(hash)
+}
+
+// Requests items.dat until the server's version is loaded, another bot installing it counts
+// too. Gives up with the last error after DOWNLOAD_ATTEMPTS or once the bot is stopped
+pub fn download(bot: &Bot, server_hash: u32) -> Result<(), String> {
+    let mut last_error = String::new();
+    for attempt in 0..DOWNLOAD_ATTEMPTS {
+        if attempt > 0 {
+            let backoff = RETRY_BACKOFF * 2u32.pow(attempt - 1);
+            bot.log_warn(&format!(
+                "items.dat download failed ({}), retrying in {}s",
+                last_error,
+                backoff.as_secs()
+            ));
+            thread::sleep(backoff);
+        }
+        request(bot, server_hash);
+        let started = Instant::now();
+        loop {
+            if is_current(server_hash) {
+                return Ok(());
+            }
+            if !bot.state.lock().unwrap().is_running {
+                return Err("bot was stopped".to_string());
+            }
+            if let Some(err) = bot.temporary_data.write().unwrap().items_error.take() {
+                last_error = err;
+                break;
+            }
+            if started.elapsed() > DOWNLOAD_TIMEOUT {
+                last_error = format!("nothing received for {}s", DOWNLOAD_TIMEOUT.as_secs());
+                break;
+            }
+            thread::sleep(Duration::from_millis(100));
+        }
+    }
+    Err(last_error)
+}
+
+fn request(bot: &Bot, server_hash: u32) {
+    bot.log_info("items.dat is missing or outdated, requesting it from the server");
+    bot.set_status("Downloading items.dat");
+    {
+        let mut temp = bot.temporary_data.write().unwrap();
+        temp.items_hash = server_hash;
+        temp.items_download.clear();
+        temp.items_error = None;
+    }
+    bot.send_packet(
+        EPacketType::NetMessageGenericText,
+        "action|refresh_item_data\n".to_string(),
+    );
+}
+
+// The payload may arrive split over several packets, it's buffered until the advertised size is reached
+pub fn handle_data(bot: &Bot, tank_packet: &TankPacket, data: &[u8]) {
+    let expected_size = tank_packet.extended_data_length as usize;
+    let (compressed, expected_hash) = {
+        let mut temp = bot.temporary_data.write().unwrap();
+        temp.items_download.extend_from_slice(data);
+        let received = temp.items_download.len();
+        if received < expected_size {
+            drop(temp);
+            bot.set_status(&format!(
+                "Downloading items.dat ({}/{} KB)",
+                received / 1024,
+                expected_size / 1024
+            ));
+            return;
+        }
+        (std::mem::take(&mut temp.items_download), temp.items_hash)
+    };
+
+    bot.set_status("Verifying items.dat");
+    match install(bot, &compressed, expected_hash) {
+        Ok(()) => {
+            bot.log_info("items.dat downloaded and loaded");
+            bot.set_login_status("Connected");
+        }
+        Err(err) => {
+            bot.log_error(&format!("Failed to install items.dat: {}", err));
+            bot.set_status("items.dat download failed");
+            bot.temporary_data.write().unwrap().items_error = Some(err);
+        }
+    }
+}
+
+fn install(bot: &Bot, compressed: &[u8], expected_hash: u32) -> Result<(), String> {
+    let mut data = Vec::new();
+    ZlibDecoder::new(compressed)
+        .read_to_end(&mut data)
+        .map_err(|err| format!("decompression failed: {}", err))?;
+
+    let hash = proton::hash_bytes(&data);
+    if expected_hash != 0 && hash != expected_hash {
+        return Err(format!(
+            "hash mismatch, expected {} got {}",
+            expected_hash, hash
+        ));
+    }
+    let database =
+        gtitem_r::load_from_memory(&data).map_err(|err| format!("parse failed: {:?}", err))?;
+
+    // Only replace the existing file once the download is known to be good
+    fs::write(ITEMS_TEMP_PATH, &data).map_err(|err| err.to_string())?;
+    fs::rename(ITEMS_TEMP_PATH, ITEMS_PATH).map_err(|err| err.to_string())?;
+
+    *bot.item_database.write().unwrap() = database;
+    LOADED_HASH.store(hash, Ordering::SeqCst);
+    Ok(())
+}
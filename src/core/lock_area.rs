@@ -0,0 +1,171 @@
+use gtworld_r::{TileType, World};
+use std::collections::{BTreeSet, HashSet};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LockSize {
+    #[default]
+    Small,
+    Big,
+    Huge,
+}
+
+impl LockSize {
+    pub const ALL: [LockSize; 3] = [LockSize::Small, LockSize::Big, LockSize::Huge];
+
+    pub fn item_id(&self) -> u32 {
+        match self {
+            LockSize::Small => 202,
+            LockSize::Big => 204,
+            LockSize::Huge => 206,
+        }
+    }
+
+    // Tiles protected, the lock's own tile included
+    pub fn tiles(&self) -> usize {
+        match self {
+            LockSize::Small => 10,
+            LockSize::Big => 48,
+            LockSize::Huge => 200,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            LockSize::Small => "Small Lock",
+            LockSize::Big => "Big Lock",
+            LockSize::Huge => "Huge Lock",
+        }
+    }
+
+    pub fn from_item_id(item_id: u32) -> Option<LockSize> {
+        LockSize::ALL.into_iter().find(|size| size.item_id() == item_id)
+    }
+}
+
+// Tiles a lock placed at `origin` would protect. Like the game it grows outwards through
+// neighbouring tiles, nearest to the lock first, and never into a tile `blocked` reports as
+// owned by another lock. Near the edge of the world it simply ends up lopsided
+pub fn coverage(
+    width: u32,
+    height: u32,
+    origin: (u32, u32),
+    size: LockSize,
+    blocked: impl Fn(u32, u32) -> bool,
+) -> Vec<(u32, u32)> {
+    let (origin_x, origin_y) = origin;
+    if origin_x >= width || origin_y >= height {
+        return Vec::new();
+    }
+    let distance = |x: u32, y: u32| {
+        let (dx, dy) = (x as i64 - origin_x as i64, y as i64 - origin_y as i64);
+        dx * dx + dy * dy
+    };
+
+    let mut covered = vec![origin];
+    let mut seen: HashSet<(u32, u32)> = HashSet::from([origin]);
+    // Ordered by distance, then row and column so ties always resolve the same way
+    let mut frontier: BTreeSet<(i64, u32, u32)> = BTreeSet::new();
+    let expand = |x: u32, y: u32, seen: &mut HashSet<(u32, u32)>, frontier: &mut BTreeSet<(i64, u32, u32)>| {
+        let neighbours = [
+            (x as i64 - 1, y as i64),
+            (x as i64 + 1, y as i64),
+            (x as i64, y as i64 - 1),
+            (x as i64, y as i64 + 1),
+        ];
+        for (nx, ny) in neighbours {
+            if nx < 0 || ny < 0 || nx >= width as i64 || ny >= height as i64 {
+                continue;
+            }
+            let (nx, ny) = (nx as u32, ny as u32);
+            if seen.insert((nx, ny)) && !blocked(nx, ny) {
+                frontier.insert((distance(nx, ny), ny, nx));
+            }
+        }
+    };
+    expand(origin_x, origin_y, &mut seen, &mut frontier);
+    while covered.len() < size.tiles() {
+        let Some((_, y, x)) = frontier.pop_first() else {
+            break;
+        };
+        covered.push((x, y));
+        expand(x, y, &mut seen, &mut frontier);
+    }
+    covered
+}
+
+// Tiles already protected by the area locks in `world`, worked out the same way
+pub fn claimed(world: &World) -> HashSet<(u32, u32)> {
+    let mut claimed = HashSet::new();
+    let locks: Vec<(u32, u32, LockSize)> = world
+        .tiles
+        .iter()
+        .filter(|tile| matches!(tile.tile_type, TileType::Lock { .. }))
+        .filter_map(|tile| {
+            LockSize::from_item_id(tile.foreground_item_id as u32).map(|size| (tile.x, tile.y, size))
+        })
+        .collect();
+    // Other locks' own tiles are never covered, whatever order they were placed in
+    let lock_tiles: HashSet<(u32, u32)> = locks.iter().map(|&(x, y, _)| (x, y)).collect();
+    for &(x, y, size) in &locks {
+        let tiles = coverage(world.width, world.height, (x, y), size, |tile_x, tile_y| {
+            claimed.contains(&(tile_x, tile_y)) || lock_tiles.contains(&(tile_x, tile_y))
+        });
+        claimed.extend(tiles);
+    }
+    claimed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn open(_: u32, _: u32) -> bool {
+        false
+    }
+
+    #[test]
+    fn covers_the_size_of_each_lock() {
+        for size in LockSize::ALL {
+            let tiles = coverage(100, 60, (50, 30), size, open);
+            assert_eq!(tiles.len(), size.tiles(), "{}", size.name());
+            assert_eq!(tiles[0], (50, 30));
+            let unique: HashSet<(u32, u32)> = tiles.iter().copied().collect();
+            assert_eq!(unique.len(), tiles.len());
+        }
+    }
+
+    #[test]
+    fn grows_nearest_first() {
+        let tiles = coverage(100, 60, (50, 30), LockSize::Small, open);
+        // The four neighbours come before any diagonal
+        let mut first: Vec<(u32, u32)> = tiles[1..5].to_vec();
+        first.sort();
+        assert_eq!(first, vec![(49, 30), (50, 29), (50, 31), (51, 30)]);
+        assert!(tiles.iter().all(|&(x, y)| x.abs_diff(50) <= 2 && y.abs_diff(30) <= 2));
+    }
+
+    #[test]
+    fn clips_at_the_edge_of_the_world() {
+        for size in LockSize::ALL {
+            let tiles = coverage(100, 60, (0, 0), size, open);
+            assert_eq!(tiles.len(), size.tiles(), "{}", size.name());
+            assert!(tiles.iter().all(|&(x, y)| x < 100 && y < 60));
+        }
+        // A world too small to hold the whole area gets every tile it has
+        let tiles = coverage(4, 3, (3, 2), LockSize::Big, open);
+        assert_eq!(tiles.len(), 12);
+    }
+
+    #[test]
+    fn stays_out_of_blocked_tiles() {
+        // A wall right of the lock, the area only grows left
+        let tiles = coverage(100, 60, (50, 30), LockSize::Huge, |x, _| x == 51);
+        assert_eq!(tiles.len(), LockSize::Huge.tiles());
+        assert!(tiles.iter().all(|&(x, _)| x <= 50));
+    }
+
+    #[test]
+    fn origin_outside_the_world_covers_nothing() {
+        assert!(coverage(10, 10, (10, 3), LockSize::Small, open).is_empty());
+    }
+}
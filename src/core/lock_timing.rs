@@ -0,0 +1,234 @@
+// Drop-in Mutex/RwLock wrappers for the contended Bot fields. With the `lock_timing`
+// feature every blocking acquire records its wait against the caller's file:line and
+// slow waits are logged, without it these are plain re-exports of the std locks
+#[cfg(not(feature = "lock_timing"))]
+pub use std::sync::{Mutex as TimedMutex, RwLock as TimedRwLock};
+
+#[cfg(feature = "lock_timing")]
+pub use timed::{TimedMutex, TimedRwLock};
+
+#[derive(Debug, Clone)]
+pub struct Contention {
+    pub site: String,
+    pub count: u64,
+    pub total: std::time::Duration,
+    pub max: std::time::Duration,
+}
+
+// Call sites with the most total wait first, empty without the feature
+pub fn report() -> Vec<Contention> {
+    #[cfg(feature = "lock_timing")]
+    return timed::report();
+    #[cfg(not(feature = "lock_timing"))]
+    Vec::new()
+}
+
+pub fn reset() {
+    #[cfg(feature = "lock_timing")]
+    timed::reset();
+}
+
+#[cfg(feature = "lock_timing")]
+mod timed {
+    use super::Contention;
+    use paris::warn;
+    use std::collections::HashMap;
+    use std::panic::Location;
+    use std::sync::{
+        LockResult, Mutex, MutexGuard, RwLock, RwLockReadGuard, RwLockWriteGuard,
+        TryLockResult,
+    };
+    use std::time::{Duration, Instant};
+
+    const SLOW_WAIT: Duration = Duration::from_millis(100);
+
+    #[derive(Default)]
+    struct SiteStats {
+        count: u64,
+        total: Duration,
+        max: Duration,
+    }
+
+    static SITES: Mutex<Option<HashMap<&'static Location<'static>, SiteStats>>> =
+        Mutex::new(None);
+
+    fn record(site: &'static Location<'static>, waited: Duration) {
+        if waited >= SLOW_WAIT {
+            warn!("Waited {:.1?} for a lock at {}", waited, site);
+        }
+        let mut sites = SITES.lock().unwrap();
+        let stats = sites.get_or_insert_with(HashMap::new).entry(site).or_default();
+        stats.count += 1;
+        stats.total += waited;
+        stats.max = stats.max.max(waited);
+    }
+
+    pub fn report() -> Vec<Contention> {
+        let sites = SITES.lock().unwrap();
+        let mut report: Vec<Contention> = sites
+            .iter()
+            .flatten()
+            .map(|(site, stats)| Contention {
+                site: site.to_string(),
+                count: stats.count,
+                total: stats.total,
+                max: stats.max,
+            })
+            .collect();
+        report.sort_by(|a, b| b.total.cmp(&a.total));
+        report
+    }
+
+    pub fn reset() {
+        *SITES.lock().unwrap() = None;
+    }
+
+    #[derive(Debug, Default)]
+    pub struct TimedMutex<T>(Mutex<T>);
+
+    impl<T> TimedMutex<T> {
+        pub fn new(value: T) -> Self {
+            TimedMutex(Mutex::new(value))
+        }
+
+        #[track_caller]
+        pub fn lock(&self) -> LockResult<MutexGuard<'_, T>> {
+            let site = Location::caller();
+            let started = Instant::now();
+            let guard = self.0.lock();
+            record(site, started.elapsed());
+            guard
+        }
+
+        pub fn try_lock(&self) -> TryLockResult<MutexGuard<'_, T>> {
+            self.0.try_lock()
+        }
+    }
+
+    #[derive(Debug, Default)]
+    pub struct TimedRwLock<T>(RwLock<T>);
+
+    impl<T> TimedRwLock<T> {
+        pub fn new(value: T) -> Self {
+            TimedRwLock(RwLock::new(value))
+        }
+
+        #[track_caller]
+        pub fn read(&self) -> LockResult<RwLockReadGuard<'_, T>> {
+            let site = Location::caller();
+            let started = Instant::now();
+            let guard = self.0.read();
+            record(site, started.elapsed());
+            guard
+        }
+
+        #[track_caller]
+        pub fn write(&self) -> LockResult<RwLockWriteGuard<'_, T>> {
+            let site = Location::caller();
+            let started = Instant::now();
+            let guard = self.0.write();
+            record(site, started.elapsed());
+            guard
+        }
+
+        pub fn try_read(&self) -> TryLockResult<RwLockReadGuard<'_, T>> {
+            self.0.try_read()
+        }
+
+        pub fn try_write(&self) -> TryLockResult<RwLockWriteGuard<'_, T>> {
+            self.0.try_write()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+    use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    const SENDERS: usize = 4;
+    const SENDS: usize = 2000;
+
+    // Stands in for the event loop: holds `host` for each service() call, roughly as long
+    // as a busy world takes, and drains the outgoing queue under it
+    fn event_loop(host: Arc<Mutex<()>>, queue: Arc<Mutex<VecDeque<u32>>>, stop: Arc<AtomicBool>) {
+        while !stop.load(Ordering::Relaxed) {
+            let guard = host.lock().unwrap();
+            queue.lock().unwrap().clear();
+            let started = Instant::now();
+            while started.elapsed() < Duration::from_micros(300) {}
+            drop(guard);
+            thread::sleep(Duration::from_micros(50));
+        }
+    }
+
+    fn senders(send: impl Fn() + Send + Sync + 'static) {
+        let send = Arc::new(send);
+        let handles: Vec<_> = (0..SENDERS)
+            .map(|_| {
+                let send = send.clone();
+                thread::spawn(move || {
+                    for _ in 0..SENDS {
+                        send();
+                        thread::sleep(Duration::from_micros(100));
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+    }
+
+    // cargo test -- --ignored --nocapture host_contention
+    // Sending by try_lock on `host` against queueing for the event loop
+    #[test]
+    #[ignore]
+    fn host_contention() {
+        let host = Arc::new(Mutex::new(()));
+        let queue = Arc::new(Mutex::new(VecDeque::new()));
+        let stop = Arc::new(AtomicBool::new(false));
+        let looping = {
+            let (host, queue, stop) = (host.clone(), queue.clone(), stop.clone());
+            thread::spawn(move || event_loop(host, queue, stop))
+        };
+
+        let dropped = Arc::new(AtomicU64::new(0));
+        {
+            let (host, dropped) = (host.clone(), dropped.clone());
+            senders(move || {
+                if host.try_lock().is_err() {
+                    dropped.fetch_add(1, Ordering::Relaxed);
+                }
+            });
+        }
+
+        let waited = Arc::new(Mutex::new((Duration::ZERO, Duration::ZERO)));
+        {
+            let (queue, waited) = (queue.clone(), waited.clone());
+            senders(move || {
+                let started = Instant::now();
+                queue.lock().unwrap().push_back(0);
+                let wait = started.elapsed();
+                let mut waited = waited.lock().unwrap();
+                waited.0 += wait;
+                waited.1 = waited.1.max(wait);
+            });
+        }
+        stop.store(true, Ordering::Relaxed);
+        looping.join().unwrap();
+
+        let total = (SENDERS * SENDS) as u64;
+        let (sum, max) = *waited.lock().unwrap();
+        println!(
+            "try_lock on host: {}/{} sends dropped ({:.1}%)",
+            dropped.load(Ordering::Relaxed),
+            total,
+            dropped.load(Ordering::Relaxed) as f64 * 100.0 / total as f64
+        );
+        println!("queue: 0 dropped, average wait {:?}, max {:?}", sum / total as u32, max);
+    }
+}
@@ -1,4 +1,6 @@
 use crate::core::Bot;
+use crate::types::bot_event::BotEvent;
+use crate::types::esteam_guard::ESteamGuard;
 use crate::utils;
 use crate::utils::error;
 use base64::engine::general_purpose;
@@ -7,9 +9,10 @@ use egui::TextBuffer;
 use paris::{error, info, warn};
 use regex::Regex;
 use serde_json::Value;
+use std::collections::HashMap;
 use std::process::Stdio;
 use std::sync::Arc;
-use std::{env, io, process::Command, time::Duration};
+use std::{env, fs, io, path::Path, process::Command, thread, time::Duration};
 use ureq::Agent;
 use urlencoding::encode;
 use wait_timeout::ChildExt;
@@ -88,13 +91,9 @@ pub fn get_ubisoft_game_token(agent: &Agent, token: &str) -> Result<(String, Str
     ))
 }
 
-pub fn get_ubisoft_session(
-    agent: &Agent,
-    bot: &Bot,
-    email: &str,
-    password: &str,
-    recovery_code: &str,
-) -> Result<(String, String), ureq::Error> {
+// Email and password step of the Ubisoft login, a 401 means the credentials are wrong.
+// The response holds either the session ticket or a 2FA ticket
+pub fn post_ubisoft_session(agent: &Agent, email: &str, password: &str) -> Result<Value, ureq::Error> {
     let encoded = general_purpose::STANDARD.encode(format!("{}:{}", email, password));
     let body = agent
         .post("https://public-ubiservices.ubi.com/v3/profiles/sessions")
@@ -106,8 +105,17 @@ pub fn get_ubisoft_session(
         .send_json(ureq::json!({
             "rememberMe": true,
         }))?;
+    Ok(body.into_json()?)
+}
 
-    let json: Value = body.into_json()?;
+pub fn get_ubisoft_session(
+    agent: &Agent,
+    bot: &Bot,
+    email: &str,
+    password: &str,
+    recovery_code: &str,
+) -> Result<(String, String), ureq::Error> {
+    let json = post_ubisoft_session(agent, email, password)?;
     if json.get("twoFactorAuthenticationTicket").is_some() {
         let ticket = &json["twoFactorAuthenticationTicket"]
             .as_str()
@@ -117,12 +125,9 @@ pub fn get_ubisoft_session(
         match post_ubisoft_2fa_ticket(&agent, ticket, &token.to_string()) {
             Ok(res) => Ok(res),
             Err(err) => {
-                {
-                    let mut state = bot.state.lock().expect("Failed to lock state");
-                    let mut info = bot.info.lock().expect("Failed to lock info");
-                    state.is_running = false;
-                    info.status = "2FA Failed".to_string();
-                }
+                bot.state.lock().expect("Failed to lock state").is_running = false;
+                bot.set_login_status("2FA Failed");
+                bot.emit(BotEvent::TwoFactorFailed);
                 Err(err)
             }
         }
@@ -170,6 +175,7 @@ pub fn get_ubisoft_token(
     password: &str,
     steamuser: &str,
     steampassword: &str,
+    steam_shared_secret: &str,
 ) -> Result<String, error::CustomError> {
     let info = {
         let data = bot.info.lock().unwrap().login_info.to_string();
@@ -182,7 +188,7 @@ pub fn get_ubisoft_token(
             Err(err) => {
                 if err.to_string().contains("code 401") {
                     bot.state.lock().unwrap().is_running = false;
-                    bot.info.lock().unwrap().status = "Unauthorized".to_string();
+                    bot.set_login_status("Unauthorized");
                 }
                 return Err(error::CustomError::Other(format!(
                     "Failed to get ubisoft session: {}",
@@ -191,6 +197,149 @@ pub fn get_ubisoft_token(
             }
         };
 
+    let data = get_steam_ticket(bot, steamuser, steampassword, steam_shared_secret)?;
+    let steam_token = utils::textparse::format_string_as_steam_token(&data[0]);
+
+    let formated = encode(format!("UbiTicket|{}{}\n", &session, info).as_str()).to_string();
+    let body = agent
+        .post("https://login.growtopiagame.com/player/login/dashboard?valKey=40db4045f2d8c572efe8c4a060605726")
+        .set("user-agent", USER_AGENT)
+        .send_string(format!("{}steamToken%7C{}.240", formated, steam_token.trim_end()).as_str())?;
+
+    let body_str = body.into_string().unwrap();
+    let json: Value = match serde_json::from_str(&body_str) {
+        Ok(json) => json,
+        Err(err) => {
+            return Err(error::CustomError::Other(format!(
+                "Failed to parse json: {}",
+                err
+            )));
+        }
+    };
+
+    if json.get("message").is_some() {
+        let message = json["message"].as_str().unwrap().to_string();
+        if message.contains("Please try login with Steam account connected with Ubisoft Connect.") {
+            warn!("Linking Ubisoft to Steam");
+            let steam_ticket = data.get(1).map(String::as_str).unwrap_or_default();
+            return match link_ubisoft_to_steam(&agent, &session, &profile_id, steam_ticket) {
+                Ok(_token) => {
+                    info!("Successfully linked Ubisoft to Steam");
+                    Ok("".to_string())
+                }
+                Err(err) => Err(error::CustomError::Other(format!(
+                    "Failed to link Ubisoft to Steam: {}",
+                    err
+                ))),
+            };
+        }
+    }
+    Ok(json["token"].to_string())
+}
+
+// steamtoken prints one of these instead of the ticket when Steam Guard stops the login
+const STEAM_GUARD_EMAIL: &str = "STEAM_GUARD_EMAIL";
+const STEAM_GUARD_MOBILE: &str = "STEAM_GUARD_MOBILE";
+const STEAM_GUARD_INVALID: &str = "STEAM_GUARD_INVALID";
+
+// Returns steamtoken's output lines: the app ticket followed by the auth ticket.
+// A Guard challenge is answered with a generated code when a shared secret is set,
+// otherwise the login waits on Bot::submit_2fa. A rejected code asks again
+fn get_steam_ticket(
+    bot: &Bot,
+    steamuser: &str,
+    steampassword: &str,
+    shared_secret: &str,
+) -> Result<Vec<String>, error::CustomError> {
+    let session_path = steam_session_path(steamuser);
+    // Only once Guard got involved, so accounts without it keep the plain invocation
+    let mut use_session = Path::new(&session_path).exists();
+    let mut guard_code: Option<String> = None;
+    let mut challenge = ESteamGuard::Mobile;
+    let mut generated = 0;
+
+    loop {
+        let mut args = vec![
+            "866020".to_string(),
+            steamuser.to_string(),
+            steampassword.to_string(),
+        ];
+        if use_session {
+            args.push("--session".to_string());
+            args.push(session_path.clone());
+        }
+        if let Some(code) = guard_code.take() {
+            args.push("--guard".to_string());
+            args.push(code);
+        }
+
+        let output = run_steamtoken(&args);
+        let lines: Vec<String> = output.split("\n").map(|line| line.to_string()).collect();
+        match lines[0].trim() {
+            STEAM_GUARD_EMAIL => challenge = ESteamGuard::Email,
+            STEAM_GUARD_MOBILE => challenge = ESteamGuard::Mobile,
+            STEAM_GUARD_INVALID => bot.log_warn("Steam Guard code was rejected"),
+            _ => {
+                bot.temporary_data.write().unwrap().steam_guard = None;
+                return Ok(lines);
+            }
+        }
+        if !use_session {
+            let _ = fs::create_dir_all("sessions");
+            use_session = true;
+        }
+
+        // The second generated code is for the previous window in case our clock runs ahead
+        if challenge == ESteamGuard::Mobile && !shared_secret.is_empty() && generated < 2 {
+            let now = utils::date::now_secs();
+            let timestamp = if generated == 0 { now } else { now.saturating_sub(30) };
+            generated += 1;
+            match utils::steam_totp::generate(shared_secret, timestamp) {
+                Ok(code) => {
+                    bot.log_info("Answering Steam Guard with a generated code");
+                    guard_code = Some(code);
+                    continue;
+                }
+                Err(err) => {
+                    bot.log_error(&format!("Failed to generate Steam Guard code: {}", err));
+                    generated = 2;
+                }
+            }
+        }
+        guard_code = Some(wait_for_guard_code(bot, challenge)?);
+    }
+}
+
+fn wait_for_guard_code(bot: &Bot, challenge: ESteamGuard) -> Result<String, error::CustomError> {
+    {
+        let mut temp = bot.temporary_data.write().unwrap();
+        temp.steam_guard = Some(challenge);
+        temp.steam_guard_code = None;
+    }
+    bot.set_login_status("TwoFactorRequired");
+    bot.log_warn(&format!("Steam Guard is asking for the {}", challenge.describe()));
+
+    loop {
+        if !bot.state.lock().unwrap().is_running {
+            bot.temporary_data.write().unwrap().steam_guard = None;
+            return Err(error::CustomError::SteamError(
+                "login stopped while waiting for a Steam Guard code".to_string(),
+            ));
+        }
+        if let Some(code) = bot.temporary_data.write().unwrap().steam_guard_code.take() {
+            bot.set_login_status("Getting token");
+            return Ok(code);
+        }
+        thread::sleep(Duration::from_millis(500));
+    }
+}
+
+// steamtoken keeps its refresh token here so Guard isn't asked for again on relog
+fn steam_session_path(steamuser: &str) -> String {
+    format!("sessions/{}.steam", steamuser.to_lowercase())
+}
+
+fn run_steamtoken(args: &[String]) -> String {
     let current_dir = env::current_dir().expect("Failed to get current directory");
     let executable_path = if cfg!(windows) {
         current_dir.join("steamtoken.exe")
@@ -201,63 +350,17 @@ pub fn get_ubisoft_token(
 
     loop {
         let mut child = Command::new(&executable_path)
-            .arg("866020")
-            .arg(steamuser)
-            .arg(steampassword)
+            .args(args)
             .stdout(Stdio::piped())
             .spawn()
             .expect("Failed to execute steamtoken");
 
         match child.wait_timeout(timeout).unwrap() {
             Some(status) => {
-                if status.success() {
-                    let output = child.wait_with_output().expect("Failed to read output");
-                    let output_str = String::from_utf8_lossy(&output.stdout);
-                    let data = output_str.split("\n").collect::<Vec<&str>>();
-                    let steam_token = utils::textparse::format_string_as_steam_token(&data[0]);
-
-                    let formated =
-                        encode(format!("UbiTicket|{}{}\n", &session, info).as_str()).to_string();
-                    let body = agent
-                        .post("https://login.growtopiagame.com/player/login/dashboard?valKey=40db4045f2d8c572efe8c4a060605726")
-                        .set("user-agent", USER_AGENT)
-                        .send_string(format!("{}steamToken%7C{}.240", formated, steam_token.trim_end()).as_str())?;
-
-                    let body_str = body.into_string().unwrap();
-                    let json: Value = match serde_json::from_str(&body_str) {
-                        Ok(json) => json,
-                        Err(err) => {
-                            return Err(error::CustomError::Other(format!(
-                                "Failed to parse json: {}",
-                                err
-                            )));
-                        }
-                    };
-
-                    if json.get("message").is_some() {
-                        let message = json["message"].as_str().unwrap().to_string();
-                        if message.contains(
-                            "Please try login with Steam account connected with Ubisoft Connect.",
-                        ) {
-                            warn!("Linking Ubisoft to Steam");
-                            return match link_ubisoft_to_steam(
-                                &agent,
-                                &session,
-                                &profile_id,
-                                &data[1],
-                            ) {
-                                Ok(_token) => {
-                                    info!("Successfully linked Ubisoft to Steam");
-                                    Ok("".to_string())
-                                }
-                                Err(err) => Err(error::CustomError::Other(format!(
-                                    "Failed to link Ubisoft to Steam: {}",
-                                    err
-                                ))),
-                            };
-                        }
-                    }
-                    return Ok(json["token"].to_string());
+                let output = child.wait_with_output().expect("Failed to read output");
+                let output_str = String::from_utf8_lossy(&output.stdout).to_string();
+                if status.success() || output_str.starts_with("STEAM_GUARD_") {
+                    return output_str;
                 }
             }
             None => {
@@ -291,11 +394,22 @@ pub fn get_apple_token(url: &str) -> Result<String, io::Error> {
     Ok(buffer)
 }
 
+// One attempt through the local Google login helper
+pub fn request_google_token(
+    agent: &Agent,
+    url: &str,
+    username: &str,
+    password: &str,
+) -> Result<ureq::Response, ureq::Error> {
+    agent
+        .post("http://localhost:5123/token")
+        .send_form(&[("url", url), ("email", username), ("password", password)])
+}
+
 pub fn get_google_token(url: &str, username: &str, password: &str) -> Result<String, ureq::Error> {
+    let agent = ureq::AgentBuilder::new().timeout(Duration::from_secs(60)).build();
     loop {
-        let response = ureq::post("http://localhost:5123/token")
-            .timeout(Duration::from_secs(60))
-            .send_form(&[("url", url), ("email", username), ("password", password)]);
+        let response = request_google_token(&agent, url, username, password);
 
         match response {
             Ok(res) => {
@@ -314,30 +428,80 @@ pub fn get_google_token(url: &str, username: &str, password: &str) -> Result<Str
     }
 }
 
-pub fn get_legacy_token(url: &str, username: &str, password: &str) -> Result<String, ureq::Error> {
-    let agent = ureq::AgentBuilder::new().build();
+// Submits the GrowID form and returns the validate response as is, it carries a `token`
+// on success and a `message` otherwise
+pub fn legacy_login(
+    agent: &Agent,
+    url: &str,
+    username: &str,
+    password: &str,
+) -> Result<Value, error::CustomError> {
     let body = agent
         .get(url)
         .set("User-Agent", USER_AGENT)
         .call()?
-        .into_string()?;
+        .into_string()
+        .map_err(|err| error::CustomError::Other(err.to_string()))?;
 
-    let token = match extract_token_from_html(&body) {
-        Some(token) => token,
-        None => panic!("Failed to extract token"),
-    };
+    let token = extract_token_from_html(&body)
+        .ok_or(error::CustomError::Other("No login form on the GrowID page".to_string()))?;
 
-    let req = agent
+    let body = agent
         .post("https://login.growtopiagame.com/player/growid/login/validate")
         .send_form(&[
             ("_token", &token),
             ("growId", &username),
             ("password", &password),
-        ])?;
+        ])?
+        .into_string()
+        .map_err(|err| error::CustomError::Other(err.to_string()))?;
+    serde_json::from_str(&body).map_err(|err| error::CustomError::Other(err.to_string()))
+}
 
-    let body = req.into_string()?;
-    let json: Value = serde_json::from_str(&body).unwrap();
-    Ok(json["token"].as_str().unwrap().to_string())
+pub fn get_legacy_token(url: &str, username: &str, password: &str) -> Result<String, error::CustomError> {
+    let agent = ureq::AgentBuilder::new().build();
+    let json = legacy_login(&agent, url, username, password)?;
+    json["token"]
+        .as_str()
+        .map(|token| token.to_string())
+        .ok_or_else(|| {
+            error::CustomError::Other(json["message"].as_str().unwrap_or("No token").to_string())
+        })
+}
+
+pub fn server_data_url() -> &'static str {
+    if utils::config::get_use_alternate_server() {
+        "https://www.growtopia2.com/growtopia/server_data.php"
+    } else {
+        "https://www.growtopia1.com/growtopia/server_data.php"
+    }
+}
+
+pub fn request_server_data(agent: &Agent) -> Result<ureq::Response, ureq::Error> {
+    agent
+        .post(server_data_url())
+        .set("User-Agent", "UbiServices_SDK_2022.Release.9_PC64_ansi_static")
+        .send_string("")
+}
+
+pub fn parse_server_data(data: &str) -> HashMap<String, String> {
+    data.lines()
+        .filter_map(|line| {
+            let mut parts = line.splitn(2, '|');
+            match (parts.next(), parts.next()) {
+                (Some(key), Some(value)) => Some((key.to_string(), value.to_string())),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+// `login_info` is the url-encoded LoginInfo, the page lists the OAuth links for it
+pub fn request_dashboard(agent: &Agent, login_info: &str) -> Result<ureq::Response, ureq::Error> {
+    agent
+        .post("https://login.growtopiagame.com/player/login/dashboard")
+        .set("User-Agent", USER_AGENT)
+        .send_string(login_info)
 }
 
 pub fn extract_token_from_html(body: &str) -> Option<String> {
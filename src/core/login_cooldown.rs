@@ -0,0 +1,65 @@
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+// Minimum time between an account check and any other token request for the same account
+const IDENTITY_GAP: Duration = Duration::from_secs(10);
+// Account checks all leave from this machine's address, so they are spaced out as well
+const GLOBAL_GAP: Duration = Duration::from_millis(500);
+
+struct Registry {
+    identities: BTreeMap<String, Instant>,
+    global: Option<Instant>,
+}
+
+static REGISTRY: Mutex<Registry> = Mutex::new(Registry {
+    identities: BTreeMap::new(),
+    global: None,
+});
+
+// Records a bot's own token request without waiting. Bots log in at their own pace, the
+// entry only keeps an account check from hitting the same account right after
+pub fn note_login(identity: &str) {
+    REGISTRY
+        .lock()
+        .unwrap()
+        .identities
+        .insert(identity.to_string(), Instant::now() + IDENTITY_GAP);
+}
+
+// Blocks until an account check for `identity` may start and books the slot for it
+pub fn wait_turn(identity: &str) {
+    loop {
+        let wait = {
+            let mut registry = REGISTRY.lock().unwrap();
+            let now = Instant::now();
+            let ready = registry
+                .identities
+                .get(identity)
+                .copied()
+                .into_iter()
+                .chain(registry.global)
+                .max()
+                .unwrap_or(now);
+            if ready <= now {
+                registry
+                    .identities
+                    .insert(identity.to_string(), now + IDENTITY_GAP);
+                registry.global = Some(now + GLOBAL_GAP);
+                return;
+            }
+            ready - now
+        };
+        thread::sleep(wait);
+    }
+}
+
+// Called when the login servers answered with a rate limit, holds back every account check
+pub fn back_off(duration: Duration) {
+    let mut registry = REGISTRY.lock().unwrap();
+    let until = Instant::now() + duration;
+    if registry.global.map_or(true, |global| global < until) {
+        registry.global = Some(until);
+    }
+}
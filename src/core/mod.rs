@@ -1,70 +1,216 @@
-mod astar;
+pub mod activation;
+pub mod astar;
+pub mod bandwidth;
+pub mod calibration;
+mod channel;
+mod character_state;
+mod chat;
+mod collect;
+mod console;
+pub mod crash;
+mod desync;
+mod disconnect;
+mod dispatch;
+pub mod drop_age;
+mod effects;
 pub mod features;
+pub mod hazard;
+mod heartbeat;
+mod humanizer;
 mod inventory;
-mod login;
+mod inventory_alert;
+pub mod items;
+pub mod lock_area;
+pub mod lock_timing;
+pub mod login;
+pub mod login_cooldown;
+pub mod oauth;
+mod onboarding;
+pub mod outgoing;
 mod packet_handler;
+mod particles;
+pub mod profiler;
 mod proxy;
+mod quest;
+pub mod reactions;
+pub mod script_guard;
+mod server_cache;
+pub mod storage;
+pub mod supervisor;
+mod telemetry;
+pub mod timeline;
+mod timer_wheel;
+pub mod value_table;
 mod variant_handler;
-
-use astar::AStar;
+mod version;
+mod watchdog;
+mod whisper;
+pub mod world_diff;
+mod world_password;
+pub mod world_snapshot;
+mod world_summary;
+
+use astar::{AStar, PathError, PathHandle};
+use bandwidth::Bandwidth;
+use chat::Chat;
+use console::Console;
+use dispatch::{Dispatch, Incoming, Pushed};
+use humanizer::Humanizer;
+use heartbeat::Heartbeat;
+use watchdog::{Stall, Watchdog};
 use byteorder::{ByteOrder, LittleEndian};
 use gtitem_r::structs::ItemDatabase;
 use inventory::Inventory;
+use lock_timing::{TimedMutex, TimedRwLock};
+use outgoing::Outgoing;
+use storage::Storage;
+use timeline::Timeline;
 use mlua::prelude::*;
 use rusty_enet as enet;
 use socks::Socks5Datagram;
-use std::collections::HashMap;
-use std::mem::size_of;
-use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4, UdpSocket};
+use std::collections::{HashMap, VecDeque};
+use std::net::{SocketAddr, UdpSocket};
 use std::str::{self, FromStr};
 use std::sync::mpsc::Sender;
-use std::sync::{Arc, Mutex, RwLock};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicUsize};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, RwLock};
+use std::time::Instant;
 use std::{thread, time::Duration, vec};
 use urlencoding::encode;
 
-use crate::core::proxy::{SocketType, Socks5UdpSocket};
-use crate::manager::proxy_manager::ProxyManager;
-use crate::types::bot_info::{TemporaryData, FTUE};
+use crate::core::proxy::{SocketType, SocketWait, Socks5UdpSocket};
+use crate::manager::message_bus::MessageBus;
+use crate::manager::notifications;
+use crate::manager::proxy_manager::{self, ProxyManager};
+use crate::types::active_effect::ActiveEffect;
+use crate::types::bot_event::BotEvent;
+use crate::types::packet_filter::{LivePacketFilter, PacketFilter};
+use crate::types::particle::Particle;
+use crate::types::character_state::BASE_SPEED;
+use crate::types::captured_packet::CapturedPacket;
+use crate::types::epacket_direction::EPacketDirection;
+use crate::types::bot_info::{ProxyInfo, TemporaryData, FTUE};
+use crate::types::bot_summary::BotSummary;
+use crate::types::worker_status::WorkerStatus;
+use crate::types::oauth_links::OAuthLinks;
+use crate::types::quest::Quest;
+use crate::types::session_stats::SessionStats;
+use crate::utils::export::{self, ExportFormat};
+use crate::utils::logging::LogEntry;
 use crate::types::{etank_packet_type::ETankPacketType, player::Player, tank_packet::TankPacket};
+use crate::utils::error::CustomError;
 use crate::utils::safe_check;
+use crate::utils::watch::Watch;
 use crate::{
-    lua_register, types,
+    lua_register, lua_sandbox, types,
     types::{
         bot_info::{Info, Server, State},
+        edirection::EDirection,
+        echat_state::EChatState,
+        edisconnect_reason::EDisconnectReason,
+        ehumanizer_preset::EHumanizerPreset,
         elogin_method::ELoginMethod,
         epacket_type::EPacketType,
-        login_info::LoginInfo,
+        login_info,
         vector::Vector2,
     },
     utils,
     utils::{
         config, logging,
-        proton::{self},
         random::{self},
     },
 };
 
-static USER_AGENT: &str =
-    "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36 Edg/120.0.0.0";
+const MAX_PENDING_EVENTS: usize = 256;
+const RATE_LIMIT_BACKOFF: Duration = Duration::from_secs(60);
+// How often a feature held by a pause looks at it again
+const PAUSED_POLL: Duration = Duration::from_millis(500);
+const MAX_CAPTURED_PACKETS: usize = 1000;
+// Tiles of a straight segment covered between two state packets
+const SEGMENT_PACKET_TILES: u32 = 2;
+// Bounds of the event loop's wait while nothing is coming in or going out. Blocked in the
+// socket it only has to wake for enet's own resends and pings
+const IDLE_WAIT_MIN: Duration = Duration::from_millis(1);
+const IDLE_WAIT_MAX: Duration = Duration::from_millis(250);
+const IDLE_WAIT_MAX_PROXY: Duration = Duration::from_millis(50);
+// Collecting and script events only need the fast tick inside a world
+const POLL_IN_WORLD: Duration = Duration::from_millis(100);
+const POLL_OUTSIDE_WORLD: Duration = Duration::from_millis(500);
+// NetGamePacketState flags for a step
+const STATE_MOVING: u32 = 1 << 1;
+const STATE_ON_GROUND: u32 = 1 << 5;
 
 pub struct Bot {
-    pub info: Mutex<Info>,
+    pub info: TimedMutex<Info>,
     pub state: Mutex<State>,
     pub server: Mutex<Server>,
     pub position: Mutex<Vector2>,
     pub temporary_data: RwLock<TemporaryData>,
-    pub host: Mutex<enet::Host<SocketType>>,
+    // What the GUI shows every frame, versioned so panels only re-read after a change.
+    // Reactions to a change hook in with on_change, see attach_watch_hooks
+    pub status: Watch<String>,
+    pub ping: Watch<u32>,
+    pub world_name: Watch<String>,
+    pub gems: Watch<i32>,
+    pub host: TimedMutex<enet::Host<SocketType>>,
     pub peer_id: Mutex<Option<enet::PeerID>>,
-    pub world: RwLock<gtworld_r::World>,
+    // Packets waiting for the event loop, which sends them under its own host lock
+    outgoing: Mutex<Outgoing>,
+    // Wakes an idle event loop as soon as something is queued, blocked in the socket when
+    // there is a SocketWait and on the condvar behind a proxy
+    outgoing_ready: Condvar,
+    socket_wait: Option<Arc<SocketWait>>,
+    // Set by disconnect(), the event loop disconnects the peer under its host lock
+    disconnect_requested: AtomicBool,
+    // Peer round trip time in ms, stored by the event loop after every service() so
+    // nothing else has to wait on `host` for it
+    round_trip_time: AtomicU32,
+    pub bandwidth: Bandwidth,
+    // Received packets waiting for the dispatcher thread, see dispatch::spawn
+    dispatch: Dispatch,
+    pub world: TimedRwLock<gtworld_r::World>,
     pub inventory: Mutex<Inventory>,
     pub players: Mutex<Vec<Player>>,
     pub astar: Mutex<AStar>,
     pub ftue: Mutex<FTUE>,
     pub item_database: Arc<RwLock<ItemDatabase>>,
     pub proxy_manager: Arc<RwLock<ProxyManager>>,
-    pub logs: Arc<Mutex<Vec<String>>>,
+    pub message_bus: Arc<MessageBus>,
+    pub logs: Arc<Mutex<Vec<LogEntry>>>,
     pub sender: Sender<String>,
     pub lua: Mutex<Lua>,
+    // Separate state for the REPL panel, created on its first chunk
+    pub repl: Mutex<Option<Lua>>,
+    pub stats: Mutex<SessionStats>,
+    // Console messages by channel, whispers included
+    pub console: Mutex<Console>,
+    pub whisper_replies: Mutex<HashMap<String, Instant>>,
+    pub events: Mutex<VecDeque<BotEvent>>,
+    pub humanizer: Mutex<Humanizer>,
+    pub active_effects: Mutex<HashMap<u32, ActiveEffect>>,
+    pub captured_packets: Mutex<VecDeque<CapturedPacket>>,
+    // Off until the dev tools turn it on, nothing is copied for capture before that
+    pub capturing: AtomicBool,
+    // Set by lua_sandbox::run_script for as long as a script runs
+    pub script_running: AtomicBool,
+    // Set while the GUI shows this bot's world and inventory, only its packets redraw
+    pub shown: AtomicBool,
+    // Edited from the dev tools, applies to the next packet without a reconnect
+    pub packet_filter: LivePacketFilter,
+    pub chat: Mutex<Chat>,
+    pub storage: Mutex<Storage>,
+    pub timeline: Mutex<Timeline>,
+    // Last NetGamePacketState we sent, the idle heartbeat keys off it
+    pub last_movement: Mutex<Instant>,
+    // Number of live collect::BusyGuard, see Bot::busy
+    pub busy: AtomicUsize,
+    // Bumped on every position resync, a path walked across one is abandoned
+    pub resyncs: AtomicUsize,
+    // Destructive calls made by scripts, newest last
+    pub script_actions: Mutex<VecDeque<script_guard::ScriptAction>>,
+    // Supervised background threads, see supervisor
+    pub workers: Mutex<Vec<WorkerStatus>>,
+    pub local_address: SocketAddr,
 }
 
 impl Bot {
@@ -72,8 +218,15 @@ impl Bot {
         bot_config: types::config::BotConfig,
         item_database: Arc<RwLock<ItemDatabase>>,
         proxy_manager: Arc<RwLock<ProxyManager>>,
-    ) -> Arc<Self> {
-        let lua = Mutex::new(Lua::new());
+        message_bus: Arc<MessageBus>,
+    ) -> Result<Arc<Self>, CustomError> {
+        // Checked up front so a bad config can't panic inside rusty_enet
+        let network = config::get_network();
+        network.validate().map_err(CustomError::NetworkConfig)?;
+
+        let lua = Mutex::new(
+            lua_sandbox::new_state().map_err(|err| CustomError::Other(err.to_string()))?,
+        );
         let logs = Arc::new(Mutex::new(Vec::new()));
         let (sender, receiver) = std::sync::mpsc::channel();
         let logs_clone = Arc::clone(&logs);
@@ -81,7 +234,7 @@ impl Bot {
             match receiver.recv() {
                 Ok(message) => {
                     let mut logs = logs_clone.lock().unwrap();
-                    logs.push(message);
+                    logs.push(LogEntry::from_message(&message));
                 }
                 Err(_) => {
                     break;
@@ -90,19 +243,37 @@ impl Bot {
         });
 
         let payload = utils::textparse::parse_and_store_as_vec(&bot_config.payload);
+        let humanizer_preset = bot_config.humanizer.unwrap_or_default();
+        let daily_stats = utils::daily_stats::load(&payload[0]);
+        let (storage, storage_warning) = Storage::load(&payload[0]);
+        let timeline = Timeline::load(&payload[0]);
+        if let Some(warning) = storage_warning {
+            logging::warn(&warning, &sender);
+        }
+        let local_address = utils::net::parse_bind_address(&bot_config.bind_address)
+            .map_err(CustomError::NetworkConfig)?;
         let mut proxy_address: Option<SocketAddr> = None;
         let mut proxy_username = String::new();
         let mut proxy_password = String::new();
 
+        let mut proxy_unavailable = None;
         if config::get_bot_use_proxy(payload[0].clone()) {
+            let pool = proxy_manager::resolve_pool(&bot_config);
             let mut proxy_manager = proxy_manager.write().unwrap();
-            if let Some(proxy_index) = proxy_manager
-                .proxies
-                .iter()
-                .position(|proxy| proxy.whos_using.len() < 3)
-            {
-                if let Some(proxy_data) = proxy_manager.get_mut(proxy_index) {
-                    proxy_data.whos_using.push(payload[0].clone());
+            let assigned = proxy_manager.assign(&payload[0], &pool);
+            if assigned.is_none() {
+                if config::get_never_connect_direct() {
+                    logging::error(&format!("No proxy available in pool {}", pool), &sender);
+                    proxy_unavailable = Some(pool.clone());
+                } else {
+                    logging::warn(
+                        &format!("No proxy available in pool {}, connecting direct", pool),
+                        &sender,
+                    );
+                }
+            }
+            if let Some(proxy_index) = assigned {
+                if let Some(proxy_data) = proxy_manager.get(proxy_index) {
                     proxy_address = Some(
                         SocketAddr::from_str(&format!(
                             "{}:{}",
@@ -116,70 +287,162 @@ impl Bot {
             }
         }
 
+        let proxy_info = proxy_address.map(|address| ProxyInfo {
+            username: proxy_username.clone(),
+            password: proxy_password.clone(),
+            ip: address.ip().to_string(),
+            port: address.port(),
+        });
+
+        let mut socket_wait = None;
         let socket: SocketType = if let Some(proxy) = proxy_address {
             if proxy_username.is_empty() || proxy_password.is_empty() {
                 logging::error("Proxy username or password is empty", &sender);
             }
             let udp_datagram = Socks5Datagram::bind_with_password(
                 proxy,
-                SocketAddr::from_str("0.0.0.0:0").unwrap(),
+                local_address,
                 &proxy_username,
                 &proxy_password,
             )
-            .expect("Failed to bind SOCKS5 datagram");
+            .map_err(|err| {
+                CustomError::NetworkConfig(format!(
+                    "failed to bind SOCKS5 datagram on {}: {}",
+                    local_address, err
+                ))
+            })?;
             logging::info("Bound to proxy", &sender);
             SocketType::Socks5(Socks5UdpSocket::new(udp_datagram))
         } else {
-            let udp_socket = UdpSocket::bind(SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, 0))
-                .expect("Failed to bind UDP socket");
-            SocketType::Udp(udp_socket)
+            let udp_socket = UdpSocket::bind(local_address).map_err(|err| {
+                CustomError::NetworkConfig(format!(
+                    "failed to bind UDP socket on {}: {}",
+                    local_address, err
+                ))
+            })?;
+            let wait = udp_socket
+                .local_addr()
+                .and_then(SocketWait::new)
+                .map(Arc::new)
+                .map_err(|err| CustomError::NetworkConfig(format!("failed to set up the socket wake-up: {}", err)))?;
+            socket_wait = Some(wait.clone());
+            SocketType::Udp(udp_socket, wait)
         };
 
-        let host = enet::Host::<SocketType>::new(
+        let mut host = enet::Host::<SocketType>::new(
             socket,
             enet::HostSettings {
                 peer_limit: 1,
                 channel_limit: 2,
-                compressor: Some(Box::new(enet::RangeCoder::new())),
-                checksum: Some(Box::new(enet::crc32)),
+                incoming_bandwidth_limit: network.incoming_bandwidth_limit,
+                outgoing_bandwidth_limit: network.outgoing_bandwidth_limit,
+                compressor: if network.use_compression_and_checksum {
+                    Some(Box::new(enet::RangeCoder::new()))
+                } else {
+                    None
+                },
+                checksum: if network.use_compression_and_checksum {
+                    Some(Box::new(enet::crc32))
+                } else {
+                    None
+                },
                 using_new_packet: true,
                 ..Default::default()
             },
         )
-        .expect("Failed to create host");
+        .map_err(|err| CustomError::NetworkConfig(format!("failed to create host: {:?}", err)))?;
+        host.set_mtu(network.mtu)
+            .map_err(|err| CustomError::NetworkConfig(format!("mtu {}: {:?}", network.mtu, err)))?;
 
-        Arc::new(Self {
-            info: Mutex::new(Info {
+        let bot = Arc::new(Self {
+            info: TimedMutex::new(Info {
                 payload,
                 recovery_code: bot_config.recovery_code,
+                steam_shared_secret: bot_config.steam_shared_secret,
                 login_method: bot_config.login_method,
                 token: bot_config.token,
-                login_info: LoginInfo::new(),
+                login_info: login_info::generate(),
+                proxy: proxy_info,
+                proxy_unavailable,
                 ..Default::default()
             }),
             state: Mutex::new(State::default()),
             server: Mutex::new(Server::default()),
             position: Mutex::new(Vector2::default()),
             temporary_data: RwLock::new(TemporaryData::default()),
-            host: Mutex::new(host),
+            status: Watch::default(),
+            ping: Watch::default(),
+            world_name: Watch::new("EXIT".to_string()),
+            gems: Watch::default(),
+            host: TimedMutex::new(host),
             peer_id: Mutex::new(None),
-            world: RwLock::new(gtworld_r::World::new(item_database.clone())),
+            outgoing: Mutex::new(Outgoing::default()),
+            outgoing_ready: Condvar::new(),
+            socket_wait,
+            disconnect_requested: AtomicBool::new(false),
+            round_trip_time: AtomicU32::new(0),
+            bandwidth: Bandwidth::default(),
+            dispatch: Dispatch::default(),
+            world: TimedRwLock::new(gtworld_r::World::new(item_database.clone())),
             inventory: Mutex::new(Inventory::new()),
             players: Mutex::new(Vec::new()),
             astar: Mutex::new(AStar::new(item_database.clone())),
             ftue: Mutex::new(FTUE::default()),
             item_database,
             proxy_manager,
+            message_bus,
             logs,
             sender,
             lua,
-        })
+            repl: Mutex::new(None),
+            stats: Mutex::new(SessionStats {
+                daily: daily_stats,
+                ..Default::default()
+            }),
+            console: Mutex::new(Console::default()),
+            whisper_replies: Mutex::new(HashMap::new()),
+            events: Mutex::new(VecDeque::new()),
+            humanizer: Mutex::new(Humanizer::new(humanizer_preset, bot_config.humanizer_seed)),
+            active_effects: Mutex::new(HashMap::new()),
+            captured_packets: Mutex::new(VecDeque::new()),
+            capturing: AtomicBool::new(false),
+            script_running: AtomicBool::new(false),
+            shown: AtomicBool::new(false),
+            packet_filter: LivePacketFilter::new(&PacketFilter::all()),
+            chat: Mutex::new(Chat::default()),
+            storage: Mutex::new(storage),
+            timeline: Mutex::new(timeline),
+            last_movement: Mutex::new(Instant::now()),
+            busy: AtomicUsize::new(0),
+            resyncs: AtomicUsize::new(0),
+            script_actions: Mutex::new(VecDeque::new()),
+            workers: Mutex::new(Vec::new()),
+            local_address,
+        });
+        bot.attach_watch_hooks();
+        Ok(bot)
+    }
+
+    // What runs when a watched value changes. The bot list shows every bot's status and
+    // world, ping and gems are only on the selected bot's panel
+    fn attach_watch_hooks(self: &Arc<Self>) {
+        self.status.on_change(|_| utils::repaint::request());
+        self.world_name.on_change(|_| utils::repaint::request());
+        self.ping.on_change(repaint_when_shown(self));
+        self.gems.on_change(repaint_when_shown(self));
     }
 
     pub fn log_info(&self, message: &str) {
         logging::info(message, &self.sender);
     }
 
+    // Only kept when debug logs are enabled, for decisions made many times a second
+    pub fn log_debug(&self, message: &str) {
+        if config::get_debug_logs() {
+            logging::debug(message, &self.sender);
+        }
+    }
+
     pub fn log_warn(&self, message: &str) {
         logging::warn(message, &self.sender);
     }
@@ -188,12 +451,166 @@ impl Bot {
         logging::error(message, &self.sender);
     }
 
+    pub fn add_stat(&self, name: &str, amount: u64) {
+        let mut stats = self.stats.lock().expect("Failed to lock stats");
+        stats.record(name, amount, utils::date::now_secs(), || today_key(0));
+    }
+
+    // `day` is "today", "yesterday" or a YYYY-MM-DD date
+    pub fn daily_stat(&self, name: &str, day: &str) -> u64 {
+        let day = match day {
+            "today" => today_key(0),
+            "yesterday" => today_key(-1),
+            day => day.to_string(),
+        };
+        self.stats.lock().expect("Failed to lock stats").get_daily(name, &day)
+    }
+
+    // Oldest first, days without activity are included as 0
+    pub fn daily_history(&self, name: &str, days: i64) -> Vec<(String, u64)> {
+        let stats = self.stats.lock().expect("Failed to lock stats");
+        (1 - days..=0)
+            .map(|offset| {
+                let day = today_key(offset);
+                let value = stats.get_daily(name, &day);
+                (day, value)
+            })
+            .collect()
+    }
+
+    pub fn save_daily_stats(&self) {
+        let daily = self.stats.lock().expect("Failed to lock stats").daily.clone();
+        let bot_name = self.info.lock().expect("Failed to lock info").payload[0].clone();
+        if let Err(err) = utils::daily_stats::save(&bot_name, &daily) {
+            self.log_error(&format!("Failed to save daily stats: {}", err));
+        }
+    }
+
+    // Writes out everything that is otherwise only flushed on a timer, for disconnects and exit
+    pub fn save_persistent(&self) {
+        self.save_daily_stats();
+        if let Err(err) = self.storage.lock().unwrap().flush() {
+            self.log_error(&format!("Failed to save script storage: {}", err));
+        }
+        if let Err(err) = self.timeline.lock().unwrap().flush() {
+            self.log_error(&format!("Failed to save timeline: {}", err));
+        }
+    }
+
+    pub fn emit(&self, event: BotEvent) {
+        self.timeline.lock().expect("Failed to lock timeline").record(&event);
+        if let Some((severity, message, action)) = event.notification() {
+            let bot_name = self.info.lock().expect("Failed to lock info").payload[0].clone();
+            notifications::publish(&bot_name, severity, message, action);
+        }
+        if let BotEvent::Feature { name, running } = &event {
+            let mut temp = self.temporary_data.write().expect("Failed to lock temporary data");
+            if *running {
+                temp.active_feature = Some(name.clone());
+            } else if temp.active_feature.as_ref() == Some(name) {
+                temp.active_feature = None;
+            }
+        }
+        let mut events = self.events.lock().expect("Failed to lock events");
+        // Nobody is draining the queue when no script is loaded, drop the oldest
+        if events.len() >= MAX_PENDING_EVENTS {
+            events.pop_front();
+        }
+        events.push_back(event);
+        utils::repaint::request();
+    }
+
+    // Row data for the bot list
+    pub fn summary(&self) -> BotSummary {
+        let (name, proxy) = {
+            let info = self.info.lock().expect("Failed to lock info");
+            (info.payload[0].clone(), info.proxy.is_some())
+        };
+        let (connected, banned) = {
+            let state = self.state.lock().expect("Failed to lock state");
+            (state.is_ingame, state.is_banned)
+        };
+        BotSummary {
+            name,
+            world: self.world_name.get(),
+            status: self.status.get(),
+            connected,
+            banned,
+            proxy,
+            script_running: self.script_running.load(std::sync::atomic::Ordering::Relaxed),
+            mb_per_hour: self.bandwidth.mb_per_hour(),
+            feature: self
+                .temporary_data
+                .read()
+                .expect("Failed to lock temporary data")
+                .active_feature
+                .clone(),
+            degraded: self.degraded_workers(),
+            ..Default::default()
+        }
+    }
+
+    // Names of workers that crashed since they were started
+    pub fn degraded_workers(&self) -> Vec<String> {
+        self.workers
+            .lock()
+            .expect("Failed to lock workers")
+            .iter()
+            .filter(|worker| worker.is_degraded())
+            .map(|worker| worker.name.clone())
+            .collect()
+    }
+
+    // Particles received within the last `window`, oldest first
+    pub fn recent_particles(&self, window: Duration) -> Vec<Particle> {
+        let temp = self.temporary_data.read().expect("Failed to lock temporary data");
+        temp.particles
+            .iter()
+            .filter(|particle| particle.received_at.elapsed() <= window)
+            .cloned()
+            .collect()
+    }
+
+    pub fn take_events(&self) -> Vec<BotEvent> {
+        let mut events = self.events.lock().expect("Failed to lock events");
+        events.drain(..).collect()
+    }
+
+    pub fn export_logs(&self, path: &str, format: ExportFormat) -> std::io::Result<()> {
+        let logs = self.logs.lock().expect("Failed to lock logs").clone();
+        let session_start = self.stats.lock().expect("Failed to lock stats").started_at;
+        let bot_name = self.info.lock().expect("Failed to lock info").payload[0].clone();
+        export::write_logs(path, format, &bot_name, session_start, &logs)
+    }
+
+    pub fn export_stats(&self, path: &str, format: ExportFormat) -> std::io::Result<()> {
+        let (session_start, counters) = {
+            let stats = self.stats.lock().expect("Failed to lock stats");
+            (
+                stats.started_at,
+                stats
+                    .counters
+                    .iter()
+                    .map(|(name, value)| (name.clone(), *value))
+                    .collect::<Vec<(String, u64)>>(),
+            )
+        };
+        let bot_name = self.info.lock().expect("Failed to lock info").payload[0].clone();
+        export::write_stats(path, format, &bot_name, session_start, &counters)
+    }
+
     pub fn logon(self: Arc<Self>, data: String) {
         {
-            let lua = self.lua.lock().expect("Failed to lock Lua");
-            let _ = lua_register::register(&lua, &self);
+            let mut lua = self.lua.lock().expect("Failed to lock Lua");
+            let _ = lua_sandbox::prepare(&mut lua, &self);
         }
-        self.set_status("Logging in...");
+        let proxy_unavailable = self.info.lock().unwrap().proxy_unavailable.clone();
+        if let Some(pool) = proxy_unavailable {
+            self.log_error(&format!("Not logging in, pool {} has no free proxy", pool));
+            self.set_login_status("No proxy available");
+            return;
+        }
+        self.set_login_status("Logging in...");
         if data.is_empty() {
             self.spoof();
         } else {
@@ -207,21 +624,60 @@ impl Bot {
         self.process_events();
     }
 
+    // logon for the mock server tests. Starts as if a redirect had pointed the bot at
+    // `ip:port`, which skips fetching a token over HTTPS
+    #[cfg(all(test, feature = "mock_server"))]
+    pub(crate) fn logon_redirected(self: Arc<Self>, ip: &str, port: u16) {
+        self.spoof();
+        {
+            let mut server = self.server.lock().unwrap();
+            server.ip = ip.to_string();
+            server.port = port;
+        }
+        {
+            let mut state = self.state.lock().unwrap();
+            state.is_running = true;
+            state.is_redirecting = true;
+        }
+        poll(Arc::clone(&self));
+        self.process_events();
+    }
+
+    // Answers a pending Steam Guard prompt, false when the login isn't waiting on a code
+    pub fn submit_2fa(&self, code: &str) -> bool {
+        let code = code.trim();
+        let mut temp = self.temporary_data.write().unwrap();
+        if temp.steam_guard.is_none() || code.is_empty() {
+            return false;
+        }
+        temp.steam_guard_code = Some(code.to_uppercase());
+        true
+    }
+
     pub fn set_status(&self, message: &str) {
-        let mut info = self.info.lock().expect("Failed to lock info");
-        info.status = message.to_string();
+        self.status.set(message.to_string());
+    }
+
+    // A step of logging in, also passed to scripts as a login_status event
+    pub fn set_login_status(&self, message: &str) {
+        if self.status.set(message.to_string()) {
+            self.emit(BotEvent::LoginStatus { status: message.to_string() });
+        }
     }
 
     pub fn reconnect(&self) -> bool {
-        self.set_status("Reconnecting...");
-        self.to_http();
+        self.set_login_status("Reconnecting...");
+        if !self.to_http() {
+            return false;
+        }
 
-        let (meta, login_method, oauth_links_empty) = {
+        let (meta, login_method, oauth_links_empty, has_token) = {
             let info = self.info.lock().unwrap();
             (
                 info.server_data.get("meta").cloned(),
                 info.login_method.clone(),
                 info.oauth_links.is_empty(),
+                !info.token.is_empty(),
             )
         };
 
@@ -230,12 +686,12 @@ impl Bot {
             info.login_info.meta = meta;
         }
 
-        if login_method != ELoginMethod::STEAM && oauth_links_empty {
+        // A resumed session brings its token along, the links are only fetched if it's rejected
+        if login_method != ELoginMethod::STEAM && oauth_links_empty && !has_token {
             match self.get_oauth_links() {
                 Ok(links) => {
                     let mut info = self.info.lock().unwrap();
                     info.oauth_links = links;
-                    self.log_info("Successfully got OAuth links for: apple, google and legacy");
                 }
                 Err(err) => {
                     self.log_info(&format!("Failed to get OAuth links: {}", err));
@@ -244,6 +700,7 @@ impl Bot {
             }
         }
 
+        self.check_login_info();
         self.get_token();
 
         {
@@ -278,13 +735,13 @@ impl Bot {
             state.is_running = false;
             state.is_redirecting = false;
         }
-        self.set_status("Relogging");
+        self.set_login_status("Relogging");
         self.disconnect();
         self.reconnect();
     }
 
     fn update_login_info(&self, data: String) {
-        self.set_status("Updating login info");
+        self.set_login_status("Updating login info");
         let mut info = self.info.lock().expect("Failed to lock info");
         let parsed_data = utils::textparse::parse_and_store_as_map(&data);
         for (key, value) in parsed_data {
@@ -325,7 +782,7 @@ impl Bot {
 
     fn token_still_valid(&self) -> bool {
         self.log_info("Checking if token is still valid");
-        self.set_status("Checking refresh token");
+        self.set_login_status("Checking refresh token");
 
         let (token, login_info) = {
             let info = self.info.lock().unwrap();
@@ -397,12 +854,28 @@ impl Bot {
         }
 
         self.log_info("Getting token for bot");
-        self.set_status("Getting token");
-        let (payload, recovery_code, method, oauth_links) = {
+        self.set_login_status("Getting token");
+        let bot_name = self.info.lock().unwrap().payload[0].clone();
+        login_cooldown::note_login(&bot_name);
+        let needs_links = {
+            let info = self.info.lock().unwrap();
+            info.login_method != ELoginMethod::STEAM && info.oauth_links.is_empty()
+        };
+        if needs_links {
+            match self.get_oauth_links() {
+                Ok(links) => self.info.lock().unwrap().oauth_links = links,
+                Err(err) => {
+                    self.log_error(&format!("Failed to get OAuth links: {}", err));
+                    return;
+                }
+            }
+        }
+        let (payload, recovery_code, steam_shared_secret, method, oauth_links) = {
             let info = self.info.lock().unwrap();
             (
                 info.payload.clone(),
                 info.recovery_code.clone(),
+                info.steam_shared_secret.clone(),
                 info.login_method.clone(),
                 info.oauth_links.clone(),
             )
@@ -410,13 +883,14 @@ impl Bot {
 
         let token_result = match method {
             ELoginMethod::GOOGLE => match login::get_google_token(
-                oauth_links.get(1).unwrap_or(&"".to_string()),
+                oauth_links.google.as_deref().unwrap_or_default(),
                 &payload[0],
                 &payload[1],
             ) {
                 Ok(res) => res,
                 Err(err) => {
                     if err.to_string().contains("too many people") {
+                        login_cooldown::back_off(RATE_LIMIT_BACKOFF);
                         self.log_error("Too many people trying to login");
                     } else {
                         self.log_error(&format!("Failed to get Google token: {}", err));
@@ -425,7 +899,7 @@ impl Bot {
                 }
             },
             ELoginMethod::LEGACY => match login::get_legacy_token(
-                oauth_links.get(2).unwrap_or(&"".to_string()),
+                oauth_links.legacy.as_deref().unwrap_or_default(),
                 &payload[0],
                 &payload[1],
             ) {
@@ -447,6 +921,7 @@ impl Bot {
                     &payload[1],
                     &payload[2],
                     &payload[3],
+                    &steam_shared_secret,
                 ) {
                     Ok(res) => res,
                     Err(err) => {
@@ -468,9 +943,9 @@ impl Bot {
         }
     }
 
-    pub fn get_oauth_links(&self) -> Result<Vec<String>, ureq::Error> {
+    pub fn get_oauth_links(&self) -> Result<OAuthLinks, ureq::Error> {
         self.log_info("Getting OAuth links");
-        self.set_status("Getting OAuth links");
+        self.set_login_status("Getting OAuth links");
 
         let login_info = {
             let info = self.info.lock().unwrap().login_info.to_string();
@@ -478,10 +953,9 @@ impl Bot {
             encoded_info
         };
 
+        let agent = ureq::agent();
         loop {
-            let res = ureq::post("https://login.growtopiagame.com/player/login/dashboard")
-                .set("User-Agent", USER_AGENT)
-                .send_string(&login_info);
+            let res = login::request_dashboard(&agent, &login_info);
 
             match res {
                 Ok(res) => {
@@ -490,16 +964,20 @@ impl Bot {
                         self.sleep();
                     } else {
                         let body = res.into_string()?;
-                        let pattern =
-                            regex::Regex::new("https://login\\.growtopiagame\\.com/(apple|google|player/growid)/(login|redirect)\\?token=[^\"]+");
-                        let links = match pattern {
-                            Ok(regex) => regex
-                                .find_iter(&body)
-                                .map(|m| m.as_str().to_owned())
-                                .collect::<Vec<String>>(),
-                            Err(_) => Vec::new(),
-                        };
-                        return Ok(links);
+                        return Ok(match oauth::extract(&body) {
+                            Some((links, strategies)) => {
+                                self.log_info(&format!(
+                                    "Got OAuth links for {} ({})",
+                                    links.found().join(", "),
+                                    strategies.join(", ")
+                                ));
+                                links
+                            }
+                            None => {
+                                self.log_warn("Dashboard page had no OAuth links in any known format");
+                                OAuthLinks::default()
+                            }
+                        });
                     }
                 }
                 Err(err) => {
@@ -510,41 +988,51 @@ impl Bot {
         }
     }
 
+    // Warns about login fields that contradict each other, fixing the derived ones if enabled
+    fn check_login_info(&self) {
+        let mut info = self.info.lock().unwrap();
+        let issues = login_info::check_consistency(&info.login_info);
+        if issues.is_empty() {
+            return;
+        }
+        for issue in &issues {
+            self.log_warn(&format!("Inconsistent login info: {}", issue));
+        }
+        if config::get_auto_fix_login_info() {
+            let fixed = login_info::fix(&mut info.login_info, &issues);
+            if fixed > 0 {
+                self.log_info(&format!("Recomputed {} derived login field(s)", fixed));
+            }
+        }
+    }
+
     pub fn spoof(&self) {
         self.log_info("Spoofing core data");
-        self.set_status("Spoofing core data");
-        let mut info = self.info.lock().unwrap();
-        info.login_info.klv = proton::generate_klv(
-            &info.login_info.protocol,
-            &info.login_info.game_version,
-            &info.login_info.rid,
-        );
-        info.login_info.hash =
-            proton::hash_string(&format!("{}RT", info.login_info.mac)).to_string();
-        info.login_info.hash2 =
-            proton::hash_string(&format!("{}RT", random::hex(16, true))).to_string();
+        self.set_login_status("Spoofing core data");
+        login_info::spoof(&mut self.info.lock().unwrap().login_info);
     }
 
-    pub fn to_http(&self) {
+    // False when the configured server override can't be used, the login stops then
+    pub fn to_http(&self) -> bool {
+        if let Some(usable) = self.use_server_override() {
+            return usable;
+        }
         self.log_info("Fetching server data");
-        let server = if config::get_use_alternate_server() {
-            "https://www.growtopia2.com/growtopia/server_data.php"
-        } else {
-            "https://www.growtopia1.com/growtopia/server_data.php"
-        };
-        self.set_status("Fetching server data");
+        self.set_login_status("Fetching server data");
+        let agent = ureq::agent();
+        let url = login::server_data_url();
+        let mut failures = 0;
         loop {
-            let req = ureq::post(server)
-                .set(
-                    "User-Agent",
-                    "UbiServices_SDK_2022.Release.9_PC64_ansi_static",
-                )
-                .send_string("");
+            let req = login::request_server_data(&agent);
 
             let res = match req {
                 Ok(res) => res,
                 Err(err) => {
                     self.log_error(&format!("Request error: {}, retrying...", err));
+                    failures += 1;
+                    if self.use_cached_server_data(url, failures) {
+                        break;
+                    }
                     self.sleep();
                     continue;
                 }
@@ -552,41 +1040,111 @@ impl Bot {
 
             if res.status() != 200 {
                 self.log_warn("Failed to fetch server data");
+                failures += 1;
+                if self.use_cached_server_data(url, failures) {
+                    break;
+                }
                 self.sleep();
             } else {
                 let body = res.into_string().unwrap_or_default();
+                server_cache::save(url, &body);
                 self.parse_server_data(body);
                 break;
             }
         }
+        true
+    }
+
+    // server_ip and server_port from the config skip server_data.php entirely, None when
+    // they aren't set. The meta still has to come from a fresh cached response, the server
+    // turns a login without it away
+    fn use_server_override(&self) -> Option<bool> {
+        let (ip, port) = (config::get_server_ip(), config::get_server_port());
+        if ip.trim().is_empty() || port == 0 {
+            return None;
+        }
+        let Some((body, _)) = server_cache::load(login::server_data_url()) else {
+            self.log_error(&format!(
+                "The server override {}:{} needs a cached server_data.php response from the last {} hours for the meta. Log in once without the override",
+                ip.trim(),
+                port,
+                config::get_server_data_max_age_hours()
+            ));
+            self.set_login_status("Server override has no cached meta");
+            return Some(false);
+        };
+        self.log_info(&format!("Using the configured server {}:{}", ip, port));
+        let mut server_data = login::parse_server_data(&body);
+        server_data.insert("server".to_string(), ip.trim().to_string());
+        server_data.insert("port".to_string(), port.to_string());
+        self.info.lock().unwrap().server_data = server_data;
+        Some(true)
+    }
+
+    // The game server is often up while the web endpoint isn't, after enough failed
+    // fetches the last good response is used when it isn't too old
+    fn use_cached_server_data(&self, url: &str, failures: u32) -> bool {
+        let attempts = config::get_server_data_fallback_attempts();
+        if attempts == 0 || failures < attempts {
+            return false;
+        }
+        match server_cache::load(url) {
+            Some((body, age)) => {
+                self.log_warn(&format!(
+                    "server_data.php failed {} times, using the cached response from {} minutes ago",
+                    failures,
+                    age / 60
+                ));
+                self.parse_server_data(body);
+                true
+            }
+            None => {
+                if failures == attempts {
+                    self.log_warn("server_data.php is unreachable and there is no recent cached response");
+                }
+                false
+            }
+        }
     }
 
     pub fn parse_server_data(&self, data: String) {
         self.log_info("Parsing server data");
-        self.set_status("Parsing server data");
+        self.set_login_status("Parsing server data");
         let mut info = self.info.lock().unwrap();
-        info.server_data = data
-            .lines()
-            .filter_map(|line| {
-                let mut parts = line.splitn(2, '|');
-                match (parts.next(), parts.next()) {
-                    (Some(key), Some(value)) => Some((key.to_string(), value.to_string())),
-                    _ => None,
-                }
-            })
-            .collect::<HashMap<String, String>>();
+        info.server_data = login::parse_server_data(&data);
     }
 
     fn connect_to_server(&self, ip: &str, port: &str) {
         self.log_info(&format!("Connecting to the server {}:{}", ip, port));
-        self.set_status("Connecting to the server");
-
-        let socket_address = SocketAddr::from_str(&format!("{}:{}", ip, port)).unwrap();
+        self.set_login_status("Connecting to the server");
+
+        let socket_address = match port
+            .parse::<u16>()
+            .map_err(|err| err.to_string())
+            .and_then(|port| {
+                utils::net::resolve(ip, port, &self.local_address).map_err(|err| err.to_string())
+            }) {
+            Ok(address) => address,
+            Err(err) => {
+                self.log_error(&format!("Failed to resolve the server {}:{}: {}", ip, port, err));
+                return;
+            }
+        };
+        self.temporary_data.write().unwrap().connecting_to =
+            Some((ip.to_string(), socket_address.port()));
 
         let mut host = self.host.lock().unwrap();
         match host.connect(socket_address, 2, 0) {
             Ok(peer) => {
-                peer.set_ping_interval(100);
+                let network = config::get_network();
+                peer.set_ping_interval(network.ping_interval);
+                peer.set_timeout(
+                    network.peer_timeout_limit,
+                    network.peer_timeout_minimum,
+                    network.peer_timeout_maximum,
+                );
+                // Remembered before Connect so the watchdog can drop a half-open attempt
+                *self.peer_id.lock().unwrap() = Some(peer.id());
             }
             Err(err) => {
                 self.log_error(&format!("Failed to connect to the server: {}", err));
@@ -595,19 +1153,18 @@ impl Bot {
     }
 
     pub fn set_ping(&self) {
-        if let Ok(mut host) = self.host.try_lock() {
-            if let Ok(peer_id) = self.peer_id.try_lock() {
-                if let Some(peer_id) = *peer_id {
-                    let peer = host.peer_mut(peer_id);
-                    if let Ok(mut temp) = self.temporary_data.try_write() {
-                        temp.ping = peer.round_trip_time().as_millis() as u32;
-                    }
-                }
-            }
-        }
+        self.ping.set(self.round_trip_time.load(std::sync::atomic::Ordering::Relaxed));
     }
 
+    // Handlers run on the dispatcher thread, this loop only services enet so a slow
+    // handler can't delay acknowledgements into retransmits or a timeout
     fn process_events(self: Arc<Self>) {
+        dispatch::spawn(Arc::clone(&self));
+        self.service_events();
+        self.dispatch.close();
+    }
+
+    fn service_events(&self) {
         loop {
             let (is_running, is_redirecting, ip, port) = {
                 let state = self.state.lock().unwrap();
@@ -634,102 +1191,325 @@ impl Bot {
                 }
             }
 
+            let mut watchdog = Watchdog::new();
+            let mut idle_wait = IDLE_WAIT_MIN;
             loop {
                 let event = {
+                    let outgoing = self.outgoing.lock().unwrap().take_batch(Instant::now());
+                    let peer_id = *self.peer_id.lock().unwrap();
                     let mut host = self.host.lock().unwrap();
-                    host.service().ok().flatten().map(|e| e.no_ref())
+                    if let Some(peer_id) = peer_id {
+                        let peer = host.peer_mut(peer_id);
+                        for (channel, pkt) in outgoing {
+                            match peer.send(channel, &pkt) {
+                                Ok(_) => self.bandwidth.record_out(pkt.data().len()),
+                                Err(err) => self.log_error(&format!("Failed to send packet: {}", err)),
+                            }
+                        }
+                        if self.disconnect_requested.swap(false, std::sync::atomic::Ordering::SeqCst) {
+                            peer.disconnect(0);
+                        }
+                    }
+                    let event = host.service().ok().flatten().map(|e| e.no_ref());
+                    if let Some(peer_id) = peer_id {
+                        let round_trip_time = host.peer_mut(peer_id).round_trip_time().as_millis() as u32;
+                        self.round_trip_time.store(round_trip_time, std::sync::atomic::Ordering::Relaxed);
+                    }
+                    event
                 };
+                // Events can come in bursts, only an idle iteration waits
+                let idle = event.is_none();
 
                 if let Some(event) = event {
                     match event {
                         enet::EventNoRef::Connect { peer, .. } => {
                             self.log_info("Connected to the server");
-                            self.set_status("Connected");
-                            let mut peer_id = self.peer_id.lock().unwrap();
-                            *peer_id = Some(peer);
+                            self.set_login_status("Connected");
+                            watchdog.on_connect();
+                            disconnect::clear_pending(self);
+                            *self.peer_id.lock().unwrap() = Some(peer);
+                            let connecting_to = self.temporary_data.read().unwrap().connecting_to.clone();
+                            if let Some((server_ip, port)) = connecting_to {
+                                self.emit(BotEvent::Connect { server_ip, port });
+                            }
                         }
-                        enet::EventNoRef::Disconnect { .. } => {
-                            self.log_warn("Disconnected from the server");
-                            self.set_status("Disconnected");
-                            let mut world = self.world.write().unwrap();
-                            let mut position = self.position.lock().unwrap();
-                            let mut temp = self.temporary_data.write().unwrap();
-                            self.players.lock().unwrap().clear();
-                            world.reset();
-                            position.reset();
-                            temp.entered_world = false;
+                        enet::EventNoRef::Disconnect { data, .. } => {
+                            let reason = disconnect::on_disconnect(self, data);
+                            self.dispatch.clear();
+                            self.set_status(&format!("Disconnected: {}", reason.name()));
+                            self.add_stat("disconnects", 1);
+                            self.reset_session();
+                            self.wait_before_reconnect(reason);
                             break;
                         }
-                        enet::EventNoRef::Receive { packet, .. } => {
+                        enet::EventNoRef::Receive {
+                            packet, channel_id, ..
+                        } => {
                             let data = packet.data();
                             if data.len() < 4 {
                                 continue;
                             }
+                            watchdog.on_packet();
+                            self.bandwidth.record_in(data.len());
+                            self.add_stat("packets_received", 1);
+                            self.add_stat(channel::received_stat(channel_id), 1);
+                            let logged = self.admit_packet(data);
+                            if logged {
+                                self.capture_packet(EPacketDirection::Incoming, channel_id, data);
+                            }
                             let packet_id = LittleEndian::read_u32(&data[0..4]);
                             let packet_type = EPacketType::from(packet_id);
-                            let bot_clone = Arc::clone(&self);
-                            packet_handler::handle(bot_clone, packet_type, &data[4..]);
+                            if packet_type == EPacketType::NetMessageUnknown {
+                                // The raw id is lost past this point, so unknown ids are counted here
+                                self.add_stat(&format!("packets_unknown_type_{}", packet_id), 1);
+                            }
+                            let pushed = self.dispatch.push(Incoming {
+                                packet_type,
+                                data: data[4..].to_vec(),
+                                logged,
+                            });
+                            match pushed {
+                                Pushed::Queued => {}
+                                Pushed::Dropped => self.add_stat("packets_dropped_backpressure", 1),
+                                Pushed::Overflow => {
+                                    // Skipping a packet that can't be dropped would leave the
+                                    // session out of sync, a fresh one is cheaper
+                                    self.log_warn("Packet handlers fell too far behind, reconnecting");
+                                    disconnect::record(
+                                        self,
+                                        EDisconnectReason::NetworkTimeout,
+                                        "packet handlers fell behind".to_string(),
+                                    );
+                                    self.set_status("Overloaded");
+                                    self.add_stat("dispatch_overflows", 1);
+                                    self.drop_connection();
+                                    self.dispatch.clear();
+                                    self.reset_session();
+                                    break;
+                                }
+                            }
+                        }
+                    }
+                }
+
+                let awaiting = || {
+                    !self.state.lock().unwrap().logged_in
+                        || self.temporary_data.read().unwrap().warp_target.is_some()
+                };
+                if let Some(stall) = watchdog.check(awaiting) {
+                    match stall {
+                        Stall::ConnectTimeout(elapsed) => self.log_warn(&format!(
+                            "No response from the server after {}s, retrying",
+                            elapsed.as_secs()
+                        )),
+                        Stall::NoPackets(elapsed) => self.log_warn(&format!(
+                            "Nothing received for {}s while waiting on the server, reconnecting",
+                            elapsed.as_secs()
+                        )),
+                    }
+                    disconnect::record(
+                        self,
+                        EDisconnectReason::NetworkTimeout,
+                        "no packets from the server".to_string(),
+                    );
+                    self.set_status("Stalled");
+                    self.add_stat("stalls", 1);
+                    self.drop_connection();
+                    self.dispatch.clear();
+                    self.reset_session();
+                    break;
+                }
+                // The wait grows while nothing happens, so an idle bot barely wakes up. Queued
+                // packets cut it short once their rate limit allows. Without a proxy the next
+                // service() blocks in the socket and incoming packets end the wait too, behind
+                // one they are picked up within IDLE_WAIT_MAX_PROXY
+                if idle {
+                    let outgoing = self.outgoing.lock().unwrap();
+                    let wait = outgoing.ready_in(Instant::now()).map_or(idle_wait, |ready| ready.min(idle_wait));
+                    match &self.socket_wait {
+                        Some(socket_wait) => socket_wait.arm(wait),
+                        None if !wait.is_zero() => {
+                            drop(self.outgoing_ready.wait_timeout(outgoing, wait).unwrap());
                         }
+                        None => {}
                     }
+                    let max = if self.socket_wait.is_some() { IDLE_WAIT_MAX } else { IDLE_WAIT_MAX_PROXY };
+                    idle_wait = (idle_wait * 2).min(max);
+                } else {
+                    idle_wait = IDLE_WAIT_MIN;
                 }
-                thread::sleep(Duration::from_millis(10));
             }
         }
     }
 
-    pub fn disconnect(&self) {
-        let peer_id = self.peer_id.lock().unwrap().clone();
-        if let Some(peer_id) = peer_id {
-            if let Ok(mut host) = self.host.try_lock() {
-                let peer = host.peer_mut(peer_id);
-                peer.disconnect(0);
+    fn wait_before_reconnect(&self, reason: EDisconnectReason) {
+        match reason.reconnect_delay() {
+            None => {
+                self.log_warn(&format!("Not reconnecting after: {}", reason.name()));
+                self.state.lock().unwrap().is_running = false;
+            }
+            Some(delay) if !delay.is_zero() => {
+                self.log_info(&format!("Waiting {}s before reconnecting", delay.as_secs()));
+                self.temporary_data.write().unwrap().timeout = delay.as_secs() as u32;
+                self.sleep();
             }
+            Some(_) => {}
+        }
+    }
+
+    // Drops the peer without waiting for the server, no Disconnect event follows
+    fn drop_connection(&self) {
+        let peer_id = self.peer_id.lock().unwrap().take();
+        self.outgoing.lock().unwrap().clear();
+        self.disconnect_requested.store(false, std::sync::atomic::Ordering::SeqCst);
+        if let Some(peer_id) = peer_id {
+            let mut host = self.host.lock().unwrap();
+            host.peer_mut(peer_id).disconnect_now(0);
+        }
+        self.state.lock().unwrap().is_redirecting = false;
+    }
+
+    fn reset_session(&self) {
+        let mut world = self.world.write().unwrap();
+        let mut position = self.position.lock().unwrap();
+        let mut temp = self.temporary_data.write().unwrap();
+        self.players.lock().unwrap().clear();
+        if self.state.lock().unwrap().connection_lost() {
+            temp.speed_multiplier = None;
+        }
+        world.reset();
+        self.world_name.set(world.name.clone());
+        position.reset();
+        temp.entered_world = false;
+        temp.world_summary = None;
+        temp.particles.clear();
+        temp.unreachable_drops.clear();
+    }
+
+    // Packets waiting to be sent and whether a user action is among them
+    pub fn outgoing_state(&self) -> (usize, bool) {
+        let outgoing = self.outgoing.lock().unwrap();
+        (outgoing.len(), outgoing.user_pending())
+    }
+
+    // Handled by the event loop, which holds `host` for every service() and would make
+    // a try_lock here miss the disconnect whenever it lost the race
+    pub fn disconnect(&self) {
+        if self.peer_id.lock().unwrap().is_some() {
+            self.disconnect_requested.store(true, std::sync::atomic::Ordering::SeqCst);
+            self.wake_event_loop();
         }
     }
 
     pub fn send_packet(&self, packet_type: EPacketType, message: String) {
-        let mut packet_data = Vec::new();
-        packet_data.extend_from_slice(&(packet_type as u32).to_le_bytes());
-        packet_data.extend_from_slice(message.as_bytes());
+        let channel = channel::for_message(&packet_type);
+        let packet_data = text_packet_data(packet_type, &message);
         let pkt = enet::Packet::reliable(packet_data.as_slice());
+        self.send_enet_packet(channel, &pkt);
+    }
 
-        if let Ok(peer_id) = self.peer_id.lock() {
-            if let Some(peer_id) = *peer_id {
-                if let Ok(mut host) = self.host.try_lock() {
-                    let peer = host.peer_mut(peer_id);
-                    if let Err(err) = peer.send(0, &pkt) {
-                        self.log_error(&format!("Failed to send packet: {}", err));
-                    }
-                }
+    // send_packet for messages carrying a password, the dev tools capture (and with it any
+    // crash report) keeps a copy with `secret` masked out
+    pub fn send_secret_packet(&self, packet_type: EPacketType, message: String, secret: &str) {
+        let channel = channel::for_message(&packet_type);
+        let masked = text_packet_data(packet_type, &message.replace(secret, "********"));
+        self.capture_outgoing(channel, &masked);
+        let packet_data = text_packet_data(packet_type, &message);
+        self.queue_packet(channel, &enet::Packet::reliable(packet_data.as_slice()));
+    }
+
+    fn send_enet_packet(&self, channel: u8, pkt: &enet::Packet) {
+        self.capture_outgoing(channel, pkt.data());
+        self.queue_packet(channel, pkt);
+    }
+
+    // Queued rather than sent here, the event loop holds `host` for every service() and a
+    // try_lock from other threads used to silently drop the packet whenever it lost
+    fn queue_packet(&self, channel: u8, pkt: &enet::Packet) {
+        if self.peer_id.lock().unwrap().is_some() {
+            if !self.outgoing.lock().unwrap().push(channel, pkt.clone()) {
+                self.add_stat("packets_dropped_outgoing", 1);
+                self.log_warn("Outgoing queue is full, dropped a packet");
             }
+            self.wake_event_loop();
         }
     }
 
-    pub fn send_packet_raw(&self, packet: &TankPacket) {
-        let packet_size = size_of::<EPacketType>()
-            + size_of::<TankPacket>()
-            + packet.extended_data_length as usize;
-        let mut enet_packet_data = vec![0u8; packet_size];
-
-        let packet_type = EPacketType::NetMessageGamePacket as u32;
-        enet_packet_data[..size_of::<u32>()].copy_from_slice(&packet_type.to_le_bytes());
+    fn wake_event_loop(&self) {
+        match &self.socket_wait {
+            Some(socket_wait) => socket_wait.wake(),
+            None => self.outgoing_ready.notify_one(),
+        }
+    }
 
-        let tank_packet_bytes = bincode::serialize(packet).expect("Failed to serialize TankPacket");
-        enet_packet_data[size_of::<u32>()..size_of::<u32>() + tank_packet_bytes.len()]
-            .copy_from_slice(&tank_packet_bytes);
+    // `extended` is copied right after the header and has to be exactly
+    // `packet.extended_data_length` bytes, a mismatch is logged and nothing is sent
+    pub fn send_packet_raw(&self, packet: &TankPacket, extended: Option<&[u8]>) {
+        let enet_packet_data = match packet.to_message(extended.unwrap_or(&[])) {
+            Ok(data) => data,
+            Err(err) => {
+                self.log_error(&format!("Not sending {:?}: {}", packet._type, err));
+                return;
+            }
+        };
 
         let enet_packet = enet::Packet::reliable(enet_packet_data.as_slice());
+        self.send_enet_packet(channel::for_tank_packet(&packet._type), &enet_packet);
+        if packet._type == ETankPacketType::NetGamePacketState {
+            *self.last_movement.lock().unwrap() = Instant::now();
+        }
+    }
 
-        if let Ok(peer_id) = self.peer_id.lock() {
-            if let Some(peer_id) = *peer_id {
-                if let Ok(mut host) = self.host.try_lock() {
-                    let peer = host.peer_mut(peer_id);
-                    if let Err(err) = peer.send(0, &enet_packet) {
-                        self.log_error(&format!("Failed to send packet: {}", err));
-                    }
-                }
-            }
+    // Developer tool, sends raw bytes as-is including the leading message type
+    pub fn send_raw_bytes(&self, channel: u8, data: &[u8]) {
+        if !config::get_developer_mode() {
+            self.log_error("Manual packet sending requires developer mode");
+            return;
+        }
+        self.log_warn(&format!(
+            "[DEV] Manually sending {} bytes on channel {}: {}",
+            data.len(),
+            channel,
+            hex::encode(data)
+        ));
+        let pkt = enet::Packet::reliable(data);
+        self.send_enet_packet(channel, &pkt);
+    }
+
+    // Whether the packet filter lets a packet through, counted against the sample limit.
+    // A received packet is admitted once for both the capture and the handler's log line
+    fn admit_packet(&self, data: &[u8]) -> bool {
+        let packet_type = data.get(0..4).map_or(0, LittleEndian::read_u32);
+        self.packet_filter.admit(packet_type, data.get(4).copied())
+    }
+
+    fn capture_outgoing(&self, channel: u8, data: &[u8]) {
+        if self.capturing.load(std::sync::atomic::Ordering::Relaxed) && self.admit_packet(data) {
+            self.capture_packet(EPacketDirection::Outgoing, channel, data);
+        }
+    }
+
+    fn capture_packet(&self, direction: EPacketDirection, channel: u8, data: &[u8]) {
+        if !self.capturing.load(std::sync::atomic::Ordering::Relaxed) {
+            return;
+        }
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+        let mut captured = self
+            .captured_packets
+            .lock()
+            .expect("Failed to lock captured packets");
+        if captured.len() >= MAX_CAPTURED_PACKETS {
+            captured.pop_front();
         }
+        captured.push_back(CapturedPacket {
+            timestamp,
+            direction,
+            channel,
+            data: data.to_vec(),
+        });
     }
 
     pub fn is_inworld(&self) -> bool {
@@ -737,6 +1517,7 @@ impl Bot {
     }
 
     pub fn collect(&self) {
+        let _span = profiler::span(self, "collect");
         if !self.is_inworld() {
             return;
         }
@@ -746,46 +1527,70 @@ impl Bot {
             (position.x, position.y)
         };
 
-        let items = {
-            let world = self.world.read().expect("Failed to lock world");
-            world.dropped.items.clone()
-        };
-
-        for obj in items {
-            let dx = (bot_x - obj.x).abs() / 32.0;
-            let dy = (bot_y - obj.y).abs() / 32.0;
-            let distance = (dx.powi(2) + dy.powi(2)).sqrt();
-            if distance <= 5.0 {
-                let can_collect = {
-                    let inventory = self.inventory.lock().expect("Failed to lock inventory");
-                    let inventory_size = inventory.size;
-
-                    if inventory.items.get(&obj.id).is_none()
-                        && inventory_size > inventory.item_count as u32
-                    {
-                        true
+        let items = self.dropped_items_with_age();
+        let filter = self.temporary_data.read().unwrap().collect_filter.clone();
+        let threshold = config::get_collect_threshold_wl();
+        let busy = self.is_busy();
+
+        // Most valuable first, so a full inventory doesn't fill up on dirt before the lock.
+        // Drops close to despawning count for more
+        let mut items: Vec<(f32, f32, gtworld_r::DroppedItem)> = items
+            .into_iter()
+            .filter(|(obj, _)| filter.as_ref().map_or(true, |filter| filter.contains(&obj.id)))
+            .filter(|(obj, _)| collect::distance(bot_x, bot_y, obj.x, obj.y) <= collect::PICKUP_RANGE)
+            .map(|(obj, age)| {
+                let value = value_table::value_of(obj.id as u32, obj.count as u32);
+                (drop_age::weighted_value(value, age), value, obj)
+            })
+            .collect();
+        items.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+        for (_, value, obj) in items {
+            if busy && value < threshold {
+                self.log_debug(&format!("Holding off on item {} ({:.2} WL) while busy", obj.id, value));
+                continue;
+            }
+            let can_collect = {
+                let inventory = self.inventory.lock().expect("Failed to lock inventory");
+                let inventory_size = inventory.size;
+
+                if inventory.items.get(&obj.id).is_none()
+                    && inventory_size > inventory.item_count as u32
+                {
+                    true
+                } else {
+                    if let Some(item) = inventory.items.get(&obj.id) {
+                        item.amount < 200
                     } else {
-                        if let Some(item) = inventory.items.get(&obj.id) {
-                            item.amount < 200
-                        } else {
-                            false
-                        }
+                        false
                     }
-                };
-
-                if can_collect {
-                    let mut pkt = TankPacket::default();
-                    pkt._type = ETankPacketType::NetGamePacketItemActivateObjectRequest;
-                    pkt.vector_x = obj.x;
-                    pkt.vector_y = obj.y;
-                    pkt.value = obj.uid;
-                    self.send_packet_raw(&pkt);
-                    self.log_info("Collect packet sent");
                 }
+            };
+
+            if can_collect {
+                let mut pkt = TankPacket::default();
+                pkt._type = ETankPacketType::NetGamePacketItemActivateObjectRequest;
+                pkt.vector_x = obj.x;
+                pkt.vector_y = obj.y;
+                pkt.value = obj.uid;
+                self.send_packet_raw(&pkt, None);
+                self.log_debug(&format!("Collecting item {} ({:.2} WL)", obj.id, value));
             }
         }
     }
 
+    pub fn busy(&self) -> collect::BusyGuard<'_> {
+        collect::BusyGuard::new(&self.busy)
+    }
+
+    pub fn try_busy(&self) -> Option<collect::BusyGuard<'_>> {
+        collect::BusyGuard::try_new(&self.busy)
+    }
+
+    pub fn is_busy(&self) -> bool {
+        self.busy.load(std::sync::atomic::Ordering::SeqCst) > 0
+    }
+
     pub fn place(&self, offset_x: i32, offset_y: i32, item_id: u32) {
         let mut pkt = TankPacket::default();
         pkt._type = ETankPacketType::NetGamePacketTileChangeRequest;
@@ -808,17 +1613,73 @@ impl Bot {
             && pkt.int_y <= base_y + 4
             && pkt.int_y >= base_y - 4
         {
-            self.send_packet_raw(&pkt);
-            /*
-            00000000 00000000 00001010 00110000
-            the fifth bit from the end is set if facing left
-             */
-            pkt.flags = if offset_x > 0 { 2592 } else { 2608 };
+            self.face_towards(offset_x);
+            self.send_packet_raw(&pkt, None);
+            pkt.flags = self.state_flags(2592);
             pkt._type = ETankPacketType::NetGamePacketState;
-            self.send_packet_raw(&pkt);
+            self.send_packet_raw(&pkt, None);
+        }
+    }
+
+    // Consuming is placing the item on our own tile. The configured duration is an estimate
+    // until the server's "mod added" line gives the real one, see effects::note_message
+    pub fn consume(&self, item_id: u32) {
+        self.temporary_data.write().expect("Failed to lock temporary data").last_consumed =
+            Some((item_id, Instant::now()));
+        self.place(0, 0, item_id);
+        let duration = config::get_consumables()
+            .into_iter()
+            .find(|consumable| consumable.item_id == item_id)
+            .map(|consumable| Duration::from_secs(consumable.duration_secs));
+        if let Some(duration) = duration {
+            self.add_effect(item_id, item_id, "", duration);
         }
     }
 
+    pub fn add_effect(&self, id: u32, source_item: u32, name: &str, duration: Duration) {
+        let mut effects = self.active_effects.lock().expect("Failed to lock effects");
+        effects.insert(
+            id,
+            ActiveEffect {
+                id,
+                source_item,
+                name: name.to_string(),
+                expires_at: Instant::now() + duration,
+            },
+        );
+    }
+
+    // The server ended the mod early or on time, by the name it reported it with
+    pub fn remove_effect(&self, name: &str) {
+        let mut effects = self.active_effects.lock().expect("Failed to lock effects");
+        effects.retain(|_, effect| !effect.name.eq_ignore_ascii_case(name));
+    }
+
+    pub fn has_effect(&self, id: u32) -> bool {
+        let effects = self.active_effects.lock().expect("Failed to lock effects");
+        effects.get(&id).map_or(false, |effect| !effect.is_expired())
+    }
+
+    pub fn effects(&self) -> Vec<ActiveEffect> {
+        let mut effects = self.active_effects.lock().expect("Failed to lock effects");
+        effects.retain(|_, effect| !effect.is_expired());
+        effects.values().cloned().collect()
+    }
+
+    pub fn facing(&self) -> EDirection {
+        self.state.lock().expect("Failed to lock state").facing
+    }
+
+    fn face_towards(&self, offset_x: i32) {
+        if let Some(direction) = EDirection::from_offset(offset_x) {
+            self.state.lock().expect("Failed to lock state").facing = direction;
+        }
+    }
+
+    fn state_flags(&self, flags: u32) -> u32 {
+        self.facing().state_flags(flags)
+    }
+
     pub fn punch(&self, offset_x: i32, offset_y: i32) {
         self.place(offset_x, offset_y, 18);
     }
@@ -834,7 +1695,7 @@ impl Bot {
             ..Default::default()
         };
 
-        self.send_packet_raw(&packet);
+        self.send_packet_raw(&packet, None);
     }
 
     pub fn warp(&self, world_name: String) {
@@ -847,17 +1708,96 @@ impl Bot {
             return;
         }
         self.log_info(&format!("Warping to world: {}", world_name));
+        {
+            let mut temp = self.temporary_data.write().unwrap();
+            temp.warp_target = Some(world_name.to_uppercase());
+            temp.warp_error = None;
+        }
         self.send_packet(
             EPacketType::NetMessageGameMessage,
             format!("action|join_request\nname|{}\ninvitedWorld|0\n", world_name),
         );
     }
 
-    pub fn talk(&self, message: String) {
+    // Warps unless already there and waits for the world to load. Fails early when the world
+    // asked for a password that isn't saved or was rejected
+    pub fn warp_and_wait(&self, world_name: &str, timeout: Duration) -> Result<(), String> {
+        let world_name = world_name.to_uppercase();
+        let arrived = || self.is_inworld() && self.world.read().unwrap().name == world_name;
+        if arrived() {
+            return Ok(());
+        }
+        self.warp(world_name.clone());
+        let started = Instant::now();
+        while started.elapsed() < timeout {
+            if arrived() {
+                self.temporary_data.write().unwrap().warp_target = None;
+                return Ok(());
+            }
+            if let Some(err) = self.temporary_data.write().unwrap().warp_error.take() {
+                return Err(err);
+            }
+            thread::sleep(Duration::from_millis(250));
+        }
+        self.temporary_data.write().unwrap().warp_target = None;
+        Err(format!("{} didn't load within {}s", world_name, timeout.as_secs()))
+    }
+
+    // Goes through the chat queue, returns false if any part had to wait for the cooldown or a mute
+    pub fn talk(&self, message: String) -> bool {
+        chat::say(self, &message)
+    }
+
+    pub fn chat_state(&self) -> EChatState {
+        self.chat.lock().unwrap().state()
+    }
+
+    // Wrenches Crazy Jim and waits for his dialog to be parsed into `TemporaryData::quests`.
+    // The dialog only exists client-side so there's nothing to close afterwards
+    pub fn refresh_quests(&self) -> Option<Quest> {
+        let net_id = {
+            let players = self.players.lock().expect("Failed to lock players");
+            players
+                .iter()
+                .find(|player| {
+                    utils::textparse::strip_color(&player.name)
+                        .to_lowercase()
+                        .contains("crazy jim")
+                })
+                .map(|player| player.net_id)
+        };
+        let net_id = match net_id {
+            Some(net_id) => net_id,
+            None => {
+                self.log_warn("Crazy Jim isn't in this world");
+                return None;
+            }
+        };
+
+        self.temporary_data
+            .write()
+            .expect("Failed to lock temporary data")
+            .quests
+            .daily = None;
         self.send_packet(
             EPacketType::NetMessageGenericText,
-            format!("action|input\n|text|{}\n", message),
+            format!("action|wrench\n|netid|{}\n", net_id),
         );
+        for _ in 0..30 {
+            thread::sleep(Duration::from_millis(100));
+            let temp = self
+                .temporary_data
+                .read()
+                .expect("Failed to lock temporary data");
+            if temp.quests.daily.is_some() {
+                return temp.quests.daily.clone();
+            }
+        }
+        None
+    }
+
+    pub fn whisper(&self, name: &str, message: &str) {
+        self.talk(format!("/msg {} {}", name, message));
     }
 
     pub fn leave(&self) {
@@ -869,8 +1809,33 @@ impl Bot {
         }
     }
 
+    // Whether paths go straight through blocks, a script override wins over the character state
+    pub fn noclip_pathing(&self) -> bool {
+        let state = self.state.lock().expect("Failed to lock state");
+        state.noclip_override.unwrap_or(state.character.has_noclip())
+    }
+
+    pub fn set_noclip_pathing(&self, noclip: Option<bool>) {
+        self.state.lock().expect("Failed to lock state").noclip_override = noclip;
+    }
+
+    // The pathfinder set up for the current noclip mode
+    fn pathfinder(&self) -> MutexGuard<'_, AStar> {
+        let noclip = self.noclip_pathing();
+        let mut astar = self.astar.lock().expect("Failed to lock astar");
+        astar.noclip = noclip;
+        astar
+    }
+
     pub fn walk(&self, x: i32, y: i32, ap: bool) {
+        // Manual and script steps can come from the GUI thread, they're dropped rather than
+        // waiting out the test walk
+        if !ap && calibration::is_calibrating(self) {
+            self.log_info("Movement is being calibrated, step ignored");
+            return;
+        }
         if !ap {
+            self.face_towards(x);
             let mut position = self.position.lock().expect("Failed to lock position");
             position.x += (x * 32) as f32;
             position.y += (y * 32) as f32;
@@ -884,38 +1849,469 @@ impl Bot {
             pkt.vector_y = position.y;
             pkt.int_x = -1;
             pkt.int_y = -1;
-            pkt.flags |= (1 << 1) | (1 << 5);
         }
+        // Floating without the on-ground bit while ghosting, gravity doesn't apply
+        let flags = if self.noclip_pathing() {
+            STATE_MOVING
+        } else {
+            STATE_MOVING | STATE_ON_GROUND
+        };
+        pkt.flags = self.state_flags(flags);
+
+        if safe_check::is_connected(self) && self.is_inworld() {
+            self.send_packet_raw(&pkt, None);
+        }
+    }
+
+    // Current position with no movement bits, what an idle client sends
+    pub fn send_idle_state(&self) {
+        let mut pkt = TankPacket::default();
+        {
+            let position = self.position.lock().expect("Failed to lock position");
+            pkt._type = ETankPacketType::NetGamePacketState;
+            pkt.vector_x = position.x;
+            pkt.vector_y = position.y;
+            pkt.int_x = -1;
+            pkt.int_y = -1;
+        }
+        pkt.flags = self.state_flags(0);
 
         if safe_check::is_connected(self) && self.is_inworld() {
-            self.send_packet_raw(&pkt);
+            self.send_packet_raw(&pkt, None);
+        }
+    }
+
+    // Walks to (x, y). When the target is blocked or has no route, the closest reachable
+    // tile within `path_tolerance` tiles of it is walked to instead
+    pub fn find_path(&self, x: u32, y: u32) -> Result<PathHandle, PathError> {
+        if !self.is_inworld() {
+            return Err(PathError::NotInWorld);
+        }
+        let (path, handle) = {
+            let _span = profiler::span(self, "path_search");
+            let (from_x, from_y) = {
+                let position = self.position.lock().expect("Failed to lock position");
+                ((position.x as u32) / 32, (position.y as u32) / 32)
+            };
+            self.pathfinder()
+                .plan(from_x, from_y, x, y, config::get_path_tolerance())?
+        };
+        self.walk_path(&path);
+        Ok(handle)
+    }
+
+    // Walks to whichever tile matching `predicate` is closest by path, returns where it ended up
+    pub fn find_path_to_nearest<F>(&self, predicate: F) -> Option<(u32, u32)>
+    where
+        F: Fn(&gtworld_r::World, &gtworld_r::Tile) -> bool,
+    {
+        let targets: Vec<(u32, u32)> = {
+            let world = self.world.read().expect("Failed to lock world");
+            world
+                .tiles
+                .iter()
+                .filter(|tile| predicate(&world, tile))
+                .map(|tile| (tile.x, tile.y))
+                .collect()
+        };
+        self.walk_to_nearest(&targets)
+    }
+
+    pub fn walk_to_nearest(&self, targets: &[(u32, u32)]) -> Option<(u32, u32)> {
+        let paths = {
+            let _span = profiler::span(self, "path_search");
+            let (x, y) = {
+                let position = self.position.lock().expect("Failed to lock position");
+                ((position.x as u32) / 32, (position.y as u32) / 32)
+            };
+            self.pathfinder().find_path_to_any(x, y, targets)?
+        };
+        self.walk_path(&paths);
+        paths.last().map(|node| (node.x, node.y))
+    }
+
+    fn walk_path(&self, paths: &[astar::Node]) {
+        // Busy first, a calibration starting now sees it and doesn't walk alongside
+        let _busy = self.busy();
+        calibration::wait(self);
+        let _span = profiler::span(self, "path_walk");
+        let delay = self.step_delay();
+        let overshoot = self
+            .humanizer
+            .lock()
+            .expect("Failed to lock humanizer")
+            .should_overshoot(paths.len());
+        let noclip = self.noclip_pathing();
+        let resyncs = self.resyncs.load(std::sync::atomic::Ordering::SeqCst);
+        let segments = {
+            let start = {
+                let position = self.position.lock().expect("Failed to lock position");
+                ((position.x / 32.0) as u32, (position.y / 32.0) as u32)
+            };
+            let max_tiles = config::get_path_segment_max().max(1);
+            self.pathfinder().smooth_path(start, paths, max_tiles)
+        };
+        let mut packets = 0;
+        let mut last_offset_x = 0;
+        for segment in segments.iter() {
+            // The path was planned from a position the server didn't agree with
+            if self.resynced_since(resyncs) {
+                self.log_info("Position resynced, abandoning the path");
+                return;
+            }
+            if noclip && !self.noclip_pathing() {
+                self.replan_without_noclip(paths);
+                return;
+            }
+            if segment.tiles == 1 {
+                last_offset_x = self.step_to(segment.x, segment.y);
+                self.humanize_delay(delay);
+                packets += 1;
+            } else {
+                match self.walk_segment(segment, delay, resyncs) {
+                    Some((offset_x, sent)) => {
+                        last_offset_x = offset_x;
+                        packets += sent;
+                    }
+                    None => {
+                        self.log_info("Position resynced mid-segment, abandoning the path");
+                        return;
+                    }
+                }
+            }
+        }
+        if packets < paths.len() {
+            self.log_debug(&format!(
+                "Walked {} tiles with {} state packets",
+                paths.len(),
+                packets
+            ));
+        }
+
+        if overshoot && last_offset_x != 0 {
+            if let Some(last) = paths.last() {
+                self.overshoot(last.x, last.y, last_offset_x, delay);
+            }
+        }
+    }
+
+    // A straight run along the ground, sent as a state packet every SEGMENT_PACKET_TILES
+    // tiles at positions interpolated from the humanized time spent rather than snapped
+    // to tiles. Returns the direction walked and the packets sent, None when the server moved
+    // the bot since `resyncs` was read, before the next packet would overwrite its position
+    fn walk_segment(&self, segment: &astar::Segment, delay: u64, resyncs: usize) -> Option<(i32, usize)> {
+        let (start_x, pos_y) = {
+            let position = self.position.lock().expect("Failed to lock position");
+            (position.x, position.y)
+        };
+        let end_x = segment.x as f32 * 32.0;
+        let offset_x = ((end_x - start_x) / 32.0).round() as i32;
+        self.face_towards(offset_x);
+        let planned = Duration::from_millis(delay * segment.tiles as u64);
+        let started = Instant::now();
+        let mut covered = 0;
+        let mut sent = 0;
+        while covered < segment.tiles {
+            let chunk = SEGMENT_PACKET_TILES.min(segment.tiles - covered);
+            self.humanize_delay(delay * chunk as u64);
+            if self.resynced_since(resyncs) {
+                return None;
+            }
+            covered += chunk;
+            // The last packet always lands exactly on the end of the segment
+            let progress = if covered == segment.tiles {
+                1.0
+            } else {
+                (started.elapsed().as_secs_f32() / planned.as_secs_f32()).min(1.0)
+            };
+            {
+                let mut position = self.position.lock().expect("Failed to lock position");
+                position.x = start_x + (end_x - start_x) * progress;
+                position.y = pos_y;
+            }
+            self.walk(0, 0, true);
+            sent += 1;
         }
+        Some((offset_x, sent))
+    }
+
+    fn resynced_since(&self, resyncs: usize) -> bool {
+        self.resyncs.load(std::sync::atomic::Ordering::SeqCst) != resyncs
     }
 
-    pub fn find_path(&self, x: u32, y: u32) {
+    // Noclip ended mid-path, the rest of it may run through blocks
+    fn replan_without_noclip(&self, paths: &[astar::Node]) {
+        let (to_x, to_y) = match paths.last() {
+            Some(last) => (last.x, last.y),
+            None => return,
+        };
+        self.log_warn("Noclip ended mid-path, planning the rest with collision");
+        let path = {
+            let (x, y) = {
+                let position = self.position.lock().expect("Failed to lock position");
+                ((position.x as u32) / 32, (position.y as u32) / 32)
+            };
+            self.pathfinder().find_path(x, y, to_x, to_y)
+        };
+        match path {
+            Some(path) => self.walk_path(&path),
+            None => self.log_warn(&format!("No route to {},{} without noclip", to_x, to_y)),
+        }
+    }
+
+    // Walks a saved route waypoint to waypoint, the bot has to be in the route's world already
+    pub fn run_route(&self, name: &str) -> Result<(), String> {
+        let route = config::get_route(name).ok_or(format!("No route named {}", name))?;
+        let world = self.world.read().expect("Failed to lock world").name.clone();
+        if !route.world.is_empty() && !route.world.eq_ignore_ascii_case(&world) {
+            return Err(format!("Route {} is for {}, not {}", name, route.world, world));
+        }
+        for &(x, y) in &route.waypoints {
+            if !self.is_inworld() {
+                return Err("Left the world".to_string());
+            }
+            let path = self
+                .plan_path(x, y, &AtomicBool::new(false))
+                .ok_or(format!("No path to waypoint {},{}", x, y))?;
+            self.walk_path(&path);
+        }
+        Ok(())
+    }
+
+    // The configured delay is tuned for base speed, items and effects that change it scale the steps
+    pub fn step_delay(&self) -> u64 {
+        self.step_delay_with(self.speed_multiplier())
+    }
+
+    // Step delay as if calibration had measured `multiplier`
+    pub fn step_delay_with(&self, multiplier: f32) -> u64 {
+        let base = config::get_findpath_delay() as f32 / multiplier.max(0.01);
+        let speed = self.state.lock().expect("Failed to lock state").character.speed;
+        if speed <= 0.0 {
+            return base as u64;
+        }
+        (base * BASE_SPEED / speed) as u64
+    }
+
+    // Measured by calibration for this session, 1 until then
+    pub fn speed_multiplier(&self) -> f32 {
+        self.temporary_data.read().unwrap().speed_multiplier.unwrap_or(1.0)
+    }
+
+    // Route from the current position without walking it, used for previews
+    pub fn plan_path(&self, x: u32, y: u32, cancel: &AtomicBool) -> Option<Vec<astar::Node>> {
         let position = {
             let position = self.position.lock().expect("Failed to lock position");
             position.clone()
         };
 
-        let paths = {
-            let astar = self.astar.lock().expect("Failed to lock astar");
-            astar.find_path((position.x as u32) / 32, (position.y as u32) / 32, x, y)
+        let astar = self.pathfinder();
+        astar.find_path_cancellable((position.x as u32) / 32, (position.y as u32) / 32, x, y, cancel)
+    }
+
+    pub(crate) fn step_to(&self, x: u32, y: u32) -> i32 {
+        let pos_y = if self.noclip_pathing() {
+            y as f32 * 32.0
+        } else {
+            get_coordinate_to_touch_ground(y as f32 * 32.0)
         };
+        let offset_x = {
+            let mut position = self.position.lock().expect("Failed to lock position");
+            let offset_x = x as i32 - (position.x / 32.0).floor() as i32;
+            position.x = x as f32 * 32.0;
+            position.y = pos_y;
+            offset_x
+        };
+        self.face_towards(offset_x);
+        self.walk(x as i32, y as i32, true);
+        offset_x
+    }
 
-        let delay = config::get_findpath_delay();
-        if let Some(paths) = paths {
-            for node in paths {
-                let pos_y = get_coordinate_to_touch_ground(node.y as f32 * 32.0);
-                {
-                    let mut position = self.position.lock().expect("Failed to lock position");
-                    position.x = node.x as f32 * 32.0;
-                    position.y = pos_y;
+    fn humanize_delay(&self, base_ms: u64) {
+        let delay = self
+            .humanizer
+            .lock()
+            .expect("Failed to lock humanizer")
+            .action_delay(base_ms);
+        thread::sleep(delay);
+    }
+
+    // Walk one tile past the target and come back, only when that tile is reachable in a single step
+    fn overshoot(&self, x: u32, y: u32, direction: i32, delay: u64) {
+        let target_x = x as i32 + direction.signum();
+        let width = self.world.read().expect("Failed to lock world").width as i32;
+        if target_x < 0 || target_x >= width {
+            return;
+        }
+        let reachable = {
+            let astar = self.pathfinder();
+            astar
+                .find_path(x, y, target_x as u32, y)
+                .map_or(false, |path| path.len() <= 2)
+        };
+        if !reachable {
+            return;
+        }
+        self.step_to(target_x as u32, y);
+        self.humanize_delay(delay);
+        self.step_to(x, y);
+    }
+
+    // None goes back to the template's preset
+    pub fn set_humanizer(&self, preset: Option<EHumanizerPreset>) {
+        let username = self.info.lock().expect("Failed to lock info").payload[0].clone();
+        config::set_bot_humanizer(username.clone(), preset);
+        let resolved = config::get_bot_config(&username).and_then(|bot_config| bot_config.humanizer);
+        self.humanizer.lock().expect("Failed to lock humanizer").preset = resolved.unwrap_or_default();
+    }
+
+    pub fn set_humanizer_seed(&self, seed: u64) {
+        let username = self.info.lock().expect("Failed to lock info").payload[0].clone();
+        self.humanizer.lock().expect("Failed to lock humanizer").reseed(seed);
+        config::update_bot_config(&username, |bot_config| bot_config.humanizer_seed = seed);
+    }
+
+    pub fn pause_automation(&self) {
+        self.temporary_data.write().unwrap().automation_paused = true;
+        self.log_warn("Automation paused");
+    }
+
+    pub fn resume_automation(&self) {
+        self.temporary_data.write().unwrap().automation_paused = false;
+        self.log_info("Automation resumed");
+    }
+
+    pub fn automation_paused(&self) -> bool {
+        self.temporary_data.read().unwrap().automation_paused
+    }
+
+    // Holds a feature between two of its steps for as long as automation is paused and
+    // `running` says it wasn't stopped meanwhile, so a pause suspends the run rather than ending it
+    pub fn wait_while_paused(&self, running: impl Fn() -> bool) {
+        while self.automation_paused() && running() {
+            thread::sleep(PAUSED_POLL);
+        }
+    }
+
+    pub fn humanizer_preset(&self) -> EHumanizerPreset {
+        self.humanizer.lock().expect("Failed to lock humanizer").preset
+    }
+
+    pub fn humanizer_seed(&self) -> u64 {
+        self.humanizer.lock().expect("Failed to lock humanizer").seed
+    }
+
+    // (item id, name, foreground count, background count) of every item placed in the world,
+    // most common first. Names are only looked up for the final list
+    pub fn world_census(&self) -> Vec<(u32, String, u32, u32)> {
+        let mut counts: HashMap<u16, (u32, u32)> = HashMap::new();
+        {
+            let world = self.world.read().expect("Failed to lock world");
+            for tile in world.tiles.iter() {
+                if tile.foreground_item_id != 0 {
+                    counts.entry(tile.foreground_item_id).or_insert((0, 0)).0 += 1;
+                }
+                if tile.background_item_id != 0 {
+                    counts.entry(tile.background_item_id).or_insert((0, 0)).1 += 1;
                 }
-                self.walk(node.x as i32, node.y as i32, true);
-                thread::sleep(Duration::from_millis(delay as u64));
             }
         }
+
+        let item_database = self.item_database.read().expect("Failed to lock item database");
+        let mut census: Vec<(u32, String, u32, u32)> = counts
+            .into_iter()
+            .map(|(id, (foreground, background))| {
+                let name = item_database
+                    .get_item(&(id as u32))
+                    .map(|item| item.name.clone())
+                    .unwrap_or_else(|| format!("Item {}", id));
+                (id as u32, name, foreground, background)
+            })
+            .collect();
+        census.sort_by(|a, b| (b.2 + b.3).cmp(&(a.2 + a.3)).then(a.0.cmp(&b.0)));
+        census
+    }
+
+    // Every drop in the world with how long ago it spawned
+    pub fn dropped_items_with_age(&self) -> Vec<(gtworld_r::DroppedItem, Duration)> {
+        drop_age::with_age(self)
+    }
+
+    pub fn main_door(&self) -> Option<(u32, u32)> {
+        let world = self.world.read().expect("Failed to lock world");
+        world
+            .tiles
+            .iter()
+            .find(|tile| tile.foreground_item_id == 6)
+            .map(|tile| (tile.x, tile.y))
+    }
+
+    // Walks to the world's spawn spot when one is set, otherwise `door_walk_off` tiles
+    // to the side of the main door
+    pub fn walk_off_door(&self) {
+        let (world_name, width) = {
+            let world = self.world.read().expect("Failed to lock world");
+            (world.name.clone(), world.width as i32)
+        };
+        let target = match config::get_spawn_spot(&world_name) {
+            Some(spot) => spot,
+            None => {
+                let offset = config::get_door_walk_off();
+                if offset == 0 {
+                    return;
+                }
+                let (door_x, door_y) = match self.main_door() {
+                    Some(door) => door,
+                    None => return,
+                };
+                let target_x = door_x as i32 + offset;
+                if target_x < 0 || target_x >= width {
+                    return;
+                }
+                (target_x as u32, door_y)
+            }
+        };
+        thread::sleep(Duration::from_millis(random::range(500, 1500)));
+        if !self.is_inworld() {
+            return;
+        }
+        if let Err(err) = self.find_path(target.0, target.1) {
+            self.log_debug(&format!("Not walking off the door: {}", err));
+        }
+    }
+
+    // Walks to the main door and enters it. Nothing is sent when it can't be reached, the
+    // server would ignore a door entered from across the world
+    pub fn enter_main_door(&self) -> Result<(), activation::ActivateError> {
+        let (door_x, door_y) = self.main_door().ok_or(activation::ActivateError::NoMainDoor)?;
+        self.find_path(door_x, door_y).map_err(activation::ActivateError::NoRoute)?;
+        let mut pkt = TankPacket::default();
+        pkt._type = ETankPacketType::NetGamePacketTileActivateRequest;
+        {
+            let position = self.position.lock().expect("Failed to lock position");
+            pkt.vector_x = position.x;
+            pkt.vector_y = position.y;
+        }
+        pkt.int_x = door_x as i32;
+        pkt.int_y = door_y as i32;
+        self.send_packet_raw(&pkt, None);
+        Ok(())
+    }
+
+    pub fn tile_activate(&self, x: u32, y: u32) -> Result<(), activation::ActivateError> {
+        activation::tile_activate(self, x, y)
+    }
+
+    pub fn enter_door(&self, x: u32, y: u32) -> Result<(), activation::ActivateError> {
+        activation::enter_door(self, x, y)
+    }
+
+    pub fn toggle_switch(&self, x: u32, y: u32) -> Result<(), activation::ActivateError> {
+        activation::toggle_switch(self, x, y)
+    }
+
+    pub fn use_checkpoint(&self, x: u32, y: u32) -> Result<(), activation::ActivateError> {
+        activation::use_checkpoint(self, x, y)
     }
 
     pub fn drop_item(&self, item_id: u32, amount: u32) {
@@ -940,22 +2336,91 @@ impl Bot {
 }
 
 fn poll(bot: Arc<Bot>) {
-    let bot_clone = Arc::clone(&bot);
-    thread::spawn(move || loop {
-        if !safe_check::is_connected(&bot_clone) {
-            break;
-        }
-        if config::get_auto_collect() {
-            bot_clone.collect();
+    supervisor::spawn(&bot, "poll", |bot_clone| {
+        let mut last_keepalive = Instant::now();
+        let mut last_stats_save = Instant::now();
+        let mut heartbeat = Heartbeat::new();
+        loop {
+            if !safe_check::is_connected(&bot_clone) {
+                bot_clone.save_persistent();
+                break;
+            }
+            if let Err(err) = bot_clone.storage.lock().unwrap().flush_if_due() {
+                bot_clone.log_error(&format!("Failed to save script storage: {}", err));
+            }
+            if let Err(err) = bot_clone.timeline.lock().unwrap().flush_if_due() {
+                bot_clone.log_error(&format!("Failed to save timeline: {}", err));
+            }
+            drop_age::prune(&bot_clone);
+            if config::get_auto_collect() && !bot_clone.automation_paused() {
+                bot_clone.collect();
+                collect::detour(&bot_clone);
+            }
+            inventory_alert::tick(&bot_clone);
+            features::auto_deposit::tick(&bot_clone);
+            if config::get_auto_reconsume() {
+                features::auto_reconsume::tick(&bot_clone);
+            }
+            chat::flush(&bot_clone);
+            let last_movement = *bot_clone.last_movement.lock().unwrap();
+            heartbeat.tick(&bot_clone, last_movement);
+            bot_clone.set_ping();
+            if last_keepalive.elapsed() >= Duration::from_secs(300) {
+                if bot_clone.state.lock().unwrap().is_ingame {
+                    telemetry::on_keepalive(&bot_clone);
+                }
+                last_keepalive = Instant::now();
+            }
+            if last_stats_save.elapsed() >= Duration::from_secs(60) {
+                let (bytes_in, bytes_out) = bot_clone.bandwidth.take_unsaved();
+                bot_clone.add_stat("bytes_in", bytes_in);
+                bot_clone.add_stat("bytes_out", bytes_out);
+                bot_clone.save_daily_stats();
+                last_stats_save = Instant::now();
+            }
+            // A running script holds the Lua lock and dispatches from bot.sleep instead
+            if let Ok(lua) = bot_clone.lua.try_lock() {
+                if let Err(err) = lua_register::dispatch_events(&lua, &bot_clone) {
+                    bot_clone.log_error(&format!("Failed to dispatch script event: {}", err));
+                }
+            }
+            timer_wheel::sleep(if bot_clone.is_inworld() { POLL_IN_WORLD } else { POLL_OUTSIDE_WORLD });
         }
-        bot_clone.set_ping();
-        thread::sleep(Duration::from_millis(100));
     });
 }
 
+// A watch hook redrawing only while the GUI shows `bot`. It holds the bot weakly, the
+// bot owns its watches
+fn repaint_when_shown<T>(bot: &Arc<Bot>) -> impl Fn(&T) + Send + Sync + 'static {
+    let bot = Arc::downgrade(bot);
+    move |_| {
+        if bot.upgrade().map_or(false, |bot| bot.shown.load(std::sync::atomic::Ordering::Relaxed)) {
+            utils::repaint::request();
+        }
+    }
+}
+
+fn today_key(offset_days: i64) -> String {
+    let config = config::get();
+    let today = utils::date::day_index(
+        utils::date::now_secs(),
+        config.stats_utc_offset,
+        config.daily_reset_hour,
+    );
+    utils::date::format_day(today + offset_days)
+}
+
 pub fn get_coordinate_to_touch_ground(y: f32) -> f32 {
     let colrect_bottom_center_y = y + 30.0;
     let block_y = ((colrect_bottom_center_y / 32.0).floor() + 1.0) * 32.0;
 
     block_y - 30.0
 }
+
+// Message type followed by the text, how every text packet goes on the wire
+fn text_packet_data(packet_type: EPacketType, message: &str) -> Vec<u8> {
+    let mut data = Vec::with_capacity(4 + message.len());
+    data.extend_from_slice(&(packet_type as u32).to_le_bytes());
+    data.extend_from_slice(message.as_bytes());
+    data
+}
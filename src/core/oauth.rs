@@ -0,0 +1,155 @@
+use crate::types::oauth_links::OAuthLinks;
+use regex::Regex;
+use std::sync::LazyLock;
+
+const HOST: &str = "login.growtopiagame.com";
+
+static LINK: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        "https://login\\.growtopiagame\\.com/(apple|google|player/growid)/(login|redirect)\\?token=[^\"]+",
+    )
+    .unwrap()
+});
+static ATTRIBUTE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"(?i)(?:href|action|data-[\w-]+)\s*=\s*(?:"([^"]*)"|'([^']*)')"#).unwrap());
+static SCRIPT: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?is)<script[^>]*>(.*?)</script>").unwrap());
+static JSON_STRING: LazyLock<Regex> = LazyLock::new(|| Regex::new(r#""((?:[^"\\]|\\.)*)""#).unwrap());
+
+// Ways of pulling the login links out of the dashboard HTML, tried in order. The page
+// changes every so often, each strategy covers a layout seen so far
+const STRATEGIES: [(&str, fn(&str) -> Vec<String>); 3] = [
+    ("regex", by_regex),
+    ("attribute scan", by_attributes),
+    ("embedded json", by_json),
+];
+
+// Each method's link from the first strategy that found one, with the names of the
+// strategies that contributed. A page that only half matches a layout still gets the
+// missing methods from the later strategies
+pub fn extract(body: &str) -> Option<(OAuthLinks, Vec<&'static str>)> {
+    let mut links = OAuthLinks::default();
+    let mut used = Vec::new();
+    for (name, strategy) in STRATEGIES {
+        let before = links.found().len();
+        for link in strategy(body) {
+            links.insert(link);
+        }
+        if links.found().len() > before {
+            used.push(name);
+        }
+        if links.is_complete() {
+            break;
+        }
+    }
+    (!links.is_empty()).then_some((links, used))
+}
+
+// Plain links with the token in the query, the layout the scraper was written against
+fn by_regex(body: &str) -> Vec<String> {
+    LINK.find_iter(body).map(|m| m.as_str().to_owned()).collect()
+}
+
+// href/action/data-* attribute values pointing at the login host, in either quote style
+// and possibly HTML escaped or relative
+fn by_attributes(body: &str) -> Vec<String> {
+    ATTRIBUTE
+        .captures_iter(body)
+        .filter_map(|captures| captures.get(1).or_else(|| captures.get(2)))
+        .map(|value| value.as_str().replace("&amp;", "&"))
+        .filter(|value| value.contains("token="))
+        .filter_map(|value| {
+            if value.contains(HOST) {
+                Some(value)
+            } else if value.starts_with('/') {
+                Some(format!("https://{}{}", HOST, value))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+// String values inside <script> blocks, for pages that build the buttons from a JSON blob
+fn by_json(body: &str) -> Vec<String> {
+    SCRIPT
+        .captures_iter(body)
+        .filter_map(|captures| captures.get(1))
+        .flat_map(|script| {
+            JSON_STRING
+                .captures_iter(script.as_str())
+                .filter_map(|captures| captures.get(1))
+                .map(|value| unescape_json(value.as_str()))
+                .filter(|value| value.contains(HOST) && value.contains("token="))
+                .collect::<Vec<String>>()
+        })
+        .collect()
+}
+
+fn unescape_json(value: &str) -> String {
+    value
+        .replace("\\/", "/")
+        .replace("\\u0026", "&")
+        .replace("\\u003d", "=")
+        .replace("\\u003D", "=")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const APPLE: &str = "https://login.growtopiagame.com/apple/redirect?token=YXBwbGUtdG9rZW4";
+    const GOOGLE: &str = "https://login.growtopiagame.com/google/redirect?token=Z29vZ2xlLXRva2Vu";
+    const LEGACY: &str = "https://login.growtopiagame.com/player/growid/login?token=Z3Jvd2lkLXRva2Vu";
+
+    fn fixture(name: &str) -> &'static str {
+        match name {
+            "plain_links" => include_str!("../../tests/fixtures/dashboard/plain_links.html"),
+            "relative_attributes" => include_str!("../../tests/fixtures/dashboard/relative_attributes.html"),
+            "json_blob" => include_str!("../../tests/fixtures/dashboard/json_blob.html"),
+            "mixed" => include_str!("../../tests/fixtures/dashboard/mixed.html"),
+            "maintenance" => include_str!("../../tests/fixtures/dashboard/maintenance.html"),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn plain_links_need_only_the_regex() {
+        let (links, used) = extract(fixture("plain_links")).unwrap();
+        assert_eq!(links.apple.as_deref(), Some(APPLE));
+        assert_eq!(links.google.as_deref(), Some(GOOGLE));
+        assert_eq!(links.legacy.as_deref(), Some(LEGACY));
+        assert_eq!(used, ["regex"]);
+    }
+
+    #[test]
+    fn relative_escaped_attributes_are_resolved() {
+        let (links, used) = extract(fixture("relative_attributes")).unwrap();
+        assert_eq!(links.apple, Some(format!("{}&lang=en", APPLE)));
+        assert_eq!(links.google, Some(format!("{}&lang=en", GOOGLE)));
+        assert_eq!(links.legacy, Some(format!("{}&lang=en", LEGACY)));
+        assert_eq!(used, ["attribute scan"]);
+    }
+
+    #[test]
+    fn json_blob_is_unescaped() {
+        let (links, used) = extract(fixture("json_blob")).unwrap();
+        assert_eq!(links.apple.as_deref(), Some(APPLE));
+        assert_eq!(links.google.as_deref(), Some(GOOGLE));
+        assert_eq!(links.legacy, Some(format!("{}&lang=en", LEGACY)));
+        assert_eq!(used, ["embedded json"]);
+    }
+
+    #[test]
+    fn partial_match_falls_through_to_later_strategies() {
+        let (links, used) = extract(fixture("mixed")).unwrap();
+        assert_eq!(links.legacy.as_deref(), Some(LEGACY));
+        assert_eq!(links.apple.as_deref(), Some(APPLE));
+        assert_eq!(links.google.as_deref(), Some(GOOGLE));
+        assert_eq!(used, ["regex", "embedded json"]);
+    }
+
+    #[test]
+    fn page_without_links_is_none() {
+        assert_eq!(extract(fixture("maintenance")), None);
+    }
+}
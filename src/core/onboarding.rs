@@ -0,0 +1,190 @@
+use super::quest;
+use crate::core::Bot;
+use crate::types::eonboarding_dialog::EOnboardingDialog;
+use crate::types::epacket_type::EPacketType;
+use crate::utils::{config, textparse};
+
+// Buttons that move a consent or age dialog along, and the ones that dismiss a rating
+// prompt. Matched as whole words of the label or key, so "no" doesn't pick "Rate now"
+const ACCEPT_WORDS: [&str; 5] = ["accept", "agree", "continue", "submit", "confirm"];
+const DISMISS_WORDS: [&str; 6] = ["later", "no thanks", "not now", "close", "cancel", "no"];
+
+// Answers the age gate, GDPR and rating dialogs of a fresh account, once per bot. Returns
+// whether the dialog was one of them
+pub fn handle_dialog(bot: &Bot, message: &str) -> bool {
+    if !config::get_auto_onboarding() {
+        return false;
+    }
+    let name = quest::dialog_name(message).unwrap_or_default();
+    let kind = match EOnboardingDialog::detect(&name, &heading(message)) {
+        Some(kind) => kind,
+        None => return false,
+    };
+    let username = bot.info.lock().unwrap().payload[0].clone();
+    let answered = config::get_bot_config(&username)
+        .map_or(false, |bot_config| bot_config.answered_dialogs.iter().any(|key| key == kind.key()));
+    if answered {
+        bot.log_warn(&format!("Got the {} dialog again, it was answered before so it's left alone", kind.name()));
+        return true;
+    }
+
+    let age = config::get_onboarding_age();
+    match reply(kind, &name, message, age, config::get_onboarding_consent()) {
+        Some(reply) => bot.send_packet(EPacketType::NetMessageGenericText, reply),
+        // Clicking whatever comes first could as well rate the game or refuse the terms
+        None => {
+            bot.log_warn(&format!("No button of the {} dialog fits, it's left for you", kind.name()));
+            return true;
+        }
+    }
+    bot.log_info(&format!("Answered the {} dialog", kind.name()));
+
+    if kind == EOnboardingDialog::AgeGate {
+        // Later logins report the same age the account was registered with
+        let (token, login_info) = {
+            let mut info = bot.info.lock().unwrap();
+            info.login_info.player_age = age.to_string();
+            (info.token.clone(), info.login_info.to_string())
+        };
+        config::save_token_to_bot(username.clone(), token, login_info);
+    }
+    config::update_bot_config(&username, |bot_config| {
+        bot_config.answered_dialogs.push(kind.key().to_string());
+    });
+    true
+}
+
+// The big label the dialog opens with, written by the server
+fn heading(message: &str) -> String {
+    message
+        .lines()
+        .find_map(|line| match line.split('|').collect::<Vec<_>>().as_slice() {
+            ["add_label_with_icon" | "add_label", "big", text, ..] => Some(textparse::strip_color(text)),
+            _ => None,
+        })
+        .unwrap_or_default()
+}
+
+// Whether `phrase` appears as whole words in `text`
+fn has_words(text: &str, phrase: &str) -> bool {
+    let split = |text: &str| -> Vec<String> {
+        text.split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .map(str::to_lowercase)
+            .collect()
+    };
+    let (words, phrase) = (split(text), split(phrase));
+    !phrase.is_empty() && words.windows(phrase.len()).any(|window| window == phrase.as_slice())
+}
+
+// Every input of the dialog filled in, embedded values echoed back and the button that
+// fits the kind of dialog clicked. None when no button fits
+fn reply(kind: EOnboardingDialog, name: &str, message: &str, age: u32, consent: bool) -> Option<String> {
+    let mut reply = format!("action|dialog_return\ndialog_name|{}\n", name);
+    let mut buttons: Vec<(String, String)> = Vec::new();
+    for line in message.lines() {
+        let parts: Vec<&str> = line.split('|').collect();
+        match parts.as_slice() {
+            ["embed_data", key, value, ..] => reply.push_str(&format!("{}|{}\n", key, value)),
+            ["add_text_input", key, ..] => {
+                let value = if kind == EOnboardingDialog::AgeGate { age.to_string() } else { String::new() };
+                reply.push_str(&format!("{}|{}\n", key, value));
+            }
+            ["add_checkbox", key, label, ..] => {
+                // The terms have to be accepted to get past the dialog, anything else is optional
+                let label = textparse::strip_color(label).to_lowercase();
+                let required = label.contains("terms") || label.contains("privacy");
+                reply.push_str(&format!("{}|{}\n", key, (required || consent) as u8));
+            }
+            ["add_button", key, label, ..] => {
+                buttons.push((key.to_string(), textparse::strip_color(label)));
+            }
+            _ => {}
+        }
+    }
+
+    let words: &[&str] = if kind == EOnboardingDialog::Rating { &DISMISS_WORDS } else { &ACCEPT_WORDS };
+    let (key, _) = words
+        .iter()
+        .find_map(|word| buttons.iter().find(|(key, label)| has_words(label, word) || has_words(key, word)))?;
+    reply.push_str(&format!("buttonClicked|{}\n", key));
+    Some(reply)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(name: &str) -> &'static str {
+        match name {
+            "age_gate" => include_str!("../../tests/fixtures/onboarding/age_gate.txt"),
+            "gdpr" => include_str!("../../tests/fixtures/onboarding/gdpr.txt"),
+            "gdpr_no_button" => include_str!("../../tests/fixtures/onboarding/gdpr_no_button.txt"),
+            "rating" => include_str!("../../tests/fixtures/onboarding/rating.txt"),
+            "rating_plain_no" => include_str!("../../tests/fixtures/onboarding/rating_plain_no.txt"),
+            "sign" => include_str!("../../tests/fixtures/onboarding/sign.txt"),
+            _ => unreachable!(),
+        }
+    }
+
+    fn detect(name: &str) -> Option<EOnboardingDialog> {
+        let message = fixture(name);
+        EOnboardingDialog::detect(&quest::dialog_name(message).unwrap_or_default(), &heading(message))
+    }
+
+    fn answer(name: &str, consent: bool) -> Option<String> {
+        let message = fixture(name);
+        let dialog_name = quest::dialog_name(message).unwrap_or_default();
+        reply(detect(name).unwrap(), &dialog_name, message, 20, consent)
+    }
+
+    #[test]
+    fn detects_each_dialog() {
+        assert_eq!(detect("age_gate"), Some(EOnboardingDialog::AgeGate));
+        assert_eq!(detect("gdpr"), Some(EOnboardingDialog::Gdpr));
+        assert_eq!(detect("rating"), Some(EOnboardingDialog::Rating));
+        assert_eq!(detect("rating_plain_no"), Some(EOnboardingDialog::Rating));
+    }
+
+    #[test]
+    fn ignores_player_text_in_the_body() {
+        assert_eq!(detect("sign"), None);
+    }
+
+    #[test]
+    fn fills_in_the_age() {
+        let reply = answer("age_gate", false).unwrap();
+        assert!(reply.starts_with("action|dialog_return\ndialog_name|age_gate\n"));
+        assert!(reply.contains("player_age|20\n"));
+        assert!(reply.contains("session|83127\n"));
+        assert!(reply.ends_with("buttonClicked|age_confirm\n"));
+    }
+
+    #[test]
+    fn accepts_the_terms_and_only_consents_when_asked() {
+        let reply = answer("gdpr", false).unwrap();
+        assert!(reply.contains("accept_terms|1\n"));
+        assert!(reply.contains("personalised_ads|0\n"));
+        assert!(reply.ends_with("buttonClicked|gdpr_accept\n"));
+        assert!(answer("gdpr", true).unwrap().contains("personalised_ads|1\n"));
+    }
+
+    #[test]
+    fn dismisses_ratings_without_rating() {
+        assert!(answer("rating", false).unwrap().ends_with("buttonClicked|rate_later\n"));
+        assert!(answer("rating_plain_no", false).unwrap().ends_with("buttonClicked|rate_dismiss\n"));
+    }
+
+    #[test]
+    fn leaves_a_dialog_without_a_fitting_button() {
+        assert_eq!(answer("gdpr_no_button", false), None);
+    }
+
+    #[test]
+    fn matches_whole_words() {
+        assert!(has_words("Not now", "not now"));
+        assert!(has_words("rate_later", "later"));
+        assert!(!has_words("Rate now", "no"));
+        assert!(!has_words("Book", "ok"));
+    }
+}
@@ -0,0 +1,253 @@
+use rusty_enet as enet;
+use std::cell::Cell;
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+// Packets a lane holds before new ones are dropped. Far more than a connected bot ever
+// has waiting, only a stuck event loop gets there
+const MAX_USER: usize = 64;
+const MAX_BACKGROUND: usize = 1024;
+
+thread_local! {
+    static USER_INITIATED: Cell<bool> = const { Cell::new(false) };
+}
+
+// Runs `f` with every packet it sends on this thread going through the user lane.
+// Meant for GUI actions, which already run on their own spawned thread
+pub fn user_initiated<R>(f: impl FnOnce() -> R) -> R {
+    let previous = USER_INITIATED.with(|flag| flag.replace(true));
+    let result = f();
+    USER_INITIATED.with(|flag| flag.set(previous));
+    result
+}
+
+// Actions the server kicks for when they come in faster than their interval, limited
+// whichever lane they are in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Action {
+    TileChange,
+    JoinRequest,
+}
+
+impl Action {
+    fn of(data: &[u8]) -> Option<Action> {
+        let message_type = u32::from_le_bytes(data.get(..4)?.try_into().ok()?);
+        let body = &data[4..];
+        match message_type {
+            4 if body.first() == Some(&3) => Some(Action::TileChange),
+            2 | 3 if body.starts_with(b"action|join_request") => Some(Action::JoinRequest),
+            _ => None,
+        }
+    }
+
+    fn min_interval(self) -> Duration {
+        match self {
+            Action::TileChange => Duration::from_millis(100),
+            Action::JoinRequest => Duration::from_millis(1000),
+        }
+    }
+}
+
+struct Queued {
+    channel: u8,
+    pkt: enet::Packet,
+    action: Option<Action>,
+}
+
+// Packets waiting for the event loop. A user action goes ahead of automation traffic,
+// but each lane stays in order: a packet held back by its action's rate limit holds
+// back everything queued behind it in the same lane
+#[derive(Default)]
+pub struct Outgoing {
+    user: VecDeque<Queued>,
+    background: VecDeque<Queued>,
+    last_sent: HashMap<Action, Instant>,
+}
+
+impl Outgoing {
+    // False when the lane is full and the packet was dropped
+    pub fn push(&mut self, channel: u8, pkt: enet::Packet) -> bool {
+        let (lane, limit) = if USER_INITIATED.with(|flag| flag.get()) {
+            (&mut self.user, MAX_USER)
+        } else {
+            (&mut self.background, MAX_BACKGROUND)
+        };
+        if lane.len() >= limit {
+            return false;
+        }
+        let action = Action::of(pkt.data());
+        lane.push_back(Queued { channel, pkt, action });
+        true
+    }
+
+    // Every packet that can go out at `now`, user lane first
+    pub fn take_batch(&mut self, now: Instant) -> Vec<(u8, enet::Packet)> {
+        let mut batch = Vec::new();
+        loop {
+            let lane = if self.wait(self.user.front(), now).is_zero() {
+                &mut self.user
+            } else if self.wait(self.background.front(), now).is_zero() {
+                &mut self.background
+            } else {
+                break;
+            };
+            let queued = lane.pop_front().unwrap();
+            if let Some(action) = queued.action {
+                self.last_sent.insert(action, now);
+            }
+            batch.push((queued.channel, queued.pkt));
+        }
+        batch
+    }
+
+    // How long until the next packet can be sent, None when nothing is queued
+    pub fn ready_in(&self, now: Instant) -> Option<Duration> {
+        if self.is_empty() {
+            return None;
+        }
+        Some(self.wait(self.user.front(), now).min(self.wait(self.background.front(), now)))
+    }
+
+    // Zero when the packet can be sent now, Duration::MAX when there is none
+    fn wait(&self, queued: Option<&Queued>, now: Instant) -> Duration {
+        let queued = match queued {
+            Some(queued) => queued,
+            None => return Duration::MAX,
+        };
+        let last = queued.action.and_then(|action| Some((action, *self.last_sent.get(&action)?)));
+        match last {
+            Some((action, sent_at)) => (sent_at + action.min_interval()).saturating_duration_since(now),
+            None => Duration::ZERO,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.user.clear();
+        self.background.clear();
+        self.last_sent.clear();
+    }
+
+    pub fn user_pending(&self) -> bool {
+        !self.user.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.user.len() + self.background.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.user.is_empty() && self.background.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tile_change(x: u8) -> enet::Packet {
+        let mut data = 4u32.to_le_bytes().to_vec();
+        data.extend([3, x]);
+        data.resize(60, 0);
+        enet::Packet::reliable(data.as_slice())
+    }
+
+    fn text(message: &str) -> enet::Packet {
+        let mut data = 3u32.to_le_bytes().to_vec();
+        data.extend_from_slice(message.as_bytes());
+        enet::Packet::reliable(data.as_slice())
+    }
+
+    fn warp() -> enet::Packet {
+        text("action|join_request\nname|START\ninvitedWorld|0\n")
+    }
+
+    fn sent(batch: Vec<(u8, enet::Packet)>) -> Vec<Vec<u8>> {
+        batch.into_iter().map(|(_, pkt)| pkt.data().to_vec()).collect()
+    }
+
+    #[test]
+    fn user_lane_goes_first() {
+        let mut outgoing = Outgoing::default();
+        outgoing.push(0, text("action|one"));
+        outgoing.push(0, text("action|two"));
+        user_initiated(|| outgoing.push(0, text("action|user")));
+        assert!(outgoing.user_pending());
+        let batch = sent(outgoing.take_batch(Instant::now()));
+        let expected: Vec<Vec<u8>> =
+            ["action|user", "action|one", "action|two"].iter().map(|m| text(m).data().to_vec()).collect();
+        assert_eq!(batch, expected);
+        assert_eq!(outgoing.len(), 0);
+    }
+
+    #[test]
+    fn rate_limited_packet_holds_back_its_lane() {
+        let start = Instant::now();
+        let mut outgoing = Outgoing::default();
+        outgoing.push(0, tile_change(1));
+        outgoing.push(0, text("action|between"));
+        outgoing.push(0, tile_change(2));
+        outgoing.push(0, text("action|after"));
+        let first = sent(outgoing.take_batch(start));
+        assert_eq!(first, vec![tile_change(1).data().to_vec(), text("action|between").data().to_vec()]);
+        // Nothing overtakes the second tile change while it waits
+        assert!(outgoing.take_batch(start + Duration::from_millis(50)).is_empty());
+        assert_eq!(outgoing.ready_in(start + Duration::from_millis(50)), Some(Duration::from_millis(50)));
+        let second = sent(outgoing.take_batch(start + Duration::from_millis(100)));
+        assert_eq!(second, vec![tile_change(2).data().to_vec(), text("action|after").data().to_vec()]);
+        assert_eq!(outgoing.ready_in(start + Duration::from_millis(100)), None);
+    }
+
+    #[test]
+    fn user_actions_share_the_rate_limit() {
+        let start = Instant::now();
+        let mut outgoing = Outgoing::default();
+        outgoing.push(0, tile_change(1));
+        assert_eq!(outgoing.take_batch(start).len(), 1);
+        user_initiated(|| outgoing.push(0, tile_change(2)));
+        outgoing.push(0, tile_change(3));
+        assert!(outgoing.take_batch(start + Duration::from_millis(10)).is_empty());
+        // Once the window is over the user's punch goes out before the farm's
+        let batch = sent(outgoing.take_batch(start + Duration::from_millis(100)));
+        assert_eq!(batch, vec![tile_change(2).data().to_vec()]);
+    }
+
+    // A farm loop keeps the background lane full of tile changes, one more per 20ms
+    // tick. A warp clicked in the middle has to go out within one rate-limit window
+    #[test]
+    fn user_warp_beats_saturated_farm_loop() {
+        let start = Instant::now();
+        let tick = Duration::from_millis(20);
+        let mut outgoing = Outgoing::default();
+        for x in 0..200 {
+            assert!(outgoing.push(0, tile_change(x as u8)));
+        }
+        let warp_data = warp().data().to_vec();
+        let mut clicked_at = None;
+        let mut warped_at = None;
+        for step in 0..100u32 {
+            let now = start + tick * step;
+            outgoing.push(0, tile_change(step as u8));
+            if step == 37 {
+                user_initiated(|| outgoing.push(0, warp()));
+                clicked_at = Some(now);
+            }
+            if sent(outgoing.take_batch(now)).contains(&warp_data) {
+                warped_at = Some(now);
+                break;
+            }
+        }
+        let waited = warped_at.expect("warp never sent") - clicked_at.unwrap();
+        assert!(waited <= Action::JoinRequest.min_interval(), "warp waited {:?}", waited);
+        assert!(outgoing.len() > 100, "farm traffic should still be queued");
+    }
+
+    #[test]
+    fn full_lane_drops() {
+        let mut outgoing = Outgoing::default();
+        for _ in 0..MAX_USER {
+            assert!(user_initiated(|| outgoing.push(0, text("action|user"))));
+        }
+        assert!(!user_initiated(|| outgoing.push(0, text("action|user"))));
+        assert!(outgoing.push(0, text("action|background")));
+    }
+}
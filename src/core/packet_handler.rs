@@ -1,19 +1,26 @@
-use super::{inventory::InventoryItem, Bot};
+use super::{inventory::InventoryItem, world_snapshot, Bot};
 use crate::{
-    core::variant_handler,
+    core::{
+        calibration, character_state, desync, disconnect, drop_age, items, particles, telemetry, variant_handler, version,
+    },
     types::{
-        epacket_type::EPacketType, etank_packet_type::ETankPacketType, tank_packet::TankPacket,
+        bot_event::BotEvent, edisconnect_reason::EDisconnectReason, epacket_type::EPacketType,
+        etank_packet_type::ETankPacketType, tank_packet::TankPacket,
     },
     utils,
 };
-use flate2::read::ZlibDecoder;
 use gtworld_r::TileType;
-use regex::Regex;
-use std::io::{Cursor, Read};
+use std::io::Cursor;
 use std::time::Instant;
+use std::collections::BTreeSet;
+use std::sync::Mutex;
 use std::{fs, sync::Arc};
 
-pub fn handle(bot: Arc<Bot>, packet_type: EPacketType, data: &[u8]) {
+static LOGGED_UNKNOWN_TYPES: Mutex<BTreeSet<u8>> = Mutex::new(BTreeSet::new());
+// Pickups at least this big show up on the activity timeline
+const LARGE_GAIN: u8 = 100;
+
+pub fn handle(bot: Arc<Bot>, packet_type: EPacketType, data: &[u8], logged: bool) {
     match packet_type {
         EPacketType::NetMessageServerHello => {
             let is_redirecting = bot.state.lock().unwrap().is_redirecting;
@@ -27,10 +34,14 @@ pub fn handle(bot: Arc<Bot>, packet_type: EPacketType, data: &[u8]) {
                 };
                 bot.send_packet(EPacketType::NetMessageGenericText, message);
             } else {
-                let token = bot.info.lock().unwrap().token.clone();
+                bot.state.lock().unwrap().logon_started();
+                let (protocol, token) = {
+                    let info = bot.info.lock().unwrap();
+                    (info.login_info.protocol.clone(), info.token.clone())
+                };
                 let message = format!(
                     "protocol|{}\nltoken|{}\nplatformID|{}\n",
-                    209, token, "0,1,1"
+                    protocol, token, "0,1,1"
                 );
                 bot.send_packet(EPacketType::NetMessageGenericText, message);
             }
@@ -38,7 +49,10 @@ pub fn handle(bot: Arc<Bot>, packet_type: EPacketType, data: &[u8]) {
         EPacketType::NetMessageGenericText => {}
         EPacketType::NetMessageGameMessage => {
             let message = String::from_utf8_lossy(&data);
-            bot.log_info(format!("Message: {}", message).as_str());
+            if logged {
+                bot.log_info(format!("Message: {}", message).as_str());
+            }
+            disconnect::note_message(&bot, &message);
 
             if message.contains("logon_fail") {
                 {
@@ -67,6 +81,14 @@ pub fn handle(bot: Arc<Bot>, packet_type: EPacketType, data: &[u8]) {
                 }
                 bot.disconnect();
             }
+            if message.contains("logged on from another location") {
+                bot.log_warn("Account logged on from another location, not reconnecting");
+                {
+                    let mut state = bot.state.lock().unwrap();
+                    state.is_running = false;
+                }
+                bot.disconnect();
+            }
             if message.contains("has been suspended") {
                 let mut state = bot.state.lock().unwrap();
                 state.is_running = false;
@@ -78,26 +100,23 @@ pub fn handle(bot: Arc<Bot>, packet_type: EPacketType, data: &[u8]) {
                 temp.timeout = 60;
                 bot.sleep();
             }
-            if message.contains("UPDATE REQUIRED") {
-                let re = Regex::new(r"\$V(\d+\.\d+)").unwrap();
-                if let Some(caps) = re.captures(&message) {
-                    let version = caps.get(1).unwrap().as_str();
-                    bot.log_warn(format!("Update required: {}, updating...", version).as_str());
-                    {
-                        let mut info = bot.info.lock().unwrap();
-                        info.login_info.game_version = version.to_string();
-                    }
-                    utils::config::set_game_version(version.to_string());
-                    let username = bot.info.lock().unwrap().payload[0].clone();
-                    utils::config::save_token_to_bot(username, "".to_string(), "".to_string());
-                }
+            if version::is_update_required(&message) {
+                version::handle_update_required(&bot, &message);
             }
         }
-        EPacketType::NetMessageGamePacket => match bincode::deserialize::<TankPacket>(&data) {
+        EPacketType::NetMessageGamePacket => match TankPacket::from_bytes(&data) {
             Ok(tank_packet) => {
-                bot.log_info(format!("Received: {:?}", tank_packet._type).as_str());
+                if logged {
+                    bot.log_info(format!("Received: {}", tank_packet._type).as_str());
+                }
                 match tank_packet._type {
                     ETankPacketType::NetGamePacketState => {
+                        // Our own state reflected back, knockback and rejected moves show up here
+                        if tank_packet.net_id == bot.state.lock().unwrap().net_id {
+                            desync::check(&bot, tank_packet.vector_x, tank_packet.vector_y, "state");
+                            calibration::note_ack(&bot, tank_packet.vector_x, tank_packet.vector_y);
+                            return;
+                        }
                         let mut players = bot.players.lock().unwrap();
                         for player in players.iter_mut() {
                             if player.net_id == tank_packet.net_id {
@@ -107,6 +126,16 @@ pub fn handle(bot: Arc<Bot>, packet_type: EPacketType, data: &[u8]) {
                             }
                         }
                     }
+                    ETankPacketType::NetGamePacketSendParticleEffect
+                    | ETankPacketType::NetGamePacketSendParticleEffectV2 => {
+                        particles::handle(&bot, &tank_packet);
+                    }
+                    ETankPacketType::NetGamePacketDisconnect => {
+                        disconnect::note_packet(&bot);
+                    }
+                    ETankPacketType::NetGamePacketSetCharacterState => {
+                        character_state::handle(&bot, &tank_packet);
+                    }
                     ETankPacketType::NetGamePacketCallFunction => {
                         variant_handler::handle(bot, &tank_packet, &data[56..]);
                     }
@@ -121,31 +150,38 @@ pub fn handle(bot: Arc<Bot>, packet_type: EPacketType, data: &[u8]) {
                             ..Default::default()
                         };
 
-                        bot.send_packet_raw(&packet);
+                        bot.send_packet_raw(&packet, None);
                         bot.log_info("Replied to ping request");
                     }
                     ETankPacketType::NetGamePacketSendInventoryState => {
                         bot.inventory.lock().unwrap().parse(&data[56..]);
                     }
                     ETankPacketType::NetGamePacketSendMapData => {
+                        bot.add_stat("worlds_entered", 1);
                         fs::write("world.dat", &data[56..]).unwrap();
                         {
                             let mut world = bot.world.write().unwrap();
                             world.parse(&data[56..]);
+                            bot.world_name.set(world.name.clone());
                         }
                         bot.players.lock().unwrap().clear();
+                        bot.temporary_data.write().unwrap().unreachable_drops.clear();
+                        drop_age::record_loaded(&bot);
                         bot.astar.lock().unwrap().update(&bot);
                         bot.send_packet(
                             EPacketType::NetMessageGenericText,
                             "action|getDRAnimations\n".to_string(),
                         );
+                        let world_name = bot.world.read().unwrap().name.clone();
+                        if !world_snapshot::queue_save(&world_name, &data[56..]) {
+                            bot.log_debug("World snapshot writer is behind, not saving this visit");
+                        }
+                        telemetry::on_world_enter(&bot, &world_name);
+                        calibration::spawn(&bot, "entered world");
                     }
                     ETankPacketType::NetGamePacketTileChangeRequest => {
-                        let should_update_inventory = {
-                            let state = bot.state.lock().unwrap();
-                            state.net_id == tank_packet.net_id && tank_packet.value != 18
-                        };
-
+                        let own = bot.state.lock().unwrap().net_id == tank_packet.net_id;
+                        let should_update_inventory = own && tank_packet.value != 18;
                         if should_update_inventory {
                             let mut remove_item = None;
                             {
@@ -165,6 +201,9 @@ pub fn handle(bot: Arc<Bot>, packet_type: EPacketType, data: &[u8]) {
                             }
                         }
 
+                        // 18 is the fist, a punch that didn't empty anything (air, bedrock
+                        // echoes) isn't a broken block
+                        let mut broke_block = false;
                         {
                             let mut world = bot.world.write().unwrap();
                             if let Some(tile) = world
@@ -173,8 +212,10 @@ pub fn handle(bot: Arc<Bot>, packet_type: EPacketType, data: &[u8]) {
                                 if tank_packet.value == 18 {
                                     if tile.foreground_item_id != 0 {
                                         tile.foreground_item_id = 0;
-                                    } else {
+                                        broke_block = true;
+                                    } else if tile.background_item_id != 0 {
                                         tile.background_item_id = 0;
+                                        broke_block = true;
                                     }
                                 } else {
                                     if let Some(item) = bot
@@ -210,6 +251,9 @@ pub fn handle(bot: Arc<Bot>, packet_type: EPacketType, data: &[u8]) {
                                 }
                             }
                         }
+                        if broke_block && own {
+                            bot.add_stat("blocks_broken", 1);
+                        }
 
                         bot.astar.lock().unwrap().update(&bot);
                     }
@@ -227,6 +271,7 @@ pub fn handle(bot: Arc<Bot>, packet_type: EPacketType, data: &[u8]) {
                                 uid: world.dropped.last_dropped_item_uid + 1,
                             };
 
+                            drop_age::record_spawn(&bot, item.uid);
                             world.dropped.items.push(item);
                             world.dropped.last_dropped_item_uid += 1;
                             world.dropped.items_count += 1;
@@ -247,8 +292,10 @@ pub fn handle(bot: Arc<Bot>, packet_type: EPacketType, data: &[u8]) {
                                 if obj.uid == tank_packet.value {
                                     if tank_packet.net_id == bot.state.lock().unwrap().net_id {
                                         if obj.id == 112 {
-                                            bot.state.lock().unwrap().gems += obj.count as i32;
+                                            bot.gems.set(bot.gems.get() + obj.count as i32);
+                                            bot.add_stat("gems_collected", obj.count as u64);
                                         } else {
+                                            bot.add_stat("items_collected", obj.count as u64);
                                             let mut inventory = bot.inventory.lock().unwrap();
                                             if let Some(item) = inventory.items.get_mut(&obj.id) {
                                                 let temp = item.amount + obj.count;
@@ -261,6 +308,12 @@ pub fn handle(bot: Arc<Bot>, packet_type: EPacketType, data: &[u8]) {
                                                 };
                                                 inventory.items.insert(obj.id, item);
                                             }
+                                            if obj.count >= LARGE_GAIN {
+                                                bot.emit(BotEvent::ItemGain {
+                                                    item_id: obj.id as u32,
+                                                    amount: obj.count as u32,
+                                                });
+                                            }
                                         }
                                     }
                                     remove_index = Some(i);
@@ -268,8 +321,9 @@ pub fn handle(bot: Arc<Bot>, packet_type: EPacketType, data: &[u8]) {
                                 }
                             }
                             if let Some(i) = remove_index {
-                                world.dropped.items.remove(i);
+                                let obj = world.dropped.items.remove(i);
                                 world.dropped.items_count -= 1;
+                                drop_age::forget(&bot, obj.uid);
                             }
                         }
                     }
@@ -301,15 +355,24 @@ pub fn handle(bot: Arc<Bot>, packet_type: EPacketType, data: &[u8]) {
                             .write()
                             .unwrap()
                             .update_tile(tile, &mut cursor, true);
+                        bot.temporary_data.write().unwrap().last_tile_update = Some((
+                            tank_packet.int_x as u32,
+                            tank_packet.int_y as u32,
+                            Instant::now(),
+                        ));
                     }
                     ETankPacketType::NetGamePacketSendItemDatabaseData => {
-                        let data = &data[56..];
-                        let mut decoder = ZlibDecoder::new(data);
-                        let mut data = Vec::new();
-                        decoder.read_to_end(&mut data).unwrap();
-                        fs::write("items.dat", &data).unwrap();
-                        let mut item_database = bot.item_database.write().unwrap();
-                        *item_database = gtitem_r::load_from_memory(&data).unwrap();
+                        items::handle_data(&bot, &tank_packet, &data[56..]);
+                    }
+                    ETankPacketType::Unknown(value) => {
+                        // Only the first sighting is worth a warning, these tend to come in bursts
+                        if LOGGED_UNKNOWN_TYPES.lock().unwrap().insert(value) {
+                            bot.log_warn(&format!(
+                                "Unhandled tank packet type {} ({} bytes)",
+                                value,
+                                data.len()
+                            ));
+                        }
                     }
                     _ => {}
                 }
@@ -318,19 +381,42 @@ pub fn handle(bot: Arc<Bot>, packet_type: EPacketType, data: &[u8]) {
                 bot.log_error(format!("Failed to deserialize TankPacket: {:?}", data[0]).as_str());
             }
         },
+        EPacketType::NetMessageError => {
+            let message = String::from_utf8_lossy(&data);
+            bot.log_error(&format!("Server error: {}", message));
+            match disconnect::note_error(&bot, &message) {
+                Some(EDisconnectReason::Banned) => {
+                    {
+                        let mut state = bot.state.lock().unwrap();
+                        state.is_running = false;
+                        state.is_banned = true;
+                    }
+                    bot.disconnect();
+                }
+                Some(EDisconnectReason::LoggedInElsewhere) => {
+                    bot.state.lock().unwrap().is_running = false;
+                    bot.disconnect();
+                }
+                _ => {}
+            }
+        }
         EPacketType::NetMessageClientLogRequest => {
             let message = String::from_utf8_lossy(&data);
-            bot.log_info(format!("Message: {}", message).as_str());
+            bot.log_info(format!("Client log requested: {}", message).as_str());
+            telemetry::on_log_request(&bot);
         }
         EPacketType::NetMessageTrack => {
             let message = String::from_utf8_lossy(&data);
             let data = utils::textparse::parse_and_store_as_map(&message);
-            if data.contains_key("Level") {
-                let level = data.get("Level").unwrap();
-                let mut state = bot.state.lock().unwrap();
-                state.level = level.parse().unwrap();
+            if let Some(level) = data.get("Level").and_then(|level| level.parse().ok()) {
+                bot.state.lock().unwrap().level = level;
             }
+            telemetry::on_track(&bot, &data);
+        }
+        // Already counted by id when it was received
+        EPacketType::NetMessageUnknown => {}
+        other => {
+            bot.add_stat(&format!("packets_unhandled_{}", other.name()), 1);
         }
-        _ => (),
     }
 }
@@ -0,0 +1,41 @@
+use super::Bot;
+use crate::types::{bot_event::BotEvent, particle::Particle, tank_packet::TankPacket};
+use std::time::{Duration, Instant};
+
+const MAX_HISTORY: usize = 200;
+// Fireworks and similar send bursts of the same effect, scripts get at most one per window
+const EVENT_INTERVAL: Duration = Duration::from_millis(250);
+
+// Both packet versions carry the position in vector_x/y and the effect id in vector_y2
+pub fn handle(bot: &Bot, packet: &TankPacket) {
+    let particle = Particle {
+        effect_id: packet.vector_y2 as u32,
+        x: packet.vector_x,
+        y: packet.vector_y,
+        received_at: Instant::now(),
+    };
+
+    let emit = {
+        let mut temp = bot.temporary_data.write().unwrap();
+        if temp.particles.len() >= MAX_HISTORY {
+            temp.particles.pop_front();
+        }
+        temp.particles.push_back(particle.clone());
+
+        let last = temp.particle_events.get(&particle.effect_id);
+        if last.map_or(true, |last| last.elapsed() >= EVENT_INTERVAL) {
+            temp.particle_events.insert(particle.effect_id, particle.received_at);
+            true
+        } else {
+            false
+        }
+    };
+
+    if emit {
+        bot.emit(BotEvent::Particle {
+            effect_id: particle.effect_id,
+            x: particle.x,
+            y: particle.y,
+        });
+    }
+}
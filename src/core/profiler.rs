@@ -0,0 +1,48 @@
+use super::Bot;
+use std::time::Duration;
+#[cfg(feature = "profiler")]
+use std::time::Instant;
+
+// Times the enclosing scope into the bot's profiler, e.g. `let _span = profiler::span(bot, "collect");`.
+// Without the `profiler` feature this is a zero sized no-op
+#[cfg(feature = "profiler")]
+pub struct Span<'a> {
+    bot: &'a Bot,
+    name: &'static str,
+    started_at: Instant,
+}
+
+#[cfg(not(feature = "profiler"))]
+pub struct Span;
+
+#[cfg(feature = "profiler")]
+pub fn span<'a>(bot: &'a Bot, name: &'static str) -> Span<'a> {
+    Span {
+        bot,
+        name,
+        started_at: Instant::now(),
+    }
+}
+
+#[cfg(not(feature = "profiler"))]
+pub fn span(_bot: &Bot, _name: &'static str) -> Span {
+    Span
+}
+
+#[cfg(feature = "profiler")]
+impl Drop for Span<'_> {
+    fn drop(&mut self) {
+        record(self.bot, self.name, self.started_at.elapsed());
+    }
+}
+
+pub fn record(bot: &Bot, name: &str, elapsed: Duration) {
+    #[cfg(feature = "profiler")]
+    bot.temporary_data
+        .read()
+        .unwrap()
+        .profiler
+        .record(name, elapsed);
+    #[cfg(not(feature = "profiler"))]
+    let _ = (bot, name, elapsed);
+}
@@ -1,5 +1,8 @@
 use std::io;
-use std::net::{SocketAddr, UdpSocket};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, UdpSocket};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
 use rusty_enet::{PacketReceived, Socket, SocketOptions, MTU_MAX};
 use socks::{Socks5Datagram, TargetAddr};
 
@@ -43,9 +46,82 @@ impl Socket for Socks5UdpSocket {
     }
 }
 
+// Lets an idle event loop block in the socket's receive instead of sleeping, so incoming
+// packets wake it right away. Queued outgoing packets wake it with an empty datagram sent
+// to the socket itself
+pub struct SocketWait {
+    wait_ms: AtomicU64,
+    waiting: AtomicBool,
+    pending: AtomicBool,
+    waker: UdpSocket,
+    address: SocketAddr,
+}
+
+impl SocketWait {
+    // `local` is the address the woken socket is bound to
+    pub fn new(local: SocketAddr) -> io::Result<Self> {
+        let ip = match local.ip() {
+            ip if ip.is_unspecified() && ip.is_ipv6() => IpAddr::V6(Ipv6Addr::LOCALHOST),
+            ip if ip.is_unspecified() => IpAddr::V4(Ipv4Addr::LOCALHOST),
+            ip => ip,
+        };
+        Ok(SocketWait {
+            wait_ms: AtomicU64::new(0),
+            waiting: AtomicBool::new(false),
+            pending: AtomicBool::new(false),
+            waker: UdpSocket::bind(SocketAddr::new(ip, 0))?,
+            address: SocketAddr::new(ip, local.port()),
+        })
+    }
+
+    // The next receive blocks for up to `wait` unless something arrives or wake() is called
+    pub fn arm(&self, wait: Duration) {
+        self.wait_ms.store(wait.as_millis() as u64, Ordering::SeqCst);
+    }
+
+    pub fn wake(&self) {
+        self.pending.store(true, Ordering::SeqCst);
+        if self.waiting.load(Ordering::SeqCst) {
+            let _ = self.waker.send_to(&[], self.address);
+        }
+    }
+
+    // False when the wait ran out or was woken with nothing to read
+    fn wait_readable(&self, socket: &UdpSocket, buffer: &mut [u8]) -> io::Result<bool> {
+        let wait = self.wait_ms.swap(0, Ordering::SeqCst);
+        if wait == 0 {
+            return Ok(true);
+        }
+        self.waiting.store(true, Ordering::SeqCst);
+        let readable = if self.pending.swap(false, Ordering::SeqCst) {
+            Ok(false)
+        } else {
+            block_until_readable(socket, buffer, Duration::from_millis(wait))
+        };
+        self.waiting.store(false, Ordering::SeqCst);
+        readable
+    }
+}
+
+fn block_until_readable(socket: &UdpSocket, buffer: &mut [u8], wait: Duration) -> io::Result<bool> {
+    socket.set_nonblocking(false)?;
+    socket.set_read_timeout(Some(wait))?;
+    let peeked = socket.peek_from(buffer);
+    socket.set_nonblocking(true)?;
+    match peeked {
+        // A wake, read so the next wait doesn't return on it
+        Ok((0, _)) => socket.recv_from(buffer).map(|_| false),
+        Ok(_) => Ok(true),
+        Err(err) if matches!(err.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut) => Ok(false),
+        Err(err) => Err(err),
+    }
+}
+
+// The SOCKS5 relay socket is connected to the proxy and can't be woken by SocketWait, its
+// event loop keeps waiting on a condvar
 pub enum SocketType {
     Socks5(Socks5UdpSocket),
-    Udp(UdpSocket),
+    Udp(UdpSocket, Arc<SocketWait>),
 }
 
 impl Socket for SocketType {
@@ -55,21 +131,100 @@ impl Socket for SocketType {
     fn init(&mut self, socket_options: SocketOptions) -> Result<(), Self::Error> {
         match self {
             SocketType::Socks5(s) => s.init(socket_options),
-            SocketType::Udp(u) => u.init(socket_options),
+            SocketType::Udp(u, _) => u.init(socket_options),
         }
     }
 
     fn send(&mut self, address: Self::Address, buffer: &[u8]) -> Result<usize, Self::Error> {
         match self {
             SocketType::Socks5(s) => s.send(address, buffer),
-            SocketType::Udp(u) => u.send(address, buffer),
+            SocketType::Udp(u, _) => u.send(address, buffer),
         }
     }
 
     fn receive(&mut self, buffer: &mut [u8; MTU_MAX]) -> Result<Option<(Self::Address, PacketReceived)>, Self::Error> {
         match self {
             SocketType::Socks5(s) => s.receive(buffer),
-            SocketType::Udp(u) => u.receive(buffer),
+            SocketType::Udp(u, wait) => {
+                if !wait.wait_readable(u, buffer)? {
+                    return Ok(None);
+                }
+                loop {
+                    match u.receive(buffer)? {
+                        // A wake that came in after its wait had already ended
+                        Some((_, PacketReceived::Complete(0))) => continue,
+                        received => return Ok(received),
+                    }
+                }
+            }
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+    use std::time::Instant;
+
+    fn bound() -> (UdpSocket, SocketWait) {
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        socket.set_nonblocking(true).unwrap();
+        let wait = SocketWait::new(socket.local_addr().unwrap()).unwrap();
+        (socket, wait)
+    }
+
+    #[test]
+    fn wake_ends_a_blocked_wait() {
+        let (socket, wait) = bound();
+        let wait = Arc::new(wait);
+        let waker = wait.clone();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            waker.wake();
+        });
+        let mut buffer = [0; MTU_MAX];
+        wait.arm(Duration::from_secs(5));
+        let started = Instant::now();
+        assert!(!wait.wait_readable(&socket, &mut buffer).unwrap());
+        assert!(started.elapsed() < Duration::from_secs(1));
+    }
+
+    #[test]
+    fn incoming_datagram_ends_a_wait() {
+        let (socket, wait) = bound();
+        let address = socket.local_addr().unwrap();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            UdpSocket::bind("127.0.0.1:0").unwrap().send_to(b"hi", address).unwrap();
+        });
+        let mut buffer = [0; MTU_MAX];
+        wait.arm(Duration::from_secs(5));
+        let started = Instant::now();
+        assert!(wait.wait_readable(&socket, &mut buffer).unwrap());
+        assert!(started.elapsed() < Duration::from_secs(1));
+        assert_eq!(socket.recv_from(&mut buffer).unwrap().0, 2);
+    }
+
+    #[test]
+    fn wait_runs_out_and_only_applies_once() {
+        let (socket, wait) = bound();
+        let mut buffer = [0; MTU_MAX];
+        wait.arm(Duration::from_millis(100));
+        let started = Instant::now();
+        assert!(!wait.wait_readable(&socket, &mut buffer).unwrap());
+        assert!(started.elapsed() >= Duration::from_millis(90));
+        assert!(wait.wait_readable(&socket, &mut buffer).unwrap());
+    }
+
+    #[test]
+    fn wake_before_the_wait_skips_it() {
+        let (socket, wait) = bound();
+        let mut buffer = [0; MTU_MAX];
+        wait.wake();
+        wait.arm(Duration::from_secs(5));
+        let started = Instant::now();
+        assert!(!wait.wait_readable(&socket, &mut buffer).unwrap());
+        assert!(started.elapsed() < Duration::from_millis(100));
+    }
+}
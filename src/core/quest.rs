@@ -0,0 +1,138 @@
+use super::Bot;
+use crate::types::quest::{Quest, QuestItem, QuestProgress};
+use crate::utils::textparse;
+use gtitem_r::structs::ItemDatabase;
+use regex::Regex;
+use std::sync::LazyLock;
+
+static AMOUNT: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^(\d+)\s+(.+)$").unwrap());
+static INLINE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(\d+)\s+([A-Za-z][A-Za-z' ]*?)(?:\s+and\s+|[!.,]|$)").unwrap());
+static REWARD: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)give you (?:an? )?(.+?)(?: if | for |[!.]|$)").unwrap());
+static PROGRESS: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^(.*?)\s*\(?(\d+)\s*/\s*(\d+)\)?\s*$").unwrap());
+
+pub fn dialog_name(dialog: &str) -> Option<String> {
+    dialog
+        .lines()
+        .find(|line| line.starts_with("end_dialog|"))
+        .and_then(|line| line.split('|').nth(1))
+        .map(|name| name.to_string())
+}
+
+pub fn handle_dialog(bot: &Bot, dialog: &str) {
+    let name = dialog_name(dialog).unwrap_or_default();
+    let plain = textparse::strip_color(dialog);
+    let lowercase = plain.to_lowercase();
+
+    if lowercase.contains("crazy jim") {
+        let quest = {
+            let item_database = bot.item_database.read().unwrap();
+            parse_daily_quest(&name, &plain, &item_database)
+        };
+        let mut temp = bot.temporary_data.write().unwrap();
+        match quest {
+            Some(quest) => temp.quests.daily = Some(quest),
+            None => {
+                temp.quests.unknown.insert(name, dialog.to_string());
+            }
+        }
+    } else if lowercase.contains("epic quest") {
+        let progress = parse_epic_quests(&plain);
+        let mut temp = bot.temporary_data.write().unwrap();
+        if progress.is_empty() {
+            temp.quests.unknown.insert(name, dialog.to_string());
+        } else {
+            temp.quests.epic = progress;
+        }
+    } else if lowercase.contains("quest") {
+        let mut temp = bot.temporary_data.write().unwrap();
+        temp.quests.unknown.insert(name, dialog.to_string());
+    }
+}
+
+// Requirements come either as icon labels (`add_label_with_icon|small|200 Dirt|left|2|`)
+// or inline in the text, e.g. "bring me 200 Dirt and 200 Rock"
+pub fn parse_daily_quest(
+    dialog_name: &str,
+    dialog: &str,
+    item_database: &ItemDatabase,
+) -> Option<Quest> {
+    let mut quest = Quest {
+        dialog_name: dialog_name.to_string(),
+        ..Default::default()
+    };
+
+    for line in dialog.lines() {
+        let fields: Vec<&str> = line.split('|').collect();
+        match fields.first() {
+            Some(&"add_label_with_icon") if fields.len() >= 5 => {
+                if let Some(caps) = AMOUNT.captures(fields[2].trim()) {
+                    quest.requirements.push(QuestItem {
+                        item_id: fields[4].trim().parse().unwrap_or(0),
+                        name: caps[2].trim().to_string(),
+                        amount: caps[1].parse().unwrap_or(0),
+                    });
+                }
+            }
+            Some(&"add_button") if fields.len() >= 3 => {
+                quest
+                    .buttons
+                    .push((fields[1].to_string(), fields[2].to_string()));
+            }
+            _ => {}
+        }
+        if quest.reward.is_empty() {
+            if let Some(caps) = REWARD.captures(line) {
+                quest.reward = caps[1].trim().to_string();
+            }
+        }
+    }
+
+    if quest.requirements.is_empty() {
+        let text = dialog
+            .lines()
+            .filter(|line| line.to_lowercase().contains("bring me"))
+            .collect::<Vec<&str>>()
+            .join(" ");
+        for caps in INLINE.captures_iter(&text) {
+            let name = caps[2].trim().to_string();
+            if let Some(item_id) = find_item_id(item_database, &name) {
+                quest.requirements.push(QuestItem {
+                    item_id,
+                    name,
+                    amount: caps[1].parse().unwrap_or(0),
+                });
+            }
+        }
+    }
+
+    if quest.requirements.is_empty() {
+        return None;
+    }
+    Some(quest)
+}
+
+pub fn parse_epic_quests(dialog: &str) -> Vec<QuestProgress> {
+    dialog
+        .lines()
+        .filter_map(|line| {
+            let text = line.split('|').nth(2)?;
+            let caps = PROGRESS.captures(text.trim())?;
+            Some(QuestProgress {
+                text: caps[1].trim().to_string(),
+                current: caps[2].parse().ok()?,
+                total: caps[3].parse().ok()?,
+            })
+        })
+        .collect()
+}
+
+fn find_item_id(item_database: &ItemDatabase, name: &str) -> Option<u32> {
+    item_database
+        .items
+        .iter()
+        .find(|(_, item)| item.name.eq_ignore_ascii_case(name))
+        .map(|(&id, _)| id)
+}
@@ -0,0 +1,87 @@
+use crate::core::features::auto_deposit;
+use crate::core::{crash, Bot};
+use crate::types::config::BotConfig;
+use crate::types::ereaction::EReaction;
+use crate::utils::{config, webhook};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+const WARP_TIMEOUT: Duration = Duration::from_secs(15);
+
+// Works through `reactions` in order on a separate thread, travelling and dropping
+// would otherwise hold up the caller. Ignored while a previous list is still running
+pub fn run(bot: &Arc<Bot>, reactions: Vec<EReaction>, reason: String) {
+    if reactions.is_empty() {
+        return;
+    }
+    {
+        let mut temp = bot.temporary_data.write().unwrap();
+        if temp.reacting {
+            return;
+        }
+        temp.reacting = true;
+    }
+
+    let bot = bot.clone();
+    thread::spawn(move || {
+        let username = bot.info.lock().unwrap().payload[0].clone();
+        crash::set_current_bot(&username);
+        let bot_config = config::get_bot_config(&username).unwrap_or_default();
+        bot.log_info(&format!("Reacting to: {}", reason));
+        for reaction in reactions {
+            match reaction {
+                EReaction::PauseAutomation => bot.pause_automation(),
+                EReaction::Trash => trash(&bot, &bot_config),
+                EReaction::Deposit => {
+                    if let Err(err) = auto_deposit::run(&bot, &bot_config) {
+                        bot.log_error(&format!("Deposit failed: {}", err));
+                    }
+                }
+                EReaction::Notify => notify(&username, &reason, &bot),
+            }
+        }
+        bot.temporary_data.write().unwrap().reacting = false;
+    });
+}
+
+fn trash(bot: &Bot, bot_config: &BotConfig) {
+    for &item_id in &bot_config.trash_items {
+        let amount = amount_of(bot, item_id);
+        if amount > 0 {
+            bot.log_info(&format!("Trashing {} of item {}", amount, item_id));
+            bot.trash_item(item_id, amount);
+            thread::sleep(Duration::from_millis(500));
+        }
+    }
+}
+
+fn notify(username: &str, reason: &str, bot: &Bot) {
+    let url = config::get_webhook_url();
+    if url.is_empty() {
+        return;
+    }
+    if let Err(err) = webhook::send(&url, &format!("{}: {}", username, reason)) {
+        bot.log_error(&format!("Failed to send webhook: {}", err));
+    }
+}
+
+// Warps and waits for the world to load, false when it didn't within WARP_TIMEOUT
+pub fn warp_to(bot: &Bot, world: &str) -> bool {
+    match bot.warp_and_wait(world, WARP_TIMEOUT) {
+        Ok(()) => true,
+        Err(err) => {
+            bot.log_warn(&format!("Warp failed: {}", err));
+            false
+        }
+    }
+}
+
+fn amount_of(bot: &Bot, item_id: u32) -> u32 {
+    bot.inventory
+        .lock()
+        .unwrap()
+        .items
+        .get(&(item_id as u16))
+        .map_or(0, |item| item.amount as u32)
+}
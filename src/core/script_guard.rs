@@ -0,0 +1,61 @@
+use super::Bot;
+use crate::utils::{config, date};
+
+const MAX_ACTIONS: usize = 200;
+
+// Small, Big and Huge Lock, World Lock, Diamond Lock, Blue Gem Lock and gems
+pub const DEFAULT_PROTECTED: [u32; 7] = [202, 204, 206, 242, 1796, 7188, 112];
+
+#[derive(Debug, Clone)]
+pub struct ScriptAction {
+    pub time: u64,
+    pub description: String,
+    // Only logged, nothing was sent
+    pub dry_run: bool,
+}
+
+pub fn protected_items(bot: &Bot) -> Vec<u32> {
+    let username = bot.info.lock().unwrap().payload[0].clone();
+    config::get_bot_config(&username)
+        .and_then(|bot_config| bot_config.protected_items)
+        .unwrap_or_else(|| DEFAULT_PROTECTED.to_vec())
+}
+
+pub fn is_protected(bot: &Bot, item_id: u32) -> bool {
+    protected_items(bot).contains(&item_id)
+}
+
+// Err with the item's name when scripts may not give it away
+pub fn check(bot: &Bot, item_id: u32) -> Result<(), String> {
+    if is_protected(bot, item_id) {
+        return Err(format!("{} is a protected item", item_name(bot, item_id)));
+    }
+    Ok(())
+}
+
+pub fn item_name(bot: &Bot, item_id: u32) -> String {
+    bot.item_database
+        .read()
+        .unwrap()
+        .get_item(&item_id)
+        .map_or_else(|| format!("item {}", item_id), |item| item.name.clone())
+}
+
+// Records a destructive script call in the action log. Returns false in dry-run, the caller
+// then skips sending it and reports success to the script as usual
+pub fn perform(bot: &Bot, description: String) -> bool {
+    let dry_run = config::get_script_dry_run();
+    if dry_run {
+        bot.log_info(&format!("[dry-run] Would {}", description));
+    }
+    let mut actions = bot.script_actions.lock().unwrap();
+    if actions.len() >= MAX_ACTIONS {
+        actions.pop_front();
+    }
+    actions.push_back(ScriptAction {
+        time: date::now_secs(),
+        description,
+        dry_run,
+    });
+    !dry_run
+}
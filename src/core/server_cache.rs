@@ -0,0 +1,57 @@
+use crate::utils::{config, date};
+use paris::warn;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::sync::Mutex;
+
+const CACHE_PATH: &str = "server_data_cache.json";
+
+// Bots log in concurrently, their read-modify-writes of the file must not interleave
+static CACHE_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedServerData {
+    fetched_at: u64,
+    body: String,
+}
+
+// Last good server_data.php response, keyed by the url it came from so the main and
+// alternate servers don't mix
+pub fn save(url: &str, body: &str) {
+    let _lock = CACHE_LOCK.lock().unwrap();
+    let mut cache = load_all();
+    cache.insert(
+        url.to_string(),
+        CachedServerData {
+            fetched_at: date::now_secs(),
+            body: body.to_string(),
+        },
+    );
+    match serde_json::to_string_pretty(&cache) {
+        Ok(contents) => {
+            if let Err(err) = fs::write(CACHE_PATH, contents) {
+                warn!("Failed to write {}: {}", CACHE_PATH, err);
+            }
+        }
+        Err(err) => warn!("Failed to serialize the server data cache: {}", err),
+    }
+}
+
+// The cached response and its age in seconds, None past server_data_max_age_hours
+pub fn load(url: &str) -> Option<(String, u64)> {
+    let _lock = CACHE_LOCK.lock().unwrap();
+    let cached = load_all().remove(url)?;
+    let age = date::now_secs().saturating_sub(cached.fetched_at);
+    if age > config::get_server_data_max_age_hours() as u64 * 3600 {
+        return None;
+    }
+    Some((cached.body, age))
+}
+
+fn load_all() -> HashMap<String, CachedServerData> {
+    fs::read_to_string(CACHE_PATH)
+        .ok()
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
@@ -0,0 +1,88 @@
+use serde_json::{Map, Value};
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::utils::date;
+use crate::utils::debounced_file::DebouncedFile;
+
+const STORAGE_DIR: &str = "storage";
+// Writes are batched, a farm loop setting a key every tile shouldn't hit the disk every time
+const FLUSH_DELAY: Duration = Duration::from_secs(2);
+
+// Script key-value store, one file per bot with a namespace per script name
+pub struct Storage {
+    file: DebouncedFile,
+    data: Map<String, Value>,
+}
+
+impl Storage {
+    // Returns a warning when the existing file couldn't be read and was moved aside
+    pub fn load(bot_name: &str) -> (Self, Option<String>) {
+        let path = PathBuf::from(STORAGE_DIR).join(format!("{}.json", bot_name.to_lowercase()));
+        let mut warning = None;
+        let data = match fs::read_to_string(&path) {
+            Ok(contents) => match serde_json::from_str::<Map<String, Value>>(&contents) {
+                Ok(data) => data,
+                Err(err) => {
+                    let corrupt = path.with_extension(format!("corrupt-{}.json", date::now_secs()));
+                    let _ = fs::rename(&path, &corrupt);
+                    warning = Some(format!(
+                        "Script storage was corrupt ({}), moved to {} and started fresh",
+                        err,
+                        corrupt.display()
+                    ));
+                    Map::new()
+                }
+            },
+            Err(_) => Map::new(),
+        };
+
+        (
+            Storage {
+                file: DebouncedFile::new(path, FLUSH_DELAY),
+                data,
+            },
+            warning,
+        )
+    }
+
+    pub fn get(&self, namespace: &str, key: &str) -> Option<Value> {
+        self.data.get(namespace)?.get(key).cloned()
+    }
+
+    pub fn set(&mut self, namespace: &str, key: &str, value: Value) {
+        if let Value::Object(entries) = self
+            .data
+            .entry(namespace.to_string())
+            .or_insert_with(|| Value::Object(Map::new()))
+        {
+            entries.insert(key.to_string(), value);
+        }
+        self.file.mark_dirty();
+    }
+
+    pub fn delete(&mut self, namespace: &str, key: &str) {
+        let removed = match self.data.get_mut(namespace) {
+            Some(Value::Object(entries)) => entries.remove(key).is_some(),
+            _ => false,
+        };
+        if removed {
+            self.file.mark_dirty();
+        }
+    }
+
+    pub fn flush_if_due(&mut self) -> io::Result<()> {
+        if self.file.is_due() {
+            self.flush()
+        } else {
+            Ok(())
+        }
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        let data = &self.data;
+        self.file.flush(|| serde_json::to_string_pretty(data))
+    }
+}
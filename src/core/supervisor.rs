@@ -0,0 +1,107 @@
+use super::{crash, Bot};
+use crate::types::eworker_state::EWorkerState;
+use crate::types::worker_status::WorkerStatus;
+use paris::error;
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+const MAX_RESTARTS: u32 = 3;
+// Doubled after every restart
+const BACKOFF: Duration = Duration::from_secs(2);
+
+// Runs `work` on its own thread and starts it again after a panic, up to MAX_RESTARTS
+// times. The bot's log gets the panic, the worker list shows it as degraded
+pub fn spawn<F>(bot: &Arc<Bot>, name: &str, work: F) -> Option<JoinHandle<()>>
+where
+    F: Fn(&Arc<Bot>) + Send + 'static,
+{
+    start(bot, name, MAX_RESTARTS, work)
+}
+
+// Same, but never restarted. For work that shouldn't run twice, like a script
+pub fn spawn_once<F>(bot: &Arc<Bot>, name: &str, work: F)
+where
+    F: Fn(&Arc<Bot>) + Send + 'static,
+{
+    start(bot, name, 0, work);
+}
+
+fn start<F>(bot: &Arc<Bot>, name: &str, max_restarts: u32, work: F) -> Option<JoinHandle<()>>
+where
+    F: Fn(&Arc<Bot>) + Send + 'static,
+{
+    let bot = bot.clone();
+    let name = name.to_string();
+    let username = bot.info.lock().unwrap().payload[0].clone();
+    let thread_name = format!("{} {}", username, name);
+    let spawned = thread::Builder::new().name(thread_name.clone()).spawn(move || {
+        crash::set_current_bot(&username);
+        supervise(&bot, &name, max_restarts, work);
+    });
+    match spawned {
+        Ok(handle) => Some(handle),
+        Err(err) => {
+            error!("Failed to start {}: {}", thread_name, err);
+            None
+        }
+    }
+}
+
+fn supervise<F>(bot: &Arc<Bot>, name: &str, max_restarts: u32, work: F)
+where
+    F: Fn(&Arc<Bot>),
+{
+    register(bot, name);
+    let mut crashes = 0;
+    loop {
+        let message = match crash::catch(|| work(bot)) {
+            Ok(()) => {
+                update(bot, name, |worker| worker.state = EWorkerState::Stopped);
+                return;
+            }
+            Err(message) => message,
+        };
+        crashes += 1;
+        error!("{} crashed: {}", thread::current().name().unwrap_or(name), message);
+        bot.log_error(&format!("{} worker crashed: {}", name, first_line(&message)));
+        update(bot, name, |worker| worker.last_panic = Some(message.clone()));
+
+        if crashes > max_restarts {
+            update(bot, name, |worker| worker.state = EWorkerState::Failed);
+            if max_restarts > 0 {
+                bot.log_error(&format!(
+                    "{} worker crashed {} times, relog the bot to get it back",
+                    name, crashes
+                ));
+                bot.set_status(&format!("Degraded: {} worker failed, relog needed", name));
+            }
+            return;
+        }
+        update(bot, name, |worker| worker.state = EWorkerState::Restarting);
+        thread::sleep(BACKOFF * 2u32.pow(crashes - 1));
+        bot.log_warn(&format!("Restarting {} worker ({}/{})", name, crashes, max_restarts));
+        update(bot, name, |worker| {
+            worker.state = EWorkerState::Running;
+            worker.restarts += 1;
+        });
+    }
+}
+
+// A new start of the worker, e.g. the poll loop on the next login, begins with a clean slate
+fn register(bot: &Bot, name: &str) {
+    let mut workers = bot.workers.lock().unwrap();
+    workers.retain(|worker| worker.name != name);
+    workers.push(WorkerStatus::new(name));
+}
+
+fn update(bot: &Bot, name: &str, f: impl FnOnce(&mut WorkerStatus)) {
+    let mut workers = bot.workers.lock().unwrap();
+    if let Some(worker) = workers.iter_mut().find(|worker| worker.name == name) {
+        f(worker);
+    }
+}
+
+fn first_line(message: &str) -> &str {
+    message.lines().next().unwrap_or_default()
+}
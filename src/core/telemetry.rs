@@ -0,0 +1,181 @@
+use super::{bandwidth, Bot};
+use crate::types::epacket_type::EPacketType;
+use crate::types::login_info::LoginInfo;
+use crate::utils::{config, date, textparse::TextPacket};
+use std::collections::HashMap;
+
+// Called without the state lock held, this takes the info lock
+pub fn on_login(bot: &Bot) {
+    let message = login_message(&bot.info.lock().unwrap().login_info);
+    send(bot, message);
+}
+
+// World visits and keepalives are held off over the bandwidth cap, login and answers to
+// the server are not
+pub fn on_world_enter(bot: &Bot, world_name: &str) {
+    if !bandwidth::throttled() {
+        send(bot, world_message("300_WORLD_VISIT", world_name));
+    }
+}
+
+pub fn on_world_exit(bot: &Bot, world_name: &str) {
+    if !bandwidth::throttled() {
+        send(bot, world_message("301_WORLD_EXIT", world_name));
+    }
+}
+
+pub fn on_keepalive(bot: &Bot) {
+    if bandwidth::throttled() {
+        return;
+    }
+    let message = keepalive_message(&bot.info.lock().unwrap().login_info);
+    send(bot, message);
+}
+
+fn login_message(login_info: &LoginInfo) -> String {
+    TextPacket::new()
+        .add("eventName", "102_PLAYER.AUTHENTICATION")
+        .add("Authenticated", "1")
+        .add("Authentication_error", "0")
+        .add("Device_Id", &login_info.rid)
+        .add("Device_Os", &login_info.platform_id)
+        .add("Grow_Id", &login_info.tank_id_name)
+        .add("Name", &login_info.requested_name)
+        .add("Version", &login_info.game_version)
+        .build()
+}
+
+fn world_message(event_name: &str, world_name: &str) -> String {
+    TextPacket::new()
+        .add("eventName", event_name)
+        .add("World_name", world_name)
+        .build()
+}
+
+fn keepalive_message(login_info: &LoginInfo) -> String {
+    TextPacket::new()
+        .add("eventName", "100_MOBILE.FOREGROUND")
+        .add("Device_Id", &login_info.rid)
+        .add("Version", &login_info.game_version)
+        .build()
+}
+
+// Server-sent track events get the event name echoed back, the way the client confirms them
+pub fn on_track(bot: &Bot, data: &HashMap<String, String>) {
+    if let Some(message) = track_ack(data) {
+        send(bot, message);
+    }
+}
+
+fn track_ack(data: &HashMap<String, String>) -> Option<String> {
+    let event_name = data.get("eventName")?;
+    Some(
+        TextPacket::new()
+            .add("eventName", event_name)
+            .add("Acknowledged", "1")
+            .build(),
+    )
+}
+
+// Always answered, even with telemetry off, a client that ignores these stands out
+pub fn on_log_request(bot: &Bot) {
+    let message = {
+        let info = bot.info.lock().unwrap();
+        let world = bot.world.read().unwrap().name.clone();
+        log_response(&info.login_info, &world, date::now_secs())
+    };
+    bot.send_packet(EPacketType::NetMessageClientLogResponse, message);
+}
+
+fn log_response(login_info: &LoginInfo, world: &str, now: u64) -> String {
+    format!(
+        "msg|[{}] Growtopia V{} ({}), {}\n",
+        date::format_time(now, 0),
+        login_info.game_version,
+        login_info.platform_id,
+        if world.is_empty() || world == "EXIT" {
+            "in the world menu".to_string()
+        } else {
+            format!("in world {}", world)
+        }
+    )
+}
+
+fn send(bot: &Bot, message: String) {
+    if !config::get_telemetry() {
+        return;
+    }
+    bot.send_packet(EPacketType::NetMessageTrack, message);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn login_info() -> LoginInfo {
+        let mut login_info = LoginInfo::new();
+        login_info.rid = "01ABCDEF".to_string();
+        login_info.platform_id = "0,1,1".to_string();
+        login_info.tank_id_name = "grower".to_string();
+        login_info.requested_name = "Grower".to_string();
+        login_info.game_version = "5.11".to_string();
+        login_info
+    }
+
+    #[test]
+    fn login_event() {
+        assert_eq!(
+            login_message(&login_info()),
+            "eventName|102_PLAYER.AUTHENTICATION\n\
+             Authenticated|1\n\
+             Authentication_error|0\n\
+             Device_Id|01ABCDEF\n\
+             Device_Os|0,1,1\n\
+             Grow_Id|grower\n\
+             Name|Grower\n\
+             Version|5.11\n"
+        );
+    }
+
+    #[test]
+    fn world_events() {
+        assert_eq!(
+            world_message("300_WORLD_VISIT", "START"),
+            "eventName|300_WORLD_VISIT\nWorld_name|START\n"
+        );
+        assert_eq!(
+            world_message("301_WORLD_EXIT", "START"),
+            "eventName|301_WORLD_EXIT\nWorld_name|START\n"
+        );
+    }
+
+    #[test]
+    fn track_requests_are_acknowledged() {
+        let request = crate::utils::textparse::parse_and_store_as_map(include_str!("../../tests/fixtures/packets/track_level_up.txt"));
+        assert_eq!(
+            track_ack(&request).as_deref(),
+            Some("eventName|200_PLAYER.LEVEL_UP\nAcknowledged|1\n")
+        );
+        let request = crate::utils::textparse::parse_and_store_as_map(include_str!("../../tests/fixtures/packets/client_log_request.txt"));
+        assert_eq!(track_ack(&request), None);
+    }
+
+    #[test]
+    fn log_requests_get_a_client_line() {
+        // 12:34:56 UTC
+        let now = 45296;
+        assert_eq!(
+            log_response(&login_info(), "START", now),
+            format!("msg|[{}] Growtopia V5.11 (0,1,1), in world START\n", date::format_time(now, 0))
+        );
+        assert!(log_response(&login_info(), "EXIT", now).ends_with(", in the world menu\n"));
+    }
+
+    #[test]
+    fn keepalive_event() {
+        assert_eq!(
+            keepalive_message(&login_info()),
+            "eventName|100_MOBILE.FOREGROUND\nDevice_Id|01ABCDEF\nVersion|5.11\n"
+        );
+    }
+}
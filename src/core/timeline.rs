@@ -0,0 +1,68 @@
+use std::collections::VecDeque;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::types::bot_event::BotEvent;
+use crate::types::timeline_entry::TimelineEntry;
+use crate::utils::date;
+use crate::utils::debounced_file::DebouncedFile;
+
+const TIMELINE_DIR: &str = "timeline";
+const MAX_ENTRIES: usize = 500;
+const FLUSH_DELAY: Duration = Duration::from_secs(5);
+
+// Significant bot events with timestamps, fed from Bot::emit and kept across restarts
+pub struct Timeline {
+    file: DebouncedFile,
+    entries: VecDeque<TimelineEntry>,
+}
+
+impl Timeline {
+    // An unreadable file only costs the history, start over instead of failing the bot
+    pub fn load(bot_name: &str) -> Self {
+        let path = PathBuf::from(TIMELINE_DIR).join(format!("{}.json", bot_name.to_lowercase()));
+        let entries = fs::read_to_string(&path)
+            .ok()
+            .and_then(|contents| serde_json::from_str::<VecDeque<TimelineEntry>>(&contents).ok())
+            .unwrap_or_default();
+        Timeline {
+            file: DebouncedFile::new(path, FLUSH_DELAY),
+            entries,
+        }
+    }
+
+    pub fn record(&mut self, event: &BotEvent) {
+        let detail = match event.timeline_detail() {
+            Some(detail) => detail,
+            None => return,
+        };
+        if self.entries.len() >= MAX_ENTRIES {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(TimelineEntry {
+            timestamp: date::now_secs(),
+            kind: event.name().to_string(),
+            detail,
+        });
+        self.file.mark_dirty();
+    }
+
+    pub fn entries(&self) -> Vec<TimelineEntry> {
+        self.entries.iter().cloned().collect()
+    }
+
+    pub fn flush_if_due(&mut self) -> io::Result<()> {
+        if self.file.is_due() {
+            self.flush()
+        } else {
+            Ok(())
+        }
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        let entries = &self.entries;
+        self.file.flush(|| serde_json::to_string(entries))
+    }
+}
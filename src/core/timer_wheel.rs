@@ -0,0 +1,173 @@
+use std::sync::mpsc::{self, SyncSender};
+use std::sync::{Condvar, LazyLock, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// Resolution of every timer, a sleep ends on the tick closest after its duration
+const TICK: Duration = Duration::from_millis(50);
+// One revolution, longer sleeps go around the wheel more than once
+const SLOTS: usize = 64;
+
+// Timers hashed into slots by their due tick, so advancing one tick only looks at the
+// timers of one slot however many are waiting
+struct Wheel<T> {
+    slots: Vec<Vec<(u64, T)>>,
+    current: usize,
+    len: usize,
+}
+
+impl<T> Wheel<T> {
+    fn new() -> Self {
+        Wheel {
+            slots: (0..SLOTS).map(|_| Vec::new()).collect(),
+            current: 0,
+            len: 0,
+        }
+    }
+
+    // Due after `ticks` calls to advance, never sooner than the next one
+    fn insert(&mut self, ticks: u64, timer: T) {
+        let ticks = ticks.max(1);
+        let slot = (self.current + (ticks % SLOTS as u64) as usize) % SLOTS;
+        self.slots[slot].push(((ticks - 1) / SLOTS as u64, timer));
+        self.len += 1;
+    }
+
+    // Moves on one tick and returns the timers that came due
+    fn advance(&mut self) -> Vec<T> {
+        self.current = (self.current + 1) % SLOTS;
+        let slot = std::mem::take(&mut self.slots[self.current]);
+        let mut due = Vec::new();
+        for (rounds, timer) in slot {
+            if rounds == 0 {
+                due.push(timer);
+            } else {
+                self.slots[self.current].push((rounds - 1, timer));
+            }
+        }
+        self.len -= due.len();
+        due
+    }
+
+    fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+struct Timers {
+    wheel: Mutex<Wheel<SyncSender<()>>>,
+    // Wakes the wheel thread out of an empty wheel
+    added: Condvar,
+}
+
+static TIMERS: LazyLock<Timers> = LazyLock::new(|| {
+    thread::Builder::new()
+        .name("timer_wheel".to_string())
+        .spawn(run)
+        .expect("Failed to spawn the timer wheel");
+    Timers {
+        wheel: Mutex::new(Wheel::new()),
+        added: Condvar::new(),
+    }
+});
+
+// Blocks the calling thread for about `duration`. Every bot's periodic work sleeps here,
+// so fifty idle bots share one timer thread waking every TICK instead of fifty
+// independent sleeps, and nothing wakes at all while no timer is pending
+pub fn sleep(duration: Duration) {
+    let (waker, woken) = mpsc::sync_channel(1);
+    let ticks = duration.as_millis().div_ceil(TICK.as_millis()) as u64;
+    {
+        let mut wheel = TIMERS.wheel.lock().unwrap();
+        if wheel.is_empty() {
+            TIMERS.added.notify_one();
+        }
+        wheel.insert(ticks, waker);
+    }
+    let _ = woken.recv();
+}
+
+fn run() {
+    let timers = &*TIMERS;
+    let mut wheel = timers.wheel.lock().unwrap();
+    let mut next_tick = Instant::now() + TICK;
+    loop {
+        if wheel.is_empty() {
+            wheel = timers.added.wait(wheel).unwrap();
+            next_tick = Instant::now() + TICK;
+            continue;
+        }
+        let now = Instant::now();
+        if now < next_tick {
+            wheel = timers.added.wait_timeout(wheel, next_tick - now).unwrap().0;
+            continue;
+        }
+        for waker in wheel.advance() {
+            let _ = waker.try_send(());
+        }
+        // A late tick isn't caught up on, the timers in it just fire late
+        next_tick = (next_tick + TICK).max(now);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn advance_until_due<T>(wheel: &mut Wheel<T>, limit: u64) -> Option<(u64, Vec<T>)> {
+        for tick in 1..=limit {
+            let due = wheel.advance();
+            if !due.is_empty() {
+                return Some((tick, due));
+            }
+        }
+        None
+    }
+
+    #[test]
+    fn fires_after_the_requested_ticks() {
+        for ticks in [1, 2, 10, SLOTS as u64 - 1, SLOTS as u64, SLOTS as u64 + 1, 3 * SLOTS as u64 + 7] {
+            let mut wheel = Wheel::new();
+            wheel.insert(ticks, ticks);
+            assert_eq!(advance_until_due(&mut wheel, 10 * SLOTS as u64), Some((ticks, vec![ticks])));
+            assert!(wheel.is_empty());
+        }
+    }
+
+    #[test]
+    fn zero_ticks_waits_for_the_next_one() {
+        let mut wheel = Wheel::new();
+        wheel.insert(0, "now");
+        assert_eq!(wheel.advance(), vec!["now"]);
+    }
+
+    #[test]
+    fn timers_in_one_slot_fire_in_their_own_round() {
+        let mut wheel = Wheel::new();
+        wheel.insert(5, "first");
+        wheel.insert(5 + SLOTS as u64, "second");
+        assert_eq!(advance_until_due(&mut wheel, 100), Some((5, vec!["first"])));
+        assert!(!wheel.is_empty());
+        assert_eq!(advance_until_due(&mut wheel, 100), Some((SLOTS as u64, vec!["second"])));
+        assert!(wheel.is_empty());
+    }
+
+    #[test]
+    fn inserts_count_from_the_current_tick() {
+        let mut wheel = Wheel::new();
+        for _ in 0..10 {
+            wheel.advance();
+        }
+        wheel.insert(3, 'a');
+        assert_eq!(advance_until_due(&mut wheel, 100), Some((3, vec!['a'])));
+    }
+
+    #[test]
+    fn sleep_returns_after_the_duration() {
+        let started = Instant::now();
+        sleep(Duration::from_millis(120));
+        let elapsed = started.elapsed();
+        assert!(elapsed >= Duration::from_millis(100), "woke after {:?}", elapsed);
+        assert!(elapsed < Duration::from_secs(2), "woke after {:?}", elapsed);
+    }
+}
@@ -0,0 +1,241 @@
+use super::inventory::Inventory;
+use crate::types::value_table::ValueTable;
+use crate::utils::{config, date};
+use paris::{info, warn};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Read};
+use std::sync::{Arc, Mutex, RwLock};
+use std::thread;
+use std::time::Duration;
+
+pub const VALUES_PATH: &str = "item_values.json";
+const SYNC_INTERVAL: u64 = 24 * 3600;
+const POLL: Duration = Duration::from_secs(600);
+const TIMEOUT: Duration = Duration::from_secs(15);
+// A value list is a few hundred KB at most, anything bigger isn't one
+const MAX_DOWNLOAD: u64 = 8 * 1024 * 1024;
+
+// Loaded once, replaced as a whole on every change so readers never see half a sync
+static TABLE: RwLock<Option<Arc<ValueTable>>> = RwLock::new(None);
+static WRITE_LOCK: Mutex<()> = Mutex::new(());
+
+// What the sync URL serves, documented in the README
+#[derive(Deserialize)]
+struct SyncFile {
+    values: HashMap<String, f32>,
+}
+
+pub fn get() -> Arc<ValueTable> {
+    if let Some(table) = TABLE.read().unwrap().as_ref() {
+        return table.clone();
+    }
+    let mut cache = TABLE.write().unwrap();
+    let table = cache.get_or_insert_with(|| Arc::new(load()));
+    table.clone()
+}
+
+// In world locks, items without a value are worth nothing
+pub fn value_of(item_id: u32, amount: u32) -> f32 {
+    get().get(item_id).unwrap_or(0.0) * amount as f32
+}
+
+pub fn inventory_value(inventory: &Inventory) -> f32 {
+    let table = get();
+    inventory
+        .items
+        .values()
+        .map(|item| table.get(item.id as u32).unwrap_or(0.0) * item.amount as f32)
+        .sum()
+}
+
+// Items counted as zero by `inventory_value` because nothing values them
+pub fn unvalued(inventory: &Inventory) -> Vec<u32> {
+    let table = get();
+    let mut item_ids: Vec<u32> = inventory
+        .items
+        .values()
+        .map(|item| item.id as u32)
+        .filter(|item_id| table.get(*item_id).is_none())
+        .collect();
+    item_ids.sort_unstable();
+    item_ids
+}
+
+// None removes the override so the synced value applies again
+pub fn set_override(item_id: u32, value: Option<f32>) {
+    let _guard = WRITE_LOCK.lock().unwrap();
+    let mut table = get().as_ref().clone();
+    match value {
+        Some(value) => table.overrides.insert(item_id, value.max(0.0)),
+        None => table.overrides.remove(&item_id),
+    };
+    if let Err(err) = save(table) {
+        warn!("Failed to save {}: {}", VALUES_PATH, err);
+    }
+}
+
+// Replaces the synced values with the list at `url`. Nothing changes unless the whole
+// download is a valid list, a cut off or malformed one keeps the previous table
+pub fn sync(url: &str) -> Result<usize, String> {
+    let values = fetch(url)?;
+    let count = values.len();
+    let _guard = WRITE_LOCK.lock().unwrap();
+    let mut table = get().as_ref().clone();
+    table.synced = values;
+    table.source = url.to_string();
+    table.synced_at = date::now_secs();
+    save(table).map_err(|err| err.to_string())?;
+    Ok(count)
+}
+
+// Syncs on startup and then daily while a URL is set, failures are retried on the next poll
+pub fn spawn() {
+    import_collect_values();
+    thread::spawn(|| loop {
+        let url = config::get_value_sync_url();
+        let table = get();
+        let due = table.source != url || date::now_secs().saturating_sub(table.synced_at) >= SYNC_INTERVAL;
+        if !url.is_empty() && due {
+            match sync(&url) {
+                Ok(count) => info!("Synced {} item values from {}", count, url),
+                Err(err) => warn!("Item value sync failed, keeping the previous table: {}", err),
+            }
+        }
+        thread::sleep(POLL);
+    });
+}
+
+// The collect values the config held before this table, kept as overrides where none is
+// set yet. They're taken out of the config once the table is saved
+fn import_collect_values() {
+    let item_values = config::get().item_values.clone();
+    if item_values.is_empty() {
+        return;
+    }
+    let _guard = WRITE_LOCK.lock().unwrap();
+    let mut table = get().as_ref().clone();
+    for entry in &item_values {
+        table.overrides.entry(entry.item_id).or_insert(entry.value as f32);
+    }
+    if let Err(err) = save(table) {
+        warn!("Failed to move the collect values into {}: {}", VALUES_PATH, err);
+        return;
+    }
+    config::update(|config| config.item_values.clear());
+    warn!(
+        "Moved {} collect values into {}, they now count as world locks, check them under Settings > Item values",
+        item_values.len(),
+        VALUES_PATH
+    );
+}
+
+// No global proxy setting exists, so this follows the usual HTTPS_PROXY / ALL_PROXY variables
+fn fetch(url: &str) -> Result<HashMap<u32, f32>, String> {
+    let agent = ureq::AgentBuilder::new()
+        .timeout(TIMEOUT)
+        .try_proxy_from_env(true)
+        .build();
+    let response = agent.get(url).call().map_err(|err| err.to_string())?;
+    let mut body = Vec::new();
+    response
+        .into_reader()
+        .take(MAX_DOWNLOAD + 1)
+        .read_to_end(&mut body)
+        .map_err(|err| format!("download cut off: {}", err))?;
+    parse(&body)
+}
+
+// The whole list or an error, never part of it
+fn parse(body: &[u8]) -> Result<HashMap<u32, f32>, String> {
+    if body.len() as u64 > MAX_DOWNLOAD {
+        return Err("the list is larger than 8 MB".to_string());
+    }
+    let file: SyncFile = serde_json::from_slice(&body).map_err(|err| format!("not a value list: {}", err))?;
+    let mut values = HashMap::new();
+    for (key, value) in file.values {
+        let item_id: u32 = key.trim().parse().map_err(|_| format!("{} isn't an item id", key))?;
+        if !value.is_finite() || value < 0.0 {
+            return Err(format!("item {} has an invalid value {}", item_id, value));
+        }
+        values.insert(item_id, value);
+    }
+    if values.is_empty() {
+        return Err("the list is empty".to_string());
+    }
+    Ok(values)
+}
+
+fn load() -> ValueTable {
+    let contents = match fs::read_to_string(VALUES_PATH) {
+        Ok(contents) => contents,
+        Err(_) => return ValueTable::default(),
+    };
+    serde_json::from_str(&contents).unwrap_or_else(|err| {
+        warn!("{} is invalid, starting with an empty table: {}", VALUES_PATH, err);
+        ValueTable::default()
+    })
+}
+
+// Written next to the old file and renamed over it, so a crash never leaves half a table
+fn save(table: ValueTable) -> io::Result<()> {
+    let json = serde_json::to_string_pretty(&table)?;
+    let tmp_path = format!("{}.tmp", VALUES_PATH);
+    fs::write(&tmp_path, json)?;
+    fs::rename(&tmp_path, VALUES_PATH)?;
+    *TABLE.write().unwrap() = Some(Arc::new(table));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_a_value_list() {
+        let values = parse(br#"{"values": {"242": 1, " 1796 ": 100, "7188": 0.05}}"#).unwrap();
+        assert_eq!(values.len(), 3);
+        assert_eq!(values[&242], 1.0);
+        assert_eq!(values[&1796], 100.0);
+        assert_eq!(values[&7188], 0.05);
+    }
+
+    #[test]
+    fn rejects_partial_or_invalid_downloads() {
+        let bodies: [&[u8]; 9] = [
+            // Cut off mid-download
+            br#"{"values": {"242": 1, "1796": 10"#,
+            br#"{"values": {"242": 1,"#,
+            b"",
+            b"<html>502 Bad Gateway</html>",
+            br#"{"prices": {"242": 1}}"#,
+            br#"{"values": {}}"#,
+            br#"{"values": {"242": 1, "dirt": 2}}"#,
+            br#"{"values": {"242": 1, "1796": -5}}"#,
+            br#"{"values": {"242": 1, "1796": "lots"}}"#,
+        ];
+        for body in bodies {
+            assert!(parse(body).is_err(), "{}", String::from_utf8_lossy(body));
+        }
+    }
+
+    #[test]
+    fn rejects_oversized_downloads() {
+        let mut body = br#"{"values": {"242": 1}}"#.to_vec();
+        body.resize(MAX_DOWNLOAD as usize + 1, b' ');
+        assert!(parse(&body).is_err());
+    }
+
+    #[test]
+    fn overrides_win_over_synced_values() {
+        let table = ValueTable {
+            overrides: HashMap::from([(242, 2.0)]),
+            synced: HashMap::from([(242, 1.0), (1796, 100.0)]),
+            ..Default::default()
+        };
+        assert_eq!(table.get(242), Some(2.0));
+        assert_eq!(table.get(1796), Some(100.0));
+        assert_eq!(table.get(2), None);
+    }
+}
@@ -1,15 +1,21 @@
 use super::Bot;
-use crate::core;
+use crate::core::features::{auto_surgery, repeat_activate};
+use crate::core::{
+    self, activation, character_state, chat, console, desync, disconnect, effects, items, onboarding, quest, telemetry, whisper,
+    world_password, world_summary,
+};
+use crate::types::bot_event::BotEvent;
+use crate::types::clothing::Clothing;
 use crate::types::epacket_type::EPacketType;
 use crate::types::player::Player;
 use crate::types::tank_packet::TankPacket;
-use crate::types::vector::Vector2;
 use crate::utils::variant::VariantList;
 use crate::utils::{self, textparse};
 use std::sync::Arc;
 use std::thread;
+use std::time::Instant;
 
-pub fn handle(bot: Arc<Bot>, _: &TankPacket, data: &[u8]) {
+pub fn handle(bot: Arc<Bot>, tank_packet: &TankPacket, data: &[u8]) {
     let variant = VariantList::deserialize(&data).unwrap();
     let function_call: String = variant.get(0).unwrap().as_string();
     bot.log_info(format!("Received function call: {}", function_call).as_str());
@@ -33,47 +39,40 @@ pub fn handle(bot: Arc<Bot>, _: &TankPacket, data: &[u8]) {
             info.login_info.user = user_id.to_string();
             info.login_info.door_id = parsed_server_data.get(1).unwrap().to_string();
             info.login_info.uuid = parsed_server_data.get(2).unwrap().to_string();
+            let redirect_to = format!("{}:{}", server.ip, server.port);
+            drop(state);
+            drop(server);
+            drop(info);
+            bot.emit(BotEvent::Redirect { server: redirect_to });
             bot.disconnect();
         }
         "OnSuperMainStartAcceptLogonHrdxs47254722215a" => {
             let server_hash = variant.get(1).unwrap().as_uint32();
-            let item_database_loaded = {
-                let item_database = bot.item_database.read().unwrap();
-                item_database.loaded
-            };
 
-            if !item_database_loaded {
-                match utils::proton::hash_file("items.dat") {
-                    Ok(hash) => {
-                        if hash == server_hash {
-                            let mut item_database = bot.item_database.write().unwrap();
-                            *item_database = gtitem_r::load_from_file("items.dat").unwrap();
-                            bot.send_packet(
-                                EPacketType::NetMessageGenericText,
-                                "action|enter_game\n".to_string(),
-                            );
-                            return;
-                        }
-                    }
-                    Err(_) => {}
+            if !items::is_current(server_hash) {
+                // Another bot may have already written a newer file since startup
+                if items::load_from_disk(&bot.item_database) == Some(server_hash) {
+                    bot.send_packet(
+                        EPacketType::NetMessageGenericText,
+                        "action|enter_game\n".to_string(),
+                    );
+                    return;
                 }
 
-                bot.send_packet(
-                    EPacketType::NetMessageGenericText,
-                    "action|refresh_item_data\n".to_string(),
-                );
-                let item_database_clone = bot.item_database.clone();
                 let bot_clone = Arc::clone(&bot);
                 thread::spawn(move || {
-                    loop {
-                        let item_database_loaded = {
-                            let item_database = item_database_clone.read().unwrap();
-                            item_database.loaded
-                        };
-                        if item_database_loaded {
-                            break;
+                    if let Err(err) = items::download(&bot_clone, server_hash) {
+                        {
+                            let mut state = bot_clone.state.lock().unwrap();
+                            if !state.is_running {
+                                return;
+                            }
+                            state.is_running = false;
                         }
-                        thread::sleep(std::time::Duration::from_millis(100));
+                        bot_clone.log_error(&format!("Giving up on items.dat, stopping: {}", err));
+                        bot_clone.set_status("items.dat download failed");
+                        bot_clone.disconnect();
+                        return;
                     }
                     bot_clone.send_packet(
                         EPacketType::NetMessageGenericText,
@@ -95,6 +94,12 @@ pub fn handle(bot: Arc<Bot>, _: &TankPacket, data: &[u8]) {
         "OnDialogRequest" => {
             let message = variant.get(1).unwrap().as_string();
             bot.log_info(format!("Received dialog request: {}", message).as_str());
+            quest::handle_dialog(&bot, &message);
+            onboarding::handle_dialog(&bot, &message);
+            if world_password::is_password_dialog(&message) {
+                world_password::handle_dialog(&bot, &message);
+            }
+            bot.temporary_data.write().unwrap().dialog = message.clone();
             if message.contains("Gazette") {
                 bot.send_packet(
                     EPacketType::NetMessageGenericText,
@@ -130,12 +135,24 @@ pub fn handle(bot: Arc<Bot>, _: &TankPacket, data: &[u8]) {
         }
         "OnSetBux" => {
             let bux = variant.get(1).unwrap().as_int32();
-            let mut state = bot.state.lock().unwrap();
-            state.gems = bux;
+            bot.gems.set(bux);
         }
         "OnConsoleMessage" => {
             let message = variant.get(1).unwrap().as_string();
             bot.log_info(format!("Received console message: {}", message).as_str());
+            if let Some((sender, text)) = whisper::parse(&message) {
+                whisper::handle(&bot, sender, text);
+            }
+            chat::handle_console_message(&bot, &message);
+            console::handle(&bot, &message);
+            disconnect::note_message(&bot, &message);
+            effects::note_message(&bot, &message);
+            auto_surgery::note_message(&bot, &message);
+            repeat_activate::note_message(&bot, &message);
+            world_password::handle_console_message(&bot, &message);
+            if let Some(name) = trade_request_sender(&message) {
+                bot.emit(BotEvent::TradeRequest { name });
+            }
             if message.contains("wants to add you to")
                 && message.contains("Wrench yourself to accept")
             {
@@ -151,6 +168,19 @@ pub fn handle(bot: Arc<Bot>, _: &TankPacket, data: &[u8]) {
         "OnSetPos" => {
             let pos = variant.get(1).unwrap().as_vec2();
             bot.log_info(format!("Received position: {:?}", pos).as_str());
+            bot.temporary_data.write().unwrap().last_set_pos = Some(Instant::now());
+            let entered_world = bot.temporary_data.read().unwrap().entered_world;
+            if entered_world {
+                let (x, y) = (pos.0, core::get_coordinate_to_touch_ground(pos.1));
+                if activation::is_teleport(&bot, x, y) {
+                    desync::teleported(&bot, x, y);
+                } else if !desync::check(&bot, x, y, "set position") {
+                    let mut position = bot.position.lock().unwrap();
+                    position.x = x;
+                    position.y = y;
+                }
+                return;
+            }
             let mut position = bot.position.lock().unwrap();
             let mut temp = bot.temporary_data.write().unwrap();
             let (world_name, main_door_x, main_door_y) = {
@@ -166,6 +196,14 @@ pub fn handle(bot: Arc<Bot>, _: &TankPacket, data: &[u8]) {
 
             let is_warp_to_id = world_name.contains("|");
 
+            // Warping to a door id lands somewhere on purpose, only the main door is walked off
+            if !temp.entered_world && !is_warp_to_id {
+                let bot_clone = Arc::clone(&bot);
+                thread::spawn(move || {
+                    bot_clone.walk_off_door();
+                });
+            }
+
             if !temp.entered_world
                 && (pos.0 != main_door_x || pos.1 != main_door_y)
                 && !is_warp_to_id
@@ -214,73 +252,88 @@ pub fn handle(bot: Arc<Bot>, _: &TankPacket, data: &[u8]) {
             let data = textparse::parse_and_store_as_map(&message);
             if data.contains_key("type") {
                 if data.get("type").unwrap() == "local" {
+                    let net_id = data.get("netID").and_then(|id| id.parse().ok()).unwrap_or(0);
+                    let user_id = data.get("userID").and_then(|id| id.parse().ok()).unwrap_or(0);
                     let mut state = bot.state.lock().unwrap();
-                    state.is_ingame = true;
-                    state.net_id = data.get("netID").unwrap().parse().unwrap();
+                    let was_ingame = state.is_ingame;
+                    let first_spawn = state.local_spawn(net_id, user_id);
+                    drop(state);
+                    if !was_ingame {
+                        telemetry::on_login(&bot);
+                        bot.temporary_data.write().unwrap().version_retried = false;
+                    }
+                    world_summary::on_local_spawn(&bot);
 
                     bot.send_packet(
                         EPacketType::NetMessageGenericText,
                         "action|getDRAnimations\n".to_string(),
                     );
+                    // What the client asks for once after logging in, before any world
+                    if first_spawn {
+                        bot.send_packet(
+                            EPacketType::NetMessageGenericText,
+                            "action|refresh_player_tribute_data\n".to_string(),
+                        );
+                        bot.log_info("Logged in");
+                        bot.emit(BotEvent::Login { net_id, user_id });
+                    }
                     return;
                 }
             } else {
-                let player = Player {
-                    _type: data.get("type").unwrap_or(&"".to_string()).to_string(),
-                    avatar: data.get("avatar").unwrap_or(&"".to_string()).to_string(),
-                    net_id: data
-                        .get("netID")
-                        .unwrap_or(&"0".to_string())
-                        .parse()
-                        .unwrap_or(0),
-                    online_id: data
-                        .get("onlineID")
-                        .unwrap_or(&"0".to_string())
-                        .parse()
-                        .unwrap_or("".to_string()),
-                    e_id: data
-                        .get("eid")
-                        .unwrap_or(&"0".to_string())
-                        .parse()
-                        .unwrap_or("".to_string()),
-                    ip: data.get("ip").unwrap_or(&"".to_string()).to_string(),
-                    colrect: data.get("colrect").unwrap_or(&"".to_string()).to_string(),
-                    title_icon: data.get("titleIcon").unwrap_or(&"".to_string()).to_string(),
-                    mstate: data
-                        .get("mstate")
-                        .unwrap_or(&"0".to_string())
-                        .parse()
-                        .unwrap_or(0),
-                    user_id: data
-                        .get("userID")
-                        .unwrap_or(&"0".to_string())
-                        .parse()
-                        .unwrap_or(0),
-                    invis: data
-                        .get("invis")
-                        .unwrap_or(&"0".to_string())
-                        .parse()
-                        .unwrap_or(false),
-                    name: data.get("name").unwrap_or(&"".to_string()).to_string(),
-                    country: data.get("country").unwrap_or(&"".to_string()).to_string(),
-                    position: {
-                        if data.contains_key("posXY") {
-                            let pos_xy = data.get("posXY").unwrap();
-                            Vector2 {
-                                x: pos_xy[..pos_xy.find("|").unwrap()].parse().unwrap_or(0.0),
-                                y: pos_xy[pos_xy.find("|").unwrap() + 1..]
-                                    .parse()
-                                    .unwrap_or(0.0),
-                            }
-                        } else {
-                            Vector2 { x: 0.0, y: 0.0 }
-                        }
-                    },
-                };
+                let player = Player::from_spawn(&data);
+                if player.is_moderator() {
+                    character_state::intruder(
+                        &bot,
+                        textparse::strip_color(&player.name),
+                        "has a moderator title",
+                    );
+                }
                 let mut players = bot.players.lock().unwrap();
                 players.push(player);
             }
         }
+        "OnSetClothing" => {
+            let (hair, shirt, pants) = variant.get(1).unwrap().as_vec3();
+            let (feet, face, hand) = variant.get(2).unwrap().as_vec3();
+            let (back, mask, necklace) = variant.get(3).unwrap().as_vec3();
+            let skin_color = variant.get(4).map(|v| v.as_uint32()).unwrap_or(0);
+            let ances = variant.get(5).map(|v| v.as_vec3().0).unwrap_or(0.0);
+            let clothing = Clothing {
+                hair: hair as u32,
+                shirt: shirt as u32,
+                pants: pants as u32,
+                feet: feet as u32,
+                face: face as u32,
+                hand: hand as u32,
+                back: back as u32,
+                mask: mask as u32,
+                necklace: necklace as u32,
+                ances: ances as u32,
+                skin_color,
+            };
+
+            let mut state = bot.state.lock().unwrap();
+            if tank_packet.net_id == state.net_id {
+                let apply_skin = skin_color != 0 && std::mem::take(&mut state.skin_pending);
+                state.clothing = clothing;
+                drop(state);
+                if apply_skin {
+                    bot.send_packet(
+                        EPacketType::NetMessageGenericText,
+                        format!("action|setSkin\ncolor|{}\n", skin_color),
+                    );
+                }
+            } else {
+                drop(state);
+                let mut players = bot.players.lock().unwrap();
+                if let Some(player) = players
+                    .iter_mut()
+                    .find(|player| player.net_id == tank_packet.net_id)
+                {
+                    player.clothing = clothing;
+                }
+            }
+        }
         "OnRemove" => {
             let message = variant.get(1).unwrap().as_string();
             let data = textparse::parse_and_store_as_map(&message);
@@ -293,14 +346,60 @@ pub fn handle(bot: Arc<Bot>, _: &TankPacket, data: &[u8]) {
             let message = variant.get(2).unwrap().as_string();
             bot.log_info(format!("Received talk bubble message: {}", message).as_str());
         }
+        "OnTextOverlay" => {
+            let message = variant.get(1).unwrap().as_string();
+            bot.log_info(format!("Received text overlay: {}", message).as_str());
+            repeat_activate::note_message(&bot, &message);
+        }
         "OnClearTutorialArrow" => {
             let v1 = variant.get(1).unwrap().as_string();
             bot.log_info(format!("Received OnClearTutorialArrow: {} ", v1).as_str());
         }
         "OnRequestWorldSelectMenu" => {
-            bot.world.write().unwrap().reset();
+            let world_name = bot.world.read().unwrap().name.clone();
+            if world_name != "EXIT" {
+                telemetry::on_world_exit(&bot, &world_name);
+                bot.emit(BotEvent::WorldLeave { name: world_name });
+            }
+            let mut world = bot.world.write().unwrap();
+            world.reset();
+            bot.world_name.set(world.name.clone());
+            drop(world);
             bot.players.lock().unwrap().clear();
         }
         _ => {}
     }
 }
+
+// "`#TRADE ALERT:`` `wname`` wants to trade with you! ...", only from the server. A player
+// saying the same thing in chat would otherwise raise a notification for any name
+fn trade_request_sender(message: &str) -> Option<String> {
+    if !textparse::is_system_line(message) {
+        return None;
+    }
+    let index = message.find(" wants to trade with you")?;
+    let prefix = textparse::strip_color(&message[..index]);
+    prefix.split_whitespace().last().map(|name| name.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trade_request_from_server() {
+        let message = "`#TRADE ALERT:`` `wSeller`` wants to trade with you!  To start, use the `wWrench`` on that person's wrench icon, or type `w/trade Seller``";
+        assert_eq!(trade_request_sender(message).as_deref(), Some("Seller"));
+    }
+
+    #[test]
+    fn trade_request_in_chat_is_ignored() {
+        for message in [
+            "CP:0_PL:4_OID:_CT:[W]_ `6<`wMallory``>`` `$`#TRADE ALERT:`` Victim wants to trade with you!``",
+            "`6<`wMallory``>`` Victim wants to trade with you!",
+            "`6>> from (`wMallory``) in [`$WORLD``] > `$Victim wants to trade with you!``",
+        ] {
+            assert_eq!(trade_request_sender(message), None, "{}", message);
+        }
+    }
+}
@@ -0,0 +1,105 @@
+use super::Bot;
+use crate::utils::{self, config, textparse};
+use regex::Regex;
+use std::sync::LazyLock;
+
+// The update message carries the required version as `$V4.71`
+static REQUIRED_VERSION: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\$V(\d+\.\d+)").unwrap());
+
+pub fn is_update_required(message: &str) -> bool {
+    message.contains("UPDATE REQUIRED")
+}
+
+pub fn parse_required_version(message: &str) -> Option<String> {
+    REQUIRED_VERSION
+        .captures(message)
+        .and_then(|caps| caps.get(1))
+        .map(|version| version.as_str().to_string())
+}
+
+// Returns the version and, when the source provides it, the protocol
+fn fetch_from_source() -> Option<(String, Option<String>)> {
+    let source = config::get_version_source();
+    if source.is_empty() {
+        return None;
+    }
+    let body = ureq::get(&source).call().ok()?.into_string().ok()?;
+    let data = textparse::parse_and_store_as_map(&body);
+    let version = data.get("version")?.clone();
+    Some((version, data.get("protocol").cloned()))
+}
+
+pub fn handle_update_required(bot: &Bot, message: &str) {
+    let already_retried = bot.temporary_data.read().unwrap().version_retried;
+    if already_retried {
+        bot.log_error("Server still requires an update after updating the version, stopping");
+        bot.state.lock().unwrap().is_running = false;
+        bot.disconnect();
+        return;
+    }
+
+    let (version, protocol) = match fetch_from_source() {
+        Some(latest) => latest,
+        None => match parse_required_version(message) {
+            Some(version) => (version, None),
+            None => {
+                bot.log_error("Update required but no version could be detected");
+                bot.state.lock().unwrap().is_running = false;
+                bot.disconnect();
+                return;
+            }
+        },
+    };
+
+    let (old_version, old_protocol, username) = {
+        let mut info = bot.info.lock().unwrap();
+        let old = (
+            info.login_info.game_version.clone(),
+            info.login_info.protocol.clone(),
+            info.payload[0].clone(),
+        );
+        info.login_info.game_version = version.clone();
+        if let Some(protocol) = &protocol {
+            info.login_info.protocol = protocol.clone();
+        }
+        old
+    };
+    bot.log_warn(&format!(
+        "Update required, game version {} -> {}",
+        old_version, version
+    ));
+    utils::config::set_game_version(version);
+    if let Some(protocol) = protocol {
+        bot.log_warn(&format!("Protocol {} -> {}", old_protocol, protocol));
+        utils::config::set_protocol(protocol);
+    }
+    utils::config::save_token_to_bot(username, "".to_string(), "".to_string());
+
+    bot.temporary_data.write().unwrap().version_retried = true;
+    bot.spoof();
+    bot.disconnect();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // As captured from the server's OnConsoleMessage / action|log after a game update
+    const UPDATE_LOG: &str = "action|log\nmsg|`4UPDATE REQUIRED!`` : The `$V4.71`` update is now available for your device.  Go get it!  You'll need to install it before you can play online.\n";
+    const UPDATE_DIALOG: &str = "set_default_color|`o\nadd_label_with_icon|big|`4UPDATE REQUIRED!``|left|18|\nadd_textbox|The `$V5.02`` update is now available.|left|\nend_dialog|update|Cancel|Update|\n";
+    const SERVER_BUSY: &str = "action|log\nmsg|`4SERVER OVERLOADED`` : Try again soon.\n";
+
+    #[test]
+    fn detects_update_required() {
+        assert!(is_update_required(UPDATE_LOG));
+        assert!(is_update_required(UPDATE_DIALOG));
+        assert!(!is_update_required(SERVER_BUSY));
+    }
+
+    #[test]
+    fn parses_the_required_version() {
+        assert_eq!(parse_required_version(UPDATE_LOG).as_deref(), Some("4.71"));
+        assert_eq!(parse_required_version(UPDATE_DIALOG).as_deref(), Some("5.02"));
+        assert_eq!(parse_required_version(SERVER_BUSY), None);
+    }
+}
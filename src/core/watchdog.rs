@@ -0,0 +1,121 @@
+use crate::utils::config;
+use std::time::{Duration, Instant};
+
+pub enum Stall {
+    ConnectTimeout(Duration),
+    NoPackets(Duration),
+}
+
+// Tracks one connection attempt, checked from the event loop so no extra thread is needed
+pub struct Watchdog {
+    connect_timeout: Duration,
+    stall_timeout: Duration,
+    started: Instant,
+    last_packet: Option<Instant>,
+    // The first silent window while waiting on the server only raises suspicion, the
+    // wait may have started late in it
+    suspect: bool,
+}
+
+impl Watchdog {
+    pub fn new() -> Self {
+        Self {
+            connect_timeout: Duration::from_secs(config::get_connect_timeout()),
+            stall_timeout: Duration::from_secs(config::get_stall_timeout()),
+            started: Instant::now(),
+            last_packet: None,
+            suspect: false,
+        }
+    }
+
+    pub fn on_connect(&mut self) {
+        self.last_packet = Some(Instant::now());
+    }
+
+    pub fn on_packet(&mut self) {
+        self.last_packet = Some(Instant::now());
+        self.suspect = false;
+    }
+
+    // An idle bot hears nothing from the server for as long as it stands still, so silence
+    // only counts while `awaiting` says a login or world join is waiting on a reply. It's
+    // asked once a window has passed, dead idle connections are left to ENet's own timeout
+    pub fn check(&mut self, awaiting: impl FnOnce() -> bool) -> Option<Stall> {
+        self.check_at(Instant::now(), awaiting)
+    }
+
+    fn check_at(&mut self, now: Instant, awaiting: impl FnOnce() -> bool) -> Option<Stall> {
+        let last_packet = match self.last_packet {
+            Some(last_packet) => last_packet,
+            None => {
+                let elapsed = now.duration_since(self.started);
+                return (elapsed > self.connect_timeout).then(|| Stall::ConnectTimeout(elapsed));
+            }
+        };
+        // 0 disables the silence check
+        if self.stall_timeout.is_zero() || now.duration_since(last_packet) <= self.stall_timeout {
+            return None;
+        }
+        let awaiting = awaiting();
+        if awaiting && self.suspect {
+            return Some(Stall::NoPackets(now.duration_since(last_packet)));
+        }
+        self.suspect = awaiting;
+        self.last_packet = Some(now);
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn watchdog(started: Instant) -> Watchdog {
+        Watchdog {
+            connect_timeout: Duration::from_secs(15),
+            stall_timeout: Duration::from_secs(60),
+            started,
+            last_packet: Some(started),
+            suspect: false,
+        }
+    }
+
+    #[test]
+    fn idle_bots_are_not_stalled() {
+        let start = Instant::now();
+        let mut watchdog = watchdog(start);
+        for minute in 1..10 {
+            let now = start + Duration::from_secs(61 * minute);
+            assert!(watchdog.check_at(now, || false).is_none());
+        }
+    }
+
+    #[test]
+    fn unanswered_waits_stall_after_a_second_window() {
+        let start = Instant::now();
+        let mut watchdog = watchdog(start);
+        assert!(watchdog.check_at(start + Duration::from_secs(61), || true).is_none());
+        assert!(watchdog.check_at(start + Duration::from_secs(100), || true).is_none());
+        let stall = watchdog.check_at(start + Duration::from_secs(122), || true);
+        assert!(matches!(stall, Some(Stall::NoPackets(_))));
+    }
+
+    #[test]
+    fn a_packet_clears_suspicion() {
+        let start = Instant::now();
+        let mut watchdog = watchdog(start);
+        assert!(watchdog.check_at(start + Duration::from_secs(61), || true).is_none());
+        watchdog.last_packet = Some(start + Duration::from_secs(70));
+        watchdog.suspect = false;
+        assert!(watchdog.check_at(start + Duration::from_secs(131), || true).is_none());
+    }
+
+    #[test]
+    fn connect_timeout_without_any_packet() {
+        let start = Instant::now();
+        let mut watchdog = Watchdog { last_packet: None, ..watchdog(start) };
+        assert!(watchdog.check_at(start + Duration::from_secs(10), || true).is_none());
+        let stall = watchdog.check_at(start + Duration::from_secs(16), || false);
+        assert!(matches!(stall, Some(Stall::ConnectTimeout(_))));
+    }
+}
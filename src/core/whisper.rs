@@ -0,0 +1,112 @@
+use super::{bandwidth, Bot};
+use crate::types::bot_event::BotEvent;
+use crate::utils::{config, textparse, webhook};
+use std::sync::mpsc::{self, SyncSender};
+use std::sync::{Arc, LazyLock};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// Senders remembered for the reply cooldown. The whispers themselves are kept in the
+// console's whisper channel, which drops the oldest past its own capacity
+const MAX_REPLY_SENDERS: usize = 256;
+// Whispers waiting to be forwarded, more than this and new ones are dropped
+const FORWARD_QUEUE_SIZE: usize = 16;
+
+// One webhook thread for every bot, so a whisper flood can't spawn a thread per message
+static FORWARDER: LazyLock<SyncSender<(Arc<Bot>, String, String)>> = LazyLock::new(|| {
+    let (sender, receiver) = mpsc::sync_channel::<(Arc<Bot>, String, String)>(FORWARD_QUEUE_SIZE);
+    thread::spawn(move || {
+        for (bot, url, content) in receiver {
+            if let Err(err) = webhook::send(&url, &content) {
+                bot.log_error(&format!("Failed to forward whisper: {}", err));
+            }
+        }
+    });
+    sender
+});
+
+// Private messages arrive as console messages, either bare or wrapped in the
+// `CP:..._CT:[MSG]_` chat prefix, e.g. ">> from (name) in [WORLD] > text"
+pub fn parse(message: &str) -> Option<(String, String)> {
+    let message = textparse::strip_color(message);
+    let message = match message.split_once("CT:[MSG]_") {
+        // The tag has to be the line's own, one typed in chat comes after the player's
+        // `CT:[W]_` and reads like a whisper otherwise
+        Some((header, body)) if header.starts_with("CP:") && textparse::is_system_line(header) => body,
+        Some(_) => return None,
+        None => message.as_str(),
+    };
+    let rest = message.trim_start().strip_prefix(">> from (")?;
+    let (sender, rest) = rest.split_once(')')?;
+    let (_, text) = rest.split_once("> ")?;
+    let sender = sender.trim();
+    if sender.is_empty() {
+        return None;
+    }
+    Some((sender.to_string(), text.trim().to_string()))
+}
+
+pub fn handle(bot: &Arc<Bot>, sender: String, text: String) {
+    bot.log_info(&format!("Whisper from {}: {}", sender, text));
+    bot.add_stat("whispers_received", 1);
+    bot.emit(BotEvent::Whisper {
+        sender: sender.clone(),
+        text: text.clone(),
+    });
+
+    if config::get_forward_whispers() {
+        let url = config::get_webhook_url();
+        if !url.is_empty() {
+            let bot_name = bot.info.lock().unwrap().payload[0].clone();
+            let content = format!("[{}] whisper from {}: {}", bot_name, sender, text);
+            if FORWARDER.try_send((Arc::clone(bot), url, content)).is_err() {
+                bot.log_warn("Whisper forwarding is behind, dropped a whisper");
+            }
+        }
+    }
+
+    if config::get_auto_reply() && !bandwidth::throttled() && cooldown_elapsed(bot, &sender) {
+        let reply = config::get_auto_reply_message().replace("{sender}", &sender);
+        bot.whisper(&sender, &reply);
+    }
+}
+
+fn cooldown_elapsed(bot: &Bot, sender: &str) -> bool {
+    let cooldown = Duration::from_secs(config::get_auto_reply_cooldown() as u64);
+    let mut last_replies = bot.whisper_replies.lock().unwrap();
+    let key = sender.to_lowercase();
+    if let Some(last) = last_replies.get(&key) {
+        if last.elapsed() < cooldown {
+            return false;
+        }
+    }
+    // Senders whose cooldown is over don't need remembering, and a flood of new names
+    // can't grow the map past the cap either
+    last_replies.retain(|_, last| last.elapsed() < cooldown);
+    if last_replies.len() >= MAX_REPLY_SENDERS {
+        if let Some(oldest) = last_replies.iter().min_by_key(|(_, last)| **last).map(|(name, _)| name.clone()) {
+            last_replies.remove(&oldest);
+        }
+    }
+    last_replies.insert(key, Instant::now());
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_bare_and_tagged_whispers() {
+        let expected = Some(("Friend".to_string(), "hello there".to_string()));
+        assert_eq!(parse(">> from (`wFriend``) in [START] > hello there"), expected);
+        assert_eq!(parse("CP:0_PL:0_OID:_CT:[MSG]_>> from (Friend) in [START] > hello there"), expected);
+    }
+
+    #[test]
+    fn ignores_whispers_typed_in_chat() {
+        assert_eq!(parse("CP:0_PL:4_OID:_CT:[W]_ <Mallory> CT:[MSG]_>> from (Admin) in [X] > give"), None);
+        assert_eq!(parse("CP:0_PL:4_OID:_CT:[W]_ <Mallory> >> from (Admin) in [X] > give"), None);
+        assert_eq!(parse("<Mallory> >> from (Admin) in [X] > give"), None);
+    }
+}
@@ -0,0 +1,248 @@
+use gtworld_r::{TileType, World};
+use std::collections::BTreeSet;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TileChange {
+    pub x: u32,
+    pub y: u32,
+    pub foreground: (u16, u16),
+    pub background: (u16, u16),
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WorldDiff {
+    // Width and height before and after, only set when they differ
+    pub dimensions: Option<((u32, u32), (u32, u32))>,
+    // Tiles outside the smaller of the two worlds aren't compared
+    pub tiles: Vec<TileChange>,
+    // Position and item id of each lock
+    pub locks_added: Vec<(u32, u32, u16)>,
+    pub locks_removed: Vec<(u32, u32, u16)>,
+    pub dropped: (usize, usize),
+}
+
+impl WorldDiff {
+    pub fn is_empty(&self) -> bool {
+        self.dimensions.is_none()
+            && self.tiles.is_empty()
+            && self.locks_added.is_empty()
+            && self.locks_removed.is_empty()
+            && self.dropped.0 == self.dropped.1
+    }
+
+    pub fn dropped_delta(&self) -> i64 {
+        self.dropped.1 as i64 - self.dropped.0 as i64
+    }
+
+    // One line per change, `item_name` turns item ids into something readable
+    pub fn describe(&self, item_name: impl Fn(u16) -> String) -> Vec<String> {
+        let mut lines = Vec::new();
+        if let Some(((width, height), (new_width, new_height))) = self.dimensions {
+            lines.push(format!(
+                "Size changed from {}x{} to {}x{}",
+                width, height, new_width, new_height
+            ));
+        }
+        for &(x, y, id) in &self.locks_added {
+            lines.push(format!("{}|{}: {} added", x, y, item_name(id)));
+        }
+        for &(x, y, id) in &self.locks_removed {
+            lines.push(format!("{}|{}: {} removed", x, y, item_name(id)));
+        }
+        if self.dropped.0 != self.dropped.1 {
+            lines.push(format!(
+                "Dropped items: {} -> {} ({:+})",
+                self.dropped.0,
+                self.dropped.1,
+                self.dropped_delta()
+            ));
+        }
+        for change in &self.tiles {
+            let mut parts = Vec::new();
+            if change.foreground.0 != change.foreground.1 {
+                parts.push(format!(
+                    "foreground {} -> {}",
+                    item_name(change.foreground.0),
+                    item_name(change.foreground.1)
+                ));
+            }
+            if change.background.0 != change.background.1 {
+                parts.push(format!(
+                    "background {} -> {}",
+                    item_name(change.background.0),
+                    item_name(change.background.1)
+                ));
+            }
+            lines.push(format!("{}|{}: {}", change.x, change.y, parts.join(", ")));
+        }
+        lines
+    }
+}
+
+// What changed going from `before` to `after`, both usually snapshots of the same world
+pub fn diff(before: &World, after: &World) -> WorldDiff {
+    diff_layouts(&Layout::of(before), &Layout::of(after))
+}
+
+// The part of a world the diff looks at, so it can be tested without map data
+struct Layout {
+    width: u32,
+    height: u32,
+    // Row by row, (foreground, background, is a lock)
+    tiles: Vec<(u16, u16, bool)>,
+    dropped: usize,
+}
+
+impl Layout {
+    fn of(world: &World) -> Self {
+        let mut tiles = Vec::with_capacity((world.width * world.height) as usize);
+        for y in 0..world.height {
+            for x in 0..world.width {
+                tiles.push(world.get_tile(x, y).map_or((0, 0, false), |tile| {
+                    (
+                        tile.foreground_item_id,
+                        tile.background_item_id,
+                        matches!(tile.tile_type, TileType::Lock { .. }),
+                    )
+                }));
+            }
+        }
+        Layout {
+            width: world.width,
+            height: world.height,
+            tiles,
+            dropped: world.dropped.items.len(),
+        }
+    }
+
+    fn get(&self, x: u32, y: u32) -> Option<(u16, u16, bool)> {
+        self.tiles.get((y * self.width + x) as usize).copied()
+    }
+
+    fn locks(&self) -> BTreeSet<(u32, u32, u16)> {
+        (0..self.height)
+            .flat_map(|y| (0..self.width).map(move |x| (x, y)))
+            .filter_map(|(x, y)| match self.get(x, y)? {
+                (foreground, _, true) => Some((x, y, foreground)),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+fn diff_layouts(before: &Layout, after: &Layout) -> WorldDiff {
+    let dimensions = if before.width != after.width || before.height != after.height {
+        Some(((before.width, before.height), (after.width, after.height)))
+    } else {
+        None
+    };
+
+    let mut tiles = Vec::new();
+    for y in 0..before.height.min(after.height) {
+        for x in 0..before.width.min(after.width) {
+            let (old, new) = match (before.get(x, y), after.get(x, y)) {
+                (Some(old), Some(new)) => (old, new),
+                _ => continue,
+            };
+            if old.0 != new.0 || old.1 != new.1 {
+                tiles.push(TileChange {
+                    x,
+                    y,
+                    foreground: (old.0, new.0),
+                    background: (old.1, new.1),
+                });
+            }
+        }
+    }
+
+    let (old_locks, new_locks) = (before.locks(), after.locks());
+    WorldDiff {
+        dimensions,
+        tiles,
+        locks_added: new_locks.difference(&old_locks).copied().collect(),
+        locks_removed: old_locks.difference(&new_locks).copied().collect(),
+        dropped: (before.dropped, after.dropped),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIRT: u16 = 2;
+    const CAVE: u16 = 14;
+    const WORLD_LOCK: u16 = 242;
+
+    fn layout(width: u32, height: u32) -> Layout {
+        Layout {
+            width,
+            height,
+            tiles: vec![(DIRT, CAVE, false); (width * height) as usize],
+            dropped: 0,
+        }
+    }
+
+    fn set(layout: &mut Layout, x: u32, y: u32, tile: (u16, u16, bool)) {
+        layout.tiles[(y * layout.width + x) as usize] = tile;
+    }
+
+    #[test]
+    fn identical_worlds_have_no_changes() {
+        let diff = diff_layouts(&layout(5, 4), &layout(5, 4));
+        assert!(diff.is_empty());
+        assert!(diff.describe(|id| id.to_string()).is_empty());
+    }
+
+    #[test]
+    fn reports_changed_tiles() {
+        let before = layout(5, 4);
+        let mut after = layout(5, 4);
+        set(&mut after, 1, 2, (0, CAVE, false));
+        set(&mut after, 3, 0, (DIRT, 0, false));
+        let diff = diff_layouts(&before, &after);
+        assert_eq!(
+            diff.tiles,
+            vec![
+                TileChange { x: 3, y: 0, foreground: (DIRT, DIRT), background: (CAVE, 0) },
+                TileChange { x: 1, y: 2, foreground: (DIRT, 0), background: (CAVE, CAVE) },
+            ]
+        );
+        assert_eq!(
+            diff.describe(|id| format!("#{}", id)),
+            vec!["3|0: background #14 -> #0", "1|2: foreground #2 -> #0"]
+        );
+    }
+
+    #[test]
+    fn reports_added_and_removed_locks() {
+        let mut before = layout(5, 4);
+        set(&mut before, 0, 0, (WORLD_LOCK, CAVE, true));
+        let mut after = layout(5, 4);
+        set(&mut after, 4, 3, (WORLD_LOCK, CAVE, true));
+        let diff = diff_layouts(&before, &after);
+        assert_eq!(diff.locks_added, vec![(4, 3, WORLD_LOCK)]);
+        assert_eq!(diff.locks_removed, vec![(0, 0, WORLD_LOCK)]);
+    }
+
+    #[test]
+    fn reports_the_dropped_item_delta() {
+        let before = Layout { dropped: 7, ..layout(2, 2) };
+        let after = Layout { dropped: 4, ..layout(2, 2) };
+        let diff = diff_layouts(&before, &after);
+        assert_eq!(diff.dropped_delta(), -3);
+        assert_eq!(diff.describe(|id| id.to_string()), vec!["Dropped items: 7 -> 4 (-3)"]);
+    }
+
+    #[test]
+    fn compares_the_overlap_of_different_sizes() {
+        let before = layout(5, 4);
+        let mut after = layout(3, 6);
+        set(&mut after, 2, 5, (0, 0, false));
+        set(&mut after, 2, 1, (0, CAVE, false));
+        let diff = diff_layouts(&before, &after);
+        assert_eq!(diff.dimensions, Some(((5, 4), (3, 6))));
+        assert_eq!(diff.tiles.len(), 1);
+        assert_eq!((diff.tiles[0].x, diff.tiles[0].y), (2, 1));
+        assert_eq!(diff.describe(|id| id.to_string())[0], "Size changed from 5x4 to 3x6");
+    }
+}
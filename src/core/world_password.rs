@@ -0,0 +1,69 @@
+use crate::core::Bot;
+use crate::types::bot_event::BotEvent;
+use crate::types::epacket_type::EPacketType;
+use crate::utils::{config, textparse};
+
+// The access-password prompt, answered with a dialog_return of the same name
+const DIALOG_NAME: &str = "password_reply";
+
+pub fn is_password_dialog(message: &str) -> bool {
+    message.contains(&format!("end_dialog|{}|", DIALOG_NAME))
+}
+
+// Answers the prompt with the stored password for the world being warped to. Without one the
+// pending warp fails and the user is asked for it. A prompt with no warp pending, like a
+// password door inside the current world, is left alone. The password itself is never
+// logged or captured
+pub fn handle_dialog(bot: &Bot, message: &str) {
+    let world = match target_world(bot) {
+        Some(world) => world,
+        None => {
+            bot.log_info("Password prompt without a pending warp, not answering it");
+            return;
+        }
+    };
+    let password = match config::get_world_password(&world) {
+        Some(password) => password,
+        None => {
+            bot.log_warn(&format!("{} asks for a password and none is saved", world));
+            fail_warp(bot, format!("No password saved for {}", world));
+            bot.emit(BotEvent::WorldPasswordRequired { world });
+            return;
+        }
+    };
+
+    bot.log_info(&format!("Entering the saved password for {}", world));
+    // The client sends embedded values back untouched, the server matches the reply on them
+    let mut reply = format!("action|dialog_return\ndialog_name|{}\n", DIALOG_NAME);
+    for line in message.lines() {
+        let mut parts = line.split('|');
+        if let (Some("embed_data"), Some(key), Some(value)) = (parts.next(), parts.next(), parts.next()) {
+            reply.push_str(&format!("{}|{}\n", key, value));
+        }
+    }
+    reply.push_str(&format!("password|{}\n", password));
+    bot.send_secret_packet(EPacketType::NetMessageGenericText, reply, &password);
+}
+
+pub fn handle_console_message(bot: &Bot, message: &str) {
+    if !textparse::is_system_line(message) || !message.to_lowercase().contains("wrong password") {
+        return;
+    }
+    let world = match target_world(bot) {
+        Some(world) => world,
+        None => return,
+    };
+    bot.log_warn(&format!("The saved password for {} was rejected", world));
+    fail_warp(bot, format!("Wrong password for {}", world));
+    bot.emit(BotEvent::WorldPasswordRequired { world });
+}
+
+fn target_world(bot: &Bot) -> Option<String> {
+    bot.temporary_data.read().unwrap().warp_target.clone()
+}
+
+fn fail_warp(bot: &Bot, reason: String) {
+    let mut temp = bot.temporary_data.write().unwrap();
+    temp.warp_target = None;
+    temp.warp_error = Some(reason);
+}
@@ -0,0 +1,146 @@
+use crate::utils::date;
+use gtitem_r::structs::ItemDatabase;
+use paris::warn;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, SyncSender};
+use std::sync::{Arc, LazyLock, RwLock};
+use std::thread;
+use std::time::UNIX_EPOCH;
+
+const SNAPSHOT_DIR: &str = "worlds";
+const HISTORY_DIR: &str = "worlds/history";
+// Older snapshots kept per world, at most one per interval so frequent visits don't
+// push yesterday's version out
+const HISTORY_PER_WORLD: usize = 24;
+const HISTORY_INTERVAL: u64 = 3600;
+// Saves waiting for the writer, more than this and the save is dropped until the next visit
+const QUEUE_SIZE: usize = 8;
+
+// One writer thread for every bot, so the packet thread never waits on the disk. A world
+// that comes back unchanged since its last save isn't written again
+static WRITER: LazyLock<SyncSender<(String, Vec<u8>)>> = LazyLock::new(|| {
+    let (sender, receiver) = mpsc::sync_channel::<(String, Vec<u8>)>(QUEUE_SIZE);
+    thread::spawn(move || {
+        let mut saved: HashMap<String, u64> = HashMap::new();
+        for (world_name, data) in receiver {
+            let mut hasher = DefaultHasher::new();
+            data.hash(&mut hasher);
+            let hash = hasher.finish();
+            if saved.get(&world_name) == Some(&hash) {
+                continue;
+            }
+            match save(&world_name, &data) {
+                Ok(()) => {
+                    saved.insert(world_name, hash);
+                }
+                Err(err) => warn!("Failed to save world snapshot of {}: {}", world_name, err),
+            }
+        }
+    });
+    sender
+});
+
+// Hands the map data to the writer thread, false when its queue is full
+pub fn queue_save(world_name: &str, data: &[u8]) -> bool {
+    WRITER.try_send((world_name.to_string(), data.to_vec())).is_ok()
+}
+
+// Raw map data of the last visit to each world, enough to rebuild the World offline
+fn save(world_name: &str, data: &[u8]) -> io::Result<()> {
+    fs::create_dir_all(SNAPSHOT_DIR)?;
+    let path = path(world_name);
+    if path.exists() {
+        archive(world_name)?;
+    }
+    let tmp = path.with_extension("dat.tmp");
+    fs::write(&tmp, data)?;
+    fs::rename(&tmp, &path)
+}
+
+// Saved world names, sorted
+pub fn list() -> Vec<String> {
+    let mut names: Vec<String> = fs::read_dir(SNAPSHOT_DIR)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .filter(|path| path.extension().map_or(false, |ext| ext == "dat"))
+                .filter_map(|path| Some(path.file_stem()?.to_str()?.to_string()))
+                .collect()
+        })
+        .unwrap_or_default();
+    names.sort();
+    names
+}
+
+pub fn load(world_name: &str, item_database: Arc<RwLock<ItemDatabase>>) -> io::Result<gtworld_r::World> {
+    let data = fs::read(path(world_name))?;
+    let mut world = gtworld_r::World::new(item_database);
+    world.parse(&data);
+    Ok(world)
+}
+
+// Unix times of the archived snapshots of a world, newest first
+pub fn history(world_name: &str) -> Vec<u64> {
+    let prefix = format!("{}_", world_name.to_uppercase());
+    let mut times: Vec<u64> = fs::read_dir(HISTORY_DIR)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .filter_map(|entry| {
+                    let name = entry.file_name().to_str()?.to_string();
+                    name.strip_prefix(&prefix)?.strip_suffix(".dat")?.parse().ok()
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    times.sort_unstable_by(|a, b| b.cmp(a));
+    times
+}
+
+pub fn load_archived(
+    world_name: &str,
+    saved_at: u64,
+    item_database: Arc<RwLock<ItemDatabase>>,
+) -> io::Result<gtworld_r::World> {
+    let data = fs::read(archived_path(world_name, saved_at))?;
+    let mut world = gtworld_r::World::new(item_database);
+    world.parse(&data);
+    Ok(world)
+}
+
+// Copies the current snapshot into the history, stamped with when it was saved,
+// unless the newest archived one is more recent than HISTORY_INTERVAL
+fn archive(world_name: &str) -> io::Result<()> {
+    let saved_at = fs::metadata(path(world_name))?
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map_or_else(date::now_secs, |since| since.as_secs());
+    let history = history(world_name);
+    if history.first().map_or(false, |&newest| saved_at.saturating_sub(newest) < HISTORY_INTERVAL) {
+        return Ok(());
+    }
+    fs::create_dir_all(HISTORY_DIR)?;
+    fs::copy(path(world_name), archived_path(world_name, saved_at))?;
+    // A leftover old snapshot is no reason to lose the new one
+    for &old in history.iter().skip(HISTORY_PER_WORLD - 1) {
+        if let Err(err) = fs::remove_file(archived_path(world_name, old)) {
+            warn!("Failed to remove an old snapshot of {}: {}", world_name, err);
+        }
+    }
+    Ok(())
+}
+
+fn archived_path(world_name: &str, saved_at: u64) -> PathBuf {
+    PathBuf::from(HISTORY_DIR).join(format!("{}_{}.dat", world_name.to_uppercase(), saved_at))
+}
+
+fn path(world_name: &str) -> PathBuf {
+    PathBuf::from(SNAPSHOT_DIR).join(format!("{}.dat", world_name.to_uppercase()))
+}
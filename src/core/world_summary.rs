@@ -0,0 +1,109 @@
+use super::Bot;
+use crate::types::{bot_event::BotEvent, world_summary::WorldSummary};
+use crate::utils::{config, textparse, webhook};
+use gtworld_r::TileType;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+const GEM_ITEM_ID: u16 = 112;
+// Other players' OnSpawn arrive right after the local one
+const SPAWN_SETTLE: Duration = Duration::from_millis(1000);
+
+pub fn on_local_spawn(bot: &Arc<Bot>) {
+    let bot = Arc::clone(bot);
+    thread::spawn(move || {
+        let world_name = bot.world.read().unwrap().name.clone();
+        thread::sleep(SPAWN_SETTLE);
+        if !bot.is_inworld() || bot.world.read().unwrap().name != world_name {
+            return;
+        }
+        announce(&bot, build(&bot));
+    });
+}
+
+pub fn build(bot: &Bot) -> WorldSummary {
+    let user_id: u32 = bot.info.lock().unwrap().login_info.user.parse().unwrap_or(0);
+    let farmable_item = config::get_farmable_item();
+    let players = bot.players.lock().unwrap().clone();
+    let world = bot.world.read().unwrap();
+    let item_database = bot.item_database.read().unwrap();
+
+    let mut owner = None;
+    let mut has_access = true;
+    for tile in world.tiles.iter() {
+        if let TileType::Lock {
+            owner_uid,
+            access_uids,
+            ..
+        } = &tile.tile_type
+        {
+            let is_world_lock = item_database
+                .get_item(&(tile.foreground_item_id as u32))
+                .map_or(false, |item| item.name.contains("World Lock"));
+            if !is_world_lock {
+                continue;
+            }
+            owner = Some(
+                players
+                    .iter()
+                    .find(|player| player.user_id == *owner_uid)
+                    .map(|player| textparse::strip_color(&player.name))
+                    .unwrap_or_else(|| format!("#{}", owner_uid)),
+            );
+            has_access = *owner_uid == user_id || access_uids.contains(&user_id);
+            break;
+        }
+    }
+
+    let farmable_tiles = if farmable_item == 0 {
+        None
+    } else {
+        Some(
+            world
+                .tiles
+                .iter()
+                .filter(|tile| tile.foreground_item_id as u32 == farmable_item)
+                .count(),
+        )
+    };
+
+    WorldSummary {
+        name: world.name.clone(),
+        width: world.width,
+        height: world.height,
+        owner,
+        players: players.len(),
+        farmable_tiles,
+        dropped_objects: world.dropped.items.len(),
+        dropped_gems: world
+            .dropped
+            .items
+            .iter()
+            .filter(|item| item.id == GEM_ITEM_ID)
+            .map(|item| item.count as u32)
+            .sum(),
+        has_access,
+    }
+}
+
+fn announce(bot: &Arc<Bot>, summary: WorldSummary) {
+    bot.log_info(&format!("Entered {}", summary.describe().replace('\n', ", ")));
+    bot.temporary_data.write().unwrap().world_summary = Some(summary.clone());
+
+    if config::get_world_summary_webhook() {
+        let url = config::get_webhook_url();
+        if !url.is_empty() {
+            let bot_name = bot.info.lock().unwrap().payload[0].clone();
+            let content = format!("[{}] entered {}", bot_name, summary.describe());
+            let bot_clone = Arc::clone(bot);
+            thread::spawn(move || {
+                if let Err(err) = webhook::send(&url, &content) {
+                    bot_clone.log_error(&format!("Failed to send world summary: {}", err));
+                }
+            });
+        }
+    }
+
+    bot.emit(BotEvent::WorldEnter(summary));
+}
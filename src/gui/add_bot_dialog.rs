@@ -1,9 +1,8 @@
-use std::fs;
 use std::sync::{Arc, RwLock};
 use eframe::egui::{self};
 use crate::{
-    manager::bot_manager::BotManager,
-    types::{config::BotConfig, elogin_method::ELoginMethod},
+    manager::{bot_manager::BotManager, bot_template},
+    types::{config::{BotConfig, BotTemplate}, elogin_method::ELoginMethod},
     utils,
 };
 
@@ -13,9 +12,16 @@ pub struct AddBotDialog {
     pub password: String,
     pub steam_user: String,
     pub steam_pass: String,
+    pub steam_shared_secret: String,
     pub code: String,
     pub method: ELoginMethod,
     pub use_proxy: bool,
+    pub bind_address: String,
+    pub group: String,
+    pub login_window: String,
+    pub template: String,
+    // The picked template, its values are shown as hints for the fields left empty
+    template_values: BotTemplate,
     pub open: bool,
 }
 
@@ -31,6 +37,9 @@ impl AddBotDialog {
                         .min_col_width(100.0)
                         .max_col_width(100.0)
                         .show(ui, |ui| {
+                            ui.label("Template");
+                            self.render_template_picker(ui);
+                            ui.end_row();
                             ui.label("Username");
                             ui.text_edit_singleline(&mut self.username);
                             ui.end_row();
@@ -43,11 +52,17 @@ impl AddBotDialog {
                             ui.label("Steam Password");
                             ui.text_edit_singleline(&mut self.steam_pass);
                             ui.end_row();
+                            ui.label("Shared secret");
+                            ui.add(
+                                egui::TextEdit::singleline(&mut self.steam_shared_secret)
+                                    .hint_text("optional, from maFile"),
+                            );
+                            ui.end_row();
                             ui.label("2FA Code");
                             ui.text_edit_singleline(&mut self.code);
                             ui.end_row();
                             ui.label("Login Method");
-                            egui::ComboBox::from_id_source("login_method")
+                            egui::ComboBox::from_id_salt("login_method")
                                 .selected_text(format!("{:?}", self.method))
                                 .show_ui(ui, |ui| {
                                     ui.selectable_value(
@@ -72,7 +87,21 @@ impl AddBotDialog {
                                     );
                                 });
                             ui.end_row();
-                            ui.checkbox(&mut self.use_proxy, "Use proxy");
+                            ui.label("Bind address");
+                            ui.add(
+                                egui::TextEdit::singleline(&mut self.bind_address)
+                                    .hint_text("0.0.0.0"),
+                            );
+                            ui.end_row();
+                            ui.label("Group");
+                            ui.add(egui::TextEdit::singleline(&mut self.group).hint_text(or_hint(&self.template_values.group, "optional")));
+                            ui.end_row();
+                            ui.label("Login window");
+                            ui.add(egui::TextEdit::singleline(&mut self.login_window).hint_text(or_hint(&self.template_values.login_window, "14:00-16:00")))
+                                .on_hover_text("Only online in this window each day, empty to use the group's");
+                            ui.end_row();
+                            ui.add_enabled(!self.template_values.use_proxy, egui::Checkbox::new(&mut self.use_proxy, "Use proxy"))
+                                .on_disabled_hover_text("The template uses a proxy");
                         });
                     if ui.button("Add").clicked() {
                         let config;
@@ -84,6 +113,11 @@ impl AddBotDialog {
                                 token: "".to_string(),
                                 data: "".to_string(),
                                 use_proxy: self.use_proxy,
+                                bind_address: self.bind_address.trim().to_string(),
+                                group: self.group.trim().to_string(),
+                                steam_shared_secret: self.steam_shared_secret.trim().to_string(),
+                                login_window: self.login_window.trim().to_string(),
+                                ..bot_template::config_from(&self.template)
                             };
                         } else {
                             config = BotConfig {
@@ -93,19 +127,26 @@ impl AddBotDialog {
                                 token: "".to_string(),
                                 data: "".to_string(),
                                 use_proxy: self.use_proxy,
+                                bind_address: self.bind_address.trim().to_string(),
+                                group: self.group.trim().to_string(),
+                                steam_shared_secret: String::new(),
+                                login_window: self.login_window.trim().to_string(),
+                                ..bot_template::config_from(&self.template)
                             };
                         }
                         {
-                            manager.write().unwrap().add_bot(config.clone());
+                            manager.read().unwrap().add_bot(config.clone());
                         }
-                        let mut data = utils::config::parse_config().unwrap();
-                        data.bots.push(config);
-                        fs::write("config.json", &serde_json::to_string_pretty(&data).unwrap())
-                            .unwrap();
+                        utils::config::add_bot(config);
                         self.username.clear();
                         self.password.clear();
                         self.code.clear();
-                        self.method = ELoginMethod::LEGACY;
+                        self.bind_address.clear();
+                        self.steam_shared_secret.clear();
+                        self.group.clear();
+                        self.login_window.clear();
+                        // The template stays picked, the next bot is likely from it as well
+                        self.apply_template();
                         close_dialog = true;
                     }
                 });
@@ -114,4 +155,44 @@ impl AddBotDialog {
             }
         }
     }
+
+    fn render_template_picker(&mut self, ui: &mut egui::Ui) {
+        let templates = utils::config::get_bot_templates();
+        let selected = if self.template.is_empty() { "None" } else { self.template.as_str() };
+        let mut picked = None;
+        egui::ComboBox::from_id_salt("add_bot_template")
+            .selected_text(selected.to_string())
+            .show_ui(ui, |ui| {
+                if ui.selectable_label(self.template.is_empty(), "None").clicked() {
+                    picked = Some(String::new());
+                }
+                for template in templates.iter() {
+                    if ui.selectable_label(self.template == template.name, &template.name).clicked() {
+                        picked = Some(template.name.clone());
+                    }
+                }
+            });
+        if let Some(name) = picked {
+            self.template = name;
+            self.apply_template();
+        }
+    }
+
+    // Takes the login method from the picked template, credentials are left as typed. The
+    // other fields start empty and only override the template when filled in
+    fn apply_template(&mut self) {
+        self.template_values = bot_template::find(&self.template).unwrap_or_default();
+        self.method = self.template_values.login_method.clone();
+        self.use_proxy = false;
+        self.group.clear();
+        self.login_window.clear();
+    }
+}
+
+fn or_hint(template_value: &str, hint: &str) -> String {
+    if template_value.is_empty() {
+        hint.to_string()
+    } else {
+        format!("{} (template)", template_value)
+    }
 }
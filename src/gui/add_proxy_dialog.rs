@@ -7,6 +7,7 @@ use crate::utils;
 #[derive(Default)]
 pub struct AddProxyDialog {
     pub payload: String,
+    pub pool: String,
     pub open: bool,
 }
 
@@ -25,6 +26,9 @@ impl AddProxyDialog {
                             ui.label("Payload");
                             ui.text_edit_singleline(&mut self.payload);
                             ui.end_row();
+                            ui.label("Pool");
+                            ui.add(egui::TextEdit::singleline(&mut self.pool).hint_text("default"));
+                            ui.end_row();
                         });
                     if ui.button("Add").clicked() {
                         // TODO: Add validation
@@ -34,6 +38,7 @@ impl AddProxyDialog {
                             port: payload[1].parse::<u16>().unwrap(),
                             username: payload[2].clone(),
                             password: payload[3].clone(),
+                            pool: self.pool.trim().to_string(),
                         };
                         manager.write().unwrap().add(proxy_config);
                         self.payload.clear();
@@ -1,11 +1,27 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver};
 use std::sync::{Arc, RwLock};
 use std::thread;
 
+use crate::gui::dev_tools::DevTools;
+use crate::gui::features::Features;
 use crate::gui::growscan::Growscan;
 use crate::gui::inventory::Inventory;
+use crate::gui::repl::Repl;
 use crate::gui::scripting::Scripting;
+use crate::gui::timeline;
 use crate::gui::world_map::WorldMap;
+use crate::core::{bandwidth, outgoing, value_table, Bot};
+use crate::types::bot_summary::BotSummary;
+use crate::types::console_line::ConsoleLine;
+use crate::types::ebot_filter::EBotFilter;
+use crate::types::echat_channel::EChatChannel;
+use crate::types::echat_state::EChatState;
+use crate::types::ehumanizer_preset::EHumanizerPreset;
 use crate::texture_manager::TextureManager;
+use crate::utils::export::ExportFormat;
+use crate::utils::gt_text;
 use crate::{manager::bot_manager::BotManager, types::config::BotConfig, utils};
 use eframe::egui::{self, Ui};
 use egui::scroll_area::ScrollBarVisibility;
@@ -21,45 +37,86 @@ pub struct BotMenu {
     pub inventory: Inventory,
     pub growscan: Growscan,
     pub scripting: Scripting,
+    pub repl: Repl,
+    pub dev_tools: DevTools,
+    pub features: Features,
+    pub export_open: bool,
+    pub export_path: String,
+    pub export_format: ExportFormat,
+    // Save dialog still open, it runs on its own thread and sends back the chosen path
+    export_pick: Option<Receiver<Option<PathBuf>>>,
+    // Console channel shown instead of the logs
+    pub console_tab: Option<EChatChannel>,
+    pub guard_code: String,
+    // Log timestamp to scroll to, set by clicking a timeline marker
+    pub log_jump: Option<u64>,
+    // Humanizer seed being edited and the bot it's for, saved once the drag or edit ends
+    humanizer_seed: Option<(String, u64)>,
+    live: LiveFields,
+    console: ConsoleView,
+}
+
+// Last read of the selected bot's watched fields, only read again once their version moves
+#[derive(Default)]
+struct LiveFields {
+    bot: String,
+    versions: [u64; 4],
+    status: String,
+    ping: u32,
+    world_name: String,
+    gems: i32,
+}
+
+impl LiveFields {
+    fn refresh(&mut self, bot: &Bot, username: &str) {
+        if self.bot != username {
+            // Versions no bot has reached, so everything is read for the new one
+            *self = LiveFields {
+                bot: username.to_string(),
+                versions: [u64::MAX; 4],
+                ..Default::default()
+            };
+        }
+        if let Some(status) = bot.status.changed_since(&mut self.versions[0]) {
+            self.status = status;
+        }
+        if let Some(ping) = bot.ping.changed_since(&mut self.versions[1]) {
+            self.ping = ping;
+        }
+        if let Some(world_name) = bot.world_name.changed_since(&mut self.versions[2]) {
+            self.world_name = world_name;
+        }
+        if let Some(gems) = bot.gems.changed_since(&mut self.versions[3]) {
+            self.gems = gems;
+        }
+    }
+}
+
+// Copy of the console channel being shown, only taken again once its version moves
+#[derive(Default)]
+struct ConsoleView {
+    bot: String,
+    channel: Option<EChatChannel>,
+    version: u64,
+    lines: Vec<ConsoleLine>,
 }
 
 impl BotMenu {
     pub fn render(&mut self, ui: &mut Ui, manager: &Arc<RwLock<BotManager>>, texture_manager: &TextureManager) {
         self.bots = utils::config::get_bots();
         self.selected_bot = utils::config::get_selected_bot();
+        self.inventory.hotbar.handle_keys(ui.ctx(), manager);
         ui.add_space(4.0);
         ui.allocate_new_ui(
             UiBuilder::new()
                 .layout(egui::Layout::left_to_right(egui::Align::Min)),
             |ui| {
-                let bot = {
-                    let manager = manager.read().unwrap();
-
-                    match manager.get_bot(&self.selected_bot) {
-                        Some(bot) => Some(bot.clone()),
-                        None => None,
-                    }
-                };
+                let bot = manager.read().unwrap().get_bot(&self.selected_bot);
                 ui.allocate_ui(
                     egui::vec2(ui.available_width() * 0.18, ui.available_height()),
                     |ui| {
-                        egui::ScrollArea::vertical().id_salt("bot_list").show(ui, |ui| {
-                            ui.vertical(|ui| {
-                                let bots_clone = self.bots.clone();
-                                if bots_clone.is_empty() {
-                                    ui.centered_and_justified(|ui| {
-                                        ui.add_sized([ui.available_width(), 0.0], egui::Label::new("No bots added"));
-                                    });
-                                } else {
-                                    for bot in self.bots.clone() {
-                                        let payload = utils::textparse::parse_and_store_as_vec(&bot.payload);
-                                        if ui.add_sized([ui.available_width(), 0.0], egui::Button::new(payload[0].clone()).truncate()).clicked() {
-                                            self.selected_bot = payload[0].clone();
-                                            utils::config::set_selected_bot(self.selected_bot.clone());
-                                        }
-                                    }
-                                }
-                            });
+                        ui.vertical(|ui| {
+                            self.render_bot_list(ui, manager);
                         });
                     },
                 );
@@ -96,15 +153,26 @@ impl BotMenu {
                         )).clicked() {
                             self.current_menu = "scripting".to_string();
                         }
+                        if ui.add_sized([30.0, 30.0], egui::Button::new(
+                            egui::RichText::new(egui_remixicon::icons::CODE_BOX_FILL),
+                        )).on_hover_text("Lua REPL").clicked() {
+                            self.current_menu = "repl".to_string();
+                        }
                         if ui.add_sized([30.0, 30.0], egui::Button::new(
                             egui::RichText::new(egui_remixicon::icons::TERMINAL_BOX_FILL),
                         )).clicked() {
                             self.current_menu = "terminal".to_string();
                         }
+                        if ui.add_sized([30.0, 30.0], egui::Button::new(
+                            egui::RichText::new(egui_remixicon::icons::TOOLS_FILL),
+                        )).clicked() {
+                            self.current_menu = "dev_tools".to_string();
+                        }
                     });
                 }
                 if self.current_menu.is_empty() || self.current_menu == "bot_info" {
                     if let Some(bot) = bot {
+                        self.live.refresh(&bot, &self.selected_bot);
                         ui.allocate_ui(egui::vec2(ui.available_width() / 2.0, ui.available_height()), |ui| {
                             ui.vertical(|ui| {
                                 ui.group(|ui| {
@@ -115,18 +183,11 @@ impl BotMenu {
                                             .min_col_width(120.0)
                                             .max_col_width(120.0)
                                             .show(ui, |ui| {
-                                                let (username, status, ping, world_name, timeout) = {
-                                                    let info = bot.info.lock().unwrap();
-                                                    let temp: std::sync::RwLockReadGuard<'_, crate::types::bot_info::TemporaryData> = bot.temporary_data.read().unwrap();
-                                                    let world = bot.world.read().unwrap();
-                                                    (
-                                                        info.login_info.tank_id_name.clone(),
-                                                        info.status.clone(),
-                                                        temp.ping.clone().to_string(),
-                                                        world.name.clone(),
-                                                        temp.timeout.clone(),
-                                                    )
-                                                };
+                                                let username = bot.info.lock().unwrap().login_info.tank_id_name.clone();
+                                                let timeout = bot.temporary_data.read().unwrap().timeout;
+                                                let status = self.live.status.clone();
+                                                let ping = self.live.ping.to_string();
+                                                let world_name = self.live.world_name.clone();
                                                 ui.label("GrowID");
                                                 ui.add(egui::Label::new(username).truncate());
                                                 ui.end_row();
@@ -142,10 +203,101 @@ impl BotMenu {
                                                 ui.label("Timeout");
                                                 ui.label(timeout.to_string());
                                                 ui.end_row();
+                                                ui.label("Chat");
+                                                ui.label(match bot.chat_state() {
+                                                    EChatState::Ready => "Ready".to_string(),
+                                                    EChatState::Queued(count) => format!("{} queued", count),
+                                                    EChatState::Muted(until) => format!(
+                                                        "Muted ({}s)",
+                                                        until.saturating_duration_since(std::time::Instant::now()).as_secs()
+                                                    ),
+                                                });
+                                                ui.end_row();
+                                                ui.label("Outgoing");
+                                                let (queued, user_pending) = bot.outgoing_state();
+                                                if user_pending {
+                                                    ui.colored_label(Color32::from_rgb(255, 193, 7), "user command pending");
+                                                } else {
+                                                    ui.label(format!("{} queued", queued));
+                                                }
+                                                ui.end_row();
+                                                ui.label("Bandwidth");
+                                                let (bytes_in, bytes_out) = bot.bandwidth.totals();
+                                                ui.label(format!("{:.1} MB/h", bot.bandwidth.mb_per_hour())).on_hover_text(format!(
+                                                    "{:.1} MB in, {:.1} MB out since start",
+                                                    bytes_in as f64 / 1048576.0,
+                                                    bytes_out as f64 / 1048576.0
+                                                ));
+                                                ui.end_row();
+                                                ui.label("Last disconnect");
+                                                let last_disconnect = bot.state.lock().unwrap().last_disconnect.clone();
+                                                match last_disconnect {
+                                                    Some(disconnect) => {
+                                                        let ago = utils::date::now_secs().saturating_sub(disconnect.timestamp);
+                                                        ui.label(format!("{} ({}m ago)", disconnect.reason.name(), ago / 60))
+                                                            .on_hover_text(&disconnect.detail);
+                                                    }
+                                                    None => {
+                                                        ui.label("None");
+                                                    }
+                                                }
+                                                ui.end_row();
+                                                ui.label("Humanizer");
+                                                let preset = bot.humanizer_preset();
+                                                egui::ComboBox::from_id_salt("humanizer_preset")
+                                                    .selected_text(format!("{:?}", preset))
+                                                    .show_ui(ui, |ui| {
+                                                        if ui.selectable_label(false, "Template's").on_hover_text("Off when the bot has no template").clicked() {
+                                                            bot.set_humanizer(None);
+                                                        }
+                                                        for option in EHumanizerPreset::ALL {
+                                                            if ui.selectable_label(preset == option, format!("{:?}", option)).clicked() {
+                                                                bot.set_humanizer(Some(option));
+                                                            }
+                                                        }
+                                                    });
+                                                ui.end_row();
+                                                ui.label("Humanizer seed");
+                                                let mut seed = match &self.humanizer_seed {
+                                                    Some((name, seed)) if *name == self.selected_bot => *seed,
+                                                    _ => bot.humanizer_seed(),
+                                                };
+                                                let response = ui
+                                                    .add(egui::DragValue::new(&mut seed))
+                                                    .on_hover_text("Replays the same delays from every start, 0 for fresh ones");
+                                                if response.drag_stopped() || response.lost_focus() {
+                                                    bot.set_humanizer_seed(seed);
+                                                    self.humanizer_seed = None;
+                                                } else if response.changed() {
+                                                    self.humanizer_seed = Some((self.selected_bot.clone(), seed));
+                                                }
+                                                ui.end_row();
+                                                ui.label("Proxy pool");
+                                                self.render_pool_picker(ui, manager);
+                                                ui.end_row();
                                                 
                                             });
                                     });
                                 });
+                                let steam_guard = bot.temporary_data.read().unwrap().steam_guard;
+                                if let Some(challenge) = steam_guard {
+                                    ui.allocate_space(egui::vec2(ui.available_width(), 5.0));
+                                    ui.group(|ui| {
+                                        ui.label("Steam Guard");
+                                        ui.separator();
+                                        ui.label(format!("Enter the {}", challenge.describe()));
+                                        ui.horizontal(|ui| {
+                                            ui.add(
+                                                egui::TextEdit::singleline(&mut self.guard_code)
+                                                    .hint_text("XXXXX")
+                                                    .desired_width(80.0),
+                                            );
+                                            if ui.button("Submit").clicked() && bot.submit_2fa(&self.guard_code) {
+                                                self.guard_code.clear();
+                                            }
+                                        });
+                                    });
+                                }
                                 ui.allocate_space(egui::vec2(ui.available_width(), 5.0));
                                 ui.group(|ui| {
                                     ui.label("Warp");
@@ -166,17 +318,20 @@ impl BotMenu {
                                         //         });
                                         //     }
                                         // }
+                                        if !manager.read().unwrap().is_started(&self.selected_bot) && ui.button("Login").clicked() {
+                                            manager.read().unwrap().start_bot(&self.selected_bot);
+                                        }
                                         if ui.button("Leave").clicked() {
                                             let bot_clone = bot.clone();
                                             thread::spawn(move || {
-                                                bot_clone.leave();
+                                                outgoing::user_initiated(|| bot_clone.leave());
                                             });
                                         }
                                         if ui.button("Warp").clicked() {
                                             let bot_clone = bot.clone();
                                             let world_name = self.warp_name.clone();
                                             thread::spawn(move || {
-                                                bot_clone.warp(world_name);
+                                                outgoing::user_initiated(|| bot_clone.warp(world_name));
                                             });
                                         }
                                     });
@@ -264,7 +419,7 @@ impl BotMenu {
                                             ui.label(bot.state.lock().unwrap().level.to_string());
                                             ui.end_row();
                                             ui.label("Gems");
-                                            ui.label(bot.state.lock().unwrap().gems.to_string());
+                                            ui.label(self.live.gems.to_string());
                                             ui.end_row();
                                             ui.label("Position");
                                             ui.horizontal(|ui| {
@@ -275,6 +430,102 @@ impl BotMenu {
                                             ui.end_row();
                                         });
                                 });
+                            });
+                            ui.allocate_space(egui::vec2(ui.available_width(), 5.0));
+                            ui.group(|ui| {
+                                ui.vertical(|ui| {
+                                    ui.label("Effects");
+                                    ui.separator();
+                                    let effects = bot.effects();
+                                    if effects.is_empty() {
+                                        ui.label("No active effects");
+                                    }
+                                    egui::Grid::new("bot_effects")
+                                        .min_col_width(120.0)
+                                        .max_col_width(120.0)
+                                        .show(ui, |ui| {
+                                            for effect in effects {
+                                                let name = {
+                                                    let item_database = bot.item_database.read().unwrap();
+                                                    item_database
+                                                        .get_item(&effect.source_item)
+                                                        .map(|item| item.name.clone())
+                                                        .unwrap_or_else(|| effect.source_item.to_string())
+                                                };
+                                                let remaining = effect.remaining().as_secs();
+                                                ui.add(egui::Label::new(name).truncate());
+                                                ui.label(format!("{}:{:02}", remaining / 60, remaining % 60));
+                                                ui.end_row();
+                                            }
+                                        });
+                                });
+                            });
+                            ui.allocate_space(egui::vec2(ui.available_width(), 5.0));
+                            ui.group(|ui| {
+                                ui.vertical(|ui| {
+                                    ui.label("Workers");
+                                    ui.separator();
+                                    let workers = bot.workers.lock().unwrap().clone();
+                                    egui::Grid::new("bot_workers")
+                                        .min_col_width(120.0)
+                                        .max_col_width(120.0)
+                                        .show(ui, |ui| {
+                                            for worker in workers {
+                                                let color = if worker.is_degraded() {
+                                                    Color32::from_rgb(255, 140, 0)
+                                                } else {
+                                                    ui.visuals().text_color()
+                                                };
+                                                ui.label(&worker.name);
+                                                let state = ui.label(
+                                                    egui::RichText::new(format!(
+                                                        "{} ({} restarts)",
+                                                        worker.state.name(),
+                                                        worker.restarts
+                                                    ))
+                                                    .color(color),
+                                                );
+                                                if let Some(panic) = &worker.last_panic {
+                                                    state.on_hover_text(panic);
+                                                }
+                                                ui.end_row();
+                                            }
+                                        });
+                                });
+                            });
+                            ui.allocate_space(egui::vec2(ui.available_width(), 5.0));
+                            ui.group(|ui| {
+                                ui.vertical(|ui| {
+                                    let (worth, unvalued) = {
+                                        let inventory = bot.inventory.lock().unwrap();
+                                        (value_table::inventory_value(&inventory), value_table::unvalued(&inventory))
+                                    };
+                                    ui.horizontal(|ui| {
+                                        ui.label(format!("Inventory worth {:.2} WL", worth));
+                                        if !unvalued.is_empty() {
+                                            let item_database = bot.item_database.read().unwrap();
+                                            let names = unvalued
+                                                .iter()
+                                                .map(|item_id| {
+                                                    item_database
+                                                        .get_item(item_id)
+                                                        .map_or_else(|| item_id.to_string(), |item| item.name.clone())
+                                                })
+                                                .collect::<Vec<String>>()
+                                                .join(", ");
+                                            ui.colored_label(
+                                                Color32::from_rgb(255, 193, 7),
+                                                format!("{} unvalued items present", unvalued.len()),
+                                            )
+                                            .on_hover_text(format!("Counted as 0: {}", names));
+                                        }
+                                    });
+                                    ui.separator();
+                                    ui.label("Last 7 days");
+                                    ui.separator();
+                                    render_daily_chart(ui, "Gems", &bot.daily_history("gems", 7));
+                                    render_daily_chart(ui, "Blocks", &bot.daily_history("blocks", 7));
+                                });
                                 ui.add_space(ui.available_height());
                             });
                         });
@@ -289,72 +540,131 @@ impl BotMenu {
                         });
                     }
                 } else if self.current_menu == "world_map" {
+                    self.world_map.place_item = self.inventory.hotbar.selected_item(&self.selected_bot);
                     ui.allocate_ui(egui::vec2(ui.available_width(), ui.available_height()), |ui| {
                         self.world_map.render(ui, &manager, &texture_manager);
                     });
                 } else if self.current_menu == "inventory" {
                     ui.allocate_ui(egui::vec2(ui.available_width(), ui.available_height()), |ui| {
-                        self.inventory.render(ui, &manager);
+                        self.inventory.render(ui, &manager, &texture_manager);
                     });
                 } else if self.current_menu == "radar" {
                     ui.allocate_ui(egui::vec2(ui.available_width(), ui.available_height()), |ui| {
                         self.growscan.render(ui, &manager);
                     });
                 } else if self.current_menu == "features" {
-                    ui.allocate_ui(egui::vec2(ui.available_width(), ui.available_height()), |ui| {
-                        ui.label("Not implemented yet");
-                    });
+                    if let Some(bot) = &bot {
+                        ui.allocate_ui(egui::vec2(ui.available_width(), ui.available_height()), |ui| {
+                            self.features.render(ui, &manager, bot);
+                        });
+                    }
                 } else if self.current_menu == "scripting" {
                     ui.allocate_ui(egui::vec2(ui.available_width(), ui.available_height()), |ui| {
                         self.scripting.render(ui, &manager);
                     });
+                } else if self.current_menu == "repl" {
+                    if let Some(bot) = &bot {
+                        ui.allocate_ui(egui::vec2(ui.available_width(), ui.available_height()), |ui| {
+                            ui.vertical(|ui| {
+                                self.repl.render(ui, &self.selected_bot, bot);
+                            });
+                        });
+                    }
                 } else if self.current_menu == "terminal" {
                     ui.allocate_ui(egui::vec2(ui.available_width(), ui.available_height()), |ui| {
-                        egui::ScrollArea::vertical()
-                            .scroll_bar_visibility(ScrollBarVisibility::AlwaysVisible)
-                            .auto_shrink(false)
-                            .stick_to_bottom(true)
-                            .show(ui, |ui| {
-                                let logs = {
-                                    let manager = manager.read().unwrap();
+                        ui.vertical(|ui| {
+                            ui.horizontal(|ui| {
+                                ui.selectable_value(&mut self.console_tab, None, "Logs");
+                                let bot = manager.read().unwrap().get_bot(&self.selected_bot);
+                                for channel in EChatChannel::ALL {
+                                    let unread = bot.as_ref().map_or(0, |bot| bot.console.lock().unwrap().unread(channel));
+                                    let label = match unread {
+                                        0 => channel.label().to_string(),
+                                        unread => format!("{} ({})", channel.label(), unread),
+                                    };
+                                    ui.selectable_value(&mut self.console_tab, Some(channel), label);
+                                }
+                                ui.separator();
+                                if ui.button("Export...").clicked() {
+                                    self.export_open = true;
+                                }
+                            });
+                            if let Some(channel) = self.console_tab {
+                                self.render_console(ui, manager, channel);
+                                return;
+                            }
+                            if let Some(bot) = manager.read().unwrap().get_bot(&self.selected_bot) {
+                                let entries = bot.timeline.lock().unwrap().entries();
+                                if let Some(timestamp) = timeline::render(ui, &entries) {
+                                    self.log_jump = Some(timestamp);
+                                }
+                            }
+                            let log_jump = self.log_jump.take();
+                            egui::ScrollArea::vertical()
+                                .scroll_bar_visibility(ScrollBarVisibility::AlwaysVisible)
+                                .auto_shrink(false)
+                                .stick_to_bottom(log_jump.is_none())
+                                .show(ui, |ui| {
+                                    let logs = {
+                                        let manager = manager.read().unwrap();
 
-                                    match manager.get_bot(&self.selected_bot) {
-                                        Some(bot) => {
-                                            let logs = bot.logs.lock().unwrap();
-                                            Some(logs.clone())
+                                        match manager.get_bot(&self.selected_bot) {
+                                            Some(bot) => {
+                                                let logs = bot.logs.lock().unwrap();
+                                                Some(logs.clone())
+                                            }
+                                            None => None,
                                         }
-                                        None => None,
-                                    }
-                                };
+                                    };
 
-                                ui.vertical(|ui| {
-                                    if let Some(logs) = logs {
-                                        for log in logs.iter() {
-                                            let data = log.split("|").collect::<Vec<&str>>();
-                                            ui.horizontal(|ui| {
-                                                match data[0] {
-                                                    "info" => {
-                                                        ui.label(egui::RichText::new(egui_remixicon::icons::INFORMATION_FILL).color(Color32::from_rgb(0, 123, 255)).size(16.0));
-                                                        ui.add(egui::Label::new(data[1]).wrap());
-                                                    }
-                                                    "warn" => {
-                                                        ui.label(egui::RichText::new(egui_remixicon::icons::ERROR_WARNING_FILL).color(Color32::from_rgb(255, 193, 7)).size(16.0));
-                                                        ui.add(egui::Label::new(data[1]).wrap());
-                                                    }
-                                                    "error" => {
-                                                        ui.label(egui::RichText::new(egui_remixicon::icons::BUG_FILL).color(Color32::from_rgb(220, 53, 69)).size(16.0));
-                                                        ui.add(egui::Label::new(data[1]).wrap());
-                                                    }
-                                                    _ => {
-                                                        ui.label(log);
-                                                    }
-                                                };
+                                    ui.vertical(|ui| {
+                                        if let Some(logs) = logs {
+                                            // Entries before this session's logs jump to the top
+                                            let jump_index = log_jump.map(|timestamp| {
+                                                logs.iter().position(|log| log.timestamp >= timestamp).unwrap_or(logs.len().saturating_sub(1))
                                             });
+                                            for (index, log) in logs.iter().enumerate() {
+                                                let row = ui.horizontal(|ui| {
+                                                    match log.level.as_str() {
+                                                        "info" => {
+                                                            ui.label(egui::RichText::new(egui_remixicon::icons::INFORMATION_FILL).color(Color32::from_rgb(0, 123, 255)).size(16.0));
+                                                            ui.add(egui::Label::new(colored_text(ui, &log.message)).wrap());
+                                                        }
+                                                        "warn" => {
+                                                            ui.label(egui::RichText::new(egui_remixicon::icons::ERROR_WARNING_FILL).color(Color32::from_rgb(255, 193, 7)).size(16.0));
+                                                            ui.add(egui::Label::new(colored_text(ui, &log.message)).wrap());
+                                                        }
+                                                        "error" => {
+                                                            ui.label(egui::RichText::new(egui_remixicon::icons::BUG_FILL).color(Color32::from_rgb(220, 53, 69)).size(16.0));
+                                                            ui.add(egui::Label::new(colored_text(ui, &log.message)).wrap());
+                                                        }
+                                                        "debug" => {
+                                                            ui.label(egui::RichText::new(egui_remixicon::icons::BUG_LINE).weak().size(16.0));
+                                                            ui.add(egui::Label::new(egui::RichText::new(utils::textparse::strip_color(&log.message)).weak()).wrap());
+                                                        }
+                                                        _ => {
+                                                            ui.label(colored_text(ui, &log.message));
+                                                        }
+                                                    };
+                                                });
+                                                if jump_index == Some(index) {
+                                                    row.response.scroll_to_me(Some(egui::Align::TOP));
+                                                }
+                                            }
                                         }
-                                    }
-                                    ui.add_space(10.0);
+                                        ui.add_space(10.0);
+                                    });
                                 });
-                            });
+                        });
+                    });
+                    // Bound first, the manager lock would otherwise be held through the window
+                    let bot = manager.read().unwrap().get_bot(&self.selected_bot);
+                    if let Some(bot) = bot {
+                        self.render_export_window(ui.ctx(), &bot);
+                    }
+                } else if self.current_menu == "dev_tools" {
+                    ui.allocate_ui(egui::vec2(ui.available_width(), ui.available_height()), |ui| {
+                        self.dev_tools.render(ui, &manager);
                     });
                 } else {
                     ui.label("How?");
@@ -362,4 +672,370 @@ impl BotMenu {
             },
         );
     }
+
+    // Pins the selected bot to a pool, empty leaves it to its group's. Picked up by the
+    // next proxy assignment, i.e. when the bot is started again
+    fn render_pool_picker(&self, ui: &mut Ui, manager: &Arc<RwLock<BotManager>>) {
+        let Some(bot_config) = self.bots.iter().find(|bot| {
+            utils::textparse::parse_and_store_as_vec(&bot.payload)[0] == self.selected_bot
+        }) else {
+            return;
+        };
+        let current = bot_config.proxy_pool.trim();
+        egui::ComboBox::from_id_salt("bot_proxy_pool")
+            .selected_text(if current.is_empty() { "Group's pool" } else { current })
+            .show_ui(ui, |ui| {
+                let pools = manager.read().unwrap().proxy_manager.read().unwrap().pools();
+                let options = std::iter::once(String::new()).chain(pools);
+                for pool in options {
+                    let label = if pool.is_empty() { "Group's pool" } else { pool.as_str() };
+                    if ui.selectable_label(current == pool, label).clicked() && current != pool {
+                        utils::config::update_bot_config(&self.selected_bot, |bot| bot.proxy_pool = pool.clone());
+                    }
+                }
+            })
+            .response
+            .on_hover_text("Used from the next start of the bot");
+    }
+
+    fn render_bot_list(&mut self, ui: &mut Ui, manager: &Arc<RwLock<BotManager>>) {
+        let mut filter_text = utils::config::get_bot_filter_text();
+        let mut filter = utils::config::get_bot_filter();
+        let summaries = manager.read().unwrap().summaries();
+
+        if ui
+            .add(
+                egui::TextEdit::singleline(&mut filter_text)
+                    .hint_text("Filter name/world")
+                    .desired_width(f32::INFINITY),
+            )
+            .changed()
+        {
+            utils::config::set_bot_filter_text(filter_text.clone());
+        }
+
+        let previous_filter = filter.clone();
+        ui.horizontal_wrapped(|ui| {
+            for chip in [EBotFilter::All, EBotFilter::Connected, EBotFilter::Disconnected, EBotFilter::Banned] {
+                let label = chip.label();
+                ui.selectable_value(&mut filter, chip, label);
+            }
+            let mut worlds: Vec<&str> = summaries
+                .iter()
+                .filter(|summary| summary.connected && summary.world != "EXIT" && !summary.world.is_empty())
+                .map(|summary| summary.world.as_str())
+                .collect();
+            worlds.sort();
+            worlds.dedup();
+            let mut groups: Vec<&str> = summaries
+                .iter()
+                .filter(|summary| !summary.group.is_empty())
+                .map(|summary| summary.group.as_str())
+                .collect();
+            groups.sort();
+            groups.dedup();
+            if !worlds.is_empty() {
+                egui::ComboBox::from_id_salt("bot_filter_world")
+                    .selected_text(match &filter {
+                        EBotFilter::World(world) => world.clone(),
+                        _ => "World".to_string(),
+                    })
+                    .show_ui(ui, |ui| {
+                        for world in worlds {
+                            ui.selectable_value(&mut filter, EBotFilter::World(world.to_string()), world);
+                        }
+                    });
+            }
+            if !groups.is_empty() {
+                egui::ComboBox::from_id_salt("bot_filter_group")
+                    .selected_text(match &filter {
+                        EBotFilter::Group(group) => group.clone(),
+                        _ => "Group".to_string(),
+                    })
+                    .show_ui(ui, |ui| {
+                        for group in groups {
+                            ui.selectable_value(&mut filter, EBotFilter::Group(group.to_string()), group);
+                        }
+                    });
+            }
+        });
+        if filter != previous_filter {
+            utils::config::set_bot_filter(filter.clone());
+        }
+        let farm_rate = format!("Farm: {:.1} MB/h", bandwidth::farm_mb_per_hour());
+        if bandwidth::throttled() {
+            ui.colored_label(Color32::from_rgb(220, 53, 69), farm_rate)
+                .on_hover_text("Over the bandwidth cap, low priority traffic is paused");
+        } else {
+            ui.label(egui::RichText::new(farm_rate).weak());
+        }
+        ui.separator();
+
+        // Bots without a summary yet (just added) are treated as disconnected
+        let by_name: HashMap<&str, &BotSummary> =
+            summaries.iter().map(|summary| (summary.name.as_str(), summary)).collect();
+        let names: Vec<String> = self
+            .bots
+            .iter()
+            .map(|bot| utils::textparse::parse_and_store_as_vec(&bot.payload)[0].clone())
+            .collect();
+        let needle = filter_text.trim().to_lowercase();
+
+        egui::ScrollArea::vertical().id_salt("bot_list").show(ui, |ui| {
+            ui.vertical(|ui| {
+                if names.is_empty() {
+                    ui.centered_and_justified(|ui| {
+                        ui.add_sized([ui.available_width(), 0.0], egui::Label::new("No bots added"));
+                    });
+                    return;
+                }
+                let mut shown = 0;
+                for name in names {
+                    let summary = by_name.get(name.as_str()).map(|summary| (*summary).clone()).unwrap_or(BotSummary {
+                        name: name.clone(),
+                        ..Default::default()
+                    });
+                    let text_matches = needle.is_empty()
+                        || name.to_lowercase().contains(&needle)
+                        || summary.world.to_lowercase().contains(&needle);
+                    if !text_matches || !filter.matches(&summary) {
+                        continue;
+                    }
+                    shown += 1;
+
+                    ui.horizontal(|ui| {
+                        let dot = if summary.banned {
+                            Color32::from_rgb(220, 53, 69)
+                        } else if summary.connected {
+                            Color32::from_rgb(40, 167, 69)
+                        } else if !summary.status.is_empty() {
+                            Color32::from_rgb(255, 193, 7)
+                        } else {
+                            Color32::GRAY
+                        };
+                        ui.label(egui::RichText::new("●").color(dot))
+                            .on_hover_text(format!("{}\n{:.1} MB/h", summary.status, summary.mb_per_hour));
+                        if summary.proxy {
+                            ui.label(egui::RichText::new(egui_remixicon::icons::SHIELD_FILL).size(12.0))
+                                .on_hover_text("Using a proxy");
+                        }
+                        if summary.script_running {
+                            ui.label(egui::RichText::new(egui_remixicon::icons::CODE_FILL).size(12.0))
+                                .on_hover_text("Script running");
+                        }
+                        if let Some(feature) = &summary.feature {
+                            ui.label(egui::RichText::new(egui_remixicon::icons::ROBOT_2_FILL).size(12.0))
+                                .on_hover_text(feature);
+                        }
+                        if !summary.degraded.is_empty() {
+                            ui.label(
+                                egui::RichText::new(egui_remixicon::icons::ERROR_WARNING_FILL)
+                                    .size(12.0)
+                                    .color(Color32::from_rgb(255, 140, 0)),
+                            )
+                            .on_hover_text(format!(
+                                "Degraded: {} worker crashed, relog to recover",
+                                summary.degraded.join(", ")
+                            ));
+                        }
+                        let button = egui::Button::new(name.clone())
+                            .truncate()
+                            .selected(name == self.selected_bot);
+                        if ui.add_sized([ui.available_width(), 0.0], button).clicked() {
+                            self.selected_bot = name.clone();
+                            utils::config::set_selected_bot(self.selected_bot.clone());
+                        }
+                    });
+                }
+                if shown == 0 {
+                    ui.label(egui::RichText::new("No bots match the filter").weak());
+                }
+            });
+        });
+    }
+
+    // One channel of the console, reading it clears its unread count. Clicking the world of
+    // a super-broadcast warps there
+    fn render_console(&mut self, ui: &mut Ui, manager: &Arc<RwLock<BotManager>>, channel: EChatChannel) {
+        let bot = match manager.read().unwrap().get_bot(&self.selected_bot) {
+            Some(bot) => bot,
+            None => return,
+        };
+        let view = &mut self.console;
+        if view.bot != self.selected_bot || view.channel != Some(channel) {
+            // A version no channel has reached, so the new one is read
+            *view = ConsoleView {
+                bot: self.selected_bot.clone(),
+                channel: Some(channel),
+                version: u64::MAX,
+                ..Default::default()
+            };
+        }
+        {
+            let mut console = bot.console.lock().unwrap();
+            console.mark_read(channel);
+            if let Some(lines) = console.lines_since(channel, &mut view.version) {
+                view.lines = lines;
+            }
+        }
+        let lines = &view.lines;
+        let utc_offset = utils::config::get_stats_utc_offset();
+        let icon = match channel {
+            EChatChannel::World => egui_remixicon::icons::CHAT_3_FILL,
+            EChatChannel::Guild => egui_remixicon::icons::TEAM_FILL,
+            EChatChannel::SuperBroadcast => egui_remixicon::icons::MEGAPHONE_FILL,
+            EChatChannel::System => egui_remixicon::icons::INFORMATION_FILL,
+            EChatChannel::Whisper => egui_remixicon::icons::CHAT_PRIVATE_FILL,
+        };
+
+        egui::ScrollArea::vertical()
+            .id_salt("console")
+            .scroll_bar_visibility(ScrollBarVisibility::AlwaysVisible)
+            .auto_shrink(false)
+            .stick_to_bottom(true)
+            .show(ui, |ui| {
+                if lines.is_empty() {
+                    ui.label(format!("Nothing in {} yet", channel.label()));
+                }
+                for line in lines.iter() {
+                    ui.horizontal(|ui| {
+                        ui.label(egui::RichText::new(icon).color(Color32::from_rgb(111, 66, 193)).size(16.0));
+                        ui.label(egui::RichText::new(utils::date::format_time(line.timestamp, utc_offset)).weak());
+                        if let Some(world) = &line.world {
+                            if ui.link(world).on_hover_text(format!("Warp to {}", world)).clicked() {
+                                let (bot, world) = (bot.clone(), world.clone());
+                                thread::spawn(move || {
+                                    outgoing::user_initiated(|| bot.warp(world));
+                                });
+                            }
+                        }
+                        if !line.sender.is_empty() {
+                            ui.label(egui::RichText::new(&line.sender).strong());
+                        }
+                        ui.add(egui::Label::new(colored_text(ui, &line.text)).wrap());
+                    });
+                }
+                ui.add_space(10.0);
+            });
+    }
+
+    fn render_export_window(&mut self, ctx: &egui::Context, bot: &Arc<Bot>) {
+        if !self.export_open {
+            return;
+        }
+        if let Some(picked) = self.export_pick.as_ref().and_then(|pick| pick.try_recv().ok()) {
+            if let Some(path) = picked {
+                self.export_path = path.display().to_string();
+            }
+            self.export_pick = None;
+        }
+        let mut open = self.export_open;
+        egui::Window::new("Export")
+            .resizable(false)
+            .open(&mut open)
+            .show(ctx, |ui| {
+                egui::Grid::new("export_grid")
+                    .min_col_width(100.0)
+                    .show(ui, |ui| {
+                        ui.label("Path");
+                        ui.horizontal(|ui| {
+                            ui.add(egui::TextEdit::singleline(&mut self.export_path).hint_text("Pick a file"));
+                            if ui.add_enabled(self.export_pick.is_none(), egui::Button::new("Browse...")).clicked() {
+                                self.export_pick = Some(pick_export_path(ctx, self.export_format));
+                            }
+                        });
+                        ui.end_row();
+                        ui.label("Format");
+                        egui::ComboBox::from_id_salt("export_format")
+                            .selected_text(format!("{:?}", self.export_format))
+                            .show_ui(ui, |ui| {
+                                ui.selectable_value(
+                                    &mut self.export_format,
+                                    ExportFormat::JsonLines,
+                                    "JSON lines",
+                                );
+                                ui.selectable_value(&mut self.export_format, ExportFormat::Csv, "CSV");
+                            });
+                        ui.end_row();
+                    });
+                let ready = !self.export_path.trim().is_empty() && self.export_pick.is_none();
+                ui.horizontal(|ui| {
+                    // Written from a thread, a long log would freeze the window otherwise
+                    if ui.add_enabled(ready, egui::Button::new("Export logs")).clicked() {
+                        let (bot, path, format) = (bot.clone(), self.export_path.clone(), self.export_format);
+                        thread::spawn(move || {
+                            if let Err(err) = bot.export_logs(&path, format) {
+                                bot.log_error(&format!("Failed to export logs: {}", err));
+                            }
+                        });
+                    }
+                    if ui.add_enabled(ready, egui::Button::new("Export stats")).clicked() {
+                        let (bot, path, format) = (bot.clone(), self.export_path.clone(), self.export_format);
+                        thread::spawn(move || {
+                            if let Err(err) = bot.export_stats(&path, format) {
+                                bot.log_error(&format!("Failed to export stats: {}", err));
+                            }
+                        });
+                    }
+                });
+            });
+        self.export_open = open;
+    }
+}
+
+// The native save dialog blocks until it's closed, so it runs on its own thread. None when
+// it was cancelled
+fn pick_export_path(ctx: &egui::Context, format: ExportFormat) -> Receiver<Option<PathBuf>> {
+    let (sender, receiver) = mpsc::channel();
+    let ctx = ctx.clone();
+    thread::spawn(move || {
+        let (name, extension) = match format {
+            ExportFormat::JsonLines => ("JSON lines", "jsonl"),
+            ExportFormat::Csv => ("CSV", "csv"),
+        };
+        let path = rfd::FileDialog::new()
+            .add_filter(name, &[extension])
+            .set_file_name(format!("export.{}", extension))
+            .save_file();
+        let _ = sender.send(path);
+        ctx.request_repaint();
+    });
+    receiver
+}
+
+fn render_daily_chart(ui: &mut Ui, title: &str, history: &[(String, u64)]) {
+    let max = history.iter().map(|(_, value)| *value).max().unwrap_or(0).max(1);
+    let today = history.last().map(|(_, value)| *value).unwrap_or(0);
+    ui.label(format!("{} (today: {})", title, today));
+    let (rect, _) = ui.allocate_exact_size(egui::vec2(ui.available_width(), 40.0), egui::Sense::hover());
+    let painter = ui.painter_at(rect);
+    let slot = rect.width() / history.len().max(1) as f32;
+    for (index, (day, value)) in history.iter().enumerate() {
+        let height = rect.height() * (*value as f32 / max as f32);
+        let bar = egui::Rect::from_min_max(
+            egui::pos2(rect.left() + slot * index as f32 + 2.0, rect.bottom() - height),
+            egui::pos2(rect.left() + slot * (index + 1) as f32 - 2.0, rect.bottom()),
+        );
+        painter.rect_filled(bar, 2.0, ui.visuals().selection.bg_fill);
+        let hover = egui::Rect::from_min_max(
+            egui::pos2(bar.left(), rect.top()),
+            egui::pos2(bar.right(), rect.bottom()),
+        );
+        ui.interact(hover, ui.id().with((title, index)), egui::Sense::hover())
+            .on_hover_text(format!("{}: {}", day, value));
+    }
+}
+
+// Growtopia color codes in a message as colored runs, uncolored text keeps the theme's color
+fn colored_text(ui: &Ui, text: &str) -> egui::text::LayoutJob {
+    let font_id = egui::TextStyle::Body.resolve(ui.style());
+    let default_color = ui.visuals().text_color();
+    let mut job = egui::text::LayoutJob::default();
+    for span in gt_text::parse(text) {
+        let color = span
+            .color
+            .map_or(default_color, |[r, g, b]| Color32::from_rgb(r, g, b));
+        job.append(&span.text, 0.0, egui::TextFormat::simple(font_id.clone(), color));
+    }
+    job
 }
@@ -0,0 +1,49 @@
+use crate::{core::crash, gui::toasts, utils::setup_check};
+use eframe::egui;
+use std::path::Path;
+
+// Shown once after a crash left a report behind, until it's opened or dismissed
+#[derive(Default)]
+pub struct CrashDialog {
+    pub report: Option<String>,
+}
+
+impl CrashDialog {
+    pub fn from_pending() -> Self {
+        Self { report: crash::pending_report() }
+    }
+
+    pub fn render(&mut self, ctx: &egui::Context) {
+        let report = match &self.report {
+            Some(report) => report.clone(),
+            None => return,
+        };
+        let mut answered = false;
+        egui::Window::new("Mori crashed last time")
+            .resizable(false)
+            .collapsible(false)
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "A crash report was saved to {}/{}.",
+                    crash::CRASH_DIR,
+                    report
+                ));
+                ui.label("It holds the bot's recent logs and packets, check it before sharing.");
+                ui.horizontal(|ui| {
+                    if ui.button("Open folder").clicked() {
+                        if let Err(err) = setup_check::open_folder(Path::new(crash::CRASH_DIR)) {
+                            toasts::error(&err);
+                        }
+                        answered = true;
+                    }
+                    if ui.button("Dismiss").clicked() {
+                        answered = true;
+                    }
+                });
+            });
+        if answered {
+            crash::dismiss_pending();
+            self.report = None;
+        }
+    }
+}
@@ -0,0 +1,423 @@
+use crate::core::{lock_timing, Bot};
+use crate::manager::bot_manager::BotManager;
+use crate::types::{
+    captured_packet::CapturedPacket,
+    epacket_direction::EPacketDirection,
+    epacket_type::EPacketType,
+    etank_packet_type::ETankPacketType,
+    packet_filter::{PacketFilter, PacketFilterPreset},
+    tank_packet::TankPacket,
+};
+use crate::utils::{self, config};
+use eframe::egui::{self, Ui};
+use egui::scroll_area::ScrollBarVisibility;
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, RwLock};
+
+#[derive(Default)]
+pub struct DevTools {
+    pub selected_bot: String,
+    pub paused: bool,
+    pub paused_packets: Vec<CapturedPacket>,
+    pub filter_type: Option<u32>,
+    pub filter_tank_type: Option<u8>,
+    pub expanded: Option<usize>,
+    pub send_raw: bool,
+    pub send_channel: u8,
+    pub send_type: u32,
+    pub send_hex: String,
+    pub tank_type: u8,
+    pub tank: TankFields,
+    pub error: String,
+    pub preset_name: String,
+}
+
+#[derive(Default)]
+pub struct TankFields {
+    pub net_id: u32,
+    pub flags: u32,
+    pub value: u32,
+    pub vector_x: f32,
+    pub vector_y: f32,
+    pub int_x: i32,
+    pub int_y: i32,
+}
+
+impl DevTools {
+    pub fn render(&mut self, ui: &mut Ui, manager: &Arc<RwLock<BotManager>>) {
+        self.selected_bot = utils::config::get_selected_bot();
+        let bot = match manager.read().unwrap().get_bot(&self.selected_bot) {
+            Some(bot) => bot,
+            None => return,
+        };
+
+        ui.vertical(|ui| {
+            self.render_profiler(ui, &bot);
+            self.render_contention(ui);
+            ui.separator();
+            self.render_sender(ui, &bot);
+            ui.separator();
+            self.render_capture_filter(ui, &bot);
+            self.render_capture(ui, &bot);
+        });
+    }
+
+    // What the bot logs and captures in the first place, the type filter below only narrows the view
+    fn render_capture_filter(&mut self, ui: &mut Ui, bot: &Arc<Bot>) {
+        egui::CollapsingHeader::new("Log and capture filter")
+            .id_salt("dev_capture_filter")
+            .show(ui, |ui| {
+                let mut filter = bot.packet_filter.get();
+                let mut changed = false;
+                let saved = config::get_packet_filter_presets();
+
+                ui.horizontal(|ui| {
+                    if ui.button("All").clicked() {
+                        filter = PacketFilter::all();
+                        changed = true;
+                    }
+                    if ui.button("None").clicked() {
+                        filter = PacketFilter::none();
+                        changed = true;
+                    }
+                    egui::ComboBox::from_id_salt("dev_filter_preset")
+                        .selected_text("Presets")
+                        .show_ui(ui, |ui| {
+                            for preset in PacketFilterPreset::builtin().iter().chain(saved.iter()) {
+                                if ui.selectable_label(false, &preset.name).clicked() {
+                                    filter = preset.filter.clone();
+                                    self.preset_name = preset.name.clone();
+                                    changed = true;
+                                }
+                            }
+                        });
+                    ui.separator();
+                    let mut sampling = filter.sample > 0;
+                    if ui.checkbox(&mut sampling, "Only the first").changed() {
+                        filter.sample = if sampling { 10 } else { 0 };
+                        changed = true;
+                    }
+                    if sampling {
+                        changed |= ui.add(egui::DragValue::new(&mut filter.sample).range(1..=10000)).changed();
+                        ui.label("of each type");
+                        if ui.button("Restart").clicked() {
+                            changed = true;
+                        }
+                    }
+                });
+
+                ui.horizontal(|ui| {
+                    ui.add(
+                        egui::TextEdit::singleline(&mut self.preset_name)
+                            .hint_text("Preset name")
+                            .desired_width(150.0),
+                    );
+                    let name = self.preset_name.trim().to_string();
+                    let existing = saved.iter().position(|preset| preset.name == name);
+                    if ui.add_enabled(!name.is_empty(), egui::Button::new("Save preset")).clicked() {
+                        let mut presets = saved.clone();
+                        let preset = PacketFilterPreset { name, filter: filter.clone() };
+                        match existing {
+                            Some(index) => presets[index] = preset,
+                            None => presets.push(preset),
+                        }
+                        config::set_packet_filter_presets(presets);
+                    }
+                    if ui.add_enabled(existing.is_some(), egui::Button::new("Delete preset")).clicked() {
+                        let mut presets = saved.clone();
+                        presets.retain(|preset| preset.name != self.preset_name.trim());
+                        config::set_packet_filter_presets(presets);
+                    }
+                });
+
+                ui.label("Message types");
+                ui.horizontal_wrapped(|ui| {
+                    for id in 0..=9 {
+                        let mut enabled = filter.allows_type(id);
+                        if ui.checkbox(&mut enabled, EPacketType::from(id).to_string()).changed() {
+                            filter.set_type(id, enabled);
+                            changed = true;
+                        }
+                    }
+                    let mut other = filter.allows_type(10);
+                    if ui.checkbox(&mut other, "Other").changed() {
+                        for id in 10..32 {
+                            filter.set_type(id, other);
+                        }
+                        changed = true;
+                    }
+                });
+
+                ui.label("Tank packet types");
+                ui.horizontal_wrapped(|ui| {
+                    let mut unknown = Vec::new();
+                    for tank_type in 0..=u8::MAX {
+                        let name = match ETankPacketType::from(tank_type) {
+                            ETankPacketType::Unknown(_) => {
+                                unknown.push(tank_type);
+                                continue;
+                            }
+                            known => known.name(),
+                        };
+                        let mut enabled = filter.allows_tank_type(tank_type);
+                        if ui.checkbox(&mut enabled, name).changed() {
+                            filter.set_tank_type(tank_type, enabled);
+                            changed = true;
+                        }
+                    }
+                    let mut other = unknown.iter().any(|tank_type| filter.allows_tank_type(*tank_type));
+                    if ui.checkbox(&mut other, "Unknown").changed() {
+                        for tank_type in unknown {
+                            filter.set_tank_type(tank_type, other);
+                        }
+                        changed = true;
+                    }
+                });
+
+                if changed {
+                    bot.packet_filter.set(&filter);
+                }
+            });
+    }
+
+    fn render_capture(&mut self, ui: &mut Ui, bot: &Arc<Bot>) {
+        ui.horizontal(|ui| {
+            let mut capturing = bot.capturing.load(Ordering::Relaxed);
+            if ui.checkbox(&mut capturing, "Capture").changed() {
+                bot.capturing.store(capturing, Ordering::Relaxed);
+            }
+            let label = if self.paused { "Resume" } else { "Pause" };
+            if ui.button(label).clicked() {
+                self.paused = !self.paused;
+                if self.paused {
+                    self.paused_packets = bot.captured_packets.lock().unwrap().iter().cloned().collect();
+                }
+            }
+            ui.separator();
+            ui.label("Type");
+            egui::ComboBox::from_id_salt("dev_filter_type")
+                .selected_text(match self.filter_type {
+                    Some(id) => EPacketType::from(id).to_string(),
+                    None => "All".to_string(),
+                })
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut self.filter_type, None, "All");
+                    for id in 0..=9 {
+                        ui.selectable_value(&mut self.filter_type, Some(id), EPacketType::from(id).to_string());
+                    }
+                });
+            let mut filter_tank = self.filter_tank_type.is_some();
+            if ui.checkbox(&mut filter_tank, "Tank type").changed() {
+                self.filter_tank_type = if filter_tank { Some(0) } else { None };
+            }
+            if let Some(tank_type) = self.filter_tank_type.as_mut() {
+                ui.add(egui::DragValue::new(tank_type));
+            }
+        });
+
+        let packets: Vec<CapturedPacket> = if self.paused {
+            self.paused_packets.clone()
+        } else {
+            bot.captured_packets.lock().unwrap().iter().cloned().collect()
+        };
+
+        egui::ScrollArea::vertical()
+            .id_salt("dev_packets")
+            .scroll_bar_visibility(ScrollBarVisibility::AlwaysVisible)
+            .auto_shrink(false)
+            .stick_to_bottom(!self.paused)
+            .show(ui, |ui| {
+                for (index, packet) in packets.iter().enumerate() {
+                    if self.filter_type.map_or(false, |id| id != packet.packet_type()) {
+                        continue;
+                    }
+                    if self.filter_tank_type.is_some() && self.filter_tank_type != packet.tank_packet_type() {
+                        continue;
+                    }
+
+                    let direction = match packet.direction {
+                        EPacketDirection::Incoming => egui_remixicon::icons::ARROW_DOWN_LINE,
+                        EPacketDirection::Outgoing => egui_remixicon::icons::ARROW_UP_LINE,
+                    };
+                    let tank_packet = packet.tank_packet();
+                    let name = match &tank_packet {
+                        Some(tank_packet) => tank_packet._type.to_string(),
+                        None => EPacketType::from(packet.packet_type()).to_string(),
+                    };
+                    let preview = hex::encode(&packet.data[..packet.data.len().min(16)]);
+                    let header = format!("{} ch{} {} ({} bytes) {}", direction, packet.channel, name, packet.data.len(), preview);
+                    if ui.selectable_label(self.expanded == Some(index), header).clicked() {
+                        self.expanded = if self.expanded == Some(index) { None } else { Some(index) };
+                    }
+                    if self.expanded == Some(index) {
+                        ui.group(|ui| {
+                            if let Some(tank_packet) = &tank_packet {
+                                ui.label(format!("{:#?}", tank_packet));
+                            }
+                            ui.add(egui::Label::new(egui::RichText::new(hex::encode(&packet.data)).monospace()).wrap());
+                        });
+                    }
+                }
+            });
+    }
+
+    fn render_profiler(&mut self, ui: &mut Ui, bot: &Arc<Bot>) {
+        egui::CollapsingHeader::new("Profiler")
+            .id_salt("dev_profiler")
+            .show(ui, |ui| {
+                if cfg!(not(feature = "profiler")) {
+                    ui.label(egui::RichText::new("built without the profiler feature").weak());
+                    return;
+                }
+                let temporary_data = bot.temporary_data.read().unwrap();
+                let spans = temporary_data.profiler.summary();
+                if ui.button("Reset").clicked() {
+                    temporary_data.profiler.reset();
+                }
+                if spans.is_empty() {
+                    ui.label(egui::RichText::new("no spans recorded yet").weak());
+                    return;
+                }
+                egui::Grid::new("dev_profiler_spans").striped(true).show(ui, |ui| {
+                    ui.strong("Span");
+                    ui.strong("Count");
+                    ui.strong("Total");
+                    ui.strong("Mean");
+                    ui.strong("p95");
+                    ui.end_row();
+                    for span in spans {
+                        let mean = span.total / span.count.max(1) as u32;
+                        ui.label(&span.name);
+                        ui.label(span.count.to_string());
+                        ui.label(format!("{:.1?}", span.total));
+                        ui.label(format!("{:.1?}", mean));
+                        ui.label(format!("{:.1?}", span.p95));
+                        ui.end_row();
+                    }
+                });
+            });
+    }
+
+    // Shared by every bot, the wrapped locks don't know which bot they belong to
+    fn render_contention(&mut self, ui: &mut Ui) {
+        egui::CollapsingHeader::new("Lock contention")
+            .id_salt("dev_contention")
+            .show(ui, |ui| {
+                if cfg!(not(feature = "lock_timing")) {
+                    ui.label(egui::RichText::new("built without the lock_timing feature").weak());
+                    return;
+                }
+                if ui.button("Reset").clicked() {
+                    lock_timing::reset();
+                }
+                let report = lock_timing::report();
+                if report.is_empty() {
+                    ui.label(egui::RichText::new("no waits recorded yet").weak());
+                    return;
+                }
+                egui::Grid::new("dev_contention_sites").striped(true).show(ui, |ui| {
+                    ui.strong("Call site");
+                    ui.strong("Count");
+                    ui.strong("Total");
+                    ui.strong("Mean");
+                    ui.strong("Max");
+                    ui.end_row();
+                    for site in report {
+                        let mean = site.total / site.count.max(1) as u32;
+                        ui.label(&site.site);
+                        ui.label(site.count.to_string());
+                        ui.label(format!("{:.1?}", site.total));
+                        ui.label(format!("{:.1?}", mean));
+                        ui.label(format!("{:.1?}", site.max));
+                        ui.end_row();
+                    }
+                });
+            });
+    }
+
+    fn render_sender(&mut self, ui: &mut Ui, bot: &Arc<Bot>) {
+        let developer_mode = config::get_developer_mode();
+        ui.horizontal(|ui| {
+            ui.label("Manual sender");
+            if !developer_mode {
+                ui.label(egui::RichText::new("enable developer mode in settings to send packets").weak());
+            }
+        });
+        ui.add_enabled_ui(developer_mode, |ui| {
+            ui.horizontal(|ui| {
+                ui.selectable_value(&mut self.send_raw, false, "Tank packet");
+                ui.selectable_value(&mut self.send_raw, true, "Raw hex");
+                ui.separator();
+                ui.label("Channel");
+                ui.add(egui::DragValue::new(&mut self.send_channel).range(0..=1));
+            });
+            if self.send_raw {
+                ui.add(egui::TextEdit::multiline(&mut self.send_hex).hint_text("04 00 00 00 ...").desired_rows(2));
+            } else {
+                egui::Grid::new("dev_tank_fields").show(ui, |ui| {
+                    ui.label("Type");
+                    ui.add(egui::DragValue::new(&mut self.tank_type));
+                    ui.label("NetID");
+                    ui.add(egui::DragValue::new(&mut self.tank.net_id));
+                    ui.label("Flags");
+                    ui.add(egui::DragValue::new(&mut self.tank.flags));
+                    ui.label("Value");
+                    ui.add(egui::DragValue::new(&mut self.tank.value));
+                    ui.end_row();
+                    ui.label("Vector X");
+                    ui.add(egui::DragValue::new(&mut self.tank.vector_x));
+                    ui.label("Vector Y");
+                    ui.add(egui::DragValue::new(&mut self.tank.vector_y));
+                    ui.label("Int X");
+                    ui.add(egui::DragValue::new(&mut self.tank.int_x));
+                    ui.label("Int Y");
+                    ui.add(egui::DragValue::new(&mut self.tank.int_y));
+                    ui.end_row();
+                });
+            }
+            ui.horizontal(|ui| {
+                if ui.button("Send").clicked() {
+                    match self.build_packet() {
+                        Ok(data) => {
+                            self.error.clear();
+                            bot.send_raw_bytes(self.send_channel, &data);
+                        }
+                        Err(err) => self.error = err,
+                    }
+                }
+                if !self.error.is_empty() {
+                    ui.colored_label(egui::Color32::from_rgb(220, 53, 69), &self.error);
+                }
+            });
+        });
+    }
+
+    fn build_packet(&self) -> Result<Vec<u8>, String> {
+        if self.send_raw {
+            let digits: String = self.send_hex.chars().filter(|c| !c.is_whitespace()).collect();
+            let data = hex::decode(digits).map_err(|err| err.to_string())?;
+            if data.len() < 4 {
+                return Err("packet needs at least the 4 byte message type".to_string());
+            }
+            return Ok(data);
+        }
+
+        let packet = TankPacket {
+            net_id: self.tank.net_id,
+            flags: self.tank.flags,
+            value: self.tank.value,
+            vector_x: self.tank.vector_x,
+            vector_y: self.tank.vector_y,
+            int_x: self.tank.int_x,
+            int_y: self.tank.int_y,
+            ..Default::default()
+        };
+        let mut tank_bytes = packet.to_bytes().map_err(|err| err.to_string())?;
+        // The packet type is the first byte, set it directly so unknown types can be sent too
+        tank_bytes[0] = self.tank_type;
+
+        let mut data = (EPacketType::NetMessageGamePacket as u32).to_le_bytes().to_vec();
+        data.extend_from_slice(&tank_bytes);
+        Ok(data)
+    }
+}
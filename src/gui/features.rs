@@ -0,0 +1,422 @@
+use crate::core::features::transfer_items::TransferReport;
+use crate::core::features::{auto_quest, auto_surgery, repeat_activate, static_farm, world_watcher};
+use crate::core::Bot;
+use crate::gui::toasts;
+use crate::manager::bot_manager::BotManager;
+use crate::utils::{self, date};
+use eframe::egui::{self, Ui};
+use std::sync::{Arc, RwLock};
+use std::thread::{spawn, JoinHandle};
+use std::time::Duration;
+
+#[derive(Default)]
+pub struct Features {
+    // Watcher settings being edited, loaded from the config on first render
+    pub loaded: bool,
+    pub watch_worlds: String,
+    pub watch_interval: u64,
+    // Static farm settings of `farm_bot`, loaded again when another bot is selected
+    pub farm_bot: String,
+    pub farm_pattern: String,
+    pub farm_item: u32,
+    pub repeat_interval: u64,
+    pub repeat_max_uses: u32,
+    pub repeat_ticket: u32,
+    pub transfer_to: String,
+    pub transfer_world: String,
+    pub transfer_items: String,
+    pub transfer: Option<JoinHandle<TransferReport>>,
+    pub transfer_result: Option<String>,
+}
+
+impl Features {
+    pub fn render(&mut self, ui: &mut Ui, manager: &Arc<RwLock<BotManager>>, bot: &Arc<Bot>) {
+        if !self.loaded {
+            self.watch_worlds = utils::config::get_watch_worlds().join("\n");
+            self.watch_interval = utils::config::get_watch_interval();
+            self.loaded = true;
+        }
+        let username = bot.info.lock().unwrap().payload[0].clone();
+        if self.farm_bot != username {
+            let bot_config = utils::config::get_bot_config(&username).unwrap_or_default();
+            self.farm_pattern = format_pattern(&bot_config.static_farm_pattern);
+            self.farm_item = bot_config.static_farm_item;
+            self.repeat_interval = if bot_config.repeat_interval == 0 { 60 } else { bot_config.repeat_interval };
+            self.repeat_max_uses = bot_config.repeat_max_uses;
+            self.repeat_ticket = bot_config.repeat_ticket;
+            self.farm_bot = username;
+        }
+
+        ui.group(|ui| {
+            ui.vertical(|ui| {
+                ui.label("World watcher");
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.add(
+                        egui::TextEdit::multiline(&mut self.watch_worlds)
+                            .hint_text("one world per line")
+                            .desired_rows(4),
+                    );
+                    ui.vertical(|ui| {
+                        ui.horizontal(|ui| {
+                            ui.label("Every");
+                            ui.add(
+                                egui::DragValue::new(&mut self.watch_interval)
+                                    .range(60..=86400)
+                                    .suffix("s"),
+                            );
+                        });
+                        if ui.button("Save").clicked() {
+                            utils::config::set_watch_worlds(self.worlds());
+                            utils::config::set_watch_interval(self.watch_interval);
+                        }
+                        if world_watcher::is_watching(bot) {
+                            if ui.button("Stop").clicked() {
+                                world_watcher::stop(bot);
+                            }
+                        } else if ui.button("Start").clicked() {
+                            let bot = bot.clone();
+                            let worlds = self.worlds();
+                            let interval = Duration::from_secs(self.watch_interval);
+                            spawn(move || world_watcher::run(&bot, worlds, interval));
+                        }
+                    });
+                });
+
+                let reports = bot.temporary_data.read().unwrap().watch_reports.clone();
+                if reports.is_empty() {
+                    return;
+                }
+                ui.separator();
+                let utc_offset = utils::config::get_stats_utc_offset();
+                egui::Grid::new("watch_reports")
+                    .num_columns(5)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        ui.strong("World");
+                        ui.strong("Visited");
+                        ui.strong("Players");
+                        ui.strong("Gems");
+                        ui.strong("Changed tiles");
+                        ui.end_row();
+                        for report in reports {
+                            ui.label(&report.world);
+                            ui.label(date::format_time(report.visited_at, utc_offset));
+                            if report.failed {
+                                ui.colored_label(egui::Color32::LIGHT_RED, "failed to load");
+                                ui.label("");
+                                ui.label("");
+                            } else {
+                                ui.label(report.players.to_string());
+                                ui.label(report.dropped_gems.to_string());
+                                ui.label(
+                                    report
+                                        .changed_tiles
+                                        .map_or("-".to_string(), |changed| changed.to_string()),
+                                );
+                            }
+                            ui.end_row();
+                        }
+                    });
+            });
+        });
+
+        ui.add_space(8.0);
+        self.render_static_farm(ui, bot);
+        ui.add_space(8.0);
+        self.render_surgery(ui, bot);
+        ui.add_space(8.0);
+        self.render_repeat_activate(ui, bot);
+        ui.add_space(8.0);
+        self.render_daily_quest(ui, bot);
+        ui.add_space(8.0);
+        self.render_transfer(ui, manager);
+    }
+
+    fn render_transfer(&mut self, ui: &mut Ui, manager: &Arc<RwLock<BotManager>>) {
+        if self.transfer.as_ref().is_some_and(|handle| handle.is_finished()) {
+            let report = self.transfer.take().unwrap().join();
+            self.transfer_result = Some(match report {
+                Ok(report) => report.describe(),
+                Err(_) => "stopped: the transfer thread panicked".to_string(),
+            });
+        }
+        ui.group(|ui| {
+            ui.vertical(|ui| {
+                ui.label("Transfer items");
+                ui.separator();
+                ui.label(
+                    egui::RichText::new("Drops the items for another logged in bot, with nobody else in the world")
+                        .weak(),
+                );
+                ui.horizontal(|ui| {
+                    ui.label("To");
+                    ui.add(egui::TextEdit::singleline(&mut self.transfer_to).desired_width(120.0));
+                    ui.label("World");
+                    ui.add(egui::TextEdit::singleline(&mut self.transfer_world).desired_width(120.0));
+                });
+                ui.add(
+                    egui::TextEdit::multiline(&mut self.transfer_items)
+                        .hint_text("item id and amount, one per line")
+                        .desired_rows(3),
+                );
+                if self.transfer.is_some() {
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        ui.label("Transferring");
+                    });
+                } else if ui.button("Transfer").clicked() {
+                    match parse_transfer_items(&self.transfer_items) {
+                        Some(items) if !items.is_empty() && !self.transfer_world.trim().is_empty() => {
+                            let result = manager.read().unwrap().transfer_items(
+                                &self.farm_bot,
+                                self.transfer_to.trim(),
+                                items,
+                                self.transfer_world.trim().to_string(),
+                            );
+                            match result {
+                                Ok(handle) => {
+                                    self.transfer = Some(handle);
+                                    self.transfer_result = None;
+                                }
+                                Err(err) => toasts::error(&err),
+                            }
+                        }
+                        _ => toasts::error("Enter a world and at least one \"item_id amount\" line"),
+                    }
+                }
+                if let Some(result) = &self.transfer_result {
+                    ui.label(result);
+                }
+            });
+        });
+    }
+
+    fn render_daily_quest(&mut self, ui: &mut Ui, bot: &Arc<Bot>) {
+        ui.group(|ui| {
+            ui.vertical(|ui| {
+                ui.label("Daily quest");
+                ui.separator();
+                ui.label(
+                    egui::RichText::new("Asks Crazy Jim in the current world and hands the items in once they're all owned")
+                        .weak(),
+                );
+                if ui.button("Check and hand in").clicked() {
+                    let bot = bot.clone();
+                    spawn(move || auto_quest::run(&bot));
+                }
+                let daily = bot.temporary_data.read().unwrap().quests.daily.clone();
+                if let Some(quest) = daily {
+                    for requirement in &quest.requirements {
+                        ui.label(format!("{} {}", requirement.amount, requirement.name));
+                    }
+                    if !quest.reward.is_empty() {
+                        ui.label(format!("Reward: {}", quest.reward));
+                    }
+                }
+            });
+        });
+    }
+
+    fn render_repeat_activate(&mut self, ui: &mut Ui, bot: &Arc<Bot>) {
+        ui.group(|ui| {
+            ui.vertical(|ui| {
+                ui.label("Repeat activate");
+                ui.separator();
+                // Picked from the world map's tile window, so read fresh every frame
+                let tile = utils::config::get_bot_config(&self.farm_bot).and_then(|bot_config| bot_config.repeat_tile);
+                ui.horizontal(|ui| {
+                    ui.label("Tile");
+                    match tile {
+                        Some((x, y)) => ui.label(format!("{},{}", x, y)),
+                        None => ui.label(
+                            egui::RichText::new("ctrl+click a tile on the world map to pick one").weak(),
+                        ),
+                    };
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Every");
+                    ui.add(egui::DragValue::new(&mut self.repeat_interval).range(1..=86400).suffix("s"));
+                    ui.label("Uses");
+                    ui.add(egui::DragValue::new(&mut self.repeat_max_uses))
+                        .on_hover_text("0 keeps going until stopped");
+                    ui.label("Ticket");
+                    ui.add(egui::DragValue::new(&mut self.repeat_ticket))
+                        .on_hover_text("Item used before each activation, 0 for none");
+                });
+                ui.horizontal(|ui| {
+                    if ui.button("Save").clicked() {
+                        let (interval, max_uses, ticket) =
+                            (self.repeat_interval, self.repeat_max_uses, self.repeat_ticket);
+                        utils::config::update_bot_config(&self.farm_bot, |bot_config| {
+                            bot_config.repeat_interval = interval;
+                            bot_config.repeat_max_uses = max_uses;
+                            bot_config.repeat_ticket = ticket;
+                        });
+                    }
+                    if repeat_activate::is_running(bot) {
+                        if ui.button("Stop").clicked() {
+                            repeat_activate::stop(bot);
+                        }
+                    } else if let Some(tile) = tile {
+                        if ui.button("Start").clicked() {
+                            let bot = bot.clone();
+                            let interval = Duration::from_secs(self.repeat_interval);
+                            let max_uses = self.repeat_max_uses;
+                            let ticket = (self.repeat_ticket != 0).then_some(self.repeat_ticket);
+                            spawn(move || repeat_activate::run(&bot, tile, interval, max_uses, ticket));
+                        }
+                    }
+                });
+
+                let stats = bot.temporary_data.read().unwrap().repeat.stats.clone();
+                if let Some(stats) = stats {
+                    ui.label(format!("{} uses, {} tickets", stats.uses, stats.tickets_used));
+                    ui.label(repeat_activate::summary(bot, &stats));
+                }
+            });
+        });
+    }
+
+    fn render_surgery(&mut self, ui: &mut Ui, bot: &Arc<Bot>) {
+        ui.group(|ui| {
+            ui.vertical(|ui| {
+                ui.label("Auto surgery");
+                ui.separator();
+                ui.label(
+                    egui::RichText::new("Operates on the nearest bed, tools and rules are in surgery_rules.json")
+                        .weak(),
+                );
+                if auto_surgery::is_running(bot) {
+                    if ui.button("Stop").clicked() {
+                        auto_surgery::stop(bot);
+                    }
+                } else if ui.button("Start").clicked() {
+                    let bot = bot.clone();
+                    spawn(move || auto_surgery::run(&bot));
+                }
+                let stats = bot.temporary_data.read().unwrap().surgery.stats.clone();
+                if let Some(stats) = stats {
+                    ui.label(format!(
+                        "{} surgeries, {:.0}% successful, {} complications",
+                        stats.completed,
+                        stats.success_rate(),
+                        stats.complications
+                    ));
+                }
+            });
+        });
+    }
+
+    fn render_static_farm(&mut self, ui: &mut Ui, bot: &Arc<Bot>) {
+        ui.group(|ui| {
+            ui.vertical(|ui| {
+                ui.label("Static farm");
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.label("Block");
+                    ui.add(egui::DragValue::new(&mut self.farm_item));
+                    ui.label("Offsets");
+                    ui.add(
+                        egui::TextEdit::singleline(&mut self.farm_pattern)
+                            .hint_text("-1,0 1,0 0,1"),
+                    );
+                    egui::ComboBox::from_id_salt("static_farm_preset")
+                        .selected_text("Preset")
+                        .show_ui(ui, |ui| {
+                            for (name, pattern) in static_farm::PRESETS {
+                                if ui.selectable_label(false, *name).clicked() {
+                                    self.farm_pattern = format_pattern(pattern);
+                                }
+                            }
+                        });
+                });
+                let pattern = parse_pattern(&self.farm_pattern);
+                ui.horizontal(|ui| {
+                    if ui.button("Save").clicked() {
+                        match &pattern {
+                            Some(pattern) => {
+                                let pattern = pattern.clone();
+                                let item_id = self.farm_item;
+                                utils::config::update_bot_config(&self.farm_bot, |bot_config| {
+                                    bot_config.static_farm_pattern = pattern;
+                                    bot_config.static_farm_item = item_id;
+                                });
+                            }
+                            None => toasts::error("Offsets are x,y pairs separated by spaces"),
+                        }
+                    }
+                    if static_farm::is_running(bot) {
+                        if ui.button("Stop").clicked() {
+                            static_farm::stop(bot);
+                        }
+                    } else if ui
+                        .add_enabled(pattern.is_some(), egui::Button::new("Start"))
+                        .clicked()
+                    {
+                        let bot = bot.clone();
+                        let pattern = pattern.clone().unwrap_or_default();
+                        let item_id = self.farm_item;
+                        spawn(move || static_farm::run(&bot, pattern, item_id));
+                    }
+                });
+
+                let stats = bot.temporary_data.read().unwrap().static_farm.clone();
+                if let Some(stats) = stats {
+                    ui.label(format!(
+                        "Placed {}, broke {} ({:.0}/hour), {} seeds",
+                        stats.placed,
+                        stats.broken,
+                        stats.blocks_per_hour(),
+                        stats.seeds
+                    ));
+                }
+            });
+        });
+    }
+
+    fn worlds(&self) -> Vec<String> {
+        self.watch_worlds
+            .lines()
+            .map(|line| line.trim().to_uppercase())
+            .filter(|line| !line.is_empty())
+            .collect()
+    }
+}
+
+fn format_pattern(pattern: &[(i32, i32)]) -> String {
+    pattern
+        .iter()
+        .map(|(x, y)| format!("{},{}", x, y))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+// None when any offset isn't an x,y pair, or there are none
+fn parse_pattern(text: &str) -> Option<Vec<(i32, i32)>> {
+    let pattern = text
+        .split_whitespace()
+        .map(|pair| {
+            let (x, y) = pair.split_once(',')?;
+            Some((x.trim().parse().ok()?, y.trim().parse().ok()?))
+        })
+        .collect::<Option<Vec<(i32, i32)>>>()?;
+    if pattern.is_empty() {
+        None
+    } else {
+        Some(pattern)
+    }
+}
+
+// "item_id amount" per line, None when any line doesn't parse
+fn parse_transfer_items(text: &str) -> Option<Vec<(u32, u32)>> {
+    text.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            let mut parts = line.split_whitespace();
+            let item_id = parts.next()?.parse().ok()?;
+            let amount: u32 = parts.next()?.parse().ok()?;
+            (amount > 0 && parts.next().is_none()).then_some((item_id, amount))
+        })
+        .collect()
+}
@@ -7,25 +7,21 @@ use crate::utils;
 #[derive(Default)]
 pub struct Growscan {
     pub selected_bot: String,
+    pub search: String,
+    pub export_path: String,
 }
 
 impl Growscan {
     pub fn render(&mut self, ui: &mut Ui, manager: &Arc<RwLock<BotManager>>) {
         self.selected_bot = utils::config::get_selected_bot();
         if !self.selected_bot.is_empty() {
-            let bot = {
-                let manager = manager.read().unwrap();
-                manager.get_bot(&self.selected_bot).cloned()
-            };
+            let bot = manager.read().unwrap().get_bot(&self.selected_bot);
             if let Some(bot) = bot {
                 ui.allocate_ui(ui.available_size(), |ui| {
                     ui.vertical(|ui| {
-                        let (objects, tiles) = {
-                            let world = bot.world.read().unwrap();
-                            (world.dropped.clone(), world.tiles.clone())
-                        };
+                        let objects = bot.world.read().unwrap().dropped.clone();
 
-                        egui::ScrollArea::vertical().id_source("objects_scroll").show(ui, |ui| {
+                        egui::ScrollArea::vertical().id_salt("objects_scroll").show(ui, |ui| {
                             ui.heading("Objects");
 
                             let mut item_amounts = HashMap::new();
@@ -54,34 +50,50 @@ impl Growscan {
 
                         ui.add_space(20.0);
 
-                        egui::ScrollArea::vertical().id_source("tiles_scroll").show(ui, |ui| {
-                            ui.heading("Tiles");
-
-                            let mut item_counts = HashMap::new();
-                            for tile in &tiles {
-                                if tile.foreground_item_id != 0 {
-                                    *item_counts.entry(tile.foreground_item_id).or_insert(0) += 1;
-                                }
-                                if tile.background_item_id != 0 {
-                                    *item_counts.entry(tile.background_item_id).or_insert(0) += 1;
+                        ui.heading("Tiles");
+                        let census = bot.world_census();
+                        ui.horizontal(|ui| {
+                            ui.label("Search:");
+                            ui.text_edit_singleline(&mut self.search);
+                            ui.label("Export to:");
+                            ui.add(
+                                egui::TextEdit::singleline(&mut self.export_path)
+                                    .hint_text("growscan.csv")
+                                    .desired_width(120.0),
+                            );
+                            if ui.button("Export CSV").clicked() {
+                                let path = if self.export_path.is_empty() {
+                                    "growscan.csv"
+                                } else {
+                                    self.export_path.as_str()
+                                };
+                                if let Err(err) = utils::export::write_census(path, &census) {
+                                    bot.log_error(&format!("Failed to export growscan: {}", err));
                                 }
                             }
+                        });
 
-                            let mut sorted_tiles: Vec<_> = item_counts.iter().collect();
-                            sorted_tiles.sort_by_key(|&(id, _)| id);
-
+                        let search = self.search.to_lowercase();
+                        egui::ScrollArea::vertical().id_salt("tiles_scroll").show(ui, |ui| {
                             egui::Grid::new("tiles_grid")
-                                .num_columns(2)
+                                .num_columns(4)
                                 .spacing([10.0, 10.0])
                                 .striped(true)
-                                .min_col_width(ui.available_width())
                                 .show(ui, |ui| {
-                                    for (id, count) in sorted_tiles {
-                                        if let Some(item_data) = bot.item_database.read().unwrap().get_item(&(*id as u32)) {
-                                            let item_name = &item_data.name;
-                                            ui.label(format!("{} {}", count, item_name));
-                                            ui.end_row();
-                                        }
+                                    ui.strong("ID");
+                                    ui.strong("Name");
+                                    ui.strong("Foreground");
+                                    ui.strong("Background");
+                                    ui.end_row();
+                                    for (id, name, foreground, background) in census
+                                        .iter()
+                                        .filter(|(_, name, _, _)| name.to_lowercase().contains(&search))
+                                    {
+                                        ui.label(id.to_string());
+                                        ui.label(name);
+                                        ui.label(foreground.to_string());
+                                        ui.label(background.to_string());
+                                        ui.end_row();
                                     }
                                 });
                         });
@@ -0,0 +1,262 @@
+use crate::core::{outgoing, Bot};
+use crate::manager::bot_manager::BotManager;
+use crate::texture_manager::{sprite_uv, TextureManager};
+use crate::utils::config;
+use eframe::egui::{self, Color32, Ui};
+use gtitem_r::structs::Item;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::thread::spawn;
+
+pub const SLOTS: usize = 10;
+const SLOT_SIZE: f32 = 40.0;
+const KEYS: [egui::Key; SLOTS] = [
+    egui::Key::Num1,
+    egui::Key::Num2,
+    egui::Key::Num3,
+    egui::Key::Num4,
+    egui::Key::Num5,
+    egui::Key::Num6,
+    egui::Key::Num7,
+    egui::Key::Num8,
+    egui::Key::Num9,
+    egui::Key::Num0,
+];
+
+// What is being dragged onto a slot
+#[derive(Clone, Copy)]
+pub enum HotbarDrag {
+    Item(u32),
+    Slot(usize),
+}
+
+#[derive(Default)]
+pub struct Hotbar {
+    bot: String,
+    // Item id per slot, 0 for empty
+    slots: Vec<u32>,
+    // Amount seen when a consumable was used and how many uses since, shown until the
+    // inventory changes
+    pending: HashMap<u32, (u8, u8)>,
+    // Last slot used, what manual control places with F
+    selected: Option<usize>,
+}
+
+impl Hotbar {
+    fn load(&mut self, username: &str) {
+        if self.bot == username {
+            return;
+        }
+        let mut slots = config::get_bot_config(username)
+            .map(|bot_config| bot_config.hotbar)
+            .unwrap_or_default();
+        slots.resize(SLOTS, 0);
+        self.slots = slots;
+        self.pending.clear();
+        self.selected = None;
+        self.bot = username.to_string();
+    }
+
+    fn save(&self) {
+        config::set_bot_hotbar(&self.bot, self.slots.clone());
+    }
+
+    // 1-0 use the pinned items of the selected bot, unless a text field has the keyboard
+    // or the window isn't focused
+    pub fn handle_keys(&mut self, ctx: &egui::Context, manager: &Arc<RwLock<BotManager>>) {
+        if !ctx.input(|i| i.focused) || ctx.wants_keyboard_input() {
+            return;
+        }
+        let pressed = ctx.input(|i| {
+            if i.modifiers.any() {
+                return None;
+            }
+            KEYS.iter().position(|key| i.key_pressed(*key))
+        });
+        let index = match pressed {
+            Some(index) => index,
+            None => return,
+        };
+        let username = config::get_selected_bot();
+        if username.is_empty() {
+            return;
+        }
+        self.load(&username);
+        if let Some(bot) = manager.read().unwrap().get_bot(&username) {
+            self.use_slot(&bot, index);
+        }
+    }
+
+    fn use_slot(&mut self, bot: &Arc<Bot>, index: usize) {
+        let item_id = self.slots.get(index).copied().unwrap_or(0);
+        if item_id == 0 {
+            return;
+        }
+        let amount = bot
+            .inventory
+            .lock()
+            .unwrap()
+            .items
+            .get(&(item_id as u16))
+            .map_or(0, |item| item.amount);
+        if self.displayed_amount(item_id, amount) == 0 {
+            return;
+        }
+        self.selected = Some(index);
+        let consumable = config::get_consumables()
+            .iter()
+            .any(|consumable| consumable.item_id == item_id);
+        if consumable {
+            let (_, used) = self.pending.entry(item_id).or_insert((amount, 0));
+            *used += 1;
+        }
+        let bot = bot.clone();
+        spawn(move || {
+            outgoing::user_initiated(|| {
+                if consumable {
+                    bot.consume(item_id);
+                } else {
+                    bot.wear(item_id);
+                }
+            })
+        });
+    }
+
+    // Item of the last used slot, 0 when none was used or the hotbar belongs to another bot
+    pub fn selected_item(&self, username: &str) -> u32 {
+        if self.bot != username {
+            return 0;
+        }
+        self.selected
+            .and_then(|index| self.slots.get(index).copied())
+            .unwrap_or(0)
+    }
+
+    // The inventory amount minus uses the server hasn't answered yet
+    fn displayed_amount(&mut self, item_id: u32, amount: u8) -> u8 {
+        match self.pending.get(&item_id) {
+            Some(&(seen, used)) if seen == amount => amount.saturating_sub(used),
+            Some(_) => {
+                self.pending.remove(&item_id);
+                amount
+            }
+            None => amount,
+        }
+    }
+
+    pub fn render(
+        &mut self,
+        ui: &mut Ui,
+        bot: &Arc<Bot>,
+        username: &str,
+        texture_manager: &TextureManager,
+    ) {
+        self.load(username);
+        let amounts: HashMap<u32, u8> = bot
+            .inventory
+            .lock()
+            .unwrap()
+            .items
+            .values()
+            .map(|item| (item.id as u32, item.amount))
+            .collect();
+        let items: Vec<Option<Item>> = {
+            let item_database = bot.item_database.read().unwrap();
+            self.slots
+                .iter()
+                .map(|&id| (id != 0).then(|| item_database.get_item(&id)).flatten())
+                .collect()
+        };
+
+        let mut used = None;
+        let mut dropped = None;
+        let mut cleared = None;
+        ui.horizontal(|ui| {
+            for index in 0..SLOTS {
+                let mut frame = egui::Frame::group(ui.style()).inner_margin(2.0);
+                if self.selected == Some(index) {
+                    frame = frame.stroke(ui.visuals().selection.stroke);
+                }
+                let (_, payload) = ui.dnd_drop_zone::<HotbarDrag, ()>(frame, |ui| {
+                    ui.set_min_size(egui::vec2(SLOT_SIZE, SLOT_SIZE));
+                    let item = match &items[index] {
+                        Some(item) => item,
+                        None => {
+                            ui.label(egui::RichText::new(key_label(index)).weak());
+                            return;
+                        }
+                    };
+                    let item_id = self.slots[index];
+                    let amount = amounts.get(&item_id).copied().unwrap_or(0);
+                    let amount = self.displayed_amount(item_id, amount);
+                    let id = ui.id().with(("hotbar_slot", index));
+                    let response = ui
+                        .dnd_drag_source(id, HotbarDrag::Slot(index), |ui| {
+                            item_icon(ui, texture_manager, item, SLOT_SIZE - 12.0);
+                            ui.label(
+                                egui::RichText::new(format!("{} x{}", key_label(index), amount))
+                                    .small(),
+                            );
+                        })
+                        .response
+                        .interact(egui::Sense::click())
+                        .on_hover_text(format!("{}, right click to unpin", item.name));
+                    if response.clicked() {
+                        used = Some(index);
+                    }
+                    if response.secondary_clicked() {
+                        cleared = Some(index);
+                    }
+                });
+                if let Some(payload) = payload {
+                    dropped = Some((index, *payload));
+                }
+            }
+        });
+
+        if let Some(index) = used {
+            self.use_slot(bot, index);
+        }
+        if let Some(index) = cleared {
+            self.slots[index] = 0;
+            self.save();
+        }
+        match dropped {
+            Some((index, HotbarDrag::Item(item_id))) => {
+                // An item is pinned once, pinning it again moves it
+                for slot in self.slots.iter_mut().filter(|slot| **slot == item_id) {
+                    *slot = 0;
+                }
+                self.slots[index] = item_id;
+                self.save();
+            }
+            Some((index, HotbarDrag::Slot(from))) if from != index => {
+                self.slots.swap(from, index);
+                self.save();
+            }
+            _ => {}
+        }
+    }
+}
+
+fn key_label(index: usize) -> String {
+    ((index + 1) % SLOTS).to_string()
+}
+
+// The item's sprite, or its name when the texture isn't loaded
+pub fn item_icon(ui: &mut Ui, texture_manager: &TextureManager, item: &Item, size: f32) {
+    let texture = match texture_manager.get_texture(&item.texture_file_name) {
+        Some(texture) => texture,
+        None => {
+            let label = egui::Label::new(egui::RichText::new(&item.name).small()).truncate();
+            ui.add_sized([size, size], label);
+            return;
+        }
+    };
+    ui.add(
+        egui::Image::new(texture)
+            .uv(sprite_uv(texture, item.texture_x, item.texture_y, egui::vec2(32.0, 32.0)))
+            .fit_to_exact_size(egui::vec2(size, size))
+            .tint(Color32::WHITE),
+    );
+}
@@ -0,0 +1,252 @@
+use crate::{
+    manager::{
+        account_check::{self, AccountCheck},
+        account_import::ImportSummary,
+        bot_manager::BotManager,
+        bot_template,
+    },
+    types::{eaccount_status::EAccountStatus, elogin_method::ELoginMethod},
+    utils,
+};
+use eframe::egui::{self};
+use std::fs;
+use std::sync::{Arc, Mutex, RwLock};
+use std::thread;
+
+#[derive(Default)]
+pub struct ImportDialog {
+    pub path: String,
+    pub method: ELoginMethod,
+    pub assign_proxies: bool,
+    pub logon: bool,
+    pub template: String,
+    pub summary: Option<ImportSummary>,
+    pub error: Option<String>,
+    // Filled by the import thread, taken once it's done
+    pub import_result: Arc<Mutex<Option<Result<ImportSummary, String>>>>,
+    pub importing: bool,
+    pub open: bool,
+    // Filled by the validation thread, None while it runs
+    pub checks: Arc<Mutex<Option<Vec<AccountCheck>>>>,
+    pub validating: bool,
+    pub csv_status: Option<String>,
+}
+
+impl ImportDialog {
+    pub fn render(&mut self, manager: &Arc<RwLock<BotManager>>, ctx: &egui::Context) {
+        if !self.open {
+            return;
+        }
+        let mut open = self.open;
+        egui::Window::new("Import accounts")
+            .resizable(false)
+            .open(&mut open)
+            .show(ctx, |ui| {
+                egui::Grid::new("import_grid")
+                    .min_col_width(100.0)
+                    .show(ui, |ui| {
+                        ui.label("File");
+                        ui.text_edit_singleline(&mut self.path);
+                        ui.end_row();
+                        ui.label("Template");
+                        let selected = if self.template.is_empty() { "None" } else { self.template.as_str() };
+                        let mut picked = None;
+                        egui::ComboBox::from_id_salt("import_template")
+                            .selected_text(selected.to_string())
+                            .show_ui(ui, |ui| {
+                                if ui.selectable_label(self.template.is_empty(), "None").clicked() {
+                                    picked = Some(String::new());
+                                }
+                                for template in utils::config::get_bot_templates() {
+                                    if ui.selectable_label(self.template == template.name, &template.name).clicked() {
+                                        picked = Some(template.name.clone());
+                                    }
+                                }
+                            });
+                        if let Some(name) = picked {
+                            // The file still has to be in the format of the method picked below
+                            if let Some(template) = bot_template::find(&name) {
+                                self.method = template.login_method;
+                            }
+                            self.template = name;
+                        }
+                        ui.end_row();
+                        ui.label("Login Method");
+                        egui::ComboBox::from_id_salt("import_method")
+                            .selected_text(format!("{:?}", self.method))
+                            .show_ui(ui, |ui| {
+                                ui.selectable_value(&mut self.method, ELoginMethod::LEGACY, "LEGACY");
+                                ui.selectable_value(&mut self.method, ELoginMethod::GOOGLE, "GOOGLE");
+                                ui.selectable_value(&mut self.method, ELoginMethod::APPLE, "APPLE");
+                                ui.selectable_value(&mut self.method, ELoginMethod::STEAM, "STEAM");
+                            });
+                        ui.end_row();
+                        ui.label("Format");
+                        ui.label(match self.method {
+                            ELoginMethod::STEAM => "steamuser:steampass:mail:pass",
+                            ELoginMethod::LEGACY => "growid:password",
+                            _ => "mail:pass",
+                        });
+                        ui.end_row();
+                    });
+                ui.checkbox(&mut self.assign_proxies, "Assign proxies round-robin");
+                ui.checkbox(&mut self.logon, "Log in after import");
+                if let Some(result) = self.import_result.lock().unwrap().take() {
+                    self.importing = false;
+                    match result {
+                        Ok(summary) => {
+                            self.summary = Some(summary);
+                            self.error = None;
+                        }
+                        Err(err) => {
+                            self.summary = None;
+                            self.error = Some(err);
+                        }
+                    }
+                }
+                ui.horizontal(|ui| {
+                    if ui.add_enabled(!self.importing, egui::Button::new("Import")).clicked() {
+                        self.import(manager);
+                    }
+                    if self.importing {
+                        ui.spinner();
+                    }
+                    if ui
+                        .add_enabled(!self.validating, egui::Button::new("Validate"))
+                        .on_hover_text("Checks the credentials without connecting to the game")
+                        .clicked()
+                    {
+                        self.validate();
+                    }
+                    if self.validating {
+                        ui.spinner();
+                    }
+                });
+
+                if let Some(error) = &self.error {
+                    ui.separator();
+                    ui.colored_label(egui::Color32::from_rgb(220, 53, 69), error);
+                }
+                if let Some(summary) = &self.summary {
+                    ui.separator();
+                    ui.label(format!(
+                        "Added {}, skipped {} duplicates, {} malformed",
+                        summary.added.len(),
+                        summary.skipped.len(),
+                        summary.malformed.len()
+                    ));
+                    if !summary.no_proxy.is_empty() {
+                        ui.label(format!(
+                            "{} added without a proxy, their pool has no proxy with room left",
+                            summary.no_proxy.len()
+                        ));
+                    }
+                    egui::ScrollArea::vertical()
+                        .max_height(150.0)
+                        .show(ui, |ui| {
+                            for name in summary.skipped.iter() {
+                                ui.label(format!("Skipped {}: already added", name));
+                            }
+                            for (line, reason) in summary.malformed.iter() {
+                                ui.label(format!("Line {}: {}", line, reason));
+                            }
+                        });
+                }
+                self.render_checks(ui);
+            });
+        self.open = open;
+    }
+
+    // Hundreds of lines mean hundreds of bots to create, done off the GUI thread
+    fn import(&mut self, manager: &Arc<RwLock<BotManager>>) {
+        self.importing = true;
+        let manager = manager.clone();
+        let (path, method, assign_proxies, logon, template) = (
+            self.path.clone(),
+            self.method.clone(),
+            self.assign_proxies,
+            self.logon,
+            self.template.clone(),
+        );
+        let result = self.import_result.clone();
+        thread::spawn(move || {
+            let summary = manager
+                .read()
+                .unwrap()
+                .import_accounts(&path, method, assign_proxies, logon, &template)
+                .map_err(|err| format!("Failed to read file: {}", err));
+            *result.lock().unwrap() = Some(summary);
+            utils::repaint::request();
+        });
+    }
+
+    fn validate(&mut self) {
+        let contents = match fs::read_to_string(&self.path) {
+            Ok(contents) => contents,
+            Err(err) => {
+                self.error = Some(format!("Failed to read file: {}", err));
+                return;
+            }
+        };
+        let (configs, _) = account_check::parse_accounts(&contents, &self.method);
+        self.error = None;
+        self.csv_status = None;
+        self.validating = true;
+        *self.checks.lock().unwrap() = None;
+        let checks = self.checks.clone();
+        thread::spawn(move || {
+            let result = account_check::check_all(
+                configs,
+                account_check::DEFAULT_CONCURRENCY,
+                account_check::DEFAULT_TIMEOUT,
+            );
+            *checks.lock().unwrap() = Some(result);
+        });
+    }
+
+    fn render_checks(&mut self, ui: &mut egui::Ui) {
+        let checks = self.checks.lock().unwrap();
+        let checks = match checks.as_ref() {
+            Some(checks) => checks,
+            None => return,
+        };
+        self.validating = false;
+        ui.separator();
+        egui::ScrollArea::vertical()
+            .id_salt("account_checks")
+            .max_height(200.0)
+            .show(ui, |ui| {
+                egui::Grid::new("account_check_grid").striped(true).show(ui, |ui| {
+                    ui.strong("Account");
+                    ui.strong("Status");
+                    ui.strong("Detail");
+                    ui.end_row();
+                    for check in checks {
+                        ui.label(&check.name);
+                        let color = match check.status {
+                            EAccountStatus::Valid => egui::Color32::from_rgb(40, 167, 69),
+                            EAccountStatus::RateLimited | EAccountStatus::TwoFactorRequired => {
+                                egui::Color32::from_rgb(255, 193, 7)
+                            }
+                            _ => egui::Color32::from_rgb(220, 53, 69),
+                        };
+                        ui.colored_label(color, check.status.name());
+                        ui.label(&check.detail);
+                        ui.end_row();
+                    }
+                });
+            });
+        ui.horizontal(|ui| {
+            if ui.button("Save CSV").clicked() {
+                let path = format!("{}.csv", self.path);
+                self.csv_status = Some(match utils::export::write_account_checks(&path, checks) {
+                    Ok(()) => format!("Saved to {}", path),
+                    Err(err) => format!("Failed to save {}: {}", path, err),
+                });
+            }
+            if let Some(status) = &self.csv_status {
+                ui.label(status);
+            }
+        });
+    }
+}
@@ -1,4 +1,9 @@
+use crate::core::features::auto_deposit;
+use crate::core::{outgoing, Bot};
+use crate::gui::hotbar::{Hotbar, HotbarDrag};
 use crate::manager::bot_manager::BotManager;
+use crate::texture_manager::TextureManager;
+use crate::types::ereaction::EReaction;
 use crate::utils;
 use eframe::egui::{self, Ui};
 use std::sync::{Arc, RwLock};
@@ -7,20 +12,23 @@ use std::thread::spawn;
 #[derive(Default)]
 pub struct Inventory {
     pub selected_bot: String,
+    // Reaction settings being edited, loaded again when another bot is selected
+    pub reactions_bot: String,
+    pub reactions: Vec<EReaction>,
+    pub trash_items: String,
+    pub deposit_items: String,
+    pub storage_world: String,
+    pub deposit_route: String,
+    pub deposit_threshold: u32,
+    pub deposit_mule: String,
+    pub hotbar: Hotbar,
 }
 
 impl Inventory {
-    pub fn render(&mut self, ui: &mut Ui, manager: &Arc<RwLock<BotManager>>) {
+    pub fn render(&mut self, ui: &mut Ui, manager: &Arc<RwLock<BotManager>>, texture_manager: &TextureManager) {
         self.selected_bot = utils::config::get_selected_bot();
         if !self.selected_bot.is_empty() {
-            let bot = {
-                let manager = manager.read().unwrap();
-
-                match manager.get_bot(&self.selected_bot) {
-                    Some(bot) => Some(bot.clone()),
-                    None => None,
-                }
-            };
+            let bot = manager.read().unwrap().get_bot(&self.selected_bot);
             if let Some(bot) = bot {
                 let inventory_items = {
                     let inventory = bot.inventory.lock().unwrap();
@@ -28,6 +36,11 @@ impl Inventory {
                 };
 
                 ui.vertical(|ui| {
+                    self.render_reactions(ui, &bot);
+                    ui.separator();
+                    self.hotbar.render(ui, &bot, &self.selected_bot, texture_manager);
+                    ui.label(egui::RichText::new("Drag items onto the hotbar, 1-0 use them").weak());
+                    ui.separator();
                     egui::Grid::new("inventory_grid")
                         .num_columns(2)
                         .spacing([0.0, 20.0])
@@ -46,10 +59,16 @@ impl Inventory {
                                         .unwrap();
                                     (item.clone(), item.action_type != 20)
                                 };
-                                ui.horizontal(|ui| {
-                                    ui.label(item.name.clone());
-                                    ui.label(format!("x{}", inventory_item.amount));
-                                });
+                                ui.dnd_drag_source(
+                                    ui.id().with(("inventory_item", id)),
+                                    HotbarDrag::Item(id as u32),
+                                    |ui| {
+                                        ui.horizontal(|ui| {
+                                            ui.label(item.name.clone());
+                                            ui.label(format!("x{}", inventory_item.amount));
+                                        });
+                                    },
+                                );
                                 ui.with_layout(
                                     egui::Layout::right_to_left(egui::Align::Center),
                                     |ui| {
@@ -59,19 +78,19 @@ impl Inventory {
                                         {
                                             let bot_clone = bot.clone();
                                             spawn(move || {
-                                                bot_clone.wear(id as u32);
+                                                outgoing::user_initiated(|| bot_clone.wear(id as u32));
                                             });
                                         }
                                         if ui.button("Drop").clicked() {
                                             let bot_clone = bot.clone();
                                             spawn(move || {
-                                                bot_clone.drop_item(id as u32, 1);
+                                                outgoing::user_initiated(|| bot_clone.drop_item(id as u32, 1));
                                             });
                                         }
                                         if ui.button("Trash").clicked() {
                                             let bot_clone = bot.clone();
                                             spawn(move || {
-                                                bot_clone.trash_item(id as u32, 1);
+                                                outgoing::user_initiated(|| bot_clone.trash_item(id as u32, 1));
                                             });
                                         }
                                     },
@@ -83,4 +102,99 @@ impl Inventory {
             }
         }
     }
+
+    fn render_reactions(&mut self, ui: &mut Ui, bot: &Arc<Bot>) {
+        if self.reactions_bot != self.selected_bot {
+            let bot_config = utils::config::get_bot_config(&self.selected_bot).unwrap_or_default();
+            self.reactions = bot_config.inventory_reactions;
+            self.trash_items = join_ids(&bot_config.trash_items);
+            self.deposit_items = join_ids(&bot_config.deposit_items);
+            self.storage_world = bot_config.storage_world;
+            self.deposit_route = bot_config.deposit_route;
+            self.deposit_threshold = bot_config.deposit_threshold;
+            self.deposit_mule = bot_config.deposit_mule;
+            self.reactions_bot = self.selected_bot.clone();
+        }
+
+        let free_slots = bot.inventory.lock().unwrap().free_slots();
+        egui::CollapsingHeader::new(match free_slots {
+            Some(free_slots) => format!("When full ({} slots free)", free_slots),
+            None => "When full".to_string(),
+        })
+        .id_salt("inventory_reactions")
+        .show(ui, |ui| {
+            for reaction in EReaction::ALL {
+                let mut enabled = self.reactions.contains(&reaction);
+                if ui.checkbox(&mut enabled, reaction.label()).changed() {
+                    if enabled {
+                        self.reactions.push(reaction);
+                    } else {
+                        self.reactions.retain(|r| *r != reaction);
+                    }
+                }
+            }
+            egui::Grid::new("inventory_reactions_grid").show(ui, |ui| {
+                ui.label("Trash ids");
+                ui.add(egui::TextEdit::singleline(&mut self.trash_items).hint_text("2, 3, 4"));
+                ui.end_row();
+                ui.label("Deposit ids");
+                ui.add(egui::TextEdit::singleline(&mut self.deposit_items).hint_text("242, 1796"));
+                ui.end_row();
+                ui.label("Storage world");
+                ui.text_edit_singleline(&mut self.storage_world);
+                ui.end_row();
+                ui.label("Deposit route");
+                ui.add(egui::TextEdit::singleline(&mut self.deposit_route).hint_text("optional"));
+                ui.end_row();
+                ui.label("Deposit at");
+                ui.add(egui::DragValue::new(&mut self.deposit_threshold).suffix(" WL"))
+                    .on_hover_text("World locks of deposit items carried, from Settings > Item values. 0 never deposits on its own");
+                ui.end_row();
+                ui.label("Mule");
+                ui.add(egui::TextEdit::singleline(&mut self.deposit_mule).hint_text("bot that receives"));
+                ui.end_row();
+            });
+            ui.horizontal(|ui| {
+                if ui.button("Save").clicked() {
+                    let reactions = self.reactions.clone();
+                    let trash_items = parse_ids(&self.trash_items);
+                    let deposit_items = parse_ids(&self.deposit_items);
+                    let storage_world = self.storage_world.trim().to_uppercase();
+                    let deposit_route = self.deposit_route.trim().to_string();
+                    let deposit_threshold = self.deposit_threshold;
+                    let deposit_mule = self.deposit_mule.trim().to_string();
+                    utils::config::update_bot_config(&self.selected_bot, |bot_config| {
+                        bot_config.inventory_reactions = reactions;
+                        bot_config.trash_items = trash_items;
+                        bot_config.deposit_items = deposit_items;
+                        bot_config.storage_world = storage_world;
+                        bot_config.deposit_route = deposit_route;
+                        bot_config.deposit_threshold = deposit_threshold;
+                        bot_config.deposit_mule = deposit_mule;
+                    });
+                }
+                if ui.button("Deposit now").clicked() {
+                    let bot = bot.clone();
+                    let bot_config =
+                        utils::config::get_bot_config(&self.selected_bot).unwrap_or_default();
+                    spawn(move || {
+                        if let Err(err) = auto_deposit::run(&bot, &bot_config) {
+                            bot.log_error(&format!("Deposit failed: {}", err));
+                        }
+                    });
+                }
+                if bot.automation_paused() && ui.button("Resume automation").clicked() {
+                    bot.resume_automation();
+                }
+            });
+        });
+    }
+}
+
+fn parse_ids(text: &str) -> Vec<u32> {
+    text.split(',').filter_map(|id| id.trim().parse().ok()).collect()
+}
+
+fn join_ids(ids: &[u32]) -> String {
+    ids.iter().map(|id| id.to_string()).collect::<Vec<_>>().join(", ")
 }
@@ -0,0 +1,182 @@
+use crate::core::{outgoing, Bot};
+use crate::types::edirection::EDirection;
+use eframe::egui;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+// Base pace of held keys, stretched by the bot's humanizer like every automated action
+const STEP_MS: u64 = 220;
+const ACTION_MS: u64 = 180;
+
+// Drives the selected bot from the keyboard while the world map is shown. Automation is
+// paused for as long as it's on, and only resumed by it when it was the one pausing it
+pub struct ManualControl {
+    pub username: String,
+    bot: Arc<Bot>,
+    paused_automation: bool,
+    next_step: Instant,
+    next_action: Instant,
+    chat: String,
+    focus_chat: bool,
+    // The map takes the keyboard on start and again once the chat line lets go of it
+    focus_map: bool,
+}
+
+impl ManualControl {
+    pub fn start(username: &str, bot: Arc<Bot>) -> Self {
+        let paused_automation = !bot.automation_paused();
+        if paused_automation {
+            bot.pause_automation();
+        }
+        ManualControl {
+            username: username.to_string(),
+            bot,
+            paused_automation,
+            next_step: Instant::now(),
+            next_action: Instant::now(),
+            chat: String::new(),
+            focus_chat: false,
+            focus_map: true,
+        }
+    }
+
+    pub fn stop(self) {
+        if self.paused_automation {
+            self.bot.resume_automation();
+        }
+    }
+
+    // Returns false once Esc asks to leave manual control. Keys only count while the map has
+    // the keyboard, clicking it takes it back from a text field. Esc in the chat line only
+    // leaves the line
+    pub fn handle_keys(&mut self, ctx: &egui::Context, map: &egui::Response, place_item: u32) -> bool {
+        if std::mem::take(&mut self.focus_map) || map.clicked() {
+            map.request_focus();
+        }
+        if !map.has_focus() {
+            return true;
+        }
+        // Arrows would move the focus on to the next widget and Esc would drop it
+        ctx.memory_mut(|memory| {
+            memory.set_focus_lock_filter(
+                map.id,
+                egui::EventFilter {
+                    horizontal_arrows: true,
+                    vertical_arrows: true,
+                    escape: true,
+                    ..Default::default()
+                },
+            )
+        });
+        let (step, punch, place, chat, exit) = ctx.input(|i| {
+            let held = |keys: [egui::Key; 2]| keys.iter().any(|key| i.key_down(*key));
+            let step = if held([egui::Key::A, egui::Key::ArrowLeft]) {
+                Some((-1, 0))
+            } else if held([egui::Key::D, egui::Key::ArrowRight]) {
+                Some((1, 0))
+            } else if held([egui::Key::W, egui::Key::ArrowUp]) {
+                Some((0, -1))
+            } else if held([egui::Key::S, egui::Key::ArrowDown]) {
+                Some((0, 1))
+            } else {
+                None
+            };
+            (
+                step,
+                i.key_pressed(egui::Key::Space),
+                i.key_pressed(egui::Key::F),
+                i.key_pressed(egui::Key::T),
+                i.key_pressed(egui::Key::Escape),
+            )
+        });
+        if exit {
+            return false;
+        }
+        if chat {
+            self.focus_chat = true;
+        }
+        let now = Instant::now();
+        // A held key doesn't queue steps behind one still being walked
+        if let Some((dx, dy)) = step.filter(|_| now >= self.next_step && !self.bot.is_busy()) {
+            self.next_step = now + self.delay(STEP_MS);
+            self.step(dx, dy);
+        }
+        if (punch || place) && now >= self.next_action {
+            self.next_action = now + self.delay(ACTION_MS);
+            let offset_x = match self.bot.facing() {
+                EDirection::Left => -1,
+                EDirection::Right => 1,
+            };
+            let bot = self.bot.clone();
+            if punch {
+                thread::spawn(move || outgoing::user_initiated(|| bot.punch(offset_x, 0)));
+            } else if place_item != 0 {
+                thread::spawn(move || outgoing::user_initiated(|| bot.place(offset_x, 0, place_item)));
+            }
+        }
+        // Held keys have to be looked at again without waiting for the next input event
+        if step.is_some() {
+            ctx.request_repaint_after(Duration::from_millis(50));
+        }
+        true
+    }
+
+    // One tile at a time, blocked tiles are skipped rather than walked around
+    fn step(&self, dx: i32, dy: i32) {
+        let (x, y) = {
+            let position = self.bot.position.lock().unwrap();
+            ((position.x / 32.0).floor() as i32 + dx, (position.y / 32.0).floor() as i32 + dy)
+        };
+        if x < 0 || y < 0 || !self.bot.astar.lock().unwrap().is_passable(x as u32, y as u32) {
+            return;
+        }
+        let bot = self.bot.clone();
+        thread::spawn(move || {
+            if let Err(err) = outgoing::user_initiated(|| bot.find_path(x as u32, y as u32)) {
+                bot.log_debug(&format!("Manual step to {},{} failed: {}", x, y, err));
+            }
+        });
+    }
+
+    fn delay(&self, base_ms: u64) -> Duration {
+        self.bot.humanizer.lock().unwrap().action_delay(base_ms)
+    }
+
+    // Bindings and the chat line, in the map's bottom left corner
+    pub fn render_hint(&mut self, ctx: &egui::Context, map_rect: egui::Rect, place_name: Option<&str>) {
+        egui::Area::new(egui::Id::new("manual_control_hint"))
+            .fixed_pos(map_rect.left_bottom() + egui::vec2(10.0, -10.0))
+            .pivot(egui::Align2::LEFT_BOTTOM)
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.label(egui::RichText::new("Manual control").strong());
+                    ui.label("WASD / arrows  walk");
+                    ui.label("Space  punch");
+                    ui.label(match place_name {
+                        Some(name) => format!("F  place {}", name),
+                        None => "F  place (use a hotbar slot first)".to_string(),
+                    });
+                    ui.label("T  chat    Esc  exit");
+                    let response = ui.add(
+                        egui::TextEdit::singleline(&mut self.chat)
+                            .hint_text("Say something")
+                            .desired_width(180.0),
+                    );
+                    if std::mem::take(&mut self.focus_chat) {
+                        response.request_focus();
+                    }
+                    if response.lost_focus() {
+                        self.focus_map = true;
+                        if ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                            let message = std::mem::take(&mut self.chat);
+                            if !message.trim().is_empty() {
+                                let bot = self.bot.clone();
+                                thread::spawn(move || outgoing::user_initiated(|| bot.talk(message)));
+                            }
+                        }
+                    }
+                });
+            });
+    }
+}
@@ -2,10 +2,24 @@ pub mod add_bot_dialog;
 pub mod bot_menu;
 pub mod item_database;
 pub mod navbar;
+pub mod notifications;
 pub mod world_map;
 pub mod inventory;
+mod hotbar;
 pub mod proxy_list;
 pub mod add_proxy_dialog;
+pub mod crash_dialog;
 pub mod growscan;
 pub mod settings;
-mod scripting;
\ No newline at end of file
+pub mod setup_screen;
+pub mod unlock_screen;
+pub mod resume_screen;
+mod scripting;
+pub mod toasts;
+pub mod import_dialog;
+mod dev_tools;
+mod timeline;
+mod features;
+mod repl;
+mod pattern_designer;
+mod manual_control;
@@ -1,4 +1,6 @@
 use super::add_bot_dialog::AddBotDialog;
+use super::import_dialog::ImportDialog;
+use crate::manager::notifications;
 use crate::utils;
 use crate::{manager::bot_manager::BotManager, texture_manager::TextureManager};
 use eframe::egui::{self, include_image, Ui};
@@ -21,6 +23,7 @@ impl Navbar {
         &mut self,
         ui: &mut egui::Ui,
         add_bot_dialog: &mut AddBotDialog,
+        import_dialog: &mut ImportDialog,
         manager: &Arc<RwLock<BotManager>>,
     ) {
         ui.horizontal(|ui| {
@@ -56,6 +59,15 @@ impl Navbar {
             {
                 self.current_menu = "settings".to_string();
             }
+            let unread = notifications::unread_count();
+            let label = if unread > 0 {
+                format!("{} Notifications ({})", egui_remixicon::icons::NOTIFICATION_3_FILL, unread)
+            } else {
+                format!("{} Notifications", egui_remixicon::icons::NOTIFICATION_3_FILL)
+            };
+            if ui.add(egui::Button::new(label)).clicked() {
+                self.current_menu = "notifications".to_string();
+            }
             ui.separator();
             if ui
                 .add(egui::Button::new(
@@ -65,6 +77,14 @@ impl Navbar {
             {
                 add_bot_dialog.open = true;
             }
+            if ui
+                .add(egui::Button::new(
+                    egui_remixicon::icons::FILE_UPLOAD_FILL.to_owned() + " Import",
+                ))
+                .clicked()
+            {
+                import_dialog.open = true;
+            }
             if ui
                 .add(egui::Button::new(
                     egui_remixicon::icons::SUBTRACT_FILL.to_owned() + "  Remove bot",
@@ -72,7 +92,7 @@ impl Navbar {
                 .clicked()
             {
                 let selected_bot = utils::config::get_selected_bot();
-                manager.write().unwrap().remove_bot(&selected_bot);
+                manager.read().unwrap().remove_bot(&selected_bot);
             }
         });
     }
@@ -0,0 +1,265 @@
+use super::navbar::Navbar;
+use super::toasts::{self, ToastClick};
+use crate::{
+    manager::{bot_manager::BotManager, notifications, update_check::{self, Release}},
+    types::{enotification_action::ENotificationAction, eseverity::ESeverity},
+    utils::{self, config},
+};
+use eframe::egui::{self, Color32, RichText, Ui};
+use std::sync::{Arc, RwLock};
+use std::thread;
+use std::time::Duration;
+
+const WARP_TIMEOUT: Duration = Duration::from_secs(15);
+
+#[derive(Default)]
+pub struct NotificationsPanel {
+    // Bot waiting for a new 2FA recovery code and the code typed so far
+    two_factor: Option<(String, String)>,
+    // Bot, the world that asked for a password and the password typed so far
+    world_password: Option<(String, String, String)>,
+    // Notes of a newer release, opened from its notification
+    release: Option<Release>,
+}
+
+impl NotificationsPanel {
+    // Turns newly published notifications into toasts, unless do not disturb is on
+    pub fn poll(&mut self) {
+        let fresh = notifications::take_unshown();
+        if config::get_do_not_disturb() {
+            return;
+        }
+        for notification in fresh {
+            toasts::notification(&notification);
+        }
+    }
+
+    pub fn handle_click(
+        &mut self,
+        click: ToastClick,
+        manager: &Arc<RwLock<BotManager>>,
+        navbar: &mut Navbar,
+    ) {
+        match click {
+            ToastClick::Open(id) => open(id, navbar),
+            ToastClick::Action(id) => self.run_action(id, manager),
+        }
+    }
+
+    pub fn render(
+        &mut self,
+        ui: &mut Ui,
+        manager: &Arc<RwLock<BotManager>>,
+        navbar: &mut Navbar,
+    ) {
+        ui.horizontal(|ui| {
+            let mut do_not_disturb = config::get_do_not_disturb();
+            if ui.checkbox(&mut do_not_disturb, "Do not disturb").changed() {
+                config::set_do_not_disturb(do_not_disturb);
+            }
+            ui.separator();
+            if ui.button("Mark all read").clicked() {
+                notifications::mark_all_read();
+            }
+            if ui.button("Clear").clicked() {
+                notifications::clear();
+            }
+        });
+        ui.separator();
+
+        let all = notifications::all();
+        if all.is_empty() {
+            ui.label("No notifications");
+            return;
+        }
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            for notification in all {
+                ui.horizontal(|ui| {
+                    let (icon, color) = match notification.severity {
+                        ESeverity::Info => (egui_remixicon::icons::INFORMATION_FILL, Color32::GRAY),
+                        ESeverity::Warning => {
+                            (egui_remixicon::icons::ALERT_FILL, Color32::from_rgb(255, 193, 7))
+                        }
+                        ESeverity::Critical => (
+                            egui_remixicon::icons::ERROR_WARNING_FILL,
+                            Color32::from_rgb(220, 53, 69),
+                        ),
+                    };
+                    ui.label(RichText::new(icon).color(color));
+                    ui.label(utils::date::format_time(
+                        notification.timestamp,
+                        config::get_stats_utc_offset(),
+                    ));
+                    let mut text = RichText::new(format!("{}: {}", notification.bot, notification.message));
+                    if !notification.read {
+                        text = text.strong();
+                    }
+                    if ui
+                        .add(egui::Label::new(text).sense(egui::Sense::click()))
+                        .on_hover_text("Show this bot")
+                        .clicked()
+                    {
+                        open(notification.id, navbar);
+                    }
+                    if let Some(action) = &notification.action {
+                        if ui.button(action.label()).clicked() {
+                            self.run_action(notification.id, manager);
+                        }
+                    }
+                    if !notification.read && ui.small_button("Mark read").clicked() {
+                        notifications::mark_read(notification.id);
+                    }
+                });
+            }
+        });
+    }
+
+    // The 2FA prompt lives outside the panel so a toast button can open it from anywhere
+    pub fn render_two_factor(&mut self, ctx: &egui::Context, manager: &Arc<RwLock<BotManager>>) {
+        let (bot_name, code) = match &mut self.two_factor {
+            Some(two_factor) => two_factor,
+            None => return,
+        };
+        let mut open = true;
+        let mut submitted = false;
+        egui::Window::new(format!("2FA code for {}", bot_name))
+            .resizable(false)
+            .collapsible(false)
+            .open(&mut open)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("2FA Code");
+                    ui.text_edit_singleline(code);
+                });
+                if ui.button("Save and log in").clicked() && !code.trim().is_empty() {
+                    submitted = true;
+                }
+            });
+        if submitted {
+            let code = code.trim().to_string();
+            config::update_bot_config(bot_name, |bot_config| {
+                bot_config.recovery_code = code.clone();
+            });
+            let manager = manager.read().unwrap();
+            if let Some(bot) = manager.get_bot(bot_name) {
+                bot.info.lock().unwrap().recovery_code = code;
+            }
+            if !manager.restart_bot(bot_name) {
+                toasts::info(&format!("{} is still logging in, the code is used next time", bot_name));
+            }
+        }
+        if submitted || !open {
+            self.two_factor = None;
+        }
+    }
+
+    pub fn render_world_password(&mut self, ctx: &egui::Context, manager: &Arc<RwLock<BotManager>>) {
+        let (bot_name, world, password) = match &mut self.world_password {
+            Some(world_password) => world_password,
+            None => return,
+        };
+        let mut open = true;
+        let mut submitted = false;
+        egui::Window::new(format!("Password for {}", world))
+            .resizable(false)
+            .collapsible(false)
+            .open(&mut open)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Password");
+                    ui.add(egui::TextEdit::singleline(password).password(true));
+                });
+                if ui.button("Save and warp").clicked() && !password.is_empty() {
+                    submitted = true;
+                }
+            });
+        if submitted {
+            config::set_world_password(world, password.clone());
+            if let Some(bot) = manager.read().unwrap().get_bot(bot_name) {
+                let world = world.clone();
+                thread::spawn(move || {
+                    if let Err(err) = bot.warp_and_wait(&world, WARP_TIMEOUT) {
+                        bot.log_warn(&format!("Warp failed: {}", err));
+                    }
+                });
+            }
+        }
+        if submitted || !open {
+            self.world_password = None;
+        }
+    }
+
+    pub fn render_release(&mut self, ctx: &egui::Context) {
+        let release = match &self.release {
+            Some(release) => release,
+            None => return,
+        };
+        let mut open = true;
+        egui::Window::new(format!("Mori {}", release.version))
+            .collapsible(false)
+            .default_width(400.0)
+            .open(&mut open)
+            .show(ctx, |ui| {
+                ui.label(format!("You are running {}", update_check::CURRENT_VERSION));
+                ui.hyperlink_to("Download", &release.url);
+                ui.separator();
+                egui::ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                    ui.label(&release.notes);
+                });
+            });
+        if !open {
+            self.release = None;
+        }
+    }
+
+    fn run_action(&mut self, id: u64, manager: &Arc<RwLock<BotManager>>) {
+        let notification = match notifications::get(id) {
+            Some(notification) => notification,
+            None => return,
+        };
+        notifications::mark_read(id);
+        let action = match notification.action {
+            Some(action) => action,
+            None => return,
+        };
+        match action {
+            ENotificationAction::EnterTwoFactor => {
+                self.two_factor = Some((notification.bot, String::new()));
+                return;
+            }
+            ENotificationAction::EnterWorldPassword { world } => {
+                self.world_password = Some((notification.bot, world, String::new()));
+                return;
+            }
+            ENotificationAction::ShowRelease { .. } => {
+                self.release = update_check::available();
+                return;
+            }
+            _ => {}
+        }
+        let bot = match manager.read().unwrap().get_bot(&notification.bot) {
+            Some(bot) => bot,
+            None => {
+                toasts::error(&format!("{} was removed", notification.bot));
+                return;
+            }
+        };
+        match action {
+            ENotificationAction::PauseBot => bot.pause_automation(),
+            ENotificationAction::OpenTrade { name } => {
+                thread::spawn(move || bot.talk(format!("/trade {}", name)));
+            }
+            ENotificationAction::EnterTwoFactor
+            | ENotificationAction::EnterWorldPassword { .. }
+            | ENotificationAction::ShowRelease { .. } => {}
+        }
+    }
+}
+
+fn open(id: u64, navbar: &mut Navbar) {
+    if let Some(notification) = notifications::get(id) {
+        notifications::mark_read(id);
+        config::set_selected_bot(notification.bot);
+        navbar.current_menu = "bots".to_string();
+    }
+}
@@ -0,0 +1,337 @@
+use crate::core::features::build_pattern;
+use crate::core::Bot;
+use crate::gui::toasts;
+use crate::types::build_pattern::{BuildPattern, MAX_SIZE};
+use crate::utils::{self, color};
+use eframe::egui::{self, Color32, Painter, Rect, Sense};
+use gtitem_r::structs::ItemDatabase;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const CELL_SIZE: f32 = 12.0;
+// The materials the bot is short of are worked out again at most this often
+const SHORTFALL_REFRESH: Duration = Duration::from_secs(2);
+
+// What the bot was short of for a pattern and anchor when last checked
+struct Shortfall {
+    pattern: BuildPattern,
+    anchor: (u32, u32),
+    checked_at: Instant,
+    lines: Vec<String>,
+}
+
+// Pattern being edited from the world map, its top left corner is anchored with a left click
+pub struct PatternDesigner {
+    pub pattern: BuildPattern,
+    pub anchor: Option<(u32, u32)>,
+    // Palette index painted on the grid, 0 erases
+    brush: u8,
+    gap: u32,
+    image_path: String,
+    shortfall: Option<Shortfall>,
+}
+
+impl PatternDesigner {
+    pub fn new() -> Self {
+        PatternDesigner {
+            pattern: BuildPattern::checkerboard("pattern", 8, 8, vec![2, 4]),
+            anchor: None,
+            brush: 1,
+            gap: 1,
+            image_path: String::new(),
+            shortfall: None,
+        }
+    }
+
+    // Where each cell lands on the map, dimmed once the tile already holds its block
+    pub fn draw_overlay(
+        &self,
+        draw_list: &Painter,
+        world: &gtworld_r::World,
+        item_database: &ItemDatabase,
+        tile_rect: &dyn Fn(u32, u32) -> Rect,
+    ) {
+        let anchor = match self.anchor {
+            Some(anchor) => anchor,
+            None => return,
+        };
+        for (x, y, item_id) in self.pattern.tiles(anchor) {
+            let done = world
+                .get_tile(x, y)
+                .map_or(false, |tile| tile.foreground_item_id as u32 == item_id);
+            let [r, g, b, _] = item_color(item_database, item_id).to_array();
+            let alpha = if done { 40 } else { 140 };
+            draw_list.rect_filled(tile_rect(x, y), 0.0, Color32::from_rgba_unmultiplied(r, g, b, alpha));
+        }
+        let (anchor_x, anchor_y) = anchor;
+        let corner = tile_rect(anchor_x, anchor_y);
+        let far = tile_rect(anchor_x + self.pattern.width - 1, anchor_y + self.pattern.height - 1);
+        draw_list.rect_stroke(
+            Rect::from_min_max(corner.min, far.max),
+            0.0,
+            egui::Stroke::new(2.0, Color32::from_rgb(0, 123, 255)),
+        );
+    }
+
+    // Returns false once the window is closed
+    pub fn render(&mut self, ctx: &egui::Context, bot: &Arc<Bot>) -> bool {
+        let mut open = true;
+        egui::Window::new("Pattern designer")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(true)
+            .anchor(egui::Align2::RIGHT_TOP, [-10.0, 10.0])
+            .show(ctx, |ui| {
+                self.render_patterns(ui);
+                ui.separator();
+                self.render_size(ui);
+                ui.separator();
+                self.render_palette(ui, bot);
+                ui.separator();
+                self.render_grid(ui, bot);
+                ui.separator();
+                self.render_build(ui, bot);
+            });
+        open
+    }
+
+    fn render_patterns(&mut self, ui: &mut egui::Ui) {
+        let saved = utils::config::get_build_patterns();
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut self.pattern.name)
+                    .hint_text("Name")
+                    .desired_width(120.0),
+            );
+            egui::ComboBox::from_id_salt("build_patterns")
+                .selected_text("Saved")
+                .show_ui(ui, |ui| {
+                    for pattern in &saved {
+                        if ui.selectable_label(false, &pattern.name).clicked() {
+                            self.pattern = pattern.clone();
+                        }
+                    }
+                });
+            let name = self.pattern.name.trim().to_string();
+            let existing = saved.iter().position(|pattern| pattern.name == name);
+            if ui.add_enabled(!name.is_empty(), egui::Button::new("Save")).clicked() {
+                let mut patterns = saved.clone();
+                self.pattern.name = name;
+                match existing {
+                    Some(index) => patterns[index] = self.pattern.clone(),
+                    None => patterns.push(self.pattern.clone()),
+                }
+                utils::config::set_build_patterns(patterns);
+            }
+            if ui.add_enabled(existing.is_some(), egui::Button::new("Delete")).clicked() {
+                let mut patterns = saved.clone();
+                patterns.retain(|pattern| pattern.name != self.pattern.name.trim());
+                utils::config::set_build_patterns(patterns);
+            }
+        });
+    }
+
+    fn render_size(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            let (mut width, mut height) = (self.pattern.width, self.pattern.height);
+            ui.label("Size");
+            let changed = ui.add(egui::DragValue::new(&mut width).range(1..=MAX_SIZE)).changed()
+                | ui.add(egui::DragValue::new(&mut height).range(1..=MAX_SIZE)).changed();
+            if changed {
+                self.pattern.resize(width, height);
+            }
+            ui.separator();
+            let (name, palette) = (self.pattern.name.clone(), self.pattern.palette.clone());
+            if ui.button("Checkerboard").clicked() {
+                self.pattern = BuildPattern::checkerboard(&name, width, height, palette.clone());
+            }
+            if ui.button("Rows").clicked() {
+                self.pattern = BuildPattern::rows(&name, width, height, self.gap, palette);
+            }
+            ui.add(egui::DragValue::new(&mut self.gap).range(0..=10).prefix("gap "));
+        });
+    }
+
+    fn render_palette(&mut self, ui: &mut egui::Ui, bot: &Arc<Bot>) {
+        let item_database = bot.item_database.read().unwrap();
+        ui.horizontal_wrapped(|ui| {
+            ui.selectable_value(&mut self.brush, 0, "Erase");
+            let mut removed = None;
+            for (index, item_id) in self.pattern.palette.iter_mut().enumerate() {
+                let brush = index as u8 + 1;
+                let name = item_database
+                    .get_item(item_id)
+                    .map_or_else(|| format!("#{}", item_id), |item| item.name.clone());
+                let swatch = egui::RichText::new("■").color(item_color(&item_database, *item_id));
+                ui.selectable_value(&mut self.brush, brush, swatch).on_hover_text(&name);
+                ui.add(egui::DragValue::new(item_id)).on_hover_text(&name);
+                if ui.small_button(egui_remixicon::icons::CLOSE_LINE).clicked() {
+                    removed = Some(index);
+                }
+            }
+            if let Some(index) = removed {
+                self.remove_color(index);
+            }
+            if self.pattern.palette.len() < u8::MAX as usize && ui.button("Add color").clicked() {
+                self.pattern.palette.push(2);
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut self.image_path)
+                    .hint_text("image.png")
+                    .desired_width(160.0),
+            );
+            if ui
+                .button("Import image")
+                .on_hover_text("Matched against the palette's colors, or every block when it's empty")
+                .clicked()
+            {
+                let name = self.pattern.name.clone();
+                match build_pattern::import_image(&self.image_path, &name, &item_database, &self.pattern.palette) {
+                    Ok(pattern) => self.pattern = pattern,
+                    Err(err) => toasts::error(&err),
+                }
+            }
+        });
+    }
+
+    // Cells after the removed color shift down one index with the palette
+    fn remove_color(&mut self, index: usize) {
+        self.pattern.palette.remove(index);
+        let removed = index as u8 + 1;
+        for cell in self.pattern.cells.iter_mut() {
+            if *cell == removed {
+                *cell = 0;
+            } else if *cell > removed {
+                *cell -= 1;
+            }
+        }
+        self.brush = self.brush.min(self.pattern.palette.len() as u8);
+    }
+
+    fn render_grid(&mut self, ui: &mut egui::Ui, bot: &Arc<Bot>) {
+        let item_database = bot.item_database.read().unwrap();
+        egui::ScrollArea::both()
+            .id_salt("pattern_grid")
+            .max_height(300.0)
+            .show(ui, |ui| {
+                let size = egui::vec2(
+                    self.pattern.width as f32 * CELL_SIZE,
+                    self.pattern.height as f32 * CELL_SIZE,
+                );
+                let (rect, response) = ui.allocate_exact_size(size, Sense::click_and_drag());
+                let painter = ui.painter_at(rect);
+                for y in 0..self.pattern.height {
+                    for x in 0..self.pattern.width {
+                        let cell = Rect::from_min_size(
+                            rect.min + egui::vec2(x as f32 * CELL_SIZE, y as f32 * CELL_SIZE),
+                            egui::vec2(CELL_SIZE, CELL_SIZE),
+                        );
+                        let fill = match self.pattern.item_at(x, y) {
+                            Some(item_id) => item_color(&item_database, item_id),
+                            None => Color32::from_gray(40),
+                        };
+                        painter.rect_filled(cell.shrink(0.5), 0.0, fill);
+                    }
+                }
+                if response.clicked() || response.dragged() {
+                    if let Some(pointer) = response.interact_pointer_pos() {
+                        let offset = pointer - rect.min;
+                        let (x, y) = ((offset.x / CELL_SIZE) as u32, (offset.y / CELL_SIZE) as u32);
+                        self.pattern.set(x, y, self.brush);
+                    }
+                }
+            });
+    }
+
+    fn render_build(&mut self, ui: &mut egui::Ui, bot: &Arc<Bot>) {
+        match self.anchor {
+            Some((x, y)) => ui.label(format!("Top left corner at {},{}", x, y)),
+            None => ui.label(egui::RichText::new("Click a tile on the map to anchor the top left corner").weak()),
+        };
+        if let Some(anchor) = self.anchor {
+            for line in &self.shortfall(bot, anchor).lines {
+                ui.colored_label(Color32::from_rgb(255, 193, 7), line);
+            }
+        }
+
+        ui.horizontal(|ui| {
+            if build_pattern::is_running(bot) {
+                if ui.button("Stop").clicked() {
+                    build_pattern::stop(bot);
+                }
+                if let Some((done, total)) = bot.temporary_data.read().unwrap().build_progress {
+                    ui.label(format!("{}/{}", done, total));
+                }
+                return;
+            }
+            if ui.add_enabled(self.anchor.is_some(), egui::Button::new("Build")).clicked() {
+                let (bot, pattern, anchor) = (bot.clone(), self.pattern.clone(), self.anchor.unwrap_or_default());
+                thread::spawn(move || {
+                    if let Err(err) = build_pattern::run(&bot, pattern, anchor) {
+                        toasts::error(&format!("Pattern build stopped: {}", err));
+                    }
+                });
+            }
+            if let Some(checkpoint) = build_pattern::checkpoint(bot) {
+                let label = format!(
+                    "Resume {} ({}/{})",
+                    checkpoint.pattern.name,
+                    checkpoint.done,
+                    checkpoint.pattern.tiles(checkpoint.anchor).len()
+                );
+                if ui.button(label).on_hover_text(format!("In {}", checkpoint.world)).clicked() {
+                    let bot = bot.clone();
+                    thread::spawn(move || {
+                        if let Err(err) = build_pattern::resume(&bot) {
+                            toasts::error(&format!("Pattern build stopped: {}", err));
+                        }
+                    });
+                }
+            }
+        });
+    }
+
+    // Checked again once the pattern or anchor changes or SHORTFALL_REFRESH has passed, the
+    // world and inventory behind it aren't walked every frame
+    fn shortfall(&mut self, bot: &Bot, anchor: (u32, u32)) -> &Shortfall {
+        let stale = self.shortfall.as_ref().map_or(true, |shortfall| {
+            shortfall.anchor != anchor
+                || shortfall.pattern != self.pattern
+                || shortfall.checked_at.elapsed() >= SHORTFALL_REFRESH
+        });
+        if stale {
+            let missing = build_pattern::missing_materials(bot, &self.pattern.tiles(anchor));
+            let item_database = bot.item_database.read().unwrap();
+            let lines = missing
+                .iter()
+                .map(|(item_id, amount)| {
+                    let name = item_database
+                        .get_item(item_id)
+                        .map_or_else(|| format!("#{}", item_id), |item| item.name.clone());
+                    format!("Short {} {}", amount, name)
+                })
+                .collect();
+            self.shortfall = Some(Shortfall {
+                pattern: self.pattern.clone(),
+                anchor,
+                checked_at: Instant::now(),
+                lines,
+            });
+        }
+        self.shortfall.as_ref().unwrap()
+    }
+}
+
+// The block's base color, gray for items without one
+fn item_color(item_database: &ItemDatabase, item_id: u32) -> Color32 {
+    match item_database.get_item(&item_id) {
+        Some(item) if item.base_color != 0 => {
+            let (b, g, r, _) = color::extract_bgra(item.base_color);
+            Color32::from_rgb(r, g, b)
+        }
+        _ => Color32::GRAY,
+    }
+}
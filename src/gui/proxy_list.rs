@@ -1,57 +1,157 @@
+use std::collections::HashMap;
 use std::sync::{Arc, RwLock};
 use eframe::egui::{self, Ui};
-use paris::info;
 use crate::gui::add_proxy_dialog::AddProxyDialog;
-use crate::manager::proxy_manager::ProxyManager;
+use crate::manager::proxy_manager::{self, ProxyManager};
+use crate::types::config::ProxyPool;
+use crate::utils::config;
 
 #[derive(Default)]
-pub struct ProxyList {}
+pub struct ProxyList {
+    pub new_pool: String,
+    // Comma separated group lists being edited, per pool
+    pub pool_groups: HashMap<String, String>,
+}
 
 impl ProxyList {
     pub fn render(&mut self, ui: &mut Ui, proxy_manager: &Arc<RwLock<ProxyManager>>, add_proxy_dialog: &mut AddProxyDialog, _ctx: &egui::Context) {
         ui.vertical(|ui| {
-            if ui.button("Add proxy").clicked() {
-                add_proxy_dialog.open = true;
-            }
+            ui.horizontal(|ui| {
+                if ui.button("Add proxy").clicked() {
+                    add_proxy_dialog.open = true;
+                }
+                ui.separator();
+                let mut never_direct = config::get_never_connect_direct();
+                if ui
+                    .checkbox(&mut never_direct, "Never connect direct")
+                    .on_hover_text("Bots whose pool is full stay offline instead of using this machine's address")
+                    .changed()
+                {
+                    config::set_never_connect_direct(never_direct);
+                }
+                ui.separator();
+                ui.add(egui::TextEdit::singleline(&mut self.new_pool).hint_text("new pool").desired_width(100.0));
+                if ui.button("Add pool").clicked() && !self.new_pool.trim().is_empty() {
+                    let name = self.new_pool.trim().to_string();
+                    update_pool(&name, |_| {});
+                    self.new_pool.clear();
+                }
+            });
             ui.separator();
-            egui::Grid::new("proxy_list_grid")
-                .min_col_width(ui.available_width() / 5.0)
-                .striped(true)
-                .show(ui, |ui| {
-                    ui.label("IP");
-                    ui.label("Port");
-                    ui.label("Username");
-                    ui.label("Password");
-                    ui.label("Status");
-                    ui.end_row();
-                    let proxies = {
-                        let proxy_manager = proxy_manager.read().unwrap();
-                        proxy_manager.proxies.clone()
-                    };
-                    for (index, proxy_data) in proxies.iter().enumerate() {
-                        let proxy = &proxy_data.proxy;
-                        ui.label(proxy.ip.to_string());
-                        ui.label(proxy.port.to_string());
-                        ui.label(proxy.username.to_string());
-                        ui.label(proxy.password.to_string());
-                        let response = ui.group(|ui| {
-                            ui.label(proxy_data.status.to_string());
-                        }).response;
-                        ui.end_row();
 
-                        response.context_menu(|ui| {
-                            if ui.button("Test").clicked() {
-                                proxy_manager.write().unwrap().test(index);
-                                ui.close_menu();
-                            }
-                            if ui.button("Remove").clicked() {
-                                proxy_manager.write().unwrap().remove(index);
-                                ui.close_menu();
-                            }
+            let (proxies, usage) = {
+                let proxy_manager = proxy_manager.read().unwrap();
+                (proxy_manager.proxies.clone(), proxy_manager.usage())
+            };
+            let pool_names: Vec<String> = usage.iter().map(|pool| pool.name.clone()).collect();
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                for pool in usage {
+                    let header = format!(
+                        "{}: {} proxies, {}/{} bots",
+                        pool.name, pool.proxies, pool.bots, pool.capacity
+                    );
+                    egui::CollapsingHeader::new(header)
+                        .id_salt(("proxy_pool", &pool.name))
+                        .default_open(true)
+                        .show(ui, |ui| {
+                            self.render_pool_settings(ui, &pool.name);
+                            egui::Grid::new(("proxy_list_grid", &pool.name))
+                                .min_col_width(ui.available_width() / 6.0)
+                                .striped(true)
+                                .show(ui, |ui| {
+                                    ui.label("IP");
+                                    ui.label("Port");
+                                    ui.label("Username");
+                                    ui.label("Password");
+                                    ui.label("Bots");
+                                    ui.label("Status");
+                                    ui.end_row();
+                                    let members = proxies
+                                        .iter()
+                                        .enumerate()
+                                        .filter(|(_, proxy)| proxy_manager::pool_name(&proxy.proxy.pool) == pool.name);
+                                    for (index, proxy_data) in members {
+                                        let proxy = &proxy_data.proxy;
+                                        ui.label(proxy.ip.to_string());
+                                        ui.label(proxy.port.to_string());
+                                        ui.label(proxy.username.to_string());
+                                        ui.label(proxy.password.to_string());
+                                        ui.label(proxy_data.whos_using.len().to_string())
+                                            .on_hover_text(proxy_data.whos_using.join(", "));
+                                        let response = ui.group(|ui| {
+                                            ui.label(proxy_data.status.to_string());
+                                        }).response;
+                                        ui.end_row();
+
+                                        response.context_menu(|ui| {
+                                            if ui.button("Test").clicked() {
+                                                proxy_manager.write().unwrap().test(index);
+                                                ui.close_menu();
+                                            }
+                                            ui.menu_button("Move to pool", |ui| {
+                                                for name in pool_names.iter().filter(|name| **name != pool.name) {
+                                                    if ui.button(name).clicked() {
+                                                        proxy_manager.write().unwrap().set_pool(index, name);
+                                                        ui.close_menu();
+                                                    }
+                                                }
+                                            });
+                                            if ui.button("Remove").clicked() {
+                                                proxy_manager.write().unwrap().remove(index);
+                                                ui.close_menu();
+                                            }
+                                        });
+                                    }
+                                });
                         });
-                    }
-                });
+                }
+            });
+        });
+    }
+
+    fn render_pool_settings(&mut self, ui: &mut Ui, name: &str) {
+        let settings = proxy_manager::pool_settings(name);
+        ui.horizontal(|ui| {
+            let mut max_bots = settings.max_bots_per_proxy;
+            if ui
+                .add(egui::Slider::new(&mut max_bots, 1..=10).text("bots per proxy"))
+                .changed()
+            {
+                update_pool(name, |pool| pool.max_bots_per_proxy = max_bots);
+            }
+            let groups = self
+                .pool_groups
+                .entry(name.to_string())
+                .or_insert_with(|| settings.groups.join(", "));
+            ui.label("Groups");
+            if ui
+                .add(egui::TextEdit::singleline(groups).hint_text("farmers, mains").desired_width(150.0))
+                .lost_focus()
+            {
+                let groups: Vec<String> = groups
+                    .split(',')
+                    .map(|group| group.trim().to_string())
+                    .filter(|group| !group.is_empty())
+                    .collect();
+                update_pool(name, |pool| pool.groups = groups);
+            }
         });
     }
 }
 
+// Adds the pool to the config when it only existed implicitly so far
+fn update_pool(name: &str, change: impl FnOnce(&mut ProxyPool)) {
+    let mut pools = config::get_proxy_pools();
+    let index = match pools.iter().position(|pool| proxy_manager::pool_name(&pool.name) == name) {
+        Some(index) => index,
+        None => {
+            pools.push(ProxyPool {
+                name: name.to_string(),
+                ..Default::default()
+            });
+            pools.len() - 1
+        }
+    };
+    change(&mut pools[index]);
+    config::set_proxy_pools(pools);
+}
@@ -0,0 +1,190 @@
+use crate::core::{supervisor, Bot};
+use crate::lua_sandbox;
+use eframe::egui::{self, Color32, RichText, Ui};
+use std::collections::HashMap;
+use std::sync::mpsc::{self, RecvTimeoutError, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+const MAX_SCROLLBACK: usize = 500;
+// How often an idle worker checks whether its bot was stopped
+const IDLE_CHECK: Duration = Duration::from_secs(1);
+
+#[derive(Clone)]
+enum ReplLine {
+    Input(String),
+    Output(String),
+    Error(String),
+}
+
+#[derive(Default)]
+struct Session {
+    input: String,
+    history: Vec<String>,
+    // Position while browsing the history with the arrow keys, None when editing a new chunk
+    history_index: Option<usize>,
+    // Appended to by the evaluation thread
+    scrollback: Arc<Mutex<Vec<ReplLine>>>,
+    running: Arc<Mutex<bool>>,
+    // Chunks for the bot's REPL worker, started on the first submit
+    worker: Option<SyncSender<String>>,
+}
+
+#[derive(Default)]
+pub struct Repl {
+    sessions: HashMap<String, Session>,
+}
+
+impl Repl {
+    pub fn render(&mut self, ui: &mut Ui, bot_name: &str, bot: &Arc<Bot>) {
+        let session = self.sessions.entry(bot_name.to_string()).or_default();
+        let running = *session.running.lock().unwrap();
+        ui.horizontal(|ui| {
+            ui.label(RichText::new("Enter runs, Shift+Enter adds a line, Up/Down walk the history").weak());
+            if ui.button("Clear").clicked() {
+                session.scrollback.lock().unwrap().clear();
+            }
+            if running {
+                ui.spinner();
+            }
+        });
+        ui.separator();
+
+        let input_height = 70.0;
+        egui::ScrollArea::vertical()
+            .max_height(ui.available_height() - input_height)
+            .auto_shrink([false, false])
+            .stick_to_bottom(true)
+            .show(ui, |ui| {
+                for line in session.scrollback.lock().unwrap().iter() {
+                    match line {
+                        ReplLine::Input(chunk) => {
+                            ui.label(RichText::new(format!("> {}", chunk)).monospace().weak());
+                        }
+                        ReplLine::Output(output) => {
+                            ui.label(RichText::new(output).monospace());
+                        }
+                        ReplLine::Error(err) => {
+                            ui.label(
+                                RichText::new(err)
+                                    .monospace()
+                                    .color(Color32::from_rgb(220, 53, 69)),
+                            );
+                        }
+                    }
+                }
+            });
+        ui.separator();
+
+        let id = ui.make_persistent_id(("repl_input", bot_name));
+        let mut submit = false;
+        if ui.memory(|memory| memory.has_focus(id)) {
+            // consume_key ignores Shift when matching, so Shift+Enter has to be ruled out first
+            submit = ui.input_mut(|input| {
+                !input.modifiers.shift && input.consume_key(egui::Modifiers::NONE, egui::Key::Enter)
+            });
+            // Only single-line chunks browse the history, multi-line ones need the arrows to move
+            if !session.input.contains('\n') {
+                if ui.input_mut(|input| input.consume_key(egui::Modifiers::NONE, egui::Key::ArrowUp)) {
+                    session.browse(true);
+                }
+                if ui.input_mut(|input| input.consume_key(egui::Modifiers::NONE, egui::Key::ArrowDown)) {
+                    session.browse(false);
+                }
+            }
+        }
+        ui.add(
+            egui::TextEdit::multiline(&mut session.input)
+                .id(id)
+                .code_editor()
+                .desired_rows(2)
+                .desired_width(f32::INFINITY)
+                .hint_text("bot:say(\"hello\")"),
+        );
+        if submit && !running && !session.input.trim().is_empty() {
+            session.submit(bot);
+        }
+    }
+}
+
+impl Session {
+    fn browse(&mut self, back: bool) {
+        if self.history.is_empty() {
+            return;
+        }
+        let index = match (self.history_index, back) {
+            (None, true) => Some(self.history.len() - 1),
+            (None, false) => None,
+            (Some(index), true) => Some(index.saturating_sub(1)),
+            (Some(index), false) if index + 1 < self.history.len() => Some(index + 1),
+            (Some(_), false) => None,
+        };
+        self.history_index = index;
+        self.input = index.map(|index| self.history[index].clone()).unwrap_or_default();
+    }
+
+    fn submit(&mut self, bot: &Arc<Bot>) {
+        let chunk = std::mem::take(&mut self.input);
+        if self.history.last() != Some(&chunk) {
+            self.history.push(chunk.clone());
+        }
+        self.history_index = None;
+        push(&self.scrollback, ReplLine::Input(chunk.clone()));
+
+        *self.running.lock().unwrap() = true;
+        let chunk = match self.worker.as_ref().map(|worker| worker.try_send(chunk)) {
+            None => chunk,
+            Some(Ok(())) => return,
+            // The worker ended with its bot, a new one takes the chunk
+            Some(Err(TrySendError::Disconnected(chunk))) => chunk,
+            // render only submits once `running` cleared, so this is never expected
+            Some(Err(TrySendError::Full(_))) => {
+                push(&self.scrollback, ReplLine::Error("Still running the previous chunk".to_string()));
+                return;
+            }
+        };
+        let worker = self.start_worker(bot);
+        if worker.send(chunk).is_err() {
+            *self.running.lock().unwrap() = false;
+        }
+        self.worker = Some(worker);
+    }
+
+    // One supervised worker per bot evaluates every chunk in order, chunks may call
+    // bot.sleep or walk so they can't run on the UI thread. It ends once the bot stops
+    fn start_worker(&self, bot: &Arc<Bot>) -> SyncSender<String> {
+        let (sender, receiver) = mpsc::sync_channel::<String>(1);
+        let receiver = Mutex::new(receiver);
+        let scrollback = self.scrollback.clone();
+        let running = self.running.clone();
+        supervisor::spawn_once(bot, "repl", move |bot| {
+            let receiver = receiver.lock().unwrap();
+            loop {
+                let chunk = match receiver.recv_timeout(IDLE_CHECK) {
+                    Ok(chunk) => chunk,
+                    Err(RecvTimeoutError::Timeout) if bot.state.lock().unwrap().is_running => continue,
+                    Err(_) => return,
+                };
+                let line = match lua_sandbox::eval_repl(bot, &chunk) {
+                    Ok(output) if output.is_empty() => None,
+                    Ok(output) => Some(ReplLine::Output(output)),
+                    Err(err) => Some(ReplLine::Error(err)),
+                };
+                if let Some(line) = line {
+                    push(&scrollback, line);
+                }
+                *running.lock().unwrap() = false;
+            }
+        });
+        sender
+    }
+}
+
+fn push(scrollback: &Mutex<Vec<ReplLine>>, line: ReplLine) {
+    let mut scrollback = scrollback.lock().unwrap();
+    scrollback.push(line);
+    if scrollback.len() > MAX_SCROLLBACK {
+        let excess = scrollback.len() - MAX_SCROLLBACK;
+        scrollback.drain(..excess);
+    }
+}
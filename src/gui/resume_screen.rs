@@ -0,0 +1,61 @@
+use crate::manager::session;
+use eframe::egui;
+use std::path::Path;
+
+// Asks for the passphrase of a session file given with --resume whose secrets are sealed.
+// The release build has no console to type it into
+pub struct ResumeScreen {
+    path: String,
+    passphrase: String,
+    error: Option<String>,
+}
+
+impl ResumeScreen {
+    pub fn new(path: String) -> Self {
+        ResumeScreen {
+            path,
+            passphrase: String::new(),
+            error: None,
+        }
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    // Some once the user chose: the passphrase to resume with, or None to log in normally
+    pub fn render(&mut self, ctx: &egui::Context) -> Option<Option<String>> {
+        let mut choice = None;
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.heading("Resume session");
+            ui.label(format!("{} holds sealed tokens, enter its passphrase to resume the bots in it.", self.path));
+            ui.separator();
+            let response = ui.add(
+                egui::TextEdit::singleline(&mut self.passphrase)
+                    .password(true)
+                    .hint_text("passphrase"),
+            );
+            let submitted = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+            if let Some(err) = &self.error {
+                ui.colored_label(egui::Color32::from_rgb(220, 53, 69), err);
+            }
+            ui.horizontal(|ui| {
+                if ui.button("Resume").clicked() || submitted {
+                    let passphrase = std::mem::take(&mut self.passphrase);
+                    match session::read(Path::new(&self.path), Some(&passphrase)) {
+                        Ok(_) => choice = Some(Some(passphrase)),
+                        Err(err) => self.error = Some(err),
+                    }
+                }
+                if ui
+                    .button("Log in normally")
+                    .on_hover_text("Every bot goes through the full login")
+                    .clicked()
+                {
+                    choice = Some(None);
+                }
+            });
+        });
+        choice
+    }
+}
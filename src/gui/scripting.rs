@@ -1,13 +1,19 @@
+use crate::core::{script_guard, supervisor};
+use crate::lua_sandbox;
 use crate::manager::bot_manager::BotManager;
 use crate::utils;
 use eframe::egui::{self, Ui};
 use std::sync::{Arc, RwLock};
-use std::thread;
 
 #[derive(Default)]
 pub struct Scripting {
     pub selected_bot: String,
     pub code: String,
+    pub script_name: String,
+    pub dry_run: bool,
+    // Comma separated ids for `protected_bot`, reloaded when another bot is selected
+    pub protected_items: String,
+    pub protected_bot: String,
 }
 
 impl Scripting {
@@ -21,28 +27,16 @@ impl Scripting {
                 )
                 .clicked()
             {
-                let bot = {
-                    let manager = manager.read().unwrap();
-
-                    match manager.get_bot(&self.selected_bot) {
-                        Some(bot) => Some(bot.clone()),
-                        None => None,
-                    }
-                };
+                let bot = manager.read().unwrap().get_bot(&self.selected_bot);
                 if let Some(bot) = bot {
-                    let bot_clone = bot.clone();
                     let code = self.code.clone();
-                    thread::spawn(move || {
-                        let lua = bot_clone.lua.lock();
-                        match lua {
-                            Ok(lua) => {
-                                lua.load(&code).exec().unwrap();
-                            }
-                            Err(poisoned) => {
-                                let lua = poisoned.into_inner();
-                                lua.load(&code).exec().unwrap();
-                            }
-                        }
+                    let script_name = if self.script_name.trim().is_empty() {
+                        "default".to_string()
+                    } else {
+                        self.script_name.trim().to_string()
+                    };
+                    supervisor::spawn_once(&bot, "script", move |bot| {
+                        lua_sandbox::run_script(bot, &script_name, &code);
                     });
                 }
             }
@@ -53,6 +47,13 @@ impl Scripting {
                 )
                 .clicked()
             {}
+            ui.add(
+                egui::TextEdit::singleline(&mut self.script_name)
+                    .hint_text("default")
+                    .desired_width(100.0),
+            )
+            .on_hover_text("Script name, keeps storage keys separate between scripts");
+            self.render_safety(ui, manager);
         });
         egui::ScrollArea::vertical().show(ui, |ui| {
             let theme =
@@ -78,4 +79,72 @@ impl Scripting {
             );
         });
     }
+
+    fn render_safety(&mut self, ui: &mut Ui, manager: &Arc<RwLock<BotManager>>) {
+        self.dry_run = utils::config::get_script_dry_run();
+        if ui
+            .checkbox(&mut self.dry_run, "Dry-run")
+            .on_hover_text("Drop, trash and punch are only logged, nothing is sent")
+            .changed()
+        {
+            utils::config::set_script_dry_run(self.dry_run);
+        }
+        if self.dry_run {
+            ui.colored_label(egui::Color32::from_rgb(255, 193, 7), "Dry-run on, scripts change nothing");
+        }
+        let bot = match manager.read().unwrap().get_bot(&self.selected_bot) {
+            Some(bot) => bot,
+            None => return,
+        };
+        if self.protected_bot != self.selected_bot {
+            self.protected_items = script_guard::protected_items(&bot)
+                .iter()
+                .map(|id| id.to_string())
+                .collect::<Vec<_>>()
+                .join(", ");
+            self.protected_bot = self.selected_bot.clone();
+        }
+        egui::CollapsingHeader::new("Protected items")
+            .id_salt("script_protected_items")
+            .show(ui, |ui| {
+                ui.label(egui::RichText::new("Scripts can't trash or drop these, trashing them is refused everywhere").weak());
+                ui.horizontal(|ui| {
+                    ui.add(egui::TextEdit::singleline(&mut self.protected_items).hint_text("242, 1796"));
+                    if ui.button("Save").clicked() {
+                        let ids: Vec<u32> = self
+                            .protected_items
+                            .split(',')
+                            .filter_map(|id| id.trim().parse().ok())
+                            .collect();
+                        utils::config::set_bot_protected_items(&self.selected_bot, ids);
+                    }
+                    if ui.button("Defaults").clicked() {
+                        utils::config::set_bot_protected_items(&self.selected_bot, script_guard::DEFAULT_PROTECTED.to_vec());
+                        self.protected_bot.clear();
+                    }
+                });
+            });
+        let actions: Vec<script_guard::ScriptAction> = bot.script_actions.lock().unwrap().iter().cloned().collect();
+        egui::CollapsingHeader::new(format!("Action log ({})", actions.len()))
+            .id_salt("script_action_log")
+            .show(ui, |ui| {
+                if ui.small_button("Clear").clicked() {
+                    bot.script_actions.lock().unwrap().clear();
+                }
+                let offset = utils::config::get_stats_utc_offset();
+                egui::ScrollArea::vertical()
+                    .id_salt("script_actions")
+                    .max_height(150.0)
+                    .show(ui, |ui| {
+                        for action in actions.iter().rev() {
+                            let text = format!("{} {}", utils::date::format_time(action.time, offset), action.description);
+                            if action.dry_run {
+                                ui.label(egui::RichText::new(format!("{} (dry-run)", text)).weak());
+                            } else {
+                                ui.label(text);
+                            }
+                        }
+                    });
+            });
+    }
 }
@@ -1,22 +1,173 @@
 use crate::{
-    types::config::Theme,
-    utils::{captcha::CaptchaProvider, config},
+    gui::toasts,
+    core::value_table,
+    manager::{bot_manager::BotManager, bot_template, login_schedule},
+    types::{
+        config::{BotTemplate, Config, LoginWindow, Theme, WorldPassword},
+        edelay_distribution::EDelayDistribution,
+        ehumanizer_preset::EHumanizerPreset,
+        elogin_method::ELoginMethod,
+        ereaction::EReaction,
+    },
+    utils::{captcha::CaptchaProvider, config, date, secrets},
 };
 use eframe::egui::{self, Ui};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::thread;
 
 #[derive(Default)]
 pub struct Settings {
     pub use_alternate: bool,
     pub auto_collect: bool,
+    pub telemetry: bool,
+    pub check_updates: bool,
+    pub auto_reconsume: bool,
+    pub developer_mode: bool,
+    pub auto_fix_login_info: bool,
+    pub trusted_scripts: bool,
+    pub debug_logs: bool,
+    pub script_memory_limit: u32,
+    pub script_cpu_budget: u64,
+    pub auto_reply: bool,
+    pub auto_reply_message: String,
+    pub auto_reply_cooldown: u32,
+    pub chat_cooldown: u64,
+    pub forward_whispers: bool,
+    pub world_summary_webhook: bool,
+    pub transfer_webhook: bool,
+    pub farmable_item: u32,
+    pub webhook_url: String,
+    pub friends: String,
     pub theme: Theme,
     pub timeout_delay: u32,
+    pub connect_timeout: u64,
+    pub stall_timeout: u64,
+    pub idle_heartbeat: u64,
     pub findpath_delay: u32,
+    pub movement_calibration: bool,
+    pub server_ip: String,
+    pub server_port: u16,
+    pub server_data_fallback_attempts: u32,
+    pub server_data_max_age_hours: u32,
+    pub door_walk_off: i32,
+    pub path_tolerance: u32,
+    pub path_segment_max: u32,
+    pub humanizer_distribution: EDelayDistribution,
+    pub walk_to_activate: bool,
+    pub auto_onboarding: bool,
+    pub onboarding_age: u32,
+    pub onboarding_consent: bool,
+    pub bandwidth_cap_mb: u32,
+    pub drop_despawn_secs: u64,
+    pub resync_warn_count: u32,
+    pub session_max_age: u64,
+    // Export target and optional passphrase, not saved to the config
+    pub session_path: String,
+    pub session_passphrase: String,
+    pub inventory_free_threshold: u32,
+    pub collect_threshold_wl: f32,
+    pub value_sync_url: String,
+    // Manual world lock values, kept in the value table rather than the config
+    pub value_overrides: Vec<(u32, f32)>,
+    pub new_value_override: (u32, f32),
+    pub world_passwords: Vec<WorldPassword>,
+    pub new_world_password: WorldPassword,
+    pub show_world_passwords: bool,
+    pub login_windows: Vec<LoginWindow>,
+    pub new_login_window: LoginWindow,
+    pub bot_templates: Vec<BotTemplate>,
+    pub new_template_name: String,
+    // Template waiting on the re-apply confirmation
+    pub reapply_template: Option<String>,
+    // Bots added from each template, by lowercased template name
+    pub template_users: HashMap<String, Vec<String>>,
+    pub stats_utc_offset: i32,
+    pub daily_reset_hour: u32,
     pub captcha_provider: CaptchaProvider,
     pub captcha_api_key: String,
+    // New config passphrase typed twice, not saved to the config
+    pub config_passphrase: String,
+    pub config_passphrase_confirm: String,
 }
 
 impl Settings {
-    pub fn render(&mut self, ui: &mut Ui, ctx: &egui::Context) {
+    pub fn from_config(config: &Config) -> Self {
+        Settings {
+            use_alternate: config.use_alternate_server,
+            auto_collect: config.auto_collect,
+            telemetry: config.telemetry,
+            check_updates: config.check_updates,
+            auto_reconsume: config.auto_reconsume,
+            developer_mode: config.developer_mode,
+            auto_fix_login_info: config.auto_fix_login_info,
+            trusted_scripts: config.trusted_scripts,
+            debug_logs: config.debug_logs,
+            script_memory_limit: config.script_memory_limit,
+            script_cpu_budget: config.script_cpu_budget,
+            auto_reply: config.auto_reply,
+            auto_reply_message: config.auto_reply_message.clone(),
+            auto_reply_cooldown: config.auto_reply_cooldown,
+            chat_cooldown: config.chat_cooldown,
+            forward_whispers: config.forward_whispers,
+            world_summary_webhook: config.world_summary_webhook,
+            transfer_webhook: config.transfer_webhook,
+            farmable_item: config.farmable_item,
+            webhook_url: config.webhook_url.clone(),
+            friends: config.friends.join(", "),
+            theme: config.theme.clone(),
+            timeout_delay: config.timeout,
+            connect_timeout: config.connect_timeout,
+            stall_timeout: config.stall_timeout,
+            idle_heartbeat: config.idle_heartbeat,
+            findpath_delay: config.findpath_delay,
+            movement_calibration: config.movement_calibration,
+            server_ip: config.server_ip.clone(),
+            server_port: config.server_port,
+            server_data_fallback_attempts: config.server_data_fallback_attempts,
+            server_data_max_age_hours: config.server_data_max_age_hours,
+            door_walk_off: config.door_walk_off,
+            path_tolerance: config.path_tolerance,
+            path_segment_max: config.path_segment_max,
+            humanizer_distribution: config.humanizer_distribution,
+            walk_to_activate: config.walk_to_activate,
+            auto_onboarding: config.auto_onboarding,
+            onboarding_age: config.onboarding_age,
+            onboarding_consent: config.onboarding_consent,
+            bandwidth_cap_mb: config.bandwidth_cap_mb,
+            drop_despawn_secs: config.drop_despawn_secs,
+            resync_warn_count: config.resync_warn_count,
+            session_max_age: config.session_max_age,
+            session_path: "session.json".to_string(),
+            session_passphrase: String::new(),
+            inventory_free_threshold: config.inventory_free_threshold,
+            collect_threshold_wl: config.collect_threshold_wl,
+            value_sync_url: config.value_sync_url.clone(),
+            value_overrides: {
+                let mut overrides: Vec<(u32, f32)> = value_table::get().overrides.clone().into_iter().collect();
+                overrides.sort_by_key(|(item_id, _)| *item_id);
+                overrides
+            },
+            new_value_override: (0, 0.0),
+            world_passwords: config.world_passwords.clone(),
+            new_world_password: WorldPassword::default(),
+            show_world_passwords: false,
+            login_windows: config.login_windows.clone(),
+            new_login_window: LoginWindow::default(),
+            bot_templates: config.bot_templates.clone(),
+            new_template_name: String::new(),
+            reapply_template: None,
+            template_users: bot_template::users(config),
+            stats_utc_offset: config.stats_utc_offset,
+            daily_reset_hour: config.daily_reset_hour,
+            captcha_provider: config.captcha.provider.clone(),
+            captcha_api_key: config.captcha.api_key.clone(),
+            config_passphrase: String::new(),
+            config_passphrase_confirm: String::new(),
+        }
+    }
+
+    pub fn render(&mut self, ui: &mut Ui, ctx: &egui::Context, manager: &Arc<RwLock<BotManager>>) {
         egui::Grid::new("settings_grid")
             .num_columns(2)
             .spacing([0.0, 20.0])
@@ -29,12 +180,130 @@ impl Settings {
                     {
                         config::set_use_alternate_server(self.use_alternate);
                     }
+                    ui.horizontal(|ui| {
+                        ui.label("Server override");
+                        if ui
+                            .add(
+                                egui::TextEdit::singleline(&mut self.server_ip)
+                                    .hint_text("ip")
+                                    .desired_width(110.0),
+                            )
+                            .on_hover_text("Connect here without fetching server_data.php, leave empty to fetch it")
+                            .changed()
+                        {
+                            config::set_server_ip(self.server_ip.trim().to_string());
+                        }
+                        if ui
+                            .add(egui::DragValue::new(&mut self.server_port).prefix("port "))
+                            .changed()
+                        {
+                            config::set_server_port(self.server_port);
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Cached server data");
+                        let attempts = ui
+                            .add(
+                                egui::DragValue::new(&mut self.server_data_fallback_attempts)
+                                    .range(0..=100)
+                                    .prefix("after ")
+                                    .suffix(" failures"),
+                            )
+                            .on_hover_text("Log in with the last server_data.php response once fetching it failed this often, 0 to keep retrying");
+                        if attempts.drag_stopped() || attempts.lost_focus() {
+                            config::set_server_data_fallback_attempts(self.server_data_fallback_attempts);
+                        }
+                        let max_age = ui
+                            .add(
+                                egui::DragValue::new(&mut self.server_data_max_age_hours)
+                                    .range(1..=720)
+                                    .prefix("up to ")
+                                    .suffix(" h old"),
+                            )
+                            .on_hover_text("Older cached responses are ignored, also for the server override");
+                        if max_age.drag_stopped() || max_age.lost_focus() {
+                            config::set_server_data_max_age_hours(self.server_data_max_age_hours);
+                        }
+                    });
                     if ui
                         .checkbox(&mut self.auto_collect, "Use auto collect")
                         .changed()
                     {
                         config::set_auto_collect(self.auto_collect);
                     }
+                    if ui
+                        .checkbox(&mut self.telemetry, "Send telemetry")
+                        .changed()
+                    {
+                        config::set_telemetry(self.telemetry);
+                    }
+                    if ui
+                        .checkbox(&mut self.check_updates, "Check for updates")
+                        .on_hover_text("Looks for a newer release on GitHub at startup and once a day")
+                        .changed()
+                    {
+                        config::set_check_updates(self.check_updates);
+                    }
+                    if ui
+                        .checkbox(&mut self.auto_reconsume, "Auto re-consume items")
+                        .on_hover_text("Consumable durations are read from the consumables list in config.json")
+                        .changed()
+                    {
+                        config::set_auto_reconsume(self.auto_reconsume);
+                    }
+                    if ui
+                        .checkbox(&mut self.developer_mode, "Developer mode")
+                        .on_hover_text("Allows sending hand-crafted packets from the dev tools panel")
+                        .changed()
+                    {
+                        config::set_developer_mode(self.developer_mode);
+                    }
+                    if ui
+                        .checkbox(&mut self.auto_fix_login_info, "Auto-fix login info")
+                        .on_hover_text("Recompute hash and klv when they don't match the spoofed login info")
+                        .changed()
+                    {
+                        config::set_auto_fix_login_info(self.auto_fix_login_info);
+                    }
+                    if ui
+                        .checkbox(&mut self.debug_logs, "Debug logs")
+                        .on_hover_text("Log frequent decisions such as what gets collected")
+                        .changed()
+                    {
+                        config::set_debug_logs(self.debug_logs);
+                    }
+                    if ui
+                        .checkbox(&mut self.trusted_scripts, "Trusted scripts")
+                        .on_hover_text("Give scripts io/os and no memory or CPU limits, only for your own scripts")
+                        .changed()
+                    {
+                        config::set_trusted_scripts(self.trusted_scripts);
+                    }
+                    ui.add_enabled_ui(!self.trusted_scripts, |ui| {
+                        if ui
+                            .add(
+                                egui::Slider::new(&mut self.script_memory_limit, 8..=512)
+                                    .integer()
+                                    .suffix(" MB")
+                                    .text("Script memory"),
+                            )
+                            .changed()
+                        {
+                            config::set_script_memory_limit(self.script_memory_limit);
+                        }
+                        if ui
+                            .add(
+                                egui::Slider::new(&mut self.script_cpu_budget, 10..=5000)
+                                    .integer()
+                                    .suffix("M instr")
+                                    .text("Script CPU budget"),
+                            )
+                            .on_hover_text("Instructions a script may run before returning or calling bot.sleep")
+                            .changed()
+                        {
+                            config::set_script_cpu_budget(self.script_cpu_budget);
+                        }
+                    });
                     ui.horizontal(|ui| {
                         ui.label("Theme:");
                         egui::ComboBox::from_label("")
@@ -89,6 +358,44 @@ impl Settings {
                         config::set_timeout(self.timeout_delay);
                     }
                     ui.add_space(10.0);
+                    if ui
+                        .add(
+                            egui::Slider::new(&mut self.connect_timeout, 5..=120)
+                                .integer()
+                                .suffix("sec")
+                                .text("Connect timeout"),
+                        )
+                        .changed()
+                    {
+                        config::set_connect_timeout(self.connect_timeout);
+                    }
+                    ui.add_space(10.0);
+                    if ui
+                        .add(
+                            egui::Slider::new(&mut self.stall_timeout, 0..=600)
+                                .integer()
+                                .suffix("sec")
+                                .text("Stall timeout"),
+                        )
+                        .on_hover_text("Reconnect when nothing is received for this long, 0 disables it")
+                        .changed()
+                    {
+                        config::set_stall_timeout(self.stall_timeout);
+                    }
+                    ui.add_space(10.0);
+                    if ui
+                        .add(
+                            egui::Slider::new(&mut self.idle_heartbeat, 0..=300)
+                                .integer()
+                                .suffix("sec")
+                                .text("Idle heartbeat"),
+                        )
+                        .on_hover_text("Send a state packet after standing still this long, 0 disables it")
+                        .changed()
+                    {
+                        config::set_idle_heartbeat(self.idle_heartbeat);
+                    }
+                    ui.add_space(10.0);
                     if ui
                         .add(
                             egui::Slider::new(&mut self.findpath_delay, 0..=1000)
@@ -100,6 +407,391 @@ impl Settings {
                     {
                         config::set_findpath_delay(self.findpath_delay);
                     }
+                    if ui
+                        .checkbox(&mut self.movement_calibration, "Calibrate movement speed")
+                        .on_hover_text("Walks a few tiles on world entry and after speed changes to find a pace the server accepts")
+                        .changed()
+                    {
+                        config::set_movement_calibration(self.movement_calibration);
+                    }
+                    ui.add_space(10.0);
+                    if ui
+                        .add(
+                            egui::Slider::new(&mut self.door_walk_off, -2..=2)
+                                .integer()
+                                .suffix(" tiles")
+                                .text("Door walk-off"),
+                        )
+                        .changed()
+                    {
+                        config::set_door_walk_off(self.door_walk_off);
+                    }
+                    ui.add_space(10.0);
+                    if ui
+                        .add(
+                            egui::Slider::new(&mut self.path_tolerance, 0..=5)
+                                .integer()
+                                .suffix(" tiles")
+                                .text("Path tolerance"),
+                        )
+                        .on_hover_text("Stop this close to a target that is blocked or has no route")
+                        .changed()
+                    {
+                        config::set_path_tolerance(self.path_tolerance);
+                    }
+                    ui.add_space(10.0);
+                    if ui
+                        .add(
+                            egui::Slider::new(&mut self.path_segment_max, 1..=12)
+                                .integer()
+                                .suffix(" tiles")
+                                .text("Path segment length"),
+                        )
+                        .on_hover_text("Straight runs up to this long are walked smoothly with fewer packets, 1 steps tile by tile")
+                        .changed()
+                    {
+                        config::set_path_segment_max(self.path_segment_max);
+                    }
+                    ui.add_space(10.0);
+                    ui.horizontal(|ui| {
+                        ui.label("Humanizer delays:");
+                        egui::ComboBox::from_id_salt("humanizer_distribution")
+                            .selected_text(format!("{:?}", self.humanizer_distribution))
+                            .show_ui(ui, |ui| {
+                                for option in EDelayDistribution::ALL {
+                                    if ui
+                                        .selectable_value(&mut self.humanizer_distribution, option, format!("{:?}", option))
+                                        .changed()
+                                    {
+                                        config::set_humanizer_distribution(self.humanizer_distribution);
+                                    }
+                                }
+                            })
+                            .response
+                            .on_hover_text("Uniform spreads delays evenly over the jitter, Normal bunches them around the mean");
+                    });
+                    ui.add_space(10.0);
+                    if ui
+                        .checkbox(&mut self.walk_to_activate, "Walk to activated tiles")
+                        .on_hover_text("Doors, switches and checkpoints out of reach are walked to first")
+                        .changed()
+                    {
+                        config::set_walk_to_activate(self.walk_to_activate);
+                    }
+                    ui.add_space(10.0);
+                    if ui
+                        .checkbox(&mut self.auto_onboarding, "Answer fresh-account dialogs")
+                        .on_hover_text("Age gate, GDPR consent and rating prompts, each answered once per bot")
+                        .changed()
+                    {
+                        config::set_auto_onboarding(self.auto_onboarding);
+                    }
+                    if self.auto_onboarding {
+                        if ui
+                            .add(
+                                egui::Slider::new(&mut self.onboarding_age, 13..=60)
+                                    .integer()
+                                    .text("Age"),
+                            )
+                            .changed()
+                        {
+                            config::set_onboarding_age(self.onboarding_age);
+                        }
+                        if ui
+                            .checkbox(&mut self.onboarding_consent, "Give optional consents")
+                            .on_hover_text("The terms are always accepted, this covers the rest")
+                            .changed()
+                        {
+                            config::set_onboarding_consent(self.onboarding_consent);
+                        }
+                    }
+                    ui.add_space(10.0);
+                    ui.horizontal(|ui| {
+                        ui.label("Bandwidth cap");
+                        let cap = ui
+                            .add(egui::DragValue::new(&mut self.bandwidth_cap_mb).range(0..=100_000).suffix(" MB/h"))
+                            .on_hover_text("Telemetry, idle heartbeats, whisper auto-replies and world watching pause once the farm uses this much in an hour, 0 for no cap");
+                        if cap.drag_stopped() || cap.lost_focus() {
+                            config::set_bandwidth_cap_mb(self.bandwidth_cap_mb);
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Drops despawn after");
+                        let despawn = ui
+                            .add(egui::DragValue::new(&mut self.drop_despawn_secs).range(0..=86400).suffix("s"))
+                            .on_hover_text("Older drops are collected first and dropped from the map once past this, 0 to never");
+                        if despawn.drag_stopped() || despawn.lost_focus() {
+                            config::set_drop_despawn_secs(self.drop_despawn_secs);
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Warn after");
+                        let warn = ui
+                            .add(egui::DragValue::new(&mut self.resync_warn_count).range(0..=100).suffix(" resyncs/min"))
+                            .on_hover_text("Position resyncs within a minute that suggest the movement delays are too tight, 0 to never warn");
+                        if warn.drag_stopped() || warn.lost_focus() {
+                            config::set_resync_warn_count(self.resync_warn_count);
+                        }
+                    });
+                    ui.add_space(10.0);
+                    if ui
+                        .add(
+                            egui::Slider::new(&mut self.inventory_free_threshold, 0..=20)
+                                .integer()
+                                .suffix(" slots")
+                                .text("Inventory warning"),
+                        )
+                        .on_hover_text("Run the bot's inventory reactions when fewer slots than this are free")
+                        .changed()
+                    {
+                        config::set_inventory_free_threshold(self.inventory_free_threshold);
+                    }
+                    ui.add_space(10.0);
+                    ui.horizontal(|ui| {
+                        ui.label("Valuable drops from");
+                        let threshold = ui
+                            .add(
+                                egui::DragValue::new(&mut self.collect_threshold_wl)
+                                    .speed(0.1)
+                                    .range(0.0..=f32::MAX)
+                                    .suffix(" WL"),
+                            )
+                            .on_hover_text("Drops worth this much under Item values are collected while walking and detoured to when idle");
+                        if threshold.drag_stopped() || threshold.lost_focus() {
+                            config::set_collect_threshold_wl(self.collect_threshold_wl);
+                        }
+                    });
+                    egui::CollapsingHeader::new("Item values").show(ui, |ui| {
+                        self.render_value_table(ui);
+                    });
+                    egui::CollapsingHeader::new("World passwords").show(ui, |ui| {
+                        ui.checkbox(&mut self.show_world_passwords, "Show passwords");
+                        let masked = !self.show_world_passwords;
+                        let mut changed = false;
+                        let mut remove = None;
+                        egui::Grid::new("world_passwords_grid").show(ui, |ui| {
+                            for (index, entry) in self.world_passwords.iter_mut().enumerate() {
+                                ui.label(&entry.world);
+                                changed |= ui
+                                    .add(
+                                        egui::TextEdit::singleline(&mut entry.password)
+                                            .password(masked)
+                                            .desired_width(120.0),
+                                    )
+                                    .lost_focus();
+                                if ui.small_button(egui_remixicon::icons::DELETE_BIN_LINE).clicked() {
+                                    remove = Some(index);
+                                }
+                                ui.end_row();
+                            }
+                            ui.add(
+                                egui::TextEdit::singleline(&mut self.new_world_password.world)
+                                    .hint_text("world")
+                                    .desired_width(100.0),
+                            );
+                            ui.add(
+                                egui::TextEdit::singleline(&mut self.new_world_password.password)
+                                    .password(masked)
+                                    .hint_text("password")
+                                    .desired_width(120.0),
+                            );
+                            if ui.small_button(egui_remixicon::icons::ADD_FILL).clicked()
+                                && !self.new_world_password.world.trim().is_empty()
+                            {
+                                let world = self.new_world_password.world.trim().to_uppercase();
+                                self.world_passwords
+                                    .retain(|entry| !entry.world.eq_ignore_ascii_case(&world));
+                                self.world_passwords.push(WorldPassword {
+                                    world,
+                                    password: self.new_world_password.password.clone(),
+                                });
+                                self.new_world_password = WorldPassword::default();
+                                changed = true;
+                            }
+                            ui.end_row();
+                        });
+                        if let Some(index) = remove {
+                            self.world_passwords.remove(index);
+                            changed = true;
+                        }
+                        if changed {
+                            config::set_world_passwords(self.world_passwords.clone());
+                        }
+                    });
+                    egui::CollapsingHeader::new("Login windows").show(ui, |ui| {
+                        ui.label(
+                            egui::RichText::new(
+                                "Bots in these groups only log in once a day at a random time in the window",
+                            )
+                            .weak(),
+                        );
+                        let mut changed = false;
+                        let mut remove = None;
+                        egui::Grid::new("login_windows_grid").show(ui, |ui| {
+                            for (index, entry) in self.login_windows.iter_mut().enumerate() {
+                                ui.label(&entry.group);
+                                let response = ui.add(
+                                    egui::TextEdit::singleline(&mut entry.window).desired_width(100.0),
+                                );
+                                changed |= response.lost_focus();
+                                if login_schedule::parse_window(&entry.window).is_none() {
+                                    ui.colored_label(egui::Color32::from_rgb(220, 53, 69), "HH:MM-HH:MM");
+                                }
+                                if ui.small_button(egui_remixicon::icons::DELETE_BIN_LINE).clicked() {
+                                    remove = Some(index);
+                                }
+                                ui.end_row();
+                            }
+                            ui.add(
+                                egui::TextEdit::singleline(&mut self.new_login_window.group)
+                                    .hint_text("group")
+                                    .desired_width(100.0),
+                            );
+                            ui.add(
+                                egui::TextEdit::singleline(&mut self.new_login_window.window)
+                                    .hint_text("14:00-16:00")
+                                    .desired_width(100.0),
+                            );
+                            if ui.small_button(egui_remixicon::icons::ADD_FILL).clicked()
+                                && !self.new_login_window.group.trim().is_empty()
+                                && login_schedule::parse_window(&self.new_login_window.window).is_some()
+                            {
+                                let group = self.new_login_window.group.trim().to_string();
+                                self.login_windows
+                                    .retain(|entry| !entry.group.eq_ignore_ascii_case(&group));
+                                self.login_windows.push(LoginWindow {
+                                    group,
+                                    window: self.new_login_window.window.trim().to_string(),
+                                });
+                                self.new_login_window = LoginWindow::default();
+                                changed = true;
+                            }
+                            ui.end_row();
+                        });
+                        if let Some(index) = remove {
+                            self.login_windows.remove(index);
+                            changed = true;
+                        }
+                        if changed {
+                            config::set_login_windows(self.login_windows.clone());
+                        }
+                    });
+                    self.render_bot_templates(ui, ctx, manager);
+                    ui.add_space(10.0);
+                    if ui
+                        .add(
+                            egui::Slider::new(&mut self.stats_utc_offset, -12..=14)
+                                .integer()
+                                .prefix("UTC ")
+                                .text("Daily stats timezone"),
+                        )
+                        .changed()
+                    {
+                        config::set_stats_utc_offset(self.stats_utc_offset);
+                    }
+                    if ui
+                        .add(
+                            egui::Slider::new(&mut self.daily_reset_hour, 0..=23)
+                                .integer()
+                                .suffix(":00")
+                                .text("Daily reset hour"),
+                        )
+                        .changed()
+                    {
+                        config::set_daily_reset_hour(self.daily_reset_hour);
+                    }
+                    ui.add_space(10.0);
+                    if ui
+                        .checkbox(&mut self.auto_reply, "Auto reply to whispers")
+                        .changed()
+                    {
+                        config::set_auto_reply(self.auto_reply);
+                    }
+                    ui.horizontal(|ui| {
+                        ui.label("Reply message:");
+                        if ui
+                            .text_edit_singleline(&mut self.auto_reply_message)
+                            .on_hover_text("{sender} is replaced with the sender's name")
+                            .changed()
+                        {
+                            config::set_auto_reply_message(self.auto_reply_message.clone());
+                        }
+                    });
+                    if ui
+                        .add(
+                            egui::Slider::new(&mut self.auto_reply_cooldown, 0..=600)
+                                .integer()
+                                .suffix("sec")
+                                .text("Reply cooldown"),
+                        )
+                        .changed()
+                    {
+                        config::set_auto_reply_cooldown(self.auto_reply_cooldown);
+                    }
+                    if ui
+                        .add(
+                            egui::Slider::new(&mut self.chat_cooldown, 500..=5000)
+                                .integer()
+                                .suffix("ms")
+                                .text("Chat cooldown"),
+                        )
+                        .changed()
+                    {
+                        config::set_chat_cooldown(self.chat_cooldown);
+                    }
+                    if ui
+                        .checkbox(&mut self.forward_whispers, "Forward whispers to webhook")
+                        .changed()
+                    {
+                        config::set_forward_whispers(self.forward_whispers);
+                    }
+                    if ui
+                        .checkbox(
+                            &mut self.world_summary_webhook,
+                            "Post world summaries to webhook",
+                        )
+                        .changed()
+                    {
+                        config::set_world_summary_webhook(self.world_summary_webhook);
+                    }
+                    if ui
+                        .checkbox(&mut self.transfer_webhook, "Post item transfer reports to webhook")
+                        .changed()
+                    {
+                        config::set_transfer_webhook(self.transfer_webhook);
+                    }
+                    ui.horizontal(|ui| {
+                        ui.label("Webhook URL:");
+                        if ui.text_edit_singleline(&mut self.webhook_url).changed() {
+                            config::set_webhook_url(self.webhook_url.clone());
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Farmable item ID:");
+                        if ui
+                            .add(egui::DragValue::new(&mut self.farmable_item))
+                            .on_hover_text("Counted in world summaries, 0 to skip")
+                            .changed()
+                        {
+                            config::set_farmable_item(self.farmable_item);
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Friends:");
+                        if ui
+                            .text_edit_singleline(&mut self.friends)
+                            .on_hover_text("Comma separated, highlighted on the world map")
+                            .changed()
+                        {
+                            config::set_friends(
+                                self.friends
+                                    .split(',')
+                                    .map(|name| name.trim().to_string())
+                                    .filter(|name| !name.is_empty())
+                                    .collect(),
+                            );
+                        }
+                    });
                     ui.add_space(10.0);
                     ui.horizontal(|ui| {
                         ui.label("Captcha provider:");
@@ -129,6 +821,43 @@ impl Settings {
                         ui.label("Captcha API key:");
                         ui.text_edit_singleline(&mut self.captcha_api_key);
                     });
+                    ui.add_space(10.0);
+                    ui.horizontal(|ui| {
+                        ui.label("Session file:");
+                        ui.text_edit_singleline(&mut self.session_path);
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label("Passphrase:");
+                        ui.add(
+                            egui::TextEdit::singleline(&mut self.session_passphrase)
+                                .password(true)
+                                .hint_text("optional"),
+                        );
+                        if ui.button("Export session").clicked() {
+                            let passphrase = Some(self.session_passphrase.as_str())
+                                .filter(|passphrase| !passphrase.is_empty());
+                            match manager.read().unwrap().export_session(&self.session_path, passphrase) {
+                                Ok(count) => toasts::info(&format!(
+                                    "Exported {} bots, start with --resume {}",
+                                    count, self.session_path
+                                )),
+                                Err(err) => toasts::error(&format!("Failed to export session: {}", err)),
+                            }
+                        }
+                    });
+                    if ui
+                        .add(
+                            egui::Slider::new(&mut self.session_max_age, 60..=86400)
+                                .logarithmic(true)
+                                .suffix("s")
+                                .text("Resume sessions up to"),
+                        )
+                        .changed()
+                    {
+                        config::set_session_max_age(self.session_max_age);
+                    }
+                    ui.add_space(10.0);
+                    self.render_config_encryption(ui);
                 });
                 ui.vertical(|ui| {
                     ui.with_layout(egui::Layout::right_to_left(egui::Align::Min), |ui| {
@@ -157,4 +886,274 @@ impl Settings {
                 ui.end_row();
             });
     }
+
+    // World lock values used for collect priority, deposits and inventory worth, manual values
+    // win over synced ones
+    fn render_value_table(&mut self, ui: &mut Ui) {
+        let table = value_table::get();
+        ui.horizontal(|ui| {
+            ui.label("Sync URL:");
+            if ui
+                .add(egui::TextEdit::singleline(&mut self.value_sync_url).hint_text("https://.../values.json"))
+                .lost_focus()
+            {
+                config::set_value_sync_url(self.value_sync_url.trim().to_string());
+            }
+            if ui
+                .add_enabled(!self.value_sync_url.trim().is_empty(), egui::Button::new("Sync now"))
+                .clicked()
+            {
+                let url = self.value_sync_url.trim().to_string();
+                config::set_value_sync_url(url.clone());
+                thread::spawn(move || match value_table::sync(&url) {
+                    Ok(count) => toasts::info(&format!("Synced {} item values", count)),
+                    Err(err) => toasts::error(&format!("Value sync failed, kept the previous table: {}", err)),
+                });
+            }
+        });
+        let synced = match table.synced_at {
+            0 => "Never synced".to_string(),
+            synced_at => format!(
+                "{} synced values from {}, {} {}",
+                table.synced.len(),
+                table.source,
+                date::format_day(date::day_index(synced_at, self.stats_utc_offset, 0)),
+                date::format_time(synced_at, self.stats_utc_offset)
+            ),
+        };
+        ui.label(egui::RichText::new(synced).weak());
+
+        let mut remove = None;
+        egui::Grid::new("value_overrides_grid").show(ui, |ui| {
+            for (index, (item_id, value)) in self.value_overrides.iter_mut().enumerate() {
+                ui.label(format!("Item {}", item_id));
+                let edit = ui.add(egui::DragValue::new(value).speed(0.1).range(0.0..=f32::MAX).suffix(" WL"));
+                if edit.drag_stopped() || edit.lost_focus() {
+                    value_table::set_override(*item_id, Some(*value));
+                }
+                if ui.small_button(egui_remixicon::icons::DELETE_BIN_LINE).clicked() {
+                    remove = Some(index);
+                }
+                ui.end_row();
+            }
+            ui.add(egui::DragValue::new(&mut self.new_value_override.0).prefix("id "));
+            ui.add(
+                egui::DragValue::new(&mut self.new_value_override.1)
+                    .speed(0.1)
+                    .range(0.0..=f32::MAX)
+                    .suffix(" WL"),
+            );
+            if ui.small_button(egui_remixicon::icons::ADD_FILL).clicked() {
+                let (item_id, value) = self.new_value_override;
+                self.value_overrides.retain(|(id, _)| *id != item_id);
+                self.value_overrides.push((item_id, value));
+                value_table::set_override(item_id, Some(value));
+            }
+            ui.end_row();
+        });
+        if let Some(index) = remove {
+            let (item_id, _) = self.value_overrides.remove(index);
+            value_table::set_override(item_id, None);
+        }
+    }
+
+    // Passwords, recovery codes, tokens and proxy credentials sealed with a passphrase on disk
+    fn render_config_encryption(&mut self, ui: &mut Ui) {
+        ui.label("Config encryption:");
+        if secrets::is_locked() {
+            ui.colored_label(
+                egui::Color32::from_rgb(220, 53, 69),
+                "Locked, restart Mori and enter the passphrase to log bots in and save changes",
+            );
+            return;
+        }
+        if secrets::is_enabled() {
+            ui.horizontal(|ui| {
+                ui.label("Secrets are encrypted");
+                if ui.button("Decrypt").clicked() {
+                    match secrets::disable() {
+                        Ok(()) => toasts::info("Secrets are stored in plaintext again"),
+                        Err(err) => toasts::error(&err),
+                    }
+                }
+            });
+            return;
+        }
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut self.config_passphrase)
+                    .password(true)
+                    .hint_text("passphrase"),
+            );
+            ui.add(
+                egui::TextEdit::singleline(&mut self.config_passphrase_confirm)
+                    .password(true)
+                    .hint_text("again"),
+            );
+            let valid = !self.config_passphrase.is_empty()
+                && self.config_passphrase == self.config_passphrase_confirm;
+            if ui
+                .add_enabled(valid, egui::Button::new("Encrypt"))
+                .on_hover_text(format!("Asked for on startup, or set {}", secrets::PASSPHRASE_ENV))
+                .clicked()
+            {
+                secrets::enable(&self.config_passphrase);
+                self.config_passphrase.clear();
+                self.config_passphrase_confirm.clear();
+                toasts::info("Secrets are encrypted, the passphrase can't be recovered");
+            }
+        });
+    }
+
+    fn render_bot_templates(&mut self, ui: &mut Ui, ctx: &egui::Context, manager: &Arc<RwLock<BotManager>>) {
+        egui::CollapsingHeader::new("Bot templates").show(ui, |ui| {
+            ui.label(
+                egui::RichText::new("Picked when adding or importing bots, everything but the credentials").weak(),
+            );
+            let mut changed = false;
+            let mut remove = None;
+            let template_users = &self.template_users;
+            for (index, template) in self.bot_templates.iter_mut().enumerate() {
+                egui::CollapsingHeader::new(&template.name)
+                    .id_salt(("bot_template", index))
+                    .show(ui, |ui| {
+                        egui::Grid::new(("bot_template_grid", index)).show(ui, |ui| {
+                            ui.label("Login method");
+                            egui::ComboBox::from_id_salt(("bot_template_method", index))
+                                .selected_text(format!("{:?}", template.login_method))
+                                .show_ui(ui, |ui| {
+                                    for method in [
+                                        ELoginMethod::LEGACY,
+                                        ELoginMethod::GOOGLE,
+                                        ELoginMethod::APPLE,
+                                        ELoginMethod::STEAM,
+                                    ] {
+                                        let label = format!("{:?}", method);
+                                        ui.selectable_value(&mut template.login_method, method, label);
+                                    }
+                                });
+                            ui.end_row();
+                            ui.label("Group");
+                            ui.add(egui::TextEdit::singleline(&mut template.group).hint_text("optional"));
+                            ui.end_row();
+                            ui.label("Proxy pool");
+                            ui.add(egui::TextEdit::singleline(&mut template.proxy_pool).hint_text("group's pool"));
+                            ui.end_row();
+                            ui.label("Login window");
+                            ui.add(egui::TextEdit::singleline(&mut template.login_window).hint_text("14:00-16:00"));
+                            ui.end_row();
+                            ui.label("Humanizer");
+                            egui::ComboBox::from_id_salt(("bot_template_humanizer", index))
+                                .selected_text(format!("{:?}", template.humanizer))
+                                .show_ui(ui, |ui| {
+                                    for option in EHumanizerPreset::ALL {
+                                        ui.selectable_value(&mut template.humanizer, option, format!("{:?}", option));
+                                    }
+                                });
+                            ui.end_row();
+                            ui.label("Storage world");
+                            ui.text_edit_singleline(&mut template.storage_world);
+                            ui.end_row();
+                            ui.label("Deposit at");
+                            ui.add(egui::DragValue::new(&mut template.deposit_threshold).suffix(" WL"));
+                            ui.end_row();
+                        });
+                        ui.checkbox(&mut template.use_proxy, "Use proxy");
+                        for reaction in EReaction::ALL {
+                            let mut enabled = template.inventory_reactions.contains(&reaction);
+                            if ui.checkbox(&mut enabled, reaction.label()).changed() {
+                                if enabled {
+                                    template.inventory_reactions.push(reaction);
+                                } else {
+                                    template.inventory_reactions.retain(|r| *r != reaction);
+                                }
+                            }
+                        }
+                        if !template.login_window.trim().is_empty()
+                            && login_schedule::parse_window(&template.login_window).is_none()
+                        {
+                            ui.colored_label(egui::Color32::from_rgb(220, 53, 69), "Login window is not HH:MM-HH:MM");
+                        }
+                        ui.horizontal(|ui| {
+                            if ui.button("Save").clicked() {
+                                changed = true;
+                            }
+                            let used_by = template_users.get(&template.name.to_lowercase()).map_or(0, Vec::len);
+                            if ui
+                                .add_enabled(used_by > 0, egui::Button::new(format!("Re-apply to {} bots", used_by)))
+                                .on_hover_text("Saves the template and clears these settings on every bot added from it")
+                                .clicked()
+                            {
+                                self.reapply_template = Some(template.name.clone());
+                            }
+                            if ui.button("Delete").clicked() {
+                                remove = Some(index);
+                            }
+                        });
+                    });
+            }
+            ui.horizontal(|ui| {
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.new_template_name)
+                        .hint_text("template name")
+                        .desired_width(120.0),
+                );
+                let name = self.new_template_name.trim().to_string();
+                let taken = self.bot_templates.iter().any(|template| template.name.eq_ignore_ascii_case(&name));
+                if ui.small_button(egui_remixicon::icons::ADD_FILL).clicked() && !name.is_empty() && !taken {
+                    self.bot_templates.push(BotTemplate { name, ..Default::default() });
+                    self.new_template_name.clear();
+                    changed = true;
+                }
+            });
+            if let Some(index) = remove {
+                let name = self.bot_templates.remove(index).name;
+                let kept = bot_template::remove(self.bot_templates.clone(), &name);
+                if kept > 0 {
+                    toasts::info(&format!("Removed {}, its {} bots keep its settings as their own", name, kept));
+                }
+            } else if changed {
+                config::set_bot_templates(self.bot_templates.clone());
+            }
+        });
+
+        let name = match self.reapply_template.clone() {
+            Some(name) => name,
+            None => return,
+        };
+        let affected = self.template_users.get(&name.to_lowercase()).map(Vec::as_slice).unwrap_or_default();
+        let mut close = false;
+        egui::Window::new("Re-apply template")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "Save {} and clear the group, proxy, humanizer, login window, storage and inventory settings these {} bots set themselves, so they use the template's?",
+                    name,
+                    affected.len()
+                ));
+                egui::ScrollArea::vertical()
+                    .id_salt("reapply_template_bots")
+                    .max_height(200.0)
+                    .show(ui, |ui| {
+                        for username in affected {
+                            ui.label(username);
+                        }
+                    });
+                ui.horizontal(|ui| {
+                    if ui.button("Re-apply").clicked() {
+                        let updated = bot_template::reapply(&manager.read().unwrap(), self.bot_templates.clone(), &name);
+                        toasts::info(&format!("Re-applied {} to {} bots", name, updated.len()));
+                        close = true;
+                    }
+                    if ui.button("Cancel").clicked() {
+                        close = true;
+                    }
+                });
+            });
+        if close {
+            self.reapply_template = None;
+        }
+    }
 }
@@ -0,0 +1,92 @@
+use crate::types::{esetup_fix::ESetupFix, setup_problem::SetupProblem};
+use crate::utils::setup_check;
+use eframe::egui;
+
+// Shown instead of the app while setup_check finds problems
+pub struct SetupScreen {
+    pub problems: Vec<SetupProblem>,
+    pub items_path: String,
+    // Result of the last fix button, cleared on the next one
+    pub message: Option<Result<String, String>>,
+}
+
+impl SetupScreen {
+    pub fn new(problems: Vec<SetupProblem>) -> Self {
+        SetupScreen {
+            problems,
+            items_path: String::new(),
+            message: None,
+        }
+    }
+
+    // True once every problem is solved and the app can load
+    pub fn render(&mut self, ctx: &egui::Context) -> bool {
+        if self.problems.is_empty() {
+            return true;
+        }
+        let mut applied = None;
+        let mut recheck = false;
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.heading("Setup problems");
+            ui.label("Mori can't start until these are sorted out.");
+            ui.separator();
+            egui::ScrollArea::vertical()
+                .id_salt("setup_problems")
+                .max_height(ui.available_height() - 60.0)
+                .show(ui, |ui| {
+                    for (index, problem) in self.problems.iter().enumerate() {
+                        ui.group(|ui| {
+                            ui.set_width(ui.available_width());
+                            ui.colored_label(egui::Color32::from_rgb(220, 53, 69), &problem.title);
+                            ui.label(egui::RichText::new(&problem.hint).weak());
+                            let fix = match &problem.fix {
+                                Some(fix) => fix,
+                                None => return,
+                            };
+                            ui.horizontal(|ui| {
+                                if *fix == ESetupFix::BrowseItems {
+                                    ui.add(
+                                        egui::TextEdit::singleline(&mut self.items_path)
+                                            .hint_text("path to items.dat"),
+                                    );
+                                }
+                                if ui.button(fix.label()).clicked() {
+                                    applied = Some(index);
+                                }
+                            });
+                        });
+                    }
+                });
+            ui.separator();
+            if let Some(message) = &self.message {
+                match message {
+                    Ok(message) => ui.label(message),
+                    Err(err) => ui.colored_label(egui::Color32::from_rgb(220, 53, 69), err),
+                };
+            }
+            ui.horizontal(|ui| {
+                if ui.button("Check again").clicked() {
+                    recheck = true;
+                }
+                if ui.button("Quit").clicked() {
+                    ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+                }
+            });
+        });
+
+        if let Some(index) = applied {
+            if let Some(fix) = self.problems[index].fix.clone() {
+                self.message = Some(
+                    setup_check::apply(&fix, self.items_path.trim())
+                        .map(|()| format!("{} done", fix.label())),
+                );
+                // Opening a folder fixes nothing by itself
+                recheck = !matches!(fix, ESetupFix::OpenFolder(_));
+            }
+        }
+        if recheck {
+            self.problems = setup_check::run();
+        }
+        false
+    }
+}
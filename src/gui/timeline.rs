@@ -0,0 +1,62 @@
+use crate::types::timeline_entry::TimelineEntry;
+use crate::utils::{config, date};
+use eframe::egui::{self, Color32, Ui};
+
+const HEIGHT: f32 = 28.0;
+
+// Event markers laid out over the recorded time span, hovering shows the details.
+// Returns the timestamp of a clicked marker
+pub fn render(ui: &mut Ui, entries: &[TimelineEntry]) -> Option<u64> {
+    let (rect, _) =
+        ui.allocate_exact_size(egui::vec2(ui.available_width(), HEIGHT), egui::Sense::hover());
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 4.0, ui.visuals().extreme_bg_color);
+
+    let (first, last) = match (entries.first(), entries.last()) {
+        (Some(first), Some(last)) => (first.timestamp, last.timestamp),
+        _ => {
+            painter.text(
+                rect.center(),
+                egui::Align2::CENTER_CENTER,
+                "No activity recorded yet",
+                egui::FontId::proportional(12.0),
+                ui.visuals().weak_text_color(),
+            );
+            return None;
+        }
+    };
+    let span = last.saturating_sub(first).max(1) as f32;
+    let utc_offset = config::get_stats_utc_offset();
+
+    let mut clicked = None;
+    for (index, entry) in entries.iter().enumerate() {
+        let progress = entry.timestamp.saturating_sub(first) as f32 / span;
+        let center = egui::pos2(
+            rect.left() + 6.0 + progress * (rect.width() - 12.0),
+            rect.center().y,
+        );
+        let marker = egui::Rect::from_center_size(center, egui::vec2(8.0, HEIGHT - 8.0));
+        let response = ui.interact(marker, ui.id().with(("timeline", index)), egui::Sense::click());
+        let radius = if response.hovered() { 5.0 } else { 3.5 };
+        painter.circle_filled(center, radius, color_for(&entry.kind));
+        let response = response.on_hover_text(format!(
+            "{} {}",
+            date::format_time(entry.timestamp, utc_offset),
+            entry.detail
+        ));
+        if response.clicked() {
+            clicked = Some(entry.timestamp);
+        }
+    }
+    clicked
+}
+
+fn color_for(kind: &str) -> Color32 {
+    match kind {
+        "disconnect" | "intruder" => Color32::from_rgb(220, 53, 69),
+        "world_enter" | "world_leave" => Color32::from_rgb(0, 123, 255),
+        "item_gain" => Color32::from_rgb(40, 167, 69),
+        "feature" => Color32::from_rgb(255, 193, 7),
+        _ => Color32::from_rgb(111, 66, 193),
+    }
+}
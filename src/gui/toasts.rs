@@ -0,0 +1,107 @@
+use crate::types::{eseverity::ESeverity, notification::Notification};
+use eframe::egui::{self, Align2, Color32, Id, Sense};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+const TOAST_DURATION: Duration = Duration::from_secs(4);
+// Long enough to reach the button
+const NOTIFICATION_DURATION: Duration = Duration::from_secs(10);
+
+static TOASTS: Mutex<Vec<Toast>> = Mutex::new(Vec::new());
+
+struct Toast {
+    message: String,
+    is_error: bool,
+    created_at: Instant,
+    // Set for toasts raised from a notification, clicking them goes to its bot
+    notification: Option<u64>,
+    action: Option<&'static str>,
+}
+
+impl Toast {
+    fn duration(&self) -> Duration {
+        if self.notification.is_some() {
+            NOTIFICATION_DURATION
+        } else {
+            TOAST_DURATION
+        }
+    }
+}
+
+pub enum ToastClick {
+    Open(u64),
+    Action(u64),
+}
+
+pub fn info(message: &str) {
+    push(message, false);
+}
+
+pub fn error(message: &str) {
+    push(message, true);
+}
+
+pub fn notification(notification: &Notification) {
+    TOASTS.lock().unwrap().push(Toast {
+        message: format!("{}: {}", notification.bot, notification.message),
+        is_error: notification.severity == ESeverity::Critical,
+        created_at: Instant::now(),
+        notification: Some(notification.id),
+        action: notification.action.as_ref().map(|action| action.label()),
+    });
+}
+
+fn push(message: &str, is_error: bool) {
+    TOASTS.lock().unwrap().push(Toast {
+        message: message.to_string(),
+        is_error,
+        created_at: Instant::now(),
+        notification: None,
+        action: None,
+    });
+}
+
+pub fn render(ctx: &egui::Context) -> Option<ToastClick> {
+    let mut toasts = TOASTS.lock().unwrap();
+    toasts.retain(|toast| toast.created_at.elapsed() < toast.duration());
+    if toasts.is_empty() {
+        return None;
+    }
+    // Nothing else may redraw in time to let a toast expire
+    ctx.request_repaint_after(Duration::from_millis(250));
+
+    let mut clicked = None;
+    egui::Area::new(Id::new("toasts"))
+        .anchor(Align2::RIGHT_BOTTOM, egui::vec2(-10.0, -10.0))
+        .show(ctx, |ui| {
+            for toast in toasts.iter() {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        if toast.is_error {
+                            ui.label(
+                                egui::RichText::new(egui_remixicon::icons::ERROR_WARNING_FILL)
+                                    .color(Color32::from_rgb(220, 53, 69)),
+                            );
+                        }
+                        let id = match toast.notification {
+                            Some(id) => id,
+                            None => {
+                                ui.label(&toast.message);
+                                return;
+                            }
+                        };
+                        let label = egui::Label::new(&toast.message).sense(Sense::click());
+                        if ui.add(label).on_hover_text("Show this bot").clicked() {
+                            clicked = Some(ToastClick::Open(id));
+                        }
+                        if let Some(action) = toast.action {
+                            if ui.button(action).clicked() {
+                                clicked = Some(ToastClick::Action(id));
+                            }
+                        }
+                    });
+                });
+            }
+        });
+    clicked
+}
@@ -0,0 +1,51 @@
+use crate::utils::secrets;
+use eframe::egui;
+
+// Shown instead of the app while the config's secrets are sealed and no passphrase was given
+#[derive(Default)]
+pub struct UnlockScreen {
+    pub passphrase: String,
+    pub error: Option<String>,
+}
+
+impl UnlockScreen {
+    // True once the config is unlocked or the user went on read-only
+    pub fn render(&mut self, ctx: &egui::Context) -> bool {
+        let mut done = false;
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.heading("Config is encrypted");
+            ui.label("Enter the passphrase to log bots in with their saved passwords and tokens.");
+            ui.separator();
+            let response = ui.add(
+                egui::TextEdit::singleline(&mut self.passphrase)
+                    .password(true)
+                    .hint_text("passphrase"),
+            );
+            let submitted = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+            if let Some(err) = &self.error {
+                ui.colored_label(egui::Color32::from_rgb(220, 53, 69), err);
+            }
+            ui.horizontal(|ui| {
+                if ui.button("Unlock").clicked() || submitted {
+                    match secrets::unlock(&self.passphrase) {
+                        Ok(()) => done = true,
+                        Err(err) => self.error = Some(err),
+                    }
+                    self.passphrase.clear();
+                }
+                if ui
+                    .button("Continue read-only")
+                    .on_hover_text("Bots can't log in and changes aren't saved until Mori is restarted and unlocked")
+                    .clicked()
+                {
+                    secrets::continue_read_only();
+                    done = true;
+                }
+                if ui.button("Quit").clicked() {
+                    ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+                }
+            });
+        });
+        done
+    }
+}
@@ -1,12 +1,202 @@
-use crate::texture_manager::TextureManager;
+use crate::core::astar::{AStar, PathError, PathHandle};
+use crate::core::features::place_lock;
+use crate::core::lock_area::{self, LockSize};
+use crate::core::world_diff::{self, WorldDiff};
+use crate::core::{drop_age, hazard, outgoing, world_snapshot, Bot};
+use crate::gui::pattern_designer::PatternDesigner;
+use crate::gui::manual_control::ManualControl;
+use crate::gui::toasts;
+use crate::texture_manager::{sprite_uv, TextureManager};
+use crate::types::clothing::Clothing;
+use crate::types::ehazard_mode::EHazardMode;
+use crate::types::player::Player;
+use crate::types::route::Route;
+use crate::types::tile_extra::TileExtra;
 use crate::{manager::bot_manager::BotManager, types::config::BotConfig, utils};
 use eframe::egui::{self, Color32, Pos2, Rect, Ui};
 use egui::{Painter, TextBuffer};
+use gtitem_r::structs::ItemDatabase;
 use gtworld_r::TileType;
 use paris::info;
-use std::sync::{Arc, RwLock};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
 use std::thread;
 
+// Base body, tinted with the skin color before clothing is layered on top
+const BODY_TEXTURE: &str = "player_cosmetics1.rttex";
+const BODY_SPRITE: (u8, u8) = (17, 5);
+const BODY_SPRITE_SIZE: f32 = 32.0;
+
+// Name, horizontal frame count and frame duration of tiles that animate in game
+const ANIMATED_TILES: [(&str, u8, u64); 3] = [("Lava", 4, 200), ("Waterfall", 4, 150), ("Fire", 4, 120)];
+
+fn animation(item_name: &str) -> Option<(u8, u64)> {
+    ANIMATED_TILES
+        .iter()
+        .find(|(name, _, _)| item_name.contains(name))
+        .map(|(_, frames, frame_ms)| (*frames, *frame_ms))
+}
+
+// Every tile reads the same clock so neighbouring frames stay in sync
+fn animation_frame(time: f64, frames: u8, frame_ms: u64) -> u8 {
+    ((time * 1000.0) as u64 / frame_ms % frames as u64) as u8
+}
+
+// Route to a clicked tile, computed off the UI thread while the user decides whether to walk it
+struct PathPreview {
+    target: (u32, u32),
+    cancel: Arc<AtomicBool>,
+    // None while still searching, Some(None) when there's no route
+    result: Arc<Mutex<Option<Option<Vec<(u32, u32)>>>>>,
+}
+
+impl PathPreview {
+    fn start(bot: &Arc<Bot>, target: (u32, u32)) -> Self {
+        let cancel = Arc::new(AtomicBool::new(false));
+        let result = Arc::new(Mutex::new(None));
+        let bot_clone = bot.clone();
+        let cancel_clone = cancel.clone();
+        let result_clone = result.clone();
+        thread::spawn(move || {
+            let path = bot_clone
+                .plan_path(target.0, target.1, &cancel_clone)
+                .map(|nodes| nodes.iter().map(|node| (node.x, node.y)).collect());
+            if !cancel_clone.load(Ordering::Relaxed) {
+                *result_clone.lock().unwrap() = Some(path);
+                utils::repaint::request();
+            }
+        });
+
+        PathPreview {
+            target,
+            cancel,
+            result,
+        }
+    }
+
+    fn path(&self) -> Option<Option<Vec<(u32, u32)>>> {
+        self.result.lock().unwrap().clone()
+    }
+}
+
+impl Drop for PathPreview {
+    fn drop(&mut self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
+
+// Offline sandbox for the pathfinder on a saved world snapshot, no bot involved
+#[derive(Default)]
+struct Planner {
+    snapshot: String,
+    world: Option<gtworld_r::World>,
+    astar: Option<AStar>,
+    hazard_mode: EHazardMode,
+    start: Option<(u32, u32)>,
+    goals: Vec<(u32, u32)>,
+    // One entry per goal, None when that leg has no path
+    legs: Vec<Option<Vec<(u32, u32)>>>,
+    cost: u32,
+    route_name: String,
+    camera_pos: Pos2,
+    error: Option<String>,
+    // Archived snapshot of the same world the open one is compared against
+    compare: Option<u64>,
+    earlier: Option<gtworld_r::World>,
+    diff: Option<WorldDiff>,
+    // The diff described once, not every frame
+    diff_lines: Vec<String>,
+    show_earlier: bool,
+}
+
+impl Planner {
+    fn open(&mut self, name: &str, item_database: &Arc<RwLock<ItemDatabase>>) {
+        match world_snapshot::load(name, item_database.clone()) {
+            Ok(world) => {
+                let mut astar = AStar::new(item_database.clone());
+                astar.load_world(&world);
+                astar.hazard_mode = self.hazard_mode;
+                self.camera_pos = Pos2::new(world.width as f32 * 16.0, world.height as f32 * 16.0);
+                self.world = Some(world);
+                self.astar = Some(astar);
+                self.snapshot = name.to_string();
+                self.error = None;
+            }
+            Err(err) => self.error = Some(format!("Failed to open {}: {}", name, err)),
+        }
+        self.compare = None;
+        self.earlier = None;
+        self.diff = None;
+        self.diff_lines.clear();
+        self.show_earlier = false;
+        self.start = None;
+        self.goals.clear();
+        self.replan();
+    }
+
+    fn compare_with(&mut self, saved_at: u64, item_database: &Arc<RwLock<ItemDatabase>>) {
+        let world = match &self.world {
+            Some(world) => world,
+            None => return,
+        };
+        match world_snapshot::load_archived(&self.snapshot, saved_at, item_database.clone()) {
+            Ok(earlier) => {
+                let diff = world_diff::diff(&earlier, world);
+                let item_database = item_database.read().unwrap();
+                self.diff_lines = diff.describe(|item_id| {
+                    item_database
+                        .get_item(&(item_id as u32))
+                        .map_or(format!("#{}", item_id), |item| item.name)
+                });
+                self.diff = Some(diff);
+                self.earlier = Some(earlier);
+                self.compare = Some(saved_at);
+                self.error = None;
+            }
+            Err(err) => self.error = Some(format!("Failed to open the earlier snapshot: {}", err)),
+        }
+    }
+
+    // Searches each leg from the previous waypoint, so costs match what run_route will walk
+    fn replan(&mut self) {
+        self.legs.clear();
+        self.cost = 0;
+        let (astar, start) = match (&mut self.astar, self.start) {
+            (Some(astar), Some(start)) => (astar, start),
+            _ => return,
+        };
+        astar.hazard_mode = self.hazard_mode;
+        let mut from = start;
+        for &goal in &self.goals {
+            match astar.find_path(from.0, from.1, goal.0, goal.1) {
+                Some(path) => {
+                    self.cost += astar.path_cost(&path);
+                    self.legs.push(Some(path.iter().map(|node| (node.x, node.y)).collect()));
+                }
+                None => self.legs.push(None),
+            }
+            from = goal;
+        }
+    }
+}
+
+// Area lock being placed from the map, the target is picked with a left click
+struct LockWizard {
+    size: LockSize,
+    target: Option<(u32, u32)>,
+    access: String,
+}
+
+impl LockWizard {
+    fn new() -> Self {
+        LockWizard {
+            size: LockSize::default(),
+            target: None,
+            access: utils::config::get_lock_access().join(", "),
+        }
+    }
+}
+
 #[derive(Default)]
 pub struct WorldMap {
     pub selected_bot: String,
@@ -14,6 +204,23 @@ pub struct WorldMap {
     pub bots: Vec<BotConfig>,
     camera_pos: Pos2,
     zoom: f32,
+    show_hazards: bool,
+    preview: Option<PathPreview>,
+    // Bot and world of the last summary closed, so it stays closed until the next world
+    dismissed_summary: Option<(String, String)>,
+    planning: bool,
+    planner: Planner,
+    // Tile shown in the inspect window, opened with ctrl+click
+    inspecting: Option<(u32, u32)>,
+    // Door tile whose menu was opened with a right click
+    door_menu: Option<(u32, u32)>,
+    lock_wizard: Option<LockWizard>,
+    pattern_designer: Option<PatternDesigner>,
+    manual_control: Option<ManualControl>,
+    // Frame the map was last drawn on, manual control ends on the first frame it isn't
+    map_frame: u64,
+    // Item F places in manual control, the selected bot's hotbar selection
+    pub place_item: u32,
 }
 
 impl WorldMap {
@@ -26,19 +233,54 @@ impl WorldMap {
         self.bots = utils::config::get_bots();
         self.selected_bot = utils::config::get_selected_bot();
 
-        if !self.selected_bot.is_empty() {
-            let bot = {
-                let manager = manager.read().unwrap();
+        ui.horizontal(|ui| {
+            ui.toggle_value(&mut self.planning, format!("{} Route planner", egui_remixicon::icons::PIN_DISTANCE_FILL));
+            let mut placing = self.lock_wizard.is_some();
+            if ui
+                .toggle_value(&mut placing, format!("{} Place lock", egui_remixicon::icons::LOCK_LINE))
+                .changed()
+            {
+                self.lock_wizard = placing.then(LockWizard::new);
+                self.pattern_designer = None;
+            }
+            let mut designing = self.pattern_designer.is_some();
+            if ui
+                .toggle_value(&mut designing, format!("{} Pattern", egui_remixicon::icons::LAYOUT_GRID_LINE))
+                .changed()
+            {
+                self.pattern_designer = designing.then(PatternDesigner::new);
+                self.lock_wizard = None;
+            }
+            let mut controlling = self.manual_control.is_some();
+            if ui
+                .toggle_value(&mut controlling, format!("{} Manual control", egui_remixicon::icons::GAMEPAD_LINE))
+                .on_hover_text("Drive the bot with the keyboard, automation is paused meanwhile")
+                .changed()
+            {
+                self.set_manual_control(controlling, manager);
+            }
+        });
+        // Another bot was selected, the one being driven gets its automation back
+        let stale = self
+            .manual_control
+            .as_ref()
+            .is_some_and(|control| control.username != self.selected_bot);
+        if stale {
+            self.set_manual_control(false, manager);
+        }
+        if self.planning {
+            let item_database = manager.read().unwrap().items_database.clone();
+            self.render_planner(ui, &item_database, texture_manager);
+            return;
+        }
 
-                match manager.get_bot(&self.selected_bot) {
-                    Some(bot) => Some(bot.clone()),
-                    None => None,
-                }
-            };
+        if !self.selected_bot.is_empty() {
+            let bot = manager.read().unwrap().get_bot(&self.selected_bot);
             if let Some(bot) = bot {
                 let size = ui.available_size();
-                let (rect, response) = ui.allocate_exact_size(size, egui::Sense::hover());
+                let (rect, response) = ui.allocate_exact_size(size, egui::Sense::click());
                 let draw_list = ui.painter_at(rect);
+                self.map_frame = ui.ctx().frame_nr();
 
                 draw_list.rect_filled(rect, 0.0, Color32::from_rgb(96, 215, 255));
 
@@ -54,6 +296,10 @@ impl WorldMap {
                     let smoothing_factor = 0.1;
                     self.camera_pos.x += (target_pos.x - self.camera_pos.x) * smoothing_factor;
                     self.camera_pos.y += (target_pos.y - self.camera_pos.y) * smoothing_factor;
+                    // The easing needs frames until it settles, a position packet restarts it
+                    if self.camera_pos.distance(target_pos) > 0.5 {
+                        ui.ctx().request_repaint_after(std::time::Duration::from_millis(16));
+                    }
                 }
 
                 let cell_size = 32.0 * self.zoom;
@@ -65,6 +311,10 @@ impl WorldMap {
                 let tiles_in_view_x = (size.x / cell_size).ceil() as i32 + 1;
                 let tiles_in_view_y = (size.y / cell_size).ceil() as i32 + 1;
 
+                let map_locked = utils::config::get_map_locked();
+                let confirm_clicks = utils::config::get_map_click_confirmation();
+                // egui's clock only advances while frames are drawn, so a hidden map costs nothing
+                let time = ui.input(|i| i.time);
                 let world = bot.world.read().unwrap();
                 for y in 0..tiles_in_view_y {
                     for x in 0..tiles_in_view_x {
@@ -93,6 +343,15 @@ impl WorldMap {
                             );
                             continue;
                         }
+                        self.draw_tile(
+                            &draw_list,
+                            texture_manager,
+                            &bot.item_database,
+                            &world,
+                            (world_x as u32, world_y as u32),
+                            Rect::from_min_max(cell_min, cell_max),
+                            time,
+                        );
                         let tile = world.get_tile(world_x as u32, world_y as u32).unwrap();
                         let item = {
                             let item_database = bot.item_database.read().unwrap();
@@ -101,299 +360,10 @@ impl WorldMap {
                                 .unwrap()
                         };
 
-                        if tile.background_item_id != 0 {
-                            let item_database = bot.item_database.read().unwrap();
-                            let background_item = item_database
-                                .get_item(&((tile.background_item_id + 1) as u32))
-                                .unwrap();
-
-                            self.draw_texture(
-                                &draw_list,
-                                texture_manager,
-                                background_item.texture_x,
-                                background_item.texture_y,
-                                background_item.texture_file_name.clone(),
-                                cell_min,
-                                cell_max,
-                                tile.flags.flipped_x,
-                                Color32::WHITE,
-                            );
-                        }
-
-                        if item.id != 0 {
-                            let mut texture_x = item.texture_x;
-                            let mut texture_y = item.texture_y;
-                            let texture_name = item.texture_file_name.clone();
-
-                            let left_tile = if world_x > 0 {
-                                world.get_tile(world_x as u32 - 1, world_y as u32)
-                            } else {
-                                None
-                            };
-                            let right_tile = if world_x < world.width as i32 - 1 {
-                                world.get_tile(world_x as u32 + 1, world_y as u32)
-                            } else {
-                                None
-                            };
-                            let top_tile = if world_y > 0 {
-                                world.get_tile(world_x as u32, world_y as u32 - 1)
-                            } else {
-                                None
-                            };
-                            let bottom_tile = if world_y < world.height as i32 - 1 {
-                                world.get_tile(world_x as u32, world_y as u32 + 1)
-                            } else {
-                                None
-                            };
-
-                            if item.render_type == 2 {
-                                if let (
-                                    Some(left_tile),
-                                    Some(right_tile),
-                                    Some(top_tile),
-                                    Some(bottom_tile),
-                                ) = (left_tile, right_tile, top_tile, bottom_tile)
-                                {
-                                    let left_match = left_tile.foreground_item_id == item.id as u16;
-                                    let right_match =
-                                        right_tile.foreground_item_id == item.id as u16;
-                                    let top_match = top_tile.foreground_item_id == item.id as u16;
-                                    let bottom_match =
-                                        bottom_tile.foreground_item_id == item.id as u16;
-
-                                    match (left_match, right_match, top_match, bottom_match) {
-                                        (true, true, true, true) => (),
-                                        (true, true, true, false) => texture_x += 2,
-                                        (true, true, false, true) => texture_x += 1,
-                                        (true, false, true, true) => texture_x += 4,
-                                        (false, true, true, true) => texture_x += 3,
-                                        (true, true, false, false) => texture_x += 1,
-                                        (true, false, false, true) => texture_x += 6,
-                                        (false, true, true, false) => texture_x += 7,
-                                        (false, true, false, true) => texture_x += 5,
-                                        (true, false, false, false) => texture_x += 6,
-                                        (false, false, false, true) => {
-                                            texture_x += 2;
-                                            texture_y += 1;
-                                        }
-                                        (false, true, false, false) => texture_x += 5,
-                                        _ => (),
-                                    }
-                                }
-
-                                if let (None, Some(right_tile), Some(top_tile), Some(bottom_tile)) =
-                                    (left_tile, right_tile, top_tile, bottom_tile)
-                                {
-                                    let right_match =
-                                        right_tile.foreground_item_id == item.id as u16;
-                                    let bottom_match =
-                                        bottom_tile.foreground_item_id == item.id as u16;
-                                    let top_match = top_tile.foreground_item_id != item.id as u16;
-
-                                    if right_match && bottom_match && top_match {
-                                        texture_x += 1;
-                                    }
-                                }
-
-                                if let (Some(left_tile), None, Some(top_tile), Some(bottom_tile)) =
-                                    (left_tile, right_tile, top_tile, bottom_tile)
-                                {
-                                    let left_match = left_tile.foreground_item_id == item.id as u16;
-                                    let bottom_match =
-                                        bottom_tile.foreground_item_id == item.id as u16;
-                                    let top_match = top_tile.foreground_item_id != item.id as u16;
-
-                                    if left_match && bottom_match && top_match {
-                                        texture_x += 1;
-                                    }
-                                }
-                            }
-
-                            if item.render_type == 7 {
-                                if let (Some(top_tile), Some(bottom_tile)) = (top_tile, bottom_tile)
-                                {
-                                    if top_tile.foreground_item_id != item.id as u16
-                                        && bottom_tile.foreground_item_id == item.id as u16
-                                    {
-                                        texture_x += 2;
-                                    }
-                                    if top_tile.foreground_item_id == item.id as u16
-                                        && bottom_tile.foreground_item_id == item.id as u16
-                                    {
-                                        texture_x += 1;
-                                    }
-                                    if top_tile.foreground_item_id != item.id as u16
-                                        && bottom_tile.foreground_item_id != item.id as u16
-                                    {
-                                        texture_x += 3;
-                                    }
-                                }
-                            }
-
-                            if item.render_type == 3 {
-                                if let (Some(left_tile), Some(right_tile), Some(top_tile)) =
-                                    (left_tile, right_tile, top_tile)
-                                {
-                                    if left_tile.foreground_item_id == item.id as u16
-                                        && right_tile.foreground_item_id == item.id as u16
-                                    {
-                                        texture_x += 1;
-                                    }
-                                    if left_tile.foreground_item_id != item.id as u16
-                                        || right_tile.foreground_item_id != item.id as u16
-                                    {
-                                        if top_tile.foreground_item_id == 8986 {
-                                            texture_x += 4;
-                                        }
-                                    }
-                                }
-                            }
-
-                            if item.render_type == 5 {
-                                if let (
-                                    Some(left_tile),
-                                    Some(right_tile),
-                                    Some(top_tile),
-                                    Some(bottom_tile),
-                                ) = (left_tile, right_tile, top_tile, bottom_tile)
-                                {
-                                    let left_match = left_tile.foreground_item_id == item.id as u16;
-                                    let right_match =
-                                        right_tile.foreground_item_id == item.id as u16;
-                                    let top_match = top_tile.foreground_item_id == item.id as u16;
-                                    let bottom_match =
-                                        bottom_tile.foreground_item_id == item.id as u16;
-
-                                    if (left_match && !right_match && !top_match && !bottom_match)
-                                        || (!left_match
-                                            && right_match
-                                            && !top_match
-                                            && !bottom_match)
-                                    {
-                                        texture_x += 7;
-                                    }
-                                }
-                            }
-
-                            if item.id % 2 != 0 {
-                                let (b, g, r, a) = utils::color::extract_bgra(item.overlay_color);
-                                let (spread_x, spread_y) = match item.render_type {
-                                    2 | 5 => (4.0, 1.0),
-                                    4 => (4.0, 0.0),
-                                    3 | 7 | 8 | 9 | 10 => (3.0, 0.0),
-                                    _ => (0.0, 0.0),
-                                };
-
-                                self.draw_texture(
-                                    &draw_list,
-                                    texture_manager,
-                                    item.tree_base_sprite,
-                                    19,
-                                    "tiles_page1.rttex".to_string(),
-                                    cell_min,
-                                    cell_max,
-                                    tile.flags.flipped_x,
-                                    Color32::WHITE,
-                                );
-                                self.draw_texture(
-                                    &draw_list,
-                                    texture_manager,
-                                    item.tree_overlay_sprite,
-                                    18,
-                                    "tiles_page1.rttex".to_string(),
-                                    cell_min,
-                                    cell_max,
-                                    tile.flags.flipped_x,
-                                    Color32::from_rgba_unmultiplied(r, g, b, a),
-                                );
-                                let new_cell_min = Pos2::new(
-                                    cell_min.x + cell_size * 0.375,
-                                    cell_min.y + cell_size * 0.375,
-                                );
-                                let new_cell_max = Pos2::new(
-                                    cell_max.x - cell_size * 0.375,
-                                    cell_max.y - cell_size * 0.375,
-                                );
-
-                                self.draw_texture(
-                                    &draw_list,
-                                    texture_manager,
-                                    texture_x + spread_x as u8,
-                                    texture_y + spread_y as u8,
-                                    texture_name,
-                                    new_cell_min,
-                                    new_cell_max,
-                                    tile.flags.flipped_x,
-                                    Color32::WHITE,
-                                );
-
-                                draw_list.rect_stroke(
-                                    Rect::from_min_max(new_cell_min, new_cell_max),
-                                    0.2,
-                                    (2.0, Color32::WHITE),
-                                );
-                            } else {
-                                self.draw_texture(
-                                    &draw_list,
-                                    texture_manager,
-                                    texture_x,
-                                    texture_y,
-                                    texture_name,
-                                    cell_min,
-                                    cell_max,
-                                    tile.flags.flipped_x,
-                                    Color32::WHITE,
-                                );
-                            }
-                        }
-
-                        for player in bot.players.lock().unwrap().clone() {
-                            if (player.position.x / 32.0).floor() == (world_x as f32)
-                                && (player.position.y / 32.0).floor() == (world_y as f32)
-                            {
-                                let center_min = Pos2::new(
-                                    cell_min.x + cell_size * 0.25,
-                                    cell_min.y + cell_size * 0.25,
-                                );
-                                let center_max = Pos2::new(
-                                    cell_max.x - cell_size * 0.25,
-                                    cell_max.y - cell_size * 0.25,
-                                );
-                                self.draw_texture(
-                                    &draw_list,
-                                    texture_manager,
-                                    17,
-                                    5,
-                                    "player_cosmetics1.rttex".to_string(),
-                                    center_min,
-                                    center_max,
-                                    tile.flags.flipped_x,
-                                    Color32::WHITE,
-                                );
-                            }
-                        }
-
-                        let bot_position = bot.position.lock().unwrap();
-                        if (bot_position.x / 32.0).floor() == (world_x as f32)
-                            && (bot_position.y / 32.0).floor() == (world_y as f32)
-                        {
-                            self.draw_texture(
-                                &draw_list,
-                                texture_manager,
-                                3,
-                                4,
-                                "tiles_page1.rttex".to_string(),
-                                cell_min,
-                                cell_max,
-                                tile.flags.flipped_x,
-                                Color32::WHITE,
-                            );
-                        }
-
                         if response.hover_pos().map_or(false, |pos| {
                             Rect::from_min_max(cell_min, cell_max).contains(pos)
                         }) {
-                            let data;
+                            let mut data;
                             if let TileType::Seed {
                                 ready_to_harvest,
                                 timer,
@@ -420,6 +390,17 @@ impl WorldMap {
                                     world_x, world_y, item.name, item.collision_type, item.render_type
                                 )
                             }
+                            if let Some(extra) = TileExtra::from_tile_type(&tile.tile_type) {
+                                for (label, value) in extra.fields(&bot.item_database.read().unwrap()) {
+                                    data.push_str(&format!("\n{}: {}", label, value));
+                                }
+                            }
+                            for player in bot.players.lock().unwrap().iter().filter(|player| {
+                                (player.position.x / 32.0) as i32 == world_x
+                                    && (player.position.y / 32.0) as i32 == world_y
+                            }) {
+                                data.push_str(&format!("\n\n{}", describe_player(player)));
+                            }
 
                             egui::show_tooltip(
                                 ui.ctx(),
@@ -430,17 +411,286 @@ impl WorldMap {
                                 },
                             );
 
-                            if ui.input(|i| i.pointer.any_click()) {
+                            if ui.input(|i| i.pointer.any_click() && i.modifiers.command) {
+                                self.inspecting = Some((world_x as u32, world_y as u32));
+                            } else if !map_locked
+                                && matches!(tile.tile_type, TileType::Door { .. })
+                                && ui.input(|i| i.pointer.secondary_clicked())
+                            {
+                                self.door_menu = Some((world_x as u32, world_y as u32));
+                            } else if let Some(wizard) =
+                                self.lock_wizard.as_mut().filter(|_| !map_locked)
+                            {
+                                if ui.input(|i| i.pointer.primary_clicked()) {
+                                    wizard.target = Some((world_x as u32, world_y as u32));
+                                }
+                            } else if let Some(designer) =
+                                self.pattern_designer.as_mut().filter(|_| !map_locked)
+                            {
+                                if ui.input(|i| i.pointer.primary_clicked()) {
+                                    designer.anchor = Some((world_x as u32, world_y as u32));
+                                }
+                            } else if !map_locked && ui.input(|i| i.pointer.any_click()) {
                                 info!("Clicked on tile: {}|{}", world_x, world_y);
+                                let target = (world_x as u32, world_y as u32);
+                                let previewed = self
+                                    .preview
+                                    .as_ref()
+                                    .map_or(false, |preview| preview.target == target);
+                                // Holding shift skips the preview, clicking the previewed tile again walks it
+                                if confirm_clicks && !ui.input(|i| i.modifiers.shift) && !previewed {
+                                    self.preview = Some(PathPreview::start(&bot, target));
+                                } else {
+                                    self.preview = None;
+                                    let bot_clone = bot.clone();
+                                    thread::spawn(move || {
+                                        toast_path(outgoing::user_initiated(|| {
+                                            bot_clone.find_path(world_x as u32, world_y as u32)
+                                        }));
+                                    });
+                                }
+                            }
+                        }
+                    }
+                }
+
+                // Drawn after every tile so avatars and names aren't covered by later cells
+                let to_screen = |position: Pos2| {
+                    Pos2::new(
+                        rect.min.x
+                            + (position.x / 32.0 - (camera_tile_x - tiles_in_view_x / 2) as f32)
+                                * cell_size
+                            - offset_x,
+                        rect.min.y
+                            + (position.y / 32.0 - (camera_tile_y - tiles_in_view_y / 2) as f32)
+                                * cell_size
+                            - offset_y,
+                    )
+                };
+                // Drops redden as they get close to despawning
+                for (obj, age) in bot.dropped_items_with_age() {
+                    let item = bot.item_database.read().unwrap().get_item(&(obj.id as u32));
+                    let item = match item {
+                        Some(item) => item,
+                        None => continue,
+                    };
+                    let fade = ((1.0 - drop_age::urgency(age)) * 255.0) as u8;
+                    let min = to_screen(Pos2::new(obj.x, obj.y));
+                    self.draw_texture(
+                        &draw_list,
+                        texture_manager,
+                        item.texture_x,
+                        item.texture_y,
+                        item.texture_file_name.clone(),
+                        min,
+                        min + egui::vec2(cell_size / 2.0, cell_size / 2.0),
+                        false,
+                        Color32::from_rgb(255, fade, fade),
+                    );
+                }
+
+                let friends = utils::config::get_friends();
+                for player in bot.players.lock().unwrap().iter() {
+                    let outline = if player.is_moderator() {
+                        Some(Color32::from_rgb(220, 53, 69))
+                    } else if friends
+                        .iter()
+                        .any(|friend| friend.eq_ignore_ascii_case(&player.name))
+                    {
+                        Some(Color32::from_rgb(255, 193, 7))
+                    } else {
+                        None
+                    };
+                    let min = to_screen(Pos2::new(player.position.x, player.position.y));
+                    self.draw_avatar(
+                        &draw_list,
+                        texture_manager,
+                        &bot.item_database.read().unwrap(),
+                        &player.clothing,
+                        &utils::textparse::strip_color(&player.name),
+                        Rect::from_min_size(min, egui::vec2(cell_size, cell_size)),
+                        outline,
+                    );
+                }
+                {
+                    let clothing = bot.state.lock().unwrap().clothing.clone();
+                    let name = bot.info.lock().unwrap().payload[0].clone();
+                    let position = bot.position.lock().unwrap().clone();
+                    let min = to_screen(Pos2::new(position.x, position.y));
+                    self.draw_avatar(
+                        &draw_list,
+                        texture_manager,
+                        &bot.item_database.read().unwrap(),
+                        &clothing,
+                        &name,
+                        Rect::from_min_size(min, egui::vec2(cell_size, cell_size)),
+                        Some(Color32::from_rgb(40, 167, 69)),
+                    );
+                }
+
+                if map_locked {
+                    draw_list.rect_filled(rect, 0.0, Color32::from_black_alpha(40));
+                    draw_list.text(
+                        Pos2::new(rect.max.x - 12.0, rect.min.y + 12.0),
+                        egui::Align2::RIGHT_TOP,
+                        egui_remixicon::icons::LOCK_FILL,
+                        egui::FontId::proportional(28.0),
+                        Color32::WHITE,
+                    );
+                }
+
+                let preview = self
+                    .preview
+                    .as_ref()
+                    .map(|preview| (preview.target, preview.path()));
+                if let Some(((x, y), path)) = preview {
+                    let tile_center = |x: u32, y: u32| {
+                        to_screen(Pos2::new(x as f32 * 32.0 + 16.0, y as f32 * 32.0 + 16.0))
+                    };
+                    let color = Color32::from_rgb(255, 193, 7);
+                    if let Some(Some(path)) = &path {
+                        let start = bot.position.lock().unwrap().clone();
+                        let mut points = vec![tile_center(
+                            (start.x / 32.0) as u32,
+                            (start.y / 32.0) as u32,
+                        )];
+                        points.extend(path.iter().map(|&(x, y)| tile_center(x, y)));
+                        draw_list.add(egui::Shape::line(points, egui::Stroke::new(3.0, color)));
+                    }
+                    draw_list.circle_stroke(tile_center(x, y), cell_size / 2.0, egui::Stroke::new(2.0, color));
+
+                    egui::Window::new("Confirm move")
+                        .collapsible(false)
+                        .resizable(false)
+                        .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+                        .show(ui.ctx(), |ui| {
+                            ui.label(format!("Walk {} to {},{}?", self.selected_bot, x, y));
+                            match &path {
+                                None => {
+                                    ui.horizontal(|ui| {
+                                        ui.spinner();
+                                        ui.label("Finding a path...");
+                                    });
+                                }
+                                Some(None) => {
+                                    ui.colored_label(Color32::from_rgb(220, 53, 69), "No path found");
+                                }
+                                Some(Some(path)) => {
+                                    let seconds =
+                                        path.len() as f32 * bot.step_delay() as f32 / 1000.0;
+                                    ui.label(format!("{} steps, about {:.1}s", path.len(), seconds));
+                                    let multiplier = bot.speed_multiplier();
+                                    if multiplier != 1.0 {
+                                        ui.label(
+                                            egui::RichText::new(format!(
+                                                "Pace calibrated to {:.2}x",
+                                                multiplier
+                                            ))
+                                            .weak(),
+                                        );
+                                    }
+                                }
+                            }
+                            ui.label(
+                                egui::RichText::new(
+                                    "Click the tile again to walk, hold shift to skip this",
+                                )
+                                .weak(),
+                            );
+                            ui.horizontal(|ui| {
+                                let walkable = matches!(path, Some(Some(_)));
+                                if ui.add_enabled(walkable, egui::Button::new("Walk")).clicked() {
+                                    let bot_clone = bot.clone();
+                                    thread::spawn(move || {
+                                        toast_path(outgoing::user_initiated(|| bot_clone.find_path(x, y)));
+                                    });
+                                    self.preview = None;
+                                }
+                                if ui.button("Cancel").clicked() {
+                                    self.preview = None;
+                                }
+                            });
+                        });
+                }
+
+                if let Some((x, y)) = self.door_menu {
+                    let mut open = true;
+                    egui::Window::new("Door")
+                        .open(&mut open)
+                        .collapsible(false)
+                        .resizable(false)
+                        .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+                        .show(ui.ctx(), |ui| {
+                            ui.label(format!("Door at {},{}", x, y));
+                            if ui.button("Enter door").clicked() {
                                 let bot_clone = bot.clone();
                                 thread::spawn(move || {
-                                    bot_clone.find_path(world_x as u32, world_y as u32);
+                                    if let Err(err) = outgoing::user_initiated(|| bot_clone.enter_door(x, y)) {
+                                        toasts::error(&format!("Can't enter door: {}", err));
+                                    }
                                 });
+                                self.door_menu = None;
                             }
-                        }
+                        });
+                    if !open {
+                        self.door_menu = None;
                     }
                 }
 
+                if self.lock_wizard.is_some() {
+                    self.render_lock_wizard(ui.ctx(), &bot, &world, &draw_list, &to_screen, cell_size);
+                }
+                if let Some(designer) = self.pattern_designer.as_mut() {
+                    let tile_rect = |x: u32, y: u32| {
+                        Rect::from_min_size(
+                            to_screen(Pos2::new(x as f32 * 32.0, y as f32 * 32.0)),
+                            egui::vec2(cell_size, cell_size),
+                        )
+                    };
+                    designer.draw_overlay(&draw_list, &world, &bot.item_database.read().unwrap(), &tile_rect);
+                    if !designer.render(ui.ctx(), &bot) {
+                        self.pattern_designer = None;
+                    }
+                }
+
+                if let Some(control) = self.manual_control.as_mut() {
+                    if control.handle_keys(ui.ctx(), &response, self.place_item) {
+                        let place_name = bot
+                            .item_database
+                            .read()
+                            .unwrap()
+                            .get_item(&self.place_item)
+                            .filter(|_| self.place_item != 0)
+                            .map(|item| item.name.clone());
+                        control.render_hint(ui.ctx(), rect, place_name.as_deref());
+                    } else {
+                        self.set_manual_control(false, manager);
+                    }
+                }
+
+                self.render_inspect(ui.ctx(), &world, &bot.item_database.read().unwrap());
+
+                let summary = bot.temporary_data.read().unwrap().world_summary.clone();
+                if let Some(summary) = summary {
+                    let key = (self.selected_bot.clone(), summary.name.clone());
+                    if self.dismissed_summary.as_ref() != Some(&key) {
+                        egui::Area::new(egui::Id::new("world_summary"))
+                            .fixed_pos(rect.min + egui::vec2(10.0, 10.0))
+                            .show(ui.ctx(), |ui| {
+                                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                                    ui.horizontal(|ui| {
+                                        ui.label(egui::RichText::new(summary.describe()).monospace());
+                                        if ui.small_button(egui_remixicon::icons::CLOSE_LINE).clicked() {
+                                            self.dismissed_summary = Some(key.clone());
+                                        }
+                                    });
+                                });
+                            });
+                    }
+                } else {
+                    self.dismissed_summary = None;
+                }
+
                 egui::Window::new("Movement")
                     .anchor(egui::Align2::RIGHT_BOTTOM, [0.0, 0.0])
                     .default_open(false)
@@ -449,28 +699,77 @@ impl WorldMap {
                             if ui.button("Up").clicked() {
                                 let bot_clone = bot.clone();
                                 thread::spawn(move || {
-                                    bot_clone.walk(0, -1, false);
+                                    outgoing::user_initiated(|| bot_clone.walk(0, -1, false));
                                 });
                             }
                             if ui.button("Down").clicked() {
                                 let bot_clone = bot.clone();
                                 thread::spawn(move || {
-                                    bot_clone.walk(0, 1, false);
+                                    outgoing::user_initiated(|| bot_clone.walk(0, 1, false));
                                 });
                             }
                             if ui.button("Left").clicked() {
                                 let bot_clone = bot.clone();
                                 thread::spawn(move || {
-                                    bot_clone.walk(-1, 0, false);
+                                    outgoing::user_initiated(|| bot_clone.walk(-1, 0, false));
                                 });
                             }
                             if ui.button("Right").clicked() {
                                 let bot_clone = bot.clone();
                                 thread::spawn(move || {
-                                    bot_clone.walk(1, 0, false);
+                                    outgoing::user_initiated(|| bot_clone.walk(1, 0, false));
                                 });
                             }
                             ui.add(egui::Slider::new(&mut self.zoom, 0.1..=2.0).text("Zoom"));
+                            ui.checkbox(&mut self.show_hazards, "Show hazards");
+                        });
+                        ui.horizontal(|ui| {
+                            let mut locked = map_locked;
+                            let icon = if locked {
+                                egui_remixicon::icons::LOCK_FILL
+                            } else {
+                                egui_remixicon::icons::LOCK_UNLOCK_FILL
+                            };
+                            if ui
+                                .toggle_value(&mut locked, format!("{} Lock interactions", icon))
+                                .changed()
+                            {
+                                utils::config::set_map_locked(locked);
+                                self.preview = None;
+                            }
+                            let mut confirm = confirm_clicks;
+                            if ui.checkbox(&mut confirm, "Confirm click actions").changed() {
+                                utils::config::set_map_click_confirmation(confirm);
+                            }
+                        });
+                    });
+
+                egui::Window::new("Players")
+                    .anchor(egui::Align2::RIGHT_BOTTOM, [0.0, 0.0])
+                    .default_open(false)
+                    .show(ui.ctx(), |ui| {
+                        let players = bot.players.lock().unwrap().clone();
+                        if players.is_empty() {
+                            ui.label("Nobody else here");
+                        }
+                        egui::Grid::new("world_players").striped(true).show(ui, |ui| {
+                            for player in players {
+                                let name = utils::textparse::strip_color(&player.name);
+                                if player.is_moderator() {
+                                    ui.colored_label(Color32::from_rgb(220, 53, 69), name);
+                                } else {
+                                    ui.label(name);
+                                }
+                                ui.label(player.title.name());
+                                ui.label(if player.level == 0 {
+                                    String::new()
+                                } else {
+                                    format!("lvl {}", player.level)
+                                });
+                                ui.label(&player.guild);
+                                ui.label(&player.country);
+                                ui.end_row();
+                            }
                         });
                     });
 
@@ -495,6 +794,856 @@ impl WorldMap {
         }
     }
 
+    fn render_planner(
+        &mut self,
+        ui: &mut Ui,
+        item_database: &Arc<RwLock<ItemDatabase>>,
+        texture_manager: &TextureManager,
+    ) {
+        if self.zoom == 0.0 {
+            self.zoom = 0.5;
+        }
+        let mut replan = false;
+        ui.horizontal(|ui| {
+            let mut open = None;
+            egui::ComboBox::from_id_salt("planner_snapshot")
+                .selected_text(if self.planner.snapshot.is_empty() {
+                    "Open snapshot".to_string()
+                } else {
+                    self.planner.snapshot.clone()
+                })
+                .show_ui(ui, |ui| {
+                    for name in world_snapshot::list() {
+                        if ui.selectable_label(self.planner.snapshot == name, &name).clicked() {
+                            open = Some(name);
+                        }
+                    }
+                });
+            if let Some(name) = open {
+                self.planner.open(&name, item_database);
+            }
+            if self.planner.world.is_some() {
+                let utc_offset = utils::config::get_stats_utc_offset();
+                let format_saved = |saved_at: u64| {
+                    format!(
+                        "{} {}",
+                        utils::date::format_day(utils::date::day_index(saved_at, utc_offset, 0)),
+                        utils::date::format_time(saved_at, utc_offset)
+                    )
+                };
+                let mut compare = None;
+                egui::ComboBox::from_id_salt("planner_compare")
+                    .selected_text(self.planner.compare.map_or("Compare with".to_string(), format_saved))
+                    .show_ui(ui, |ui| {
+                        for saved_at in world_snapshot::history(&self.planner.snapshot) {
+                            if ui
+                                .selectable_label(self.planner.compare == Some(saved_at), format_saved(saved_at))
+                                .clicked()
+                            {
+                                compare = Some(saved_at);
+                            }
+                        }
+                    });
+                if let Some(saved_at) = compare {
+                    self.planner.compare_with(saved_at, item_database);
+                }
+                ui.add_enabled(
+                    self.planner.earlier.is_some(),
+                    egui::Checkbox::new(&mut self.planner.show_earlier, "Show earlier"),
+                );
+            }
+            egui::ComboBox::from_id_salt("planner_hazards")
+                .selected_text(format!("Hazards: {:?}", self.planner.hazard_mode))
+                .show_ui(ui, |ui| {
+                    for mode in [EHazardMode::Penalty, EHazardMode::Ignore, EHazardMode::Block] {
+                        replan |= ui
+                            .selectable_value(&mut self.planner.hazard_mode, mode, format!("{:?}", mode))
+                            .changed();
+                    }
+                });
+            ui.checkbox(&mut self.show_hazards, "Show hazards");
+            ui.add(egui::Slider::new(&mut self.zoom, 0.1..=2.0).text("Zoom"));
+            if ui.button("Clear").clicked() {
+                self.planner.start = None;
+                self.planner.goals.clear();
+                replan = true;
+            }
+        });
+        ui.horizontal(|ui| {
+            let blocked = self.planner.legs.iter().filter(|leg| leg.is_none()).count();
+            let steps: usize = self.planner.legs.iter().flatten().map(|leg| leg.len()).sum();
+            if blocked > 0 {
+                ui.colored_label(
+                    Color32::from_rgb(220, 53, 69),
+                    format!("{} legs without a path", blocked),
+                );
+            } else {
+                ui.label(format!("{} steps, cost {}", steps, self.planner.cost));
+            }
+            ui.separator();
+            ui.add(
+                egui::TextEdit::singleline(&mut self.planner.route_name)
+                    .hint_text("Route name")
+                    .desired_width(120.0),
+            );
+            let savable = !self.planner.route_name.trim().is_empty()
+                && self.planner.start.is_some()
+                && !self.planner.goals.is_empty();
+            if ui.add_enabled(savable, egui::Button::new("Save route")).clicked() {
+                // A bot walks to the start first, wherever it stands when the route runs
+                let mut waypoints: Vec<(u32, u32)> = self.planner.start.into_iter().collect();
+                waypoints.extend(self.planner.goals.iter().copied());
+                utils::config::save_route(Route {
+                    name: self.planner.route_name.trim().to_string(),
+                    world: self.planner.snapshot.clone(),
+                    waypoints,
+                });
+            }
+            ui.menu_button("Saved routes", |ui| {
+                for route in utils::config::get_routes() {
+                    ui.horizontal(|ui| {
+                        if ui.button(format!("{} ({})", route.name, route.world)).clicked() {
+                            self.planner.open(&route.world, item_database);
+                            self.planner.start = route.waypoints.first().copied();
+                            self.planner.goals = route.waypoints.iter().skip(1).copied().collect();
+                            self.planner.route_name = route.name.clone();
+                            replan = true;
+                            ui.close_menu();
+                        }
+                        if ui.small_button(egui_remixicon::icons::DELETE_BIN_LINE).clicked() {
+                            utils::config::remove_route(&route.name);
+                        }
+                    });
+                }
+            });
+        });
+        if let Some(error) = &self.planner.error {
+            ui.colored_label(Color32::from_rgb(220, 53, 69), error);
+        }
+
+        let (rect, response) = ui.allocate_exact_size(ui.available_size(), egui::Sense::click_and_drag());
+        let draw_list = ui.painter_at(rect);
+        draw_list.rect_filled(rect, 0.0, Color32::from_rgb(96, 215, 255));
+        if response.dragged() {
+            self.planner.camera_pos -= response.drag_delta() / self.zoom;
+        }
+        let shown = if self.planner.show_earlier {
+            &self.planner.earlier
+        } else {
+            &self.planner.world
+        };
+        let world = match shown {
+            Some(world) => world,
+            None => {
+                draw_list.text(
+                    rect.center(),
+                    egui::Align2::CENTER_CENTER,
+                    "Pick a world snapshot, every world a bot enters is saved",
+                    egui::FontId::proportional(16.0),
+                    Color32::WHITE,
+                );
+                return;
+            }
+        };
+
+        let cell_size = 32.0 * self.zoom;
+        let origin = rect.center() - self.planner.camera_pos.to_vec2() * self.zoom;
+        let tile_rect = |x: u32, y: u32| {
+            Rect::from_min_size(
+                origin + egui::vec2(x as f32 * cell_size, y as f32 * cell_size),
+                egui::vec2(cell_size, cell_size),
+            )
+        };
+        let first_x = ((rect.min.x - origin.x) / cell_size).floor().max(0.0) as u32;
+        let first_y = ((rect.min.y - origin.y) / cell_size).floor().max(0.0) as u32;
+        let last_x = (((rect.max.x - origin.x) / cell_size).ceil().max(0.0) as u32).min(world.width);
+        let last_y = (((rect.max.y - origin.y) / cell_size).ceil().max(0.0) as u32).min(world.height);
+        let time = ui.input(|i| i.time);
+        for y in first_y..last_y {
+            for x in first_x..last_x {
+                if y * world.width + x < world.tile_count {
+                    self.draw_tile(&draw_list, texture_manager, item_database, world, (x, y), tile_rect(x, y), time);
+                }
+            }
+        }
+
+        let color = Color32::from_rgb(255, 193, 7);
+        for leg in self.planner.legs.iter().flatten() {
+            let points = leg.iter().map(|&(x, y)| tile_rect(x, y).center()).collect();
+            draw_list.add(egui::Shape::line(points, egui::Stroke::new(3.0, color)));
+        }
+        if let Some((x, y)) = self.planner.start {
+            draw_list.circle_filled(tile_rect(x, y).center(), cell_size / 3.0, Color32::from_rgb(40, 167, 69));
+        }
+        for (index, &(x, y)) in self.planner.goals.iter().enumerate() {
+            let center = tile_rect(x, y).center();
+            let failed = self.planner.legs.get(index).map_or(false, |leg| leg.is_none());
+            let stroke_color = if failed { Color32::from_rgb(220, 53, 69) } else { color };
+            draw_list.circle_stroke(center, cell_size / 2.0, egui::Stroke::new(2.0, stroke_color));
+            draw_list.text(
+                center,
+                egui::Align2::CENTER_CENTER,
+                (index + 1).to_string(),
+                egui::FontId::proportional(14.0),
+                Color32::WHITE,
+            );
+        }
+
+        if let Some(diff) = &self.planner.diff {
+            let changed = egui::Stroke::new(2.0, Color32::from_rgb(255, 0, 255));
+            for change in &diff.tiles {
+                draw_list.rect_stroke(tile_rect(change.x, change.y), 0.0, changed);
+            }
+            for &(x, y, _) in &diff.locks_added {
+                draw_list.rect_stroke(tile_rect(x, y), 0.0, egui::Stroke::new(3.0, Color32::from_rgb(40, 167, 69)));
+            }
+            for &(x, y, _) in &diff.locks_removed {
+                draw_list.rect_stroke(tile_rect(x, y), 0.0, egui::Stroke::new(3.0, Color32::from_rgb(220, 53, 69)));
+            }
+            self.render_diff(ui.ctx(), diff);
+        }
+
+        // Left click sets the start then adds goals, right click takes the last one back
+        let hovered = response.hover_pos().map(|pos| {
+            let tile = (pos - origin) / cell_size;
+            (tile.x.floor() as i64, tile.y.floor() as i64)
+        });
+        let (width, height) = (world.width as i64, world.height as i64);
+        if let Some((x, y)) = hovered.filter(|&(x, y)| x >= 0 && y >= 0 && x < width && y < height) {
+            let tile = (x as u32, y as u32);
+            if response.clicked() {
+                if self.planner.start.is_none() {
+                    self.planner.start = Some(tile);
+                } else {
+                    self.planner.goals.push(tile);
+                }
+                replan = true;
+            }
+        }
+        if response.secondary_clicked() {
+            if self.planner.goals.pop().is_none() {
+                self.planner.start = None;
+            }
+            replan = true;
+        }
+        if replan {
+            self.planner.replan();
+        }
+    }
+
+    fn render_diff(&self, ctx: &egui::Context, diff: &WorldDiff) {
+        let lines = &self.planner.diff_lines;
+        egui::Window::new("Changes")
+            .anchor(egui::Align2::RIGHT_TOP, [0.0, 0.0])
+            .default_width(280.0)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(format!("{} tiles changed", diff.tiles.len()));
+                    if ui.button("Export").clicked() {
+                        let path = format!("world_diff_{}.txt", self.planner.snapshot);
+                        let saved_at = self.planner.compare.unwrap_or_default();
+                        let title = format!(
+                            "{} since {} {} UTC",
+                            self.planner.snapshot,
+                            utils::date::format_day(utils::date::day_index(saved_at, 0, 0)),
+                            utils::date::format_time(saved_at, 0)
+                        );
+                        match utils::export::write_world_diff(&path, &title, lines) {
+                            Ok(()) => toasts::info(&format!("Saved {}", path)),
+                            Err(err) => toasts::error(&format!("Failed to export changes: {}", err)),
+                        }
+                    }
+                });
+                ui.separator();
+                if diff.is_empty() {
+                    ui.label("Nothing changed");
+                }
+                egui::ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                    for line in lines {
+                        ui.label(egui::RichText::new(line).monospace());
+                    }
+                });
+            });
+    }
+
+    // Called every frame after the content is drawn. Another tab, the route planner or a bot
+    // that went away all hide the map, and keys can't reach a map that isn't there
+    pub fn end_hidden_manual_control(&mut self, ctx: &egui::Context) {
+        if self.map_frame != ctx.frame_nr() {
+            if let Some(control) = self.manual_control.take() {
+                control.stop();
+            }
+        }
+    }
+
+    fn set_manual_control(&mut self, on: bool, manager: &Arc<RwLock<BotManager>>) {
+        if let Some(control) = self.manual_control.take() {
+            control.stop();
+        }
+        if on {
+            let bot = manager.read().unwrap().get_bot(&self.selected_bot);
+            self.manual_control = bot.map(|bot| ManualControl::start(&self.selected_bot, bot));
+        }
+    }
+
+    // Shades the tiles existing area locks own and what the chosen lock would cover
+    fn render_lock_wizard(
+        &mut self,
+        ctx: &egui::Context,
+        bot: &Arc<Bot>,
+        world: &gtworld_r::World,
+        draw_list: &Painter,
+        to_screen: &dyn Fn(Pos2) -> Pos2,
+        cell_size: f32,
+    ) {
+        let wizard = match self.lock_wizard.as_mut() {
+            Some(wizard) => wizard,
+            None => return,
+        };
+        let tile_rect = |x: u32, y: u32| {
+            Rect::from_min_size(
+                to_screen(Pos2::new(x as f32 * 32.0, y as f32 * 32.0)),
+                egui::vec2(cell_size, cell_size),
+            )
+        };
+        let claimed = lock_area::claimed(world);
+        for &(x, y) in &claimed {
+            draw_list.rect_filled(tile_rect(x, y), 0.0, Color32::from_rgba_unmultiplied(220, 53, 69, 60));
+        }
+
+        let mut warnings = Vec::new();
+        let mut placeable = false;
+        if let Some((x, y)) = wizard.target {
+            let coverage = lock_area::coverage(world.width, world.height, (x, y), wizard.size, |tile_x, tile_y| {
+                claimed.contains(&(tile_x, tile_y))
+                    || world
+                        .get_tile(tile_x, tile_y)
+                        .map_or(false, |tile| matches!(tile.tile_type, TileType::Lock { .. }))
+            });
+            for &(tile_x, tile_y) in &coverage {
+                draw_list.rect_filled(
+                    tile_rect(tile_x, tile_y),
+                    0.0,
+                    Color32::from_rgba_unmultiplied(0, 123, 255, 70),
+                );
+            }
+            draw_list.rect_stroke(
+                tile_rect(x, y),
+                0.0,
+                egui::Stroke::new(2.0, Color32::from_rgb(0, 123, 255)),
+            );
+
+            let occupied = world
+                .get_tile(x, y)
+                .map_or(true, |tile| tile.foreground_item_id != 0);
+            if claimed.contains(&(x, y)) {
+                warnings.push("The tile is inside an existing lock's area".to_string());
+            } else if occupied {
+                warnings.push("The tile is not empty".to_string());
+            } else {
+                placeable = true;
+            }
+            if coverage.len() < wizard.size.tiles() {
+                warnings.push(format!(
+                    "Only {} of {} tiles, the rest overlaps other locks or the world edge",
+                    coverage.len(),
+                    wizard.size.tiles()
+                ));
+            }
+        }
+        let item_database = bot.item_database.read().unwrap();
+        let world_locked = world.tiles.iter().any(|tile| {
+            matches!(tile.tile_type, TileType::Lock { .. })
+                && item_database
+                    .get_item(&(tile.foreground_item_id as u32))
+                    .map_or(false, |item| item.name.contains("World Lock"))
+        });
+        if world_locked {
+            warnings.push("The world is world locked, only its owner can place area locks".to_string());
+        }
+        let owned = bot
+            .inventory
+            .lock()
+            .unwrap()
+            .items
+            .get(&(wizard.size.item_id() as u16))
+            .map_or(0, |item| item.amount);
+
+        let mut open = true;
+        egui::Window::new("Place lock")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::RIGHT_TOP, [-10.0, 10.0])
+            .show(ctx, |ui| {
+                egui::ComboBox::from_id_salt("lock_size")
+                    .selected_text(wizard.size.name())
+                    .show_ui(ui, |ui| {
+                        for size in LockSize::ALL {
+                            ui.selectable_value(&mut wizard.size, size, size.name());
+                        }
+                    });
+                ui.label(format!("{} in the inventory", owned));
+                match wizard.target {
+                    Some((x, y)) => ui.label(format!("Target {},{}", x, y)),
+                    None => ui.label(egui::RichText::new("Click a tile on the map").weak()),
+                };
+                for warning in &warnings {
+                    ui.colored_label(Color32::from_rgb(255, 193, 7), warning);
+                }
+                ui.separator();
+                let mut public = utils::config::get_lock_public();
+                if ui.checkbox(&mut public, "Public").changed() {
+                    utils::config::set_lock_public(public);
+                }
+                ui.horizontal(|ui| {
+                    ui.label("Access");
+                    let response = ui.add(
+                        egui::TextEdit::singleline(&mut wizard.access)
+                            .hint_text("names, comma separated")
+                            .desired_width(160.0),
+                    );
+                    if response.lost_focus() {
+                        utils::config::set_lock_access(
+                            wizard
+                                .access
+                                .split(',')
+                                .map(|name| name.trim().to_string())
+                                .filter(|name| !name.is_empty())
+                                .collect(),
+                        );
+                    }
+                });
+                if ui
+                    .add_enabled(placeable && owned > 0, egui::Button::new("Place"))
+                    .clicked()
+                {
+                    let (x, y) = wizard.target.unwrap_or_default();
+                    let (bot, size) = (bot.clone(), wizard.size);
+                    thread::spawn(move || {
+                        if let Err(err) = outgoing::user_initiated(|| place_lock::run(&bot, size, x, y)) {
+                            toasts::error(&format!("Can't place lock: {}", err));
+                        }
+                    });
+                    wizard.target = None;
+                }
+            });
+        if !open {
+            self.lock_wizard = None;
+        }
+    }
+
+    // Foreground, background and parsed extra data of the ctrl+clicked tile
+    fn render_inspect(&mut self, ctx: &egui::Context, world: &gtworld_r::World, item_database: &ItemDatabase) {
+        let (x, y) = match self.inspecting {
+            Some(position) => position,
+            None => return,
+        };
+        let tile = match world.get_tile(x, y) {
+            Some(tile) => tile,
+            None => {
+                self.inspecting = None;
+                return;
+            }
+        };
+        let item_name = |item_id: u32| {
+            item_database
+                .get_item(&item_id)
+                .map_or(format!("#{}", item_id), |item| item.name.clone())
+        };
+
+        let mut open = true;
+        egui::Window::new(format!("Tile {},{}", x, y))
+            .id(egui::Id::new("tile_inspect"))
+            .open(&mut open)
+            .resizable(true)
+            .show(ctx, |ui| {
+                egui::Grid::new("tile_inspect_grid").num_columns(2).show(ui, |ui| {
+                    ui.label("Foreground");
+                    ui.label(item_name(tile.foreground_item_id as u32));
+                    ui.end_row();
+                    ui.label("Background");
+                    ui.label(item_name(tile.background_item_id as u32));
+                    ui.end_row();
+                    if let Some(extra) = TileExtra::from_tile_type(&tile.tile_type) {
+                        for (label, value) in extra.fields(item_database) {
+                            ui.label(label);
+                            ui.add(egui::Label::new(egui::RichText::new(value).monospace()).wrap());
+                            ui.end_row();
+                        }
+                    }
+                });
+                if ui.button("Use for repeat activate").clicked() {
+                    utils::config::update_bot_config(&self.selected_bot, |bot_config| {
+                        bot_config.repeat_tile = Some((x, y));
+                    });
+                    toasts::info(&format!("Repeat activate tile set to {},{}", x, y));
+                }
+                ui.horizontal(|ui| {
+                    if ui
+                        .button("Use as spawn spot")
+                        .on_hover_text("Bots walk here after entering this world instead of stepping off the door")
+                        .clicked()
+                    {
+                        utils::config::set_spawn_spot(&world.name, Some((x, y)));
+                        toasts::info(&format!("Spawn spot of {} set to {},{}", world.name, x, y));
+                    }
+                    if utils::config::get_spawn_spot(&world.name) == Some((x, y)) && ui.button("Clear").clicked() {
+                        utils::config::set_spawn_spot(&world.name, None);
+                    }
+                });
+            });
+        if !open {
+            self.inspecting = None;
+        }
+    }
+
+    // Background, foreground and hazard overlay of one tile, shared by the live map and the planner
+    fn draw_tile(
+        &self,
+        draw_list: &Painter,
+        texture_manager: &TextureManager,
+        item_database: &Arc<RwLock<ItemDatabase>>,
+        world: &gtworld_r::World,
+        (world_x, world_y): (u32, u32),
+        cell: Rect,
+        time: f64,
+    ) {
+        let (cell_min, cell_max) = (cell.min, cell.max);
+        let cell_size = cell.width();
+        let (world_x, world_y) = (world_x as i32, world_y as i32);
+        let tile = world.get_tile(world_x as u32, world_y as u32).unwrap();
+        let item = {
+            let item_database = item_database.read().unwrap();
+            item_database
+                .get_item(&(tile.foreground_item_id as u32))
+                .unwrap()
+        };
+
+        if tile.background_item_id != 0 {
+            let item_database = item_database.read().unwrap();
+            let background_item = item_database
+                .get_item(&((tile.background_item_id + 1) as u32))
+                .unwrap();
+
+            self.draw_texture(
+                draw_list,
+                texture_manager,
+                background_item.texture_x,
+                background_item.texture_y,
+                background_item.texture_file_name.clone(),
+                cell_min,
+                cell_max,
+                tile.flags.flipped_x,
+                Color32::WHITE,
+            );
+        }
+
+        if item.id != 0 {
+            let mut texture_x = item.texture_x;
+            let mut texture_y = item.texture_y;
+            let texture_name = item.texture_file_name.clone();
+
+            let left_tile = if world_x > 0 {
+                world.get_tile(world_x as u32 - 1, world_y as u32)
+            } else {
+                None
+            };
+            let right_tile = if world_x < world.width as i32 - 1 {
+                world.get_tile(world_x as u32 + 1, world_y as u32)
+            } else {
+                None
+            };
+            let top_tile = if world_y > 0 {
+                world.get_tile(world_x as u32, world_y as u32 - 1)
+            } else {
+                None
+            };
+            let bottom_tile = if world_y < world.height as i32 - 1 {
+                world.get_tile(world_x as u32, world_y as u32 + 1)
+            } else {
+                None
+            };
+
+            if item.render_type == 2 {
+                if let (
+                    Some(left_tile),
+                    Some(right_tile),
+                    Some(top_tile),
+                    Some(bottom_tile),
+                ) = (left_tile, right_tile, top_tile, bottom_tile)
+                {
+                    let left_match = left_tile.foreground_item_id == item.id as u16;
+                    let right_match =
+                        right_tile.foreground_item_id == item.id as u16;
+                    let top_match = top_tile.foreground_item_id == item.id as u16;
+                    let bottom_match =
+                        bottom_tile.foreground_item_id == item.id as u16;
+
+                    match (left_match, right_match, top_match, bottom_match) {
+                        (true, true, true, true) => (),
+                        (true, true, true, false) => texture_x += 2,
+                        (true, true, false, true) => texture_x += 1,
+                        (true, false, true, true) => texture_x += 4,
+                        (false, true, true, true) => texture_x += 3,
+                        (true, true, false, false) => texture_x += 1,
+                        (true, false, false, true) => texture_x += 6,
+                        (false, true, true, false) => texture_x += 7,
+                        (false, true, false, true) => texture_x += 5,
+                        (true, false, false, false) => texture_x += 6,
+                        (false, false, false, true) => {
+                            texture_x += 2;
+                            texture_y += 1;
+                        }
+                        (false, true, false, false) => texture_x += 5,
+                        _ => (),
+                    }
+                }
+
+                if let (None, Some(right_tile), Some(top_tile), Some(bottom_tile)) =
+                    (left_tile, right_tile, top_tile, bottom_tile)
+                {
+                    let right_match =
+                        right_tile.foreground_item_id == item.id as u16;
+                    let bottom_match =
+                        bottom_tile.foreground_item_id == item.id as u16;
+                    let top_match = top_tile.foreground_item_id != item.id as u16;
+
+                    if right_match && bottom_match && top_match {
+                        texture_x += 1;
+                    }
+                }
+
+                if let (Some(left_tile), None, Some(top_tile), Some(bottom_tile)) =
+                    (left_tile, right_tile, top_tile, bottom_tile)
+                {
+                    let left_match = left_tile.foreground_item_id == item.id as u16;
+                    let bottom_match =
+                        bottom_tile.foreground_item_id == item.id as u16;
+                    let top_match = top_tile.foreground_item_id != item.id as u16;
+
+                    if left_match && bottom_match && top_match {
+                        texture_x += 1;
+                    }
+                }
+            }
+
+            if item.render_type == 7 {
+                if let (Some(top_tile), Some(bottom_tile)) = (top_tile, bottom_tile)
+                {
+                    if top_tile.foreground_item_id != item.id as u16
+                        && bottom_tile.foreground_item_id == item.id as u16
+                    {
+                        texture_x += 2;
+                    }
+                    if top_tile.foreground_item_id == item.id as u16
+                        && bottom_tile.foreground_item_id == item.id as u16
+                    {
+                        texture_x += 1;
+                    }
+                    if top_tile.foreground_item_id != item.id as u16
+                        && bottom_tile.foreground_item_id != item.id as u16
+                    {
+                        texture_x += 3;
+                    }
+                }
+            }
+
+            if item.render_type == 3 {
+                if let (Some(left_tile), Some(right_tile), Some(top_tile)) =
+                    (left_tile, right_tile, top_tile)
+                {
+                    if left_tile.foreground_item_id == item.id as u16
+                        && right_tile.foreground_item_id == item.id as u16
+                    {
+                        texture_x += 1;
+                    }
+                    if left_tile.foreground_item_id != item.id as u16
+                        || right_tile.foreground_item_id != item.id as u16
+                    {
+                        if top_tile.foreground_item_id == 8986 {
+                            texture_x += 4;
+                        }
+                    }
+                }
+            }
+
+            if item.render_type == 5 {
+                if let (
+                    Some(left_tile),
+                    Some(right_tile),
+                    Some(top_tile),
+                    Some(bottom_tile),
+                ) = (left_tile, right_tile, top_tile, bottom_tile)
+                {
+                    let left_match = left_tile.foreground_item_id == item.id as u16;
+                    let right_match =
+                        right_tile.foreground_item_id == item.id as u16;
+                    let top_match = top_tile.foreground_item_id == item.id as u16;
+                    let bottom_match =
+                        bottom_tile.foreground_item_id == item.id as u16;
+
+                    if (left_match && !right_match && !top_match && !bottom_match)
+                        || (!left_match
+                            && right_match
+                            && !top_match
+                            && !bottom_match)
+                    {
+                        texture_x += 7;
+                    }
+                }
+            }
+
+            if item.id % 2 != 0 {
+                let (b, g, r, a) = utils::color::extract_bgra(item.overlay_color);
+                let (spread_x, spread_y) = match item.render_type {
+                    2 | 5 => (4.0, 1.0),
+                    4 => (4.0, 0.0),
+                    3 | 7 | 8 | 9 | 10 => (3.0, 0.0),
+                    _ => (0.0, 0.0),
+                };
+
+                self.draw_texture(
+                    draw_list,
+                    texture_manager,
+                    item.tree_base_sprite,
+                    19,
+                    "tiles_page1.rttex".to_string(),
+                    cell_min,
+                    cell_max,
+                    tile.flags.flipped_x,
+                    Color32::WHITE,
+                );
+                self.draw_texture(
+                    draw_list,
+                    texture_manager,
+                    item.tree_overlay_sprite,
+                    18,
+                    "tiles_page1.rttex".to_string(),
+                    cell_min,
+                    cell_max,
+                    tile.flags.flipped_x,
+                    Color32::from_rgba_unmultiplied(r, g, b, a),
+                );
+                let new_cell_min = Pos2::new(
+                    cell_min.x + cell_size * 0.375,
+                    cell_min.y + cell_size * 0.375,
+                );
+                let new_cell_max = Pos2::new(
+                    cell_max.x - cell_size * 0.375,
+                    cell_max.y - cell_size * 0.375,
+                );
+
+                self.draw_texture(
+                    draw_list,
+                    texture_manager,
+                    texture_x + spread_x as u8,
+                    texture_y + spread_y as u8,
+                    texture_name,
+                    new_cell_min,
+                    new_cell_max,
+                    tile.flags.flipped_x,
+                    Color32::WHITE,
+                );
+
+                draw_list.rect_stroke(
+                    Rect::from_min_max(new_cell_min, new_cell_max),
+                    0.2,
+                    (2.0, Color32::WHITE),
+                );
+            } else {
+                if let Some((frames, frame_ms)) = animation(&item.name) {
+                    texture_x += animation_frame(time, frames, frame_ms);
+                    // Only maps showing an animated tile keep redrawing, at its frame rate
+                    draw_list.ctx().request_repaint_after(std::time::Duration::from_millis(frame_ms));
+                }
+                self.draw_texture(
+                    draw_list,
+                    texture_manager,
+                    texture_x,
+                    texture_y,
+                    texture_name,
+                    cell_min,
+                    cell_max,
+                    tile.flags.flipped_x,
+                    Color32::WHITE,
+                );
+            }
+
+            if self.show_hazards && hazard::is_harmful(&item) {
+                draw_list.rect_filled(
+                    Rect::from_min_max(cell_min, cell_max),
+                    0.0,
+                    Color32::from_rgba_unmultiplied(220, 53, 69, 70),
+                );
+            }
+        }
+    }
+
+    fn draw_avatar(
+        &self,
+        draw_list: &Painter,
+        texture_manager: &TextureManager,
+        item_database: &ItemDatabase,
+        clothing: &Clothing,
+        name: &str,
+        cell: Rect,
+        outline: Option<Color32>,
+    ) {
+        let skin = if clothing.skin_color == 0 {
+            Color32::WHITE
+        } else {
+            let (b, g, r, _) = utils::color::extract_bgra(clothing.skin_color);
+            Color32::from_rgb(r, g, b)
+        };
+        let has_body = self.draw_sprite(
+            draw_list,
+            texture_manager,
+            BODY_SPRITE.0,
+            BODY_SPRITE.1,
+            BODY_TEXTURE,
+            egui::vec2(BODY_SPRITE_SIZE, BODY_SPRITE_SIZE),
+            cell.min,
+            cell.max,
+            false,
+            skin,
+        );
+        if has_body {
+            for item_id in clothing.layers() {
+                if item_id == 0 {
+                    continue;
+                }
+                if let Some(item) = item_database.get_item(&item_id) {
+                    self.draw_texture(
+                        draw_list,
+                        texture_manager,
+                        item.texture_x,
+                        item.texture_y,
+                        item.texture_file_name.clone(),
+                        cell.min,
+                        cell.max,
+                        false,
+                        Color32::WHITE,
+                    );
+                }
+            }
+        } else {
+            // Textures aren't extracted, keep the old flat marker
+            draw_list.rect_filled(cell.shrink(cell.width() * 0.25), 0.0, skin);
+        }
+
+        if let Some(color) = outline {
+            draw_list.rect_stroke(cell, 2.0, (2.0, color));
+        }
+        draw_list.text(
+            Pos2::new(cell.center().x, cell.min.y - 2.0),
+            egui::Align2::CENTER_BOTTOM,
+            name,
+            egui::FontId::proportional(12.0),
+            outline.unwrap_or(Color32::WHITE),
+        );
+    }
+
     fn draw_texture(
         &self,
         draw_list: &Painter,
@@ -507,24 +1656,44 @@ impl WorldMap {
         flipped: bool,
         color: Color32,
     ) {
-        match texture_manager.get_texture(&texture_name) {
-            Some(texture) => {
-                let [width, height] = texture.size();
-                let uv_x_start = (texture_x as f32 * 32.0) / width as f32;
-                let uv_y_start = (texture_y as f32 * 32.0) / height as f32;
-                let uv_x_end = ((texture_x as f32 * 32.0) + 32.0) / width as f32;
-                let uv_y_end = ((texture_y as f32 * 32.0) + 32.0) / height as f32;
+        self.draw_sprite(
+            draw_list,
+            texture_manager,
+            texture_x,
+            texture_y,
+            &texture_name,
+            egui::vec2(32.0, 32.0),
+            cell_min,
+            cell_max,
+            flipped,
+            color,
+        );
+    }
 
+    // Returns false when the texture isn't loaded so callers can fall back
+    fn draw_sprite(
+        &self,
+        draw_list: &Painter,
+        texture_manager: &TextureManager,
+        texture_x: u8,
+        texture_y: u8,
+        texture_name: &str,
+        sprite_size: egui::Vec2,
+        cell_min: Pos2,
+        cell_max: Pos2,
+        flipped: bool,
+        color: Color32,
+    ) -> bool {
+        match texture_manager.get_texture(texture_name) {
+            Some(texture) => {
+                let uv = sprite_uv(texture, texture_x, texture_y, sprite_size);
                 let (uv_start, uv_end) = if flipped {
                     (
-                        egui::Pos2::new(uv_x_end, uv_y_start),
-                        egui::Pos2::new(uv_x_start, uv_y_end),
+                        egui::Pos2::new(uv.max.x, uv.min.y),
+                        egui::Pos2::new(uv.min.x, uv.max.y),
                     )
                 } else {
-                    (
-                        egui::Pos2::new(uv_x_start, uv_y_start),
-                        egui::Pos2::new(uv_x_end, uv_y_end),
-                    )
+                    (uv.min, uv.max)
                 };
 
                 let cell_min = Pos2::new(cell_min.x.round(), cell_min.y.round());
@@ -539,8 +1708,38 @@ impl WorldMap {
                     egui::Rect::from_min_max(uv_start, uv_end),
                     color,
                 );
+                true
             }
-            None => (),
+            None => false,
+        }
+    }
+}
+
+fn describe_player(player: &Player) -> String {
+    let mut lines = vec![
+        format!("Player: {}", utils::textparse::strip_color(&player.name)),
+        format!("Title: {}", player.title.name()),
+        format!("User id: {}", player.user_id),
+        format!("Country: {}", player.country),
+    ];
+    if player.level != 0 {
+        lines.push(format!("Level: {}", player.level));
+    }
+    if !player.guild.is_empty() {
+        lines.push(format!("Guild: {}", player.guild));
+    }
+    if player.invis {
+        lines.push("Invisible".to_string());
+    }
+    lines.join("\n")
+}
+
+fn toast_path(result: Result<PathHandle, PathError>) {
+    match result {
+        Ok(PathHandle::ReachedNearby { distance, .. }) => {
+            toasts::info(&format!("Target out of reach, stopped {} tiles away", distance))
         }
+        Ok(PathHandle::Reached) => {}
+        Err(err) => toasts::error(&format!("Can't walk there: {}", err)),
     }
 }
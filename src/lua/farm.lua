@@ -0,0 +1,59 @@
+local inv = require("mori.inv")
+local util = require("mori.util")
+
+local farm = {}
+
+local MAX_HITS = 20
+
+-- `rows` is either a number of tiles stacked upwards in front of the bot or a list of {x, y} offsets
+local function offsets(rows)
+    if type(rows) == "table" then
+        return rows
+    end
+    local result = {}
+    for i = 0, (rows or 1) - 1 do
+        table.insert(result, { 1, -i })
+    end
+    return result
+end
+
+-- nil left of or above the world, `bot.get_tile` only takes positive coordinates
+local function tileAt(offset)
+    local me = bot.get_local()
+    local x = math.floor(me.x / 32) + offset[1]
+    local y = math.floor(me.y / 32) + offset[2]
+    if x < 0 or y < 0 then
+        return nil
+    end
+    return bot.get_tile(x, y)
+end
+
+-- Punch-and-break: place the block on every offset, then punch each one until it breaks.
+-- Repeats while the inventory holds enough blocks for a full pattern
+function farm.pnb(itemId, rows, delay)
+    local pattern = offsets(rows)
+    delay = delay or 200
+    while inv.has(itemId, #pattern) do
+        for _, offset in ipairs(pattern) do
+            local tile = tileAt(offset)
+            if tile and tile.fg == 0 then
+                bot.place(offset[1], offset[2], itemId)
+                bot.sleep(delay)
+            end
+        end
+        for _, offset in ipairs(pattern) do
+            local hits = 0
+            util.waitFor(function()
+                local tile = tileAt(offset)
+                if not tile or tile.fg == 0 or hits >= MAX_HITS then
+                    return true
+                end
+                bot.punch(offset[1], offset[2])
+                hits = hits + 1
+                return false
+            end, nil, delay)
+        end
+    end
+end
+
+return farm
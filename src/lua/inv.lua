@@ -0,0 +1,15 @@
+local inv = {}
+
+function inv.count(id)
+    local item = bot.get_inventory().items[id]
+    if item == nil then
+        return 0
+    end
+    return item.amount
+end
+
+function inv.has(id, n)
+    return inv.count(id) >= (n or 1)
+end
+
+return inv
@@ -0,0 +1,8 @@
+-- Mori Lua standard library, bump the version whenever a helper changes behavior
+return {
+    version = "1.0.0",
+    farm = require("mori.farm"),
+    inv = require("mori.inv"),
+    util = require("mori.util"),
+    world = require("mori.world"),
+}
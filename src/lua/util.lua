@@ -0,0 +1,17 @@
+local util = {}
+
+-- Polls `predicate` every `interval` ms until it returns true, gives up after `timeout` ms
+function util.waitFor(predicate, timeout, interval)
+    interval = interval or 100
+    local waited = 0
+    while not predicate() do
+        if timeout ~= nil and waited >= timeout then
+            return false
+        end
+        bot.sleep(interval)
+        waited = waited + interval
+    end
+    return true
+end
+
+return util
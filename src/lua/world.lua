@@ -0,0 +1,13 @@
+local world = {}
+
+function world.tilesMatching(predicate)
+    local matches = {}
+    for _, tile in ipairs(bot.get_world().tiles) do
+        if predicate(tile) then
+            table.insert(matches, tile)
+        end
+    end
+    return matches
+end
+
+return world
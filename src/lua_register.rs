@@ -1,8 +1,14 @@
-use crate::core::Bot;
+use crate::core::features::claim_world::{self, ClaimOptions};
+use crate::core::features::{auto_surgery, harvest_all, static_farm};
+use crate::core::{profiler, script_guard, Bot};
+use crate::lua_sandbox;
+use crate::types::bot_event::BotEvent;
+use crate::utils::config;
 use mlua::prelude::*;
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 pub fn register(lua: &Lua, bot: &Arc<Bot>) -> LuaResult<()> {
     let bot_table = lua.create_table()?;
@@ -23,6 +29,28 @@ pub fn register(lua: &Lua, bot: &Arc<Bot>) -> LuaResult<()> {
         Ok(())
     })?;
 
+    register_bot_function(lua, bot.clone(), &bot_table, "submit_2fa", |bot, code: String| {
+        Ok(bot.submit_2fa(&code))
+    })?;
+
+    register_bot_function(lua, bot.clone(), &bot_table, "run_route", |bot, name: String| {
+        bot.run_route(&name).map_err(LuaError::RuntimeError)
+    })?;
+
+    register_bot_function(lua, bot.clone(), &bot_table, "pause_automation", |bot, (): ()| {
+        bot.pause_automation();
+        Ok(())
+    })?;
+
+    register_bot_function(lua, bot.clone(), &bot_table, "resume_automation", |bot, (): ()| {
+        bot.resume_automation();
+        Ok(())
+    })?;
+
+    register_bot_function(lua, bot.clone(), &bot_table, "automation_paused", |bot, (): ()| {
+        Ok(bot.automation_paused())
+    })?;
+
     register_bot_function(lua, bot.clone(), &bot_table, "disconnect", |bot, (): ()| {
         bot.disconnect();
         Ok(())
@@ -39,13 +67,20 @@ pub fn register(lua: &Lua, bot: &Arc<Bot>) -> LuaResult<()> {
         },
     )?;
 
+    register_bot_function(lua, bot.clone(), &bot_table, "facing", |bot, (): ()| {
+        Ok(bot.facing().as_str())
+    })?;
+
     register_bot_function(
         lua,
         bot.clone(),
         &bot_table,
         "place",
         |bot, (offset_x, offset_y, item_id): (i32, i32, u32)| {
-            bot.place(offset_x, offset_y, item_id);
+            let name = script_guard::item_name(bot, item_id);
+            if script_guard::perform(bot, format!("place {} at {},{}", name, offset_x, offset_y)) {
+                bot.place(offset_x, offset_y, item_id);
+            }
             Ok(())
         },
     )?;
@@ -56,7 +91,9 @@ pub fn register(lua: &Lua, bot: &Arc<Bot>) -> LuaResult<()> {
         &bot_table,
         "punch",
         |bot, (offset_x, offset_y): (i32, i32)| {
-            bot.punch(offset_x, offset_y);
+            if script_guard::perform(bot, format!("punch {},{}", offset_x, offset_y)) {
+                bot.punch(offset_x, offset_y);
+            }
             Ok(())
         },
     )?;
@@ -67,7 +104,9 @@ pub fn register(lua: &Lua, bot: &Arc<Bot>) -> LuaResult<()> {
         &bot_table,
         "wrench",
         |bot, (offset_x, offset_y): (i32, i32)| {
-            bot.wrench(offset_x, offset_y);
+            if script_guard::perform(bot, format!("wrench {},{}", offset_x, offset_y)) {
+                bot.wrench(offset_x, offset_y);
+            }
             Ok(())
         },
     )?;
@@ -77,6 +116,154 @@ pub fn register(lua: &Lua, bot: &Arc<Bot>) -> LuaResult<()> {
         Ok(())
     })?;
 
+    register_bot_function(lua, bot.clone(), &bot_table, "consume", |bot, item_id: u32| {
+        bot.consume(item_id);
+        Ok(())
+    })?;
+
+    register_bot_function(
+        lua,
+        bot.clone(),
+        &bot_table,
+        "has_effect",
+        |bot, id: u32| Ok(bot.has_effect(id)),
+    )?;
+
+    register_bot_function(
+        lua,
+        bot.clone(),
+        &bot_table,
+        "claim_world",
+        |bot, (world_name, public, custom_music): (String, Option<bool>, Option<bool>)| {
+            let options = ClaimOptions {
+                public: public.unwrap_or(false),
+                custom_music: custom_music.unwrap_or(false),
+            };
+            if !script_guard::perform(bot, format!("claim {}", world_name)) {
+                return Ok(());
+            }
+            claim_world::run(bot, &world_name, &options)
+                .map_err(|err| LuaError::RuntimeError(err.to_string()))
+        },
+    )?;
+
+    let bot_clone = bot.clone();
+    bot_table.set(
+        "growscan",
+        lua.create_function(move |lua, ()| {
+            let census_table = lua.create_table()?;
+            for (i, (id, name, foreground, background)) in bot_clone.world_census().into_iter().enumerate() {
+                let entry = lua.create_table()?;
+                entry.set("id", id)?;
+                entry.set("name", name)?;
+                entry.set("foreground", foreground)?;
+                entry.set("background", background)?;
+                entry.set("count", foreground + background)?;
+                census_table.set(i + 1, entry)?;
+            }
+            Ok(census_table)
+        })?,
+    )?;
+
+    // Particles from the last `seconds` (default 10), oldest first, for polling scripts that
+    // don't want an on_particle handler
+    let bot_clone = bot.clone();
+    bot_table.set(
+        "recent_particles",
+        lua.create_function(move |lua, seconds: Option<f64>| {
+            let window = Duration::from_secs_f64(seconds.unwrap_or(10.0).max(0.0));
+            let particles = lua.create_table()?;
+            for (i, particle) in bot_clone.recent_particles(window).into_iter().enumerate() {
+                let entry = lua.create_table()?;
+                entry.set("effect_id", particle.effect_id)?;
+                entry.set("x", particle.x)?;
+                entry.set("y", particle.y)?;
+                entry.set("age", particle.received_at.elapsed().as_secs_f64())?;
+                particles.set(i + 1, entry)?;
+            }
+            Ok(particles)
+        })?,
+    )?;
+
+    register_bot_function(
+        lua,
+        bot.clone(),
+        &bot_table,
+        "harvest_all",
+        |bot, filter: Option<Vec<u32>>| {
+            if !script_guard::perform(bot, "harvest every ready tree".to_string()) {
+                return Ok(0);
+            }
+            Ok(harvest_all::run(bot, filter))
+        },
+    )?;
+
+    // Runs in the background until stop_static_farm. `pattern` is a preset name or a list of
+    // {x, y} offsets, both arguments fall back to the bot's saved static farm settings
+    register_bot_function(
+        lua,
+        bot.clone(),
+        &bot_table,
+        "static_farm",
+        |bot, (item_id, pattern): (Option<u32>, LuaValue)| {
+            let username = bot.info.lock().unwrap().payload[0].clone();
+            let bot_config = config::get_bot_config(&username).unwrap_or_default();
+            let pattern = match pattern {
+                LuaValue::Nil => bot_config.static_farm_pattern,
+                LuaValue::String(name) => {
+                    let name = name.to_str()?;
+                    static_farm::preset(name).ok_or_else(|| {
+                        LuaError::RuntimeError(format!("No static farm preset named {}", name))
+                    })?
+                }
+                LuaValue::Table(offsets) => offsets
+                    .sequence_values::<Vec<i32>>()
+                    .map(|offset| match offset?.as_slice() {
+                        &[x, y] => Ok((x, y)),
+                        _ => Err(LuaError::RuntimeError("Offsets are {x, y} pairs".to_string())),
+                    })
+                    .collect::<LuaResult<Vec<(i32, i32)>>>()?,
+                _ => {
+                    return Err(LuaError::RuntimeError(
+                        "pattern must be a preset name or a list of offsets".to_string(),
+                    ))
+                }
+            };
+            let item_id = item_id.unwrap_or(bot_config.static_farm_item);
+            if static_farm::is_running(bot) {
+                return Ok(false);
+            }
+            let name = script_guard::item_name(bot, item_id);
+            if !script_guard::perform(bot, format!("static farm {} over {} tiles", name, pattern.len())) {
+                return Ok(true);
+            }
+            let bot = bot.clone();
+            thread::spawn(move || static_farm::run(&bot, pattern, item_id));
+            Ok(true)
+        },
+    )?;
+
+    register_bot_function(lua, bot.clone(), &bot_table, "stop_static_farm", |bot, (): ()| {
+        static_farm::stop(bot);
+        Ok(())
+    })?;
+
+    // Blocks until the surgeries stop, returns how many were done and how many succeeded
+    register_bot_function(lua, bot.clone(), &bot_table, "auto_surgery", |bot, (): ()| {
+        let stats = auto_surgery::run(bot);
+        Ok((stats.completed, stats.succeeded))
+    })?;
+
+    register_bot_function(
+        lua,
+        bot.clone(),
+        &bot_table,
+        "get_daily_stat",
+        |bot, (name, day): (String, Option<String>)| {
+            Ok(bot.daily_stat(&name, day.as_deref().unwrap_or("today")))
+        },
+    )?;
+
     register_bot_function(
         lua,
         bot.clone(),
@@ -99,24 +286,105 @@ pub fn register(lua: &Lua, bot: &Arc<Bot>) -> LuaResult<()> {
         },
     )?;
 
+    // Same as talk but tells the script whether the message went out immediately or was queued
+    register_bot_function(
+        lua,
+        bot.clone(),
+        &bot_table,
+        "say",
+        |bot, message: String| Ok(bot.talk(message)),
+    )?;
+
     register_bot_function(
         lua,
         bot.clone(),
         &bot_table,
         "find_path",
-        |bot, (x, y): (u32, u32)| {
-            bot.find_path(x, y);
+        |bot, (x, y): (u32, u32)| match bot.find_path(x, y) {
+            Ok(_) => Ok((true, None)),
+            Err(err) => Ok((false, Some(err.to_string()))),
+        },
+    )?;
+
+    // true/false forces noclip pathing on or off, nil goes back to following the character state
+    register_bot_function(
+        lua,
+        bot.clone(),
+        &bot_table,
+        "set_noclip_pathing",
+        |bot, noclip: Option<bool>| {
+            bot.set_noclip_pathing(noclip);
             Ok(())
         },
     )?;
 
+    // Activations return true, or false and the reason, like find_path
+    register_bot_function(
+        lua,
+        bot.clone(),
+        &bot_table,
+        "enter_main_door",
+        |bot, (): ()| match bot.enter_main_door() {
+            Ok(_) => Ok((true, None)),
+            Err(err) => Ok((false, Some(err.to_string()))),
+        },
+    )?;
+
+    register_bot_function(
+        lua,
+        bot.clone(),
+        &bot_table,
+        "tile_activate",
+        |bot, (x, y): (u32, u32)| match bot.tile_activate(x, y) {
+            Ok(_) => Ok((true, None)),
+            Err(err) => Ok((false, Some(err.to_string()))),
+        },
+    )?;
+
+    register_bot_function(
+        lua,
+        bot.clone(),
+        &bot_table,
+        "enter_door",
+        |bot, (x, y): (u32, u32)| match bot.enter_door(x, y) {
+            Ok(_) => Ok((true, None)),
+            Err(err) => Ok((false, Some(err.to_string()))),
+        },
+    )?;
+
+    register_bot_function(
+        lua,
+        bot.clone(),
+        &bot_table,
+        "toggle_switch",
+        |bot, (x, y): (u32, u32)| match bot.toggle_switch(x, y) {
+            Ok(_) => Ok((true, None)),
+            Err(err) => Ok((false, Some(err.to_string()))),
+        },
+    )?;
+
+    register_bot_function(
+        lua,
+        bot.clone(),
+        &bot_table,
+        "use_checkpoint",
+        |bot, (x, y): (u32, u32)| match bot.use_checkpoint(x, y) {
+            Ok(_) => Ok((true, None)),
+            Err(err) => Ok((false, Some(err.to_string()))),
+        },
+    )?;
+
     register_bot_function(
         lua,
         bot.clone(),
         &bot_table,
         "drop",
         |bot, (item_id, amount): (u32, u32)| {
-            bot.drop_item(item_id, amount);
+            script_guard::check(bot, item_id).map_err(LuaError::RuntimeError)?;
+            let name = script_guard::item_name(bot, item_id);
+            if script_guard::perform(bot, format!("drop {} {}", amount, name)) {
+                bot.drop_item(item_id, amount);
+            }
             Ok(())
         },
     )?;
@@ -127,15 +395,33 @@ pub fn register(lua: &Lua, bot: &Arc<Bot>) -> LuaResult<()> {
         &bot_table,
         "trash",
         |bot, (item_id, amount): (u32, u32)| {
-            bot.trash_item(item_id, amount);
+            script_guard::check(bot, item_id).map_err(LuaError::RuntimeError)?;
+            let name = script_guard::item_name(bot, item_id);
+            if script_guard::perform(bot, format!("trash {} {}", amount, name)) {
+                bot.trash_item(item_id, amount);
+            }
+            Ok(())
+        },
+    )?;
+
+    register_bot_function(
+        lua,
+        bot.clone(),
+        &bot_table,
+        "whisper",
+        |bot, (name, message): (String, String)| {
+            bot.whisper(&name, &message);
             Ok(())
         },
     )?;
 
+    let bot_clone = bot.clone();
     bot_table.set(
         "sleep",
-        lua.create_function(|_, ms: u64| {
+        lua.create_function(move |lua, ms: u64| {
+            dispatch_events(lua, &bot_clone)?;
             thread::sleep(Duration::from_millis(ms));
+            lua_sandbox::reset_budget(lua);
             Ok(())
         })?,
     )?;
@@ -146,7 +432,298 @@ pub fn register(lua: &Lua, bot: &Arc<Bot>) -> LuaResult<()> {
     register_tile_api(lua, bot.clone(), &bot_table)?;
 
     lua.globals().set("bot", bot_table)?;
+    register_storage_api(lua, bot.clone())?;
+    register_profile_api(lua, bot.clone())?;
+    register_bus_api(lua, bot.clone())?;
+    register_stdlib(lua)?;
+
+    Ok(())
+}
+
+const STDLIB: [(&str, &str); 5] = [
+    ("mori", include_str!("lua/mori.lua")),
+    ("mori.farm", include_str!("lua/farm.lua")),
+    ("mori.inv", include_str!("lua/inv.lua")),
+    ("mori.util", include_str!("lua/util.lua")),
+    ("mori.world", include_str!("lua/world.lua")),
+];
+
+// Makes the embedded modules available to `require` without touching the filesystem
+fn register_stdlib(lua: &Lua) -> LuaResult<()> {
+    let preload: LuaTable = lua
+        .globals()
+        .get::<_, LuaTable>("package")?
+        .get("preload")?;
+    for (name, source) in STDLIB {
+        let loader = lua.load(source).set_name(name).into_function()?;
+        preload.set(name, loader)?;
+    }
+    Ok(())
+}
+
+// Keys are namespaced by the SCRIPT_NAME global so two scripts can use the same key
+fn register_storage_api(lua: &Lua, bot: Arc<Bot>) -> LuaResult<()> {
+    let storage_table = lua.create_table()?;
+
+    let bot_clone = bot.clone();
+    storage_table.set(
+        "set",
+        lua.create_function(move |lua, (key, value): (String, LuaValue)| {
+            let value = lua_to_json(&value, 0)?;
+            let namespace = script_name(lua)?;
+            let mut storage = bot_clone.storage.lock().unwrap();
+            if value.is_null() {
+                storage.delete(&namespace, &key);
+            } else {
+                storage.set(&namespace, &key, value);
+            }
+            Ok(())
+        })?,
+    )?;
 
+    let bot_clone = bot.clone();
+    storage_table.set(
+        "get",
+        lua.create_function(move |lua, key: String| {
+            let namespace = script_name(lua)?;
+            let value = bot_clone.storage.lock().unwrap().get(&namespace, &key);
+            match value {
+                Some(value) => json_to_lua(lua, &value),
+                None => Ok(LuaValue::Nil),
+            }
+        })?,
+    )?;
+
+    let bot_clone = bot.clone();
+    storage_table.set(
+        "delete",
+        lua.create_function(move |lua, key: String| {
+            let namespace = script_name(lua)?;
+            bot_clone.storage.lock().unwrap().delete(&namespace, &key);
+            Ok(())
+        })?,
+    )?;
+
+    lua.globals().set("storage", storage_table)
+}
+
+const BUS_HANDLERS: &str = "bus_handlers";
+const BUS_LOOPBACK: &str = "bus_loopback";
+
+// bus.send(target, topic, table) reaches a bot by name or every bot in a group,
+// bus.on(topic, fn) is called with (table, sender). Sending to yourself needs bus.loopback(true)
+fn register_bus_api(lua: &Lua, bot: Arc<Bot>) -> LuaResult<()> {
+    let bus_table = lua.create_table()?;
+    lua.set_named_registry_value(BUS_HANDLERS, lua.create_table()?)?;
+    lua.set_named_registry_value(BUS_LOOPBACK, false)?;
+
+    let bot_clone = bot.clone();
+    bus_table.set(
+        "send",
+        lua.create_function(move |lua, (target, topic, payload): (String, String, LuaValue)| {
+            let payload = lua_to_json(&payload, 0)?;
+            let loopback: bool = lua.named_registry_value(BUS_LOOPBACK)?;
+            bot_clone
+                .message_bus
+                .send(&bot_clone, &target, &topic, payload, loopback)
+                .map_err(|err| LuaError::RuntimeError(err.to_string()))
+        })?,
+    )?;
+
+    bus_table.set(
+        "on",
+        lua.create_function(|lua, (topic, handler): (String, Option<LuaFunction>)| {
+            bus_handlers(lua)?.set(topic, handler)
+        })?,
+    )?;
+
+    bus_table.set(
+        "loopback",
+        lua.create_function(|lua, enabled: bool| {
+            lua.set_named_registry_value(BUS_LOOPBACK, enabled)
+        })?,
+    )?;
+
+    lua.globals().set("bus", bus_table)
+}
+
+fn bus_handlers(lua: &Lua) -> LuaResult<LuaTable> {
+    lua.named_registry_value(BUS_HANDLERS)
+}
+
+// `end` is a keyword, so scripts close a span with profile.finish(name) or profile["end"](name)
+fn register_profile_api(lua: &Lua, bot: Arc<Bot>) -> LuaResult<()> {
+    let profile_table = lua.create_table()?;
+    let started: Arc<Mutex<HashMap<String, Instant>>> = Arc::new(Mutex::new(HashMap::new()));
+
+    let started_clone = started.clone();
+    profile_table.set(
+        "begin",
+        lua.create_function(move |_, name: String| {
+            started_clone.lock().unwrap().insert(name, Instant::now());
+            Ok(())
+        })?,
+    )?;
+
+    let finish = lua.create_function(move |_, name: String| {
+        let started_at = started.lock().unwrap().remove(&name);
+        match started_at {
+            Some(started_at) => {
+                let elapsed = started_at.elapsed();
+                profiler::record(&bot, &format!("lua:{}", name), elapsed);
+                Ok(elapsed.as_secs_f64() * 1000.0)
+            }
+            None => Err(LuaError::RuntimeError(format!(
+                "profile span '{}' was never started",
+                name
+            ))),
+        }
+    })?;
+    profile_table.set("finish", finish.clone())?;
+    profile_table.set("end", finish)?;
+
+    lua.globals().set("profile", profile_table)
+}
+
+fn script_name(lua: &Lua) -> LuaResult<String> {
+    Ok(lua
+        .globals()
+        .get::<_, Option<String>>("SCRIPT_NAME")?
+        .unwrap_or_else(|| "default".to_string()))
+}
+
+const MAX_STORAGE_DEPTH: usize = 32;
+
+fn lua_to_json(value: &LuaValue, depth: usize) -> LuaResult<serde_json::Value> {
+    if depth > MAX_STORAGE_DEPTH {
+        return Err(LuaError::RuntimeError(
+            "storage values can't be nested this deep (or contain cycles)".to_string(),
+        ));
+    }
+    Ok(match value {
+        LuaValue::Nil => serde_json::Value::Null,
+        LuaValue::Boolean(value) => serde_json::Value::Bool(*value),
+        LuaValue::Integer(value) => serde_json::Value::from(*value),
+        LuaValue::Number(value) => serde_json::Number::from_f64(*value)
+            .map(serde_json::Value::Number)
+            .ok_or_else(|| LuaError::RuntimeError("storage can't hold NaN or infinity".to_string()))?,
+        LuaValue::String(value) => serde_json::Value::String(value.to_str()?.to_string()),
+        LuaValue::Table(table) => {
+            // Tables with exactly the keys 1..n are stored as arrays, everything else as objects
+            let length = table.raw_len();
+            let pairs = table
+                .clone()
+                .pairs::<LuaValue, LuaValue>()
+                .collect::<LuaResult<Vec<_>>>()?;
+            if length > 0 && pairs.len() == length {
+                let mut array = Vec::with_capacity(length);
+                for index in 1..=length {
+                    array.push(lua_to_json(&table.raw_get(index)?, depth + 1)?);
+                }
+                serde_json::Value::Array(array)
+            } else {
+                let mut object = serde_json::Map::new();
+                for (key, value) in pairs {
+                    let key = match key {
+                        LuaValue::String(key) => key.to_str()?.to_string(),
+                        LuaValue::Integer(key) => key.to_string(),
+                        _ => {
+                            return Err(LuaError::RuntimeError(
+                                "storage table keys must be strings or integers".to_string(),
+                            ))
+                        }
+                    };
+                    object.insert(key, lua_to_json(&value, depth + 1)?);
+                }
+                serde_json::Value::Object(object)
+            }
+        }
+        other => {
+            return Err(LuaError::RuntimeError(format!(
+                "storage can't hold a {}",
+                other.type_name()
+            )))
+        }
+    })
+}
+
+fn json_to_lua<'lua>(lua: &'lua Lua, value: &serde_json::Value) -> LuaResult<LuaValue<'lua>> {
+    Ok(match value {
+        serde_json::Value::Null => LuaValue::Nil,
+        serde_json::Value::Bool(value) => LuaValue::Boolean(*value),
+        serde_json::Value::Number(value) => match value.as_i64() {
+            Some(value) => LuaValue::Integer(value),
+            None => LuaValue::Number(value.as_f64().unwrap_or(0.0)),
+        },
+        serde_json::Value::String(value) => LuaValue::String(lua.create_string(value)?),
+        serde_json::Value::Array(values) => {
+            let table = lua.create_table()?;
+            for (index, value) in values.iter().enumerate() {
+                table.raw_set(index + 1, json_to_lua(lua, value)?)?;
+            }
+            LuaValue::Table(table)
+        }
+        serde_json::Value::Object(entries) => {
+            let table = lua.create_table()?;
+            for (key, value) in entries {
+                table.raw_set(key.as_str(), json_to_lua(lua, value)?)?;
+            }
+            LuaValue::Table(table)
+        }
+    })
+}
+
+pub fn dispatch_events(lua: &Lua, bot: &Arc<Bot>) -> LuaResult<()> {
+    for event in bot.take_events() {
+        let handler = match &event {
+            BotEvent::Message { topic, .. } => bus_handlers(lua)?.get::<_, LuaValue>(topic.as_str())?,
+            _ => lua_sandbox::script_globals(lua)?.get::<_, LuaValue>(format!("on_{}", event.name()))?,
+        };
+        let handler = match handler {
+            LuaValue::Function(handler) => handler,
+            _ => continue,
+        };
+        lua_sandbox::reset_budget(lua);
+        match event {
+            BotEvent::Login { net_id, user_id } => handler.call::<_, ()>((net_id, user_id))?,
+            BotEvent::Whisper { sender, text } => handler.call::<_, ()>((sender, text))?,
+            BotEvent::Intruder { name, reason } => handler.call::<_, ()>((name, reason))?,
+            BotEvent::WorldEnter(summary) => {
+                let summary_table = lua.create_table()?;
+                summary_table.set("name", summary.name)?;
+                summary_table.set("width", summary.width)?;
+                summary_table.set("height", summary.height)?;
+                summary_table.set("owner", summary.owner)?;
+                summary_table.set("players", summary.players)?;
+                summary_table.set("farmable_tiles", summary.farmable_tiles)?;
+                summary_table.set("dropped_objects", summary.dropped_objects)?;
+                summary_table.set("dropped_gems", summary.dropped_gems)?;
+                summary_table.set("has_access", summary.has_access)?;
+                handler.call::<_, ()>(summary_table)?
+            }
+            BotEvent::WorldLeave { name } => handler.call::<_, ()>(name)?,
+            BotEvent::Particle { effect_id, x, y } => handler.call::<_, ()>((effect_id, x, y))?,
+            BotEvent::Disconnect { reason, detail, reconnect } => {
+                handler.call::<_, ()>((reason, detail, reconnect))?
+            }
+            BotEvent::Connect { server_ip, port } => handler.call::<_, ()>((server_ip, port))?,
+            BotEvent::Redirect { server } => handler.call::<_, ()>(server)?,
+            BotEvent::LoginStatus { status } => handler.call::<_, ()>(status)?,
+            BotEvent::ItemGain { item_id, amount } => handler.call::<_, ()>((item_id, amount))?,
+            BotEvent::Feature { name, running } => handler.call::<_, ()>((name, running))?,
+            BotEvent::InventoryNearlyFull { free_slots } => handler.call::<_, ()>(free_slots)?,
+            BotEvent::InventoryFull => handler.call::<_, ()>(())?,
+            BotEvent::TradeRequest { name } => handler.call::<_, ()>(name)?,
+            BotEvent::TwoFactorFailed => handler.call::<_, ()>(())?,
+            BotEvent::WorldPasswordRequired { world } => handler.call::<_, ()>(world)?,
+            BotEvent::Resynced { dx, dy } => handler.call::<_, ()>((dx, dy))?,
+            BotEvent::SuperBroadcast { sender, world, text } => handler.call::<_, ()>((sender, world, text))?,
+            BotEvent::GuildChat { sender, text } => handler.call::<_, ()>((sender, text))?,
+            BotEvent::Message { sender, payload, .. } => {
+                handler.call::<_, ()>((json_to_lua(lua, &payload)?, sender))?
+            }
+        }
+    }
     Ok(())
 }
 
@@ -247,7 +824,7 @@ fn register_local_api<'lua>(
         local_data.set("y", position.y)?;
         local_data.set("net_id", state.net_id)?;
         local_data.set("level", state.level)?;
-        local_data.set("gems", state.gems)?;
+        local_data.set("gems", bot_clone.gems.get())?;
 
         Ok(LuaValue::Table(local_data))
     })?;
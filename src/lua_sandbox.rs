@@ -0,0 +1,285 @@
+use crate::core::Bot;
+use crate::lua_register;
+use crate::utils::config;
+use mlua::prelude::*;
+use mlua::HookTriggers;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+// The budget hook runs every this many VM instructions
+const HOOK_INTERVAL: u32 = 10_000;
+const BUDGET_EXCEEDED: &str = "script killed: exceeded budget";
+// Script name of the REPL state, keeps its storage keys apart from real scripts
+const REPL_NAME: &str = "repl";
+// Registry key of the environment the last script ran in, its event handlers live there
+const SCRIPT_ENV: &str = "script_env";
+
+// Ways out of the sandbox to the filesystem or other processes. Storage goes through
+// the storage API instead
+const BLOCKED_GLOBALS: [&str; 3] = ["io", "loadfile", "dofile"];
+const BLOCKED_OS: [&str; 6] = ["execute", "exit", "remove", "rename", "tmpname", "getenv"];
+
+// Instructions a script may run before handing control back to the bot, by returning
+// from a callback or calling bot.sleep
+struct Budget {
+    used: u64,
+    limit: u64,
+}
+
+// Whether the state was built for trusted scripts, it is rebuilt when the setting changes
+struct Trusted(bool);
+
+// A fresh state with the sandbox applied, unless trusted scripts are enabled
+pub fn new_state() -> LuaResult<Lua> {
+    if config::get_trusted_scripts() {
+        let lua = Lua::new();
+        lua.set_app_data(Trusted(true));
+        return Ok(lua);
+    }
+    sandboxed_state(config::get_script_memory_limit(), config::get_script_cpu_budget())
+}
+
+// `memory_limit` in MB, `cpu_budget` in millions of instructions
+fn sandboxed_state(memory_limit: u32, cpu_budget: u64) -> LuaResult<Lua> {
+    let lua = Lua::new();
+    lua.set_app_data(Trusted(false));
+    let globals = lua.globals();
+    for name in BLOCKED_GLOBALS {
+        globals.set(name, LuaNil)?;
+    }
+    let os: LuaTable = globals.get("os")?;
+    for name in BLOCKED_OS {
+        os.set(name, LuaNil)?;
+    }
+    // require only resolves the embedded stdlib from package.preload
+    let package: LuaTable = globals.get("package")?;
+    package.set("loadlib", LuaNil)?;
+    package.set("path", "")?;
+    package.set("cpath", "")?;
+    package.get::<_, LuaTable>("loaded")?.set("io", LuaNil)?;
+
+    lua.set_memory_limit(memory_limit as usize * 1024 * 1024)?;
+    lua.set_app_data(Budget {
+        used: 0,
+        limit: cpu_budget * 1_000_000,
+    });
+    lua.set_hook(
+        HookTriggers::new().every_nth_instruction(HOOK_INTERVAL),
+        |lua, _debug| {
+            let mut budget = match lua.app_data_mut::<Budget>() {
+                Some(budget) => budget,
+                None => return Ok(()),
+            };
+            // Keeps failing once spent, so a pcall can't swallow the kill
+            budget.used += HOOK_INTERVAL as u64;
+            if budget.used > budget.limit {
+                return Err(LuaError::RuntimeError(BUDGET_EXCEEDED.to_string()));
+            }
+            Ok(())
+        },
+    );
+    Ok(lua)
+}
+
+pub fn reset_budget(lua: &Lua) {
+    if let Some(mut budget) = lua.app_data_mut::<Budget>() {
+        budget.used = 0;
+    }
+}
+
+// The bot's state is built and registered once, every run gets its own environment on
+// top of it so one script can't patch functions another relies on.
+// Blocks until the script returns, errors end up in the bot log
+pub fn run_script(bot: &Arc<Bot>, script_name: &str, code: &str) {
+    let mut lua = bot.lua.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    bot.script_running.store(true, Ordering::Relaxed);
+    let result = prepare(&mut lua, bot).and_then(|()| {
+        lua.globals().set("SCRIPT_NAME", script_name)?;
+        let env = fresh_env(&lua)?;
+        lua.set_named_registry_value(SCRIPT_ENV, env.clone())?;
+        reset_budget(&lua);
+        lua.load(code).set_name(script_name).set_environment(env).exec()
+    });
+    bot.script_running.store(false, Ordering::Relaxed);
+    match result {
+        Ok(()) => {}
+        Err(err) if err.to_string().contains(BUDGET_EXCEEDED) => {
+            bot.log_error(&format!("Script {}: {}", script_name, BUDGET_EXCEEDED));
+        }
+        Err(err) => bot.log_error(&format!("Script {} failed: {}", script_name, err)),
+    }
+}
+
+// Rebuilds the state only when the trusted setting changed since it was built, and
+// registers the bot API when it isn't there yet
+pub fn prepare(lua: &mut Lua, bot: &Arc<Bot>) -> LuaResult<()> {
+    let trusted = lua.app_data_ref::<Trusted>().map(|trusted| trusted.0);
+    if trusted != Some(config::get_trusted_scripts()) {
+        *lua = new_state()?;
+    }
+    if !lua.globals().contains_key("bot")? {
+        lua_register::register(lua, bot)?;
+    }
+    Ok(())
+}
+
+// Globals as a script sees them: a copy of the registered ones with every table (bot,
+// string, storage...) copied one level deep, so what a run defines or patches is gone for
+// the next one
+fn fresh_env(lua: &Lua) -> LuaResult<LuaTable> {
+    let env = lua.create_table()?;
+    for pair in lua.globals().pairs::<LuaValue, LuaValue>() {
+        let (key, value) = pair?;
+        let value = match value {
+            LuaValue::Table(table) => {
+                let copy = lua.create_table()?;
+                for pair in table.pairs::<LuaValue, LuaValue>() {
+                    let (key, value) = pair?;
+                    copy.raw_set(key, value)?;
+                }
+                LuaValue::Table(copy)
+            }
+            value => value,
+        };
+        env.raw_set(key, value)?;
+    }
+    env.raw_set("_G", env.clone())?;
+    Ok(env)
+}
+
+// Where event handlers are looked up: the last script's environment, or the plain
+// globals before any script ran
+pub fn script_globals(lua: &Lua) -> LuaResult<LuaTable> {
+    match lua.named_registry_value::<Option<LuaTable>>(SCRIPT_ENV)? {
+        Some(env) => Ok(env),
+        None => Ok(lua.globals()),
+    }
+}
+
+// Runs a REPL chunk in the bot's REPL state, which keeps its globals between chunks but
+// shares nothing with running scripts. Expressions are tried first so `1 + 1` prints 2
+pub fn eval_repl(bot: &Arc<Bot>, chunk: &str) -> Result<String, String> {
+    bot.log_debug(&format!("repl> {}", chunk));
+    let result = eval_chunk(bot, chunk).map_err(|err| err.to_string());
+    match &result {
+        Ok(output) => bot.log_debug(&format!("repl: {}", output)),
+        Err(err) => bot.log_debug(&format!("repl error: {}", err)),
+    }
+    result
+}
+
+fn eval_chunk(bot: &Arc<Bot>, chunk: &str) -> LuaResult<String> {
+    let mut repl = bot.repl.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    if repl.is_none() {
+        let state = new_state()?;
+        lua_register::register(&state, bot)?;
+        state.globals().set("SCRIPT_NAME", REPL_NAME)?;
+        *repl = Some(state);
+    }
+    let lua = match repl.as_ref() {
+        Some(lua) => lua,
+        None => return Ok(String::new()),
+    };
+    reset_budget(lua);
+    let values = match lua
+        .load(format!("return {}", chunk))
+        .set_name(REPL_NAME)
+        .eval::<LuaMultiValue>()
+    {
+        Ok(values) => values,
+        Err(LuaError::SyntaxError { .. }) => {
+            lua.load(chunk).set_name(REPL_NAME).eval::<LuaMultiValue>()?
+        }
+        Err(err) => return Err(err),
+    };
+    let tostring: LuaFunction = lua.globals().get("tostring")?;
+    let mut output = Vec::new();
+    for value in values {
+        output.push(tostring.call::<_, String>(value)?);
+    }
+    Ok(output.join("\t"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, AtomicU64};
+    use std::thread;
+    use std::time::Duration;
+
+    fn killed(result: LuaResult<()>) -> bool {
+        matches!(result, Err(err) if err.to_string().contains(BUDGET_EXCEEDED))
+    }
+
+    #[test]
+    fn infinite_loop_is_killed_while_packets_keep_flowing() {
+        // Stands in for the packet thread, which never touches the Lua state
+        let stop = Arc::new(AtomicBool::new(false));
+        let handled = Arc::new(AtomicU64::new(0));
+        let packets = {
+            let (stop, handled) = (stop.clone(), handled.clone());
+            thread::spawn(move || {
+                while !stop.load(Ordering::Relaxed) {
+                    handled.fetch_add(1, Ordering::Relaxed);
+                    thread::sleep(Duration::from_millis(1));
+                }
+            })
+        };
+
+        let lua = sandboxed_state(16, 5).unwrap();
+        let before = handled.load(Ordering::Relaxed);
+        assert!(killed(lua.load("while true do end").exec()));
+        thread::sleep(Duration::from_millis(20));
+        assert!(handled.load(Ordering::Relaxed) > before);
+
+        stop.store(true, Ordering::Relaxed);
+        packets.join().unwrap();
+    }
+
+    #[test]
+    fn pcall_does_not_swallow_the_kill() {
+        let lua = sandboxed_state(16, 5).unwrap();
+        let code = "while true do pcall(function() while true do end end) end";
+        assert!(killed(lua.load(code).exec()));
+    }
+
+    #[test]
+    fn budget_is_per_callback() {
+        let lua = sandboxed_state(16, 5).unwrap();
+        let busy = "local n = 0 for i = 1, 400000 do n = n + i end";
+        for _ in 0..5 {
+            reset_budget(&lua);
+            lua.load(busy).exec().unwrap();
+        }
+    }
+
+    #[test]
+    fn escapes_are_removed() {
+        let lua = sandboxed_state(16, 5).unwrap();
+        for name in ["io", "loadfile", "dofile", "os.execute", "os.getenv", "package.loadlib"] {
+            let value: LuaValue = lua.load(name).eval().unwrap();
+            assert!(value.is_nil(), "{} is still reachable", name);
+        }
+        assert!(lua.load("require('io')").exec().is_err());
+    }
+
+    #[test]
+    fn runs_do_not_see_each_others_globals() {
+        let lua = sandboxed_state(16, 5).unwrap();
+        let first = fresh_env(&lua).unwrap();
+        lua.load("leaked = 1 string.upper = nil _G.rawlen = nil")
+            .set_environment(first)
+            .exec()
+            .unwrap();
+
+        let second = fresh_env(&lua).unwrap();
+        let (leaked, upper, rawlen): (LuaValue, String, i64) = lua
+            .load("return leaked, string.upper('a'), rawlen({1, 2})")
+            .set_environment(second)
+            .eval()
+            .unwrap();
+        assert!(leaked.is_nil());
+        assert_eq!(upper, "A");
+        assert_eq!(rawlen, 2);
+    }
+}
@@ -1,55 +1,294 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+use crate::core::{bandwidth, crash, items, value_table, world_diff, world_snapshot, Bot};
 use crate::gui::add_proxy_dialog::AddProxyDialog;
 use crate::gui::proxy_list::ProxyList;
 use crate::gui::settings::Settings;
+use crate::manager::account_check;
 use crate::manager::bot_manager::BotManager;
+use crate::manager::login_schedule;
 use crate::manager::proxy_manager::ProxyManager;
-use crate::utils::config;
+use crate::manager::session;
+use crate::manager::update_check;
+use crate::utils::{config, repaint, secrets, setup_check};
 use eframe::egui::ViewportBuilder;
 use egui::{
     vec2, Button, CentralPanel, Id, PointerButton, RichText, Sense, UiBuilder, ViewportCommand,
 };
 use gui::{
-    add_bot_dialog::AddBotDialog, bot_menu::BotMenu, item_database::ItemDatabase, navbar::Navbar,
+    add_bot_dialog::AddBotDialog, bot_menu::BotMenu, crash_dialog::CrashDialog,
+    import_dialog::ImportDialog,
+    item_database::ItemDatabase, navbar::Navbar, notifications::NotificationsPanel,
+    resume_screen::ResumeScreen, setup_screen::SetupScreen, toasts, unlock_screen::UnlockScreen,
 };
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::Ordering;
 use std::sync::{Arc, RwLock};
-use std::{
-    fs::{self, File},
-    io::Write,
-};
+use std::sync::mpsc::Receiver;
+use std::time::Duration;
 use types::config::{Config, Theme};
+use types::elogin_method::ELoginMethod;
 
 mod core;
 mod gui;
 mod lua_register;
+mod lua_sandbox;
 mod manager;
+#[cfg(feature = "mock_server")]
+mod mock_server;
 mod texture_manager;
 mod types;
 mod utils;
 
-fn init_config() {
-    if !fs::metadata("config.json").is_ok() {
-        let mut file = File::create("config.json").unwrap();
-        let config = Config {
-            bots: Vec::new(),
-            proxy: Vec::new(),
-            timeout: 5,
-            findpath_delay: 30,
-            auto_collect: true,
-            selected_bot: "".to_string(),
-            game_version: "4.70".to_string(),
-            use_alternate_server: false,
-            theme: Theme::Dark,
-            captcha: Default::default(),
-        };
-        let j = serde_json::to_string_pretty(&config).unwrap();
-        file.write_all(j.as_bytes()).unwrap();
+const IDLE_REPAINT: Duration = Duration::from_secs(1);
+
+// `--resume <path>` from the command line, with the passphrase from MORI_SESSION_PASSPHRASE.
+// The second value is false when the file's secrets are sealed and the passphrase still
+// has to be asked for
+fn resume_request() -> Option<(String, Option<String>, bool)> {
+    let args: Vec<String> = std::env::args().collect();
+    let path = args
+        .iter()
+        .position(|arg| arg == "--resume")
+        .and_then(|index| args.get(index + 1))
+        .cloned()?;
+    if !session::is_sealed(Path::new(&path)) {
+        return Some((path, None, true));
+    }
+    let passphrase = std::env::var("MORI_SESSION_PASSPHRASE").ok();
+    let ready = passphrase.is_some();
+    Some((path, passphrase, ready))
+}
+
+// An encrypted config is unlocked with MORI_CONFIG_PASSPHRASE when set, a wrong one goes
+// on read-only rather than stopping a headless start
+fn unlock_from_env() {
+    if !secrets::needs_unlock() {
+        return;
+    }
+    let passphrase = match std::env::var(secrets::PASSPHRASE_ENV) {
+        Ok(passphrase) => passphrase,
+        Err(_) => return,
+    };
+    if let Err(err) = secrets::unlock(&passphrase) {
+        eprintln!("Can't unlock {}: {}, bots won't log in", config::CONFIG_PATH, err);
+        secrets::continue_read_only();
+    }
+}
+
+// Release builds are GUI-subsystem programs on Windows and start without a console, so
+// subcommands borrow the one of the shell they were started from to print to
+#[cfg(windows)]
+fn attach_console() {
+    extern "system" {
+        fn AttachConsole(process_id: u32) -> i32;
+    }
+    const ATTACH_PARENT_PROCESS: u32 = u32::MAX;
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+#[cfg(not(windows))]
+fn attach_console() {}
+
+// `check-accounts <file> [--method NAME] [--csv PATH] [--concurrency N] [--timeout SECS]`,
+// validates the credentials in an import file and exits without starting the GUI. Setup
+// problems are printed as check-setup does and nothing is checked
+fn check_accounts_command(problems: &[types::setup_problem::SetupProblem]) -> Option<i32> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) != Some("check-accounts") {
+        return None;
+    }
+    attach_console();
+    if !problems.is_empty() {
+        print_problems(problems);
+        return Some(1);
+    }
+    let path = match args.get(1) {
+        Some(path) => path,
+        None => {
+            eprintln!("Usage: mori check-accounts <file> [--method LEGACY|GOOGLE|APPLE|STEAM] [--csv PATH] [--concurrency N] [--timeout SECS]");
+            return Some(2);
+        }
+    };
+    let option = |name: &str| {
+        args.iter()
+            .position(|arg| arg == name)
+            .and_then(|index| args.get(index + 1))
+            .cloned()
+    };
+    let method = match option("--method").as_deref().map(str::to_uppercase).as_deref() {
+        None | Some("LEGACY") => ELoginMethod::LEGACY,
+        Some("GOOGLE") => ELoginMethod::GOOGLE,
+        Some("APPLE") => ELoginMethod::APPLE,
+        Some("STEAM") => ELoginMethod::STEAM,
+        Some(other) => {
+            eprintln!("Unknown login method {}", other);
+            return Some(2);
+        }
+    };
+    let concurrency = option("--concurrency")
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(account_check::DEFAULT_CONCURRENCY);
+    let timeout = option("--timeout")
+        .and_then(|value| value.parse().ok())
+        .map(Duration::from_secs)
+        .unwrap_or(account_check::DEFAULT_TIMEOUT);
+
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(err) => {
+            eprintln!("Failed to read {}: {}", path, err);
+            return Some(1);
+        }
+    };
+    let (configs, malformed) = account_check::parse_accounts(&contents, &method);
+    for (line, reason) in &malformed {
+        eprintln!("Line {}: {}", line, reason);
+    }
+    println!("Checking {} accounts, {} at a time", configs.len(), concurrency);
+    let checks = account_check::check_all(configs, concurrency, timeout);
+    print!("{}", account_check::format_table(&checks));
+    if let Some(csv) = option("--csv") {
+        match utils::export::write_account_checks(&csv, &checks) {
+            Ok(()) => println!("Report written to {}", csv),
+            Err(err) => eprintln!("Failed to write {}: {}", csv, err),
+        }
+    }
+    Some(0)
+}
+
+// `check-setup`, prints what the setup screen would show. Exits 1 when anything is wrong
+fn check_setup_command(problems: &[types::setup_problem::SetupProblem]) -> Option<i32> {
+    if std::env::args().nth(1).as_deref() != Some("check-setup") {
+        return None;
+    }
+    attach_console();
+    if problems.is_empty() {
+        println!("Setup is complete");
+        return Some(0);
+    }
+    print_problems(problems);
+    Some(1)
+}
+
+fn print_problems(problems: &[types::setup_problem::SetupProblem]) {
+    for problem in problems {
+        println!("{}\n  {}", problem.title, problem.hint);
+    }
+}
+
+// `world-diff <world> [--since UNIX_TIME]`, prints what changed in a saved world since
+// its newest archived snapshot, or the newest one not after --since. Exits 1 when
+// anything changed, so a scheduled job can alert on it
+fn world_diff_command() -> Option<i32> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) != Some("world-diff") {
+        return None;
+    }
+    attach_console();
+    let world_name = match args.get(1) {
+        Some(world_name) => world_name.to_uppercase(),
+        None => {
+            eprintln!("Usage: mori world-diff <world> [--since UNIX_TIME]");
+            return Some(2);
+        }
+    };
+    let since = args
+        .iter()
+        .position(|arg| arg == "--since")
+        .and_then(|index| args.get(index + 1))
+        .and_then(|since| since.parse::<u64>().ok());
+    let saved_at = world_snapshot::history(&world_name)
+        .into_iter()
+        .find(|&saved_at| since.map_or(true, |since| saved_at <= since));
+    let saved_at = match saved_at {
+        Some(saved_at) => saved_at,
+        None => {
+            eprintln!("No earlier snapshot of {} to compare with", world_name);
+            return Some(2);
+        }
+    };
+    let item_database = Arc::new(RwLock::new(gtitem_r::structs::ItemDatabase::new()));
+    if items::load_from_disk(&item_database).is_none() {
+        eprintln!("Failed to load items.dat, item ids are shown instead of names");
+    }
+    let loaded = world_snapshot::load_archived(&world_name, saved_at, item_database.clone())
+        .and_then(|earlier| world_snapshot::load(&world_name, item_database.clone()).map(|world| (earlier, world)));
+    let (earlier, world) = match loaded {
+        Ok(worlds) => worlds,
+        Err(err) => {
+            eprintln!("Failed to load the snapshots of {}: {}", world_name, err);
+            return Some(2);
+        }
+    };
+    let diff = world_diff::diff(&earlier, &world);
+    let item_database = item_database.read().unwrap();
+    let lines = diff.describe(|item_id| {
+        item_database
+            .get_item(&(item_id as u32))
+            .map_or(format!("#{}", item_id), |item| item.name)
+    });
+    println!("{} since {}", world_name, saved_at);
+    if lines.is_empty() {
+        println!("Nothing changed");
+    }
+    for line in lines {
+        println!("{}", line);
+    }
+    Some(if diff.is_empty() { 0 } else { 1 })
+}
+
+// `mock-server <scenario.json> [--port N]`, only in builds with the mock_server feature
+#[cfg(feature = "mock_server")]
+fn mock_server_command() -> Option<i32> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) != Some("mock-server") {
+        return None;
+    }
+    attach_console();
+    let path = match args.get(1) {
+        Some(path) => path,
+        None => {
+            eprintln!("Usage: mori mock-server <scenario.json> [--port N]");
+            return Some(2);
+        }
+    };
+    let port = args
+        .iter()
+        .position(|arg| arg == "--port")
+        .and_then(|index| args.get(index + 1))
+        .and_then(|port| port.parse().ok())
+        .unwrap_or(mock_server::DEFAULT_PORT);
+    let result = mock_server::load(path).and_then(|scenario| mock_server::run(scenario, port));
+    match result {
+        Ok(()) => Some(0),
+        Err(err) => {
+            eprintln!("Mock server failed: {}", err);
+            Some(1)
+        }
     }
 }
 
 fn main() {
-    init_config();
+    #[cfg(feature = "mock_server")]
+    if let Some(code) = mock_server_command() {
+        std::process::exit(code);
+    }
+    let problems = setup_check::run();
+    if let Some(code) = check_setup_command(&problems) {
+        std::process::exit(code);
+    }
+    if let Some(code) = check_accounts_command(&problems) {
+        std::process::exit(code);
+    }
+    if let Some(code) = world_diff_command() {
+        std::process::exit(code);
+    }
+    let resume = resume_request();
+    unlock_from_env();
 
     let options = eframe::NativeOptions {
         centered: true,
@@ -64,63 +303,194 @@ fn main() {
             .with_transparent(true),
         ..Default::default()
     };
-    let _ = eframe::run_native("Mori", options, Box::new(|cc| Ok(Box::new(App::new(cc)))));
+    let (resume, resume_prompt) = match resume {
+        Some((path, _, false)) => (None, Some(ResumeScreen::new(path))),
+        Some((path, passphrase, true)) => (Some((path, passphrase)), None),
+        None => (None, None),
+    };
+    let launcher = Launcher {
+        setup: (!problems.is_empty()).then(|| SetupScreen::new(problems)),
+        unlock: None,
+        resume_prompt,
+        app: None,
+        resume,
+    };
+    let _ = eframe::run_native("Mori", options, Box::new(|_cc| Ok(Box::new(launcher))));
+}
+
+// Holds the app back until the setup screen is resolved, nothing is loaded before then
+struct Launcher {
+    setup: Option<SetupScreen>,
+    unlock: Option<UnlockScreen>,
+    resume_prompt: Option<ResumeScreen>,
+    app: Option<App>,
+    resume: Option<(String, Option<String>)>,
+}
+
+impl eframe::App for Launcher {
+    fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        if let Some(setup) = &mut self.setup {
+            if !setup.render(ctx) {
+                return;
+            }
+            self.setup = None;
+        }
+        if self.app.is_none() && secrets::needs_unlock() {
+            if !self.unlock.get_or_insert_with(UnlockScreen::default).render(ctx) {
+                return;
+            }
+            self.unlock = None;
+        }
+        if let Some(prompt) = &mut self.resume_prompt {
+            match prompt.render(ctx) {
+                Some(Some(passphrase)) => self.resume = Some((prompt.path().to_string(), Some(passphrase))),
+                Some(None) => {}
+                None => return,
+            }
+            self.resume_prompt = None;
+        }
+        let resume = &mut self.resume;
+        self.app
+            .get_or_insert_with(|| App::new(ctx, resume.take()))
+            .update(ctx, frame);
+    }
+
+    fn clear_color(&self, _visuals: &egui::Visuals) -> [f32; 4] {
+        egui::Rgba::TRANSPARENT.to_array()
+    }
+
+    // Timed flushes would lose whatever changed since the last one
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        if let Some(app) = &self.app {
+            app.bot_manager.read().unwrap().save_all();
+        }
+    }
 }
 
 struct App {
     navbar: Navbar,
     item_database: ItemDatabase,
     add_bot_dialog: AddBotDialog,
+    import_dialog: ImportDialog,
     add_proxy_dialog: AddProxyDialog,
+    crash_dialog: CrashDialog,
+    notifications: NotificationsPanel,
     bot_manager: Arc<RwLock<BotManager>>,
     proxy_manager: Arc<RwLock<ProxyManager>>,
     texture_manager: texture_manager::TextureManager,
     proxy_list: ProxyList,
     settings: Settings,
+    config_updates: Receiver<Arc<Config>>,
     bot_menu: BotMenu,
+    // The selected bot, the only one whose packets redraw the GUI
+    shown_bot: Option<Arc<Bot>>,
 }
 
 impl App {
-    fn new(cc: &eframe::CreationContext<'_>) -> Self {
+    fn new(ctx: &egui::Context, resume: Option<(String, Option<String>)>) -> Self {
         let mut fonts = egui::FontDefinitions::default();
         egui_remixicon::add_to_fonts(&mut fonts);
-        cc.egui_ctx.set_fonts(fonts);
+        ctx.set_fonts(fonts);
+        repaint::init(ctx);
         let mut texture_manager = texture_manager::TextureManager::new();
-        texture_manager.load_textures(&cc.egui_ctx);
+        texture_manager.load_textures(ctx);
 
+        if let Err(err) = config::load() {
+            toasts::error(&format!("Failed to load {}, using the defaults: {}", config::CONFIG_PATH, err));
+        }
         let proxy_manager = Arc::new(RwLock::new(ProxyManager::new()));
         let bot_manager = Arc::new(RwLock::new(BotManager::new(proxy_manager.clone())));
+        crash::install(bot_manager.clone());
+        update_check::spawn();
+        bandwidth::spawn();
+        value_table::spawn();
+        config::watch(|result| match result {
+            Ok(()) => toasts::info("Config reloaded"),
+            Err(err) => toasts::error(&format!(
+                "Config invalid: {}, keeping in-memory copy and not saving until it's fixed",
+                err
+            )),
+        });
+
+        // Resumed bots are started by resume_session, a failed resume logs everyone in normally
+        let resumed = match resume {
+            Some((path, passphrase)) => {
+                match bot_manager.read().unwrap().resume_session(&path, passphrase.as_deref()) {
+                    Ok(resumed) => {
+                        toasts::info(&format!("Resumed {} bots from {}", resumed.len(), path));
+                        resumed
+                    }
+                    Err(err) => {
+                        toasts::error(&format!("Not resuming {}: {}", path, err));
+                        Vec::new()
+                    }
+                }
+            }
+            None => Vec::new(),
+        };
         let bots = config::get_bots();
         for bot in bots.clone() {
-            bot_manager.write().unwrap().add_bot(bot);
+            let username = utils::textparse::parse_and_store_as_vec(&bot.payload)[0].clone();
+            if resumed.contains(&username) {
+                continue;
+            }
+            bot_manager.read().unwrap().add_bot(bot);
+        }
+        login_schedule::spawn(bot_manager.clone());
+        if secrets::is_locked() {
+            toasts::error("Config is locked: bots can't log in and changes aren't saved");
         }
 
         Self {
             navbar: Default::default(),
             item_database: Default::default(),
             add_bot_dialog: Default::default(),
+            import_dialog: Default::default(),
             add_proxy_dialog: Default::default(),
+            crash_dialog: CrashDialog::from_pending(),
+            notifications: Default::default(),
             bot_menu: Default::default(),
             proxy_list: Default::default(),
-            settings: Settings {
-                use_alternate: config::get_use_alternate_server(),
-                timeout_delay: config::get_timeout(),
-                findpath_delay: config::get_findpath_delay(),
-                auto_collect: config::get_auto_collect(),
-                theme: config::get_theme(),
-                captcha_provider: config::get_captcha_provider(),
-                captcha_api_key: config::get_captcha_api_key(),
-            },
+            settings: Settings::from_config(&config::get()),
+            config_updates: config::subscribe(),
             proxy_manager,
             bot_manager,
             texture_manager,
+            shown_bot: None,
         }
     }
+
+    // Follows the selection, and a restart of the selected bot that replaced its Arc
+    fn update_shown_bot(&mut self) {
+        let selected = self.bot_manager.read().unwrap().get_bot(&config::get_selected_bot());
+        let unchanged = match (&self.shown_bot, &selected) {
+            (Some(shown), Some(selected)) => Arc::ptr_eq(shown, selected),
+            (None, None) => true,
+            _ => false,
+        };
+        if unchanged {
+            return;
+        }
+        if let Some(shown) = &self.shown_bot {
+            shown.shown.store(false, Ordering::Relaxed);
+        }
+        if let Some(selected) = &selected {
+            selected.shown.store(true, Ordering::Relaxed);
+        }
+        self.shown_bot = selected;
+    }
 }
 
 impl eframe::App for App {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        ctx.request_repaint();
+        // Bot threads ask for a frame through repaint::request when something changes,
+        // this only keeps clocks and countdowns moving when nothing does
+        ctx.request_repaint_after(IDLE_REPAINT);
+        self.update_shown_bot();
+
+        while let Ok(config) = self.config_updates.try_recv() {
+            self.settings = Settings::from_config(&config);
+        }
 
         match self.settings.theme {
             Theme::Dark => {
@@ -191,7 +561,12 @@ impl eframe::App for App {
                     ui.heading("Mori");
                     ui.separator();
                     self.navbar
-                        .render(ui, &mut self.add_bot_dialog, &self.bot_manager);
+                        .render(
+                            ui,
+                            &mut self.add_bot_dialog,
+                            &mut self.import_dialog,
+                            &self.bot_manager,
+                        );
                 },
             );
 
@@ -254,12 +629,27 @@ impl eframe::App for App {
                     &mut self.add_proxy_dialog,
                     ctx,
                 ),
-                "settings" => self.settings.render(&mut content_ui, ctx),
+                "settings" => self.settings.render(&mut content_ui, ctx, &self.bot_manager),
+                "notifications" => self.notifications.render(
+                    &mut content_ui,
+                    &self.bot_manager,
+                    &mut self.navbar,
+                ),
                 _ => {}
             }
+            self.bot_menu.world_map.end_hidden_manual_control(ctx);
             self.add_bot_dialog.render(&mut self.bot_manager, ctx);
+            self.import_dialog.render(&self.bot_manager, ctx);
             self.add_proxy_dialog.render(&mut self.proxy_manager, ctx);
+            self.crash_dialog.render(ctx);
+            self.notifications.render_two_factor(ctx, &self.bot_manager);
+            self.notifications.render_world_password(ctx, &self.bot_manager);
+            self.notifications.render_release(ctx);
         });
+        self.notifications.poll();
+        if let Some(click) = toasts::render(ctx) {
+            self.notifications.handle_click(click, &self.bot_manager, &mut self.navbar);
+        }
     }
 
     fn clear_color(&self, _visuals: &egui::Visuals) -> [f32; 4] {
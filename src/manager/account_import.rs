@@ -0,0 +1,68 @@
+use crate::types::{config::BotConfig, elogin_method::ELoginMethod};
+
+#[derive(Debug, Default, Clone)]
+pub struct ImportSummary {
+    pub added: Vec<String>,
+    pub skipped: Vec<String>,
+    pub malformed: Vec<(usize, String)>,
+    // Added without a proxy because every proxy in their pool was full
+    pub no_proxy: Vec<String>,
+}
+
+// Lines are `growid:password` / `mail:password` for every method except Steam,
+// which uses `steamuser:steampass:mail:password`. The password is last, so any
+// further `:` belong to it
+pub fn parse_line(line: &str, method: &ELoginMethod) -> Result<BotConfig, String> {
+    let count = match method {
+        ELoginMethod::STEAM => 4,
+        _ => 2,
+    };
+    let fields: Vec<&str> = line.splitn(count, ':').map(|field| field.trim()).collect();
+    let payload = match method {
+        ELoginMethod::STEAM => {
+            if fields.len() != 4 {
+                return Err("expected steamuser:steampass:mail:password".to_string());
+            }
+            format!("{}|{}|{}|{}", fields[2], fields[3], fields[0], fields[1])
+        }
+        _ => {
+            if fields.len() != 2 {
+                return Err("expected name:password".to_string());
+            }
+            format!("{}|{}", fields[0], fields[1])
+        }
+    };
+    if fields.iter().any(|field| field.is_empty()) {
+        return Err("empty field".to_string());
+    }
+
+    Ok(BotConfig {
+        payload,
+        login_method: method.clone(),
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn password_keeps_its_colons() {
+        let bot = parse_line("grower:pa:ss:word", &ELoginMethod::LEGACY).unwrap();
+        assert_eq!(bot.payload, "grower|pa:ss:word");
+    }
+
+    #[test]
+    fn steam_lines_put_the_mail_first() {
+        let bot = parse_line("steamer:steampass:mail@x.com:pa:ss", &ELoginMethod::STEAM).unwrap();
+        assert_eq!(bot.payload, "mail@x.com|pa:ss|steamer|steampass");
+    }
+
+    #[test]
+    fn rejects_missing_and_empty_fields() {
+        assert!(parse_line("grower", &ELoginMethod::LEGACY).is_err());
+        assert!(parse_line("grower:", &ELoginMethod::LEGACY).is_err());
+        assert!(parse_line("steamer:steampass:mail@x.com", &ELoginMethod::STEAM).is_err());
+    }
+}
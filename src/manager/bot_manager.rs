@@ -1,74 +1,456 @@
-use crate::core::Bot;
-use crate::manager::proxy_manager::ProxyManager;
-use crate::types::config::BotConfig;
+use crate::core::features::transfer_items::{self, TransferReport};
+use crate::core::{crash, items, reactions, Bot};
+use crate::manager::account_import::{self, ImportSummary};
+use crate::manager::bot_template;
+use crate::manager::login_schedule;
+use crate::manager::message_bus::MessageBus;
+use crate::manager::proxy_manager::{self, ProxyManager};
+use crate::manager::session::{self, BotSession};
+use crate::types::{bot_summary::BotSummary, config::{resolved, BotConfig}, elogin_method::ELoginMethod};
 use crate::utils;
+use crate::utils::error::CustomError;
+use paris::error;
 use gtitem_r::structs::ItemDatabase;
-use std::sync::{Arc, RwLock};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex, RwLock, TryLockError};
+use std::path::Path;
+use std::{fs, io};
 use std::thread;
 use std::thread::{spawn, JoinHandle};
+use std::time::{Duration, Instant};
+
+// The bot list redraws every frame, per-bot locks are only taken this often
+const SUMMARY_REFRESH: Duration = Duration::from_millis(500);
+// How long stop_bot waits for the login thread to end
+const STOP_TIMEOUT: Duration = Duration::from_secs(30);
+const STOP_POLL: Duration = Duration::from_millis(100);
 
 pub struct BotManager {
-    pub bots: Vec<(Arc<Bot>, JoinHandle<()>)>,
+    // Copy-on-write map, readers clone the inner Arc and never wait on add/remove
+    bots: RwLock<Arc<HashMap<String, Arc<Bot>>>>,
+    // Serializes writers so concurrent add/remove don't lose each other's updates
+    write_lock: Mutex<()>,
+    handles: Mutex<HashMap<String, JoinHandle<()>>>,
+    summaries: Mutex<Option<(Instant, Arc<Vec<BotSummary>>)>>,
     pub items_database: Arc<RwLock<ItemDatabase>>,
     pub proxy_manager: Arc<RwLock<ProxyManager>>,
+    pub message_bus: Arc<MessageBus>,
 }
 
 impl BotManager {
     pub fn new(proxy_manager: Arc<RwLock<ProxyManager>>) -> Self {
         let item_database = Arc::new(RwLock::new(ItemDatabase::new()));
+        // Stale files are fine here, the hash is checked again against the server on login
+        items::load_from_disk(&item_database);
 
         Self {
-            bots: vec![],
+            bots: RwLock::new(Arc::new(HashMap::new())),
+            write_lock: Mutex::new(()),
+            handles: Mutex::new(HashMap::new()),
+            summaries: Mutex::new(None),
             items_database: item_database,
             proxy_manager,
+            message_bus: Arc::new(MessageBus::new()),
         }
     }
 }
 
 impl BotManager {
-    pub fn add_bot(&mut self, bot: BotConfig) {
+    // Bots with a login window are only registered, login_schedule starts them
+    pub fn add_bot(&self, bot: BotConfig) {
+        let username = utils::textparse::parse_and_store_as_vec(&bot.payload)[0].clone();
+        let scheduled = login_schedule::window_for(&bot).is_some();
+        if let Err(err) = self.register_bot(bot) {
+            error!("Failed to create bot {}: {}", username, err);
+            return;
+        }
+        if !scheduled {
+            self.start_bot(&username);
+        }
+    }
+
+    // Creates the bot without logging it in, see `start_bot`
+    pub fn register_bot(&self, bot: BotConfig) -> Result<(), CustomError> {
         let items_database_clone = Arc::clone(&self.items_database);
         let proxy_manager_clone = Arc::clone(&self.proxy_manager);
 
-        let new_bot = Bot::new(bot.clone(), items_database_clone, proxy_manager_clone);
-        let newbot_clone = Arc::clone(&new_bot);
+        let username = utils::textparse::parse_and_store_as_vec(&bot.payload)[0].clone();
+        let new_bot = Bot::new(
+            bot,
+            items_database_clone,
+            proxy_manager_clone,
+            Arc::clone(&self.message_bus),
+        )?;
+        self.message_bus.register(&username, &new_bot);
+        self.modify(|bots| {
+            bots.insert(username, new_bot);
+        });
+        Ok(())
+    }
+
+    pub fn start_bot(&self, username: &str) {
+        let data = utils::config::get_bots()
+            .into_iter()
+            .find(|config| utils::textparse::parse_and_store_as_vec(&config.payload)[0] == username)
+            .map(|config| config.data)
+            .unwrap_or_default();
+        self.start_bot_with(username, data);
+    }
+
+    // `data` is the LoginInfo to log in with, a fresh identity is spoofed when empty
+    fn start_bot_with(&self, username: &str, data: String) {
+        let bot = match self.get_bot(username) {
+            Some(bot) => bot,
+            None => return,
+        };
+        // Its password and token are still sealed
+        if utils::secrets::is_locked() {
+            bot.set_status("Config locked");
+            return;
+        }
+        let mut handles = self.handles.lock().unwrap();
+        if handles.contains_key(username) {
+            return;
+        }
+        let name = username.to_string();
+        handles.insert(
+            username.to_string(),
+            spawn(move || {
+                crash::set_current_bot(&name);
+                bot.logon(data);
+            }),
+        );
+    }
+
+    // Writes every running bot's token, identity, world and stats so a restarted process can
+    // pick them up with `resume_session`. Returns how many bots were written
+    pub fn export_session(&self, path: &str, passphrase: Option<&str>) -> io::Result<usize> {
+        let sessions: Vec<BotSession> = self
+            .snapshot()
+            .into_iter()
+            .filter(|(name, _)| self.is_started(name))
+            .map(|(_, bot)| session::capture(&bot))
+            .collect();
+        let count = sessions.len();
+        session::write(Path::new(path), sessions, passphrase)?;
+        Ok(count)
+    }
+
+    // Flushes every bot's stats, script storage and timeline, called when the app closes
+    pub fn save_all(&self) {
+        for (_, bot) in self.snapshot() {
+            bot.save_persistent();
+        }
+    }
+
+    // Starts the bots saved by `export_session` with their old token and identity, the token
+    // is checked by the normal login and only a rejected one goes through OAuth again. Bots
+    // then head back to their world and tile. Returns the names that were resumed, a file
+    // older than `session_max_age` is refused so the caller falls back to a normal login
+    pub fn resume_session(&self, path: &str, passphrase: Option<&str>) -> Result<Vec<String>, String> {
+        let file = session::read(Path::new(path), passphrase)?;
+        let max_age = utils::config::get_session_max_age();
+        if file.age_secs() > max_age {
+            return Err(format!(
+                "session is {}s old, older than the {}s limit",
+                file.age_secs(),
+                max_age
+            ));
+        }
+
+        let configs = utils::config::get_bots();
+        let mut resumed = Vec::new();
+        for saved in file.bots {
+            let config = match configs.iter().find(|config| {
+                utils::textparse::parse_and_store_as_vec(&config.payload)[0] == saved.username
+            }) {
+                Some(config) => config.clone(),
+                None => continue,
+            };
+            if let Err(err) = self.register_bot(config) {
+                error!("Failed to create bot {}: {}", saved.username, err);
+                continue;
+            }
+            let bot = match self.get_bot(&saved.username) {
+                Some(bot) => bot,
+                None => continue,
+            };
+            bot.info.lock().unwrap().token = saved.token.clone();
+            {
+                let mut stats = bot.stats.lock().unwrap();
+                stats.started_at = saved.started_at;
+                stats.counters = saved.counters.clone();
+            }
+            if saved.automation_paused {
+                bot.pause_automation();
+            }
+            if let Some(feature) = &saved.active_feature {
+                bot.log_warn(&format!("{} was running before the restart, start it again to continue", feature));
+            }
+            self.start_bot_with(&saved.username, saved.login_data.clone());
+            spawn(move || return_to(&bot, &saved));
+            resumed.push(saved.username);
+        }
+        Ok(resumed)
+    }
+
+    // Starts the bot again once its login thread gave up, e.g. after a failed 2FA.
+    // False while that thread is still running
+    pub fn restart_bot(&self, username: &str) -> bool {
+        {
+            let mut handles = self.handles.lock().unwrap();
+            if handles.get(username).map_or(false, |handle| !handle.is_finished()) {
+                return false;
+            }
+            handles.remove(username);
+        }
+        self.start_bot(username);
+        true
+    }
+
+    // Logs the bot out and lets start_bot log it in again later. The handle stays until the
+    // login thread is joined, so start_bot can't run a second one beside it. False when the
+    // thread didn't end within STOP_TIMEOUT, the bot then still counts as started
+    pub fn stop_bot(&self, username: &str) -> bool {
+        if let Some(bot) = self.get_bot(username) {
+            bot.state.lock().unwrap().is_running = false;
+            bot.disconnect();
+            bot.set_status("Logged out");
+        }
+        let started = Instant::now();
+        loop {
+            {
+                let mut handles = self.handles.lock().unwrap();
+                match handles.get(username).map(JoinHandle::is_finished) {
+                    None => return true,
+                    Some(true) => {
+                        if let Some(handle) = handles.remove(username) {
+                            let _ = handle.join();
+                        }
+                        return true;
+                    }
+                    Some(false) => {}
+                }
+            }
+            if started.elapsed() >= STOP_TIMEOUT {
+                return false;
+            }
+            thread::sleep(STOP_POLL);
+        }
+    }
 
-        let handle = spawn(move || {
-            newbot_clone.logon(bot.data.clone());
+    pub fn is_started(&self, username: &str) -> bool {
+        self.handles.lock().unwrap().contains_key(username)
+    }
+
+    pub fn import_accounts(
+        &self,
+        path: &str,
+        method: ELoginMethod,
+        assign_proxies: bool,
+        logon: bool,
+        template: &str,
+    ) -> io::Result<ImportSummary> {
+        let contents = fs::read_to_string(path)?;
+        let mut summary = ImportSummary::default();
+        let mut known: HashSet<String> = utils::config::get_bots()
+            .iter()
+            .map(|bot| utils::textparse::parse_and_store_as_vec(&bot.payload)[0].to_lowercase())
+            .collect();
+        let template = bot_template::find(template);
+
+        let mut imported = Vec::new();
+        for (index, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut bot = match account_import::parse_line(line, &method) {
+                Ok(bot) => bot,
+                Err(err) => {
+                    summary.malformed.push((index + 1, err));
+                    continue;
+                }
+            };
+            let username = utils::textparse::parse_and_store_as_vec(&bot.payload)[0].clone();
+            if !known.insert(username.to_lowercase()) {
+                summary.skipped.push(username);
+                continue;
+            }
+            if let Some(template) = &template {
+                bot.template = template.name.clone();
+            }
+
+            if assign_proxies {
+                // Bot::new picks up the proxy already reserved for this name. The template's
+                // pool and group count, the bot's own are still empty here
+                let pool = proxy_manager::resolve_pool(&resolved(&bot, template.as_slice()));
+                let assigned = self.proxy_manager.write().unwrap().assign_least_used(&username, &pool);
+                match assigned {
+                    Some(_) => bot.use_proxy = true,
+                    None => summary.no_proxy.push(username.clone()),
+                }
+            }
+            imported.push((index + 1, username, bot));
+        }
+
+        // One write for the whole file, Bot::new reads the bot's settings from it
+        utils::config::update(|config| {
+            config.bots.extend(imported.iter().map(|(_, _, bot)| bot.clone()));
         });
-        self.bots.push((new_bot, handle));
+        let mut failed = HashSet::new();
+        for (line, username, bot) in imported {
+            if let Err(err) = self.register_bot(bot) {
+                summary.malformed.push((line, err.to_string()));
+                for proxy in self.proxy_manager.write().unwrap().proxies.iter_mut() {
+                    proxy.whos_using.retain(|name| *name != username);
+                }
+                failed.insert(username);
+                continue;
+            }
+            summary.added.push(username);
+        }
+        if !failed.is_empty() {
+            utils::config::update(|config| {
+                config.bots.retain(|bot| {
+                    !failed.contains(&utils::textparse::parse_and_store_as_vec(&bot.payload)[0])
+                });
+            });
+        }
+        if logon {
+            for username in summary.added.iter() {
+                self.start_bot(username);
+            }
+        }
+
+        Ok(summary)
     }
 
-    pub fn remove_bot(&mut self, username: &str) {
+    pub fn remove_bot(&self, username: &str) {
         let bot = self.get_bot(username);
         if let Some(bot) = bot {
-            let bot_clone = Arc::clone(bot);
             thread::spawn(move || {
                 let is_running = {
-                    let state = bot_clone.state.lock().unwrap();
+                    let state = bot.state.lock().unwrap();
                     state.is_running
                 };
 
                 if is_running {
                     {
-                        let mut state = bot_clone.state.lock().unwrap();
+                        let mut state = bot.state.lock().unwrap();
                         state.is_running = false;
                     }
-                    bot_clone.disconnect();
+                    bot.disconnect();
                 }
             });
-            self.bots
-                .retain(|(b, _)| b.info.lock().unwrap().payload[0] != username);
+            self.modify(|bots| {
+                bots.remove(username);
+            });
+            self.message_bus.unregister(username);
+            self.handles.lock().unwrap().remove(username);
             utils::config::remove_bot(username.to_string());
         }
     }
 
-    pub fn get_bot(&self, username: &str) -> Option<&Arc<Bot>> {
-        for (bot, _) in &self.bots {
-            if bot.info.lock().unwrap().payload[0] == username {
-                return Some(bot);
+    // Runs features::transfer_items on its own thread, both bots must already be logged in
+    pub fn transfer_items(
+        &self,
+        from: &str,
+        to: &str,
+        items: Vec<(u32, u32)>,
+        world: String,
+    ) -> Result<JoinHandle<TransferReport>, String> {
+        let sender = self.get_bot(from).ok_or(format!("No bot named {}", from))?;
+        let receiver = self.get_bot(to).ok_or(format!("No bot named {}", to))?;
+        if Arc::ptr_eq(&sender, &receiver) {
+            return Err("Sender and receiver are the same bot".to_string());
+        }
+        Ok(spawn(move || transfer_items::run(&sender, &receiver, items, world)))
+    }
+
+    pub fn get_bot(&self, username: &str) -> Option<Arc<Bot>> {
+        self.bots.read().unwrap().get(username).cloned()
+    }
+
+    // Unsorted, non-blocking variant for the panic hook. None while a writer is swapping in
+    // a new map, a poisoned lock is still read
+    pub fn try_snapshot(&self) -> Option<Vec<(String, Arc<Bot>)>> {
+        let bots = match self.bots.try_read() {
+            Ok(bots) => bots.clone(),
+            Err(TryLockError::Poisoned(poisoned)) => poisoned.into_inner().clone(),
+            Err(TryLockError::WouldBlock) => return None,
+        };
+        Some(bots.iter().map(|(name, bot)| (name.clone(), Arc::clone(bot))).collect())
+    }
+
+    // Cheap copy of every bot for iteration, sorted by name
+    pub fn snapshot(&self) -> Vec<(String, Arc<Bot>)> {
+        let bots = self.bots.read().unwrap().clone();
+        let mut snapshot: Vec<(String, Arc<Bot>)> = bots
+            .iter()
+            .map(|(name, bot)| (name.clone(), Arc::clone(bot)))
+            .collect();
+        snapshot.sort_by(|a, b| a.0.cmp(&b.0));
+        snapshot
+    }
+
+    // Summary of every bot sorted by name, rebuilt at most every SUMMARY_REFRESH
+    pub fn summaries(&self) -> Arc<Vec<BotSummary>> {
+        let mut cache = self.summaries.lock().unwrap();
+        if let Some((built_at, summaries)) = cache.as_ref() {
+            if built_at.elapsed() < SUMMARY_REFRESH {
+                return Arc::clone(summaries);
             }
         }
-        None
+        let groups: HashMap<String, String> = utils::config::get_bots()
+            .into_iter()
+            .map(|config| {
+                let name = utils::textparse::parse_and_store_as_vec(&config.payload)[0].clone();
+                (name, config.group)
+            })
+            .collect();
+        let summaries: Vec<BotSummary> = self
+            .snapshot()
+            .into_iter()
+            .map(|(name, bot)| BotSummary {
+                group: groups.get(&name).cloned().unwrap_or_default(),
+                ..bot.summary()
+            })
+            .collect();
+        let summaries = Arc::new(summaries);
+        *cache = Some((Instant::now(), Arc::clone(&summaries)));
+        summaries
+    }
+
+    fn modify<F: FnOnce(&mut HashMap<String, Arc<Bot>>)>(&self, f: F) {
+        let _guard = self.write_lock.lock().unwrap();
+        let mut bots = self.bots.read().unwrap().as_ref().clone();
+        f(&mut bots);
+        *self.bots.write().unwrap() = Arc::new(bots);
+    }
+}
+
+const RESUME_LOGIN_TIMEOUT: Duration = Duration::from_secs(120);
+
+// Waits for the resumed bot to get in game, then takes it back to where it was
+fn return_to(bot: &Arc<Bot>, saved: &BotSession) {
+    if saved.world.is_empty() || saved.world == "EXIT" {
+        return;
+    }
+    let started = Instant::now();
+    while !bot.state.lock().unwrap().is_ingame {
+        if started.elapsed() > RESUME_LOGIN_TIMEOUT {
+            bot.log_warn("Not in game after resuming, not returning to the last world");
+            return;
+        }
+        thread::sleep(Duration::from_secs(1));
+    }
+    if !reactions::warp_to(bot, &saved.world) {
+        bot.log_warn(&format!("Failed to return to {}", saved.world));
+        return;
+    }
+    let (x, y) = saved.position;
+    if let Err(err) = bot.find_path((x / 32.0) as u32, (y / 32.0) as u32) {
+        bot.log_warn(&format!("Couldn't walk back to the last position: {}", err));
     }
 }
@@ -0,0 +1,170 @@
+use super::bot_manager::BotManager;
+use crate::types::config::{resolved, BotConfig, BotTemplate, Config};
+use crate::utils::{config, textparse};
+use std::collections::HashMap;
+
+pub fn find(name: &str) -> Option<BotTemplate> {
+    if name.is_empty() {
+        return None;
+    }
+    config::get_bot_templates()
+        .into_iter()
+        .find(|template| template.name.eq_ignore_ascii_case(name))
+}
+
+// Starting point for a new bot, the credentials are filled in on top of it. Only the login
+// method is copied since the credentials are in its format, the rest is read from the
+// template for as long as the bot doesn't set its own
+pub fn config_from(name: &str) -> BotConfig {
+    let mut bot_config = BotConfig::default();
+    if let Some(template) = find(name) {
+        bot_config.login_method = template.login_method.clone();
+        bot_config.template = template.name;
+    }
+    bot_config
+}
+
+// Bots added from each template by lowercased template name, worked out once per config
+// change for the settings panel
+pub fn users(config: &Config) -> HashMap<String, Vec<String>> {
+    let mut users: HashMap<String, Vec<String>> = HashMap::new();
+    for bot_config in config.bots.iter().filter(|bot_config| !bot_config.template.is_empty()) {
+        users
+            .entry(bot_config.template.to_lowercase())
+            .or_default()
+            .push(textparse::parse_and_store_as_vec(&bot_config.payload)[0].clone());
+    }
+    users
+}
+
+// Saves `templates` and drops what the bots added from `name` set themselves among its
+// settings, in one config write. The humanizer preset is swapped on running bots right
+// away, the rest is read on their next login or feature run
+pub fn reapply(manager: &BotManager, templates: Vec<BotTemplate>, name: &str) -> Vec<String> {
+    let mut updated = Vec::new();
+    config::update(|config| {
+        config.bot_templates = templates;
+        for bot_config in config.bots.iter_mut().filter(|bot_config| bot_config.template.eq_ignore_ascii_case(name)) {
+            BotTemplate::clear_overrides(bot_config);
+            updated.push(textparse::parse_and_store_as_vec(&bot_config.payload)[0].clone());
+        }
+    });
+    for username in &updated {
+        if let Some(bot) = manager.get_bot(username) {
+            bot.set_humanizer(None);
+        }
+    }
+    updated
+}
+
+// Saves `templates` without `name`. Its bots keep what it gave them as their own settings
+// rather than pointing at a template that's gone
+pub fn remove(templates: Vec<BotTemplate>, name: &str) -> usize {
+    let mut kept = 0;
+    config::update(|config| {
+        for bot_config in config.bots.iter_mut() {
+            if bot_config.template.eq_ignore_ascii_case(name) {
+                *bot_config = resolved(bot_config, &config.bot_templates);
+                bot_config.template.clear();
+                kept += 1;
+            }
+        }
+        config.bot_templates = templates;
+    });
+    kept
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::ehumanizer_preset::EHumanizerPreset;
+    use crate::types::ereaction::EReaction;
+
+    fn template() -> BotTemplate {
+        BotTemplate {
+            name: "Farmers".to_string(),
+            group: "farm".to_string(),
+            proxy_pool: "residential".to_string(),
+            use_proxy: true,
+            humanizer: EHumanizerPreset::Stealthy,
+            login_window: "14:00-16:00".to_string(),
+            inventory_reactions: vec![EReaction::ALL[0]],
+            storage_world: "vault".to_string(),
+            deposit_threshold: 500,
+            ..Default::default()
+        }
+    }
+
+    fn bot(name: &str, template: &str) -> BotConfig {
+        BotConfig {
+            payload: format!("{}|secret", name),
+            template: template.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn unset_settings_come_from_the_template() {
+        let resolved = resolved(&bot("alice", "farmers"), &[template()]);
+        assert_eq!(resolved.group, "farm");
+        assert_eq!(resolved.proxy_pool, "residential");
+        assert!(resolved.use_proxy);
+        assert_eq!(resolved.humanizer, Some(EHumanizerPreset::Stealthy));
+        assert_eq!(resolved.login_window, "14:00-16:00");
+        assert_eq!(resolved.inventory_reactions, vec![EReaction::ALL[0]]);
+        assert_eq!(resolved.storage_world, "VAULT");
+        assert_eq!(resolved.deposit_threshold, 500);
+    }
+
+    #[test]
+    fn the_bots_own_settings_win() {
+        let mut own = bot("alice", "farmers");
+        own.group = "mules".to_string();
+        own.humanizer = Some(EHumanizerPreset::Fast);
+        own.deposit_threshold = 10;
+        let resolved = resolved(&own, &[template()]);
+        assert_eq!(resolved.group, "mules");
+        assert_eq!(resolved.humanizer, Some(EHumanizerPreset::Fast));
+        assert_eq!(resolved.deposit_threshold, 10);
+        assert_eq!(resolved.proxy_pool, "residential");
+    }
+
+    #[test]
+    fn a_missing_template_changes_nothing() {
+        for own in [bot("alice", "gone"), bot("bob", "")] {
+            let resolved = resolved(&own, &[template()]);
+            assert!(resolved.group.is_empty());
+            assert!(!resolved.use_proxy);
+            assert_eq!(resolved.humanizer, None);
+            assert_eq!(resolved.deposit_threshold, 0);
+        }
+    }
+
+    #[test]
+    fn off_is_kept_over_the_template() {
+        let mut own = bot("alice", "farmers");
+        own.humanizer = Some(EHumanizerPreset::Off);
+        assert_eq!(resolved(&own, &[template()]).humanizer, Some(EHumanizerPreset::Off));
+    }
+
+    #[test]
+    fn cleared_overrides_fall_back_to_the_template() {
+        let mut own = bot("alice", "farmers");
+        own.group = "mules".to_string();
+        own.humanizer = Some(EHumanizerPreset::Fast);
+        BotTemplate::clear_overrides(&mut own);
+        assert_eq!(resolved(&own, &[template()]).group, "farm");
+        assert_eq!(resolved(&own, &[template()]).humanizer, Some(EHumanizerPreset::Stealthy));
+    }
+
+    #[test]
+    fn lists_users_by_template() {
+        let config = Config {
+            bots: vec![bot("alice", "Farmers"), bot("bob", ""), bot("carol", "farmers")],
+            ..Default::default()
+        };
+        let users = users(&config);
+        assert_eq!(users.get("farmers"), Some(&vec!["alice".to_string(), "carol".to_string()]));
+        assert_eq!(users.len(), 1);
+    }
+}
@@ -0,0 +1,221 @@
+use super::bot_manager::BotManager;
+use crate::types::config::BotConfig;
+use crate::utils::{self, config, date, random};
+use paris::warn;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::sync::{Arc, RwLock};
+use std::thread;
+use std::time::Duration;
+
+const SCHEDULE_PATH: &str = "login_schedule.json";
+const TICK: Duration = Duration::from_secs(20);
+// Logging out right on the minute the window ends is a pattern of its own
+const LOGOUT_SLACK: u64 = 600;
+// Login times are sampled from the first part of the window so every bot gets some time online
+const LOGIN_SPREAD: f64 = 0.75;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct Slot {
+    day: i64,
+    login: u64,
+    logout: u64,
+    // The window end was handled, a bot started after it stays online
+    #[serde(default)]
+    ended: bool,
+}
+
+#[derive(Debug, PartialEq)]
+enum Step {
+    Scheduled,
+    Online,
+    // Past the window, every bot still online is logged out once however it was started
+    End,
+    Done,
+}
+
+fn step(slot: &Slot, now: u64) -> Step {
+    if now < slot.login {
+        Step::Scheduled
+    } else if now < slot.logout {
+        Step::Online
+    } else if !slot.ended {
+        Step::End
+    } else {
+        Step::Done
+    }
+}
+
+// Start and end as minutes after local midnight, the end may be past 24h
+pub fn parse_window(window: &str) -> Option<(u64, u64)> {
+    let (start, end) = window.split_once('-')?;
+    let minutes = |time: &str| -> Option<u64> {
+        let (hours, minutes) = time.trim().split_once(':')?;
+        let (hours, minutes): (u64, u64) = (hours.parse().ok()?, minutes.parse().ok()?);
+        (hours < 24 && minutes < 60).then_some(hours * 60 + minutes)
+    };
+    let (start, end) = (minutes(start)?, minutes(end)?);
+    if start == end {
+        return None;
+    }
+    Some((start, if end < start { end + 24 * 60 } else { end }))
+}
+
+// The bot's own window, or its group's
+pub fn window_for(bot_config: &BotConfig) -> Option<(u64, u64)> {
+    if !bot_config.login_window.trim().is_empty() {
+        return parse_window(&bot_config.login_window);
+    }
+    if bot_config.group.is_empty() {
+        return None;
+    }
+    config::get_login_windows()
+        .into_iter()
+        .find(|entry| entry.group.eq_ignore_ascii_case(&bot_config.group))
+        .and_then(|entry| parse_window(&entry.window))
+}
+
+// Logs windowed bots in at their sampled time and out again after the window. "Login"
+// always works, outside the window the bot is only logged out at the next window end
+pub fn spawn(manager: Arc<RwLock<BotManager>>) {
+    thread::spawn(move || {
+        let mut slots: BTreeMap<String, Slot> = load();
+        loop {
+            let now = date::now_secs();
+            let utc_offset = config::get_stats_utc_offset();
+            let today = date::day_index(now, utc_offset, 0);
+            let mut changed = false;
+            for bot_config in config::get_bots() {
+                let window = match window_for(&bot_config) {
+                    Some(window) => window,
+                    None => continue,
+                };
+                let name = utils::textparse::parse_and_store_as_vec(&bot_config.payload)[0].clone();
+                let slot = match slots.get(&name) {
+                    // Yesterday's slot stays until its logout, a window can wrap past midnight
+                    Some(slot) if slot.day == today || now < slot.logout => *slot,
+                    _ => {
+                        let slot = sample(window, today, utc_offset);
+                        slots.insert(name.clone(), slot);
+                        changed = true;
+                        slot
+                    }
+                };
+                let manager = manager.read().unwrap();
+                let bot = match manager.get_bot(&name) {
+                    Some(bot) => bot,
+                    None => continue,
+                };
+                match step(&slot, now) {
+                    Step::Scheduled if !manager.is_started(&name) => bot.set_status(&format!(
+                        "Scheduled {}",
+                        &date::format_time(slot.login, utc_offset)[..5]
+                    )),
+                    Step::Online if !manager.is_started(&name) => manager.start_bot(&name),
+                    Step::End => {
+                        if manager.is_started(&name) {
+                            bot.log_info("Login window ended, logging out");
+                            if !manager.stop_bot(&name) {
+                                // Tried again next tick
+                                bot.log_warn("Login thread didn't end, logout pending");
+                                continue;
+                            }
+                        }
+                        if let Some(slot) = slots.get_mut(&name) {
+                            slot.ended = true;
+                        }
+                        changed = true;
+                    }
+                    _ => {}
+                }
+            }
+            if changed {
+                save(&slots);
+            }
+            thread::sleep(TICK);
+        }
+    });
+}
+
+// Random login inside today's window and a logout a little after it ends
+fn sample((start, end): (u64, u64), day: i64, utc_offset: i32) -> Slot {
+    let midnight = (day * 86400 - utc_offset as i64 * 3600).max(0) as u64;
+    let spread = ((end - start) as f64 * LOGIN_SPREAD) as u64 * 60;
+    Slot {
+        day,
+        login: midnight + start * 60 + random::range(0, spread),
+        logout: midnight + end * 60 + random::range(0, LOGOUT_SLACK),
+        ended: false,
+    }
+}
+
+fn load() -> BTreeMap<String, Slot> {
+    fs::read_to_string(SCHEDULE_PATH)
+        .ok()
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+fn save(slots: &BTreeMap<String, Slot>) {
+    let contents = match serde_json::to_string_pretty(slots) {
+        Ok(contents) => contents,
+        Err(_) => return,
+    };
+    if let Err(err) = fs::write(SCHEDULE_PATH, contents) {
+        warn!("Failed to save {}: {}", SCHEDULE_PATH, err);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_windows() {
+        assert_eq!(parse_window("14:00-16:00"), Some((840, 960)));
+        assert_eq!(parse_window(" 9:30 - 10:05 "), Some((570, 605)));
+        // Past midnight the end is pushed into the next day
+        assert_eq!(parse_window("23:00-01:30"), Some((1380, 1530)));
+    }
+
+    #[test]
+    fn rejects_bad_windows() {
+        for window in ["", "14:00", "14:00-14:00", "24:00-01:00", "10:60-11:00", "a-b"] {
+            assert_eq!(parse_window(window), None, "{}", window);
+        }
+    }
+
+    #[test]
+    fn samples_inside_the_window() {
+        let (start, end) = (840, 960);
+        // Day 20000 at UTC+2
+        let midnight = 20000 * 86400 - 2 * 3600;
+        for _ in 0..100 {
+            let slot = sample((start, end), 20000, 2);
+            assert!(slot.login >= midnight + start * 60);
+            assert!(slot.login <= midnight + start * 60 + 90 * 60);
+            assert!(slot.logout >= midnight + end * 60);
+            assert!(slot.logout <= midnight + end * 60 + LOGOUT_SLACK);
+            assert!(!slot.ended);
+        }
+    }
+
+    #[test]
+    fn steps_through_the_window() {
+        let mut slot = Slot {
+            day: 0,
+            login: 1000,
+            logout: 2000,
+            ended: false,
+        };
+        assert_eq!(step(&slot, 999), Step::Scheduled);
+        assert_eq!(step(&slot, 1000), Step::Online);
+        assert_eq!(step(&slot, 1999), Step::Online);
+        assert_eq!(step(&slot, 2000), Step::End);
+        assert_eq!(step(&slot, 5000), Step::End);
+        slot.ended = true;
+        // A bot logged in by hand after the window isn't logged out again
+        assert_eq!(step(&slot, 5000), Step::Done);
+    }
+}
@@ -0,0 +1,101 @@
+use crate::core::Bot;
+use crate::types::bot_event::BotEvent;
+use crate::utils;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock, Weak};
+use thiserror::Error;
+
+// Serialized JSON size, large enough for item lists but not for abuse
+const MAX_PAYLOAD: usize = 8 * 1024;
+
+#[derive(Error, Debug)]
+pub enum BusError {
+    #[error("Payload is {0} bytes, the limit is {MAX_PAYLOAD}")]
+    PayloadTooLarge(usize),
+    #[error("No bot or group named {0}")]
+    UnknownTarget(String),
+}
+
+// Process-wide bus for scripts on different bots. Messages land in the recipient's
+// event queue, which is already bounded, so nothing piles up for a bot that isn't reading
+#[derive(Default)]
+pub struct MessageBus {
+    bots: RwLock<HashMap<String, Weak<Bot>>>,
+}
+
+impl MessageBus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register(&self, name: &str, bot: &Arc<Bot>) {
+        self.bots
+            .write()
+            .unwrap()
+            .insert(name.to_string(), Arc::downgrade(bot));
+    }
+
+    pub fn unregister(&self, name: &str) {
+        self.bots.write().unwrap().remove(name);
+    }
+
+    // A registered bot that is still alive
+    pub fn bot(&self, name: &str) -> Option<Arc<Bot>> {
+        self.bots.read().unwrap().get(name)?.upgrade()
+    }
+
+    // `target` is a bot name or a group. Returns how many bots got the message,
+    // offline ones are skipped with a warning on the sender
+    pub fn send(
+        &self,
+        sender: &Bot,
+        target: &str,
+        topic: &str,
+        payload: serde_json::Value,
+        loopback: bool,
+    ) -> Result<usize, BusError> {
+        let size = payload.to_string().len();
+        if size > MAX_PAYLOAD {
+            return Err(BusError::PayloadTooLarge(size));
+        }
+        let sender_name = sender.info.lock().unwrap().payload[0].clone();
+        let recipients = self.resolve(target);
+        if recipients.is_empty() {
+            return Err(BusError::UnknownTarget(target.to_string()));
+        }
+
+        let mut delivered = 0;
+        for (name, bot) in recipients {
+            if name == sender_name && !loopback {
+                continue;
+            }
+            if !bot.state.lock().unwrap().is_ingame {
+                sender.log_warn(&format!("Dropped bus message {} to {}, it is offline", topic, name));
+                continue;
+            }
+            bot.emit(BotEvent::Message {
+                sender: sender_name.clone(),
+                topic: topic.to_string(),
+                payload: payload.clone(),
+            });
+            delivered += 1;
+        }
+        Ok(delivered)
+    }
+
+    fn resolve(&self, target: &str) -> Vec<(String, Arc<Bot>)> {
+        let bots = self.bots.read().unwrap();
+        if let Some(bot) = bots.get(target).and_then(Weak::upgrade) {
+            return vec![(target.to_string(), bot)];
+        }
+        utils::config::get_bots()
+            .into_iter()
+            .filter(|config| !config.group.is_empty() && config.group == target)
+            .filter_map(|config| {
+                let name = utils::textparse::parse_and_store_as_vec(&config.payload)[0].clone();
+                let bot = bots.get(&name).and_then(Weak::upgrade)?;
+                Some((name, bot))
+            })
+            .collect()
+    }
+}
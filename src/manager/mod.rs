@@ -1,3 +1,11 @@
+pub mod account_check;
+pub mod account_import;
 pub mod bot_manager;
+pub mod bot_template;
+pub mod login_schedule;
+pub mod message_bus;
+pub mod notifications;
 pub mod proxy_manager;
+pub mod session;
+pub mod update_check;
 
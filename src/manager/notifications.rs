@@ -0,0 +1,88 @@
+use crate::types::{
+    enotification_action::ENotificationAction, eseverity::ESeverity, notification::Notification,
+};
+use crate::utils::{date, repaint};
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+// Oldest ones are dropped past this, read or not
+const MAX_NOTIFICATIONS: usize = 200;
+
+#[derive(Default)]
+struct Store {
+    next_id: u64,
+    notifications: VecDeque<Notification>,
+    // Published since the GUI last asked, each becomes a toast once
+    unshown: Vec<u64>,
+}
+
+static STORE: Mutex<Option<Store>> = Mutex::new(None);
+
+fn with_store<R>(f: impl FnOnce(&mut Store) -> R) -> R {
+    let mut store = STORE.lock().unwrap();
+    f(store.get_or_insert_with(Store::default))
+}
+
+pub fn publish(bot: &str, severity: ESeverity, message: String, action: Option<ENotificationAction>) {
+    with_store(|store| {
+        store.next_id += 1;
+        if store.notifications.len() >= MAX_NOTIFICATIONS {
+            store.notifications.pop_front();
+        }
+        store.notifications.push_back(Notification {
+            id: store.next_id,
+            bot: bot.to_string(),
+            severity,
+            message,
+            action,
+            timestamp: date::now_secs(),
+            read: false,
+        });
+        store.unshown.push(store.next_id);
+    });
+    repaint::request();
+}
+
+// Newest first
+pub fn all() -> Vec<Notification> {
+    with_store(|store| store.notifications.iter().rev().cloned().collect())
+}
+
+pub fn get(id: u64) -> Option<Notification> {
+    with_store(|store| store.notifications.iter().find(|n| n.id == id).cloned())
+}
+
+pub fn take_unshown() -> Vec<Notification> {
+    with_store(|store| {
+        let ids: Vec<u64> = store.unshown.drain(..).collect();
+        store
+            .notifications
+            .iter()
+            .filter(|notification| ids.contains(&notification.id))
+            .cloned()
+            .collect()
+    })
+}
+
+pub fn unread_count() -> usize {
+    with_store(|store| store.notifications.iter().filter(|n| !n.read).count())
+}
+
+pub fn mark_read(id: u64) {
+    with_store(|store| {
+        if let Some(notification) = store.notifications.iter_mut().find(|n| n.id == id) {
+            notification.read = true;
+        }
+    });
+}
+
+pub fn mark_all_read() {
+    with_store(|store| store.notifications.iter_mut().for_each(|n| n.read = true));
+}
+
+pub fn clear() {
+    with_store(|store| {
+        store.notifications.clear();
+        store.unshown.clear();
+    });
+}
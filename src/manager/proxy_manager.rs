@@ -2,6 +2,10 @@ use std::net::SocketAddr;
 use std::str::FromStr;
 use socks::Socks5Datagram;
 use crate::{types, utils};
+use crate::types::config::{BotConfig, ProxyPool};
+
+// Proxies with an empty pool name and bots without a pool mapping use this one
+pub const DEFAULT_POOL: &str = "default";
 
 #[derive(Clone)]
 pub struct Proxy {
@@ -14,6 +18,47 @@ pub struct ProxyManager {
     pub proxies: Vec<Proxy>,
 }
 
+pub struct PoolUsage {
+    pub name: String,
+    pub proxies: usize,
+    pub bots: usize,
+    pub capacity: usize,
+}
+
+pub fn pool_name(name: &str) -> &str {
+    if name.trim().is_empty() {
+        DEFAULT_POOL
+    } else {
+        name.trim()
+    }
+}
+
+// The bot's own pool first, then the first pool listing its group, then the default one
+pub fn resolve_pool(bot_config: &BotConfig) -> String {
+    if !bot_config.proxy_pool.trim().is_empty() {
+        return pool_name(&bot_config.proxy_pool).to_string();
+    }
+    utils::config::get_proxy_pools()
+        .into_iter()
+        .find(|pool| {
+            !bot_config.group.is_empty()
+                && pool.groups.iter().any(|group| group.eq_ignore_ascii_case(&bot_config.group))
+        })
+        .map(|pool| pool_name(&pool.name).to_string())
+        .unwrap_or_else(|| DEFAULT_POOL.to_string())
+}
+
+// Pools only need a config entry to change their defaults
+pub fn pool_settings(name: &str) -> ProxyPool {
+    utils::config::get_proxy_pools()
+        .into_iter()
+        .find(|pool| pool_name(&pool.name) == name)
+        .unwrap_or_else(|| ProxyPool {
+            name: name.to_string(),
+            ..Default::default()
+        })
+}
+
 impl ProxyManager {
     pub fn new() -> Self {
         let proxies = utils::config::get_proxies();
@@ -41,6 +86,93 @@ impl ProxyManager {
         utils::config::add_proxy(proxy);
     }
 
+    // Proxy the bot already holds in `pool`, else the first one there with room left.
+    // None when the pool is exhausted
+    pub fn assign(&mut self, username: &str, pool: &str) -> Option<usize> {
+        if let Some(index) = self.reserved(username, pool) {
+            return Some(index);
+        }
+        let max_bots = pool_settings(pool).max_bots_per_proxy.max(1) as usize;
+        let index = self.proxies.iter().position(|proxy| {
+            pool_name(&proxy.proxy.pool) == pool && proxy.whos_using.len() < max_bots
+        })?;
+        self.proxies[index].whos_using.push(username.to_string());
+        Some(index)
+    }
+
+    // Like `assign`, but picks the proxy in `pool` with the fewest bots, so a batch of new
+    // bots is spread over the pool instead of filling the first proxy
+    pub fn assign_least_used(&mut self, username: &str, pool: &str) -> Option<usize> {
+        if let Some(index) = self.reserved(username, pool) {
+            return Some(index);
+        }
+        let max_bots = pool_settings(pool).max_bots_per_proxy.max(1) as usize;
+        let index = self
+            .proxies
+            .iter()
+            .enumerate()
+            .filter(|(_, proxy)| pool_name(&proxy.proxy.pool) == pool && proxy.whos_using.len() < max_bots)
+            .min_by_key(|(_, proxy)| proxy.whos_using.len())
+            .map(|(index, _)| index)?;
+        self.proxies[index].whos_using.push(username.to_string());
+        Some(index)
+    }
+
+    // A bot moved to another pool gives up the proxy it held in the old one
+    fn reserved(&mut self, username: &str, pool: &str) -> Option<usize> {
+        let mut reserved = None;
+        for (index, proxy) in self.proxies.iter_mut().enumerate() {
+            if !proxy.whos_using.iter().any(|name| name == username) {
+                continue;
+            }
+            if reserved.is_none() && pool_name(&proxy.proxy.pool) == pool {
+                reserved = Some(index);
+            } else {
+                proxy.whos_using.retain(|name| name != username);
+            }
+        }
+        reserved
+    }
+
+    pub fn pools(&self) -> Vec<String> {
+        let mut names: Vec<String> = utils::config::get_proxy_pools()
+            .iter()
+            .map(|pool| pool_name(&pool.name).to_string())
+            .chain(self.proxies.iter().map(|proxy| pool_name(&proxy.proxy.pool).to_string()))
+            .collect();
+        names.push(DEFAULT_POOL.to_string());
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    pub fn usage(&self) -> Vec<PoolUsage> {
+        self.pools()
+            .into_iter()
+            .map(|name| {
+                let max_bots = pool_settings(&name).max_bots_per_proxy.max(1) as usize;
+                let members: Vec<&Proxy> = self
+                    .proxies
+                    .iter()
+                    .filter(|proxy| pool_name(&proxy.proxy.pool) == name)
+                    .collect();
+                PoolUsage {
+                    proxies: members.len(),
+                    bots: members.iter().map(|proxy| proxy.whos_using.len()).sum(),
+                    capacity: members.len() * max_bots,
+                    name,
+                }
+            })
+            .collect()
+    }
+
+    pub fn set_pool(&mut self, index: usize, pool: &str) {
+        if let Some(proxy) = self.proxies.get_mut(index) {
+            proxy.proxy.pool = if pool == DEFAULT_POOL { String::new() } else { pool.to_string() };
+            utils::config::set_proxy(index, proxy.proxy.clone());
+        }
+    }
+
     pub fn remove(&mut self, index: usize) {
         self.proxies.remove(index);
         utils::config::remove_proxy(index);
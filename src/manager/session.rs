@@ -0,0 +1,125 @@
+use crate::core::Bot;
+use crate::utils::{date, seal};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+// Everything needed to pick a bot up again after a restart without a full login
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct BotSession {
+    pub username: String,
+    pub world: String,
+    pub position: (f32, f32),
+    pub started_at: u64,
+    pub counters: BTreeMap<String, u64>,
+    pub active_feature: Option<String>,
+    pub automation_paused: bool,
+    // Left empty in the file when the secrets are sealed
+    pub token: String,
+    // LoginInfo in its key|value form, the spoofed identity the token belongs to
+    pub login_data: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct SessionFile {
+    pub saved_at: u64,
+    pub bots: Vec<BotSession>,
+    // Sealed JSON of every bot's Secrets when exported with a passphrase
+    pub sealed_secrets: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Secrets {
+    username: String,
+    token: String,
+    login_data: String,
+}
+
+impl SessionFile {
+    pub fn age_secs(&self) -> u64 {
+        date::now_secs().saturating_sub(self.saved_at)
+    }
+}
+
+pub fn capture(bot: &Bot) -> BotSession {
+    let (username, token, login_data) = {
+        let info = bot.info.lock().unwrap();
+        (info.payload[0].clone(), info.token.clone(), info.login_info.to_string())
+    };
+    let position = {
+        let position = bot.position.lock().unwrap();
+        (position.x, position.y)
+    };
+    let (started_at, counters) = {
+        let stats = bot.stats.lock().unwrap();
+        (stats.started_at, stats.counters.clone())
+    };
+    let temp = bot.temporary_data.read().unwrap();
+    BotSession {
+        username,
+        world: bot.world.read().unwrap().name.clone(),
+        position,
+        started_at,
+        counters,
+        active_feature: temp.active_feature.clone(),
+        automation_paused: temp.automation_paused,
+        token,
+        login_data,
+    }
+}
+
+pub fn write(path: &Path, mut bots: Vec<BotSession>, passphrase: Option<&str>) -> io::Result<()> {
+    let sealed_secrets = match passphrase {
+        Some(passphrase) => {
+            let secrets: Vec<Secrets> = bots
+                .iter_mut()
+                .map(|bot| Secrets {
+                    username: bot.username.clone(),
+                    token: std::mem::take(&mut bot.token),
+                    login_data: std::mem::take(&mut bot.login_data),
+                })
+                .collect();
+            let json = serde_json::to_vec(&secrets).map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
+            Some(seal::seal(passphrase, &json))
+        }
+        None => None,
+    };
+    let file = SessionFile {
+        saved_at: date::now_secs(),
+        bots,
+        sealed_secrets,
+    };
+    let json = serde_json::to_string_pretty(&file).map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, json)?;
+    fs::rename(&tmp, path)
+}
+
+// Reads the file back with the secrets filled in, a passphrase is needed when they were sealed
+pub fn read(path: &Path, passphrase: Option<&str>) -> Result<SessionFile, String> {
+    let contents = fs::read_to_string(path).map_err(|err| err.to_string())?;
+    let mut file: SessionFile = serde_json::from_str(&contents).map_err(|err| err.to_string())?;
+    if let Some(sealed) = file.sealed_secrets.take() {
+        let passphrase = passphrase.ok_or("the session file needs a passphrase")?;
+        let json = seal::open(passphrase, &sealed)?;
+        let secrets: Vec<Secrets> = serde_json::from_slice(&json).map_err(|err| err.to_string())?;
+        for secret in secrets {
+            if let Some(bot) = file.bots.iter_mut().find(|bot| bot.username == secret.username) {
+                bot.token = secret.token;
+                bot.login_data = secret.login_data;
+            }
+        }
+    }
+    Ok(file)
+}
+
+pub fn is_sealed(path: &Path) -> bool {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|contents| serde_json::from_str::<SessionFile>(&contents).ok())
+        .map_or(false, |file| file.sealed_secrets.is_some())
+}
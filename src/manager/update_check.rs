@@ -0,0 +1,151 @@
+use super::notifications;
+use crate::types::{enotification_action::ENotificationAction, eseverity::ESeverity};
+use crate::utils::{config, date};
+use paris::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::thread;
+use std::time::Duration;
+
+const RELEASES_URL: &str = "https://api.github.com/repos/H-pun/Mori/releases/latest";
+const CACHE_PATH: &str = "update_check.json";
+const CHECK_INTERVAL: u64 = 24 * 3600;
+// A failed check waits this long instead, so an offline machine doesn't keep trying
+const RETRY_INTERVAL: u64 = 3600;
+const POLL: Duration = Duration::from_secs(600);
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+pub const CURRENT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Release {
+    pub version: String,
+    pub notes: String,
+    pub url: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct Cache {
+    checked_at: u64,
+    failed: bool,
+    latest: Option<Release>,
+    // Version a notification was already published for
+    notified: String,
+}
+
+// Checks on startup and then daily while enabled, entirely on its own thread
+pub fn spawn() {
+    thread::spawn(|| loop {
+        if config::get_check_updates() {
+            let mut cache = load_cache();
+            let wait = if cache.failed { RETRY_INTERVAL } else { CHECK_INTERVAL };
+            if date::now_secs().saturating_sub(cache.checked_at) >= wait {
+                check(&mut cache);
+                notify(&mut cache);
+                save_cache(&cache);
+            }
+        }
+        thread::sleep(POLL);
+    });
+}
+
+// Newest release seen, only when it is newer than this build
+pub fn available() -> Option<Release> {
+    load_cache()
+        .latest
+        .filter(|release| is_newer(&release.version, CURRENT_VERSION))
+}
+
+fn check(cache: &mut Cache) {
+    cache.checked_at = date::now_secs();
+    match fetch() {
+        Ok(release) => {
+            cache.failed = false;
+            cache.latest = Some(release);
+        }
+        Err(err) => {
+            cache.failed = true;
+            warn!("Update check failed: {}", err);
+        }
+    }
+}
+
+// No global proxy setting exists, so this follows the usual HTTPS_PROXY / ALL_PROXY variables
+fn fetch() -> Result<Release, String> {
+    let agent = ureq::AgentBuilder::new()
+        .timeout(TIMEOUT)
+        .try_proxy_from_env(true)
+        .build();
+    let json: serde_json::Value = agent
+        .get(RELEASES_URL)
+        .set("Accept", "application/vnd.github+json")
+        .set("User-Agent", &format!("Mori/{}", CURRENT_VERSION))
+        .call()
+        .map_err(|err| err.to_string())?
+        .into_json()
+        .map_err(|err| err.to_string())?;
+    let version = json["tag_name"]
+        .as_str()
+        .ok_or("Release has no tag")?
+        .trim_start_matches('v')
+        .to_string();
+    Ok(Release {
+        version,
+        notes: json["body"].as_str().unwrap_or_default().to_string(),
+        url: json["html_url"].as_str().unwrap_or_default().to_string(),
+    })
+}
+
+fn notify(cache: &mut Cache) {
+    let release = match &cache.latest {
+        Some(release) if is_newer(&release.version, CURRENT_VERSION) => release,
+        _ => return,
+    };
+    if cache.notified == release.version {
+        return;
+    }
+    info!(
+        "Mori {} is available (running {}): {}\n{}",
+        release.version, CURRENT_VERSION, release.url, release.notes
+    );
+    notifications::publish(
+        "Mori",
+        ESeverity::Info,
+        format!("Version {} is available, running {}", release.version, CURRENT_VERSION),
+        Some(ENotificationAction::ShowRelease {
+            version: release.version.clone(),
+        }),
+    );
+    cache.notified = release.version.clone();
+}
+
+// Compares major.minor.patch, anything after a '-' or '+' is ignored
+fn is_newer(version: &str, current: &str) -> bool {
+    fn parse(version: &str) -> Option<(u64, u64, u64)> {
+        let core = version.trim_start_matches('v').split(['-', '+']).next()?;
+        let mut parts = core.split('.').map(|part| part.parse::<u64>().ok());
+        Some((parts.next()??, parts.next().flatten().unwrap_or(0), parts.next().flatten().unwrap_or(0)))
+    }
+    match (parse(version), parse(current)) {
+        (Some(version), Some(current)) => version > current,
+        _ => false,
+    }
+}
+
+fn load_cache() -> Cache {
+    fs::read_to_string(CACHE_PATH)
+        .ok()
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+fn save_cache(cache: &Cache) {
+    let contents = match serde_json::to_string_pretty(cache) {
+        Ok(contents) => contents,
+        Err(_) => return,
+    };
+    if let Err(err) = fs::write(CACHE_PATH, contents) {
+        warn!("Failed to save {}: {}", CACHE_PATH, err);
+    }
+}
@@ -0,0 +1,304 @@
+// Local stand-in for a game server, enough for a bot to log in, enter a world and walk
+// around without the real one. Point server_ip and server_port at it.
+//
+// It answers every connection with the server hello, echoes the bot's own state packets
+// back, hands over every drop the bot picks up and sends `world` as the map data on a join
+// request. Everything else comes from the scenario, a JSON list of replies:
+//
+//   { "net_id": 1, "world": "world.dat", "replies": [
+//     { "trigger": "text:protocol|", "channel": 0, "data": "04000000..." },
+//     { "trigger": "text:action|quit", "disconnect": true }
+//   ] }
+//
+// `data` is a whole packet in hex, type included, as the dev tools capture shows it.
+// Triggers are `connect`, `text:<substring>` for text packets and `tank:<type>` for game
+// packets. Replies without `repeat` fire once per connection, `delay_ms` holds one back
+// without holding up anything else. tests/mock_server has example scenarios
+use crate::types::config::NetworkConfig;
+use crate::types::epacket_type::EPacketType;
+use crate::types::etank_packet_type::ETankPacketType;
+use crate::types::tank_packet::TankPacket;
+use crate::utils::config;
+use byteorder::{ByteOrder, LittleEndian};
+use paris::{info, warn};
+use rusty_enet as enet;
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::net::{ToSocketAddrs, UdpSocket};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+pub const DEFAULT_PORT: u16 = 17091;
+// Delayed replies waiting at once, a repeating trigger can't queue up without bound
+const MAX_PENDING: usize = 256;
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct Scenario {
+    // Echoed state packets carry this, canned spawns should use the same
+    pub net_id: u32,
+    pub world: String,
+    pub replies: Vec<Reply>,
+}
+
+impl Default for Scenario {
+    fn default() -> Self {
+        Scenario {
+            net_id: 1,
+            world: String::new(),
+            replies: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Reply {
+    pub trigger: String,
+    pub channel: u8,
+    pub data: String,
+    pub delay_ms: u64,
+    pub repeat: bool,
+    // Drops the connection after sending `data`, if any
+    pub disconnect: bool,
+}
+
+pub fn load(path: &str) -> Result<Scenario, String> {
+    let contents = fs::read_to_string(path).map_err(|err| format!("{}: {}", path, err))?;
+    let scenario: Scenario =
+        serde_json::from_str(&contents).map_err(|err| format!("{}: {}", path, err))?;
+    // Bad hex is reported up front rather than when the trigger fires
+    for (index, reply) in scenario.replies.iter().enumerate() {
+        if !reply.data.is_empty() {
+            hex::decode(&reply.data).map_err(|err| format!("reply {}: {}", index, err))?;
+        }
+    }
+    Ok(scenario)
+}
+
+// Serves until the process is stopped
+pub fn run(scenario: Scenario, port: u16) -> Result<(), String> {
+    let network = config::get().network.clone();
+    let mut server = Server::bind(scenario, ("0.0.0.0", port), &network)?;
+    info!("Mock server listening on port {}", server.port());
+    server.serve(&AtomicBool::new(false));
+    Ok(())
+}
+
+pub struct Server {
+    host: enet::Host<UdpSocket>,
+    port: u16,
+    scenario: Scenario,
+    world: Option<Vec<u8>>,
+    // Replies already sent, per peer
+    fired: HashMap<enet::PeerID, HashSet<usize>>,
+    // Replies with a delay and when they're due. The loop sends them, so a delay holds up
+    // nothing else
+    pending: Vec<(Instant, enet::PeerID, usize)>,
+}
+
+impl Server {
+    pub fn bind(scenario: Scenario, address: impl ToSocketAddrs, network: &NetworkConfig) -> Result<Self, String> {
+        let socket = UdpSocket::bind(address).map_err(|err| err.to_string())?;
+        let port = socket.local_addr().map_err(|err| err.to_string())?.port();
+        let host = enet::Host::<UdpSocket>::new(socket, host_settings(network))
+            .map_err(|err| format!("failed to create host: {:?}", err))?;
+        let world = if scenario.world.is_empty() {
+            None
+        } else {
+            Some(fs::read(&scenario.world).map_err(|err| format!("{}: {}", scenario.world, err))?)
+        };
+        Ok(Server {
+            host,
+            port,
+            scenario,
+            world,
+            fired: HashMap::new(),
+            pending: Vec::new(),
+        })
+    }
+
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    // Serves until `stop` is set
+    pub fn serve(&mut self, stop: &AtomicBool) {
+        while !stop.load(Ordering::Relaxed) {
+            self.send_due();
+            match self.host.service().ok().flatten().map(|event| event.no_ref()) {
+                Some(event) => self.handle(event),
+                None => thread::sleep(Duration::from_millis(5)),
+            }
+        }
+    }
+
+    fn handle(&mut self, event: enet::EventNoRef) {
+        match event {
+            enet::EventNoRef::Connect { peer, .. } => {
+                info!("Peer {:?} connected", peer);
+                self.fired.insert(peer, HashSet::new());
+                send(&mut self.host, peer, 0, &(EPacketType::NetMessageServerHello as u32).to_le_bytes());
+                self.respond(peer, |trigger| trigger == "connect");
+            }
+            enet::EventNoRef::Disconnect { peer, .. } => {
+                info!("Peer {:?} disconnected", peer);
+                self.fired.remove(&peer);
+                self.pending.retain(|&(_, pending_peer, _)| pending_peer != peer);
+            }
+            enet::EventNoRef::Receive { peer, packet, .. } => {
+                let data = packet.data().to_vec();
+                if data.len() < 4 {
+                    return;
+                }
+                match EPacketType::from(LittleEndian::read_u32(&data[0..4])) {
+                    EPacketType::NetMessageGenericText | EPacketType::NetMessageGameMessage => {
+                        let text = String::from_utf8_lossy(&data[4..]).to_string();
+                        if text.contains("action|join_request") {
+                            if let Some(world) = &self.world {
+                                send_world(&mut self.host, peer, world);
+                            }
+                        }
+                        self.respond(peer, |trigger| {
+                            trigger.strip_prefix("text:").map_or(false, |needle| text.contains(needle))
+                        });
+                    }
+                    EPacketType::NetMessageGamePacket => {
+                        let mut tank_packet = match TankPacket::from_bytes(&data[4..]) {
+                            Ok(tank_packet) => tank_packet,
+                            Err(_) => return,
+                        };
+                        let trigger = format!("tank:{}", u8::from(tank_packet._type));
+                        match tank_packet._type {
+                            ETankPacketType::NetGamePacketState => {
+                                tank_packet.net_id = self.scenario.net_id;
+                                send_tank(&mut self.host, peer, &tank_packet, &[]);
+                            }
+                            ETankPacketType::NetGamePacketItemActivateObjectRequest => {
+                                // The drop's uid comes back with the picker's net id
+                                let pickup = TankPacket {
+                                    _type: ETankPacketType::NetGamePacketItemChangeObject,
+                                    net_id: self.scenario.net_id,
+                                    value: tank_packet.value,
+                                    ..Default::default()
+                                };
+                                send_tank(&mut self.host, peer, &pickup, &[]);
+                            }
+                            _ => {}
+                        }
+                        self.respond(peer, |candidate| candidate == trigger);
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    fn respond(&mut self, peer: enet::PeerID, matches: impl Fn(&str) -> bool) {
+        let triggered: Vec<usize> = (0..self.scenario.replies.len())
+            .filter(|&index| matches(&self.scenario.replies[index].trigger))
+            .collect();
+        for index in triggered {
+            let reply = &self.scenario.replies[index];
+            if !reply.repeat && !self.fired.entry(peer).or_default().insert(index) {
+                continue;
+            }
+            if reply.delay_ms == 0 {
+                self.send_reply(peer, index);
+            } else if self.pending.len() < MAX_PENDING {
+                self.pending.push((Instant::now() + Duration::from_millis(reply.delay_ms), peer, index));
+            } else {
+                warn!("{} delayed replies already waiting, dropping {}", MAX_PENDING, reply.trigger);
+            }
+        }
+    }
+
+    fn send_due(&mut self) {
+        if self.pending.is_empty() {
+            return;
+        }
+        let now = Instant::now();
+        let (due, waiting): (Vec<_>, Vec<_>) =
+            std::mem::take(&mut self.pending).into_iter().partition(|&(at, _, _)| at <= now);
+        self.pending = waiting;
+        for (_, peer, index) in due {
+            self.send_reply(peer, index);
+        }
+    }
+
+    fn send_reply(&mut self, peer: enet::PeerID, index: usize) {
+        let reply = &self.scenario.replies[index];
+        if let Ok(data) = hex::decode(&reply.data) {
+            if !data.is_empty() {
+                send(&mut self.host, peer, reply.channel, &data);
+            }
+        }
+        if reply.disconnect {
+            info!("Dropping peer {:?} ({})", peer, reply.trigger);
+            self.host.peer_mut(peer).disconnect_later(0);
+        }
+    }
+}
+
+// Same compression and checksum as the bots use, both sides have to agree
+pub fn host_settings(network: &NetworkConfig) -> enet::HostSettings {
+    enet::HostSettings {
+        peer_limit: 32,
+        channel_limit: 2,
+        compressor: if network.use_compression_and_checksum {
+            Some(Box::new(enet::RangeCoder::new()))
+        } else {
+            None
+        },
+        checksum: if network.use_compression_and_checksum {
+            Some(Box::new(enet::crc32))
+        } else {
+            None
+        },
+        using_new_packet: true,
+        ..Default::default()
+    }
+}
+
+fn send_world(host: &mut enet::Host<UdpSocket>, peer: enet::PeerID, world: &[u8]) {
+    let tank_packet = TankPacket {
+        _type: ETankPacketType::NetGamePacketSendMapData,
+        net_id: u32::MAX,
+        flags: 8,
+        extended_data_length: world.len() as u32,
+        ..Default::default()
+    };
+    send_tank(host, peer, &tank_packet, world);
+}
+
+fn send_tank(
+    host: &mut enet::Host<UdpSocket>,
+    peer: enet::PeerID,
+    tank_packet: &TankPacket,
+    extended: &[u8],
+) {
+    let bytes = match tank_packet.to_bytes() {
+        Ok(bytes) => bytes,
+        Err(err) => {
+            warn!("Failed to serialize a tank packet: {}", err);
+            return;
+        }
+    };
+    let mut data = (EPacketType::NetMessageGamePacket as u32).to_le_bytes().to_vec();
+    data.extend_from_slice(&bytes);
+    data.extend_from_slice(extended);
+    send(host, peer, 0, &data);
+}
+
+fn send(host: &mut enet::Host<UdpSocket>, peer: enet::PeerID, channel: u8, data: &[u8]) {
+    let packet = enet::Packet::reliable(data);
+    if let Err(err) = host.peer_mut(peer).send(channel, &packet) {
+        warn!("Failed to send to peer {:?}: {}", peer, err);
+    }
+}
+
+#[cfg(test)]
+#[path = "../tests/mock_server/mod.rs"]
+mod tests;
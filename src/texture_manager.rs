@@ -1,5 +1,6 @@
-use egui::{ColorImage, Context, TextureHandle};
-use paris::info;
+use egui::{ColorImage, Context, Rect, TextureHandle, Vec2};
+use crate::utils::setup_check::TEXTURES_DIR;
+use paris::{info, warn};
 use std::collections::HashMap;
 use std::fs;
 
@@ -15,13 +16,28 @@ impl TextureManager {
     }
 
     pub fn load_textures(&mut self, ctx: &Context) {
-        let assets = fs::read_dir("game").expect("Failed to read assets directory");
-        for asset in assets {
-            let path = asset.unwrap().path();
-            let filename = path.file_name().unwrap().to_str().unwrap().to_string();
+        // setup_check reports a missing folder, a texture that fails to load is only left out
+        let assets = match fs::read_dir(TEXTURES_DIR) {
+            Ok(assets) => assets,
+            Err(err) => {
+                warn!("Failed to read the {} folder: {}", TEXTURES_DIR, err);
+                return;
+            }
+        };
+        for path in assets.filter_map(Result::ok).map(|asset| asset.path()) {
+            let filename = path.file_name().and_then(|name| name.to_str());
+            let (filename, path_str) = match (filename, path.to_str()) {
+                (Some(filename), Some(path_str)) => (filename.to_string(), path_str),
+                _ => continue,
+            };
 
-            let image_buffer =
-                rttex::get_image_buffer(path.to_str().unwrap()).expect("Failed to load image");
+            let image_buffer = match rttex::get_image_buffer(path_str) {
+                Some(image_buffer) => image_buffer,
+                None => {
+                    warn!("Failed to load texture {}", filename);
+                    continue;
+                }
+            };
             let size = [
                 image_buffer.width() as usize,
                 image_buffer.height() as usize,
@@ -46,3 +62,17 @@ impl TextureManager {
         }
     }
 }
+
+// Where the sprite at column `texture_x`, row `texture_y` of a sheet is, in the sheet's UV space
+pub fn sprite_uv(texture: &TextureHandle, texture_x: u8, texture_y: u8, sprite_size: Vec2) -> Rect {
+    let [width, height] = texture.size();
+    let min = egui::pos2(
+        texture_x as f32 * sprite_size.x / width as f32,
+        texture_y as f32 * sprite_size.y / height as f32,
+    );
+    let max = egui::pos2(
+        (texture_x as f32 * sprite_size.x + sprite_size.x) / width as f32,
+        (texture_y as f32 * sprite_size.y + sprite_size.y) / height as f32,
+    );
+    Rect::from_min_max(min, max)
+}
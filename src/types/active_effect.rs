@@ -0,0 +1,20 @@
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone)]
+pub struct ActiveEffect {
+    pub id: u32,
+    pub source_item: u32,
+    // Mod name the server reported, empty while only estimated from the config
+    pub name: String,
+    pub expires_at: Instant,
+}
+
+impl ActiveEffect {
+    pub fn remaining(&self) -> Duration {
+        self.expires_at.saturating_duration_since(Instant::now())
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.remaining().is_zero()
+    }
+}
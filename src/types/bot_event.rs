@@ -0,0 +1,140 @@
+use super::{
+    enotification_action::ENotificationAction, eseverity::ESeverity,
+    world_summary::WorldSummary,
+};
+
+#[derive(Debug, Clone)]
+pub enum BotEvent {
+    // First local spawn after connecting, once per login rather than per world
+    Login { net_id: u32, user_id: u32 },
+    Whisper { sender: String, text: String },
+    Intruder { name: String, reason: String },
+    WorldEnter(WorldSummary),
+    WorldLeave { name: String },
+    Particle { effect_id: u32, x: f32, y: f32 },
+    // `reconnect` is whether the bot will try to log back in on its own
+    Disconnect { reason: String, detail: String, reconnect: bool },
+    // ENet connected, login hasn't started yet
+    Connect { server_ip: String, port: u16 },
+    // The server sent us to another one, a disconnect and connect follow
+    Redirect { server: String },
+    // Every change of the status shown in the bot list
+    LoginStatus { status: String },
+    ItemGain { item_id: u32, amount: u32 },
+    Feature { name: String, running: bool },
+    InventoryNearlyFull { free_slots: u32 },
+    InventoryFull,
+    TradeRequest { name: String },
+    TwoFactorFailed,
+    // The world being warped to wants a password that isn't saved or was wrong
+    WorldPasswordRequired { world: String },
+    // From another bot's script over the message bus
+    Message { sender: String, topic: String, payload: serde_json::Value },
+    // The server put the bot somewhere else than it thought, by this many pixels
+    Resynced { dx: f32, dy: f32 },
+    // `world` is empty when the broadcast doesn't name one that can be warped to
+    SuperBroadcast { sender: String, world: String, text: String },
+    GuildChat { sender: String, text: String },
+}
+
+impl BotEvent {
+    // Scripts handle an event by defining a global `on_<name>` function
+    pub fn name(&self) -> &'static str {
+        match self {
+            BotEvent::Login { .. } => "login",
+            BotEvent::Whisper { .. } => "whisper",
+            BotEvent::Intruder { .. } => "intruder",
+            BotEvent::WorldEnter(_) => "world_enter",
+            BotEvent::WorldLeave { .. } => "world_leave",
+            BotEvent::Particle { .. } => "particle",
+            BotEvent::Disconnect { .. } => "disconnect",
+            BotEvent::Connect { .. } => "connect",
+            BotEvent::Redirect { .. } => "redirect",
+            BotEvent::LoginStatus { .. } => "login_status",
+            BotEvent::ItemGain { .. } => "item_gain",
+            BotEvent::Feature { .. } => "feature",
+            BotEvent::InventoryNearlyFull { .. } => "inventory_nearly_full",
+            BotEvent::InventoryFull => "inventory_full",
+            BotEvent::TradeRequest { .. } => "trade_request",
+            BotEvent::TwoFactorFailed => "two_factor_failed",
+            BotEvent::WorldPasswordRequired { .. } => "world_password_required",
+            BotEvent::Message { .. } => "message",
+            BotEvent::Resynced { .. } => "resynced",
+            BotEvent::SuperBroadcast { .. } => "sb",
+            BotEvent::GuildChat { .. } => "guild_chat",
+        }
+    }
+
+    // What the activity timeline shows, None for events too frequent to be worth keeping
+    pub fn timeline_detail(&self) -> Option<String> {
+        match self {
+            BotEvent::Login { .. } => Some("Logged in".to_string()),
+            BotEvent::Whisper { sender, text } => Some(format!("Whisper from {}: {}", sender, text)),
+            BotEvent::Intruder { name, reason } => Some(format!("Intruder {} ({})", name, reason)),
+            BotEvent::WorldEnter(summary) => Some(format!("Entered {}", summary.name)),
+            BotEvent::WorldLeave { name } => Some(format!("Left {}", name)),
+            BotEvent::Particle { .. } => None,
+            BotEvent::Disconnect { reason, detail, .. } => {
+                Some(format!("Disconnected: {} ({})", reason, detail))
+            }
+            BotEvent::Connect { server_ip, port } => Some(format!("Connected to {}:{}", server_ip, port)),
+            BotEvent::Redirect { server } => Some(format!("Redirected to {}", server)),
+            BotEvent::LoginStatus { .. } => None,
+            BotEvent::ItemGain { item_id, amount } => {
+                Some(format!("Collected {} of item {}", amount, item_id))
+            }
+            BotEvent::Feature { name, running } => Some(format!(
+                "{} {}",
+                name,
+                if *running { "started" } else { "stopped" }
+            )),
+            BotEvent::InventoryNearlyFull { free_slots } => {
+                Some(format!("Inventory nearly full, {} slots free", free_slots))
+            }
+            BotEvent::InventoryFull => Some("Inventory full".to_string()),
+            BotEvent::TradeRequest { name } => Some(format!("Trade request from {}", name)),
+            BotEvent::TwoFactorFailed => Some("2FA failed".to_string()),
+            BotEvent::WorldPasswordRequired { world } => {
+                Some(format!("Password needed for {}", world))
+            }
+            BotEvent::Message { .. } => None,
+            BotEvent::Resynced { dx, dy } => Some(format!("Position resynced by {:.0},{:.0} px", dx, dy)),
+            BotEvent::SuperBroadcast { .. } | BotEvent::GuildChat { .. } => None,
+        }
+    }
+
+    // Events someone should see even without watching the logs
+    pub fn notification(&self) -> Option<(ESeverity, String, Option<ENotificationAction>)> {
+        match self {
+            BotEvent::Disconnect { reason, .. } if reason == "Banned" => {
+                Some((ESeverity::Critical, "Banned".to_string(), None))
+            }
+            BotEvent::Intruder { name, reason } => Some((
+                ESeverity::Warning,
+                format!("Possible mod {} {}", name, reason),
+                Some(ENotificationAction::PauseBot),
+            )),
+            BotEvent::InventoryFull => Some((
+                ESeverity::Warning,
+                "Inventory full".to_string(),
+                Some(ENotificationAction::PauseBot),
+            )),
+            BotEvent::TradeRequest { name } => Some((
+                ESeverity::Info,
+                format!("{} wants to trade", name),
+                Some(ENotificationAction::OpenTrade { name: name.clone() }),
+            )),
+            BotEvent::TwoFactorFailed => Some((
+                ESeverity::Critical,
+                "2FA failed, a new code is needed".to_string(),
+                Some(ENotificationAction::EnterTwoFactor),
+            )),
+            BotEvent::WorldPasswordRequired { world } => Some((
+                ESeverity::Warning,
+                format!("{} needs a password", world),
+                Some(ENotificationAction::EnterWorldPassword { world: world.clone() }),
+            )),
+            _ => None,
+        }
+    }
+}
@@ -1,18 +1,29 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::{Duration, Instant};
 
-use super::{elogin_method::ELoginMethod, login_info::LoginInfo};
+use super::{
+    character_state::CharacterState, clothing::Clothing, disconnect_info::DisconnectInfo,
+    edisconnect_reason::EDisconnectReason, particle::Particle, profiler::Profiler,
+    watch_report::WatchReport, world_summary::WorldSummary,
+    edirection::EDirection, elogin_method::ELoginMethod, esteam_guard::ESteamGuard,
+    login_info::LoginInfo, oauth_links::OAuthLinks, quest::Quests,
+    static_farm_stats::StaticFarmStats, surgery_stats::SurgeryStats,
+    repeat_stats::RepeatStats, feature_run::FeatureRun,
+};
 
 #[derive(Debug, Default)]
 pub struct Info {
     pub payload: Vec<String>,
     pub recovery_code: String,
+    pub steam_shared_secret: String,
     pub login_method: ELoginMethod,
-    pub oauth_links: Vec<String>,
+    pub oauth_links: OAuthLinks,
     pub server_data: HashMap<String, String>,
     pub token: String,
     pub login_info: LoginInfo,
-    pub status: String,
     pub proxy: Option<ProxyInfo>,
+    // Pool that had no proxy left for this bot while direct connections are off
+    pub proxy_unavailable: Option<String>,
 }
 
 #[derive(Debug)]
@@ -26,14 +37,57 @@ pub struct ProxyInfo {
 #[derive(Debug, Default)]
 pub struct State {
     pub net_id: u32,
+    pub user_id: u32,
     pub level: i32,
-    pub gems: i32,
     pub is_running: bool,
     pub is_redirecting: bool,
     pub is_ingame: bool,
+    // Set on the first local spawn of a session. Unlike is_ingame it survives the
+    // sub-server redirects of world joins, so login hooks fire once per login
+    pub logged_in: bool,
+    // The client applies its skin color once per login, after the first OnSetClothing
+    pub skin_pending: bool,
     pub is_not_allowed_to_warp: bool,
     pub is_banned: bool,
     pub is_tutorial: bool,
+    pub facing: EDirection,
+    pub clothing: Clothing,
+    pub character: CharacterState,
+    pub last_disconnect: Option<DisconnectInfo>,
+    // Set by scripts to force noclip pathing on or off, None follows the character state
+    pub noclip_override: Option<bool>,
+}
+
+impl State {
+    // True on the first local spawn since the login started, the one login hooks fire on
+    pub fn local_spawn(&mut self, net_id: u32, user_id: u32) -> bool {
+        self.is_ingame = true;
+        self.net_id = net_id;
+        self.user_id = user_id;
+        let first = !self.logged_in;
+        self.logged_in = true;
+        self.skin_pending |= first;
+        first
+    }
+
+    // The connection is gone. A redirect to a sub-server keeps the login, anything else
+    // ends it. True when it ended
+    pub fn connection_lost(&mut self) -> bool {
+        self.is_ingame = false;
+        if self.is_redirecting {
+            return false;
+        }
+        self.logged_in = false;
+        self.skin_pending = false;
+        true
+    }
+
+    // The server greeted a fresh logon instead of a redirect, so whatever session came
+    // before is over even when a redirect was still pending
+    pub fn logon_started(&mut self) {
+        self.logged_in = false;
+        self.skin_pending = false;
+    }
 }
 
 #[derive(Debug, Default)]
@@ -55,6 +109,132 @@ pub struct TemporaryData {
     pub drop: (u32, u32),
     pub trash: (u32, u32),
     pub timeout: u32,
-    pub ping: u32,
     pub entered_world: bool,
+    pub version_retried: bool,
+    pub items_hash: u32,
+    pub items_download: Vec<u8>,
+    // Why the last items.dat download was rejected, picked up by items::download
+    pub items_error: Option<String>,
+    // Last OnDialogRequest, for features waiting on a specific dialog
+    pub dialog: String,
+    pub quests: Quests,
+    pub world_summary: Option<WorldSummary>,
+    // Reason picked up from a message before the connection actually drops
+    pub pending_disconnect: Option<(EDisconnectReason, String)>,
+    pub particles: VecDeque<Particle>,
+    // Last time each effect id was passed on as an event
+    pub particle_events: HashMap<u32, Instant>,
+    pub profiler: Profiler,
+    // Steam Guard challenge waiting on Bot::submit_2fa
+    pub steam_guard: Option<ESteamGuard>,
+    pub steam_guard_code: Option<String>,
+    // When set, collect() only picks up these item ids
+    pub collect_filter: Option<HashSet<u16>>,
+    // Last feature that reported itself running through BotEvent::Feature
+    pub active_feature: Option<String>,
+    // Set by the PauseAutomation reaction, auto-collect and features hold off until resumed
+    pub automation_paused: bool,
+    // Item and time of the last consume, matched to the "mod added" line that follows
+    pub last_consumed: Option<(u32, Instant)>,
+    // Raised inventory alerts, cleared again once enough slots free up
+    pub inventory_nearly_full: bool,
+    pub inventory_full: bool,
+    // A reaction list is being worked through, triggers don't stack another one
+    pub reacting: bool,
+    // Drop uids the collect detour found no path to, cleared on world change
+    pub unreachable_drops: HashSet<u32>,
+    // When each drop in the world was first seen by uid, see drop_age
+    pub drop_seen: HashMap<u32, Instant>,
+    // Recent position resyncs, see desync::check
+    pub resyncs: VecDeque<Instant>,
+    // World watcher run in progress, cleared to stop it after the current visit
+    pub watching: bool,
+    pub watch_reports: Vec<WatchReport>,
+    pub depositing: bool,
+    pub last_deposit: Option<Instant>,
+    // Static farm loop running, cleared to stop it after the current offset
+    pub static_farming: bool,
+    pub static_farm: Option<StaticFarmStats>,
+    // World the last warp asked for and why it failed, read by Bot::warp_and_wait
+    pub warp_target: Option<String>,
+    pub warp_error: Option<String>,
+    // Tile the server last sent an update for, switch toggles wait on it
+    pub last_tile_update: Option<(u32, u32, Instant)>,
+    // Last position the server put the bot at, doors wait on it
+    pub last_set_pos: Option<Instant>,
+    // When enter_door activated a door, the move it causes isn't a desync
+    pub door_entered: Option<Instant>,
+    // Server the last connection attempt went to, for BotEvent::Connect
+    pub connecting_to: Option<(String, u16)>,
+    // Auto surgery loop, its flag is cleared to stop it after the current patient
+    pub surgery: FeatureRun<SurgeryStats>,
+    // System console lines seen during the current surgery
+    pub surgery_messages: Vec<String>,
+    // Measured walking pace for this session, see core::calibration
+    pub speed_multiplier: Option<f32>,
+    pub calibrating: bool,
+    // Own positions the server sent back during a test walk, with the tile they're on
+    pub calibration_acks: Vec<(Instant, u32, u32)>,
+    // Repeat activate loop, its flag is cleared to stop it after the current use
+    pub repeat: FeatureRun<RepeatStats>,
+    // Cooldown the server asked for after the last use, see features::repeat_activate
+    pub repeat_cooldown: Option<Duration>,
+    // Pattern build running, cleared to stop it before the next tile
+    pub building: bool,
+    // Tile of the pattern being placed and how many it has
+    pub build_progress: Option<(usize, usize)>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Logs in, joins a world through a redirect and spawns again, then loses the connection
+    // and logs in a second time. The hook fires on the first spawn of each login only
+    #[test]
+    fn login_hook_fires_once_per_login() {
+        let mut state = State::default();
+        let mut fired = 0;
+
+        state.logon_started();
+        fired += state.local_spawn(1, 100) as u32;
+        state.is_redirecting = true;
+        assert!(!state.connection_lost());
+        state.is_redirecting = false;
+        fired += state.local_spawn(2, 100) as u32;
+        fired += state.local_spawn(2, 100) as u32;
+        assert_eq!(fired, 1);
+
+        assert!(state.connection_lost());
+        assert!(!state.logged_in);
+        state.logon_started();
+        fired += state.local_spawn(3, 100) as u32;
+        assert_eq!(fired, 2);
+    }
+
+    // A redirect whose connection never came up falls back to a full logon, which has
+    // to count as a new login even though the flag survived the redirect
+    #[test]
+    fn failed_redirect_starts_a_new_login() {
+        let mut state = State::default();
+        assert!(state.local_spawn(1, 100));
+        state.is_redirecting = true;
+        assert!(!state.connection_lost());
+        assert!(state.logged_in);
+        state.logon_started();
+        assert!(state.local_spawn(1, 100));
+    }
+
+    #[test]
+    fn skin_is_applied_once_per_login() {
+        let mut state = State::default();
+        state.local_spawn(1, 100);
+        assert!(std::mem::take(&mut state.skin_pending));
+        state.local_spawn(1, 100);
+        assert!(!state.skin_pending);
+        state.connection_lost();
+        state.logon_started();
+        state.local_spawn(1, 100);
+        assert!(state.skin_pending);
+    }
 }
@@ -0,0 +1,16 @@
+// Cheap copy of what the bot list shows per row, see BotManager::summaries
+#[derive(Debug, Clone, Default)]
+pub struct BotSummary {
+    pub name: String,
+    pub group: String,
+    pub world: String,
+    pub status: String,
+    pub connected: bool,
+    pub banned: bool,
+    pub proxy: bool,
+    pub script_running: bool,
+    pub feature: Option<String>,
+    pub mb_per_hour: f64,
+    // Workers that crashed, see Bot::degraded_workers
+    pub degraded: Vec<String>,
+}
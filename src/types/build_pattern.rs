@@ -0,0 +1,148 @@
+use serde::{Deserialize, Serialize};
+
+// Largest pattern the designer edits or an image is scaled down to
+pub const MAX_SIZE: u32 = 100;
+
+// A grid of palette indices laid out from its top left corner. Index 0 leaves the tile as
+// it is, every other index is placed as `palette[index - 1]`
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct BuildPattern {
+    pub name: String,
+    pub width: u32,
+    pub height: u32,
+    pub palette: Vec<u32>,
+    pub cells: Vec<u8>,
+}
+
+impl BuildPattern {
+    pub fn new(name: &str, width: u32, height: u32) -> Self {
+        let (width, height) = (width.clamp(1, MAX_SIZE), height.clamp(1, MAX_SIZE));
+        BuildPattern {
+            name: name.to_string(),
+            width,
+            height,
+            palette: Vec::new(),
+            cells: vec![0; (width * height) as usize],
+        }
+    }
+
+    // Alternates the first two colors
+    pub fn checkerboard(name: &str, width: u32, height: u32, palette: Vec<u32>) -> Self {
+        let mut pattern = BuildPattern::new(name, width, height);
+        pattern.palette = palette;
+        for y in 0..pattern.height {
+            for x in 0..pattern.width {
+                pattern.set(x, y, if (x + y) % 2 == 0 { 1 } else { 2 });
+            }
+        }
+        pattern
+    }
+
+    // Rows of the first color with `gap` empty rows between them, e.g. for farmable blocks
+    pub fn rows(name: &str, width: u32, height: u32, gap: u32, palette: Vec<u32>) -> Self {
+        let mut pattern = BuildPattern::new(name, width, height);
+        pattern.palette = palette;
+        for y in (0..pattern.height).step_by(gap as usize + 1) {
+            for x in 0..pattern.width {
+                pattern.set(x, y, 1);
+            }
+        }
+        pattern
+    }
+
+    pub fn get(&self, x: u32, y: u32) -> u8 {
+        if x >= self.width || y >= self.height {
+            return 0;
+        }
+        self.cells.get((y * self.width + x) as usize).copied().unwrap_or(0)
+    }
+
+    pub fn set(&mut self, x: u32, y: u32, index: u8) {
+        if x >= self.width || y >= self.height {
+            return;
+        }
+        if let Some(cell) = self.cells.get_mut((y * self.width + x) as usize) {
+            *cell = index;
+        }
+    }
+
+    // Keeps whatever still fits in the new size
+    pub fn resize(&mut self, width: u32, height: u32) {
+        let (width, height) = (width.clamp(1, MAX_SIZE), height.clamp(1, MAX_SIZE));
+        let mut cells = vec![0; (width * height) as usize];
+        for y in 0..height.min(self.height) {
+            for x in 0..width.min(self.width) {
+                cells[(y * width + x) as usize] = self.get(x, y);
+            }
+        }
+        self.width = width;
+        self.height = height;
+        self.cells = cells;
+    }
+
+    pub fn item_at(&self, x: u32, y: u32) -> Option<u32> {
+        match self.get(x, y) {
+            0 => None,
+            index => self.palette.get(index as usize - 1).copied().filter(|item_id| *item_id != 0),
+        }
+    }
+
+    // World tile and item of every cell that places something, row by row from the top
+    pub fn tiles(&self, (anchor_x, anchor_y): (u32, u32)) -> Vec<(u32, u32, u32)> {
+        let mut tiles = Vec::new();
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if let Some(item_id) = self.item_at(x, y) {
+                    tiles.push((anchor_x + x, anchor_y + y, item_id));
+                }
+            }
+        }
+        tiles
+    }
+}
+
+// Where a build got to, kept in the bot's config so it can carry on after a disconnect
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct BuildCheckpoint {
+    pub pattern: BuildPattern,
+    pub world: String,
+    pub anchor: (u32, u32),
+    // Tiles of `pattern.tiles` already placed or found correct
+    pub done: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lays_tiles_out_from_the_anchor_row_by_row() {
+        let pattern = BuildPattern::checkerboard("check", 3, 2, vec![2, 4]);
+        assert_eq!(
+            pattern.tiles((10, 20)),
+            vec![(10, 20, 2), (11, 20, 4), (12, 20, 2), (10, 21, 4), (11, 21, 2), (12, 21, 4)]
+        );
+    }
+
+    #[test]
+    fn empty_cells_and_unset_colors_place_nothing() {
+        let mut pattern = BuildPattern::rows("rows", 2, 4, 1, vec![2]);
+        assert_eq!(pattern.tiles((0, 0)), vec![(0, 0, 2), (1, 0, 2), (0, 2, 2), (1, 2, 2)]);
+        pattern.palette[0] = 0;
+        assert!(pattern.tiles((0, 0)).is_empty());
+    }
+
+    #[test]
+    fn resizing_keeps_what_still_fits() {
+        let mut pattern = BuildPattern::checkerboard("check", 4, 4, vec![2, 4]);
+        pattern.resize(2, 3);
+        assert_eq!((pattern.width, pattern.height), (2, 3));
+        assert_eq!((pattern.get(0, 0), pattern.get(1, 0), pattern.get(0, 2)), (1, 2, 1));
+        pattern.resize(3, 3);
+        assert_eq!(pattern.get(2, 0), 0);
+        pattern.resize(0, MAX_SIZE + 1);
+        assert_eq!((pattern.width, pattern.height), (1, MAX_SIZE));
+    }
+}
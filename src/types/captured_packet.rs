@@ -0,0 +1,35 @@
+use super::{epacket_direction::EPacketDirection, tank_packet::TankPacket};
+use byteorder::{ByteOrder, LittleEndian};
+
+#[derive(Debug, Clone)]
+pub struct CapturedPacket {
+    // Unix time in milliseconds
+    pub timestamp: u64,
+    pub direction: EPacketDirection,
+    pub channel: u8,
+    // Full enet payload, including the leading message type
+    pub data: Vec<u8>,
+}
+
+impl CapturedPacket {
+    pub fn packet_type(&self) -> u32 {
+        if self.data.len() < 4 {
+            return 0;
+        }
+        LittleEndian::read_u32(&self.data[0..4])
+    }
+
+    pub fn tank_packet(&self) -> Option<TankPacket> {
+        if self.packet_type() != 4 || self.data.len() < 4 + 56 {
+            return None;
+        }
+        TankPacket::from_bytes(&self.data[4..]).ok()
+    }
+
+    pub fn tank_packet_type(&self) -> Option<u8> {
+        if self.packet_type() != 4 || self.data.len() < 5 {
+            return None;
+        }
+        Some(self.data[4])
+    }
+}
@@ -0,0 +1,61 @@
+use super::tank_packet::TankPacket;
+
+// Movement and ability values the server pushes with SetCharacterState,
+// defaults are what a fresh character gets before any item or effect
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CharacterState {
+    pub flags: u32,
+    pub speed: f32,
+    pub gravity: f32,
+    pub acceleration: f32,
+    pub water_speed: f32,
+    pub punch_strength: f32,
+    pub build_range: u8,
+    pub punch_range: u8,
+}
+
+pub const BASE_SPEED: f32 = 250.0;
+// Ranges are sent offset by this so a negative modifier still fits in a byte
+const RANGE_OFFSET: u8 = 126;
+
+const FLAG_NOCLIP: u32 = 1 << 0;
+const FLAG_INVISIBLE: u32 = 1 << 2;
+
+impl Default for CharacterState {
+    fn default() -> Self {
+        CharacterState {
+            flags: 0,
+            speed: BASE_SPEED,
+            gravity: 1000.0,
+            acceleration: 1200.0,
+            water_speed: 125.0,
+            punch_strength: 300.0,
+            build_range: 2,
+            punch_range: 2,
+        }
+    }
+}
+
+impl CharacterState {
+    pub fn from_packet(packet: &TankPacket) -> Self {
+        CharacterState {
+            flags: packet.value,
+            speed: packet.vector_x2,
+            gravity: packet.vector_y2,
+            acceleration: packet.vector_y,
+            water_speed: packet.vector_x,
+            punch_strength: packet.unk12,
+            build_range: packet.unk2.saturating_sub(RANGE_OFFSET),
+            punch_range: packet.unk3.saturating_sub(RANGE_OFFSET),
+        }
+    }
+
+    pub fn is_invisible(&self) -> bool {
+        self.flags & FLAG_INVISIBLE != 0
+    }
+
+    // Walking through blocks, a mod ability unless a world owner lets us ghost through
+    pub fn has_noclip(&self) -> bool {
+        self.flags & FLAG_NOCLIP != 0
+    }
+}
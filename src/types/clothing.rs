@@ -0,0 +1,33 @@
+// Equipped items and skin color as sent in OnSetClothing
+#[derive(Debug, Default, Clone)]
+pub struct Clothing {
+    pub hair: u32,
+    pub shirt: u32,
+    pub pants: u32,
+    pub feet: u32,
+    pub face: u32,
+    pub hand: u32,
+    pub back: u32,
+    pub mask: u32,
+    pub necklace: u32,
+    pub ances: u32,
+    pub skin_color: u32,
+}
+
+impl Clothing {
+    // Back to front, the order the layers are drawn in
+    pub fn layers(&self) -> [u32; 10] {
+        [
+            self.back,
+            self.pants,
+            self.feet,
+            self.shirt,
+            self.necklace,
+            self.face,
+            self.hair,
+            self.mask,
+            self.hand,
+            self.ances,
+        ]
+    }
+}
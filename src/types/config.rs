@@ -1,19 +1,238 @@
+use super::ebot_filter::EBotFilter;
+use super::edelay_distribution::EDelayDistribution;
+use super::ehumanizer_preset::EHumanizerPreset;
 use super::elogin_method::ELoginMethod;
+use super::build_pattern::{BuildCheckpoint, BuildPattern};
+use super::ereaction::EReaction;
+use super::packet_filter::PacketFilterPreset;
+use super::route::Route;
 use crate::utils;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize, Deserialize)]
+// Missing keys fall back to `Config::default()` so older config files keep loading
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
 pub struct Config {
     pub bots: Vec<BotConfig>,
     pub proxy: Vec<Proxy>,
     pub timeout: u32,
+    pub connect_timeout: u64,
+    pub stall_timeout: u64,
     pub findpath_delay: u32,
     pub auto_collect: bool,
     pub selected_bot: String,
     pub game_version: String,
+    pub protocol: String,
+    // Optional URL serving `version|x.xx\nprotocol|xxx`, used when the game asks for an update
+    pub version_source: String,
     pub use_alternate_server: bool,
     pub theme: Theme,
     pub captcha: Captcha,
+    pub telemetry: bool,
+    pub door_walk_off: i32,
+    pub auto_reply: bool,
+    pub auto_reply_message: String,
+    pub auto_reply_cooldown: u32,
+    pub forward_whispers: bool,
+    pub webhook_url: String,
+    pub consumables: Vec<Consumable>,
+    pub auto_reconsume: bool,
+    pub reconsume_before_secs: u64,
+    pub developer_mode: bool,
+    pub chat_cooldown: u64,
+    // Names highlighted on the world map
+    pub friends: Vec<String>,
+    // Daily stats buckets roll over at `daily_reset_hour` in this UTC offset
+    pub stats_utc_offset: i32,
+    pub daily_reset_hour: u32,
+    pub network: NetworkConfig,
+    // World map safety, ask before click-to-walk / ignore clicks entirely
+    pub map_click_confirmation: bool,
+    pub map_locked: bool,
+    // Foreground item counted as farmable in world summaries, 0 to skip
+    pub farmable_item: u32,
+    pub world_summary_webhook: bool,
+    // Seconds without movement before an idle state packet is sent, 0 disables it
+    pub idle_heartbeat: u64,
+    pub transfer_webhook: bool,
+    // Recompute hash/klv when they don't match the rest of the login info
+    pub auto_fix_login_info: bool,
+    // Bot list filter, kept between sessions
+    pub bot_filter_text: String,
+    pub bot_filter: EBotFilter,
+    // Free inventory slots below which InventoryNearlyFull fires
+    pub inventory_free_threshold: u32,
+    // Trusted scripts keep the full standard library and run without limits
+    pub trusted_scripts: bool,
+    pub script_memory_limit: u32,
+    // Millions of Lua instructions a script may run between yields to the bot
+    pub script_cpu_budget: u64,
+    pub routes: Vec<Route>,
+    // Collect values from before the value table, moved into its overrides on startup
+    pub item_values: Vec<ItemValue>,
+    // Drops worth at least this many world locks are picked up while busy and walked to when idle
+    pub collect_threshold_wl: f32,
+    pub debug_logs: bool,
+    // Worlds visited in turn by the world watcher
+    pub watch_worlds: Vec<String>,
+    // Seconds between the start of two watcher rounds
+    pub watch_interval: u64,
+    // How far from a blocked or unreachable target find_path may stop instead, 0 to fail
+    pub path_tolerance: u32,
+    // Seconds after which an exported session is too old to resume from
+    pub session_max_age: u64,
+    // Notifications still reach the panel, only the toasts are skipped
+    pub do_not_disturb: bool,
+    // Answers the access-password dialog of these worlds
+    pub world_passwords: Vec<WorldPassword>,
+    // Proxy pool settings, proxies without a pool belong to the default one
+    pub proxy_pools: Vec<ProxyPool>,
+    // A bot whose pool has no free proxy stays offline instead of connecting direct
+    pub never_connect_direct: bool,
+    // Tile activations walk into range first instead of failing when too far
+    pub walk_to_activate: bool,
+    // Looks for a newer release on GitHub at startup and once a day, off until turned on
+    pub check_updates: bool,
+    // Daily login windows by bot group, a bot's own window takes precedence
+    pub login_windows: Vec<LoginWindow>,
+    // Applied to area locks placed from the world map
+    pub lock_public: bool,
+    pub lock_access: Vec<String>,
+    // Answers for the age gate, GDPR and rating dialogs fresh accounts get, off by default
+    pub auto_onboarding: bool,
+    pub onboarding_age: u32,
+    // Optional consents, the terms themselves are always accepted
+    pub onboarding_consent: bool,
+    // MB per hour the whole farm may use before low priority traffic is held off, 0 for no cap
+    pub bandwidth_cap_mb: u32,
+    // Seconds a drop stays in the world before the server removes it, 0 (the default) to
+    // never assume so
+    pub drop_despawn_secs: u64,
+    // Position resyncs within a minute that warn about too tight movement delays, 0 to never
+    pub resync_warn_count: u32,
+    // Shared settings for adding many similar bots, see manager::bot_template
+    pub bot_templates: Vec<BotTemplate>,
+    // Destructive script calls are only logged, see core::script_guard
+    pub script_dry_run: bool,
+    // Measure the walking pace on world entry and speed changes, see core::calibration
+    pub movement_calibration: bool,
+    // Connect here instead of asking server_data.php, used when both are set
+    pub server_ip: String,
+    pub server_port: u16,
+    // Failed server_data.php fetches before the cached response is used, 0 to keep retrying
+    pub server_data_fallback_attempts: u32,
+    // Cached responses older than this are ignored
+    pub server_data_max_age_hours: u32,
+    // Longest straight run walked as one segment, 1 sends a state packet per tile
+    pub path_segment_max: u32,
+    // Shape of the humanizer's action delays around their mean
+    pub humanizer_distribution: EDelayDistribution,
+    // Saved from the dev tools capture filter, the built-in ones aren't stored
+    pub packet_filter_presets: Vec<PacketFilterPreset>,
+    // Saved from the pattern designer on the world map
+    pub build_patterns: Vec<BuildPattern>,
+    // Set once passwords, tokens and proxy credentials are sealed on disk, see utils::secrets
+    pub encryption: Option<ConfigEncryption>,
+    // Item value list synced daily into the value table, empty to only use manual values
+    pub value_sync_url: String,
+    // Tile walked to after entering these worlds instead of stepping off the door
+    pub spawn_spots: Vec<SpawnSpot>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            bots: Vec::new(),
+            proxy: Vec::new(),
+            timeout: 5,
+            connect_timeout: 15,
+            stall_timeout: 60,
+            findpath_delay: 30,
+            auto_collect: true,
+            selected_bot: "".to_string(),
+            game_version: "4.70".to_string(),
+            protocol: "209".to_string(),
+            version_source: "".to_string(),
+            use_alternate_server: false,
+            theme: Theme::Dark,
+            captcha: Default::default(),
+            telemetry: false,
+            door_walk_off: 0,
+            auto_reply: false,
+            auto_reply_message: "sorry {sender}, i'm busy right now".to_string(),
+            auto_reply_cooldown: 60,
+            forward_whispers: false,
+            webhook_url: "".to_string(),
+            consumables: Vec::new(),
+            auto_reconsume: false,
+            reconsume_before_secs: 10,
+            developer_mode: false,
+            chat_cooldown: 1500,
+            friends: Vec::new(),
+            stats_utc_offset: 0,
+            daily_reset_hour: 0,
+            network: Default::default(),
+            map_click_confirmation: true,
+            map_locked: false,
+            farmable_item: 0,
+            world_summary_webhook: false,
+            idle_heartbeat: 30,
+            transfer_webhook: false,
+            auto_fix_login_info: false,
+            bot_filter_text: String::new(),
+            bot_filter: EBotFilter::All,
+            inventory_free_threshold: 3,
+            trusted_scripts: false,
+            script_memory_limit: 64,
+            script_cpu_budget: 200,
+            routes: Vec::new(),
+            item_values: Vec::new(),
+            collect_threshold_wl: 1.0,
+            debug_logs: false,
+            watch_worlds: Vec::new(),
+            watch_interval: 600,
+            path_tolerance: 0,
+            session_max_age: 1800,
+            do_not_disturb: false,
+            world_passwords: Vec::new(),
+            proxy_pools: Vec::new(),
+            never_connect_direct: false,
+            walk_to_activate: true,
+            check_updates: false,
+            login_windows: Vec::new(),
+            lock_public: false,
+            lock_access: Vec::new(),
+            auto_onboarding: false,
+            onboarding_age: 20,
+            onboarding_consent: false,
+            bandwidth_cap_mb: 0,
+            drop_despawn_secs: 0,
+            resync_warn_count: 3,
+            bot_templates: Vec::new(),
+            script_dry_run: false,
+            movement_calibration: false,
+            server_ip: String::new(),
+            server_port: 0,
+            server_data_fallback_attempts: 5,
+            server_data_max_age_hours: 24,
+            path_segment_max: 1,
+            humanizer_distribution: EDelayDistribution::Uniform,
+            packet_filter_presets: Vec::new(),
+            build_patterns: Vec::new(),
+            encryption: None,
+            value_sync_url: String::new(),
+            spawn_spots: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct ConfigEncryption {
+    // Base64 salt the key is derived with
+    pub salt: String,
+    // A known value sealed with the key, so a wrong passphrase is caught before any secret
+    pub check: String,
 }
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
@@ -32,13 +251,15 @@ impl Default for Theme {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Default)]
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+#[serde(default)]
 pub struct Captcha {
     pub api_key: String,
     pub provider: utils::captcha::CaptchaProvider,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
 pub struct BotConfig {
     pub payload: String,
     pub recovery_code: String,
@@ -46,6 +267,50 @@ pub struct BotConfig {
     pub token: String,
     pub data: String,
     pub use_proxy: bool,
+    // None goes by the template's preset, Off without a template
+    pub humanizer: Option<EHumanizerPreset>,
+    // Seeds the humanizer so a run's delays can be replayed, 0 for a fresh seed each run
+    pub humanizer_seed: u64,
+    // Local address game traffic is sent from, empty for any interface
+    pub bind_address: String,
+    // Free-form label for filtering the bot list
+    pub group: String,
+    // Base64 shared_secret from a Steam maFile, lets Steam Guard codes be generated
+    pub steam_shared_secret: String,
+    // What to do when the inventory runs out of room
+    pub inventory_reactions: Vec<EReaction>,
+    pub trash_items: Vec<u32>,
+    pub deposit_items: Vec<u32>,
+    pub storage_world: String,
+    // Planner route from the storage world's door to where deposits are dropped
+    pub deposit_route: String,
+    // World locks of deposit items carried that send the bot to deposit, 0 to never go on its own
+    pub deposit_threshold: u32,
+    // Bot that receives deposits in the storage world, nothing is deposited without one
+    pub deposit_mule: String,
+    // Tile offsets the static farm works through and the block it places there
+    pub static_farm_pattern: Vec<(i32, i32)>,
+    pub static_farm_item: u32,
+    // Proxy pool for this bot, empty to go by its group
+    pub proxy_pool: String,
+    // "HH:MM-HH:MM" local time the bot is online in each day, empty to stay online
+    pub login_window: String,
+    // Fresh-account dialogs already answered, see EOnboardingDialog::key
+    pub answered_dialogs: Vec<String>,
+    // Template the bot was added from, empty when added by hand
+    pub template: String,
+    // Items scripts may not trash or drop, None until edited for locks and gems
+    pub protected_items: Option<Vec<u32>>,
+    // Item ids pinned to the inventory hotbar, 0 for an empty slot
+    pub hotbar: Vec<u32>,
+    // Repeat activate settings: the tile, seconds between uses, uses before stopping (0 for
+    // no limit) and the ticket item used before each one (0 for none)
+    pub repeat_tile: Option<(u32, u32)>,
+    pub repeat_interval: u64,
+    pub repeat_max_uses: u32,
+    pub repeat_ticket: u32,
+    // Unfinished pattern build, see features::build_pattern
+    pub build_checkpoint: Option<BuildCheckpoint>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -54,4 +319,191 @@ pub struct Proxy {
     pub port: u16,
     pub username: String,
     pub password: String,
+    // Empty for the default pool
+    #[serde(default)]
+    pub pool: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct ProxyPool {
+    pub name: String,
+    pub max_bots_per_proxy: u32,
+    // Bot groups that draw from this pool
+    pub groups: Vec<String>,
+}
+
+impl Default for ProxyPool {
+    fn default() -> Self {
+        ProxyPool {
+            name: String::new(),
+            max_bots_per_proxy: 3,
+            groups: Vec::new(),
+        }
+    }
+}
+
+// Effect duration of a consumable, the server doesn't tell us how long an effect lasts
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Consumable {
+    pub item_id: u32,
+    pub duration_secs: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct ItemValue {
+    pub item_id: u32,
+    pub value: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct WorldPassword {
+    pub world: String,
+    pub password: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct SpawnSpot {
+    pub world: String,
+    pub x: u32,
+    pub y: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct LoginWindow {
+    pub group: String,
+    // "HH:MM-HH:MM", may wrap past midnight
+    pub window: String,
+}
+
+// Everything about a bot except its credentials. Bots added from it keep only its name and
+// read the rest through fill_in, empty text fields on both still go by the group
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct BotTemplate {
+    pub name: String,
+    pub login_method: ELoginMethod,
+    pub group: String,
+    pub proxy_pool: String,
+    pub use_proxy: bool,
+    pub humanizer: EHumanizerPreset,
+    pub login_window: String,
+    pub inventory_reactions: Vec<EReaction>,
+    pub storage_world: String,
+    pub deposit_threshold: u32,
+}
+
+impl BotTemplate {
+    // The bot's own value first and the template's where the bot leaves it unset, the way
+    // an empty pool or login window falls back to the group's. Group lookups run on the
+    // result, so a template can put a bot into a group. A template's proxy use can't be
+    // turned off per bot
+    pub fn fill_in(&self, bot_config: &mut BotConfig) {
+        let inherit = |own: &mut String, template: &str| {
+            if own.trim().is_empty() {
+                *own = template.trim().to_string();
+            }
+        };
+        inherit(&mut bot_config.group, &self.group);
+        inherit(&mut bot_config.proxy_pool, &self.proxy_pool);
+        inherit(&mut bot_config.login_window, &self.login_window);
+        inherit(&mut bot_config.storage_world, &self.storage_world.to_uppercase());
+        bot_config.use_proxy |= self.use_proxy;
+        if bot_config.humanizer.is_none() {
+            bot_config.humanizer = Some(self.humanizer);
+        }
+        if bot_config.inventory_reactions.is_empty() {
+            bot_config.inventory_reactions = self.inventory_reactions.clone();
+        }
+        if bot_config.deposit_threshold == 0 {
+            bot_config.deposit_threshold = self.deposit_threshold;
+        }
+    }
+
+    // Drops the bot's own values for everything the template covers, so fill_in gives the
+    // template's again
+    pub fn clear_overrides(bot_config: &mut BotConfig) {
+        bot_config.group.clear();
+        bot_config.proxy_pool.clear();
+        bot_config.login_window.clear();
+        bot_config.storage_world.clear();
+        bot_config.use_proxy = false;
+        bot_config.humanizer = None;
+        bot_config.inventory_reactions.clear();
+        bot_config.deposit_threshold = 0;
+    }
+}
+
+// The bot as it applies, with its template filled in
+pub fn resolved(bot_config: &BotConfig, templates: &[BotTemplate]) -> BotConfig {
+    let mut bot_config = bot_config.clone();
+    if !bot_config.template.is_empty() {
+        if let Some(template) = templates.iter().find(|template| template.name.eq_ignore_ascii_case(&bot_config.template)) {
+            template.fill_in(&mut bot_config);
+        }
+    }
+    bot_config
+}
+
+// ENet host and peer tuning, the defaults match what the game client uses
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct NetworkConfig {
+    pub peer_timeout_limit: u32,
+    pub peer_timeout_minimum: u32,
+    pub peer_timeout_maximum: u32,
+    pub ping_interval: u32,
+    // Bytes per second, None leaves it unlimited
+    pub incoming_bandwidth_limit: Option<u32>,
+    pub outgoing_bandwidth_limit: Option<u32>,
+    pub mtu: u16,
+    // Local test servers usually run plain ENet without either of them
+    pub use_compression_and_checksum: bool,
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        NetworkConfig {
+            peer_timeout_limit: 32,
+            peer_timeout_minimum: 5000,
+            peer_timeout_maximum: 30000,
+            ping_interval: 100,
+            incoming_bandwidth_limit: None,
+            outgoing_bandwidth_limit: None,
+            mtu: 1392,
+            use_compression_and_checksum: true,
+        }
+    }
+}
+
+impl NetworkConfig {
+    pub const MIN_MTU: u16 = 576;
+    pub const MAX_MTU: u16 = 4096;
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.mtu < Self::MIN_MTU || self.mtu > Self::MAX_MTU {
+            return Err(format!(
+                "mtu must be between {} and {}, got {}",
+                Self::MIN_MTU,
+                Self::MAX_MTU,
+                self.mtu
+            ));
+        }
+        if self.peer_timeout_minimum > self.peer_timeout_maximum {
+            return Err(format!(
+                "peer_timeout_minimum ({}) is larger than peer_timeout_maximum ({})",
+                self.peer_timeout_minimum, self.peer_timeout_maximum
+            ));
+        }
+        if self.ping_interval == 0 {
+            return Err("ping_interval must be greater than 0".to_string());
+        }
+        if self.incoming_bandwidth_limit == Some(0) || self.outgoing_bandwidth_limit == Some(0) {
+            return Err(
+                "bandwidth limits must be greater than 0, leave them empty for unlimited"
+                    .to_string(),
+            );
+        }
+        Ok(())
+    }
 }
@@ -0,0 +1,13 @@
+use super::echat_channel::EChatChannel;
+
+#[derive(Debug, Clone)]
+pub struct ConsoleLine {
+    pub timestamp: u64,
+    pub channel: EChatChannel,
+    // Empty for system lines
+    pub sender: String,
+    // Color codes are kept for display
+    pub text: String,
+    // The world a super-broadcast advertises
+    pub world: Option<String>,
+}
@@ -0,0 +1,9 @@
+use super::edisconnect_reason::EDisconnectReason;
+
+#[derive(Debug, Default, Clone)]
+pub struct DisconnectInfo {
+    pub reason: EDisconnectReason,
+    // Message that led to the classification, or the ENet disconnect code
+    pub detail: String,
+    pub timestamp: u64,
+}
@@ -0,0 +1,54 @@
+use super::edisconnect_reason::EDisconnectReason;
+use serde::{Deserialize, Serialize};
+
+// Contents of disconnect_phrases.json, lowercase phrases the server sends before dropping
+// us. Checked in order and the first match wins, new wordings only need an edit to the file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DisconnectPhrases {
+    pub phrases: Vec<DisconnectPhrase>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DisconnectPhrase {
+    pub phrase: String,
+    pub reason: EDisconnectReason,
+}
+
+impl DisconnectPhrases {
+    pub fn classify(&self, message: &str) -> Option<EDisconnectReason> {
+        let message = message.to_lowercase();
+        self.phrases
+            .iter()
+            .find(|entry| message.contains(entry.phrase.as_str()))
+            .map(|entry| entry.reason)
+    }
+}
+
+impl Default for DisconnectPhrases {
+    fn default() -> Self {
+        // Kicks are only matched in the second person, "<name> has been kicked" is
+        // about someone else
+        let phrases = [
+            ("logged on from another location", EDisconnectReason::LoggedInElsewhere),
+            ("currently banned", EDisconnectReason::Banned),
+            ("has been suspended", EDisconnectReason::Banned),
+            ("temporarily suspended", EDisconnectReason::Banned),
+            ("update required", EDisconnectReason::VersionMismatch),
+            ("not quite ready for users", EDisconnectReason::ServerRestart),
+            ("server is restarting", EDisconnectReason::ServerRestart),
+            ("restarting the server", EDisconnectReason::ServerRestart),
+            ("you were kicked", EDisconnectReason::Kicked),
+            ("you have been kicked", EDisconnectReason::Kicked),
+        ];
+        DisconnectPhrases {
+            phrases: phrases
+                .iter()
+                .map(|(phrase, reason)| DisconnectPhrase {
+                    phrase: phrase.to_string(),
+                    reason: *reason,
+                })
+                .collect(),
+        }
+    }
+}
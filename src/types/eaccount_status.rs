@@ -0,0 +1,23 @@
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum EAccountStatus {
+    Valid,
+    WrongPassword,
+    TwoFactorRequired,
+    RateLimited,
+    // The login method can't be checked without a game client
+    Unsupported,
+    Failed,
+}
+
+impl EAccountStatus {
+    pub fn name(&self) -> &'static str {
+        match self {
+            EAccountStatus::Valid => "valid",
+            EAccountStatus::WrongPassword => "wrong password",
+            EAccountStatus::TwoFactorRequired => "2FA required",
+            EAccountStatus::RateLimited => "rate limited",
+            EAccountStatus::Unsupported => "unsupported",
+            EAccountStatus::Failed => "failed",
+        }
+    }
+}
@@ -0,0 +1,38 @@
+use super::bot_summary::BotSummary;
+use serde::{Deserialize, Serialize};
+
+// Quick filter chip of the bot list
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub enum EBotFilter {
+    #[default]
+    All,
+    Connected,
+    Disconnected,
+    Banned,
+    World(String),
+    Group(String),
+}
+
+impl EBotFilter {
+    pub fn matches(&self, summary: &BotSummary) -> bool {
+        match self {
+            EBotFilter::All => true,
+            EBotFilter::Connected => summary.connected,
+            EBotFilter::Disconnected => !summary.connected,
+            EBotFilter::Banned => summary.banned,
+            EBotFilter::World(world) => summary.world.eq_ignore_ascii_case(world),
+            EBotFilter::Group(group) => summary.group == *group,
+        }
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            EBotFilter::All => "All".to_string(),
+            EBotFilter::Connected => "Connected".to_string(),
+            EBotFilter::Disconnected => "Disconnected".to_string(),
+            EBotFilter::Banned => "Banned".to_string(),
+            EBotFilter::World(world) => format!("In {}", world),
+            EBotFilter::Group(group) => format!("Group {}", group),
+        }
+    }
+}
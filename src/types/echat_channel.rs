@@ -0,0 +1,40 @@
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum EChatChannel {
+    World,
+    Guild,
+    // Super-broadcasts, sent to everyone online and usually advertising a world
+    SuperBroadcast,
+    // Server notices and anything not said by a player
+    System,
+    Whisper,
+}
+
+impl EChatChannel {
+    pub const ALL: [EChatChannel; 5] = [
+        EChatChannel::World,
+        EChatChannel::Guild,
+        EChatChannel::SuperBroadcast,
+        EChatChannel::System,
+        EChatChannel::Whisper,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            EChatChannel::World => "World",
+            EChatChannel::Guild => "Guild",
+            EChatChannel::SuperBroadcast => "SB",
+            EChatChannel::System => "System",
+            EChatChannel::Whisper => "Whispers",
+        }
+    }
+
+    pub fn index(&self) -> usize {
+        match self {
+            EChatChannel::World => 0,
+            EChatChannel::Guild => 1,
+            EChatChannel::SuperBroadcast => 2,
+            EChatChannel::System => 3,
+            EChatChannel::Whisper => 4,
+        }
+    }
+}
@@ -0,0 +1,9 @@
+use std::time::Instant;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum EChatState {
+    Ready,
+    // Messages are waiting for the cooldown to pass
+    Queued(usize),
+    Muted(Instant),
+}
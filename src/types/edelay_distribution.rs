@@ -0,0 +1,19 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub enum EDelayDistribution {
+    // Anywhere between mean - jitter and mean + jitter with the same chance
+    Uniform,
+    // Bunched around the mean, half the jitter is one standard deviation
+    Normal,
+}
+
+impl Default for EDelayDistribution {
+    fn default() -> Self {
+        EDelayDistribution::Uniform
+    }
+}
+
+impl EDelayDistribution {
+    pub const ALL: [EDelayDistribution; 2] = [EDelayDistribution::Uniform, EDelayDistribution::Normal];
+}
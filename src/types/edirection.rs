@@ -0,0 +1,73 @@
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum EDirection {
+    Left,
+    Right,
+}
+
+impl Default for EDirection {
+    fn default() -> Self {
+        EDirection::Right
+    }
+}
+
+impl EDirection {
+    // Moving or acting straight up or down keeps the current direction
+    pub fn from_offset(offset_x: i32) -> Option<Self> {
+        match offset_x {
+            0 => None,
+            x if x < 0 => Some(EDirection::Left),
+            _ => Some(EDirection::Right),
+        }
+    }
+
+    pub fn state_flags(self, flags: u32) -> u32 {
+        /*
+        00000000 00000000 00001010 00110000
+        the fifth bit from the end is set if facing left
+         */
+        match self {
+            EDirection::Left => flags | (1 << 4),
+            EDirection::Right => flags & !(1 << 4),
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EDirection::Left => "left",
+            EDirection::Right => "right",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Flags `Bot::place` and `Bot::walk` start from before the direction is applied
+    const PLACE_FLAGS: u32 = 2592;
+    const WALK_FLAGS: u32 = (1 << 1) | (1 << 5);
+
+    #[test]
+    fn placing_sets_the_left_bit_only_for_negative_offsets() {
+        let left = EDirection::from_offset(-1).unwrap();
+        let right = EDirection::from_offset(1).unwrap();
+        assert_eq!(left.state_flags(PLACE_FLAGS), 2608);
+        assert_eq!(right.state_flags(PLACE_FLAGS), 2592);
+    }
+
+    #[test]
+    fn walking_sets_the_left_bit_only_for_negative_offsets() {
+        assert_eq!(EDirection::from_offset(-3).unwrap().state_flags(WALK_FLAGS), 50);
+        assert_eq!(EDirection::from_offset(3).unwrap().state_flags(WALK_FLAGS), 34);
+    }
+
+    #[test]
+    fn facing_right_clears_a_stale_left_bit() {
+        assert_eq!(EDirection::Right.state_flags(2608), 2592);
+    }
+
+    #[test]
+    fn vertical_moves_keep_the_direction() {
+        assert_eq!(EDirection::from_offset(0), None);
+    }
+}
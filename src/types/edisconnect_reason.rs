@@ -0,0 +1,40 @@
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum EDisconnectReason {
+    #[default]
+    Unknown,
+    Kicked,
+    Banned,
+    ServerRestart,
+    NetworkTimeout,
+    LoggedInElsewhere,
+    VersionMismatch,
+}
+
+impl EDisconnectReason {
+    pub fn name(&self) -> &'static str {
+        match self {
+            EDisconnectReason::Unknown => "Unknown",
+            EDisconnectReason::Kicked => "Kicked",
+            EDisconnectReason::Banned => "Banned",
+            EDisconnectReason::ServerRestart => "Server restart",
+            EDisconnectReason::NetworkTimeout => "Network timeout",
+            EDisconnectReason::LoggedInElsewhere => "Logged in elsewhere",
+            EDisconnectReason::VersionMismatch => "Version mismatch",
+        }
+    }
+
+    // How long to wait before reconnecting, None when reconnecting would only make things worse
+    pub fn reconnect_delay(&self) -> Option<Duration> {
+        match self {
+            EDisconnectReason::Banned | EDisconnectReason::LoggedInElsewhere => None,
+            EDisconnectReason::ServerRestart => Some(Duration::from_secs(120)),
+            EDisconnectReason::Kicked => Some(Duration::from_secs(10)),
+            EDisconnectReason::Unknown
+            | EDisconnectReason::NetworkTimeout
+            | EDisconnectReason::VersionMismatch => Some(Duration::ZERO),
+        }
+    }
+}
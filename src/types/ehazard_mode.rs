@@ -0,0 +1,9 @@
+// How the pathfinder treats harmful tiles
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum EHazardMode {
+    // Walkable, but only worth it when there's no way around
+    #[default]
+    Penalty,
+    Ignore,
+    Block,
+}
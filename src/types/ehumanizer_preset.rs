@@ -0,0 +1,25 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub enum EHumanizerPreset {
+    // The configured delays as they are, how bots walked before presets existed
+    Off,
+    Fast,
+    Balanced,
+    Stealthy,
+}
+
+impl Default for EHumanizerPreset {
+    fn default() -> Self {
+        EHumanizerPreset::Off
+    }
+}
+
+impl EHumanizerPreset {
+    pub const ALL: [EHumanizerPreset; 4] = [
+        EHumanizerPreset::Off,
+        EHumanizerPreset::Fast,
+        EHumanizerPreset::Balanced,
+        EHumanizerPreset::Stealthy,
+    ];
+}
@@ -0,0 +1,44 @@
+use std::fmt;
+
+// A spoofed login field that disagrees with the others, see login_info::check_consistency
+#[derive(Debug, Clone, PartialEq)]
+pub enum ELoginInconsistency {
+    PlatformFormat(String),
+    PlatformDevice { platform_id: String, device_version: String },
+    NumberFormat { field: &'static str, value: String },
+    MacFormat(String),
+    Hash { expected: String, actual: String },
+    Klv,
+}
+
+impl ELoginInconsistency {
+    // Only derived fields can be recomputed, the rest needs a new identity
+    pub fn is_fixable(&self) -> bool {
+        matches!(self, ELoginInconsistency::Hash { .. } | ELoginInconsistency::Klv)
+    }
+}
+
+impl fmt::Display for ELoginInconsistency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ELoginInconsistency::PlatformFormat(platform_id) => {
+                write!(f, "platformID {} is not three numbers", platform_id)
+            }
+            ELoginInconsistency::PlatformDevice { platform_id, device_version } => write!(
+                f,
+                "deviceVersion {} doesn't belong to platformID {}",
+                device_version, platform_id
+            ),
+            ELoginInconsistency::NumberFormat { field, value } => {
+                write!(f, "{} {} is not a 32-bit number", field, value)
+            }
+            ELoginInconsistency::MacFormat(mac) => write!(f, "mac {} is malformed", mac),
+            ELoginInconsistency::Hash { expected, actual } => {
+                write!(f, "hash {} doesn't match the mac, expected {}", actual, expected)
+            }
+            ELoginInconsistency::Klv => {
+                write!(f, "klv doesn't match the protocol, game version and rid")
+            }
+        }
+    }
+}
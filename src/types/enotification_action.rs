@@ -0,0 +1,24 @@
+// What the button on a notification does, always for the bot it came from
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum ENotificationAction {
+    EnterTwoFactor,
+    PauseBot,
+    // Starts a trade with the player who asked
+    OpenTrade { name: String },
+    // Saves a password for the world and warps there again
+    EnterWorldPassword { world: String },
+    // Not tied to a bot, shows the notes of a newer Mori release
+    ShowRelease { version: String },
+}
+
+impl ENotificationAction {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ENotificationAction::EnterTwoFactor => "Enter 2FA code",
+            ENotificationAction::PauseBot => "Pause bot",
+            ENotificationAction::OpenTrade { .. } => "Open trade",
+            ENotificationAction::EnterWorldPassword { .. } => "Enter password",
+            ENotificationAction::ShowRelease { .. } => "Release notes",
+        }
+    }
+}
@@ -0,0 +1,54 @@
+// One-time dialogs a fresh account gets before it can do anything else
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum EOnboardingDialog {
+    AgeGate,
+    Gdpr,
+    Rating,
+}
+
+impl EOnboardingDialog {
+    pub fn name(&self) -> &'static str {
+        match self {
+            EOnboardingDialog::AgeGate => "age gate",
+            EOnboardingDialog::Gdpr => "GDPR consent",
+            EOnboardingDialog::Rating => "rating prompt",
+        }
+    }
+
+    // Stored in the bot config once answered
+    pub fn key(&self) -> &'static str {
+        match self {
+            EOnboardingDialog::AgeGate => "age_gate",
+            EOnboardingDialog::Gdpr => "gdpr",
+            EOnboardingDialog::Rating => "rating",
+        }
+    }
+
+    // Matched on the dialog name and the heading, never on the body, which can quote
+    // player text like a sign does
+    pub fn detect(dialog_name: &str, heading: &str) -> Option<EOnboardingDialog> {
+        let name = dialog_name.to_lowercase();
+        let text = heading.to_lowercase();
+        if name.contains("age_gate")
+            || name.contains("birth")
+            || text.contains("how old are you")
+            || text.contains("date of birth")
+        {
+            Some(EOnboardingDialog::AgeGate)
+        } else if name.contains("gdpr")
+            || name.contains("consent")
+            || text.contains("gdpr")
+            || (text.contains("privacy policy") && text.contains("consent"))
+        {
+            Some(EOnboardingDialog::Gdpr)
+        } else if name.contains("rating")
+            || name.contains("rate_")
+            || text.contains("rate growtopia")
+            || text.contains("enjoying growtopia")
+        {
+            Some(EOnboardingDialog::Rating)
+        } else {
+            None
+        }
+    }
+}
@@ -0,0 +1,5 @@
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum EPacketDirection {
+    Incoming,
+    Outgoing,
+}
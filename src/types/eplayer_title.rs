@@ -0,0 +1,20 @@
+// Title shown next to a player's name, read from their spawn string
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum EPlayerTitle {
+    #[default]
+    None,
+    Moderator,
+    Legendary,
+    Doctor,
+}
+
+impl EPlayerTitle {
+    pub fn name(&self) -> &'static str {
+        match self {
+            EPlayerTitle::None => "none",
+            EPlayerTitle::Moderator => "moderator",
+            EPlayerTitle::Legendary => "legendary",
+            EPlayerTitle::Doctor => "doctor",
+        }
+    }
+}
@@ -0,0 +1,29 @@
+use serde::{Deserialize, Serialize};
+
+// Something a bot does in response to a trigger. Triggers hand a list of these to
+// core::reactions::run, which works through them in order so they compose
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+pub enum EReaction {
+    PauseAutomation,
+    Trash,
+    Deposit,
+    Notify,
+}
+
+impl EReaction {
+    pub const ALL: [EReaction; 4] = [
+        EReaction::PauseAutomation,
+        EReaction::Trash,
+        EReaction::Deposit,
+        EReaction::Notify,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            EReaction::PauseAutomation => "Pause automation",
+            EReaction::Trash => "Trash junk items",
+            EReaction::Deposit => "Deposit in storage world",
+            EReaction::Notify => "Webhook notification",
+        }
+    }
+}
@@ -0,0 +1,21 @@
+// What the setup screen can do about a problem on its own
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum ESetupFix {
+    // Copy an items.dat from a path the user types in
+    BrowseItems,
+    // Move the broken config aside and start from the defaults
+    CreateConfig,
+    ResetNetwork,
+    OpenFolder(String),
+}
+
+impl ESetupFix {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ESetupFix::BrowseItems => "Use this items.dat",
+            ESetupFix::CreateConfig => "Create default config",
+            ESetupFix::ResetNetwork => "Reset network settings",
+            ESetupFix::OpenFolder(_) => "Open folder",
+        }
+    }
+}
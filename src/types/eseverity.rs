@@ -0,0 +1,7 @@
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum ESeverity {
+    #[default]
+    Info,
+    Warning,
+    Critical,
+}
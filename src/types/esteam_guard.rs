@@ -0,0 +1,15 @@
+// Which Steam Guard challenge steamtoken reported
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ESteamGuard {
+    Email,
+    Mobile,
+}
+
+impl ESteamGuard {
+    pub fn describe(&self) -> &'static str {
+        match self {
+            ESteamGuard::Email => "code sent to the account email",
+            ESteamGuard::Mobile => "code from the Steam mobile authenticator",
+        }
+    }
+}
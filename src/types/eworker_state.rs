@@ -0,0 +1,21 @@
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum EWorkerState {
+    Running,
+    // Crashed, waiting out the backoff before starting again
+    Restarting,
+    // Crashed more often than it may be restarted
+    Failed,
+    // Returned on its own, e.g. the poll loop after a disconnect
+    Stopped,
+}
+
+impl EWorkerState {
+    pub fn name(&self) -> &'static str {
+        match self {
+            EWorkerState::Running => "running",
+            EWorkerState::Restarting => "restarting",
+            EWorkerState::Failed => "failed",
+            EWorkerState::Stopped => "stopped",
+        }
+    }
+}
@@ -0,0 +1,33 @@
+// A feature loop's running flag and the stats of its current run, kept after it stops
+// until the next one starts
+#[derive(Debug, Clone, Default)]
+pub struct FeatureRun<S> {
+    pub running: bool,
+    pub stats: Option<S>,
+}
+
+impl<S: Clone + Default> FeatureRun<S> {
+    // Starts a run with fresh stats, false when one is already going
+    pub fn start(&mut self) -> bool {
+        if self.running {
+            return false;
+        }
+        self.running = true;
+        self.stats = Some(S::default());
+        true
+    }
+
+    // Ends the run and returns its stats
+    pub fn finish(&mut self) -> S {
+        self.running = false;
+        self.stats()
+    }
+
+    pub fn stats(&self) -> S {
+        self.stats.clone().unwrap_or_default()
+    }
+
+    pub fn record<T>(&mut self, f: impl FnOnce(&mut S) -> T) -> T {
+        f(self.stats.get_or_insert_with(S::default))
+    }
+}
@@ -1,101 +1,218 @@
+use super::elogin_inconsistency::ELoginInconsistency;
 use crate::utils;
+use crate::utils::proton;
+pub use mori_protocol::login_info::LoginInfo;
 
-#[derive(Debug, Default)]
-pub struct LoginInfo {
-    pub uuid: String,
-    pub tank_id_name: String,
-    pub tank_id_pass: String,
-    pub protocol: String,
-    pub fhash: String,
-    pub mac: String,
-    pub requested_name: String,
-    pub hash2: String,
-    pub fz: String,
-    pub f: String,
-    pub player_age: String,
-    pub game_version: String,
-    pub lmode: String,
-    pub cbits: String,
-    pub rid: String,
-    pub gdpr: String,
-    pub hash: String,
-    pub category: String,
-    pub token: String,
-    pub total_playtime: String,
-    pub door_id: String,
-    pub klv: String,
-    pub meta: String,
-    pub platform_id: String,
-    pub device_version: String,
-    pub zf: String,
-    pub country: String,
-    pub user: String,
-    pub wk: String,
+// First number of platformID
+const PLATFORM_WINDOWS: &str = "0";
+const PLATFORM_ANDROID: &str = "4";
+const PLATFORM_STEAM: &str = "15";
+
+// Fills in the per-install values the protocol crate leaves empty
+pub fn generate() -> LoginInfo {
+    LoginInfo {
+        protocol: utils::config::get_protocol(),
+        mac: utils::random::mac_address(),
+        game_version: utils::config::get_game_version(),
+        rid: utils::random::hex(32, true),
+        wk: utils::random::hex(32, true),
+        ..LoginInfo::new()
+    }
 }
 
-impl LoginInfo {
-    pub fn new() -> Self {
-        LoginInfo {
-            uuid: String::new(),
-            tank_id_name: String::new(),
-            tank_id_pass: String::new(),
-            protocol: "209".to_string(),
-            fhash: "-716928004".to_string(),
-            mac: utils::random::mac_address(),
-            requested_name: "BraveDuck".to_string(),
-            hash2: String::new(),
-            fz: "47142936".to_string(),
-            f: "1".to_string(),
-            player_age: "20".to_string(),
-            game_version: utils::config::get_game_version(),
-            lmode: "1".to_string(),
-            cbits: "1040".to_string(),
-            rid: utils::random::hex(32, true),
-            gdpr: "3".to_string(),
-            hash: "0".to_string(),
-            category: "_-5100".to_string(),
-            token: String::new(),
-            total_playtime: "0".to_string(),
-            door_id: String::new(),
-            klv: String::new(),
-            meta: String::new(),
-            platform_id: "0,1,1".to_string(),
-            device_version: "0".to_string(),
-            zf: "-821693372".to_string(),
-            country: "jp".to_string(),
-            user: String::new(),
-            wk: utils::random::hex(32, true),
+// Fills in the hashes derived from the other fields, with a fresh random second hash
+pub fn spoof(info: &mut LoginInfo) {
+    info.klv = proton::generate_klv(&info.protocol, &info.game_version, &info.rid);
+    info.hash = proton::hash_string(&format!("{}RT", info.mac)).to_string();
+    info.hash2 = proton::hash_string(&format!("{}RT", utils::random::hex(16, true))).to_string();
+}
+
+// Cross-checks the fields a real client derives from each other, any mismatch is
+// something the server can flag
+pub fn check_consistency(info: &LoginInfo) -> Vec<ELoginInconsistency> {
+    let mut issues = Vec::new();
+
+    let platform: Vec<&str> = info.platform_id.split(',').collect();
+    if platform.len() != 3 || platform.iter().any(|part| part.parse::<u32>().is_err()) {
+        issues.push(ELoginInconsistency::PlatformFormat(info.platform_id.clone()));
+    } else {
+        // Desktop clients always send 0, Android sends its SDK level
+        let device_ok = match platform[0] {
+            PLATFORM_WINDOWS | PLATFORM_STEAM => info.device_version == "0",
+            PLATFORM_ANDROID => info.device_version.parse::<u32>().map_or(false, |sdk| sdk > 0),
+            _ => true,
+        };
+        if !device_ok {
+            issues.push(ELoginInconsistency::PlatformDevice {
+                platform_id: info.platform_id.clone(),
+                device_version: info.device_version.clone(),
+            });
+        }
+    }
+
+    for (field, value) in [("zf", &info.zf), ("fz", &info.fz), ("fhash", &info.fhash)] {
+        if value.parse::<i32>().is_err() {
+            issues.push(ELoginInconsistency::NumberFormat {
+                field,
+                value: value.clone(),
+            });
+        }
+    }
+
+    let mac_parts: Vec<&str> = info.mac.split(':').collect();
+    if mac_parts.len() != 6
+        || mac_parts
+            .iter()
+            .any(|part| part.len() != 2 || u8::from_str_radix(part, 16).is_err())
+    {
+        issues.push(ELoginInconsistency::MacFormat(info.mac.clone()));
+    }
+
+    let expected_hash = expected_hash(info);
+    if info.hash != expected_hash {
+        issues.push(ELoginInconsistency::Hash {
+            expected: expected_hash,
+            actual: info.hash.clone(),
+        });
+    }
+
+    if info.klv != expected_klv(info) {
+        issues.push(ELoginInconsistency::Klv);
+    }
+
+    issues
+}
+
+// Recomputes the derived fields behind `issues`, returns how many were fixed
+pub fn fix(info: &mut LoginInfo, issues: &[ELoginInconsistency]) -> usize {
+    let mut fixed = 0;
+    for issue in issues {
+        match issue {
+            ELoginInconsistency::Hash { .. } => info.hash = expected_hash(info),
+            ELoginInconsistency::Klv => info.klv = expected_klv(info),
+            _ => continue,
         }
+        fixed += 1;
+    }
+    fixed
+}
+
+fn expected_hash(info: &LoginInfo) -> String {
+    proton::hash_string(&format!("{}RT", info.mac)).to_string()
+}
+
+fn expected_klv(info: &LoginInfo) -> String {
+    proton::generate_klv(&info.protocol, &info.game_version, &info.rid)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn consistent() -> LoginInfo {
+        let mut info = LoginInfo {
+            protocol: "209".to_string(),
+            mac: "02:1a:2b:3c:4d:5e".to_string(),
+            game_version: "4.64".to_string(),
+            rid: "0123456789ABCDEF0123456789ABCDEF".to_string(),
+            ..LoginInfo::new()
+        };
+        spoof(&mut info);
+        info
+    }
+
+    #[test]
+    fn spoofed_info_is_consistent() {
+        assert_eq!(check_consistency(&consistent()), vec![]);
+    }
+
+    #[test]
+    fn flags_malformed_platform_id() {
+        let mut info = consistent();
+        info.platform_id = "0,1".to_string();
+        assert_eq!(
+            check_consistency(&info),
+            vec![ELoginInconsistency::PlatformFormat("0,1".to_string())]
+        );
+    }
+
+    #[test]
+    fn flags_device_version_of_another_platform() {
+        let mut info = consistent();
+        info.device_version = "29".to_string();
+        assert!(matches!(
+            check_consistency(&info).as_slice(),
+            [ELoginInconsistency::PlatformDevice { .. }]
+        ));
+
+        info.platform_id = "4,1,1".to_string();
+        assert_eq!(check_consistency(&info), vec![]);
+        info.device_version = "0".to_string();
+        assert!(matches!(
+            check_consistency(&info).as_slice(),
+            [ELoginInconsistency::PlatformDevice { .. }]
+        ));
+    }
+
+    #[test]
+    fn flags_non_numeric_fields() {
+        let mut info = consistent();
+        info.zf = "abc".to_string();
+        info.fz = "99999999999".to_string();
+        assert_eq!(
+            check_consistency(&info),
+            vec![
+                ELoginInconsistency::NumberFormat { field: "zf", value: "abc".to_string() },
+                ELoginInconsistency::NumberFormat { field: "fz", value: "99999999999".to_string() },
+            ]
+        );
+    }
+
+    #[test]
+    fn flags_malformed_mac() {
+        let mut info = consistent();
+        info.mac = "02:1a:2b:3c:4d".to_string();
+        info.hash = expected_hash(&info);
+        assert_eq!(
+            check_consistency(&info),
+            vec![ELoginInconsistency::MacFormat("02:1a:2b:3c:4d".to_string())]
+        );
+    }
+
+    #[test]
+    fn flags_and_fixes_hash_mismatch() {
+        let mut info = consistent();
+        info.mac = "02:1a:2b:3c:4d:5f".to_string();
+        let issues = check_consistency(&info);
+        assert_eq!(
+            issues,
+            vec![ELoginInconsistency::Hash {
+                expected: "2396074708".to_string(),
+                actual: "2396073684".to_string(),
+            }]
+        );
+        assert!(issues[0].is_fixable());
+        assert_eq!(fix(&mut info, &issues), 1);
+        assert_eq!(check_consistency(&info), vec![]);
+    }
+
+    #[test]
+    fn flags_and_fixes_klv_mismatch() {
+        let mut info = consistent();
+        info.rid = "FEDCBA9876543210FEDCBA9876543210".to_string();
+        let issues = check_consistency(&info);
+        assert_eq!(issues, vec![ELoginInconsistency::Klv]);
+        assert_eq!(fix(&mut info, &issues), 1);
+        assert_eq!(check_consistency(&info), vec![]);
     }
 
-    pub fn to_string(&self) -> String {
-        format!(
-            "fhash|{}\nmac|{}\nrequestedName|{}\nhash2|{}\nfz|{}\nf|{}\nplayer_age|{}\ngame_version|{}\nlmode|{}\ncbits|{}\nrid|{}\ngdpr|{}\nhash|{}\ncategory|{}\ntoken|{}\ntotalPlaytime|{}\ndoorID|{}\nklv|{}\nmeta|{}\nplatformID|{}\ndeviceVersion|{}\nzf|{}\ncountry|{}\nuser|{}\nwk|{}",
-            self.fhash,
-            self.mac,
-            self.requested_name,
-            self.hash2,
-            self.fz,
-            self.f,
-            self.player_age,
-            self.game_version,
-            self.lmode,
-            self.cbits,
-            self.rid,
-            self.gdpr,
-            self.hash,
-            self.category,
-            self.token,
-            self.total_playtime,
-            self.door_id,
-            self.klv,
-            self.meta,
-            self.platform_id,
-            self.device_version,
-            self.zf,
-            self.country,
-            self.user,
-            self.wk
-        )
+    #[test]
+    fn identity_fields_are_not_fixed() {
+        let mut info = consistent();
+        info.platform_id = "x".to_string();
+        let issues = check_consistency(&info);
+        assert!(!issues[0].is_fixable());
+        assert_eq!(fix(&mut info, &issues), 0);
+        assert_eq!(info.platform_id, "x");
     }
 }
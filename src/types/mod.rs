@@ -1,9 +1,58 @@
+pub mod active_effect;
+pub mod bot_event;
 pub mod bot_info;
+pub mod bot_summary;
+pub mod build_pattern;
+pub mod captured_packet;
+pub mod character_state;
+pub mod clothing;
 pub mod config;
+pub mod console_line;
+pub mod disconnect_info;
+pub mod disconnect_phrases;
+pub mod eaccount_status;
+pub mod ebot_filter;
+pub mod echat_channel;
+pub mod echat_state;
+pub mod edelay_distribution;
+pub mod edisconnect_reason;
+pub mod edirection;
+pub mod ehumanizer_preset;
+pub mod elogin_inconsistency;
 pub mod elogin_method;
+pub mod eonboarding_dialog;
+pub mod epacket_direction;
 pub mod epacket_type;
+pub mod eplayer_title;
+pub mod ehazard_mode;
+pub mod enotification_action;
+pub mod ereaction;
+pub mod eseverity;
+pub mod esetup_fix;
+pub mod esteam_guard;
 pub mod etank_packet_type;
+pub mod eworker_state;
+pub mod feature_run;
 pub mod login_info;
+pub mod notification;
+pub mod oauth_links;
+pub mod packet_filter;
+pub mod particle;
 pub mod player;
+pub mod profiler;
+pub mod quest;
+pub mod repeat_stats;
+pub mod route;
+pub mod session_stats;
+pub mod setup_problem;
+pub mod static_farm_stats;
+pub mod surgery_rules;
+pub mod surgery_stats;
 pub mod tank_packet;
-pub mod vector;
\ No newline at end of file
+pub mod tile_extra;
+pub mod timeline_entry;
+pub mod value_table;
+pub mod vector;
+pub mod watch_report;
+pub mod worker_status;
+pub mod world_summary;
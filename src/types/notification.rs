@@ -0,0 +1,12 @@
+use super::{enotification_action::ENotificationAction, eseverity::ESeverity};
+
+#[derive(Debug, Clone)]
+pub struct Notification {
+    pub id: u64,
+    pub bot: String,
+    pub severity: ESeverity,
+    pub message: String,
+    pub action: Option<ENotificationAction>,
+    pub timestamp: u64,
+    pub read: bool,
+}
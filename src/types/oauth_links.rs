@@ -0,0 +1,46 @@
+// Per-method login links scraped from the dashboard page, None when the page had no link
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OAuthLinks {
+    pub apple: Option<String>,
+    pub google: Option<String>,
+    pub legacy: Option<String>,
+}
+
+impl OAuthLinks {
+    // Files a link under its method by path, links for anything else are ignored
+    pub fn insert(&mut self, link: String) {
+        let slot = if link.contains("/apple/") {
+            &mut self.apple
+        } else if link.contains("/google/") {
+            &mut self.google
+        } else if link.contains("/player/growid/") {
+            &mut self.legacy
+        } else {
+            return;
+        };
+        if slot.is_none() {
+            *slot = Some(link);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.apple.is_none() && self.google.is_none() && self.legacy.is_none()
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.apple.is_some() && self.google.is_some() && self.legacy.is_some()
+    }
+
+    // Names of the methods a link was found for, for logging
+    pub fn found(&self) -> Vec<&'static str> {
+        [
+            ("apple", &self.apple),
+            ("google", &self.google),
+            ("legacy", &self.legacy),
+        ]
+        .into_iter()
+        .filter(|(_, link)| link.is_some())
+        .map(|(name, _)| name)
+        .collect()
+    }
+}
@@ -0,0 +1,191 @@
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+
+const GAME_PACKET: u32 = 4;
+// Message types above this share its bit
+const LAST_TYPE_BIT: u32 = 31;
+const TANK_SAMPLES: usize = LAST_TYPE_BIT as usize + 1;
+
+// Which packets a bot logs and captures, as bitsets: one bit per message type and, for game
+// packets, one per tank packet type. A bot applies it through a LivePacketFilter
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PacketFilter {
+    pub types: u32,
+    pub tank_types: [u64; 4],
+    // 0 admits everything that passes, otherwise only the first N of each type
+    pub sample: u32,
+}
+
+impl Default for PacketFilter {
+    fn default() -> Self {
+        PacketFilter::all()
+    }
+}
+
+impl PacketFilter {
+    pub fn all() -> Self {
+        PacketFilter {
+            types: u32::MAX,
+            tank_types: [u64::MAX; 4],
+            sample: 0,
+        }
+    }
+
+    pub fn none() -> Self {
+        PacketFilter {
+            types: 0,
+            tank_types: [0; 4],
+            sample: 0,
+        }
+    }
+
+    // Game packets of the given tank types and nothing else
+    pub fn tank_only(tank_types: &[u8]) -> Self {
+        let mut filter = PacketFilter::none();
+        filter.set_type(GAME_PACKET, true);
+        for tank_type in tank_types {
+            filter.set_tank_type(*tank_type, true);
+        }
+        filter
+    }
+
+    pub fn allows_type(&self, packet_type: u32) -> bool {
+        self.types & type_bit(packet_type) != 0
+    }
+
+    pub fn set_type(&mut self, packet_type: u32, enabled: bool) {
+        let bit = type_bit(packet_type);
+        if enabled {
+            self.types |= bit;
+        } else {
+            self.types &= !bit;
+        }
+    }
+
+    pub fn allows_tank_type(&self, tank_type: u8) -> bool {
+        self.tank_types[tank_type as usize / 64] & tank_bit(tank_type) != 0
+    }
+
+    pub fn set_tank_type(&mut self, tank_type: u8, enabled: bool) {
+        let bit = tank_bit(tank_type);
+        let word = &mut self.tank_types[tank_type as usize / 64];
+        if enabled {
+            *word |= bit;
+        } else {
+            *word &= !bit;
+        }
+    }
+}
+
+// A bot's PacketFilter, read on every packet sent and received without taking a lock. A
+// packet racing a swap may see part of the old filter, the next one sees all of the new
+pub struct LivePacketFilter {
+    types: AtomicU32,
+    tank_types: [AtomicU64; 4],
+    sample: AtomicU32,
+    // Packets admitted per type while sampling, message types first then tank types
+    seen: Box<[AtomicU32]>,
+}
+
+impl LivePacketFilter {
+    pub fn new(filter: &PacketFilter) -> Self {
+        let live = LivePacketFilter {
+            types: AtomicU32::new(0),
+            tank_types: Default::default(),
+            sample: AtomicU32::new(0),
+            seen: (0..TANK_SAMPLES + 256).map(|_| AtomicU32::new(0)).collect(),
+        };
+        live.set(filter);
+        live
+    }
+
+    pub fn get(&self) -> PacketFilter {
+        PacketFilter {
+            types: self.types.load(Ordering::Relaxed),
+            tank_types: std::array::from_fn(|word| self.tank_types[word].load(Ordering::Relaxed)),
+            sample: self.sample.load(Ordering::Relaxed),
+        }
+    }
+
+    // Swapping the filter starts the sample counts over
+    pub fn set(&self, filter: &PacketFilter) {
+        self.types.store(filter.types, Ordering::Relaxed);
+        for (word, bits) in self.tank_types.iter().zip(filter.tank_types) {
+            word.store(bits, Ordering::Relaxed);
+        }
+        for seen in self.seen.iter() {
+            seen.store(0, Ordering::Relaxed);
+        }
+        self.sample.store(filter.sample, Ordering::Relaxed);
+    }
+
+    // Whether a packet is logged and captured, counting it against the sample limit when it is
+    pub fn admit(&self, packet_type: u32, tank_type: Option<u8>) -> bool {
+        if self.types.load(Ordering::Relaxed) & type_bit(packet_type) == 0 {
+            return false;
+        }
+        let tank_type = tank_type.filter(|_| packet_type == GAME_PACKET);
+        if let Some(tank_type) = tank_type {
+            if self.tank_types[tank_type as usize / 64].load(Ordering::Relaxed) & tank_bit(tank_type) == 0 {
+                return false;
+            }
+        }
+        let sample = self.sample.load(Ordering::Relaxed);
+        if sample == 0 {
+            return true;
+        }
+        let index = match tank_type {
+            Some(tank_type) => TANK_SAMPLES + tank_type as usize,
+            None => packet_type.min(LAST_TYPE_BIT) as usize,
+        };
+        self.seen[index]
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |seen| (seen < sample).then_some(seen + 1))
+            .is_ok()
+    }
+}
+
+fn type_bit(packet_type: u32) -> u32 {
+    1 << packet_type.min(LAST_TYPE_BIT)
+}
+
+fn tank_bit(tank_type: u8) -> u64 {
+    1 << (tank_type % 64)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct PacketFilterPreset {
+    pub name: String,
+    pub filter: PacketFilter,
+}
+
+impl PacketFilterPreset {
+    // Offered next to the saved ones, they can't be deleted
+    pub fn builtin() -> Vec<PacketFilterPreset> {
+        let mut dialogs = PacketFilter::tank_only(&[1]);
+        // Dialog buttons are answered with an action|dialog_return text packet
+        dialogs.set_type(2, true);
+        let mut text = PacketFilter::none();
+        text.set_type(2, true);
+        text.set_type(3, true);
+        vec![
+            PacketFilterPreset {
+                name: "Movement only".to_string(),
+                filter: PacketFilter::tank_only(&[0, 20]),
+            },
+            PacketFilterPreset {
+                name: "Dialogs and variants".to_string(),
+                filter: dialogs,
+            },
+            PacketFilterPreset {
+                name: "Text only".to_string(),
+                filter: text,
+            },
+            PacketFilterPreset {
+                name: "World and tiles".to_string(),
+                filter: PacketFilter::tank_only(&[3, 4, 5, 6, 8, 12, 15]),
+            },
+        ]
+    }
+}
@@ -0,0 +1,10 @@
+use std::time::Instant;
+
+#[derive(Debug, Clone)]
+pub struct Particle {
+    pub effect_id: u32,
+    // World position in pixels
+    pub x: f32,
+    pub y: f32,
+    pub received_at: Instant,
+}
@@ -1,4 +1,17 @@
+use super::character_state::CharacterState;
+use super::clothing::Clothing;
+use super::eplayer_title::EPlayerTitle;
 use super::vector::Vector2;
+use crate::utils::textparse;
+use std::collections::HashMap;
+
+// Spawn keys that have a field of their own, anything else lands in `Player::extra`
+const KNOWN_KEYS: [&str; 18] = [
+    "spawn", "type", "avatar", "netID", "onlineID", "eid", "ip", "colrect", "titleIcon",
+    "mstate", "smstate", "userID", "invis", "name", "country", "posXY", "level", "guild",
+];
+// Name color the server gives moderators
+const MOD_NAME_COLOR: &str = "`#@";
 
 #[derive(Default, Debug, Clone)]
 pub struct Player {
@@ -11,9 +24,133 @@ pub struct Player {
     pub colrect: String,
     pub title_icon: String,
     pub mstate: u32,
+    pub smstate: u32,
     pub user_id: u32,
     pub invis: bool,
     pub name: String,
     pub country: String,
     pub position: Vector2,
+    pub title: EPlayerTitle,
+    // 0 when the spawn string didn't say
+    pub level: u32,
+    pub guild: String,
+    pub extra: HashMap<String, String>,
+    pub clothing: Clothing,
+    pub character: CharacterState,
+}
+
+impl Player {
+    // Missing or malformed fields keep their defaults instead of dropping the player
+    pub fn from_spawn(data: &HashMap<String, String>) -> Self {
+        let text = |key: &str| data.get(key).cloned().unwrap_or_default();
+        let number = |key: &str| data.get(key).and_then(|value| value.trim().parse().ok()).unwrap_or(0);
+        let position = data
+            .get("posXY")
+            .and_then(|pos_xy| pos_xy.split_once('|'))
+            .map(|(x, y)| Vector2 {
+                x: x.parse().unwrap_or(0.0),
+                y: y.parse().unwrap_or(0.0),
+            })
+            .unwrap_or(Vector2 { x: 0.0, y: 0.0 });
+
+        let mut player = Player {
+            _type: text("type"),
+            avatar: text("avatar"),
+            net_id: number("netID"),
+            online_id: text("onlineID"),
+            e_id: text("eid"),
+            ip: text("ip"),
+            colrect: text("colrect"),
+            title_icon: text("titleIcon"),
+            mstate: number("mstate"),
+            smstate: number("smstate"),
+            user_id: number("userID"),
+            invis: number("invis") != 0,
+            name: text("name"),
+            country: text("country"),
+            position,
+            level: number("level"),
+            guild: text("guild"),
+            extra: data
+                .iter()
+                .filter(|(key, _)| !KNOWN_KEYS.contains(&key.as_str()))
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect(),
+            ..Default::default()
+        };
+        player.title = player.read_title();
+        player
+    }
+
+    pub fn is_moderator(&self) -> bool {
+        self.title == EPlayerTitle::Moderator
+    }
+
+    fn read_title(&self) -> EPlayerTitle {
+        let name = textparse::strip_color(&self.name);
+        if self.mstate != 0 || self.smstate != 0 || self.name.starts_with(MOD_NAME_COLOR) {
+            EPlayerTitle::Moderator
+        } else if name.ends_with(" of Legend") {
+            EPlayerTitle::Legendary
+        } else if name.starts_with("Dr.") {
+            EPlayerTitle::Doctor
+        } else {
+            EPlayerTitle::None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spawn(fixture: &str) -> Player {
+        let message = match fixture {
+            "moderator" => include_str!("../../tests/fixtures/spawn/moderator.txt"),
+            "guild_member" => include_str!("../../tests/fixtures/spawn/guild_member.txt"),
+            "plain" => include_str!("../../tests/fixtures/spawn/plain.txt"),
+            _ => unreachable!(),
+        };
+        Player::from_spawn(&textparse::parse_and_store_as_map(message))
+    }
+
+    #[test]
+    fn reads_a_moderator() {
+        let player = spawn("moderator");
+        assert!(player.is_moderator());
+        assert!(player.invis);
+        assert_eq!((player.net_id, player.user_id), (12, 48213077));
+        assert_eq!((player.mstate, player.smstate), (1, 1));
+        assert_eq!(player.country, "us");
+        assert!(player.title_icon.starts_with("{\"PlayerWorldID\":12"));
+        assert!(player.extra.is_empty());
+    }
+
+    #[test]
+    fn reads_a_guild_member() {
+        let player = spawn("guild_member");
+        assert_eq!(player.title, EPlayerTitle::Legendary);
+        assert_eq!(player.level, 87);
+        assert_eq!(player.guild, "Green Thumbs");
+        assert_eq!((player.position.x, player.position.y), (320.0, 1472.0));
+        // Keys without a field of their own are kept as they came
+        assert_eq!(player.extra.get("guildEmblem").map(String::as_str), Some("5|2"));
+    }
+
+    #[test]
+    fn reads_a_plain_player() {
+        let player = spawn("plain");
+        assert_eq!(player.title, EPlayerTitle::None);
+        assert_eq!(player.name, "`wquietbuilder``");
+        assert_eq!((player.level, player.guild.as_str()), (0, ""));
+        assert!(!player.invis);
+    }
+
+    #[test]
+    fn tolerates_missing_and_malformed_fields() {
+        let player = Player::from_spawn(&textparse::parse_and_store_as_map("netID|abc\nposXY|12\nname|x\n"));
+        assert_eq!(player.net_id, 0);
+        assert_eq!((player.position.x, player.position.y), (0.0, 0.0));
+        assert_eq!(player.title, EPlayerTitle::None);
+    }
 }
@@ -0,0 +1,70 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::Duration;
+
+// Recent samples kept per span for the p95
+const MAX_SAMPLES: usize = 256;
+
+#[derive(Debug, Default)]
+pub struct Profiler {
+    spans: Mutex<HashMap<String, SpanStats>>,
+}
+
+#[derive(Debug, Default)]
+struct SpanStats {
+    count: u64,
+    total: Duration,
+    samples: VecDeque<Duration>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SpanSummary {
+    pub name: String,
+    pub count: u64,
+    pub total: Duration,
+    pub p95: Duration,
+}
+
+impl Profiler {
+    pub fn record(&self, name: &str, elapsed: Duration) {
+        let mut spans = self.spans.lock().unwrap();
+        let stats = match spans.get_mut(name) {
+            Some(stats) => stats,
+            None => spans.entry(name.to_string()).or_default(),
+        };
+        stats.count += 1;
+        stats.total += elapsed;
+        if stats.samples.len() >= MAX_SAMPLES {
+            stats.samples.pop_front();
+        }
+        stats.samples.push_back(elapsed);
+    }
+
+    // Slowest spans (by total time) first
+    pub fn summary(&self) -> Vec<SpanSummary> {
+        let spans = self.spans.lock().unwrap();
+        let mut summary: Vec<SpanSummary> = spans
+            .iter()
+            .map(|(name, stats)| {
+                let mut samples: Vec<Duration> = stats.samples.iter().copied().collect();
+                samples.sort();
+                let p95 = samples
+                    .get((samples.len() * 95 / 100).min(samples.len().saturating_sub(1)))
+                    .copied()
+                    .unwrap_or_default();
+                SpanSummary {
+                    name: name.clone(),
+                    count: stats.count,
+                    total: stats.total,
+                    p95,
+                }
+            })
+            .collect();
+        summary.sort_by(|a, b| b.total.cmp(&a.total));
+        summary
+    }
+
+    pub fn reset(&self) {
+        self.spans.lock().unwrap().clear();
+    }
+}
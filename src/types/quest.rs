@@ -0,0 +1,32 @@
+use std::collections::HashMap;
+
+#[derive(Debug, Default, Clone)]
+pub struct Quests {
+    pub daily: Option<Quest>,
+    pub epic: Vec<QuestProgress>,
+    // Quest dialogs we couldn't parse, keyed by dialog name
+    pub unknown: HashMap<String, String>,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct Quest {
+    pub dialog_name: String,
+    pub requirements: Vec<QuestItem>,
+    pub reward: String,
+    // (button name, label) pairs as sent by the dialog
+    pub buttons: Vec<(String, String)>,
+}
+
+#[derive(Debug, Clone)]
+pub struct QuestItem {
+    pub item_id: u32,
+    pub name: String,
+    pub amount: u32,
+}
+
+#[derive(Debug, Clone)]
+pub struct QuestProgress {
+    pub text: String,
+    pub current: u32,
+    pub total: u32,
+}
@@ -0,0 +1,24 @@
+use std::collections::HashMap;
+
+// Progress of a repeat activate run, see FeatureRun
+#[derive(Debug, Clone, Default)]
+pub struct RepeatStats {
+    pub uses: u32,
+    pub tickets_used: u32,
+    // Net inventory change per item id over the run, the ticket left out
+    pub gained: HashMap<u32, i32>,
+}
+
+impl RepeatStats {
+    // Items that changed, most gained first
+    pub fn changes(&self) -> Vec<(u32, i32)> {
+        let mut changes: Vec<(u32, i32)> = self
+            .gained
+            .iter()
+            .filter(|(_, amount)| **amount != 0)
+            .map(|(id, amount)| (*id, *amount))
+            .collect();
+        changes.sort_by_key(|(id, amount)| (-amount, *id));
+        changes
+    }
+}
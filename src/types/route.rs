@@ -0,0 +1,10 @@
+use serde::{Deserialize, Serialize};
+
+// Named list of tiles walked in order, made in the world map planner
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct Route {
+    pub name: String,
+    pub world: String,
+    pub waypoints: Vec<(u32, u32)>,
+}
@@ -0,0 +1,106 @@
+use std::collections::BTreeMap;
+
+use crate::utils::date;
+
+// Days of history kept in `daily`, older buckets are dropped on rollover
+pub const DAILY_HISTORY_DAYS: usize = 14;
+
+pub type DailyCounters = BTreeMap<String, u64>;
+
+#[derive(Debug, Clone)]
+pub struct SessionStats {
+    pub started_at: u64,
+    pub counters: BTreeMap<String, u64>,
+    // Keyed by YYYY-MM-DD so the map iterates oldest first
+    pub daily: BTreeMap<String, DailyCounters>,
+    // Second the current day key was resolved at, so `record` resolves it at most once a second
+    today: Option<(u64, String)>,
+}
+
+impl Default for SessionStats {
+    fn default() -> Self {
+        SessionStats {
+            started_at: date::now_secs(),
+            counters: BTreeMap::new(),
+            daily: BTreeMap::new(),
+            today: None,
+        }
+    }
+}
+
+impl SessionStats {
+    // `day` is resolved by the caller while holding the stats lock, so an increment
+    // never lands half in yesterday and half in today
+    pub fn increment(&mut self, name: &str, amount: u64, day: &str) {
+        *self.counters.entry(name.to_string()).or_insert(0) += amount;
+
+        if !self.daily.contains_key(day) {
+            self.daily.insert(day.to_string(), DailyCounters::new());
+            while self.daily.len() > DAILY_HISTORY_DAYS {
+                let oldest = self.daily.keys().next().cloned().unwrap();
+                self.daily.remove(&oldest);
+            }
+        }
+        if let Some(counters) = self.daily.get_mut(day) {
+            *counters.entry(name.to_string()).or_insert(0) += amount;
+        }
+    }
+
+    // Counts into the day `day_of` returns for `now_secs`, the key is reused within the same second
+    pub fn record(&mut self, name: &str, amount: u64, now_secs: u64, day_of: impl FnOnce() -> String) {
+        let (resolved_at, day) = match self.today.take() {
+            Some((resolved_at, day)) if resolved_at == now_secs => (resolved_at, day),
+            _ => (now_secs, day_of()),
+        };
+        self.increment(name, amount, &day);
+        self.today = Some((resolved_at, day));
+    }
+
+    pub fn get(&self, name: &str) -> u64 {
+        self.counters.get(name).cloned().unwrap_or(0)
+    }
+
+    pub fn get_daily(&self, name: &str, day: &str) -> u64 {
+        self.daily
+            .get(day)
+            .and_then(|counters| counters.get(counter_name(name)))
+            .cloned()
+            .unwrap_or(0)
+    }
+}
+
+// Short names accepted by scripts and the GUI
+pub fn counter_name(name: &str) -> &str {
+    match name {
+        "gems" => "gems_collected",
+        "items" => "items_collected",
+        "blocks" => "blocks_broken",
+        "worlds" => "worlds_entered",
+        _ => name,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn record_resolves_the_day_once_per_second() {
+        let mut stats = SessionStats::default();
+        let mut resolved = 0;
+        for _ in 0..3 {
+            stats.record("gems_collected", 1, 100, || {
+                resolved += 1;
+                "2026-10-14".to_string()
+            });
+        }
+        stats.record("gems_collected", 1, 101, || {
+            resolved += 1;
+            "2026-10-15".to_string()
+        });
+        assert_eq!(resolved, 2);
+        assert_eq!(stats.get_daily("gems_collected", "2026-10-14"), 3);
+        assert_eq!(stats.get_daily("gems_collected", "2026-10-15"), 1);
+        assert_eq!(stats.get("gems_collected"), 4);
+    }
+}
@@ -0,0 +1,20 @@
+use super::esetup_fix::ESetupFix;
+
+// Something found at startup that would stop Mori from running properly
+#[derive(Debug, Clone)]
+pub struct SetupProblem {
+    pub title: String,
+    // How to fix it by hand
+    pub hint: String,
+    pub fix: Option<ESetupFix>,
+}
+
+impl SetupProblem {
+    pub fn new(title: String, hint: &str, fix: Option<ESetupFix>) -> Self {
+        SetupProblem {
+            title,
+            hint: hint.to_string(),
+            fix,
+        }
+    }
+}
@@ -0,0 +1,29 @@
+use std::time::Instant;
+
+// Progress of a static farm run, kept after it stops until the next one starts
+#[derive(Debug, Clone)]
+pub struct StaticFarmStats {
+    pub started_at: Instant,
+    pub placed: u32,
+    pub broken: u32,
+    pub seeds: u32,
+}
+
+impl StaticFarmStats {
+    pub fn new() -> Self {
+        StaticFarmStats {
+            started_at: Instant::now(),
+            placed: 0,
+            broken: 0,
+            seeds: 0,
+        }
+    }
+
+    pub fn blocks_per_hour(&self) -> f32 {
+        let hours = self.started_at.elapsed().as_secs_f32() / 3600.0;
+        if hours <= 0.0 {
+            return 0.0;
+        }
+        self.broken as f32 / hours
+    }
+}
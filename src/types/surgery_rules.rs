@@ -0,0 +1,104 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+// Contents of surgery_rules.json. Every match is on the lowercase, colour stripped text
+// of the surgery dialog, so new wordings only need an edit to the file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SurgeryRules {
+    pub dialog_name: String,
+    // Foreground items whose name contains one of these can be operated on
+    pub beds: Vec<String>,
+    // Tool name to item id
+    pub tools: BTreeMap<String, u32>,
+    // Checked in order, the first rule with a matching phrase picks the next tool
+    pub rules: Vec<SurgeryRule>,
+    pub success: Vec<String>,
+    pub failure: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct SurgeryRule {
+    pub when: Vec<String>,
+    pub tool: String,
+    // Random event rather than a regular step, only counted separately
+    pub complication: bool,
+}
+
+impl SurgeryRules {
+    pub fn next_rule(&self, text: &str) -> Option<&SurgeryRule> {
+        let text = text.to_lowercase();
+        self.rules
+            .iter()
+            .find(|rule| rule.when.iter().any(|phrase| text.contains(phrase.as_str())))
+    }
+
+    pub fn tool_id(&self, name: &str) -> Option<u32> {
+        self.tools.get(name).copied()
+    }
+
+    // Some(true) once the text says the patient pulled through, Some(false) when they didn't
+    pub fn outcome(&self, text: &str) -> Option<bool> {
+        let text = text.to_lowercase();
+        if self.failure.iter().any(|phrase| text.contains(phrase.as_str())) {
+            return Some(false);
+        }
+        if self.success.iter().any(|phrase| text.contains(phrase.as_str())) {
+            return Some(true);
+        }
+        None
+    }
+}
+
+impl Default for SurgeryRules {
+    fn default() -> Self {
+        let rule = |when: &[&str], tool: &str, complication: bool| SurgeryRule {
+            when: when.iter().map(|phrase| phrase.to_string()).collect(),
+            tool: tool.to_string(),
+            complication,
+        };
+        SurgeryRules {
+            dialog_name: "surgery".to_string(),
+            beds: vec!["Surg-E".to_string(), "Operating Table".to_string()],
+            tools: [
+                ("sponge", 1258),
+                ("scalpel", 1260),
+                ("anesthetic", 1262),
+                ("antiseptic", 1264),
+                ("antibiotics", 1266),
+                ("splint", 1268),
+                ("stitches", 1270),
+                ("pins", 4308),
+                ("transfusion", 4310),
+                ("defibrillator", 4312),
+                ("clamp", 4314),
+                ("ultrasound", 4316),
+                ("lab kit", 4318),
+            ]
+            .into_iter()
+            .map(|(name, id)| (name.to_string(), id))
+            .collect(),
+            rules: vec![
+                rule(&["heart has stopped", "heart stopped"], "defibrillator", true),
+                rule(
+                    &["infection", "fever is rapidly rising", "fever is climbing"],
+                    "antibiotics",
+                    true,
+                ),
+                rule(&["losing blood very quickly", "pulse: extremely weak"], "transfusion", false),
+                rule(&["hard to see"], "sponge", false),
+                rule(&["status: awake"], "anesthetic", false),
+                rule(&["not sanitized", "unsanitary"], "antiseptic", false),
+                rule(&["bleeding"], "clamp", false),
+                rule(&["shattered"], "pins", false),
+                rule(&["broken"], "splint", false),
+                rule(&["incisions: 0"], "scalpel", false),
+                rule(&["ready to be stitched", "needs stitches"], "stitches", false),
+                rule(&["incisions"], "scalpel", false),
+            ],
+            success: vec!["surgery was a success".to_string(), "successful surgery".to_string()],
+            failure: vec!["patient died".to_string(), "surgery has failed".to_string()],
+        }
+    }
+}
@@ -0,0 +1,18 @@
+// Progress of an auto surgery run, see FeatureRun
+#[derive(Debug, Clone, Default)]
+pub struct SurgeryStats {
+    pub completed: u32,
+    pub succeeded: u32,
+    // Complication tools used, antibiotics and the defibrillator in the default rules
+    pub complications: u32,
+}
+
+impl SurgeryStats {
+    // Percentage of finished surgeries the patient survived
+    pub fn success_rate(&self) -> f32 {
+        if self.completed == 0 {
+            return 0.0;
+        }
+        self.succeeded as f32 * 100.0 / self.completed as f32
+    }
+}
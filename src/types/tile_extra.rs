@@ -0,0 +1,85 @@
+use crate::utils::textparse;
+use gtitem_r::structs::ItemDatabase;
+use gtworld_r::TileType;
+
+// Extra tile data worth showing when scouting a world, taken from what gtworld_r parsed
+#[derive(Debug, Clone, PartialEq)]
+pub enum TileExtra {
+    // The destination isn't sent to clients, only the label
+    Door { label: String },
+    Sign { text: String },
+    Lock { owner_uid: u32, access_count: usize },
+    DisplayBlock { item_id: u32 },
+    VendingMachine { item_id: u32, price: i64 },
+    Mailbox,
+    // Not handled here yet, the parsed data as-is so the parser can be extended later
+    Other(String),
+}
+
+impl TileExtra {
+    // None for tiles without extra data and for seeds, which the map already covers
+    pub fn from_tile_type(tile_type: &TileType) -> Option<TileExtra> {
+        Some(match tile_type {
+            TileType::Basic | TileType::Seed { .. } => return None,
+            TileType::Door { text, .. } => TileExtra::Door {
+                label: textparse::strip_color(text),
+            },
+            TileType::Sign { text, .. } => TileExtra::Sign {
+                text: textparse::strip_color(text),
+            },
+            TileType::Lock {
+                owner_uid,
+                access_uids,
+                ..
+            } => TileExtra::Lock {
+                owner_uid: *owner_uid,
+                access_count: access_uids.len(),
+            },
+            TileType::DisplayBlock { item_id, .. } => TileExtra::DisplayBlock {
+                item_id: *item_id as u32,
+            },
+            TileType::VendingMachine { item_id, price, .. } => TileExtra::VendingMachine {
+                item_id: *item_id as u32,
+                price: *price as i64,
+            },
+            TileType::Mailbox { .. } => TileExtra::Mailbox,
+            other => TileExtra::Other(format!("{:?}", other)),
+        })
+    }
+
+    // Label and value pairs for tooltips and the inspect window
+    pub fn fields(&self, item_database: &ItemDatabase) -> Vec<(&'static str, String)> {
+        let item_name = |item_id: u32| {
+            item_database
+                .get_item(&item_id)
+                .map_or(format!("#{}", item_id), |item| item.name.clone())
+        };
+        match self {
+            TileExtra::Door { label } => vec![("Door label", label.clone())],
+            TileExtra::Sign { text } => vec![("Sign", text.clone())],
+            TileExtra::Lock {
+                owner_uid,
+                access_count,
+            } => vec![
+                ("Owner", format!("#{}", owner_uid)),
+                ("Access", format!("{} players", access_count)),
+            ],
+            TileExtra::DisplayBlock { item_id } => vec![("Displaying", item_name(*item_id))],
+            TileExtra::VendingMachine { item_id, price } => {
+                if *item_id == 0 {
+                    vec![("Vending", "empty".to_string())]
+                } else {
+                    // A negative price is that many items per World Lock
+                    let price = if *price < 0 {
+                        format!("{} per WL", -price)
+                    } else {
+                        format!("{} WL each", price)
+                    };
+                    vec![("Vending", item_name(*item_id)), ("Price", price)]
+                }
+            }
+            TileExtra::Mailbox => vec![("Mailbox", "contents are only shown to the owner".to_string())],
+            TileExtra::Other(data) => vec![("Extra data", data.clone())],
+        }
+    }
+}
@@ -0,0 +1,9 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimelineEntry {
+    pub timestamp: u64,
+    // BotEvent::name of the event this was recorded from
+    pub kind: String,
+    pub detail: String,
+}
@@ -0,0 +1,23 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+// Item values in world locks. Synced values come from the configured URL, overrides are
+// edited by hand and win over them
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct ValueTable {
+    pub overrides: HashMap<u32, f32>,
+    pub synced: HashMap<u32, f32>,
+    // Where and when the synced values were downloaded, 0 when never
+    pub source: String,
+    pub synced_at: u64,
+}
+
+impl ValueTable {
+    pub fn get(&self, item_id: u32) -> Option<f32> {
+        self.overrides
+            .get(&item_id)
+            .or_else(|| self.synced.get(&item_id))
+            .copied()
+    }
+}
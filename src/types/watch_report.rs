@@ -0,0 +1,29 @@
+// What the world watcher saw on its last visit to a world
+#[derive(Debug, Clone, Default)]
+pub struct WatchReport {
+    pub world: String,
+    pub visited_at: u64,
+    pub players: usize,
+    pub dropped_gems: u32,
+    // Tiles whose foreground or background differ from the previous snapshot,
+    // None on the first visit
+    pub changed_tiles: Option<usize>,
+    // Set once the world failed to load twice, it is skipped for the rest of the run
+    pub failed: bool,
+}
+
+impl WatchReport {
+    pub fn describe(&self) -> String {
+        if self.failed {
+            return format!("{}: failed to load, skipped", self.world);
+        }
+        let changed = match self.changed_tiles {
+            Some(changed) => format!("{} tiles changed", changed),
+            None => "no previous snapshot".to_string(),
+        };
+        format!(
+            "{}: {} players, {} gems dropped, {}",
+            self.world, self.players, self.dropped_gems, changed
+        )
+    }
+}
@@ -0,0 +1,26 @@
+use super::eworker_state::EWorkerState;
+
+// A supervised background thread of a bot, see core::supervisor
+#[derive(Debug, Clone)]
+pub struct WorkerStatus {
+    pub name: String,
+    pub state: EWorkerState,
+    pub restarts: u32,
+    pub last_panic: Option<String>,
+}
+
+impl WorkerStatus {
+    pub fn new(name: &str) -> Self {
+        WorkerStatus {
+            name: name.to_string(),
+            state: EWorkerState::Running,
+            restarts: 0,
+            last_panic: None,
+        }
+    }
+
+    // Crashed at least once since it was started
+    pub fn is_degraded(&self) -> bool {
+        self.last_panic.is_some()
+    }
+}
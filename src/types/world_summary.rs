@@ -0,0 +1,36 @@
+#[derive(Debug, Clone, Default)]
+pub struct WorldSummary {
+    pub name: String,
+    pub width: u32,
+    pub height: u32,
+    // Name when the owner is in the world, otherwise their user id
+    pub owner: Option<String>,
+    pub players: usize,
+    // None when no farmable item is configured
+    pub farmable_tiles: Option<usize>,
+    pub dropped_objects: usize,
+    pub dropped_gems: u32,
+    pub has_access: bool,
+}
+
+impl WorldSummary {
+    pub fn describe(&self) -> String {
+        let mut lines = vec![
+            format!("{} ({}x{})", self.name, self.width, self.height),
+            format!("Owner: {}", self.owner.as_deref().unwrap_or("none")),
+            format!("Players: {}", self.players),
+        ];
+        if let Some(farmable_tiles) = self.farmable_tiles {
+            lines.push(format!("Farmable tiles: {}", farmable_tiles));
+        }
+        lines.push(format!(
+            "Dropped: {} objects, {} gems",
+            self.dropped_objects, self.dropped_gems
+        ));
+        lines.push(format!(
+            "Build access: {}",
+            if self.has_access { "yes" } else { "no" }
+        ));
+        lines.join("\n")
+    }
+}
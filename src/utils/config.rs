@@ -1,207 +1,1100 @@
+use paris::error;
 use std::{
     fs::{self, File},
-    io::{Read, Write},
+    io::{self, Read, Write},
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex, RwLock,
+    },
+    thread,
+    time::{Duration, SystemTime},
 };
 
-use crate::types::config::{BotConfig, Config, Theme};
+use crate::types::config::{
+    resolved, BotConfig, BotTemplate, Config, Consumable, LoginWindow, NetworkConfig, ProxyPool,
+    SpawnSpot, Theme, WorldPassword,
+};
+use crate::types::ebot_filter::EBotFilter;
+use crate::types::edelay_distribution::EDelayDistribution;
+use crate::types::ehumanizer_preset::EHumanizerPreset;
+use crate::types::packet_filter::PacketFilterPreset;
+use crate::types::build_pattern::BuildPattern;
+use crate::types::route::Route;
 use crate::utils;
 
 use super::captcha::CaptchaProvider;
+use super::secrets;
+
+pub const CONFIG_PATH: &str = "config.json";
+
+// Every write goes through `update`/`save` so panels can't interleave read-modify-write cycles
+static CONFIG_LOCK: Mutex<()> = Mutex::new(());
+// Loaded once and kept in sync by `update`, `save` and `watch`, readers never touch the disk.
+// While the file on disk is invalid this keeps serving the last config that parsed
+static CONFIG: RwLock<Option<Arc<Config>>> = RwLock::new(None);
+static SUBSCRIBERS: Mutex<Vec<Sender<Arc<Config>>>> = Mutex::new(Vec::new());
+static LAST_MODIFIED: Mutex<Option<SystemTime>> = Mutex::new(None);
+// Why the file on disk doesn't parse. Saving is refused meanwhile, it would replace the
+// user's edit with the in-memory copy
+static BROKEN: RwLock<Option<String>> = RwLock::new(None);
+
+// The cached config, read from disk on first use. A missing or invalid file is an error
+// and nothing is cached, so the next call tries again
+pub fn load() -> Result<Arc<Config>, String> {
+    if let Some(config) = CONFIG.read().unwrap().as_ref() {
+        return Ok(config.clone());
+    }
+    let mut cache = CONFIG.write().unwrap();
+    if let Some(config) = cache.as_ref() {
+        return Ok(config.clone());
+    }
+    let config = Arc::new(read_config()?);
+    *cache = Some(config.clone());
+    Ok(config)
+}
+
+// `load` for the getters. setup_check has already made sure the file parses, this only
+// fails when it was broken after startup and the defaults are better than a panic. The
+// config is marked broken then, saving the defaults would wipe the file
+pub fn get() -> Arc<Config> {
+    load().unwrap_or_else(|err| {
+        error!("Failed to load {}, using the defaults: {}", CONFIG_PATH, err);
+        if fs::metadata(CONFIG_PATH).is_ok() {
+            *BROKEN.write().unwrap() = Some(err);
+        }
+        let config = Arc::new(Config::default());
+        *CONFIG.write().unwrap() = Some(config.clone());
+        config
+    })
+}
+
+// Writes the default config when there is none yet
+pub fn init() -> io::Result<()> {
+    if fs::metadata(CONFIG_PATH).is_ok() {
+        return Ok(());
+    }
+    save(&Config::default())
+}
+
+// Receives the new config after every save or external reload
+pub fn subscribe() -> Receiver<Arc<Config>> {
+    let (sender, receiver) = mpsc::channel();
+    SUBSCRIBERS.lock().unwrap().push(sender);
+    receiver
+}
+
+fn store(config: Config) {
+    let config = Arc::new(config);
+    *CONFIG.write().unwrap() = Some(config.clone());
+    SUBSCRIBERS
+        .lock()
+        .unwrap()
+        .retain(|subscriber| subscriber.send(config.clone()).is_ok());
+}
+
+pub fn read_config() -> Result<Config, String> {
+    let mut f = File::open(CONFIG_PATH).map_err(|e| e.to_string())?;
+    let mut contents = String::new();
+    f.read_to_string(&mut contents).map_err(|e| e.to_string())?;
+    let mut config: Config = serde_json::from_str(&contents).map_err(|e| e.to_string())?;
+    secrets::open_config(&mut config)?;
+    Ok(config)
+}
+
+// Reads the file again, e.g. once the secrets can be opened
+pub fn reload() -> Result<(), String> {
+    let _guard = CONFIG_LOCK.lock().unwrap();
+    store(read_config()?);
+    *BROKEN.write().unwrap() = None;
+    Ok(())
+}
+
+// Set while config.json holds an edit that doesn't parse, nothing is saved until it's fixed
+pub fn broken() -> Option<String> {
+    BROKEN.read().unwrap().clone()
+}
+
+pub fn update<F: FnOnce(&mut Config)>(f: F) {
+    let _guard = CONFIG_LOCK.lock().unwrap();
+    let mut config = get().as_ref().clone();
+    f(&mut config);
+    if let Err(err) = write_config(&config) {
+        error!("Failed to save config: {}", err);
+    }
+}
 
-pub fn parse_config() -> Result<Config, ()> {
-    if fs::metadata("config.json").is_ok() {
-        let mut f = File::open("config.json").unwrap();
-        let mut contents = String::new();
-        f.read_to_string(&mut contents).unwrap();
-        let j: Config = serde_json::from_str(&contents).unwrap();
-        return Ok(j);
+pub fn save(config: &Config) -> io::Result<()> {
+    let _guard = CONFIG_LOCK.lock().unwrap();
+    write_config(config)
+}
+
+// Write to a temp file and rename over the original so a crash mid-save never leaves a
+// truncated config behind, the previous valid version is kept as config.json.bak
+fn write_config(config: &Config) -> io::Result<()> {
+    if let Some(err) = broken() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{} is invalid ({}), fix it to save changes again", CONFIG_PATH, err),
+        ));
+    }
+    let sealed = secrets::seal_config(config)
+        .map_err(|err| io::Error::new(io::ErrorKind::PermissionDenied, err))?;
+    let j = serde_json::to_string_pretty(&sealed)?;
+    let tmp_path = format!("{}.tmp", CONFIG_PATH);
+    {
+        let mut file = File::create(&tmp_path)?;
+        file.write_all(j.as_bytes())?;
+        file.sync_all()?;
+    }
+    if read_config().is_ok() {
+        fs::copy(CONFIG_PATH, format!("{}.bak", CONFIG_PATH))?;
     }
-    Err(())
+    fs::rename(&tmp_path, CONFIG_PATH)?;
+    store(config.clone());
+    *LAST_MODIFIED.lock().unwrap() = modified_time();
+    Ok(())
+}
+
+// The backup and a config set aside by setup_check's CreateConfig, both may predate encryption
+pub fn remove_plaintext_copies() {
+    let _ = fs::remove_file(format!("{}.bak", CONFIG_PATH));
+    let _ = fs::remove_file(format!("{}.broken", CONFIG_PATH));
+}
+
+fn modified_time() -> Option<SystemTime> {
+    fs::metadata(CONFIG_PATH).and_then(|m| m.modified()).ok()
+}
+
+// Polls the config for changes made outside of the app, `on_change` gets the
+// validation result of every external edit
+pub fn watch<F>(on_change: F)
+where
+    F: Fn(Result<(), String>) + Send + 'static,
+{
+    *LAST_MODIFIED.lock().unwrap() = modified_time();
+    thread::spawn(move || loop {
+        thread::sleep(Duration::from_secs(1));
+        let result = {
+            let _guard = CONFIG_LOCK.lock().unwrap();
+            let modified = modified_time();
+            let mut last_modified = LAST_MODIFIED.lock().unwrap();
+            if modified == *last_modified {
+                continue;
+            }
+            *last_modified = modified;
+            match read_config() {
+                Ok(config) => {
+                    store(config);
+                    *BROKEN.write().unwrap() = None;
+                    Ok(())
+                }
+                // A deleted file is simply written again on the next save
+                Err(err) if modified.is_none() => Err(err),
+                Err(err) => {
+                    *BROKEN.write().unwrap() = Some(err.clone());
+                    Err(err)
+                }
+            }
+        };
+        on_change(result);
+    });
 }
 
 pub fn add_bot(bot: BotConfig) {
-    let mut config = parse_config().unwrap();
-    config.bots.push(bot);
-    let j = serde_json::to_string_pretty(&config).unwrap();
-    let mut file = File::create("config.json").unwrap();
-    file.write_all(j.as_bytes()).unwrap();
+    update(|config| {
+        config.bots.push(bot);
+    });
 }
 
+// Bots as they apply, see types::config::resolved. Writes go through update_bot_config, which
+// works on what the bot set itself
 pub fn get_bots() -> Vec<BotConfig> {
-    let config = parse_config().unwrap();
-    config.bots
+    let config = get();
+    config.bots.iter().map(|bot| resolved(bot, &config.bot_templates)).collect()
 }
 
 pub fn remove_bot(username: String) {
-    let mut config = parse_config().unwrap();
-    config.bots.retain(|x| {
-        let payload = utils::textparse::parse_and_store_as_vec(&x.payload);
-        payload[0] != username
+    update(|config| {
+        config.bots.retain(|x| {
+            let payload = utils::textparse::parse_and_store_as_vec(&x.payload);
+            payload[0] != username
+        });
     });
-    let j = serde_json::to_string_pretty(&config).unwrap();
-    let mut file = File::create("config.json").unwrap();
-    file.write_all(j.as_bytes()).unwrap();
 }
 
 pub fn set_timeout(timeout: u32) {
-    let mut config = parse_config().unwrap();
-    config.timeout = timeout;
-    let j = serde_json::to_string_pretty(&config).unwrap();
-    let mut file = File::create("config.json").unwrap();
-    file.write_all(j.as_bytes()).unwrap();
+    update(|config| {
+        config.timeout = timeout;
+    });
 }
 
 pub fn get_timeout() -> u32 {
-    let config = parse_config().unwrap();
-    config.timeout
+    get().timeout
+}
+
+pub fn set_connect_timeout(connect_timeout: u64) {
+    update(|config| {
+        config.connect_timeout = connect_timeout;
+    });
+}
+
+pub fn get_connect_timeout() -> u64 {
+    get().connect_timeout
+}
+
+pub fn set_stall_timeout(stall_timeout: u64) {
+    update(|config| {
+        config.stall_timeout = stall_timeout;
+    });
+}
+
+pub fn get_stall_timeout() -> u64 {
+    get().stall_timeout
 }
 
 pub fn set_findpath_delay(findpath_delay: u32) {
-    let mut config = parse_config().unwrap();
-    config.findpath_delay = findpath_delay;
-    let j = serde_json::to_string_pretty(&config).unwrap();
-    let mut file = File::create("config.json").unwrap();
-    file.write_all(j.as_bytes()).unwrap();
+    update(|config| {
+        config.findpath_delay = findpath_delay;
+    });
 }
 
 pub fn save_token_to_bot(username: String, token: String, data: String) {
-    let mut config = parse_config().unwrap();
-    for bot in config.bots.iter_mut() {
-        let payload = utils::textparse::parse_and_store_as_vec(&bot.payload);
-        if payload[0] == username {
-            bot.token = token.clone();
-            bot.data = data.clone();
+    update(|config| {
+        for bot in config.bots.iter_mut() {
+            let payload = utils::textparse::parse_and_store_as_vec(&bot.payload);
+            if payload[0] == username {
+                bot.token = token.clone();
+                bot.data = data.clone();
+            }
         }
-    }
-    let j = serde_json::to_string_pretty(&config).unwrap();
-    let mut file = File::create("config.json").unwrap();
-    file.write_all(j.as_bytes()).unwrap();
+    });
 }
 
 pub fn get_findpath_delay() -> u32 {
-    let config = parse_config().unwrap();
-    config.findpath_delay
+    get().findpath_delay
 }
 
 pub fn get_selected_bot() -> String {
-    let config = parse_config().unwrap();
-    config.selected_bot
+    get().selected_bot.clone()
 }
 
 pub fn set_selected_bot(username: String) {
-    let mut config = parse_config().unwrap();
-    config.selected_bot = username;
-    let j = serde_json::to_string_pretty(&config).unwrap();
-    let mut file = File::create("config.json").unwrap();
-    file.write_all(j.as_bytes()).unwrap();
+    update(|config| {
+        config.selected_bot = username;
+    });
 }
 
 pub fn get_game_version() -> String {
-    let config = parse_config().unwrap();
-    config.game_version
+    get().game_version.clone()
 }
 
 pub fn set_game_version(version: String) {
-    let mut config = parse_config().unwrap();
-    config.game_version = version;
-    let j = serde_json::to_string_pretty(&config).unwrap();
-    let mut file = File::create("config.json").unwrap();
-    file.write_all(j.as_bytes()).unwrap();
+    update(|config| {
+        config.game_version = version;
+    });
+}
+
+pub fn get_protocol() -> String {
+    get().protocol.clone()
+}
+
+pub fn set_protocol(protocol: String) {
+    update(|config| {
+        config.protocol = protocol;
+    });
+}
+
+pub fn get_version_source() -> String {
+    get().version_source.clone()
 }
 
 pub fn get_proxies() -> Vec<crate::types::config::Proxy> {
-    let config = parse_config().unwrap();
-    config.proxy
+    get().proxy.clone()
 }
 
 pub fn add_proxy(proxy: crate::types::config::Proxy) {
-    let mut config = parse_config().unwrap();
-    config.proxy.push(proxy);
-    let j = serde_json::to_string_pretty(&config).unwrap();
-    let mut file = File::create("config.json").unwrap();
-    file.write_all(j.as_bytes()).unwrap();
+    update(|config| {
+        config.proxy.push(proxy);
+    });
+}
+
+pub fn set_proxy(index: usize, proxy: crate::types::config::Proxy) {
+    update(|config| {
+        if let Some(existing) = config.proxy.get_mut(index) {
+            *existing = proxy;
+        }
+    });
 }
 
 pub fn remove_proxy(index: usize) {
-    let mut config = parse_config().unwrap();
-    config.proxy.remove(index);
-    let j = serde_json::to_string_pretty(&config).unwrap();
-    let mut file = File::create("config.json").unwrap();
-    file.write_all(j.as_bytes()).unwrap();
+    update(|config| {
+        config.proxy.remove(index);
+    });
 }
 
 pub fn get_use_alternate_server() -> bool {
-    let config = parse_config().unwrap();
-    config.use_alternate_server
+    get().use_alternate_server
 }
 
 pub fn set_use_alternate_server(use_alternate_server: bool) {
-    let mut config = parse_config().unwrap();
-    config.use_alternate_server = use_alternate_server;
-    let j = serde_json::to_string_pretty(&config).unwrap();
-    let mut file = File::create("config.json").unwrap();
-    file.write_all(j.as_bytes()).unwrap();
+    update(|config| {
+        config.use_alternate_server = use_alternate_server;
+    });
 }
 
 pub fn get_bot_use_proxy(username: String) -> bool {
-    let config = parse_config().unwrap();
-    for b in config.bots.iter() {
-        let payload = utils::textparse::parse_and_store_as_vec(&b.payload);
-        if payload[0] == username {
-            return b.use_proxy;
+    get_bot_config(&username).map_or(false, |bot| bot.use_proxy)
+}
+
+pub fn set_bot_humanizer(username: String, humanizer: Option<EHumanizerPreset>) {
+    update(|config| {
+        for bot in config.bots.iter_mut() {
+            let payload = utils::textparse::parse_and_store_as_vec(&bot.payload);
+            if payload[0] == username {
+                bot.humanizer = humanizer;
+            }
         }
-    }
-    false
+    });
+}
+
+pub fn get_bot_config(username: &str) -> Option<BotConfig> {
+    let config = get();
+    config
+        .bots
+        .iter()
+        .find(|b| utils::textparse::parse_and_store_as_vec(&b.payload)[0] == username)
+        .map(|bot| resolved(bot, &config.bot_templates))
+}
+
+pub fn update_bot_config<F: FnOnce(&mut BotConfig)>(username: &str, f: F) {
+    update(|config| {
+        let bot = config
+            .bots
+            .iter_mut()
+            .find(|b| utils::textparse::parse_and_store_as_vec(&b.payload)[0] == username);
+        if let Some(bot) = bot {
+            f(bot);
+        }
+    });
 }
 
 pub fn set_theme(theme: Theme) {
-    let mut config = parse_config().unwrap();
-    config.theme = theme;
-    let j = serde_json::to_string_pretty(&config).unwrap();
-    let mut file = File::create("config.json").unwrap();
-    file.write_all(j.as_bytes()).unwrap();
+    update(|config| {
+        config.theme = theme;
+    });
 }
 
 pub fn get_theme() -> Theme {
-    let config = parse_config().unwrap();
-    config.theme
+    get().theme.clone()
 }
 
 pub fn set_auto_collect(auto_collect: bool) {
-    let mut config = parse_config().unwrap();
-    config.auto_collect = auto_collect;
-    let j = serde_json::to_string_pretty(&config).unwrap();
-    let mut file = File::create("config.json").unwrap();
-    file.write_all(j.as_bytes()).unwrap();
+    update(|config| {
+        config.auto_collect = auto_collect;
+    });
 }
 
 pub fn get_auto_collect() -> bool {
-    let config = parse_config().unwrap();
-    config.auto_collect
+    get().auto_collect
 }
 
 pub fn get_captcha_provider() -> CaptchaProvider {
-    let config = parse_config().unwrap();
-    config.captcha.provider
+    get().captcha.provider.clone()
 }
 
 pub fn get_captcha_api_key() -> String {
-    let config = parse_config().unwrap();
-    config.captcha.api_key
+    get().captcha.api_key.clone()
 }
 
 pub fn set_captcha_provider(provider: CaptchaProvider) {
-    let mut config = parse_config().unwrap();
-    config.captcha.provider = provider;
-    let j = serde_json::to_string_pretty(&config).unwrap();
-    let mut file = File::create("config.json").unwrap();
-    file.write_all(j.as_bytes()).unwrap();
+    update(|config| {
+        config.captcha.provider = provider;
+    });
 }
 
 pub fn set_captcha_api_key(api_key: String) {
-    let mut config = parse_config().unwrap();
-    config.captcha.api_key = api_key;
-    let j = serde_json::to_string_pretty(&config).unwrap();
-    let mut file = File::create("config.json").unwrap();
-    file.write_all(j.as_bytes()).unwrap();
+    update(|config| {
+        config.captcha.api_key = api_key;
+    });
+}
+
+pub fn get_telemetry() -> bool {
+    get().telemetry
+}
+
+pub fn set_telemetry(telemetry: bool) {
+    update(|config| {
+        config.telemetry = telemetry;
+    });
+}
+
+pub fn get_door_walk_off() -> i32 {
+    get().door_walk_off
+}
+
+pub fn set_door_walk_off(door_walk_off: i32) {
+    update(|config| {
+        config.door_walk_off = door_walk_off;
+    });
+}
+
+pub fn get_auto_reply() -> bool {
+    get().auto_reply
+}
+
+pub fn set_auto_reply(auto_reply: bool) {
+    update(|config| {
+        config.auto_reply = auto_reply;
+    });
+}
+
+pub fn get_auto_reply_message() -> String {
+    get().auto_reply_message.clone()
+}
+
+pub fn set_auto_reply_message(auto_reply_message: String) {
+    update(|config| {
+        config.auto_reply_message = auto_reply_message;
+    });
+}
+
+pub fn get_auto_reply_cooldown() -> u32 {
+    get().auto_reply_cooldown
+}
+
+pub fn set_auto_reply_cooldown(auto_reply_cooldown: u32) {
+    update(|config| {
+        config.auto_reply_cooldown = auto_reply_cooldown;
+    });
+}
+
+pub fn get_forward_whispers() -> bool {
+    get().forward_whispers
+}
+
+pub fn set_forward_whispers(forward_whispers: bool) {
+    update(|config| {
+        config.forward_whispers = forward_whispers;
+    });
+}
+
+pub fn get_webhook_url() -> String {
+    get().webhook_url.clone()
+}
+
+pub fn set_webhook_url(webhook_url: String) {
+    update(|config| {
+        config.webhook_url = webhook_url;
+    });
+}
+
+pub fn get_consumables() -> Vec<Consumable> {
+    get().consumables.clone()
+}
+
+pub fn get_auto_reconsume() -> bool {
+    get().auto_reconsume
+}
+
+pub fn set_auto_reconsume(auto_reconsume: bool) {
+    update(|config| {
+        config.auto_reconsume = auto_reconsume;
+    });
+}
+
+pub fn get_reconsume_before_secs() -> u64 {
+    get().reconsume_before_secs
+}
+
+pub fn get_developer_mode() -> bool {
+    get().developer_mode
+}
+
+pub fn set_developer_mode(developer_mode: bool) {
+    update(|config| {
+        config.developer_mode = developer_mode;
+    });
+}
+
+pub fn get_stats_utc_offset() -> i32 {
+    get().stats_utc_offset
+}
+
+pub fn set_stats_utc_offset(stats_utc_offset: i32) {
+    update(|config| {
+        config.stats_utc_offset = stats_utc_offset;
+    });
+}
+
+pub fn get_daily_reset_hour() -> u32 {
+    get().daily_reset_hour
+}
+
+pub fn set_daily_reset_hour(daily_reset_hour: u32) {
+    update(|config| {
+        config.daily_reset_hour = daily_reset_hour;
+    });
+}
+
+pub fn get_friends() -> Vec<String> {
+    get().friends.clone()
+}
+
+pub fn set_friends(friends: Vec<String>) {
+    update(|config| {
+        config.friends = friends;
+    });
+}
+
+pub fn get_chat_cooldown() -> u64 {
+    get().chat_cooldown
+}
+
+pub fn set_chat_cooldown(chat_cooldown: u64) {
+    update(|config| {
+        config.chat_cooldown = chat_cooldown;
+    });
+}
+
+pub fn get_network() -> NetworkConfig {
+    get().network.clone()
+}
+
+pub fn get_map_click_confirmation() -> bool {
+    get().map_click_confirmation
+}
+
+pub fn set_map_click_confirmation(map_click_confirmation: bool) {
+    update(|config| {
+        config.map_click_confirmation = map_click_confirmation;
+    });
+}
+
+pub fn get_map_locked() -> bool {
+    get().map_locked
+}
+
+pub fn set_map_locked(map_locked: bool) {
+    update(|config| {
+        config.map_locked = map_locked;
+    });
+}
+
+pub fn get_farmable_item() -> u32 {
+    get().farmable_item
+}
+
+pub fn set_farmable_item(farmable_item: u32) {
+    update(|config| {
+        config.farmable_item = farmable_item;
+    });
+}
+
+pub fn get_world_summary_webhook() -> bool {
+    get().world_summary_webhook
+}
+
+pub fn set_world_summary_webhook(world_summary_webhook: bool) {
+    update(|config| {
+        config.world_summary_webhook = world_summary_webhook;
+    });
+}
+
+pub fn get_idle_heartbeat() -> u64 {
+    get().idle_heartbeat
+}
+
+pub fn set_idle_heartbeat(idle_heartbeat: u64) {
+    update(|config| {
+        config.idle_heartbeat = idle_heartbeat;
+    });
+}
+
+pub fn get_transfer_webhook() -> bool {
+    get().transfer_webhook
+}
+
+pub fn set_transfer_webhook(transfer_webhook: bool) {
+    update(|config| {
+        config.transfer_webhook = transfer_webhook;
+    });
+}
+
+pub fn get_auto_fix_login_info() -> bool {
+    get().auto_fix_login_info
+}
+
+pub fn set_auto_fix_login_info(auto_fix_login_info: bool) {
+    update(|config| {
+        config.auto_fix_login_info = auto_fix_login_info;
+    });
+}
+
+pub fn get_bot_filter_text() -> String {
+    get().bot_filter_text.clone()
+}
+
+pub fn set_bot_filter_text(bot_filter_text: String) {
+    update(|config| {
+        config.bot_filter_text = bot_filter_text;
+    });
+}
+
+pub fn get_bot_filter() -> EBotFilter {
+    get().bot_filter.clone()
+}
+
+pub fn set_bot_filter(bot_filter: EBotFilter) {
+    update(|config| {
+        config.bot_filter = bot_filter;
+    });
+}
+
+pub fn get_inventory_free_threshold() -> u32 {
+    get().inventory_free_threshold
+}
+
+pub fn set_inventory_free_threshold(inventory_free_threshold: u32) {
+    update(|config| {
+        config.inventory_free_threshold = inventory_free_threshold;
+    });
+}
+
+pub fn get_trusted_scripts() -> bool {
+    get().trusted_scripts
+}
+
+pub fn set_trusted_scripts(trusted_scripts: bool) {
+    update(|config| {
+        config.trusted_scripts = trusted_scripts;
+    });
+}
+
+pub fn get_script_memory_limit() -> u32 {
+    get().script_memory_limit
+}
+
+pub fn set_script_memory_limit(script_memory_limit: u32) {
+    update(|config| {
+        config.script_memory_limit = script_memory_limit;
+    });
+}
+
+pub fn get_script_cpu_budget() -> u64 {
+    get().script_cpu_budget
+}
+
+pub fn set_script_cpu_budget(script_cpu_budget: u64) {
+    update(|config| {
+        config.script_cpu_budget = script_cpu_budget;
+    });
+}
+
+pub fn get_routes() -> Vec<Route> {
+    get().routes.clone()
+}
+
+pub fn get_route(name: &str) -> Option<Route> {
+    get().routes.iter().find(|route| route.name == name).cloned()
+}
+
+// Replaces a route with the same name
+pub fn save_route(route: Route) {
+    update(|config| {
+        config.routes.retain(|existing| existing.name != route.name);
+        config.routes.push(route);
+    });
+}
+
+pub fn remove_route(name: &str) {
+    update(|config| {
+        config.routes.retain(|route| route.name != name);
+    });
+}
+
+pub fn get_collect_threshold_wl() -> f32 {
+    get().collect_threshold_wl
+}
+
+pub fn set_collect_threshold_wl(collect_threshold_wl: f32) {
+    update(|config| {
+        config.collect_threshold_wl = collect_threshold_wl;
+    });
+}
+
+pub fn get_debug_logs() -> bool {
+    get().debug_logs
+}
+
+pub fn set_debug_logs(debug_logs: bool) {
+    update(|config| {
+        config.debug_logs = debug_logs;
+    });
+}
+
+pub fn get_watch_worlds() -> Vec<String> {
+    get().watch_worlds.clone()
+}
+
+pub fn set_watch_worlds(watch_worlds: Vec<String>) {
+    update(|config| {
+        config.watch_worlds = watch_worlds;
+    });
+}
+
+pub fn get_watch_interval() -> u64 {
+    get().watch_interval
+}
+
+pub fn set_watch_interval(watch_interval: u64) {
+    update(|config| {
+        config.watch_interval = watch_interval;
+    });
+}
+
+pub fn get_path_tolerance() -> u32 {
+    get().path_tolerance
+}
+
+pub fn set_path_tolerance(path_tolerance: u32) {
+    update(|config| {
+        config.path_tolerance = path_tolerance;
+    });
+}
+
+pub fn get_session_max_age() -> u64 {
+    get().session_max_age
+}
+
+pub fn set_session_max_age(session_max_age: u64) {
+    update(|config| {
+        config.session_max_age = session_max_age;
+    });
+}
+
+pub fn get_do_not_disturb() -> bool {
+    get().do_not_disturb
+}
+
+pub fn set_do_not_disturb(do_not_disturb: bool) {
+    update(|config| {
+        config.do_not_disturb = do_not_disturb;
+    });
+}
+
+pub fn get_world_passwords() -> Vec<WorldPassword> {
+    get().world_passwords.clone()
+}
+
+pub fn set_world_passwords(world_passwords: Vec<WorldPassword>) {
+    update(|config| {
+        config.world_passwords = world_passwords;
+    });
+}
+
+// World names are matched case-insensitively, the game shows them uppercased
+pub fn get_world_password(world: &str) -> Option<String> {
+    get()
+        .world_passwords
+        .iter()
+        .find(|entry| entry.world.eq_ignore_ascii_case(world))
+        .map(|entry| entry.password.clone())
+}
+
+pub fn set_world_password(world: &str, password: String) {
+    update(|config| {
+        config
+            .world_passwords
+            .retain(|entry| !entry.world.eq_ignore_ascii_case(world));
+        config.world_passwords.push(WorldPassword {
+            world: world.to_uppercase(),
+            password,
+        });
+    });
+}
+
+pub fn get_proxy_pools() -> Vec<ProxyPool> {
+    get().proxy_pools.clone()
+}
+
+pub fn set_proxy_pools(proxy_pools: Vec<ProxyPool>) {
+    update(|config| {
+        config.proxy_pools = proxy_pools;
+    });
+}
+
+pub fn get_never_connect_direct() -> bool {
+    get().never_connect_direct
+}
+
+pub fn set_never_connect_direct(never_connect_direct: bool) {
+    update(|config| {
+        config.never_connect_direct = never_connect_direct;
+    });
+}
+
+pub fn get_walk_to_activate() -> bool {
+    get().walk_to_activate
+}
+
+pub fn set_walk_to_activate(walk_to_activate: bool) {
+    update(|config| {
+        config.walk_to_activate = walk_to_activate;
+    });
+}
+
+pub fn get_check_updates() -> bool {
+    get().check_updates
+}
+
+pub fn set_check_updates(check_updates: bool) {
+    update(|config| {
+        config.check_updates = check_updates;
+    });
+}
+
+pub fn get_login_windows() -> Vec<LoginWindow> {
+    get().login_windows.clone()
+}
+
+pub fn set_login_windows(login_windows: Vec<LoginWindow>) {
+    update(|config| {
+        config.login_windows = login_windows;
+    });
+}
+
+pub fn get_lock_public() -> bool {
+    get().lock_public
+}
+
+pub fn set_lock_public(lock_public: bool) {
+    update(|config| {
+        config.lock_public = lock_public;
+    });
+}
+
+pub fn get_lock_access() -> Vec<String> {
+    get().lock_access.clone()
+}
+
+pub fn set_lock_access(lock_access: Vec<String>) {
+    update(|config| {
+        config.lock_access = lock_access;
+    });
+}
+
+pub fn get_auto_onboarding() -> bool {
+    get().auto_onboarding
+}
+
+pub fn set_auto_onboarding(auto_onboarding: bool) {
+    update(|config| {
+        config.auto_onboarding = auto_onboarding;
+    });
+}
+
+pub fn get_onboarding_age() -> u32 {
+    get().onboarding_age
+}
+
+pub fn set_onboarding_age(onboarding_age: u32) {
+    update(|config| {
+        config.onboarding_age = onboarding_age;
+    });
+}
+
+pub fn get_onboarding_consent() -> bool {
+    get().onboarding_consent
+}
+
+pub fn set_onboarding_consent(onboarding_consent: bool) {
+    update(|config| {
+        config.onboarding_consent = onboarding_consent;
+    });
+}
+
+pub fn get_bandwidth_cap_mb() -> u32 {
+    get().bandwidth_cap_mb
+}
+
+pub fn set_bandwidth_cap_mb(bandwidth_cap_mb: u32) {
+    update(|config| {
+        config.bandwidth_cap_mb = bandwidth_cap_mb;
+    });
+}
+
+pub fn get_drop_despawn_secs() -> u64 {
+    get().drop_despawn_secs
+}
+
+pub fn set_drop_despawn_secs(drop_despawn_secs: u64) {
+    update(|config| {
+        config.drop_despawn_secs = drop_despawn_secs;
+    });
+}
+
+pub fn get_resync_warn_count() -> u32 {
+    get().resync_warn_count
+}
+
+pub fn set_resync_warn_count(resync_warn_count: u32) {
+    update(|config| {
+        config.resync_warn_count = resync_warn_count;
+    });
+}
+
+pub fn get_bot_templates() -> Vec<BotTemplate> {
+    get().bot_templates.clone()
+}
+
+pub fn set_bot_templates(bot_templates: Vec<BotTemplate>) {
+    update(|config| {
+        config.bot_templates = bot_templates;
+    });
+}
+
+pub fn get_script_dry_run() -> bool {
+    get().script_dry_run
+}
+
+pub fn set_script_dry_run(script_dry_run: bool) {
+    update(|config| {
+        config.script_dry_run = script_dry_run;
+    });
+}
+
+pub fn set_bot_protected_items(username: &str, protected_items: Vec<u32>) {
+    update_bot_config(username, |bot_config| {
+        bot_config.protected_items = Some(protected_items);
+    });
+}
+
+pub fn get_movement_calibration() -> bool {
+    get().movement_calibration
+}
+
+pub fn set_movement_calibration(movement_calibration: bool) {
+    update(|config| {
+        config.movement_calibration = movement_calibration;
+    });
+}
+
+pub fn get_server_ip() -> String {
+    get().server_ip.clone()
+}
+
+pub fn set_server_ip(server_ip: String) {
+    update(|config| {
+        config.server_ip = server_ip;
+    });
+}
+
+pub fn get_server_port() -> u16 {
+    get().server_port
+}
+
+pub fn set_server_port(server_port: u16) {
+    update(|config| {
+        config.server_port = server_port;
+    });
+}
+
+pub fn get_server_data_fallback_attempts() -> u32 {
+    get().server_data_fallback_attempts
+}
+
+pub fn set_server_data_fallback_attempts(server_data_fallback_attempts: u32) {
+    update(|config| {
+        config.server_data_fallback_attempts = server_data_fallback_attempts;
+    });
+}
+
+pub fn get_server_data_max_age_hours() -> u32 {
+    get().server_data_max_age_hours
+}
+
+pub fn set_server_data_max_age_hours(server_data_max_age_hours: u32) {
+    update(|config| {
+        config.server_data_max_age_hours = server_data_max_age_hours;
+    });
+}
+
+pub fn set_bot_hotbar(username: &str, hotbar: Vec<u32>) {
+    update_bot_config(username, |bot_config| {
+        bot_config.hotbar = hotbar;
+    });
+}
+
+pub fn get_path_segment_max() -> u32 {
+    get().path_segment_max
+}
+
+pub fn set_path_segment_max(path_segment_max: u32) {
+    update(|config| {
+        config.path_segment_max = path_segment_max;
+    });
+}
+
+pub fn get_humanizer_distribution() -> EDelayDistribution {
+    get().humanizer_distribution
+}
+
+pub fn set_humanizer_distribution(humanizer_distribution: EDelayDistribution) {
+    update(|config| {
+        config.humanizer_distribution = humanizer_distribution;
+    });
+}
+
+pub fn get_packet_filter_presets() -> Vec<PacketFilterPreset> {
+    get().packet_filter_presets.clone()
+}
+
+pub fn set_packet_filter_presets(packet_filter_presets: Vec<PacketFilterPreset>) {
+    update(|config| {
+        config.packet_filter_presets = packet_filter_presets;
+    });
+}
+
+pub fn get_build_patterns() -> Vec<BuildPattern> {
+    get().build_patterns.clone()
+}
+
+pub fn set_build_patterns(build_patterns: Vec<BuildPattern>) {
+    update(|config| {
+        config.build_patterns = build_patterns;
+    });
+}
+
+pub fn get_value_sync_url() -> String {
+    get().value_sync_url.clone()
+}
+
+pub fn set_value_sync_url(value_sync_url: String) {
+    update(|config| {
+        config.value_sync_url = value_sync_url;
+    });
+}
+
+pub fn get_spawn_spot(world: &str) -> Option<(u32, u32)> {
+    get()
+        .spawn_spots
+        .iter()
+        .find(|spot| spot.world.eq_ignore_ascii_case(world))
+        .map(|spot| (spot.x, spot.y))
+}
+
+// None forgets the world's spot, the door walk-off applies there again
+pub fn set_spawn_spot(world: &str, spot: Option<(u32, u32)>) {
+    update(|config| {
+        config
+            .spawn_spots
+            .retain(|entry| !entry.world.eq_ignore_ascii_case(world));
+        if let Some((x, y)) = spot {
+            config.spawn_spots.push(SpawnSpot {
+                world: world.to_uppercase(),
+                x,
+                y,
+            });
+        }
+    });
 }
@@ -0,0 +1,30 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use crate::types::session_stats::DailyCounters;
+
+const STATS_DIR: &str = "stats";
+
+fn path(bot_name: &str) -> PathBuf {
+    PathBuf::from(STATS_DIR).join(format!("{}.json", bot_name.to_lowercase()))
+}
+
+pub fn load(bot_name: &str) -> BTreeMap<String, DailyCounters> {
+    fs::read_to_string(path(bot_name))
+        .ok()
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+// Same temp-then-rename approach as the config so a crash mid-write keeps the old history
+pub fn save(bot_name: &str, daily: &BTreeMap<String, DailyCounters>) -> io::Result<()> {
+    fs::create_dir_all(STATS_DIR)?;
+    let path = path(bot_name);
+    let temp_path = path.with_extension("json.tmp");
+    let contents = serde_json::to_string_pretty(daily)
+        .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
+    fs::write(&temp_path, contents)?;
+    fs::rename(&temp_path, &path)
+}
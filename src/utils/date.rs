@@ -0,0 +1,34 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+// Days since the epoch in the given timezone, a day starts at `reset_hour` local time
+pub fn day_index(unix_secs: u64, utc_offset_hours: i32, reset_hour: u32) -> i64 {
+    let local = unix_secs as i64 + utc_offset_hours as i64 * 3600 - reset_hour as i64 * 3600;
+    local.div_euclid(86400)
+}
+
+// Formats a day index as YYYY-MM-DD, see http://howardhinnant.github.io/date_algorithms.html
+pub fn format_day(day_index: i64) -> String {
+    let z = day_index + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+// Formats a unix timestamp as HH:MM:SS in the given timezone
+pub fn format_time(unix_secs: u64, utc_offset_hours: i32) -> String {
+    let local = (unix_secs as i64 + utc_offset_hours as i64 * 3600).rem_euclid(86400);
+    format!("{:02}:{:02}:{:02}", local / 3600, local / 60 % 60, local % 60)
+}
@@ -0,0 +1,48 @@
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+// A JSON file that is written some time after the first change instead of on every one,
+// through a temp file so a crash mid-write never leaves half a file behind
+pub struct DebouncedFile {
+    path: PathBuf,
+    delay: Duration,
+    dirty_since: Option<Instant>,
+}
+
+impl DebouncedFile {
+    pub fn new(path: PathBuf, delay: Duration) -> Self {
+        DebouncedFile {
+            path,
+            delay,
+            dirty_since: None,
+        }
+    }
+
+    pub fn mark_dirty(&mut self) {
+        if self.dirty_since.is_none() {
+            self.dirty_since = Some(Instant::now());
+        }
+    }
+
+    pub fn is_due(&self) -> bool {
+        self.dirty_since.is_some_and(|since| since.elapsed() >= self.delay)
+    }
+
+    // `contents` is only serialized when there is something to write
+    pub fn flush(&mut self, contents: impl FnOnce() -> serde_json::Result<String>) -> io::Result<()> {
+        if self.dirty_since.is_none() {
+            return Ok(());
+        }
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let temp_path = self.path.with_extension("json.tmp");
+        let contents = contents().map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
+        fs::write(&temp_path, contents)?;
+        fs::rename(&temp_path, &self.path)?;
+        self.dirty_since = None;
+        Ok(())
+    }
+}
@@ -6,6 +6,8 @@ pub enum CustomError {
     NetworkError(#[from] ureq::Error),
     #[error("Steam initialization error: {0}")]
     SteamError(String),
+    #[error("Invalid network settings: {0}")]
+    NetworkConfig(String),
     #[error("Other error: {0}")]
     Other(String),
 }
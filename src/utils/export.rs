@@ -0,0 +1,133 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+use serde_json::json;
+
+use super::textparse;
+use super::logging::LogEntry;
+use crate::manager::account_check::AccountCheck;
+
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum ExportFormat {
+    #[default]
+    JsonLines,
+    Csv,
+}
+
+pub fn write_logs(
+    path: &str,
+    format: ExportFormat,
+    bot_name: &str,
+    session_start: u64,
+    logs: &[LogEntry],
+) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    match format {
+        ExportFormat::JsonLines => {
+            write_json_header(&mut writer, bot_name, session_start)?;
+            for entry in logs {
+                let line = json!({
+                    "timestamp": entry.timestamp,
+                    "level": entry.level,
+                    "message": textparse::strip_color(&entry.message),
+                });
+                writeln!(writer, "{}", line)?;
+            }
+        }
+        ExportFormat::Csv => {
+            write_csv_header(&mut writer, bot_name, session_start)?;
+            writeln!(writer, "timestamp,level,message")?;
+            for entry in logs {
+                writeln!(
+                    writer,
+                    "{},{},{}",
+                    entry.timestamp,
+                    csv_field(&entry.level),
+                    csv_field(&textparse::strip_color(&entry.message))
+                )?;
+            }
+        }
+    }
+    writer.flush()
+}
+
+pub fn write_stats(
+    path: &str,
+    format: ExportFormat,
+    bot_name: &str,
+    session_start: u64,
+    counters: &[(String, u64)],
+) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    match format {
+        ExportFormat::JsonLines => {
+            write_json_header(&mut writer, bot_name, session_start)?;
+            for (name, value) in counters {
+                writeln!(writer, "{}", json!({ "counter": name, "value": value }))?;
+            }
+        }
+        ExportFormat::Csv => {
+            write_csv_header(&mut writer, bot_name, session_start)?;
+            writeln!(writer, "counter,value")?;
+            for (name, value) in counters {
+                writeln!(writer, "{},{}", csv_field(name), value)?;
+            }
+        }
+    }
+    writer.flush()
+}
+
+pub fn write_census(path: &str, census: &[(u32, String, u32, u32)]) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    writeln!(writer, "id,name,foreground,background")?;
+    for (id, name, foreground, background) in census {
+        writeln!(writer, "{},{},{},{}", id, csv_field(name), foreground, background)?;
+    }
+    writer.flush()
+}
+
+pub fn write_account_checks(path: &str, checks: &[AccountCheck]) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    writeln!(writer, "account,method,status,detail")?;
+    for check in checks {
+        writeln!(
+            writer,
+            "{},{:?},{},{}",
+            csv_field(&check.name),
+            check.method,
+            csv_field(check.status.name()),
+            csv_field(&check.detail)
+        )?;
+    }
+    writer.flush()
+}
+
+pub fn write_world_diff(path: &str, title: &str, lines: &[String]) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    writeln!(writer, "{}", title)?;
+    for line in lines {
+        writeln!(writer, "{}", line)?;
+    }
+    writer.flush()
+}
+
+fn write_json_header<W: Write>(writer: &mut W, bot_name: &str, session_start: u64) -> io::Result<()> {
+    writeln!(
+        writer,
+        "{}",
+        json!({ "bot": bot_name, "session_start": session_start })
+    )
+}
+
+fn write_csv_header<W: Write>(writer: &mut W, bot_name: &str, session_start: u64) -> io::Result<()> {
+    writeln!(writer, "bot,session_start")?;
+    writeln!(writer, "{},{}", csv_field(bot_name), session_start)
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains(',') || value.contains('"') || value.contains('\n') {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
@@ -0,0 +1,155 @@
+// Growtopia color codes: a backtick and one character switches the color, a double
+// backtick goes back to the color before it. Codes nest, so the previous colors are kept
+// on a stack
+
+// RGB of every known code, unknown codes are dropped without changing the color
+const COLORS: [(char, [u8; 3]); 27] = [
+    ('0', [255, 255, 255]),
+    ('1', [173, 244, 255]),
+    ('2', [73, 252, 0]),
+    ('3', [191, 218, 255]),
+    ('4', [255, 39, 29]),
+    ('5', [235, 183, 255]),
+    ('6', [255, 202, 111]),
+    ('7', [230, 230, 230]),
+    ('8', [255, 148, 69]),
+    ('9', [255, 238, 125]),
+    ('!', [209, 255, 249]),
+    ('@', [255, 205, 201]),
+    ('#', [255, 143, 243]),
+    ('$', [255, 252, 197]),
+    ('^', [181, 255, 151]),
+    ('&', [254, 235, 255]),
+    ('w', [255, 255, 255]),
+    ('o', [252, 230, 186]),
+    ('p', [255, 223, 241]),
+    ('b', [0, 0, 0]),
+    ('q', [12, 96, 164]),
+    ('e', [25, 185, 255]),
+    ('r', [111, 211, 87]),
+    ('t', [47, 131, 13]),
+    ('a', [81, 81, 81]),
+    ('s', [158, 158, 158]),
+    ('c', [80, 255, 255]),
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Span {
+    pub text: String,
+    // None for the surrounding text's color
+    pub color: Option<[u8; 3]>,
+}
+
+pub fn color(code: char) -> Option<[u8; 3]> {
+    COLORS
+        .iter()
+        .find(|(known, _)| *known == code)
+        .map(|(_, rgb)| *rgb)
+}
+
+// Splits `text` into runs of one color. A backtick at the very end is dropped
+pub fn parse(text: &str) -> Vec<Span> {
+    let mut spans = Vec::new();
+    let mut stack: Vec<Option<[u8; 3]>> = Vec::new();
+    let mut current = None;
+    let mut text_run = String::new();
+    let mut chars = text.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '`' {
+            text_run.push(c);
+            continue;
+        }
+        let next = match chars.next() {
+            Some(next) => next,
+            None => break,
+        };
+        let new_color = if next == '`' {
+            stack.pop().unwrap_or(None)
+        } else {
+            match color(next) {
+                Some(rgb) => {
+                    stack.push(current);
+                    Some(rgb)
+                }
+                None => continue,
+            }
+        };
+        if new_color != current && !text_run.is_empty() {
+            spans.push(Span {
+                text: std::mem::take(&mut text_run),
+                color: current,
+            });
+        }
+        current = new_color;
+    }
+    if !text_run.is_empty() {
+        spans.push(Span {
+            text: text_run,
+            color: current,
+        });
+    }
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::textparse;
+
+    #[test]
+    fn known_codes_map_to_their_colors() {
+        let table = [
+            ('0', [255, 255, 255]),
+            ('2', [73, 252, 0]),
+            ('4', [255, 39, 29]),
+            ('9', [255, 238, 125]),
+            ('$', [255, 252, 197]),
+            ('w', [255, 255, 255]),
+            ('o', [252, 230, 186]),
+            ('b', [0, 0, 0]),
+            ('e', [25, 185, 255]),
+            ('c', [80, 255, 255]),
+        ];
+        for (code, rgb) in table {
+            assert_eq!(color(code), Some(rgb), "`{}", code);
+            assert_eq!(parse(&format!("`{}text", code)), vec![Span { text: "text".to_string(), color: Some(rgb) }]);
+        }
+        for code in ['x', 'z', ' ', '`'] {
+            assert_eq!(color(code), None, "`{}", code);
+        }
+    }
+
+    #[test]
+    fn nested_codes_return_to_the_outer_color() {
+        let spans = parse("`2green `4red`` green`` plain");
+        let colors: Vec<_> = spans.iter().map(|span| (span.text.as_str(), span.color)).collect();
+        assert_eq!(
+            colors,
+            [
+                ("green ", color('2')),
+                ("red", color('4')),
+                (" green", color('2')),
+                (" plain", None),
+            ]
+        );
+    }
+
+    #[test]
+    fn round_trips_to_the_stripped_text() {
+        let lines = [
+            "plain text",
+            "`oYour luck has improved!`` (`$Lucky!`` mod added, `$30 mins`` left)",
+            "`2nested `4deeper `9deepest`````` out",
+            "unterminated `",
+            "`xunknown code",
+            "too many closes`````` here",
+            "",
+        ];
+        for line in lines {
+            let joined: String = parse(line).into_iter().map(|span| span.text).collect();
+            assert_eq!(joined, textparse::strip_color(line), "{:?}", line);
+            assert!(!joined.contains('`'), "{:?}", line);
+        }
+    }
+}
@@ -1,17 +1,56 @@
-use std::sync::mpsc::Sender;
+use super::{repaint, textparse};
 use paris::{error, info, warn};
+use std::sync::mpsc::Sender;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Clone)]
+pub struct LogEntry {
+    pub timestamp: u64,
+    pub level: String,
+    pub message: String,
+}
+
+impl LogEntry {
+    pub fn from_message(message: &str) -> Self {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let mut parts = message.splitn(2, '|');
+        match (parts.next(), parts.next()) {
+            (Some(level), Some(message)) => LogEntry {
+                timestamp,
+                level: level.to_string(),
+                message: message.to_string(),
+            },
+            _ => LogEntry {
+                timestamp,
+                level: "info".to_string(),
+                message: message.to_string(),
+            },
+        }
+    }
+}
 
 pub fn info(message: &str, sender: &Sender<String>) {
-    info!("{}", message);
+    info!("{}", textparse::strip_color(message));
     sender.send(format!("info|{}", message)).unwrap();
+    repaint::request();
+}
+
+pub fn debug(message: &str, sender: &Sender<String>) {
+    sender.send(format!("debug|{}", message)).unwrap();
+    repaint::request();
 }
 
 pub fn warn(message: &str, sender: &Sender<String>) {
-    warn!("{}", message);
+    warn!("{}", textparse::strip_color(message));
     sender.send(format!("warn|{}", message)).unwrap();
+    repaint::request();
 }
 
 pub fn error(message: &str, sender: &Sender<String>) {
-    error!("{}", message);
+    error!("{}", textparse::strip_color(message));
     sender.send(format!("error|{}", message)).unwrap();
-}
\ No newline at end of file
+    repaint::request();
+}
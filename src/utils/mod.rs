@@ -1,10 +1,23 @@
 pub mod captcha;
 pub mod color;
 pub mod config;
+pub mod daily_stats;
+pub mod date;
+pub mod debounced_file;
 pub mod error;
+pub mod export;
+pub mod gt_text;
 pub mod logging;
+pub mod net;
 pub mod proton;
 pub mod random;
+pub mod repaint;
 pub mod safe_check;
+pub mod seal;
+pub mod secrets;
+pub mod setup_check;
+pub mod steam_totp;
 pub mod textparse;
 pub mod variant;
+pub mod watch;
+pub mod webhook;
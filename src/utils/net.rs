@@ -0,0 +1,28 @@
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, ToSocketAddrs};
+
+// Accepts an empty string (any IPv4 interface), a bare IPv4/IPv6 address or a full socket address
+pub fn parse_bind_address(input: &str) -> Result<SocketAddr, String> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Ok(SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 0));
+    }
+    if let Ok(ip) = input.trim_matches(|c| c == '[' || c == ']').parse::<IpAddr>() {
+        return Ok(SocketAddr::new(ip, 0));
+    }
+    input
+        .parse::<SocketAddr>()
+        .map_err(|_| format!("invalid bind address '{}'", input))
+}
+
+// Resolves a host (IPv4/IPv6 literal or hostname) and prefers an address the local socket can reach
+pub fn resolve(host: &str, port: u16, local: &SocketAddr) -> io::Result<SocketAddr> {
+    let host = host.trim_matches(|c| c == '[' || c == ']');
+    let addresses: Vec<SocketAddr> = (host, port).to_socket_addrs()?.collect();
+    addresses
+        .iter()
+        .find(|address| address.is_ipv6() == local.is_ipv6())
+        .or_else(|| addresses.first())
+        .copied()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("{} did not resolve", host)))
+}
@@ -63,10 +63,13 @@ pub fn hash_file(file_name: &str) -> io::Result<u32> {
     let mut buffer = Vec::new();
     file.read_to_end(&mut buffer)?;
 
+    Ok(hash_bytes(&buffer))
+}
+
+pub fn hash_bytes(data: &[u8]) -> u32 {
     let mut hash: u32 = 0x55555555;
-    for byte in buffer {
-        hash = (hash >> 27) + (hash << 5) + (byte as u32);
+    for byte in data {
+        hash = (hash >> 27) + (hash << 5) + (*byte as u32);
     }
-
-    Ok(hash)
+    hash
 }
@@ -1,4 +1,6 @@
-use rand::Rng;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::f64::consts::PI;
 
 pub fn hex(length: u32, upper: bool) -> String {
     let chars = if upper {
@@ -22,3 +24,34 @@ pub fn mac_address() -> String {
     }
     mac
 }
+
+pub fn range(min: u64, max: u64) -> u64 {
+    let mut rng = rand::thread_rng();
+    rng.gen_range(min..=max)
+}
+
+pub fn seeded(seed: Option<u64>) -> StdRng {
+    match seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    }
+}
+
+pub fn jitter(rng: &mut StdRng, mean: u64, jitter: u64) -> u64 {
+    rng.gen_range(mean.saturating_sub(jitter)..=mean + jitter)
+}
+
+// Normal around `mean` with half of `jitter` as the standard deviation, clamped to the
+// same range `jitter` draws from
+pub fn normal(rng: &mut StdRng, mean: u64, jitter: u64) -> u64 {
+    // Box-Muller, u1 can't be 0 or its logarithm is infinite
+    let u1: f64 = rng.gen_range(f64::EPSILON..1.0);
+    let u2: f64 = rng.gen();
+    let z = (-2.0 * u1.ln()).sqrt() * (2.0 * PI * u2).cos();
+    let value = mean as f64 + z * jitter as f64 / 2.0;
+    value.round().clamp(mean.saturating_sub(jitter) as f64, (mean + jitter) as f64) as u64
+}
+
+pub fn chance(rng: &mut StdRng, probability: f64) -> bool {
+    rng.gen_bool(probability.clamp(0.0, 1.0))
+}
@@ -0,0 +1,19 @@
+use eframe::egui;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+// A burst of changes from many bots still only costs one frame per this
+const COALESCE: Duration = Duration::from_millis(50);
+
+static CONTEXT: OnceLock<egui::Context> = OnceLock::new();
+
+pub fn init(ctx: &egui::Context) {
+    let _ = CONTEXT.set(ctx.clone());
+}
+
+// Marks the GUI dirty from any thread, it redraws once within COALESCE however often this is called
+pub fn request() {
+    if let Some(ctx) = CONTEXT.get() {
+        ctx.request_repaint_after(COALESCE);
+    }
+}
@@ -0,0 +1,158 @@
+use argon2::Argon2;
+use base64::engine::general_purpose;
+use base64::Engine;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use rand::RngCore;
+
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+const TAG_LEN: usize = 16;
+
+// Passphrase protection for secrets written to disk. Argon2id turns the passphrase and a
+// random salt into an XChaCha20-Poly1305 key, whose tag catches a wrong passphrase or a
+// modified file. Output is base64 of salt | nonce | ciphertext
+pub fn seal(passphrase: &str, plaintext: &[u8]) -> String {
+    let salt = new_salt();
+    let key = SealKey::derive(passphrase, &salt);
+    let mut out = salt.to_vec();
+    out.extend(key.encrypt(plaintext));
+    general_purpose::STANDARD.encode(out)
+}
+
+// Fails on a wrong passphrase, a tampered file or anything that isn't `seal` output
+pub fn open(passphrase: &str, sealed: &str) -> Result<Vec<u8>, String> {
+    let data = decode(sealed)?;
+    if data.len() < SALT_LEN + NONCE_LEN + TAG_LEN {
+        return Err("sealed data is truncated".to_string());
+    }
+    let (salt, rest) = data.split_at(SALT_LEN);
+    SealKey::derive(passphrase, salt).decrypt(rest)
+}
+
+// Key derived once from a passphrase and a stored salt, for sealing many small values
+// without paying for Argon2 on each one. Every value gets its own random nonce
+#[derive(Clone)]
+pub struct SealKey {
+    cipher: XChaCha20Poly1305,
+}
+
+impl SealKey {
+    pub fn derive(passphrase: &str, salt: &[u8]) -> Self {
+        let mut key = [0u8; 32];
+        Argon2::default()
+            .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+            .expect("salt and key lengths are within Argon2's limits");
+        SealKey {
+            cipher: XChaCha20Poly1305::new(&key.into()),
+        }
+    }
+
+    // nonce | ciphertext with the tag appended
+    fn encrypt(&self, plaintext: &[u8]) -> Vec<u8> {
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, plaintext)
+            .expect("plaintext is far below the cipher's length limit");
+        let mut out = nonce.to_vec();
+        out.extend(ciphertext);
+        out
+    }
+
+    fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>, String> {
+        if data.len() < NONCE_LEN + TAG_LEN {
+            return Err("sealed data is truncated".to_string());
+        }
+        let (nonce, ciphertext) = data.split_at(NONCE_LEN);
+        self.cipher
+            .decrypt(XNonce::from_slice(nonce), ciphertext)
+            .map_err(|_| "wrong passphrase or the data was modified".to_string())
+    }
+}
+
+pub fn new_salt() -> [u8; SALT_LEN] {
+    let mut salt = [0u8; SALT_LEN];
+    rand::thread_rng().fill_bytes(&mut salt);
+    salt
+}
+
+// base64 of nonce | ciphertext
+pub fn seal_with(key: &SealKey, plaintext: &[u8]) -> String {
+    general_purpose::STANDARD.encode(key.encrypt(plaintext))
+}
+
+pub fn open_with(key: &SealKey, sealed: &str) -> Result<Vec<u8>, String> {
+    key.decrypt(&decode(sealed)?)
+}
+
+fn decode(sealed: &str) -> Result<Vec<u8>, String> {
+    general_purpose::STANDARD
+        .decode(sealed.trim())
+        .map_err(|err| format!("not valid base64: {}", err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let sealed = seal("hunter2", b"token|abc");
+        assert_eq!(open("hunter2", &sealed).unwrap(), b"token|abc");
+        // A fresh salt and nonce every time
+        assert_ne!(seal("hunter2", b"token|abc"), sealed);
+    }
+
+    #[test]
+    fn wrong_passphrase_fails() {
+        let sealed = seal("hunter2", b"token|abc");
+        assert!(open("hunter3", &sealed).is_err());
+    }
+
+    #[test]
+    fn tampered_data_fails() {
+        let mut data = general_purpose::STANDARD.decode(seal("hunter2", b"token|abc")).unwrap();
+        for index in [0, SALT_LEN, SALT_LEN + NONCE_LEN, data.len() - 1] {
+            data[index] ^= 1;
+            assert!(open("hunter2", &general_purpose::STANDARD.encode(&data)).is_err(), "byte {}", index);
+            data[index] ^= 1;
+        }
+        assert!(open("hunter2", &general_purpose::STANDARD.encode(&data)).is_ok());
+    }
+
+    #[test]
+    fn truncated_data_fails() {
+        let data = general_purpose::STANDARD.decode(seal("hunter2", b"")).unwrap();
+        let short = general_purpose::STANDARD.encode(&data[..data.len() - 1]);
+        assert_eq!(open("hunter2", &short), Err("sealed data is truncated".to_string()));
+        assert!(open("hunter2", "not base64!").is_err());
+    }
+
+    #[test]
+    fn round_trip_with_a_derived_key() {
+        let salt = new_salt();
+        let key = SealKey::derive("hunter2", &salt);
+        let sealed = seal_with(&key, b"token|abc");
+        assert_eq!(open_with(&key, &sealed).unwrap(), b"token|abc");
+        // The same key derived again opens it, a fresh nonce every time
+        assert_eq!(open_with(&SealKey::derive("hunter2", &salt), &sealed).unwrap(), b"token|abc");
+        assert_ne!(seal_with(&key, b"token|abc"), sealed);
+        assert!(open_with(&SealKey::derive("hunter3", &salt), &sealed).is_err());
+        assert!(open_with(&SealKey::derive("hunter2", &new_salt()), &sealed).is_err());
+    }
+
+    #[test]
+    fn tampered_data_with_a_derived_key_fails() {
+        let key = SealKey::derive("hunter2", &new_salt());
+        let mut data = general_purpose::STANDARD.decode(seal_with(&key, b"token|abc")).unwrap();
+        for index in [0, NONCE_LEN - 1, NONCE_LEN, data.len() - 1] {
+            data[index] ^= 1;
+            assert!(open_with(&key, &general_purpose::STANDARD.encode(&data)).is_err(), "byte {}", index);
+            data[index] ^= 1;
+        }
+        assert_eq!(open_with(&key, &general_purpose::STANDARD.encode(&data)).unwrap(), b"token|abc");
+        let short = general_purpose::STANDARD.encode(&data[..NONCE_LEN + TAG_LEN - 1]);
+        assert_eq!(open_with(&key, &short), Err("sealed data is truncated".to_string()));
+    }
+}
@@ -0,0 +1,252 @@
+use super::config;
+use super::seal::{self, SealKey};
+use crate::types::config::{Config, ConfigEncryption};
+use base64::engine::general_purpose;
+use base64::Engine;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::RwLock;
+
+// Unlocks an encrypted config without the unlock screen, for headless runs
+pub const PASSPHRASE_ENV: &str = "MORI_CONFIG_PASSPHRASE";
+// Marks a sealed value so plaintext left in an encrypted config still loads
+const PREFIX: &str = "sealed:";
+const CHECK: &[u8] = b"mori";
+
+// Derived once on unlock or when encryption is turned on, never written anywhere
+static KEY: RwLock<Option<SealKey>> = RwLock::new(None);
+// The user went on without the passphrase, the unlock screen isn't shown again
+static READ_ONLY: AtomicBool = AtomicBool::new(false);
+
+// The config on disk is encrypted and neither a passphrase nor read-only mode was chosen yet
+pub fn needs_unlock() -> bool {
+    !has_key() && !READ_ONLY.load(Ordering::Acquire) && header_on_disk().is_some()
+}
+
+// Secrets are sealed and the key is missing, bots can't log in and nothing is saved
+pub fn is_locked() -> bool {
+    !has_key() && config::get().encryption.is_some()
+}
+
+pub fn is_enabled() -> bool {
+    config::get().encryption.is_some()
+}
+
+pub fn unlock(passphrase: &str) -> Result<(), String> {
+    let header = header_on_disk().ok_or("The config isn't encrypted")?;
+    let salt = general_purpose::STANDARD
+        .decode(&header.salt)
+        .map_err(|err| format!("The config's salt is broken: {}", err))?;
+    let key = SealKey::derive(passphrase, &salt);
+    match seal::open_with(&key, &header.check) {
+        Ok(check) if check == CHECK => {}
+        _ => return Err("Wrong passphrase".to_string()),
+    }
+    *KEY.write().unwrap() = Some(key);
+    READ_ONLY.store(false, Ordering::Release);
+    // The cached config still holds the sealed values
+    config::reload()
+}
+
+pub fn continue_read_only() {
+    READ_ONLY.store(true, Ordering::Release);
+}
+
+// Seals the secrets with a new passphrase on this save. The backup and a config set aside
+// as broken still hold them in plaintext, so they go too
+pub fn enable(passphrase: &str) {
+    let salt = seal::new_salt();
+    let key = SealKey::derive(passphrase, &salt);
+    let header = ConfigEncryption {
+        salt: general_purpose::STANDARD.encode(salt),
+        check: seal::seal_with(&key, CHECK),
+    };
+    *KEY.write().unwrap() = Some(key);
+    config::update(|config| config.encryption = Some(header));
+    config::remove_plaintext_copies();
+}
+
+// Writes the secrets back in plaintext, only possible while unlocked
+pub fn disable() -> Result<(), String> {
+    if is_locked() {
+        return Err("Unlock the config first".to_string());
+    }
+    config::update(|config| config.encryption = None);
+    *KEY.write().unwrap() = None;
+    Ok(())
+}
+
+// The copy written to disk. Fails while locked, saving would replace the sealed secrets
+// with whatever is in memory
+pub fn seal_config(config: &Config) -> Result<Config, String> {
+    let mut sealed = config.clone();
+    if sealed.encryption.is_none() {
+        return Ok(sealed);
+    }
+    let key = KEY.read().unwrap();
+    let key = key
+        .as_ref()
+        .ok_or("The config is locked, restart Mori and unlock it to save changes")?;
+    seal_secrets(&mut sealed, key)?;
+    Ok(sealed)
+}
+
+// Opens the secrets of a config read from disk in place. Without the key they stay sealed
+pub fn open_config(config: &mut Config) -> Result<(), String> {
+    if config.encryption.is_none() {
+        return Ok(());
+    }
+    let key = KEY.read().unwrap();
+    match key.as_ref() {
+        Some(key) => open_secrets(config, key),
+        None => Ok(()),
+    }
+}
+
+fn seal_secrets(config: &mut Config, key: &SealKey) -> Result<(), String> {
+    for_each_secret(config, |value| {
+        if !value.is_empty() && !value.starts_with(PREFIX) {
+            *value = format!("{}{}", PREFIX, seal::seal_with(key, value.as_bytes()));
+        }
+        Ok(())
+    })
+}
+
+fn open_secrets(config: &mut Config, key: &SealKey) -> Result<(), String> {
+    for_each_secret(config, |value| {
+        if let Some(sealed) = value.strip_prefix(PREFIX) {
+            let plaintext = seal::open_with(key, sealed)?;
+            *value = String::from_utf8(plaintext).map_err(|err| err.to_string())?;
+        }
+        Ok(())
+    })
+}
+
+// Passwords in the payload (everything after the username), recovery codes, tokens, Steam
+// secrets, proxy credentials, world passwords and the captcha API key. Usernames and world names stay readable
+// so a locked config still lists them
+fn for_each_secret<F>(config: &mut Config, mut f: F) -> Result<(), String>
+where
+    F: FnMut(&mut String) -> Result<(), String>,
+{
+    for bot in config.bots.iter_mut() {
+        if let Some((username, rest)) = bot.payload.split_once('|') {
+            let (username, mut rest) = (username.to_string(), rest.to_string());
+            f(&mut rest)?;
+            bot.payload = format!("{}|{}", username, rest);
+        }
+        f(&mut bot.recovery_code)?;
+        f(&mut bot.token)?;
+        f(&mut bot.steam_shared_secret)?;
+    }
+    for proxy in config.proxy.iter_mut() {
+        f(&mut proxy.username)?;
+        f(&mut proxy.password)?;
+    }
+    for entry in config.world_passwords.iter_mut() {
+        f(&mut entry.password)?;
+    }
+    f(&mut config.captcha.api_key)?;
+    Ok(())
+}
+
+// Checked before touching the config, loading it takes the key lock again
+fn has_key() -> bool {
+    KEY.read().unwrap().is_some()
+}
+
+fn header_on_disk() -> Option<ConfigEncryption> {
+    config::read_config().ok()?.encryption
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::config::{BotConfig, Proxy, WorldPassword};
+
+    fn config() -> Config {
+        let mut config = Config {
+            bots: vec![BotConfig {
+                payload: "alice|hunter2".to_string(),
+                recovery_code: "recovery".to_string(),
+                token: "token".to_string(),
+                steam_shared_secret: "steam".to_string(),
+                ..Default::default()
+            }],
+            proxy: vec![Proxy {
+                ip: "127.0.0.1".to_string(),
+                port: 1080,
+                username: "proxy-user".to_string(),
+                password: "proxy-pass".to_string(),
+                pool: String::new(),
+            }],
+            world_passwords: vec![WorldPassword {
+                world: "VAULT".to_string(),
+                password: "door".to_string(),
+            }],
+            ..Default::default()
+        };
+        config.captcha.api_key = "captcha-key".to_string();
+        config
+    }
+
+    fn secrets(config: &mut Config) -> Vec<String> {
+        let mut values = Vec::new();
+        for_each_secret(config, |value| {
+            values.push(value.clone());
+            Ok(())
+        })
+        .unwrap();
+        values
+    }
+
+    #[test]
+    fn seals_every_secret_and_opens_them_again() {
+        let key = SealKey::derive("passphrase", &seal::new_salt());
+        let mut sealed = config();
+        seal_secrets(&mut sealed, &key).unwrap();
+        let plain = secrets(&mut config());
+        assert_eq!(plain.len(), 8);
+        for (sealed, plain) in secrets(&mut sealed).iter().zip(&plain) {
+            assert!(sealed.starts_with(PREFIX), "{} left in plaintext", plain);
+        }
+        // Names stay readable so a locked config still lists them
+        assert!(sealed.bots[0].payload.starts_with("alice|"));
+        assert_eq!(sealed.world_passwords[0].world, "VAULT");
+
+        // Sealing twice doesn't wrap a value again
+        let once = secrets(&mut sealed);
+        seal_secrets(&mut sealed, &key).unwrap();
+        assert_eq!(secrets(&mut sealed), once);
+
+        open_secrets(&mut sealed, &key).unwrap();
+        assert_eq!(secrets(&mut sealed), plain);
+        assert_eq!(sealed.bots[0].payload, "alice|hunter2");
+    }
+
+    #[test]
+    fn plaintext_left_in_an_encrypted_config_still_opens() {
+        let key = SealKey::derive("passphrase", &seal::new_salt());
+        let mut config = config();
+        open_secrets(&mut config, &key).unwrap();
+        assert_eq!(secrets(&mut config), secrets(&mut self::config()));
+    }
+
+    #[test]
+    fn a_tampered_secret_or_wrong_key_fails() {
+        let salt = seal::new_salt();
+        let key = SealKey::derive("passphrase", &salt);
+        let mut sealed = config();
+        seal_secrets(&mut sealed, &key).unwrap();
+
+        let mut wrong_key = sealed.clone();
+        assert!(open_secrets(&mut wrong_key, &SealKey::derive("other", &salt)).is_err());
+
+        let mut tampered = sealed.clone();
+        let data = tampered.captcha.api_key.strip_prefix(PREFIX).unwrap();
+        let mut bytes = general_purpose::STANDARD.decode(data).unwrap();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 1;
+        tampered.captcha.api_key = format!("{}{}", PREFIX, general_purpose::STANDARD.encode(bytes));
+        assert!(open_secrets(&mut tampered, &key).is_err());
+    }
+}
@@ -0,0 +1,123 @@
+use crate::core::items::ITEMS_PATH;
+use crate::types::config::{Config, NetworkConfig};
+use crate::types::esetup_fix::ESetupFix;
+use crate::types::setup_problem::SetupProblem;
+use crate::utils::config;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+pub const TEXTURES_DIR: &str = "game";
+
+// Everything that has to be in place before the GUI loads, all problems at once rather
+// than the first one found
+pub fn run() -> Vec<SetupProblem> {
+    let mut problems = Vec::new();
+    check_config(&mut problems);
+    check_textures(&mut problems);
+    check_items(&mut problems);
+    problems
+}
+
+fn check_config(problems: &mut Vec<SetupProblem>) {
+    if let Err(err) = config::init() {
+        problems.push(SetupProblem::new(
+            format!("Can't create {}: {}", config::CONFIG_PATH, err),
+            "Run Mori from a folder it is allowed to write to",
+            Some(ESetupFix::OpenFolder(".".to_string())),
+        ));
+        return;
+    }
+    let config = match config::read_config() {
+        Ok(config) => config,
+        Err(err) => {
+            problems.push(SetupProblem::new(
+                format!("{} is malformed: {}", config::CONFIG_PATH, err),
+                "Fix the line mentioned, or start over. The broken file is kept as config.json.broken",
+                Some(ESetupFix::CreateConfig),
+            ));
+            return;
+        }
+    };
+    if let Err(err) = config.network.validate() {
+        problems.push(SetupProblem::new(
+            format!("Network settings are invalid: {}", err),
+            "Edit the network section of config.json or go back to the defaults",
+            Some(ESetupFix::ResetNetwork),
+        ));
+    }
+}
+
+fn check_textures(problems: &mut Vec<SetupProblem>) {
+    let textures = fs::read_dir(TEXTURES_DIR).map(|entries| {
+        entries
+            .filter_map(Result::ok)
+            .filter(|entry| entry.path().extension().map_or(false, |ext| ext == "rttex"))
+            .count()
+    });
+    let title = match textures {
+        Ok(0) => format!("The {} folder has no .rttex textures", TEXTURES_DIR),
+        Ok(_) => return,
+        Err(err) => format!("Can't read the {} folder: {}", TEXTURES_DIR, err),
+    };
+    problems.push(SetupProblem::new(
+        title,
+        "Copy the game folder from the Mori release next to the executable",
+        Some(ESetupFix::OpenFolder(".".to_string())),
+    ));
+}
+
+// A missing items.dat is fine, the first login downloads it. One that doesn't parse
+// would be used as-is until then
+fn check_items(problems: &mut Vec<SetupProblem>) {
+    let data = match fs::read(ITEMS_PATH) {
+        Ok(data) => data,
+        Err(_) => return,
+    };
+    if gtitem_r::load_from_memory(&data).is_err() {
+        problems.push(SetupProblem::new(
+            format!("{} is damaged or from an unsupported version", ITEMS_PATH),
+            "Point to a working items.dat, or delete this one and it is downloaded on the next login",
+            Some(ESetupFix::BrowseItems),
+        ));
+    }
+}
+
+// `path` is only used by BrowseItems
+pub fn apply(fix: &ESetupFix, path: &str) -> Result<(), String> {
+    match fix {
+        ESetupFix::BrowseItems => {
+            let data = fs::read(path).map_err(|err| format!("Can't read {}: {}", path, err))?;
+            gtitem_r::load_from_memory(&data)
+                .map_err(|_| format!("{} isn't a valid items.dat", path))?;
+            fs::write(ITEMS_PATH, data).map_err(|err| err.to_string())
+        }
+        ESetupFix::CreateConfig => {
+            if Path::new(config::CONFIG_PATH).exists() {
+                fs::rename(config::CONFIG_PATH, format!("{}.broken", config::CONFIG_PATH))
+                    .map_err(|err| err.to_string())?;
+            }
+            config::save(&Config::default()).map_err(|err| err.to_string())
+        }
+        ESetupFix::ResetNetwork => {
+            config::update(|config| config.network = NetworkConfig::default());
+            Ok(())
+        }
+        ESetupFix::OpenFolder(path) => open_folder(Path::new(path)),
+    }
+}
+
+pub fn open_folder(path: &Path) -> Result<(), String> {
+    let program = if cfg!(target_os = "windows") {
+        "explorer"
+    } else if cfg!(target_os = "macos") {
+        "open"
+    } else {
+        "xdg-open"
+    };
+    Command::new(program)
+        .arg(path)
+        .spawn()
+        .map(|_| ())
+        .map_err(|err| format!("Failed to open {}: {}", path.display(), err))
+}
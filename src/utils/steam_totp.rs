@@ -0,0 +1,32 @@
+use base64::engine::general_purpose;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use sha1::Sha1;
+
+const ALPHABET: &[u8] = b"23456789BCDFGHJKMNPQRTVWXY";
+
+// Steam's authenticator codes: RFC 6238 with a 30s step, rendered as 5 characters
+// from Steam's own alphabet. `shared_secret` is the base64 value from a maFile
+pub fn generate(shared_secret: &str, timestamp: u64) -> Result<String, String> {
+    let key = general_purpose::STANDARD
+        .decode(shared_secret.trim())
+        .map_err(|err| format!("shared secret is not valid base64: {}", err))?;
+    let mut mac = Hmac::<Sha1>::new_from_slice(&key).map_err(|err| err.to_string())?;
+    mac.update(&(timestamp / 30).to_be_bytes());
+    let hash = mac.finalize().into_bytes();
+
+    let offset = (hash[19] & 0x0f) as usize;
+    let mut full = u32::from_be_bytes([
+        hash[offset],
+        hash[offset + 1],
+        hash[offset + 2],
+        hash[offset + 3],
+    ]) & 0x7fff_ffff;
+
+    let mut code = String::with_capacity(5);
+    for _ in 0..5 {
+        code.push(ALPHABET[(full % ALPHABET.len() as u32) as usize] as char);
+        full /= ALPHABET.len() as u32;
+    }
+    Ok(code)
+}
@@ -0,0 +1,90 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, RwLock};
+
+type Hook<T> = Box<dyn Fn(&T) + Send + Sync>;
+
+// A value with a version that goes up on every change. Readers remember the version they
+// last saw and only lock when it moved. Setting the same value again is not a change
+#[derive(Default)]
+pub struct Watch<T> {
+    value: Mutex<T>,
+    version: AtomicU64,
+    hooks: RwLock<Vec<Hook<T>>>,
+}
+
+impl<T: Clone + PartialEq> Watch<T> {
+    pub fn new(value: T) -> Self {
+        Watch {
+            value: Mutex::new(value),
+            version: AtomicU64::new(0),
+            hooks: RwLock::new(Vec::new()),
+        }
+    }
+
+    pub fn get(&self) -> T {
+        self.value.lock().unwrap().clone()
+    }
+
+    pub fn version(&self) -> u64 {
+        self.version.load(Ordering::Acquire)
+    }
+
+    // Returns whether the value changed, the hooks only run when it did
+    pub fn set(&self, value: T) -> bool {
+        {
+            let mut current = self.value.lock().unwrap();
+            if *current == value {
+                return false;
+            }
+            *current = value.clone();
+            self.version.fetch_add(1, Ordering::AcqRel);
+        }
+        for hook in self.hooks.read().unwrap().iter() {
+            hook(&value);
+        }
+        true
+    }
+
+    // The value when it changed since `seen`, which is moved up to the current version
+    pub fn changed_since(&self, seen: &mut u64) -> Option<T> {
+        let version = self.version();
+        if version == *seen {
+            return None;
+        }
+        *seen = version;
+        Some(self.get())
+    }
+
+    // Runs `hook` with the new value after every change, on the thread that made it, so
+    // it has to be quick
+    pub fn on_change(&self, hook: impl Fn(&T) + Send + Sync + 'static) {
+        self.hooks.write().unwrap().push(Box::new(hook));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::Arc;
+
+    #[test]
+    fn only_changes_move_the_version_and_run_hooks() {
+        let watch = Watch::new(1);
+        let calls = Arc::new(AtomicUsize::new(0));
+        {
+            let calls = calls.clone();
+            watch.on_change(move |value| {
+                assert_eq!(*value, 2);
+                calls.fetch_add(1, Ordering::Relaxed);
+            });
+        }
+        let mut seen = watch.version();
+        assert!(!watch.set(1));
+        assert_eq!(watch.changed_since(&mut seen), None);
+        assert!(watch.set(2));
+        assert_eq!(watch.changed_since(&mut seen), Some(2));
+        assert_eq!(watch.changed_since(&mut seen), None);
+        assert_eq!(calls.load(Ordering::Relaxed), 1);
+    }
+}
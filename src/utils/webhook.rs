@@ -0,0 +1,7 @@
+use super::textparse;
+use serde_json::json;
+
+pub fn send(url: &str, content: &str) -> Result<(), ureq::Error> {
+    ureq::post(url).send_json(json!({ "content": textparse::strip_color(content) }))?;
+    Ok(())
+}
@@ -0,0 +1,399 @@
+// Integration tests against the mock server, `cargo test --features mock_server`. A real Bot
+// logs on through its own event loop and packet handlers. A fresh login fetches its token
+// over HTTPS first, so the bot starts as if redirected to the mock server, the rest of the
+// session is the same from the server hello on
+use super::{load, Reply, Scenario, Server};
+use crate::core::astar::PathHandle;
+use crate::core::{self, items, Bot};
+use crate::manager::message_bus::MessageBus;
+use crate::manager::proxy_manager::ProxyManager;
+use crate::types::bot_event::BotEvent;
+use crate::types::config::BotConfig;
+use crate::types::etank_packet_type::ETankPacketType;
+use crate::types::tank_packet::TankPacket;
+use crate::utils::config;
+use crate::utils::variant::Variant;
+use gtitem_r::structs::ItemDatabase;
+use std::fs;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+const ACCEPT_LOGON: &str = "OnSuperMainStartAcceptLogonHrdxs47254722215a";
+const NET_ID: u32 = 7;
+// Name of the world in world.dat
+const WORLD: &str = "PIWD";
+// A World Lock, nothing world.dat drops by itself
+const DROP_ID: u32 = 242;
+const DROP_COUNT: u8 = 3;
+
+// Entering a world rewrites world.dat, which the next server reads
+static SERIAL: Mutex<()> = Mutex::new(());
+
+// The mock server on its own thread, stopped when dropped
+struct Harness {
+    port: u16,
+    stop: Arc<AtomicBool>,
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+impl Harness {
+    fn start(scenario: Scenario) -> Harness {
+        let mut server = Server::bind(scenario, ("127.0.0.1", 0), &config::get_network()).unwrap();
+        let port = server.port();
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread = {
+            let stop = stop.clone();
+            thread::spawn(move || server.serve(&stop))
+        };
+        Harness {
+            port,
+            stop,
+            thread: Some(thread),
+        }
+    }
+}
+
+impl Drop for Harness {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+// A bot logged on to a mock server, stopped when dropped. Declare it after the Harness so
+// it disconnects while the server is still there to answer
+struct Session {
+    bot: Arc<Bot>,
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+impl Session {
+    fn start(item_database: &Arc<RwLock<ItemDatabase>>, port: u16) -> Session {
+        let bot_config = BotConfig {
+            payload: "mock|mock".to_string(),
+            ..Default::default()
+        };
+        let bot = Bot::new(
+            bot_config,
+            item_database.clone(),
+            Arc::new(RwLock::new(ProxyManager::new())),
+            Arc::new(MessageBus::new()),
+        )
+        .unwrap();
+        let thread = {
+            let bot = bot.clone();
+            thread::spawn(move || bot.logon_redirected("127.0.0.1", port))
+        };
+        Session {
+            bot,
+            thread: Some(thread),
+        }
+    }
+
+    // Warps into world.dat and waits for the server to place the bot
+    fn enter_world(&self) {
+        wait_for("the login", || self.bot.state.lock().unwrap().logged_in);
+        self.bot.warp_and_wait(WORLD, TIMEOUT).unwrap();
+        wait_for("the spawn position", || self.bot.temporary_data.read().unwrap().entered_world);
+    }
+
+    fn position(&self) -> (f32, f32) {
+        let position = self.bot.position.lock().unwrap();
+        (position.x, position.y)
+    }
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        self.bot.state.lock().unwrap().is_running = false;
+        self.bot.disconnect();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn wait_for(what: &str, mut done: impl FnMut() -> bool) {
+    let started = Instant::now();
+    while !done() {
+        assert!(started.elapsed() < TIMEOUT, "no {} within {:?}", what, TIMEOUT);
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+// Loads items.dat, its hash is what the server has to announce for the bot to skip the download
+fn item_database() -> (Arc<RwLock<ItemDatabase>>, u32) {
+    let item_database = Arc::new(RwLock::new(ItemDatabase::new()));
+    let hash = items::load_from_disk(&item_database).expect("items.dat");
+    (item_database, hash)
+}
+
+fn main_door(item_database: &Arc<RwLock<ItemDatabase>>) -> (u32, u32) {
+    let mut world = gtworld_r::World::new(item_database.clone());
+    world.parse(&fs::read("world.dat").unwrap());
+    let door = world.tiles.iter().find(|tile| tile.foreground_item_id == 6).expect("world.dat has no main door");
+    (door.x, door.y)
+}
+
+// Where the bot stands on a tile, as the packet handlers place it
+fn standing_on(x: u32, y: u32) -> (f32, f32) {
+    (x as f32 * 32.0, core::get_coordinate_to_touch_ground(y as f32 * 32.0))
+}
+
+fn reply(trigger: &str, data: String) -> Reply {
+    Reply {
+        trigger: trigger.to_string(),
+        data,
+        ..Default::default()
+    }
+}
+
+// A game packet as scenario hex
+fn tank(tank_packet: TankPacket, extended: &[u8]) -> String {
+    let tank_packet = TankPacket {
+        extended_data_length: extended.len() as u32,
+        ..tank_packet
+    };
+    hex::encode(tank_packet.to_message(extended).unwrap())
+}
+
+// A call function packet as scenario hex, arguments encoded the way the server does
+fn call(args: &[Variant]) -> String {
+    let mut extended = vec![args.len() as u8];
+    for (index, arg) in args.iter().enumerate() {
+        extended.push(index as u8);
+        match arg {
+            Variant::Float(value) => {
+                extended.push(1);
+                extended.extend_from_slice(&value.to_le_bytes());
+            }
+            Variant::String(value) => {
+                extended.push(2);
+                extended.extend_from_slice(&(value.len() as u32).to_le_bytes());
+                extended.extend_from_slice(value.as_bytes());
+            }
+            Variant::Vec2((x, y)) => {
+                extended.push(3);
+                extended.extend_from_slice(&x.to_le_bytes());
+                extended.extend_from_slice(&y.to_le_bytes());
+            }
+            Variant::Unsigned(value) => {
+                extended.push(5);
+                extended.extend_from_slice(&value.to_le_bytes());
+            }
+            Variant::Signed(value) => {
+                extended.push(9);
+                extended.extend_from_slice(&value.to_le_bytes());
+            }
+            other => panic!("can't encode {:?}", other),
+        }
+    }
+    let tank_packet = TankPacket {
+        _type: ETankPacketType::NetGamePacketCallFunction,
+        net_id: u32::MAX,
+        flags: 8,
+        ..Default::default()
+    };
+    tank(tank_packet, &extended)
+}
+
+fn text(value: &str) -> Variant {
+    Variant::String(value.to_string())
+}
+
+// Accepts the bot's logon, spawns it with NET_ID and a 16 slot inventory on enter_game
+fn login_replies(trigger: &str, hash: u32) -> Vec<Reply> {
+    let inventory = TankPacket {
+        _type: ETankPacketType::NetGamePacketSendInventoryState,
+        ..Default::default()
+    };
+    vec![
+        reply(trigger, call(&[text(ACCEPT_LOGON), Variant::Unsigned(hash)])),
+        reply("text:action|enter_game", tank(inventory, &inventory_data())),
+        reply(
+            "text:action|enter_game",
+            call(&[
+                text("OnSpawn"),
+                text(&format!("spawn|avatar\nnetID|{}\nuserID|1\ntype|local\n", NET_ID)),
+            ]),
+        ),
+    ]
+}
+
+// Inventory size then the item count, no items
+fn inventory_data() -> Vec<u8> {
+    let mut data = vec![0];
+    data.extend_from_slice(&16u32.to_le_bytes());
+    data.extend_from_slice(&0u16.to_le_bytes());
+    data
+}
+
+// Logs in and enters world.dat. The first position the server sends is off the door, the
+// bot puts itself on the door as the client does. `in_world` follows the world
+fn game(hash: u32, in_world: Vec<Reply>) -> Scenario {
+    let mut replies = login_replies("text:UUIDToken|", hash);
+    replies.push(reply("text:action|join_request", call(&[text("OnSetPos"), Variant::Vec2((0.0, 0.0))])));
+    replies.extend(in_world);
+    Scenario {
+        net_id: NET_ID,
+        world: "world.dat".to_string(),
+        replies,
+    }
+}
+
+#[test]
+fn logs_in_and_enters_a_world() {
+    let _serial = SERIAL.lock().unwrap_or_else(|err| err.into_inner());
+    let (item_database, hash) = item_database();
+    let (door_x, door_y) = main_door(&item_database);
+    let server = Harness::start(game(hash, Vec::new()));
+    let session = Session::start(&item_database, server.port);
+    session.enter_world();
+
+    let bot = &session.bot;
+    {
+        let state = bot.state.lock().unwrap();
+        assert_eq!((state.net_id, state.user_id), (NET_ID, 1));
+        assert!(state.is_ingame);
+        assert!(!state.is_redirecting);
+    }
+    assert_eq!(bot.world_name.get(), WORLD);
+    assert_eq!(bot.inventory.lock().unwrap().size, 16);
+    assert_eq!(session.position(), standing_on(door_x, door_y));
+}
+
+#[test]
+fn follows_a_redirect() {
+    let _serial = SERIAL.lock().unwrap_or_else(|err| err.into_inner());
+    let (item_database, hash) = item_database();
+    // Only a login carrying the uuid from the redirect is accepted
+    let target = Harness::start(Scenario {
+        net_id: NET_ID,
+        replies: login_replies("text:UUIDToken|mock-uuid\n", hash),
+        ..Default::default()
+    });
+    let redirect = call(&[
+        text("OnSendToServer"),
+        Variant::Signed(target.port as i32),
+        Variant::Signed(17),
+        Variant::Signed(555),
+        text("127.0.0.1|0|mock-uuid"),
+    ]);
+    let server = Harness::start(Scenario {
+        net_id: NET_ID,
+        replies: vec![reply("text:UUIDToken|", redirect)],
+        ..Default::default()
+    });
+    let session = Session::start(&item_database, server.port);
+    wait_for("the login", || session.bot.state.lock().unwrap().logged_in);
+
+    let bot = &session.bot;
+    assert_eq!(bot.server.lock().unwrap().port, target.port);
+    {
+        let info = bot.info.lock().unwrap();
+        assert_eq!((info.login_info.token.as_str(), info.login_info.user.as_str()), ("17", "555"));
+    }
+    let connects: Vec<u16> = bot
+        .take_events()
+        .into_iter()
+        .filter_map(|event| match event {
+            BotEvent::Connect { port, .. } => Some(port),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(connects, vec![server.port, target.port]);
+}
+
+#[test]
+fn reconnects_after_a_drop() {
+    let _serial = SERIAL.lock().unwrap_or_else(|err| err.into_inner());
+    let (item_database, _) = item_database();
+    // Every logon is dropped, the bot keeps coming back
+    let server = Harness::start(Scenario {
+        net_id: NET_ID,
+        replies: vec![Reply {
+            trigger: "text:UUIDToken|".to_string(),
+            delay_ms: 100,
+            disconnect: true,
+            ..Default::default()
+        }],
+        ..Default::default()
+    });
+    let session = Session::start(&item_database, server.port);
+
+    let mut events = Vec::new();
+    wait_for("a second connection", || {
+        events.extend(session.bot.take_events());
+        events.iter().filter(|event| matches!(event, BotEvent::Connect { .. })).count() >= 2
+    });
+    let drop = events
+        .iter()
+        .position(|event| matches!(event, BotEvent::Disconnect { reconnect: true, .. }))
+        .expect("the drop wasn't reported as one to reconnect after");
+    assert!(events[drop..].iter().any(|event| matches!(event, BotEvent::Connect { .. })));
+    assert!(session.bot.stats.lock().unwrap().get("disconnects") >= 1);
+}
+
+#[test]
+fn walks_a_path() {
+    let _serial = SERIAL.lock().unwrap_or_else(|err| err.into_inner());
+    let (item_database, hash) = item_database();
+    let (door_x, door_y) = main_door(&item_database);
+    let server = Harness::start(game(hash, Vec::new()));
+    let session = Session::start(&item_database, server.port);
+    session.enter_world();
+
+    let target_x = if door_x >= 3 { door_x - 3 } else { door_x + 3 };
+    let handle = session.bot.find_path(target_x, door_y).unwrap();
+    let (x, y) = match handle {
+        PathHandle::Reached => (target_x, door_y),
+        PathHandle::ReachedNearby { x, y, .. } => (x, y),
+    };
+    assert_ne!((x, y), (door_x, door_y), "the path didn't leave the door");
+    assert_eq!(session.position(), standing_on(x, y));
+}
+
+#[test]
+fn collects_a_drop() {
+    let _serial = SERIAL.lock().unwrap_or_else(|err| err.into_inner());
+    let (item_database, hash) = item_database();
+    let (door_x, door_y) = main_door(&item_database);
+    // Dropped next to the door the bot lands on
+    let spawn = TankPacket {
+        _type: ETankPacketType::NetGamePacketItemChangeObject,
+        net_id: u32::MAX,
+        value: DROP_ID,
+        vector_x: door_x as f32 * 32.0 + 8.0,
+        vector_y: door_y as f32 * 32.0 + 8.0,
+        unk6: DROP_COUNT as f32,
+        ..Default::default()
+    };
+    let server = Harness::start(game(hash, vec![reply("text:action|join_request", tank(spawn, &[]))]));
+    let session = Session::start(&item_database, server.port);
+    session.enter_world();
+
+    let bot = &session.bot;
+    let picked_up = || bot.inventory.lock().unwrap().items.contains_key(&(DROP_ID as u16));
+    // auto_collect may get to it first
+    wait_for("the drop", || {
+        picked_up() || bot.dropped_items_with_age().iter().any(|(item, _)| item.id as u32 == DROP_ID)
+    });
+    bot.collect();
+    wait_for("the pickup", picked_up);
+    assert_eq!(bot.inventory.lock().unwrap().items[&(DROP_ID as u16)].amount, DROP_COUNT);
+    assert!(!bot.dropped_items_with_age().iter().any(|(item, _)| item.id as u32 == DROP_ID));
+}
+
+// The scenarios shipped as examples for `mori mock-server` stay loadable
+#[test]
+fn example_scenarios_load() {
+    for entry in fs::read_dir("tests/mock_server/scenarios").unwrap() {
+        let path = entry.unwrap().path();
+        load(path.to_str().unwrap()).unwrap();
+    }
+}